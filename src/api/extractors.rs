@@ -0,0 +1,136 @@
+use axum::{
+    body::Bytes,
+    extract::{rejection::PathRejection, FromRequest, FromRequestParts, Request},
+    http::{header::CONTENT_TYPE, request::Parts},
+};
+use serde::de::DeserializeOwned;
+use validator::Validate;
+use crate::base::{
+    error::{AppError, FieldError},
+    validation::to_field_errors,
+};
+
+// Drop-in replacements for axum's `Json`, `Query` and `Path` that report every
+// problem as a `VALIDATION_FAILED` problem body instead of plain-text rejections.
+
+pub struct ValidatedJson<T>(pub T);
+
+impl<T, S> FromRequest<S> for ValidatedJson<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let is_json = req
+            .headers()
+            .get(CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.starts_with("application/json"));
+        if !is_json {
+            return Err(AppError::invalid_field(
+                "body",
+                "CONTENT_TYPE",
+                "Expected request with `Content-Type: application/json`",
+            ));
+        }
+
+        let bytes = Bytes::from_request(req, state)
+            .await
+            .map_err(|e| AppError::invalid_field("body", "UNREADABLE", &e.body_text()))?;
+
+        let deserializer = &mut serde_json::Deserializer::from_slice(&bytes);
+        let value: T = serde_path_to_error::deserialize(deserializer).map_err(|e| {
+            let message = e.inner().to_string();
+            let field = field_path(e.path().to_string(), &message, "body");
+            let code = if e.inner().is_syntax() || e.inner().is_eof() {
+                "INVALID_JSON"
+            } else {
+                type_error_code(&message)
+            };
+            AppError::invalid_field(&field, code, &message)
+        })?;
+
+        validate(value).map(ValidatedJson)
+    }
+}
+
+pub struct ValidatedQuery<T>(pub T);
+
+impl<T, S> FromRequestParts<S> for ValidatedQuery<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let query = parts.uri.query().unwrap_or_default();
+        let deserializer =
+            serde_urlencoded::Deserializer::new(form_urlencoded::parse(query.as_bytes()));
+
+        let value: T = serde_path_to_error::deserialize(deserializer).map_err(|e| {
+            let message = e.inner().to_string();
+            let field = field_path(e.path().to_string(), &message, "query");
+            AppError::invalid_field(&field, type_error_code(&message), &message)
+        })?;
+
+        validate(value).map(ValidatedQuery)
+    }
+}
+
+pub struct Path<T>(pub T);
+
+impl<T, S> FromRequestParts<S> for Path<T>
+where
+    T: DeserializeOwned + Send,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        match axum::extract::Path::<T>::from_request_parts(parts, state).await {
+            Ok(axum::extract::Path(value)) => Ok(Path(value)),
+            Err(PathRejection::FailedToDeserializePathParams(e)) => {
+                let field = match e.kind() {
+                    axum::extract::path::ErrorKind::ParseErrorAtKey { key, .. }
+                    | axum::extract::path::ErrorKind::DeserializeError { key, .. }
+                    | axum::extract::path::ErrorKind::InvalidUtf8InPathParam { key } => key.clone(),
+                    _ => "path".to_string(),
+                };
+                Err(AppError::InvalidFields(vec![FieldError::new(
+                    field,
+                    "INVALID_FORMAT",
+                    e.body_text(),
+                )]))
+            }
+            Err(e) => Err(AppError::Internal(e.body_text())),
+        }
+    }
+}
+
+fn validate<T: Validate>(value: T) -> Result<T, AppError> {
+    value
+        .validate()
+        .map_err(|e| AppError::InvalidFields(to_field_errors(&e)))?;
+    Ok(value)
+}
+
+fn type_error_code(message: &str) -> &'static str {
+    if message.starts_with("missing field") { "REQUIRED" } else { "INVALID_TYPE" }
+}
+
+// serde_path_to_error reports errors on the container itself (such as a missing
+// field) at the root, so the field name is recovered from serde's message.
+fn field_path(path: String, message: &str, root: &str) -> String {
+    if path != "." && path != "?" {
+        return path;
+    }
+
+    message
+        .strip_prefix("missing field `")
+        .and_then(|rest| rest.split('`').next())
+        .map(str::to_string)
+        .unwrap_or_else(|| root.to_string())
+}
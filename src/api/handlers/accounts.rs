@@ -1,32 +1,67 @@
-use axum::{extract::{Path, Query, State, Extension}, Json};
+use std::net::SocketAddr;
+use axum::{extract::{ConnectInfo, State, Extension}, http::StatusCode, Json};
+use chrono::Utc;
 use deadpool_postgres::Pool;
-use uuid::Uuid;
 use rust_decimal::Decimal;
+use serde_json::json;
+use uuid::Uuid;
 use crate::{
-    db::dal::{accounts as account_queries, transactions as transaction_queries},
+    db::dal::{
+        accounts as account_queries, approvals as approval_queries, audit as audit_queries, balances as balance_queries,
+        transactions as transaction_queries,
+    },
     base::{
+        constants::DEFAULT_CURRENCY,
         models::{
-            accounts::{Account, CreateAccountRequest, UpdateAccountRequest, AccountPaginationParams, DepositRequest, WithdrawalRequest},
+            accounts::{
+                Account, AccountStatus, CreateAccountRequest, UpdateAccountRequest, AccountPaginationParams,
+                CloseAccountRequest, DepositRequest, WithdrawalRequest,
+            },
+            approvals::{ApprovalOperation, ApprovalRequest, NewApprovalRequest},
+            audit::NewAuditEntry,
+            balances::{AccountBalance, BalanceParams},
+            fees::{AccountWithFee, FeeTransactionType},
+            risk::RiskAction,
+            screening::{ScreeningDecision, ScreeningSubject, ScreeningTrigger},
             transactions::{CreateTransactionRequest, TransactionType},
         },
         error::AppError,
+        metrics,
+        pagination::{Page, PageRequest},
+    },
+    api::{extractors::{Path, ValidatedJson, ValidatedQuery}, middleware::auth::AuthUser},
+    services::{
+        approvals as approval_service, events as event_service, fees as fee_service, kyc as kyc_service,
+        limits as limit_service, risk as risk_service, screening::{self as screening_service, WatchlistCache},
     },
-    api::middleware::auth::AuthUser,
 };
 
 pub async fn create_account(
     Extension(auth): Extension<AuthUser>,
     State(pool): State<Pool>,
-    Json(mut account): Json<CreateAccountRequest>,
+    ValidatedJson(mut account): ValidatedJson<CreateAccountRequest>,
 ) -> Result<Json<Account>, AppError> {
-    let client = pool.get().await.map_err(|e| AppError::Database(e.to_string()))?;
+    let mut client = pool.get().await?;
 
     // Only authenticated users can create an account
     account.user_id = auth.user_id;
 
-    let account: Account = account_queries::create_account(&client, &account)
-        .await
-        .map_err(|e| AppError::Database(e.to_string()))?;
+    let db_transaction = client.transaction().await?;
+
+    let currency = account.currency.as_deref().unwrap_or(DEFAULT_CURRENCY);
+    let initial_balance = account.initial_balance.unwrap_or(Decimal::ZERO);
+    kyc_service::check_account_opening(&db_transaction, auth.user_id, currency, initial_balance).await?;
+
+    let account: Account = account_queries::create_account(&db_transaction, &account)
+        .await?;
+
+    audit_queries::create_entry(
+        &db_transaction,
+        &NewAuditEntry::new(Some(auth.user_id), "account.created", "account", Some(account.id)).after(&account),
+    )
+    .await?;
+
+    db_transaction.commit().await?;
 
     Ok(Json(account))
 }
@@ -36,120 +71,238 @@ pub async fn get_account(
     State(pool): State<Pool>,
     Path(id): Path<Uuid>,
 ) -> Result<Json<Account>, AppError> {
-    let client = pool.get().await.map_err(|e| AppError::Database(e.to_string()))?;
+    let client = pool.get().await?;
 
     let account = account_queries::get_account_by_id(&client, id)
-        .await
-        .map_err(|e| AppError::Database(e.to_string()))?
+        .await?
         .ok_or_else(|| AppError::NotFound("Account not found".into()))?;
 
     // User can only view their own accounts
     if account.user_id != auth.user_id {
-        return Err(AppError::Auth("Unauthorized access to account".into()));
+        return Err(AppError::Forbidden("Not allowed to access this account".into()));
     }
 
     Ok(Json(account))
 }
 
+pub async fn get_balance(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+    Path(id): Path<Uuid>,
+    ValidatedQuery(params): ValidatedQuery<BalanceParams>,
+) -> Result<Json<AccountBalance>, AppError> {
+    let client = pool.get().await?;
+
+    let account = account_queries::get_account_by_id(&client, id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Account not found".into()))?;
+
+    // User can only view their own accounts
+    if account.user_id != auth.user_id {
+        return Err(AppError::Forbidden("Not allowed to access this account".into()));
+    }
+
+    let at = params.at.unwrap_or_else(Utc::now);
+    let balance = balance_queries::get_balance_at(&client, id, at)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Account not found".into()))?;
+
+    Ok(Json(balance))
+}
+
 pub async fn update_account(
     Extension(auth): Extension<AuthUser>,
     State(pool): State<Pool>,
     Path(id): Path<Uuid>,
-    Json(account): Json<UpdateAccountRequest>,
+    ValidatedJson(account): ValidatedJson<UpdateAccountRequest>,
 ) -> Result<Json<Account>, AppError> {
-    let client = pool.get().await.map_err(|e| AppError::Database(e.to_string()))?;
+    let mut client = pool.get().await?;
+    let db_transaction = client.transaction().await?;
 
     // Verifying account ownership
-    let existing = account_queries::get_account_by_id(&client, id)
-        .await
-        .map_err(|e| AppError::Database(e.to_string()))?
+    let existing = account_queries::get_account_for_update(&db_transaction, id)
+        .await?
         .ok_or_else(|| AppError::NotFound("Account not found".into()))?;
 
     if existing.user_id != auth.user_id {
-        return Err(AppError::Auth("Unauthorized to update this account".into()));
+        return Err(AppError::Forbidden("Not allowed to update this account".into()));
+    }
+    existing.ensure_can_credit()?;
+    // Moving the account to another currency opens one there
+    if let Some(currency) = account.currency.as_deref().filter(|currency| *currency != existing.currency) {
+        kyc_service::check_account_opening(&db_transaction, auth.user_id, currency, existing.balance).await?;
     }
 
-    let updated_account = account_queries::update_account(&client, id, &account)
-        .await
-        .map_err(|e| AppError::Database(e.to_string()))?
+    let updated_account = account_queries::update_account(&db_transaction, id, &account)
+        .await?
         .ok_or_else(|| AppError::NotFound("Account not found".into()))?;
 
+    audit_queries::create_entry(
+        &db_transaction,
+        &NewAuditEntry::new(Some(auth.user_id), "account.updated", "account", Some(id))
+            .before(&existing)
+            .after(&updated_account),
+    )
+    .await?;
+
+    db_transaction.commit().await?;
+
     Ok(Json(updated_account))
 }
 
+// Accounts are closed rather than deleted, which needs a zero balance. Like
+// `close_account`, this only asks for the closure, which another user has to
+// approve.
 pub async fn delete_account(
     Extension(auth): Extension<AuthUser>,
     State(pool): State<Pool>,
     Path(id): Path<Uuid>,
-) -> Result<(), AppError> {
-    let client = pool.get().await.map_err(|e| AppError::Database(e.to_string()))?;
+) -> Result<(StatusCode, Json<ApprovalRequest>), AppError> {
+    let mut client = pool.get().await?;
+
+    let request = approval_service::request_closure(&mut client, auth.user_id, id, None).await?;
+
+    Ok((StatusCode::ACCEPTED, Json(request)))
+}
+
+// Answers with the approval request; the account is closed when it is approved.
+pub async fn close_account(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+    Path(id): Path<Uuid>,
+    ValidatedJson(request): ValidatedJson<CloseAccountRequest>,
+) -> Result<(StatusCode, Json<ApprovalRequest>), AppError> {
+    let mut client = pool.get().await?;
+
+    let request = approval_service::request_closure(&mut client, auth.user_id, id, request.payout_account_id).await?;
+
+    Ok((StatusCode::ACCEPTED, Json(request)))
+}
+
+pub async fn freeze_account(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Account>, AppError> {
+    auth.require_admin()?;
+    change_status(&pool, auth.user_id, id, AccountStatus::Active, AccountStatus::Frozen, "account.frozen").await
+}
+
+pub async fn unfreeze_account(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Account>, AppError> {
+    auth.require_admin()?;
+    change_status(&pool, auth.user_id, id, AccountStatus::Frozen, AccountStatus::Active, "account.unfrozen").await
+}
 
-    // Verify account ownership
-    let existing = account_queries::get_account_by_id(&client, id)
-        .await
-        .map_err(|e| AppError::Database(e.to_string()))?
+pub async fn reopen_account(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Account>, AppError> {
+    auth.require_admin()?;
+    change_status(&pool, auth.user_id, id, AccountStatus::Closed, AccountStatus::Active, "account.reopened").await
+}
+
+async fn change_status(
+    pool: &Pool,
+    actor_id: Uuid,
+    id: Uuid,
+    from: AccountStatus,
+    to: AccountStatus,
+    action: &'static str,
+) -> Result<Json<Account>, AppError> {
+    let mut client = pool.get().await?;
+    let db_transaction = client.transaction().await?;
+
+    let account = account_queries::get_account_for_update(&db_transaction, id)
+        .await?
         .ok_or_else(|| AppError::NotFound("Account not found".into()))?;
 
-    if existing.user_id != auth.user_id {
-        return Err(AppError::Auth("Unauthorized to delete this account".into()));
+    if account.status != from {
+        return Err(AppError::Conflict(format!(
+            "Account is {}, expected {}",
+            account.status, from
+        )));
+    }
+    if from == AccountStatus::Closed {
+        kyc_service::check_account_opening(&db_transaction, account.user_id, &account.currency, account.balance).await?;
     }
 
-    let deleted = account_queries::delete_account(&client, id)
-        .await
-        .map_err(|e| AppError::Database(e.to_string()))?;
+    let updated = account_queries::set_account_status(&db_transaction, id, to)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Account not found".into()))?;
 
-    if !deleted {
-        return Err(AppError::NotFound("Account not found".into()));
-    }
+    audit_queries::create_entry(
+        &db_transaction,
+        &NewAuditEntry::new(Some(actor_id), action, "account", Some(id))
+            .before(&account)
+            .after(&updated),
+    )
+    .await?;
+
+    db_transaction.commit().await?;
 
-    Ok(())
+    tracing::info!(account_id = %id, from = %from, to = %to, "Account status changed");
+
+    Ok(Json(updated))
 }
 
 pub async fn list_accounts(
     Extension(auth): Extension<AuthUser>,
     State(pool): State<Pool>,
-    Query(params): Query<AccountPaginationParams>,
-) -> Result<Json<Vec<Account>>, AppError> {
-    let client = pool.get().await.map_err(|e| AppError::Database(e.to_string()))?;
-
-    let page = params.page.unwrap_or(1);
-    let per_page = params.per_page.unwrap_or(10);
-    let offset = (page - 1) * per_page;
+    ValidatedQuery(params): ValidatedQuery<AccountPaginationParams>,
+) -> Result<Json<Page<Account>>, AppError> {
+    let client = pool.get().await?;
 
     // If user_id is provided and doesn't match authenticated user, return error
-    if let Some(user_id) = params.user_id {
-        if user_id != auth.user_id {
-            return Err(AppError::Auth("Unauthorized to view other users' accounts".into()));
-        }
+    if let Some(user_id) = params.user_id
+        && user_id != auth.user_id
+    {
+        return Err(AppError::Forbidden("Not allowed to view other users' accounts".into()));
     }
 
-    let accounts = account_queries::get_accounts_by_user_id(&client, auth.user_id, offset, per_page)
-        .await
-        .map_err(|e| AppError::Database(e.to_string()))?;
+    let page = PageRequest::new(params.limit, params.cursor.as_deref(), params.sort, params.order)?;
+
+    let accounts = account_queries::get_accounts_by_user_id(&client, auth.user_id, &page).await?;
+
+    let total_count = if params.include_total {
+        Some(account_queries::count_accounts_by_user_id(&client, auth.user_id).await?)
+    } else {
+        None
+    };
 
-    Ok(Json(accounts))
+    Ok(Json(Page::new(accounts, &page, total_count)))
 }
 
 pub async fn deposit(
     Extension(auth): Extension<AuthUser>,
     State(pool): State<Pool>,
     Path(id): Path<Uuid>,
-    Json(deposit): Json<DepositRequest>,
-) -> Result<Json<Account>, AppError> {
-    let client = pool.get().await.map_err(|e| AppError::Database(e.to_string()))?;
-
-    if deposit.amount <= Decimal::from(0) {
-        return Err(AppError::Validation("Invalid amount".into()));
-    }
+    ValidatedJson(deposit): ValidatedJson<DepositRequest>,
+) -> Result<Json<AccountWithFee>, AppError> {
+    let mut client = pool.get().await?;
+    let db_transaction = client.transaction().await?;
 
     // Verifying account ownership
-    let account = account_queries::get_account_by_id(&client, id)
-        .await
-        .map_err(|e| AppError::Database(e.to_string()))?
+    let account = account_queries::get_account_for_update(&db_transaction, id)
+        .await?
         .ok_or_else(|| AppError::NotFound("Account not found".into()))?;
 
     if account.user_id != auth.user_id {
-        return Err(AppError::Auth("Unauthorized to deposit to this account".into()));
+        return Err(AppError::Forbidden("Not allowed to deposit to this account".into()));
+    }
+    account.ensure_can_credit()?;
+    screening_service::ensure_not_restricted(&db_transaction, auth.user_id).await?;
+    kyc_service::check_transaction(&db_transaction, &account, deposit.amount, true, Utc::now()).await?;
+
+    // The fee is taken from the deposited funds
+    let fee =
+        fee_service::quote(&db_transaction, FeeTransactionType::Deposit, &account.currency, deposit.amount).await?;
+    if fee.net_amount <= Decimal::ZERO {
+        return Err(AppError::invalid_field("amount", "FEE_EXCEEDS_AMOUNT", "Amount must be greater than the deposit fee"));
     }
 
     // Creating transaction
@@ -158,45 +311,153 @@ pub async fn deposit(
         amount: deposit.amount,
         transaction_type: TransactionType::Deposit,
         description: deposit.description.clone(),
+        metadata: deposit.metadata.clone(),
+        tags: deposit.tags.clone(),
     };
 
-    let _transaction = transaction_queries::create_transaction(&client, &transaction_request)
-        .await
-        .map_err(|e| AppError::Database(e.to_string()))?;
+    let transaction = transaction_queries::create_transaction(&db_transaction, &transaction_request)
+        .await?;
 
     // Adding amount to account balance
-    let updated_account = account_queries::update_account_balance(&client, id, deposit.amount, true)
-        .await
-        .map_err(|e| AppError::Database(e.to_string()))?
+    let updated_account = account_queries::update_account_balance(&db_transaction, id, deposit.amount, true)
+        .await?
         .ok_or_else(|| AppError::NotFound("Account not found or balance update failed".into()))?;
 
-    Ok(Json(updated_account))
+    event_service::transaction_created(&db_transaction, auth.user_id, &transaction).await?;
+    event_service::balance_changed(&db_transaction, &account, &updated_account, transaction.id).await?;
+
+    let updated_account = fee_service::charge(&db_transaction, &updated_account, &fee, transaction.id)
+        .await?
+        .unwrap_or(updated_account);
+
+    audit_queries::create_entry(
+        &db_transaction,
+        &NewAuditEntry::new(Some(auth.user_id), "account.deposit", "account", Some(id))
+            .before(&account)
+            .after(&updated_account)
+            .details(json!({ "transaction_id": transaction.id, "amount": deposit.amount, "fee": fee.fee })),
+    )
+    .await?;
+
+    db_transaction.commit().await?;
+
+    metrics::record_deposit(&updated_account.currency, deposit.amount);
+    if fee.fee > Decimal::ZERO {
+        metrics::record_fee(&updated_account.currency, &fee.transaction_type.to_string(), fee.fee);
+    }
+
+    Ok(Json(AccountWithFee { account: updated_account, fee, approval_request_id: None }))
 }
 
+// Withdrawals the risk checks flag for review are held as PENDING transactions
+// and answered with 202; the fee is charged when the review approves them.
+// Withdrawals above the approval threshold of their currency are answered with
+// 202 too, but nothing is booked until the approval request is approved.
 pub async fn withdraw(
     Extension(auth): Extension<AuthUser>,
     State(pool): State<Pool>,
+    Extension(watchlists): Extension<WatchlistCache>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
     Path(id): Path<Uuid>,
-    Json(withdrawal): Json<WithdrawalRequest>,
-) -> Result<Json<Account>, AppError> {
-    let client = pool.get().await.map_err(|e| AppError::Database(e.to_string()))?;
+    ValidatedJson(withdrawal): ValidatedJson<WithdrawalRequest>,
+) -> Result<(StatusCode, Json<AccountWithFee>), AppError> {
+    let mut client = pool.get().await?;
+
+    // The payee is screened before the account is locked, since matching
+    // takes a while; the outcome is acted on below.
+    let screening = match &withdrawal.counterparty_name {
+        Some(name) => Some(
+            screening_service::screen_name(&client, &watchlists, name, ScreeningSubject::Counterparty, None).await?,
+        ),
+        None => None,
+    };
 
-    if withdrawal.amount <= Decimal::from(0) {
-        return Err(AppError::Validation("Invalid amount".into()));
-    }
+    let db_transaction = client.transaction().await?;
 
     // Verifying account ownership
-    let account = account_queries::get_account_by_id(&client, id)
-        .await
-        .map_err(|e| AppError::Database(e.to_string()))?
+    let account = account_queries::get_account_for_update(&db_transaction, id)
+        .await?
         .ok_or_else(|| AppError::NotFound("Account not found".into()))?;
 
     if account.user_id != auth.user_id {
-        return Err(AppError::Auth("Unauthorized to withdraw from this account".into()));
+        return Err(AppError::Forbidden("Not allowed to withdraw from this account".into()));
+    }
+    account.ensure_can_debit()?;
+    screening_service::ensure_not_restricted(&db_transaction, auth.user_id).await?;
+    limit_service::check_debit(&db_transaction, &account, withdrawal.amount, Utc::now()).await?;
+    kyc_service::check_transaction(&db_transaction, &account, withdrawal.amount, false, Utc::now()).await?;
+
+    // The balance must cover the amount and the fee on top of it
+    let fee =
+        fee_service::quote(&db_transaction, FeeTransactionType::Withdrawal, &account.currency, withdrawal.amount).await?;
+    if account.balance < fee.net_amount {
+        metrics::record_insufficient_balance(&account.currency);
+        return Err(AppError::InsufficientFunds);
+    }
+
+    // A possible match of the payee holds the withdrawal for a screening
+    // review, which takes the place of the risk checks.
+    if let (Some(name), Some(screening)) = (&withdrawal.counterparty_name, &screening)
+        && screening.decision == ScreeningDecision::Block
+    {
+        screening_service::record(
+            &db_transaction,
+            ScreeningSubject::Counterparty,
+            ScreeningTrigger::Withdrawal,
+            Some(auth.user_id),
+            None,
+            name,
+            screening,
+        )
+        .await?;
+        db_transaction.commit().await?;
+        return Err(AppError::TransactionDeclined);
+    }
+    let held_for_screening = screening
+        .as_ref()
+        .is_some_and(|screening| screening.decision == ScreeningDecision::Review);
+
+    let ip_address = addr.ip().to_string();
+    let decision = if held_for_screening {
+        None
+    } else {
+        Some(risk_service::assess(&db_transaction, &account, withdrawal.amount, Some(&ip_address), Utc::now()).await?)
+    };
+    if let Some(decision) = &decision
+        && decision.action == RiskAction::Block
+    {
+        risk_service::record(&db_transaction, &account, None, None, withdrawal.amount, Some(&ip_address), decision)
+            .await?;
+        db_transaction.commit().await?;
+        return Err(AppError::TransactionDeclined);
     }
 
-    if account.balance < withdrawal.amount {
-        return Err(AppError::Validation("Insufficient balance".into()));
+    // A withdrawal over the approval threshold is only filed as a request,
+    // which takes the place of a risk review; it is booked once approved
+    let threshold = approval_queries::get_threshold(&db_transaction, &account.currency).await?;
+    if !held_for_screening && threshold.is_some_and(|threshold| withdrawal.amount > threshold.withdrawal_threshold) {
+        let request = NewApprovalRequest {
+            operation: ApprovalOperation::Withdrawal,
+            account_id: id,
+            requested_by: auth.user_id,
+            amount: Some(withdrawal.amount),
+            details: json!({
+                "description": withdrawal.description,
+                "metadata": withdrawal.metadata_with_counterparty(),
+                "tags": withdrawal.tags,
+                "fee": fee.fee,
+                "risk_score": decision.as_ref().map(|decision| decision.score),
+                "matched_rules": decision.as_ref().map(|decision| &decision.matched_rules),
+            }),
+        };
+        let approval_request = approval_service::submit(&db_transaction, &request).await?;
+
+        db_transaction.commit().await?;
+
+        metrics::record_approval_request(&approval_request.operation.to_string(), &approval_request.status.to_string());
+
+        let approval_request_id = Some(approval_request.id);
+        return Ok((StatusCode::ACCEPTED, Json(AccountWithFee { account, fee, approval_request_id })));
     }
 
     // Creating transaction
@@ -205,17 +466,90 @@ pub async fn withdraw(
         amount: withdrawal.amount,
         transaction_type: TransactionType::Withdrawal,
         description: withdrawal.description.clone(),
+        metadata: withdrawal.metadata_with_counterparty(),
+        tags: withdrawal.tags.clone(),
     };
 
-    let _transaction = transaction_queries::create_transaction(&client, &transaction_request)
-        .await
-        .map_err(|e| AppError::Database(e.to_string()))?;
+    let transaction = transaction_queries::create_transaction(&db_transaction, &transaction_request)
+        .await?;
 
     // Subtracting amount from account balance
-    let updated_account = account_queries::update_account_balance(&client, id, withdrawal.amount, false)
-        .await
-        .map_err(|e| AppError::Database(e.to_string()))?
-        .ok_or_else(|| AppError::Validation("Insufficient balance or account not found".into()))?;
+    let updated_account = account_queries::update_account_balance(&db_transaction, id, withdrawal.amount, false)
+        .await?
+        .ok_or_else(|| {
+            metrics::record_insufficient_balance(&account.currency);
+            AppError::InsufficientFunds
+        })?;
+
+    event_service::transaction_created(&db_transaction, auth.user_id, &transaction).await?;
+    event_service::balance_changed(&db_transaction, &account, &updated_account, transaction.id).await?;
+
+    let assessment = match &decision {
+        Some(decision) => {
+            risk_service::record(
+                &db_transaction,
+                &account,
+                Some(transaction.id),
+                None,
+                withdrawal.amount,
+                Some(&ip_address),
+                decision,
+            )
+            .await?
+        }
+        None => None,
+    };
+    let screening_result = match (&withdrawal.counterparty_name, &screening) {
+        (Some(name), Some(screening)) => Some(
+            screening_service::record(
+                &db_transaction,
+                ScreeningSubject::Counterparty,
+                ScreeningTrigger::Withdrawal,
+                Some(auth.user_id),
+                Some(transaction.id),
+                name,
+                screening,
+            )
+            .await?,
+        ),
+        _ => None,
+    };
+    let held = held_for_screening || decision.is_some_and(|decision| decision.action == RiskAction::Review);
+
+    let updated_account = if held {
+        updated_account
+    } else {
+        fee_service::charge(&db_transaction, &updated_account, &fee, transaction.id)
+            .await?
+            .unwrap_or(updated_account)
+    };
 
-    Ok(Json(updated_account))
+    audit_queries::create_entry(
+        &db_transaction,
+        &NewAuditEntry::new(Some(auth.user_id), "account.withdrawal", "account", Some(id))
+            .before(&account)
+            .after(&updated_account)
+            .details(json!({
+                "transaction_id": transaction.id,
+                "amount": withdrawal.amount,
+                "fee": fee.fee,
+                "risk_assessment_id": assessment.as_ref().map(|assessment| assessment.id),
+                "screening_result_id": screening_result.as_ref().map(|result| result.id),
+                "held_for_review": held,
+            })),
+    )
+    .await?;
+
+    db_transaction.commit().await?;
+
+    if held {
+        return Ok((StatusCode::ACCEPTED, Json(AccountWithFee { account: updated_account, fee, approval_request_id: None })));
+    }
+
+    metrics::record_withdrawal(&updated_account.currency, withdrawal.amount);
+    if fee.fee > Decimal::ZERO {
+        metrics::record_fee(&updated_account.currency, &fee.transaction_type.to_string(), fee.fee);
+    }
+
+    Ok((StatusCode::OK, Json(AccountWithFee { account: updated_account, fee, approval_request_id: None })))
 }
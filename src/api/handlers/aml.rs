@@ -0,0 +1,164 @@
+use axum::{extract::{State, Extension}, Json};
+use deadpool_postgres::Pool;
+use serde_json::json;
+use uuid::Uuid;
+use crate::{
+    db::dal::{aml as aml_queries, audit as audit_queries},
+    base::{
+        aml,
+        error::AppError,
+        models::{
+            aml::{
+                AmlCase, AmlCaseDetail, AmlCasePaginationParams, AmlRun, AmlRunPaginationParams, AmlScenario,
+                AmlScenarioCode, UpdateAmlCaseStatusRequest, UpdateAmlScenarioRequest,
+            },
+            audit::NewAuditEntry,
+        },
+        pagination::{Page, PageRequest},
+    },
+    api::{extractors::{Path, ValidatedJson, ValidatedQuery}, middleware::auth::AuthUser},
+    services::aml as aml_service,
+};
+
+pub async fn list_scenarios(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+) -> Result<Json<Vec<AmlScenario>>, AppError> {
+    auth.require_admin()?;
+
+    let client = pool.get().await?;
+
+    let scenarios = aml_queries::list_scenarios(&client).await?;
+
+    Ok(Json(scenarios))
+}
+
+pub async fn update_scenario(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+    Path(code): Path<AmlScenarioCode>,
+    ValidatedJson(request): ValidatedJson<UpdateAmlScenarioRequest>,
+) -> Result<Json<AmlScenario>, AppError> {
+    auth.require_admin()?;
+
+    if let Some(params) = &request.params {
+        aml::parse_params(code, params)?;
+    }
+
+    let mut client = pool.get().await?;
+    let db_transaction = client.transaction().await?;
+
+    let scenario = aml_queries::get_scenario_for_update(&db_transaction, code)
+        .await?
+        .ok_or_else(|| AppError::NotFound("AML scenario not found".into()))?;
+
+    let updated = aml_queries::update_scenario(
+        &db_transaction,
+        code,
+        request.enabled.unwrap_or(scenario.enabled),
+        request.params.as_ref().unwrap_or(&scenario.params),
+        auth.user_id,
+    )
+    .await?;
+
+    audit_queries::create_entry(
+        &db_transaction,
+        &NewAuditEntry::new(Some(auth.user_id), "aml_scenario.updated", "aml_scenario", None)
+            .before(&scenario)
+            .after(&updated)
+            .details(json!({ "code": code })),
+    )
+    .await?;
+
+    db_transaction.commit().await?;
+
+    tracing::info!(scenario = %code, enabled = updated.enabled, "AML scenario updated");
+
+    Ok(Json(updated))
+}
+
+// Runs the monitoring now instead of waiting for the scheduled run.
+pub async fn create_run(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+) -> Result<Json<AmlRun>, AppError> {
+    auth.require_admin()?;
+
+    let mut client = pool.get().await?;
+
+    let run = aml_service::run(&mut client, Some(auth.user_id))
+        .await?
+        .ok_or_else(|| AppError::Conflict("An AML monitoring run is already in progress".into()))?;
+
+    Ok(Json(run))
+}
+
+pub async fn list_runs(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+    ValidatedQuery(params): ValidatedQuery<AmlRunPaginationParams>,
+) -> Result<Json<Page<AmlRun>>, AppError> {
+    auth.require_admin()?;
+
+    let client = pool.get().await?;
+
+    let page = PageRequest::new(params.limit, params.cursor.as_deref(), None, params.order)?;
+    let runs = aml_queries::list_runs(&client, &page).await?;
+
+    Ok(Json(Page::new(runs, &page, None)))
+}
+
+// The work queue is `status=OPEN`.
+pub async fn list_cases(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+    ValidatedQuery(params): ValidatedQuery<AmlCasePaginationParams>,
+) -> Result<Json<Page<AmlCase>>, AppError> {
+    auth.require_admin()?;
+
+    let client = pool.get().await?;
+
+    let page = PageRequest::new(params.limit, params.cursor.as_deref(), None, params.order)?;
+    let cases = aml_queries::list_cases(&client, &params, &page).await?;
+
+    let total_count = if params.include_total {
+        Some(aml_queries::count_cases(&client, &params).await?)
+    } else {
+        None
+    };
+
+    Ok(Json(Page::new(cases, &page, total_count)))
+}
+
+pub async fn get_case(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<AmlCaseDetail>, AppError> {
+    auth.require_admin()?;
+
+    let client = pool.get().await?;
+
+    let case = aml_queries::get_case_by_id(&client, id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("AML case not found".into()))?;
+    let transactions = aml_queries::list_case_transactions(&client, &case).await?;
+
+    Ok(Json(AmlCaseDetail { case, transactions }))
+}
+
+pub async fn update_case_status(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+    Path(id): Path<Uuid>,
+    ValidatedJson(request): ValidatedJson<UpdateAmlCaseStatusRequest>,
+) -> Result<Json<AmlCase>, AppError> {
+    auth.require_admin()?;
+
+    let mut client = pool.get().await?;
+
+    let case = aml_service::set_case_status(&mut client, auth.user_id, id, request.status, request.note.as_deref())
+        .await?;
+
+    Ok(Json(case))
+}
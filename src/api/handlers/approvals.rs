@@ -0,0 +1,286 @@
+use axum::{extract::{State, Extension}, http::StatusCode, Json};
+use deadpool_postgres::{GenericClient, Pool};
+use rust_decimal::Decimal;
+use serde_json::json;
+use uuid::Uuid;
+use crate::{
+    db::dal::{approvals as approval_queries, audit as audit_queries},
+    base::{
+        error::AppError,
+        metrics,
+        models::{
+            approvals::{
+                ApprovalComment, ApprovalOperation, ApprovalRequest, ApprovalRequestDetail,
+                ApprovalRequestPaginationParams, ApprovalThreshold, BalanceAdjustmentRequest,
+                CreateApprovalCommentRequest, SetApprovalThresholdRequest,
+            },
+            audit::NewAuditEntry,
+            risk::ReviewDecisionRequest,
+        },
+        pagination::{Page, PageRequest},
+    },
+    api::{extractors::{Path, ValidatedJson, ValidatedQuery}, middleware::auth::AuthUser},
+    services::{approvals as approval_service, screening::WatchlistCache},
+};
+
+// The user's own requests, whatever `requested_by` says.
+pub async fn list_own_requests(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+    ValidatedQuery(mut params): ValidatedQuery<ApprovalRequestPaginationParams>,
+) -> Result<Json<Page<ApprovalRequest>>, AppError> {
+    params.requested_by = Some(auth.user_id);
+
+    list(&pool, params).await
+}
+
+pub async fn get_own_request(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ApprovalRequestDetail>, AppError> {
+    let client = pool.get().await?;
+
+    let request = own_request(&client, auth.user_id, id).await?;
+    let comments = approval_queries::list_comments(&client, id).await?;
+
+    Ok(Json(ApprovalRequestDetail { request, comments }))
+}
+
+pub async fn comment_own_request(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+    Path(id): Path<Uuid>,
+    ValidatedJson(request): ValidatedJson<CreateApprovalCommentRequest>,
+) -> Result<(StatusCode, Json<ApprovalComment>), AppError> {
+    let mut client = pool.get().await?;
+
+    let approval_request = own_request(&client, auth.user_id, id).await?;
+    let comment = approval_service::comment(&mut client, auth.user_id, &approval_request, &request.body).await?;
+
+    Ok((StatusCode::CREATED, Json(comment)))
+}
+
+// Only the user who made a request can cancel it. Nothing was booked for it
+// yet, so cancelling only closes the request.
+pub async fn cancel_request(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ApprovalRequest>, AppError> {
+    let mut client = pool.get().await?;
+
+    let request = approval_service::cancel(&mut client, auth.user_id, id).await?;
+
+    Ok(Json(request))
+}
+
+async fn own_request(client: &impl GenericClient, user_id: Uuid, id: Uuid) -> Result<ApprovalRequest, AppError> {
+    let request = approval_queries::get_request_by_id(client, id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Approval request not found".into()))?;
+
+    if request.requested_by != Some(user_id) {
+        return Err(AppError::Forbidden("Not allowed to access this approval request".into()));
+    }
+    Ok(request)
+}
+
+// The work queue is `status=PENDING`.
+pub async fn list_requests(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+    ValidatedQuery(params): ValidatedQuery<ApprovalRequestPaginationParams>,
+) -> Result<Json<Page<ApprovalRequest>>, AppError> {
+    auth.require_admin()?;
+
+    list(&pool, params).await
+}
+
+async fn list(pool: &Pool, params: ApprovalRequestPaginationParams) -> Result<Json<Page<ApprovalRequest>>, AppError> {
+    let client = pool.get().await?;
+
+    let page = PageRequest::new(params.limit, params.cursor.as_deref(), None, params.order)?;
+    let requests = approval_queries::list_requests(&client, &params, &page).await?;
+
+    let total_count = if params.include_total {
+        Some(approval_queries::count_requests(&client, &params).await?)
+    } else {
+        None
+    };
+
+    Ok(Json(Page::new(requests, &page, total_count)))
+}
+
+pub async fn get_request(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ApprovalRequestDetail>, AppError> {
+    auth.require_admin()?;
+
+    let client = pool.get().await?;
+
+    let request = approval_queries::get_request_by_id(&client, id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Approval request not found".into()))?;
+    let comments = approval_queries::list_comments(&client, id).await?;
+
+    Ok(Json(ApprovalRequestDetail { request, comments }))
+}
+
+pub async fn comment_request(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+    Path(id): Path<Uuid>,
+    ValidatedJson(request): ValidatedJson<CreateApprovalCommentRequest>,
+) -> Result<(StatusCode, Json<ApprovalComment>), AppError> {
+    auth.require_admin()?;
+
+    let mut client = pool.get().await?;
+
+    let approval_request = approval_queries::get_request_by_id(&client, id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Approval request not found".into()))?;
+    let comment = approval_service::comment(&mut client, auth.user_id, &approval_request, &request.body).await?;
+
+    Ok((StatusCode::CREATED, Json(comment)))
+}
+
+pub async fn approve_request(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+    Extension(watchlists): Extension<WatchlistCache>,
+    Path(id): Path<Uuid>,
+    ValidatedJson(request): ValidatedJson<ReviewDecisionRequest>,
+) -> Result<Json<ApprovalRequest>, AppError> {
+    auth.require_admin()?;
+
+    let mut client = pool.get().await?;
+
+    let (approved, fee) = approval_service::approve(&mut client, &watchlists, auth.user_id, id, request.note.as_deref()).await?;
+
+    if approved.operation == ApprovalOperation::Withdrawal
+        && let (Some(fee), Some(amount)) = (fee, approved.amount)
+    {
+        metrics::record_withdrawal(&fee.currency, amount);
+        if fee.fee > Decimal::ZERO {
+            metrics::record_fee(&fee.currency, &fee.transaction_type.to_string(), fee.fee);
+        }
+    }
+
+    Ok(Json(approved))
+}
+
+pub async fn reject_request(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+    Path(id): Path<Uuid>,
+    ValidatedJson(request): ValidatedJson<ReviewDecisionRequest>,
+) -> Result<Json<ApprovalRequest>, AppError> {
+    auth.require_admin()?;
+
+    let mut client = pool.get().await?;
+
+    let rejected = approval_service::reject(&mut client, auth.user_id, id, request.note.as_deref()).await?;
+
+    Ok(Json(rejected))
+}
+
+// The adjustment is booked when another admin approves the request.
+pub async fn create_adjustment(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+    Path(id): Path<Uuid>,
+    ValidatedJson(request): ValidatedJson<BalanceAdjustmentRequest>,
+) -> Result<(StatusCode, Json<ApprovalRequest>), AppError> {
+    auth.require_admin()?;
+
+    let mut client = pool.get().await?;
+
+    let approval_request = approval_service::request_adjustment(&mut client, auth.user_id, id, &request).await?;
+
+    Ok((StatusCode::ACCEPTED, Json(approval_request)))
+}
+
+pub async fn list_thresholds(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+) -> Result<Json<Vec<ApprovalThreshold>>, AppError> {
+    auth.require_admin()?;
+
+    let client = pool.get().await?;
+
+    let thresholds = approval_queries::list_thresholds(&client).await?;
+
+    Ok(Json(thresholds))
+}
+
+// Creates the currency's threshold or replaces it. Requests already pending
+// are not affected.
+pub async fn set_threshold(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+    ValidatedJson(request): ValidatedJson<SetApprovalThresholdRequest>,
+) -> Result<(StatusCode, Json<ApprovalThreshold>), AppError> {
+    auth.require_admin()?;
+
+    let mut client = pool.get().await?;
+    let db_transaction = client.transaction().await?;
+
+    let before = approval_queries::get_threshold(&db_transaction, &request.currency).await?;
+    let threshold = approval_queries::set_threshold(
+        &db_transaction,
+        &request.currency,
+        request.withdrawal_threshold,
+        auth.user_id,
+    )
+    .await?;
+
+    let mut entry = NewAuditEntry::new(Some(auth.user_id), "approval_threshold.set", "approval_threshold", None)
+        .after(&threshold)
+        .details(json!({ "currency": threshold.currency }));
+    if let Some(before) = &before {
+        entry = entry.before(before);
+    }
+    audit_queries::create_entry(&db_transaction, &entry).await?;
+
+    db_transaction.commit().await?;
+
+    tracing::info!(
+        currency = %threshold.currency,
+        withdrawal_threshold = %threshold.withdrawal_threshold,
+        "Approval threshold set"
+    );
+
+    let status = if before.is_some() { StatusCode::OK } else { StatusCode::CREATED };
+    Ok((status, Json(threshold)))
+}
+
+// Removes the threshold; withdrawals in the currency then need no approval.
+pub async fn delete_threshold(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+    Path(currency): Path<String>,
+) -> Result<StatusCode, AppError> {
+    auth.require_admin()?;
+
+    let mut client = pool.get().await?;
+    let db_transaction = client.transaction().await?;
+
+    let threshold = approval_queries::delete_threshold(&db_transaction, &currency)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Approval threshold not found".into()))?;
+
+    audit_queries::create_entry(
+        &db_transaction,
+        &NewAuditEntry::new(Some(auth.user_id), "approval_threshold.deleted", "approval_threshold", None)
+            .before(&threshold)
+            .details(json!({ "currency": threshold.currency })),
+    )
+    .await?;
+
+    db_transaction.commit().await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
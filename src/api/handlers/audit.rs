@@ -0,0 +1,34 @@
+use axum::{extract::{State, Extension}, Json};
+use deadpool_postgres::Pool;
+use crate::{
+    db::dal::audit as audit_queries,
+    base::{
+        error::AppError,
+        models::audit::{AuditEntry, AuditLogFilter, AuditLogParams},
+        pagination::{Page, PageRequest},
+    },
+    api::{extractors::ValidatedQuery, middleware::auth::AuthUser},
+};
+
+pub async fn list_audit_log(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+    ValidatedQuery(params): ValidatedQuery<AuditLogParams>,
+) -> Result<Json<Page<AuditEntry>>, AppError> {
+    auth.require_admin()?;
+
+    let client = pool.get().await?;
+
+    let page = PageRequest::new(params.limit, params.cursor.as_deref(), None, params.order)?;
+    let filter = AuditLogFilter::from(&params);
+
+    let entries = audit_queries::list_entries(&client, &filter, &page).await?;
+
+    let total_count = if params.include_total {
+        Some(audit_queries::count_entries(&client, &filter).await?)
+    } else {
+        None
+    };
+
+    Ok(Json(Page::new(entries, &page, total_count)))
+}
@@ -0,0 +1,124 @@
+use axum::{
+    extract::{ws::{Message, WebSocket, WebSocketUpgrade}, Extension, State},
+    http::HeaderMap,
+    response::{sse::{Event, KeepAlive, Sse}, Response},
+};
+use deadpool_postgres::Pool;
+use futures_util::{Stream, StreamExt};
+use uuid::Uuid;
+use crate::{
+    db::dal::accounts as account_queries,
+    base::{error::AppError, models::events::{AccountEvent, AccountEventParams}},
+    api::{extractors::{Path, ValidatedQuery}, middleware::auth::AuthUser},
+    services::events::{AccountEventFeed, AccountEventHub},
+};
+
+const LAST_EVENT_ID_HEADER: &str = "last-event-id";
+
+// Server-sent events of the account. Each event carries its id, so an
+// `EventSource` that reconnects resumes where it left off.
+pub async fn account_events(
+    Extension(auth): Extension<AuthUser>,
+    Extension(hub): Extension<AccountEventHub>,
+    State(pool): State<Pool>,
+    Path(id): Path<Uuid>,
+    headers: HeaderMap,
+    ValidatedQuery(params): ValidatedQuery<AccountEventParams>,
+) -> Result<Sse<impl Stream<Item = Result<Event, AppError>>>, AppError> {
+    let last_event_id = match headers.get(LAST_EVENT_ID_HEADER) {
+        Some(value) => Some(
+            value
+                .to_str()
+                .ok()
+                .and_then(|value| value.trim().parse::<i64>().ok())
+                .filter(|id| *id >= 0)
+                .ok_or_else(|| {
+                    AppError::invalid_field("Last-Event-ID", "INVALID_FORMAT", "Must be an event id")
+                })?,
+        ),
+        None => params.last_event_id,
+    };
+
+    let feed = subscribe(&pool, &hub, &auth, id, last_event_id).await?;
+
+    let events = feed.into_stream().map(|event| {
+        let event = event?;
+        Event::default()
+            .id(event.id.to_string())
+            .event(&event.event_type)
+            .json_data(&event)
+            .map_err(|e| AppError::Internal(e.to_string()))
+    });
+
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
+
+// The same events over a WebSocket, one JSON text message each. Resume with
+// `?last_event_id=`.
+pub async fn account_events_ws(
+    Extension(auth): Extension<AuthUser>,
+    Extension(hub): Extension<AccountEventHub>,
+    State(pool): State<Pool>,
+    Path(id): Path<Uuid>,
+    ValidatedQuery(params): ValidatedQuery<AccountEventParams>,
+    upgrade: WebSocketUpgrade,
+) -> Result<Response, AppError> {
+    let feed = subscribe(&pool, &hub, &auth, id, params.last_event_id).await?;
+
+    Ok(upgrade.on_upgrade(move |socket| stream_events(socket, feed)))
+}
+
+async fn stream_events(mut socket: WebSocket, mut feed: AccountEventFeed) {
+    loop {
+        tokio::select! {
+            event = feed.next() => {
+                let Some(event) = event else { break };
+                if send_event(&mut socket, event).await.is_err() {
+                    return;
+                }
+            }
+            // Anything from the client other than a close is ignored
+            message = socket.recv() => match message {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+
+    let _ = socket.send(Message::Close(None)).await;
+}
+
+async fn send_event(socket: &mut WebSocket, event: Result<AccountEvent, AppError>) -> Result<(), axum::Error> {
+    match event {
+        Ok(event) => {
+            let text = serde_json::to_string(&event).map_err(axum::Error::new)?;
+            socket.send(Message::Text(text.into())).await
+        }
+        Err(e) => {
+            tracing::error!(error = %e, "Account event stream failed");
+            Err(axum::Error::new(e))
+        }
+    }
+}
+
+async fn subscribe(
+    pool: &Pool,
+    hub: &AccountEventHub,
+    auth: &AuthUser,
+    account_id: Uuid,
+    last_event_id: Option<i64>,
+) -> Result<AccountEventFeed, AppError> {
+    let client = pool.get().await?;
+
+    let account = account_queries::get_account_by_id(&client, account_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Account not found".into()))?;
+
+    // Users can only follow their own accounts
+    if account.user_id != auth.user_id {
+        return Err(AppError::Forbidden("Not allowed to access this account".into()));
+    }
+    drop(client);
+
+    hub.subscribe(pool, account_id, last_event_id).await
+}
@@ -0,0 +1,128 @@
+use axum::{
+    extract::{State, Extension},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::Utc;
+use deadpool_postgres::Pool;
+use uuid::Uuid;
+use crate::{
+    base::{
+        constants::MAX_LISTED_EXPORTS,
+        error::AppError,
+        exports::{download_url, verify_download_signature},
+        models::{
+            audit::NewAuditEntry,
+            exports::{DataExport, ExportDownloadParams, ExportStatus},
+        },
+    },
+    db::dal::{audit as audit_queries, exports as export_queries},
+    api::{extractors::{Path, ValidatedQuery}, middleware::auth::AuthUser},
+};
+
+// Queues an export of everything held about the caller. The archive is built
+// by a background job; poll the export until it is READY for a download link.
+pub async fn request_export(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+) -> Result<(StatusCode, Json<DataExport>), AppError> {
+    let mut client = pool.get().await?;
+
+    if let Some(active) = export_queries::get_active_export(&client, auth.user_id).await? {
+        return Err(AppError::Conflict(format!("Export {} is already in progress", active.id)));
+    }
+
+    let db_transaction = client.transaction().await?;
+
+    let export = export_queries::create_export(&db_transaction, auth.user_id).await?;
+
+    audit_queries::create_entry(
+        &db_transaction,
+        &NewAuditEntry::new(Some(auth.user_id), "data_export.requested", "data_export", Some(export.id)),
+    )
+    .await?;
+
+    db_transaction.commit().await?;
+
+    Ok((StatusCode::ACCEPTED, Json(export)))
+}
+
+pub async fn list_exports(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+) -> Result<Json<Vec<DataExport>>, AppError> {
+    let client = pool.get().await?;
+
+    let exports = export_queries::list_exports_by_user_id(&client, auth.user_id, MAX_LISTED_EXPORTS).await?;
+    let key = signing_key()?;
+
+    Ok(Json(exports.into_iter().map(|export| with_download_url(export, &key)).collect()))
+}
+
+pub async fn get_export(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<DataExport>, AppError> {
+    let client = pool.get().await?;
+
+    let export = export_queries::get_export_by_id(&client, id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Export not found".into()))?;
+
+    if export.user_id != auth.user_id {
+        return Err(AppError::Forbidden("Not allowed to access this export".into()));
+    }
+
+    Ok(Json(with_download_url(export, &signing_key()?)))
+}
+
+// Unauthenticated: the signed link itself grants access, so it can be opened
+// straight from a browser or an email.
+pub async fn download_export(
+    State(pool): State<Pool>,
+    Path(id): Path<Uuid>,
+    ValidatedQuery(params): ValidatedQuery<ExportDownloadParams>,
+) -> Result<Response, AppError> {
+    if !verify_download_signature(id, params.expires, &params.signature, &signing_key()?) {
+        return Err(AppError::Forbidden("Invalid download link".into()));
+    }
+    if params.expires <= Utc::now().timestamp() {
+        return Err(AppError::Forbidden("Download link has expired".into()));
+    }
+
+    let client = pool.get().await?;
+
+    let archive = export_queries::get_archive(&client, id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Export not found or expired".into()))?;
+
+    let disposition = format!("attachment; filename=\"data-export-{}.zip\"", id);
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/zip".to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        archive,
+    )
+        .into_response())
+}
+
+fn with_download_url(mut export: DataExport, key: &[u8]) -> DataExport {
+    if export.status == ExportStatus::Ready
+        && let Some(expires_at) = export.expires_at
+        && expires_at > Utc::now()
+    {
+        export.download_url = Some(download_url(export.id, expires_at, key));
+    }
+    export
+}
+
+// Links are signed with the token secret, under their own prefix.
+fn signing_key() -> Result<Vec<u8>, AppError> {
+    std::env::var("JWT_SECRET")
+        .map(String::into_bytes)
+        .map_err(|_| AppError::Internal("JWT_SECRET not found".into()))
+}
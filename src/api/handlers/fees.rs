@@ -0,0 +1,149 @@
+use axum::{extract::{State, Extension}, http::StatusCode, Json};
+use deadpool_postgres::Pool;
+use serde_json::json;
+use uuid::Uuid;
+use crate::{
+    db::dal::{accounts as account_queries, audit as audit_queries, fees as fee_queries},
+    base::{
+        error::AppError,
+        models::{
+            audit::NewAuditEntry,
+            fees::{CreateFeeScheduleRequest, FeeBreakdown, FeePreviewRequest, FeeSchedule, FeeSchedulePaginationParams},
+        },
+        pagination::{Page, PageRequest},
+    },
+    api::{extractors::{Path, ValidatedJson, ValidatedQuery}, middleware::auth::AuthUser},
+    services::fees as fee_service,
+};
+
+// The fee a transaction would be charged now, without booking anything.
+pub async fn preview_fee(
+    State(pool): State<Pool>,
+    ValidatedJson(request): ValidatedJson<FeePreviewRequest>,
+) -> Result<Json<FeeBreakdown>, AppError> {
+    let client = pool.get().await?;
+
+    let fee = fee_service::quote(&client, request.transaction_type, &request.currency, request.amount).await?;
+
+    Ok(Json(fee))
+}
+
+// The new schedule replaces the one active for the same currency and
+// transaction type.
+pub async fn create_fee_schedule(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+    ValidatedJson(request): ValidatedJson<CreateFeeScheduleRequest>,
+) -> Result<(StatusCode, Json<FeeSchedule>), AppError> {
+    auth.require_admin()?;
+
+    let mut client = pool.get().await?;
+
+    let revenue_account = account_queries::get_account_by_id(&client, request.revenue_account_id)
+        .await?
+        .ok_or_else(|| AppError::invalid_field("revenue_account_id", "NOT_FOUND", "revenue account not found"))?;
+    if revenue_account.currency != request.currency {
+        return Err(AppError::invalid_field(
+            "revenue_account_id",
+            "CURRENCY_MISMATCH",
+            "revenue account must have the schedule's currency",
+        ));
+    }
+    revenue_account.ensure_can_credit()?;
+
+    let db_transaction = client.transaction().await?;
+
+    let replaced =
+        fee_queries::deactivate_active_schedule(&db_transaction, &request.currency, request.transaction_type).await?;
+    let schedule = fee_queries::create_schedule(&db_transaction, auth.user_id, &request).await?;
+
+    audit_queries::create_entry(
+        &db_transaction,
+        &NewAuditEntry::new(Some(auth.user_id), "fee_schedule.created", "fee_schedule", Some(schedule.id))
+            .after(&schedule)
+            .details(json!({ "replaced_schedule_id": replaced.as_ref().map(|replaced| replaced.id) })),
+    )
+    .await?;
+
+    db_transaction.commit().await?;
+
+    tracing::info!(
+        fee_schedule_id = %schedule.id,
+        currency = %schedule.currency,
+        transaction_type = %schedule.transaction_type,
+        "Fee schedule created"
+    );
+
+    Ok((StatusCode::CREATED, Json(schedule)))
+}
+
+pub async fn list_fee_schedules(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+    ValidatedQuery(params): ValidatedQuery<FeeSchedulePaginationParams>,
+) -> Result<Json<Page<FeeSchedule>>, AppError> {
+    auth.require_admin()?;
+
+    let client = pool.get().await?;
+
+    let page = PageRequest::new(params.limit, params.cursor.as_deref(), None, params.order)?;
+    let schedules = fee_queries::list_schedules(&client, &params, &page).await?;
+
+    let total_count = if params.include_total {
+        Some(fee_queries::count_schedules(&client, &params).await?)
+    } else {
+        None
+    };
+
+    Ok(Json(Page::new(schedules, &page, total_count)))
+}
+
+pub async fn get_fee_schedule(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<FeeSchedule>, AppError> {
+    auth.require_admin()?;
+
+    let client = pool.get().await?;
+
+    let schedule = fee_queries::get_schedule_by_id(&client, id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Fee schedule not found".into()))?;
+
+    Ok(Json(schedule))
+}
+
+// Stops charging the schedule's fee; there is no fee for its currency and
+// transaction type until a new schedule is created.
+pub async fn deactivate_fee_schedule(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<FeeSchedule>, AppError> {
+    auth.require_admin()?;
+
+    let mut client = pool.get().await?;
+
+    let schedule = fee_queries::get_schedule_by_id(&client, id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Fee schedule not found".into()))?;
+
+    let db_transaction = client.transaction().await?;
+
+    let deactivated = fee_queries::deactivate_schedule(&db_transaction, id)
+        .await?
+        .ok_or_else(|| AppError::Conflict("Fee schedule is not active".into()))?;
+
+    audit_queries::create_entry(
+        &db_transaction,
+        &NewAuditEntry::new(Some(auth.user_id), "fee_schedule.deactivated", "fee_schedule", Some(id))
+            .before(&schedule)
+            .after(&deactivated),
+    )
+    .await?;
+
+    db_transaction.commit().await?;
+
+    Ok(Json(deactivated))
+}
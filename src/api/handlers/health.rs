@@ -0,0 +1,44 @@
+use axum::{extract::State, http::StatusCode, Json};
+use deadpool_postgres::Pool;
+use serde_json::{json, Value};
+use std::fmt::Display;
+use crate::db::migrations;
+
+// Liveness: the process is up and serving requests.
+pub async fn healthz() -> Json<Value> {
+    Json(json!({ "status": "ok" }))
+}
+
+// Readiness: a pooled connection can run a query and the schema is current.
+pub async fn readyz(State(pool): State<Pool>) -> (StatusCode, Json<Value>) {
+    let client = match pool.get().await {
+        Ok(client) => client,
+        Err(e) => return not_ready("database unavailable", e),
+    };
+
+    if let Err(e) = client.simple_query("SELECT 1").await {
+        return not_ready("database query failed", e);
+    }
+
+    let expected = migrations::latest_version();
+    match migrations::current_version(&client).await {
+        Ok(version) if version >= expected => (
+            StatusCode::OK,
+            Json(json!({ "status": "ready", "schema_version": version })),
+        ),
+        Ok(version) => not_ready(
+            "migrations pending",
+            format!("schema version {} is behind {}", version, expected),
+        ),
+        Err(e) => not_ready("migrations pending", e),
+    }
+}
+
+// Details are logged rather than returned since the probe is unauthenticated.
+fn not_ready(reason: &str, detail: impl Display) -> (StatusCode, Json<Value>) {
+    tracing::warn!("Readiness check failed: {}: {}", reason, detail);
+    (
+        StatusCode::SERVICE_UNAVAILABLE,
+        Json(json!({ "status": "unavailable", "reason": reason })),
+    )
+}
@@ -0,0 +1,211 @@
+use axum::{extract::{State, Extension}, http::StatusCode, Json};
+use deadpool_postgres::Pool;
+use serde_json::json;
+use uuid::Uuid;
+use crate::{
+    db::dal::{audit as audit_queries, kyc as kyc_queries},
+    base::{
+        error::AppError,
+        models::{
+            audit::NewAuditEntry,
+            kyc::{
+                CreateKycApplicationRequest, KycApplication, KycApplicationPaginationParams, KycDocument, KycProfile,
+                KycTier, KycTierCaps, SetKycTierCapsRequest, SubmitKycDocumentRequest,
+            },
+            risk::ReviewDecisionRequest,
+        },
+        pagination::{Page, PageRequest},
+    },
+    api::{extractors::{Path, ValidatedJson, ValidatedQuery}, middleware::auth::AuthUser},
+    services::kyc as kyc_service,
+};
+
+pub async fn get_own_kyc(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+) -> Result<Json<KycProfile>, AppError> {
+    let client = pool.get().await?;
+
+    let profile = kyc_service::profile(&client, auth.user_id).await?;
+
+    Ok(Json(profile))
+}
+
+// The file itself goes to the document store; only its description is sent here.
+pub async fn submit_document(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+    ValidatedJson(request): ValidatedJson<SubmitKycDocumentRequest>,
+) -> Result<(StatusCode, Json<KycDocument>), AppError> {
+    let mut client = pool.get().await?;
+
+    let document = kyc_service::submit_document(&mut client, auth.user_id, &request).await?;
+
+    Ok((StatusCode::CREATED, Json(document)))
+}
+
+pub async fn create_application(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+    ValidatedJson(request): ValidatedJson<CreateKycApplicationRequest>,
+) -> Result<(StatusCode, Json<KycApplication>), AppError> {
+    let mut client = pool.get().await?;
+
+    let application = kyc_service::apply(&mut client, auth.user_id, request.tier).await?;
+
+    Ok((StatusCode::CREATED, Json(application)))
+}
+
+pub async fn get_user_kyc(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<KycProfile>, AppError> {
+    auth.require_admin()?;
+
+    let client = pool.get().await?;
+
+    let profile = kyc_service::profile(&client, id).await?;
+
+    Ok(Json(profile))
+}
+
+// The review queue is `status=PENDING`.
+pub async fn list_applications(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+    ValidatedQuery(params): ValidatedQuery<KycApplicationPaginationParams>,
+) -> Result<Json<Page<KycApplication>>, AppError> {
+    auth.require_admin()?;
+
+    let client = pool.get().await?;
+
+    let page = PageRequest::new(params.limit, params.cursor.as_deref(), None, params.order)?;
+    let applications = kyc_queries::list_applications(&client, &params, &page).await?;
+
+    let total_count = if params.include_total {
+        Some(kyc_queries::count_applications(&client, &params).await?)
+    } else {
+        None
+    };
+
+    Ok(Json(Page::new(applications, &page, total_count)))
+}
+
+pub async fn get_application(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<KycApplication>, AppError> {
+    auth.require_admin()?;
+
+    let client = pool.get().await?;
+
+    let application = kyc_queries::get_application_by_id(&client, id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("KYC application not found".into()))?;
+
+    Ok(Json(application))
+}
+
+pub async fn approve_application(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+    Path(id): Path<Uuid>,
+    ValidatedJson(request): ValidatedJson<ReviewDecisionRequest>,
+) -> Result<Json<KycApplication>, AppError> {
+    auth.require_admin()?;
+
+    let mut client = pool.get().await?;
+
+    let application = kyc_service::approve(&mut client, auth.user_id, id, request.note.as_deref()).await?;
+
+    Ok(Json(application))
+}
+
+pub async fn reject_application(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+    Path(id): Path<Uuid>,
+    ValidatedJson(request): ValidatedJson<ReviewDecisionRequest>,
+) -> Result<Json<KycApplication>, AppError> {
+    auth.require_admin()?;
+
+    let mut client = pool.get().await?;
+
+    let application = kyc_service::reject(&mut client, auth.user_id, id, request.note.as_deref()).await?;
+
+    Ok(Json(application))
+}
+
+pub async fn list_tier_caps(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+) -> Result<Json<Vec<KycTierCaps>>, AppError> {
+    auth.require_admin()?;
+
+    let client = pool.get().await?;
+
+    let caps = kyc_queries::list_caps(&client, None).await?;
+
+    Ok(Json(caps))
+}
+
+// Creates the caps of the tier in the currency, or replaces all of the ones it has.
+pub async fn set_tier_caps(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+    ValidatedJson(request): ValidatedJson<SetKycTierCapsRequest>,
+) -> Result<(StatusCode, Json<KycTierCaps>), AppError> {
+    auth.require_admin()?;
+
+    let mut client = pool.get().await?;
+    let db_transaction = client.transaction().await?;
+
+    let before = kyc_queries::get_caps(&db_transaction, request.tier, &request.currency).await?;
+    let caps =
+        kyc_queries::set_caps(&db_transaction, request.tier, &request.currency, &request.caps(), auth.user_id).await?;
+
+    let mut entry = NewAuditEntry::new(Some(auth.user_id), "kyc_tier_caps.set", "kyc_tier_caps", None)
+        .after(&caps)
+        .details(json!({ "tier": caps.tier, "currency": caps.currency }));
+    if let Some(before) = &before {
+        entry = entry.before(before);
+    }
+    audit_queries::create_entry(&db_transaction, &entry).await?;
+
+    db_transaction.commit().await?;
+
+    tracing::info!(tier = %caps.tier, currency = %caps.currency, "KYC tier caps set");
+
+    let status = if before.is_some() { StatusCode::OK } else { StatusCode::CREATED };
+    Ok((status, Json(caps)))
+}
+
+// Removes the caps; the tier is then uncapped in the currency.
+pub async fn delete_tier_caps(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+    Path((tier, currency)): Path<(KycTier, String)>,
+) -> Result<StatusCode, AppError> {
+    auth.require_admin()?;
+
+    let mut client = pool.get().await?;
+    let db_transaction = client.transaction().await?;
+
+    let caps = kyc_queries::delete_caps(&db_transaction, tier, &currency)
+        .await?
+        .ok_or_else(|| AppError::NotFound("KYC tier caps not found".into()))?;
+
+    audit_queries::create_entry(
+        &db_transaction,
+        &NewAuditEntry::new(Some(auth.user_id), "kyc_tier_caps.deleted", "kyc_tier_caps", None)
+            .before(&caps)
+            .details(json!({ "tier": caps.tier, "currency": caps.currency })),
+    )
+    .await?;
+
+    db_transaction.commit().await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,168 @@
+use axum::{extract::{State, Extension}, http::StatusCode, Json};
+use chrono::Utc;
+use deadpool_postgres::Pool;
+use uuid::Uuid;
+use crate::{
+    db::dal::{accounts as account_queries, audit as audit_queries, limits as limit_queries, users as user_queries},
+    base::{
+        error::AppError,
+        models::{
+            audit::NewAuditEntry,
+            limits::{AccountLimits, LimitPaginationParams, LimitScope, SetTransactionLimitRequest, TransactionLimit},
+        },
+        pagination::{Page, PageRequest},
+    },
+    api::{extractors::{Path, ValidatedJson, ValidatedQuery}, middleware::auth::AuthUser},
+    services::limits as limit_service,
+};
+
+// The limits that apply to the account and how much of each is left.
+pub async fn get_account_limits(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<AccountLimits>, AppError> {
+    let client = pool.get().await?;
+
+    let account = account_queries::get_account_by_id(&client, id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Account not found".into()))?;
+
+    if account.user_id != auth.user_id {
+        return Err(AppError::Forbidden("Not allowed to access this account".into()));
+    }
+
+    let limits = limit_service::headroom(&client, &account, Utc::now()).await?;
+
+    Ok(Json(limits))
+}
+
+// Creates the limits of the target, or replaces all of the ones it has.
+pub async fn set_limit(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+    ValidatedJson(request): ValidatedJson<SetTransactionLimitRequest>,
+) -> Result<(StatusCode, Json<TransactionLimit>), AppError> {
+    auth.require_admin()?;
+
+    let mut client = pool.get().await?;
+
+    let currency = match request.scope {
+        LimitScope::Account => {
+            let account_id = request.account_id.unwrap_or_default();
+            let account = account_queries::get_account_by_id(&client, account_id)
+                .await?
+                .ok_or_else(|| AppError::invalid_field("account_id", "NOT_FOUND", "account not found"))?;
+            if request.currency.as_ref().is_some_and(|currency| *currency != account.currency) {
+                return Err(AppError::invalid_field(
+                    "currency",
+                    "CURRENCY_MISMATCH",
+                    "currency must match the account's currency",
+                ));
+            }
+            account.currency
+        }
+        LimitScope::User => {
+            let user_id = request.user_id.unwrap_or_default();
+            user_queries::get_user_by_id(&client, user_id)
+                .await?
+                .ok_or_else(|| AppError::invalid_field("user_id", "NOT_FOUND", "user not found"))?;
+            request.currency.clone().unwrap_or_default()
+        }
+        LimitScope::Default => request.currency.clone().unwrap_or_default(),
+    };
+
+    let db_transaction = client.transaction().await?;
+
+    let before =
+        limit_queries::find_limit(&db_transaction, request.scope, request.user_id, request.account_id, &currency)
+            .await?;
+    let limit = limit_queries::set_limit(
+        &db_transaction,
+        request.scope,
+        request.user_id,
+        request.account_id,
+        &currency,
+        &request.limits(),
+        auth.user_id,
+    )
+    .await?;
+
+    let mut entry = NewAuditEntry::new(Some(auth.user_id), "transaction_limit.set", "transaction_limit", Some(limit.id))
+        .after(&limit);
+    if let Some(before) = &before {
+        entry = entry.before(before);
+    }
+    audit_queries::create_entry(&db_transaction, &entry).await?;
+
+    db_transaction.commit().await?;
+
+    tracing::info!(limit_id = %limit.id, scope = %limit.scope, currency = %limit.currency, "Transaction limits set");
+
+    let status = if before.is_some() { StatusCode::OK } else { StatusCode::CREATED };
+    Ok((status, Json(limit)))
+}
+
+pub async fn list_limits(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+    ValidatedQuery(params): ValidatedQuery<LimitPaginationParams>,
+) -> Result<Json<Page<TransactionLimit>>, AppError> {
+    auth.require_admin()?;
+
+    let client = pool.get().await?;
+
+    let page = PageRequest::new(params.limit, params.cursor.as_deref(), None, params.order)?;
+    let limits = limit_queries::list_limits(&client, &params, &page).await?;
+
+    let total_count = if params.include_total {
+        Some(limit_queries::count_limits(&client, &params).await?)
+    } else {
+        None
+    };
+
+    Ok(Json(Page::new(limits, &page, total_count)))
+}
+
+pub async fn get_limit(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<TransactionLimit>, AppError> {
+    auth.require_admin()?;
+
+    let client = pool.get().await?;
+
+    let limit = limit_queries::get_limit_by_id(&client, id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Transaction limit not found".into()))?;
+
+    Ok(Json(limit))
+}
+
+// Removes the limits; an account falls back to the defaults for its currency.
+pub async fn delete_limit(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, AppError> {
+    auth.require_admin()?;
+
+    let mut client = pool.get().await?;
+    let db_transaction = client.transaction().await?;
+
+    let limit = limit_queries::delete_limit(&db_transaction, id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Transaction limit not found".into()))?;
+
+    audit_queries::create_entry(
+        &db_transaction,
+        &NewAuditEntry::new(Some(auth.user_id), "transaction_limit.deleted", "transaction_limit", Some(id))
+            .before(&limit),
+    )
+    .await?;
+
+    db_transaction.commit().await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,17 @@
+use axum::{extract::{State, Extension}, http::header, response::IntoResponse};
+use deadpool_postgres::Pool;
+use metrics_exporter_prometheus::PrometheusHandle;
+use crate::base::metrics;
+
+pub async fn prometheus_metrics(
+    Extension(handle): Extension<PrometheusHandle>,
+    State(pool): State<Pool>,
+) -> impl IntoResponse {
+    // Pool gauges are sampled at scrape time
+    metrics::record_pool_status(pool.status());
+
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        handle.render(),
+    )
+}
@@ -1,3 +1,19 @@
 pub mod users;
 pub mod accounts;
 pub mod transactions;
+pub mod health;
+pub mod metrics;
+pub mod statements;
+pub mod reconciliation;
+pub mod exports;
+pub mod audit;
+pub mod webhooks;
+pub mod events;
+pub mod scheduled_payments;
+pub mod fees;
+pub mod limits;
+pub mod risk;
+pub mod screening;
+pub mod kyc;
+pub mod aml;
+pub mod approvals;
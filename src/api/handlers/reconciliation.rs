@@ -0,0 +1,117 @@
+use axum::{body::Bytes, extract::{State, Extension}, Json};
+use deadpool_postgres::Pool;
+use uuid::Uuid;
+use crate::{
+    db::dal::reconciliation as reconciliation_queries,
+    base::{
+        error::AppError,
+        models::reconciliation::{
+            ImportPaginationParams, ImportParams, ImportReport, StatementImport, StatementLine, StatementLineParams,
+        },
+        pagination::{Page, PageRequest},
+    },
+    api::{extractors::{Path, ValidatedQuery}, middleware::auth::AuthUser},
+    services::reconciliation as reconciliation_service,
+};
+
+// The settlement file is the raw request body (MT940 text or camt.053 XML).
+pub async fn import_statement(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+    ValidatedQuery(params): ValidatedQuery<ImportParams>,
+    body: Bytes,
+) -> Result<Json<ImportReport>, AppError> {
+    auth.require_admin()?;
+
+    let content = std::str::from_utf8(&body)
+        .map_err(|_| AppError::invalid_field("file", "ENCODING", "Statement file must be UTF-8 encoded"))?;
+
+    let mut client = pool.get().await?;
+
+    let report = reconciliation_service::import_statement(
+        &mut client,
+        content,
+        params.format,
+        params.filename,
+        Some(auth.user_id),
+    )
+    .await?;
+
+    Ok(Json(report))
+}
+
+pub async fn list_imports(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+    ValidatedQuery(params): ValidatedQuery<ImportPaginationParams>,
+) -> Result<Json<Page<StatementImport>>, AppError> {
+    auth.require_admin()?;
+
+    let client = pool.get().await?;
+
+    let page = PageRequest::new(params.limit, params.cursor.as_deref(), None, params.order)?;
+    let imports = reconciliation_queries::list_imports(&client, &page).await?;
+
+    let total_count = if params.include_total {
+        Some(reconciliation_queries::count_imports(&client).await?)
+    } else {
+        None
+    };
+
+    Ok(Json(Page::new(imports, &page, total_count)))
+}
+
+pub async fn get_import(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<StatementImport>, AppError> {
+    auth.require_admin()?;
+
+    let client = pool.get().await?;
+
+    let import = reconciliation_queries::get_import_by_id(&client, id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Statement import not found".into()))?;
+
+    Ok(Json(import))
+}
+
+pub async fn list_import_lines(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+    Path(id): Path<Uuid>,
+    ValidatedQuery(params): ValidatedQuery<StatementLineParams>,
+) -> Result<Json<Vec<StatementLine>>, AppError> {
+    auth.require_admin()?;
+
+    let client = pool.get().await?;
+    ensure_import_exists(&client, id).await?;
+
+    let lines = reconciliation_queries::list_lines(&client, id, params.status, false).await?;
+
+    Ok(Json(lines))
+}
+
+// Exceptions report: every line that did not settle a transaction, with the reason.
+pub async fn list_import_exceptions(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<Vec<StatementLine>>, AppError> {
+    auth.require_admin()?;
+
+    let client = pool.get().await?;
+    ensure_import_exists(&client, id).await?;
+
+    let lines = reconciliation_queries::list_lines(&client, id, None, true).await?;
+
+    Ok(Json(lines))
+}
+
+async fn ensure_import_exists(client: &deadpool_postgres::Client, id: Uuid) -> Result<(), AppError> {
+    reconciliation_queries::get_import_by_id(client, id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Statement import not found".into()))?;
+    Ok(())
+}
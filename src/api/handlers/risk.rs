@@ -0,0 +1,160 @@
+use axum::{extract::{State, Extension}, Json};
+use deadpool_postgres::Pool;
+use rust_decimal::Decimal;
+use serde_json::json;
+use uuid::Uuid;
+use crate::{
+    db::dal::{audit as audit_queries, risk as risk_queries},
+    base::{
+        error::AppError,
+        metrics,
+        models::{
+            audit::NewAuditEntry,
+            fees::FeeTransactionType,
+            risk::{
+                ReviewDecisionRequest, RiskAssessment, RiskAssessmentPaginationParams, RiskRule, RiskRuleCode,
+                UpdateRiskRuleRequest,
+            },
+        },
+        pagination::{Page, PageRequest},
+        risk,
+    },
+    api::{extractors::{Path, ValidatedJson, ValidatedQuery}, middleware::auth::AuthUser},
+    services::risk as risk_service,
+};
+
+pub async fn list_risk_rules(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+) -> Result<Json<Vec<RiskRule>>, AppError> {
+    auth.require_admin()?;
+
+    let client = pool.get().await?;
+
+    let rules = risk_queries::list_rules(&client).await?;
+
+    Ok(Json(rules))
+}
+
+pub async fn update_risk_rule(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+    Path(code): Path<RiskRuleCode>,
+    ValidatedJson(request): ValidatedJson<UpdateRiskRuleRequest>,
+) -> Result<Json<RiskRule>, AppError> {
+    auth.require_admin()?;
+
+    if let Some(params) = &request.params {
+        risk::parse_params(code, params)?;
+    }
+
+    let mut client = pool.get().await?;
+    let db_transaction = client.transaction().await?;
+
+    let rule = risk_queries::get_rule_for_update(&db_transaction, code)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Risk rule not found".into()))?;
+
+    let updated = risk_queries::update_rule(
+        &db_transaction,
+        code,
+        request.enabled.unwrap_or(rule.enabled),
+        request.score.unwrap_or(rule.score),
+        request.params.as_ref().unwrap_or(&rule.params),
+        auth.user_id,
+    )
+    .await?;
+
+    audit_queries::create_entry(
+        &db_transaction,
+        &NewAuditEntry::new(Some(auth.user_id), "risk_rule.updated", "risk_rule", None)
+            .before(&rule)
+            .after(&updated)
+            .details(json!({ "code": code })),
+    )
+    .await?;
+
+    db_transaction.commit().await?;
+
+    tracing::info!(rule = %code, enabled = updated.enabled, score = updated.score, "Risk rule updated");
+
+    Ok(Json(updated))
+}
+
+// The review queue is `review_status=PENDING`.
+pub async fn list_risk_assessments(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+    ValidatedQuery(params): ValidatedQuery<RiskAssessmentPaginationParams>,
+) -> Result<Json<Page<RiskAssessment>>, AppError> {
+    auth.require_admin()?;
+
+    let client = pool.get().await?;
+
+    let page = PageRequest::new(params.limit, params.cursor.as_deref(), None, params.order)?;
+    let assessments = risk_queries::list_assessments(&client, &params, &page).await?;
+
+    let total_count = if params.include_total {
+        Some(risk_queries::count_assessments(&client, &params).await?)
+    } else {
+        None
+    };
+
+    Ok(Json(Page::new(assessments, &page, total_count)))
+}
+
+pub async fn get_risk_assessment(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<RiskAssessment>, AppError> {
+    auth.require_admin()?;
+
+    let client = pool.get().await?;
+
+    let assessment = risk_queries::get_assessment_by_id(&client, id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Risk assessment not found".into()))?;
+
+    Ok(Json(assessment))
+}
+
+pub async fn approve_risk_assessment(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+    Path(id): Path<Uuid>,
+    ValidatedJson(request): ValidatedJson<ReviewDecisionRequest>,
+) -> Result<Json<RiskAssessment>, AppError> {
+    auth.require_admin()?;
+
+    let mut client = pool.get().await?;
+
+    let (assessment, fee) = risk_service::approve(&mut client, auth.user_id, id, request.note.as_deref()).await?;
+
+    metrics::record_risk_review("APPROVED");
+    if fee.transaction_type == FeeTransactionType::Withdrawal {
+        metrics::record_withdrawal(&fee.currency, assessment.amount);
+    }
+    if fee.fee > Decimal::ZERO {
+        metrics::record_fee(&fee.currency, &fee.transaction_type.to_string(), fee.fee);
+    }
+
+    Ok(Json(assessment))
+}
+
+pub async fn reject_risk_assessment(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+    Path(id): Path<Uuid>,
+    ValidatedJson(request): ValidatedJson<ReviewDecisionRequest>,
+) -> Result<Json<RiskAssessment>, AppError> {
+    auth.require_admin()?;
+
+    let mut client = pool.get().await?;
+
+    let assessment = risk_service::reject(&mut client, auth.user_id, id, request.note.as_deref()).await?;
+
+    metrics::record_risk_review("REJECTED");
+
+    Ok(Json(assessment))
+}
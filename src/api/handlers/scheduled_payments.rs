@@ -0,0 +1,217 @@
+use axum::{extract::{State, Extension}, http::StatusCode, Json};
+use chrono::Utc;
+use deadpool_postgres::{Client, Pool};
+use uuid::Uuid;
+use crate::{
+    db::dal::{accounts as account_queries, audit as audit_queries, scheduled_payments as schedule_queries},
+    base::{
+        error::AppError,
+        models::{
+            audit::NewAuditEntry,
+            scheduled_payments::{
+                CreateScheduledPaymentRequest, RunPaginationParams, ScheduleStatus, ScheduledPayment,
+                ScheduledPaymentPaginationParams, ScheduledPaymentRun,
+            },
+        },
+        pagination::{Page, PageRequest},
+    },
+    api::{extractors::{Path, ValidatedJson, ValidatedQuery}, middleware::auth::AuthUser},
+};
+
+pub async fn create_scheduled_payment(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+    ValidatedJson(request): ValidatedJson<CreateScheduledPaymentRequest>,
+) -> Result<(StatusCode, Json<ScheduledPayment>), AppError> {
+    let mut client = pool.get().await?;
+
+    let source = account_queries::get_account_by_id(&client, request.source_account_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Source account not found".into()))?;
+    let destination = account_queries::get_account_by_id(&client, request.destination_account_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Destination account not found".into()))?;
+
+    // Standing orders only move money between the user's own accounts
+    if source.user_id != auth.user_id || destination.user_id != auth.user_id {
+        return Err(AppError::Forbidden("Not allowed to schedule payments between these accounts".into()));
+    }
+    if source.currency != destination.currency {
+        return Err(AppError::invalid_field(
+            "destination_account_id",
+            "CURRENCY_MISMATCH",
+            "destination account must have the same currency",
+        ));
+    }
+    source.ensure_can_credit()?;
+    destination.ensure_can_credit()?;
+
+    let now = Utc::now();
+    let plan = request
+        .plan(now)
+        .ok_or_else(|| AppError::invalid_field("rrule", "RULE", "The schedule rules are invalid"))?;
+    let next_run_at = plan
+        .next_run(now, 0)
+        .ok_or_else(|| AppError::invalid_field("end_at", "NO_RUNS", "The schedule has no runs left"))?;
+
+    let db_transaction = client.transaction().await?;
+
+    let schedule =
+        schedule_queries::create_schedule(&db_transaction, auth.user_id, &request, plan.start_at, next_run_at).await?;
+
+    audit_queries::create_entry(
+        &db_transaction,
+        &NewAuditEntry::new(Some(auth.user_id), "scheduled_payment.created", "scheduled_payment", Some(schedule.id))
+            .after(&schedule),
+    )
+    .await?;
+
+    db_transaction.commit().await?;
+
+    Ok((StatusCode::CREATED, Json(schedule)))
+}
+
+pub async fn list_scheduled_payments(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+    ValidatedQuery(params): ValidatedQuery<ScheduledPaymentPaginationParams>,
+) -> Result<Json<Page<ScheduledPayment>>, AppError> {
+    let client = pool.get().await?;
+
+    let page = PageRequest::new(params.limit, params.cursor.as_deref(), None, params.order)?;
+    let schedules = schedule_queries::list_schedules(&client, auth.user_id, params.status, &page).await?;
+
+    let total_count = if params.include_total {
+        Some(schedule_queries::count_schedules(&client, auth.user_id, params.status).await?)
+    } else {
+        None
+    };
+
+    Ok(Json(Page::new(schedules, &page, total_count)))
+}
+
+pub async fn get_scheduled_payment(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ScheduledPayment>, AppError> {
+    let client = pool.get().await?;
+
+    Ok(Json(owned_schedule(&client, &auth, id).await?))
+}
+
+pub async fn list_runs(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+    Path(id): Path<Uuid>,
+    ValidatedQuery(params): ValidatedQuery<RunPaginationParams>,
+) -> Result<Json<Page<ScheduledPaymentRun>>, AppError> {
+    let client = pool.get().await?;
+
+    owned_schedule(&client, &auth, id).await?;
+
+    let page = PageRequest::new(params.limit, params.cursor.as_deref(), None, params.order)?;
+    let runs = schedule_queries::list_runs(&client, id, params.status, &page).await?;
+
+    let total_count = if params.include_total {
+        Some(schedule_queries::count_runs(&client, id, params.status).await?)
+    } else {
+        None
+    };
+
+    Ok(Json(Page::new(runs, &page, total_count)))
+}
+
+pub async fn pause_scheduled_payment(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ScheduledPayment>, AppError> {
+    change_status(&pool, &auth, id, &[ScheduleStatus::Active], ScheduleStatus::Paused, "scheduled_payment.paused").await
+}
+
+// Occurrences that fell in the pause are skipped.
+pub async fn resume_scheduled_payment(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ScheduledPayment>, AppError> {
+    change_status(&pool, &auth, id, &[ScheduleStatus::Paused], ScheduleStatus::Active, "scheduled_payment.resumed").await
+}
+
+pub async fn cancel_scheduled_payment(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ScheduledPayment>, AppError> {
+    change_status(
+        &pool,
+        &auth,
+        id,
+        &[ScheduleStatus::Active, ScheduleStatus::Paused],
+        ScheduleStatus::Cancelled,
+        "scheduled_payment.cancelled",
+    )
+    .await
+}
+
+async fn change_status(
+    pool: &Pool,
+    auth: &AuthUser,
+    id: Uuid,
+    from: &[ScheduleStatus],
+    to: ScheduleStatus,
+    action: &'static str,
+) -> Result<Json<ScheduledPayment>, AppError> {
+    let mut client = pool.get().await?;
+    let db_transaction = client.transaction().await?;
+
+    // Waits for a run of the schedule that is in progress
+    let schedule = schedule_queries::get_schedule_for_update(&db_transaction, id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Scheduled payment not found".into()))?;
+
+    if schedule.user_id != auth.user_id {
+        return Err(AppError::Forbidden("Not allowed to change this scheduled payment".into()));
+    }
+    if !from.contains(&schedule.status) {
+        return Err(AppError::Conflict(format!("Scheduled payment is {}", schedule.status)));
+    }
+
+    // A resumed schedule without runs left is completed right away
+    let (status, next_run_at) = match to {
+        ScheduleStatus::Active => match schedule.plan().next_run(Utc::now(), schedule.runs_count) {
+            Some(next_run_at) => (ScheduleStatus::Active, Some(next_run_at)),
+            None => (ScheduleStatus::Completed, None),
+        },
+        _ => (to, None),
+    };
+
+    let updated = schedule_queries::set_schedule_status(&db_transaction, id, status, next_run_at)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Scheduled payment not found".into()))?;
+
+    audit_queries::create_entry(
+        &db_transaction,
+        &NewAuditEntry::new(Some(auth.user_id), action, "scheduled_payment", Some(id))
+            .before(&schedule)
+            .after(&updated),
+    )
+    .await?;
+
+    db_transaction.commit().await?;
+
+    Ok(Json(updated))
+}
+
+async fn owned_schedule(client: &Client, auth: &AuthUser, id: Uuid) -> Result<ScheduledPayment, AppError> {
+    let schedule = schedule_queries::get_schedule_by_id(client, id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Scheduled payment not found".into()))?;
+
+    if schedule.user_id != auth.user_id {
+        return Err(AppError::Forbidden("Not allowed to access this scheduled payment".into()));
+    }
+
+    Ok(schedule)
+}
@@ -0,0 +1,110 @@
+use axum::{extract::{State, Extension}, Json};
+use deadpool_postgres::Pool;
+use rust_decimal::Decimal;
+use uuid::Uuid;
+use crate::{
+    db::dal::screening as screening_queries,
+    base::{
+        error::AppError,
+        metrics,
+        models::{
+            risk::ReviewDecisionRequest,
+            screening::{ScreeningResult, ScreeningResultPaginationParams, WatchlistSummary},
+        },
+        pagination::{Page, PageRequest},
+    },
+    api::{extractors::{Path, ValidatedJson, ValidatedQuery}, middleware::auth::AuthUser},
+    services::screening as screening_service,
+};
+
+// Lists are loaded with the `reload-watchlists` command.
+pub async fn list_watchlists(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+) -> Result<Json<Vec<WatchlistSummary>>, AppError> {
+    auth.require_admin()?;
+
+    let client = pool.get().await?;
+
+    let lists = screening_queries::watchlist_summary(&client).await?;
+
+    Ok(Json(lists))
+}
+
+// The review queue is `review_status=PENDING`.
+pub async fn list_screening_results(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+    ValidatedQuery(params): ValidatedQuery<ScreeningResultPaginationParams>,
+) -> Result<Json<Page<ScreeningResult>>, AppError> {
+    auth.require_admin()?;
+
+    let client = pool.get().await?;
+
+    let page = PageRequest::new(params.limit, params.cursor.as_deref(), None, params.order)?;
+    let results = screening_queries::list_results(&client, &params, &page).await?;
+
+    let total_count = if params.include_total {
+        Some(screening_queries::count_results(&client, &params).await?)
+    } else {
+        None
+    };
+
+    Ok(Json(Page::new(results, &page, total_count)))
+}
+
+pub async fn get_screening_result(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ScreeningResult>, AppError> {
+    auth.require_admin()?;
+
+    let client = pool.get().await?;
+
+    let result = screening_queries::get_result_by_id(&client, id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Screening result not found".into()))?;
+
+    Ok(Json(result))
+}
+
+pub async fn clear_screening_result(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+    Path(id): Path<Uuid>,
+    ValidatedJson(request): ValidatedJson<ReviewDecisionRequest>,
+) -> Result<Json<ScreeningResult>, AppError> {
+    auth.require_admin()?;
+
+    let mut client = pool.get().await?;
+
+    let (result, released) = screening_service::clear(&mut client, auth.user_id, id, request.note.as_deref()).await?;
+
+    metrics::record_screening_review("CLEARED");
+    if let Some((transaction, fee)) = released {
+        metrics::record_withdrawal(&fee.currency, transaction.amount);
+        if fee.fee > Decimal::ZERO {
+            metrics::record_fee(&fee.currency, &fee.transaction_type.to_string(), fee.fee);
+        }
+    }
+
+    Ok(Json(result))
+}
+
+pub async fn confirm_screening_result(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+    Path(id): Path<Uuid>,
+    ValidatedJson(request): ValidatedJson<ReviewDecisionRequest>,
+) -> Result<Json<ScreeningResult>, AppError> {
+    auth.require_admin()?;
+
+    let mut client = pool.get().await?;
+
+    let result = screening_service::confirm(&mut client, auth.user_id, id, request.note.as_deref()).await?;
+
+    metrics::record_screening_review("CONFIRMED");
+
+    Ok(Json(result))
+}
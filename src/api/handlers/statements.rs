@@ -0,0 +1,170 @@
+use axum::{
+    body::{Body, Bytes},
+    extract::{State, Extension},
+    http::header,
+    response::{IntoResponse, Response},
+};
+use chrono::Utc;
+use deadpool_postgres::{Client, Pool};
+use futures_util::stream;
+use rust_decimal::Decimal;
+use uuid::Uuid;
+use crate::{
+    db::dal::{accounts as account_queries, balances as balance_queries, transactions as transaction_queries, users as user_queries},
+    base::{
+        constants::STATEMENT_BATCH_SIZE,
+        error::AppError,
+        models::{
+            statements::StatementParams,
+            transactions::{TransactionFilter, TransactionStatus},
+        },
+        pagination::{Keyset, PageRequest, SortField, SortOrder},
+        statements::{Statement, StatementEntry, StatementFormat, StatementRenderer},
+    },
+    api::{extractors::{Path, ValidatedQuery}, middleware::auth::AuthUser},
+};
+
+pub async fn account_statement(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+    Path(id): Path<Uuid>,
+    ValidatedQuery(params): ValidatedQuery<StatementParams>,
+) -> Result<Response, AppError> {
+    statement_response(&auth, &pool, id, params).await
+}
+
+pub async fn account_statement_pdf(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+    Path(id): Path<Uuid>,
+    ValidatedQuery(mut params): ValidatedQuery<StatementParams>,
+) -> Result<Response, AppError> {
+    params.format = StatementFormat::Pdf;
+    statement_response(&auth, &pool, id, params).await
+}
+
+async fn statement_response(
+    auth: &AuthUser,
+    pool: &Pool,
+    id: Uuid,
+    params: StatementParams,
+) -> Result<Response, AppError> {
+    let client = pool.get().await?;
+
+    let account = account_queries::get_account_by_id(&client, id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Account not found".into()))?;
+
+    // User can only export statements of their own accounts
+    if account.user_id != auth.user_id {
+        return Err(AppError::Forbidden("Not allowed to access this account".into()));
+    }
+
+    let holder = user_queries::get_user_by_id(&client, account.user_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Account holder not found".into()))?;
+
+    let (from, to) = params.period();
+    let from = from.unwrap_or(account.created_at);
+    let to = to.unwrap_or_else(Utc::now);
+    if from >= to {
+        return Err(AppError::invalid_field("from", "RANGE", "from must be before to"));
+    }
+
+    let opening = balance_queries::get_balance_at(&client, id, from)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Account not found".into()))?;
+    let closing = balance_queries::get_balance_at(&client, id, to)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Account not found".into()))?;
+    let statement = Statement::new(&account, &holder, from, to, opening.balance, closing.balance);
+
+    let filter = TransactionFilter {
+        user_id: auth.user_id,
+        account_id: Some(id),
+        from: Some(from),
+        to: Some(to),
+        ..Default::default()
+    };
+    let page = PageRequest {
+        limit: STATEMENT_BATCH_SIZE,
+        cursor: None,
+        sort: SortField::CreatedAt,
+        order: SortOrder::Asc,
+    };
+
+    let state = StatementStream {
+        client,
+        filter,
+        page,
+        renderer: params.format.renderer(),
+        running_balance: statement.opening_balance,
+        statement: statement.clone(),
+        started: false,
+        finished: false,
+    };
+
+    let body = Body::from_stream(stream::try_unfold(state, next_chunk));
+    let disposition = format!("attachment; filename=\"{}\"", statement.filename(params.format));
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, params.format.content_type().to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        body,
+    )
+        .into_response())
+}
+
+// Transactions are read one batch at a time, keeping the connection for the
+// duration of the download, so memory use doesn't grow with the history.
+struct StatementStream {
+    client: Client,
+    filter: TransactionFilter,
+    page: PageRequest,
+    renderer: Box<dyn StatementRenderer>,
+    statement: Statement,
+    running_balance: Decimal,
+    started: bool,
+    finished: bool,
+}
+
+async fn next_chunk(mut state: StatementStream) -> Result<Option<(Bytes, StatementStream)>, AppError> {
+    if state.finished {
+        return Ok(None);
+    }
+
+    let mut chunk = String::new();
+    if !state.started {
+        chunk.push_str(&state.renderer.header(&state.statement));
+        state.started = true;
+    }
+
+    let mut transactions = transaction_queries::list_filtered_transactions(&state.client, &state.filter, &state.page)
+        .await
+        .inspect_err(|e| tracing::error!(error = %e, "Statement stream aborted"))?;
+
+    let has_more = transactions.len() as i64 > state.page.limit;
+    transactions.truncate(state.page.limit as usize);
+
+    for transaction in &transactions {
+        // Failed and unbooked transactions never moved the balance
+        if !transaction.booked || matches!(transaction.status, TransactionStatus::Failed) {
+            continue;
+        }
+        let entry = StatementEntry::new(transaction, state.running_balance);
+        state.running_balance = entry.running_balance;
+        chunk.push_str(&state.renderer.entry(&state.statement, &entry));
+    }
+
+    match transactions.last() {
+        Some(last) if has_more => state.page.cursor = Some(last.cursor(state.page.sort)),
+        _ => {
+            chunk.push_str(&state.renderer.footer(&state.statement));
+            state.finished = true;
+        }
+    }
+
+    Ok(Some((Bytes::from(chunk), state)))
+}
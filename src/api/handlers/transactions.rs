@@ -1,35 +1,55 @@
-use axum::{extract::{Path, Query, State, Extension}, Json};
+use axum::{extract::{State, Extension}, Json};
+use chrono::Utc;
 use deadpool_postgres::Pool;
+use serde_json::json;
 use uuid::Uuid;
 use crate::{
-    db::{dal::{accounts as account_queries, transactions as transaction_queries}},
+    db::{dal::{accounts as account_queries, audit as audit_queries, balances as balance_queries, risk as risk_queries, screening as screening_queries, transactions as transaction_queries}},
     base::{
-        models::{transactions::{Transaction, CreateTransactionRequest, UpdateTransactionStatusRequest, TransactionPaginationParams}},
+        models::{audit::NewAuditEntry, transactions::{Transaction, TransactionStatus, TransactionType, CreateTransactionRequest, UpdateTransactionStatusRequest, TransactionFilter, TransactionPaginationParams}},
         error::AppError,
+        pagination::{Page, PageRequest},
     },
-    api::middleware::auth::AuthUser,
+    api::{extractors::{Path, ValidatedJson, ValidatedQuery}, middleware::auth::AuthUser},
+    services::{accounts as account_service, events as event_service, kyc as kyc_service, screening as screening_service},
 };
 
 pub async fn create_transaction(
     Extension(auth): Extension<AuthUser>,
     State(pool): State<Pool>,
-    Json(transaction): Json<CreateTransactionRequest>,
+    ValidatedJson(transaction): ValidatedJson<CreateTransactionRequest>,
 ) -> Result<Json<Transaction>, AppError> {
-    let client = pool.get().await.map_err(|e| AppError::Database(e.to_string()))?;
+    let mut client = pool.get().await?;
     
     // Verifying account ownership
     let account = account_queries::get_account_by_id(&client, transaction.account_id)
-        .await
-        .map_err(|e| AppError::Database(e.to_string()))?
+        .await?
         .ok_or_else(|| AppError::NotFound("Account not found".into()))?;
 
     if account.user_id != auth.user_id {
-        return Err(AppError::Auth("Unauthorized to create transaction for this account".into()));
+        return Err(AppError::Forbidden("Not allowed to create transactions for this account".into()));
     }
+    match transaction.transaction_type {
+        TransactionType::Deposit => account.ensure_can_credit()?,
+        TransactionType::Withdrawal => account.ensure_can_debit()?,
+    }
+
+    let db_transaction = client.transaction().await?;
+    screening_service::ensure_not_restricted(&db_transaction, auth.user_id).await?;
+    kyc_service::check_transaction(&db_transaction, &account, transaction.amount, false, Utc::now()).await?;
 
-    let transaction = transaction_queries::create_transaction(&client, &transaction)
-        .await
-        .map_err(|e| AppError::Database(e.to_string()))?;
+    let transaction = transaction_queries::create_unbooked_transaction(&db_transaction, &transaction)
+        .await?;
+
+    audit_queries::create_entry(
+        &db_transaction,
+        &NewAuditEntry::new(Some(auth.user_id), "transaction.created", "transaction", Some(transaction.id))
+            .after(&transaction),
+    )
+    .await?;
+    event_service::transaction_created(&db_transaction, auth.user_id, &transaction).await?;
+
+    db_transaction.commit().await?;
     
     Ok(Json(transaction))
 }
@@ -39,21 +59,19 @@ pub async fn get_transaction(
     State(pool): State<Pool>,
     Path(id): Path<Uuid>,
 ) -> Result<Json<Transaction>, AppError> {
-    let client = pool.get().await.map_err(|e| AppError::Database(e.to_string()))?;
+    let client = pool.get().await?;
     
     let transaction = transaction_queries::get_transaction_by_id(&client, id)
-        .await
-        .map_err(|e| AppError::Database(e.to_string()))?
+        .await?
         .ok_or_else(|| AppError::NotFound("Transaction not found".into()))?;
     
     // Verifying account ownership
     let account = account_queries::get_account_by_id(&client, transaction.account_id)
-        .await
-        .map_err(|e| AppError::Database(e.to_string()))?
+        .await?
         .ok_or_else(|| AppError::NotFound("Account not found".into()))?;
     
     if account.user_id != auth.user_id {
-        return Err(AppError::Auth("Unauthorized to view this transaction".into()));
+        return Err(AppError::Forbidden("Not allowed to view this transaction".into()));
     }
     
     Ok(Json(transaction))
@@ -63,29 +81,67 @@ pub async fn update_transaction_status(
     Extension(auth): Extension<AuthUser>,
     State(pool): State<Pool>,
     Path(id): Path<Uuid>,
-    Json(status): Json<UpdateTransactionStatusRequest>,
+    ValidatedJson(status): ValidatedJson<UpdateTransactionStatusRequest>,
 ) -> Result<Json<Transaction>, AppError> {
-    let client = pool.get().await.map_err(|e| AppError::Database(e.to_string()))?;
+    let mut client = pool.get().await?;
+    let db_transaction = client.transaction().await?;
     
-    let transaction = transaction_queries::get_transaction_by_id(&client, id)
-        .await
-        .map_err(|e| AppError::Database(e.to_string()))?
-        .ok_or_else(|| AppError::NotFound("Transaction not found".into()))?;
+    let account_id = transaction_queries::get_transaction_by_id(&db_transaction, id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Transaction not found".into()))?
+        .account_id;
     
-    // Verifying account ownership
-    let account = account_queries::get_account_by_id(&client, transaction.account_id)
-        .await
-        .map_err(|e| AppError::Database(e.to_string()))?
+    // Verifying account ownership; the account is locked as its balance may move
+    let account = account_queries::get_account_for_update(&db_transaction, account_id)
+        .await?
         .ok_or_else(|| AppError::NotFound("Account not found".into()))?;
+    let transaction = transaction_queries::get_transaction_for_update(&db_transaction, id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Transaction not found".into()))?;
     
-    if account.user_id != auth.user_id {
-        return Err(AppError::Auth("Unauthorized to update this transaction".into()));
+    if account.user_id != auth.user_id && !auth.is_admin() {
+        return Err(AppError::Forbidden("Not allowed to update this transaction".into()));
+    }
+    // Failing a booked transaction or reviving it moves the balance, so an
+    // owner could otherwise take money back out of a deposit or return a
+    // withdrawal to themselves
+    let failed = |status: &TransactionStatus| matches!(status, TransactionStatus::Failed);
+    if failed(&status.status) != failed(&transaction.status) && !auth.is_admin() {
+        return Err(AppError::Forbidden("Only admins can move a transaction into or out of FAILED".into()));
+    }
+    if risk_queries::is_under_review(&db_transaction, id).await? {
+        return Err(AppError::Conflict("Transaction is held for risk review".into()));
+    }
+    if screening_queries::is_under_review(&db_transaction, id).await? {
+        return Err(AppError::Conflict("Transaction is held for screening review".into()));
     }
     
-    let updated = transaction_queries::update_transaction_status(&client, id, &status)
-        .await
-        .map_err(|e| AppError::Database(e.to_string()))?
+    let updated = transaction_queries::update_transaction_status(&db_transaction, id, &status)
+        .await?
         .ok_or_else(|| AppError::NotFound("Transaction not found".into()))?;
+
+    let mut rebooked = None;
+    if failed(&updated.status) != failed(&transaction.status) {
+        if transaction.booked {
+            rebooked = Some(account_service::rebook(&db_transaction, &account, &transaction, failed(&updated.status)).await?);
+        }
+        // Snapshots taken since the transaction was created counted it by its old status
+        balance_queries::invalidate_snapshots(&db_transaction, updated.account_id, updated.created_at).await?;
+    }
+
+    audit_queries::create_entry(
+        &db_transaction,
+        &NewAuditEntry::new(Some(auth.user_id), "transaction.status_changed", "transaction", Some(id))
+            .before(&transaction)
+            .after(&updated)
+            .details(json!({ "balance": rebooked.as_ref().map(|account| account.balance) })),
+    )
+    .await?;
+    if updated.status != transaction.status {
+        event_service::transaction_status_changed(&db_transaction, account.user_id, &transaction, &updated).await?;
+    }
+
+    db_transaction.commit().await?;
     
     Ok(Json(updated))
 }
@@ -93,37 +149,31 @@ pub async fn update_transaction_status(
 pub async fn list_transactions(
     Extension(auth): Extension<AuthUser>,
     State(pool): State<Pool>,
-    Query(params): Query<TransactionPaginationParams>,
-) -> Result<Json<Vec<Transaction>>, AppError> {
-    let client = pool.get().await.map_err(|e| AppError::Database(e.to_string()))?;
-
-    let page = params.page.unwrap_or(1);
-    let per_page = params.per_page.unwrap_or(10);
-    let offset = (page - 1) * per_page;
+    ValidatedQuery(params): ValidatedQuery<TransactionPaginationParams>,
+) -> Result<Json<Page<Transaction>>, AppError> {
+    let client = pool.get().await?;
 
     // If account_id is provided, verify ownership
     if let Some(account_id) = params.account_id {
         let account = account_queries::get_account_by_id(&client, account_id)
-            .await
-            .map_err(|e| AppError::Database(e.to_string()))?
+            .await?
             .ok_or_else(|| AppError::NotFound("Account not found".into()))?;
 
         if account.user_id != auth.user_id {
-            return Err(AppError::Auth("Unauthorized to view transactions for this account".into()));
+            return Err(AppError::Forbidden("Not allowed to view transactions for this account".into()));
         }
     }
 
-    let transactions = transaction_queries::list_filtered_transactions(
-        &client,
-        auth.user_id,
-        params.account_id,
-        params.transaction_type.as_ref(),
-        params.status.as_ref(),
-        offset,
-        per_page
-    )
-    .await
-    .map_err(|e| AppError::Database(e.to_string()))?;
+    let page = PageRequest::new(params.limit, params.cursor.as_deref(), params.sort, params.order)?;
+    let filter = TransactionFilter::new(auth.user_id, &params);
 
-    Ok(Json(transactions))
-}
\ No newline at end of file
+    let transactions = transaction_queries::list_filtered_transactions(&client, &filter, &page).await?;
+
+    let total_count = if params.include_total {
+        Some(transaction_queries::count_filtered_transactions(&client, &filter).await?)
+    } else {
+        None
+    };
+
+    Ok(Json(Page::new(transactions, &page, total_count)))
+}
@@ -1,48 +1,103 @@
-use axum::{extract::{Path, Query, State, Extension}, Json};
+use std::net::SocketAddr;
+use axum::{extract::{ConnectInfo, State, Extension}, http::{header, HeaderMap}, Json};
 use deadpool_postgres::Pool;
+use serde_json::json;
 use uuid::Uuid;
 use crate::{
     base::{
         error::AppError,
         utils::{hash_password, validate_password},
-        models::users::{CreateUserRequest, LoginRequest, LoginResponse, PaginationParams, UpdateUserRequest, User},
+        models::{
+            audit::NewAuditEntry,
+            consents::{Consent, RecordConsentRequest},
+            screening::{ScreeningDecision, ScreeningSubject, ScreeningTrigger},
+            users::{CreateUserRequest, LoginRequest, LoginResponse, PaginationParams, UpdateUserRequest, User},
+        },
+        pagination::{Page, PageRequest},
     },
-    db::dal::users as user_queries,
-    api::middleware::auth::{create_token, AuthUser},
+    db::dal::{audit as audit_queries, consents as consent_queries, users as user_queries},
+    api::{extractors::{Path, ValidatedJson, ValidatedQuery}, middleware::auth::{create_token, AuthUser}},
+    services::{screening::{self as screening_service, WatchlistCache}, users as user_service},
 };
 
 pub async fn create_user(
     State(pool): State<Pool>,
-    Json(mut user): Json<CreateUserRequest>,
+    Extension(watchlists): Extension<WatchlistCache>,
+    ValidatedJson(mut user): ValidatedJson<CreateUserRequest>,
 ) -> Result<Json<User>, AppError> {
-    let db_client = pool.get().await.map_err(|e| AppError::Database(e.to_string()))?;
+    let mut db_client = pool.get().await?;
 
-    let password_hash = hash_password(&user.password).map_err(|e| AppError::Auth(e.to_string()))?;
+    let password_hash = hash_password(&user.password).map_err(|e| AppError::Internal(e.to_string()))?;
     user.password = password_hash;
 
-    let user = user_queries::create_user(&db_client, &user)
-        .await
-        .map_err(|e| AppError::Database(e.to_string()))?;
+    let db_transaction = db_client.transaction().await?;
+
+    let screening = screening_service::screen_name(&db_transaction, &watchlists, &user.name, ScreeningSubject::User, None)
+            .await?;
+    if screening.decision == ScreeningDecision::Block {
+        screening_service::record(
+            &db_transaction,
+            ScreeningSubject::User,
+            ScreeningTrigger::Signup,
+            None,
+            None,
+            &user.name,
+            &screening,
+        )
+        .await?;
+        db_transaction.commit().await?;
+        return Err(AppError::RegistrationDeclined);
+    }
+
+    let user = user_queries::create_user(&db_transaction, &user)
+        .await?;
+
+    // A possible match is let in but restricted until it is reviewed
+    screening_service::record(
+        &db_transaction,
+        ScreeningSubject::User,
+        ScreeningTrigger::Signup,
+        Some(user.id),
+        None,
+        &user.name,
+        &screening,
+    )
+    .await?;
+
+    audit_queries::create_entry(
+        &db_transaction,
+        &NewAuditEntry::new(Some(user.id), "user.created", "user", Some(user.id)).after(&user.audit_snapshot()),
+    )
+    .await?;
+
+    db_transaction.commit().await?;
 
     Ok(Json(user))
 }
 
+// Attempts against existing users are recorded as their login history.
 pub async fn login(
     State(pool): State<Pool>,
-    Json(credentials): Json<LoginRequest>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    headers: HeaderMap,
+    ValidatedJson(credentials): ValidatedJson<LoginRequest>,
 ) -> Result<Json<LoginResponse>, AppError> {
-    let db_client = pool.get().await.map_err(|e| AppError::Database(e.to_string()))?;
+    let db_client = pool.get().await?;
 
     let user = user_queries::get_user_by_email(&db_client, &credentials.email)
-        .await
-        .map_err(|e| AppError::Database(e.to_string()))?
+        .await?
         .ok_or_else(|| AppError::Auth("Invalid credentials".into()))?;
 
-    if validate_password(&credentials.password, &user.password).is_err() {
-        return Err(AppError::Auth("Invalid password".into()));
+    let succeeded = validate_password(&credentials.password, &user.password).is_ok();
+    let ip_address = addr.ip().to_string();
+    let user_agent = headers.get(header::USER_AGENT).and_then(|value| value.to_str().ok());
+    user_queries::record_login_event(&db_client, user.id, succeeded, Some(&ip_address), user_agent).await?;
+
+    if !succeeded {
+        return Err(AppError::Auth("Invalid credentials".into()));
     }
 
-    let token = create_token(user.id)?;
+    let token = create_token(user.id, user.role)?;
 
     Ok(Json(LoginResponse { token, user }))
 }
@@ -52,11 +107,10 @@ pub async fn get_user(
     State(pool): State<Pool>,
     Path(id): Path<Uuid>,
 ) -> Result<Json<User>, AppError> {
-    let client = pool.get().await.map_err(|e| AppError::Database(e.to_string()))?;
+    let client = pool.get().await?;
 
     let user = user_queries::get_user_by_id(&client, id)
-        .await
-        .map_err(|e| AppError::Database(e.to_string()))?
+        .await?
         .ok_or_else(|| AppError::NotFound("User not found".into()))?;
 
     Ok(Json(user))
@@ -65,64 +119,137 @@ pub async fn get_user(
 pub async fn update_user(
     Extension(auth): Extension<AuthUser>,
     State(pool): State<Pool>,
+    Extension(watchlists): Extension<WatchlistCache>,
     Path(id): Path<Uuid>,
-    Json(mut user): Json<UpdateUserRequest>,
+    ValidatedJson(mut user): ValidatedJson<UpdateUserRequest>,
 ) -> Result<Json<User>, AppError> {
     if auth.user_id != id {
-        return Err(AppError::Auth("Unauthorized".into()));
+        return Err(AppError::Forbidden("Not allowed to modify another user".into()));
     }
 
-    let db_client = pool.get().await.map_err(|e| AppError::Database(e.to_string()))?;
+    let mut db_client = pool.get().await?;
 
     if let Some(ref password) = user.password {
-        let password_hash = hash_password(password).map_err(|e| AppError::Auth(e.to_string()))?;
+        let password_hash = hash_password(password).map_err(|e| AppError::Internal(e.to_string()))?;
         user.password = Some(password_hash);
     }
 
-    let user = user_queries::update_user(&db_client, id, &user)
-        .await
-        .map_err(|e| AppError::Database(e.to_string()))?
+    let db_transaction = db_client.transaction().await?;
+
+    let existing = user_queries::get_user_for_update(&db_transaction, id)
+        .await?
         .ok_or_else(|| AppError::NotFound("User not found".into()))?;
 
-    Ok(Json(user))
+    let updated = user_queries::update_user(&db_transaction, id, &user)
+        .await?
+        .ok_or_else(|| AppError::NotFound("User not found".into()))?;
+
+    // A new name is screened again; any match restricts the user until reviewed
+    if updated.name != existing.name {
+        let screening = screening_service::screen_name(
+            &db_transaction,
+            &watchlists,
+            &updated.name,
+            ScreeningSubject::User,
+            Some(id),
+        )
+        .await?;
+        screening_service::record(
+            &db_transaction,
+            ScreeningSubject::User,
+            ScreeningTrigger::NameChange,
+            Some(id),
+            None,
+            &updated.name,
+            &screening,
+        )
+        .await?;
+    }
+
+    audit_queries::create_entry(
+        &db_transaction,
+        &NewAuditEntry::new(Some(auth.user_id), "user.updated", "user", Some(id))
+            .before(&existing.audit_snapshot())
+            .after(&updated.audit_snapshot())
+            .details(json!({ "changed": user.changed_fields() })),
+    )
+    .await?;
+
+    db_transaction.commit().await?;
+
+    Ok(Json(updated))
 }
 
+// Erases the user's personal data; admins can erase any user.
 pub async fn delete_user(
     Extension(auth): Extension<AuthUser>,
     State(pool): State<Pool>,
     Path(id): Path<Uuid>,
 ) -> Result<(), AppError> {
     if auth.user_id != id {
-        return Err(AppError::Auth("Unauthorized".into()));
+        auth.require_admin()
+            .map_err(|_| AppError::Forbidden("Not allowed to delete another user".into()))?;
     }
 
-    let db_client = pool.get().await.map_err(|e| AppError::Database(e.to_string()))?;
-    
-    let deleted = user_queries::delete_user(&db_client, id)
-        .await
-        .map_err(|e| AppError::Database(e.to_string()))?;
-    
-    if !deleted {
-        return Err(AppError::NotFound("User not found".into()));
-    }
-    
+    let mut db_client = pool.get().await?;
+
+    user_service::erase_user(&mut db_client, id, auth.user_id).await?;
+
     Ok(())
 }
 
 pub async fn list_users(
     Extension(_auth): Extension<AuthUser>,
     State(pool): State<Pool>,
-    Query(params): Query<PaginationParams>,
-) -> Result<Json<Vec<User>>, AppError> {
-    let db_client = pool.get().await.map_err(|e| AppError::Database(e.to_string()))?;
-    
-    let page = params.page.unwrap_or(1);
-    let per_page = params.per_page.unwrap_or(10);
-    let offset = (page - 1) * per_page;
-    
-    let users = user_queries::list_users(&db_client, offset, per_page)
-        .await
-        .map_err(|e| AppError::Database(e.to_string()))?;
-    
-    Ok(Json(users))
+    ValidatedQuery(params): ValidatedQuery<PaginationParams>,
+) -> Result<Json<Page<User>>, AppError> {
+    let db_client = pool.get().await?;
+
+    let page = PageRequest::new(params.limit, params.cursor.as_deref(), None, params.order)?;
+
+    let users = user_queries::list_users(&db_client, &page).await?;
+
+    let total_count = if params.include_total {
+        Some(user_queries::count_users(&db_client).await?)
+    } else {
+        None
+    };
+
+    Ok(Json(Page::new(users, &page, total_count)))
+}
+
+pub async fn list_consents(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+) -> Result<Json<Vec<Consent>>, AppError> {
+    let db_client = pool.get().await?;
+
+    let consents = consent_queries::current_consents(&db_client, auth.user_id).await?;
+
+    Ok(Json(consents))
+}
+
+// Records a decision; earlier decisions for the purpose are kept as history.
+pub async fn record_consent(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    ValidatedJson(consent): ValidatedJson<RecordConsentRequest>,
+) -> Result<Json<Consent>, AppError> {
+    let mut db_client = pool.get().await?;
+    let db_transaction = db_client.transaction().await?;
+
+    let ip_address = addr.ip().to_string();
+    let consent = consent_queries::create_consent(&db_transaction, auth.user_id, &consent, Some(&ip_address)).await?;
+
+    audit_queries::create_entry(
+        &db_transaction,
+        &NewAuditEntry::new(Some(auth.user_id), "consent.recorded", "consent", Some(consent.id))
+            .details(json!({ "purpose": consent.purpose, "granted": consent.granted })),
+    )
+    .await?;
+
+    db_transaction.commit().await?;
+
+    Ok(Json(consent))
 }
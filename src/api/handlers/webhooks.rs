@@ -0,0 +1,247 @@
+use axum::{extract::{State, Extension}, http::StatusCode, Json};
+use deadpool_postgres::{Client, Pool};
+use serde_json::json;
+use uuid::Uuid;
+use crate::{
+    db::dal::{audit as audit_queries, webhooks as webhook_queries},
+    base::{
+        error::AppError,
+        models::{
+            audit::NewAuditEntry,
+            webhooks::{
+                event_type_names, CreateWebhookEndpointRequest, CreatedWebhookEndpoint, DeliveryPaginationParams,
+                DeliveryStatus, ReplayReport, UpdateWebhookEndpointRequest, WebhookDelivery, WebhookEndpoint,
+            },
+        },
+        pagination::{Page, PageRequest},
+        webhooks::generate_secret,
+    },
+    api::{extractors::{Path, ValidatedJson, ValidatedQuery}, middleware::auth::AuthUser},
+};
+
+// The signing secret is only returned here; store it on the receiving side.
+pub async fn create_webhook(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+    ValidatedJson(request): ValidatedJson<CreateWebhookEndpointRequest>,
+) -> Result<(StatusCode, Json<CreatedWebhookEndpoint>), AppError> {
+    let mut client = pool.get().await?;
+    let secret = generate_secret();
+
+    let db_transaction = client.transaction().await?;
+
+    let endpoint = webhook_queries::create_endpoint(
+        &db_transaction,
+        auth.user_id,
+        &request,
+        &event_type_names(&request.event_types),
+        &secret,
+    )
+    .await?;
+
+    audit_queries::create_entry(
+        &db_transaction,
+        &NewAuditEntry::new(Some(auth.user_id), "webhook.created", "webhook_endpoint", Some(endpoint.id)).after(&endpoint),
+    )
+    .await?;
+
+    db_transaction.commit().await?;
+
+    Ok((StatusCode::CREATED, Json(CreatedWebhookEndpoint { endpoint, secret })))
+}
+
+pub async fn list_webhooks(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+) -> Result<Json<Vec<WebhookEndpoint>>, AppError> {
+    let client = pool.get().await?;
+
+    Ok(Json(webhook_queries::list_endpoints_by_user_id(&client, auth.user_id).await?))
+}
+
+pub async fn get_webhook(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<WebhookEndpoint>, AppError> {
+    let client = pool.get().await?;
+
+    Ok(Json(owned_endpoint(&client, &auth, id).await?))
+}
+
+pub async fn update_webhook(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+    Path(id): Path<Uuid>,
+    ValidatedJson(request): ValidatedJson<UpdateWebhookEndpointRequest>,
+) -> Result<Json<WebhookEndpoint>, AppError> {
+    let mut client = pool.get().await?;
+    let db_transaction = client.transaction().await?;
+
+    let existing = webhook_queries::get_endpoint_for_update(&db_transaction, id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Webhook endpoint not found".into()))?;
+
+    if existing.user_id != auth.user_id {
+        return Err(AppError::Forbidden("Not allowed to update this webhook endpoint".into()));
+    }
+
+    let event_types = request.event_types.as_deref().map(event_type_names);
+    let updated = webhook_queries::update_endpoint(&db_transaction, id, &request, event_types.as_deref())
+        .await?
+        .ok_or_else(|| AppError::NotFound("Webhook endpoint not found".into()))?;
+
+    audit_queries::create_entry(
+        &db_transaction,
+        &NewAuditEntry::new(Some(auth.user_id), "webhook.updated", "webhook_endpoint", Some(id))
+            .before(&existing)
+            .after(&updated),
+    )
+    .await?;
+
+    db_transaction.commit().await?;
+
+    Ok(Json(updated))
+}
+
+// Pending deliveries of the endpoint are dropped with it.
+pub async fn delete_webhook(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+    Path(id): Path<Uuid>,
+) -> Result<StatusCode, AppError> {
+    let mut client = pool.get().await?;
+    let db_transaction = client.transaction().await?;
+
+    let existing = webhook_queries::get_endpoint_for_update(&db_transaction, id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Webhook endpoint not found".into()))?;
+
+    if existing.user_id != auth.user_id {
+        return Err(AppError::Forbidden("Not allowed to delete this webhook endpoint".into()));
+    }
+
+    webhook_queries::delete_endpoint(&db_transaction, id).await?;
+
+    audit_queries::create_entry(
+        &db_transaction,
+        &NewAuditEntry::new(Some(auth.user_id), "webhook.deleted", "webhook_endpoint", Some(id)).before(&existing),
+    )
+    .await?;
+
+    db_transaction.commit().await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+pub async fn list_deliveries(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+    Path(id): Path<Uuid>,
+    ValidatedQuery(params): ValidatedQuery<DeliveryPaginationParams>,
+) -> Result<Json<Page<WebhookDelivery>>, AppError> {
+    let client = pool.get().await?;
+
+    owned_endpoint(&client, &auth, id).await?;
+
+    let page = PageRequest::new(params.limit, params.cursor.as_deref(), None, params.order)?;
+    let deliveries = webhook_queries::list_deliveries(&client, id, params.status, &page).await?;
+
+    let total_count = if params.include_total {
+        Some(webhook_queries::count_deliveries(&client, id, params.status).await?)
+    } else {
+        None
+    };
+
+    Ok(Json(Page::new(deliveries, &page, total_count)))
+}
+
+pub async fn get_delivery(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+    Path((id, delivery_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<WebhookDelivery>, AppError> {
+    let client = pool.get().await?;
+
+    owned_endpoint(&client, &auth, id).await?;
+
+    let delivery = webhook_queries::get_delivery(&client, id, delivery_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Delivery not found".into()))?;
+
+    Ok(Json(delivery))
+}
+
+// Sends a delivered or dead delivery again, with a fresh set of attempts.
+pub async fn replay_delivery(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+    Path((id, delivery_id)): Path<(Uuid, Uuid)>,
+) -> Result<(StatusCode, Json<WebhookDelivery>), AppError> {
+    let mut client = pool.get().await?;
+
+    owned_endpoint(&client, &auth, id).await?;
+
+    let db_transaction = client.transaction().await?;
+
+    let existing = webhook_queries::get_delivery(&db_transaction, id, delivery_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Delivery not found".into()))?;
+    if existing.status == DeliveryStatus::Pending {
+        return Err(AppError::Conflict("Delivery is still pending".into()));
+    }
+
+    let delivery = webhook_queries::replay_delivery(&db_transaction, id, delivery_id)
+        .await?
+        .ok_or_else(|| AppError::Conflict("Delivery is still pending".into()))?;
+
+    audit_queries::create_entry(
+        &db_transaction,
+        &NewAuditEntry::new(Some(auth.user_id), "webhook.delivery_replayed", "webhook_endpoint", Some(id))
+            .details(json!({ "delivery_id": delivery_id, "previous_status": existing.status })),
+    )
+    .await?;
+
+    db_transaction.commit().await?;
+
+    Ok((StatusCode::ACCEPTED, Json(delivery)))
+}
+
+// Queues every dead delivery of the endpoint again, e.g. after an outage of
+// the receiving side.
+pub async fn replay_dead_deliveries(
+    Extension(auth): Extension<AuthUser>,
+    State(pool): State<Pool>,
+    Path(id): Path<Uuid>,
+) -> Result<(StatusCode, Json<ReplayReport>), AppError> {
+    let mut client = pool.get().await?;
+
+    owned_endpoint(&client, &auth, id).await?;
+
+    let db_transaction = client.transaction().await?;
+
+    let replayed = webhook_queries::replay_dead_deliveries(&db_transaction, id).await?;
+
+    audit_queries::create_entry(
+        &db_transaction,
+        &NewAuditEntry::new(Some(auth.user_id), "webhook.deliveries_replayed", "webhook_endpoint", Some(id))
+            .details(json!({ "replayed": replayed })),
+    )
+    .await?;
+
+    db_transaction.commit().await?;
+
+    Ok((StatusCode::ACCEPTED, Json(ReplayReport { replayed })))
+}
+
+async fn owned_endpoint(client: &Client, auth: &AuthUser, id: Uuid) -> Result<WebhookEndpoint, AppError> {
+    let endpoint = webhook_queries::get_endpoint_by_id(client, id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Webhook endpoint not found".into()))?;
+
+    if endpoint.user_id != auth.user_id {
+        return Err(AppError::Forbidden("Not allowed to access this webhook endpoint".into()));
+    }
+
+    Ok(endpoint)
+}
@@ -1,26 +1,49 @@
-use axum::{http::{Request, header::AUTHORIZATION}, middleware::Next, body::Body};
+use axum::{extract::State, http::{Request, header::AUTHORIZATION}, middleware::Next, body::Body};
+use deadpool_postgres::Pool;
 use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
 use serde::{Deserialize, Serialize};
 use std::time::{SystemTime, UNIX_EPOCH};
 use uuid::Uuid;
-use crate::base::error::AppError;
+use crate::{
+    base::{error::AppError, models::users::Role},
+    db::dal::users as user_queries,
+};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Claims {
     pub sub: String,
     pub exp: usize,
+    // Tokens issued before roles existed belong to regular users
+    #[serde(default)]
+    pub role: Role,
 }
 
 #[derive(Debug, Clone)]
 pub struct AuthUser {
     pub user_id: Uuid,
+    pub role: Role,
+}
+
+impl AuthUser {
+    // Roles are read from the token, so a role change applies from the next login.
+    pub fn is_admin(&self) -> bool {
+        self.role == Role::Admin
+    }
+
+    pub fn require_admin(&self) -> Result<(), AppError> {
+        if !self.is_admin() {
+            return Err(AppError::Forbidden("Admin access required".into()));
+        }
+        Ok(())
+    }
 }
 
 pub async fn auth_middleware(
+    State(pool): State<Pool>,
     mut req: Request<Body>,
     next: Next,
 ) -> Result<axum::response::Response, AppError> {
-    if req.uri().path() == "/users/login" || (req.uri().path() == "/users" && req.method() == &axum::http::Method::POST) {
+    if req.uri().path() == "/users/login" || (req.uri().path() == "/users" && req.method() == axum::http::Method::POST) {
         return Ok(next.run(req).await);
     }
 
@@ -37,7 +60,7 @@ pub async fn auth_middleware(
         .ok_or_else(|| AppError::Auth("Invalid authorization header format".into()))?;
 
     let key = std::env::var("JWT_SECRET")
-        .map_err(|_| AppError::Auth("JWT_SECRET not found".into()))?;
+        .map_err(|_| AppError::Internal("JWT_SECRET not found".into()))?;
 
     let claims = decode::<Claims>(
         token,
@@ -46,17 +69,27 @@ pub async fn auth_middleware(
     )
     .map_err(|e| AppError::Auth(format!("Invalid token: {}", e)))?;
 
+    let user_id = Uuid::parse_str(&claims.claims.sub)
+        .map_err(|_| AppError::Auth("Invalid user ID in token".into()))?;
+
+    // Tokens outlive an erasure, so the user is looked up on every request
+    let client = pool.get().await?;
+    if !user_queries::is_active_user(&client, user_id).await? {
+        return Err(AppError::Auth("Invalid token: user no longer exists".into()));
+    }
+    drop(client);
+
     req.extensions_mut().insert(AuthUser {
-        user_id: Uuid::parse_str(&claims.claims.sub)
-            .map_err(|_| AppError::Auth("Invalid user ID in token".into()))?,
+        user_id,
+        role: claims.claims.role,
     });
 
     Ok(next.run(req).await)
 }
 
-pub fn create_token(user_id: Uuid) -> Result<String, AppError> {
+pub fn create_token(user_id: Uuid, role: Role) -> Result<String, AppError> {
     let key = std::env::var("JWT_SECRET")
-        .map_err(|_| AppError::Auth("JWT_SECRET not found".into()))?;
+        .map_err(|_| AppError::Internal("JWT_SECRET not found".into()))?;
     let expiration = std::env::var("JWT_EXPIRATION")
         .unwrap_or_else(|_| "24".to_string())
         .parse::<i64>()
@@ -71,6 +104,7 @@ pub fn create_token(user_id: Uuid) -> Result<String, AppError> {
     let claims = Claims {
         sub: user_id.to_string(),
         exp,
+        role,
     };
 
     encode(
@@ -78,5 +112,5 @@ pub fn create_token(user_id: Uuid) -> Result<String, AppError> {
         &claims,
         &EncodingKey::from_secret(key.as_bytes()),
     )
-    .map_err(|e| AppError::Auth(e.to_string()))
+    .map_err(|e| AppError::Internal(e.to_string()))
 }
\ No newline at end of file
@@ -0,0 +1,21 @@
+use axum::{extract::MatchedPath, http::Request, middleware::Next, response::Response, body::Body};
+use std::time::Instant;
+use crate::base::metrics;
+
+pub async fn metrics_middleware(req: Request<Body>, next: Next) -> Response {
+    let start = Instant::now();
+    let method = req.method().to_string();
+
+    // Label by route template rather than the raw path to keep cardinality bounded
+    let route = req
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| "unmatched".to_string());
+
+    let response = next.run(req).await;
+
+    metrics::record_http_request(&method, &route, response.status().as_u16(), start.elapsed());
+
+    response
+}
@@ -1,2 +1,4 @@
 pub mod auth;
 pub mod rate_limit;
+pub mod metrics;
+pub mod request_id;
@@ -1,6 +1,6 @@
 use axum::{
     extract::ConnectInfo,
-    http::{Request, StatusCode},
+    http::Request,
     middleware::Next,
     response::Response,
     body::Body,
@@ -11,6 +11,7 @@ use std::{
     sync::{Arc, Mutex},
     time::{Duration, Instant},
 };
+use crate::base::{error::AppError, metrics};
 
 #[derive(Debug, Clone)]
 struct RateLimitEntry {
@@ -77,11 +78,12 @@ pub async fn rate_limit_middleware(
     limiter: axum::extract::Extension<RateLimiter>,
     request: Request<Body>,
     next: Next,
-) -> Result<Response, StatusCode> {
+) -> Result<Response, AppError> {
     let ip = addr.ip().to_string();
     
     if limiter.is_rate_limited(&ip) {
-        return Err(StatusCode::TOO_MANY_REQUESTS);
+        metrics::record_rate_limit_rejection();
+        return Err(AppError::RateLimited);
     }
     
     Ok(next.run(request).await)
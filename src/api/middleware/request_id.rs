@@ -0,0 +1,71 @@
+use std::net::SocketAddr;
+use axum::{
+    extract::ConnectInfo,
+    http::{HeaderName, HeaderValue, Request},
+    middleware::Next,
+    response::Response,
+    body::Body,
+};
+use tracing::Instrument;
+use uuid::Uuid;
+use crate::base::telemetry::{self, RequestContext, TraceParent};
+
+pub static X_REQUEST_ID: HeaderName = HeaderName::from_static("x-request-id");
+pub static TRACEPARENT: HeaderName = HeaderName::from_static("traceparent");
+
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+pub async fn request_id_middleware(req: Request<Body>, next: Next) -> Response {
+    // Reuse the caller's id when it is safe to echo back, otherwise generate one
+    let request_id = req
+        .headers()
+        .get(&X_REQUEST_ID)
+        .and_then(|value| value.to_str().ok())
+        .filter(|id| is_valid_request_id(id))
+        .map(str::to_string)
+        .unwrap_or_else(|| Uuid::new_v4().to_string());
+
+    let trace = TraceParent::from_header(
+        req.headers().get(&TRACEPARENT).and_then(|value| value.to_str().ok()),
+    );
+
+    let context = RequestContext {
+        request_id: request_id.clone(),
+        path: req.uri().path().to_string(),
+        ip_address: req
+            .extensions()
+            .get::<ConnectInfo<SocketAddr>>()
+            .map(|ConnectInfo(addr)| addr.ip().to_string()),
+    };
+
+    let span = tracing::info_span!(
+        "http",
+        request_id = %request_id,
+        trace_id = %trace.trace_id,
+        span_id = %trace.span_id,
+        parent_span_id = tracing::field::Empty,
+    );
+    if let Some(parent_span_id) = &trace.parent_span_id {
+        span.record("parent_span_id", parent_span_id.as_str());
+    }
+
+    let mut response = telemetry::with_request_context(context, next.run(req))
+        .instrument(span)
+        .await;
+
+    let headers = response.headers_mut();
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        headers.insert(X_REQUEST_ID.clone(), value);
+    }
+    if let Ok(value) = HeaderValue::from_str(&trace.header_value()) {
+        headers.insert(TRACEPARENT.clone(), value);
+    }
+
+    response
+}
+
+fn is_valid_request_id(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= MAX_REQUEST_ID_LEN
+        && id.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.' | ':'))
+}
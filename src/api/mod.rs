@@ -1,3 +1,4 @@
 pub mod routes;
 pub mod handlers;
-pub mod middleware;
\ No newline at end of file
+pub mod middleware;
+pub mod extractors;
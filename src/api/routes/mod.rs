@@ -1,20 +1,30 @@
-use axum::{routing::{get, post, put, delete}, Router, middleware};
+use axum::{extract::DefaultBodyLimit, routing::{get, post, put, delete}, Router, middleware};
 use deadpool_postgres::Pool;
+use crate::base::constants::MAX_STATEMENT_FILE_SIZE;
 use crate::api::{
-    handlers::{users, accounts, transactions},
-    middleware::{auth::auth_middleware, rate_limit::rate_limit_middleware}
+    handlers::{users, accounts, transactions, statements, reconciliation, exports, audit, webhooks, events, scheduled_payments, fees, limits, risk, screening, kyc, aml, approvals, health, metrics},
+    middleware::{auth::auth_middleware, rate_limit::rate_limit_middleware, metrics::metrics_middleware}
 };
 
 pub fn create_router(pool: Pool) -> Router {
     let public_routes = Router::new()
         .route("/users", post(users::create_user))
-        .route("/users/login", post(users::login));
+        .route("/users/login", post(users::login))
+        .route("/exports/{id}/download", get(exports::download_export));
 
     let protected_routes = Router::new()
         .route("/users", get(users::list_users))
         .route("/users/{id}", get(users::get_user))
         .route("/users/{id}", put(users::update_user))
         .route("/users/{id}", delete(users::delete_user))
+        .route("/users/me/consents", get(users::list_consents))
+        .route("/users/me/consents", post(users::record_consent))
+        .route("/users/me/export", post(exports::request_export))
+        .route("/users/me/exports", get(exports::list_exports))
+        .route("/users/me/exports/{id}", get(exports::get_export))
+        .route("/users/me/kyc", get(kyc::get_own_kyc))
+        .route("/users/me/kyc/documents", post(kyc::submit_document))
+        .route("/users/me/kyc/applications", post(kyc::create_application))
 
         .route("/accounts", post(accounts::create_account))
         .route("/accounts", get(accounts::list_accounts))
@@ -23,17 +33,120 @@ pub fn create_router(pool: Pool) -> Router {
         .route("/accounts/{id}", delete(accounts::delete_account))
         .route("/accounts/{id}/deposit", post(accounts::deposit))
         .route("/accounts/{id}/withdraw", post(accounts::withdraw))
+        .route("/accounts/{id}/balance", get(accounts::get_balance))
+        .route("/accounts/{id}/limits", get(limits::get_account_limits))
+        .route("/accounts/{id}/close", post(accounts::close_account))
+        .route("/accounts/{id}/freeze", post(accounts::freeze_account))
+        .route("/accounts/{id}/unfreeze", post(accounts::unfreeze_account))
+        .route("/accounts/{id}/reopen", post(accounts::reopen_account))
+        .route("/accounts/{id}/statement", get(statements::account_statement))
+        .route("/accounts/{id}/statement.pdf", get(statements::account_statement_pdf))
+        .route("/accounts/{id}/events", get(events::account_events))
+        .route("/accounts/{id}/events/ws", get(events::account_events_ws))
 
         .route("/transactions", post(transactions::create_transaction))
         .route("/transactions", get(transactions::list_transactions))
         .route("/transactions/{id}", get(transactions::get_transaction))
         .route("/transactions/{id}/status", put(transactions::update_transaction_status))
 
-        .layer(middleware::from_fn(auth_middleware));
+        .route("/scheduled-payments", post(scheduled_payments::create_scheduled_payment))
+        .route("/scheduled-payments", get(scheduled_payments::list_scheduled_payments))
+        .route("/scheduled-payments/{id}", get(scheduled_payments::get_scheduled_payment))
+        .route("/scheduled-payments/{id}/pause", post(scheduled_payments::pause_scheduled_payment))
+        .route("/scheduled-payments/{id}/resume", post(scheduled_payments::resume_scheduled_payment))
+        .route("/scheduled-payments/{id}/cancel", post(scheduled_payments::cancel_scheduled_payment))
+        .route("/scheduled-payments/{id}/runs", get(scheduled_payments::list_runs))
+
+        .route("/fees/preview", post(fees::preview_fee))
+
+        .route("/approval-requests", get(approvals::list_own_requests))
+        .route("/approval-requests/{id}", get(approvals::get_own_request))
+        .route("/approval-requests/{id}/comments", post(approvals::comment_own_request))
+        .route("/approval-requests/{id}/cancel", post(approvals::cancel_request))
+
+        .route("/webhooks", post(webhooks::create_webhook))
+        .route("/webhooks", get(webhooks::list_webhooks))
+        .route("/webhooks/{id}", get(webhooks::get_webhook))
+        .route("/webhooks/{id}", put(webhooks::update_webhook))
+        .route("/webhooks/{id}", delete(webhooks::delete_webhook))
+        .route("/webhooks/{id}/deliveries", get(webhooks::list_deliveries))
+        .route("/webhooks/{id}/deliveries/replay", post(webhooks::replay_dead_deliveries))
+        .route("/webhooks/{id}/deliveries/{delivery_id}", get(webhooks::get_delivery))
+        .route("/webhooks/{id}/deliveries/{delivery_id}/replay", post(webhooks::replay_delivery))
+
+        .route(
+            "/admin/reconciliation/imports",
+            post(reconciliation::import_statement).layer(DefaultBodyLimit::max(MAX_STATEMENT_FILE_SIZE)),
+        )
+        .route("/admin/reconciliation/imports", get(reconciliation::list_imports))
+        .route("/admin/reconciliation/imports/{id}", get(reconciliation::get_import))
+        .route("/admin/reconciliation/imports/{id}/lines", get(reconciliation::list_import_lines))
+        .route("/admin/reconciliation/imports/{id}/exceptions", get(reconciliation::list_import_exceptions))
+        .route("/admin/audit-log", get(audit::list_audit_log))
+        .route("/admin/fee-schedules", post(fees::create_fee_schedule))
+        .route("/admin/fee-schedules", get(fees::list_fee_schedules))
+        .route("/admin/fee-schedules/{id}", get(fees::get_fee_schedule))
+        .route("/admin/fee-schedules/{id}/deactivate", post(fees::deactivate_fee_schedule))
+        .route("/admin/limits", put(limits::set_limit))
+        .route("/admin/limits", get(limits::list_limits))
+        .route("/admin/limits/{id}", get(limits::get_limit))
+        .route("/admin/limits/{id}", delete(limits::delete_limit))
+        .route("/admin/risk-rules", get(risk::list_risk_rules))
+        .route("/admin/risk-rules/{code}", put(risk::update_risk_rule))
+        .route("/admin/risk-assessments", get(risk::list_risk_assessments))
+        .route("/admin/risk-assessments/{id}", get(risk::get_risk_assessment))
+        .route("/admin/risk-assessments/{id}/approve", post(risk::approve_risk_assessment))
+        .route("/admin/risk-assessments/{id}/reject", post(risk::reject_risk_assessment))
+        .route("/admin/watchlists", get(screening::list_watchlists))
+        .route("/admin/screening-results", get(screening::list_screening_results))
+        .route("/admin/screening-results/{id}", get(screening::get_screening_result))
+        .route("/admin/screening-results/{id}/clear", post(screening::clear_screening_result))
+        .route("/admin/screening-results/{id}/confirm", post(screening::confirm_screening_result))
+        .route("/admin/users/{id}/kyc", get(kyc::get_user_kyc))
+        .route("/admin/kyc-applications", get(kyc::list_applications))
+        .route("/admin/kyc-applications/{id}", get(kyc::get_application))
+        .route("/admin/kyc-applications/{id}/approve", post(kyc::approve_application))
+        .route("/admin/kyc-applications/{id}/reject", post(kyc::reject_application))
+        .route("/admin/kyc-tier-caps", get(kyc::list_tier_caps))
+        .route("/admin/kyc-tier-caps", put(kyc::set_tier_caps))
+        .route("/admin/kyc-tier-caps/{tier}/{currency}", delete(kyc::delete_tier_caps))
+        .route("/admin/aml-scenarios", get(aml::list_scenarios))
+        .route("/admin/aml-scenarios/{code}", put(aml::update_scenario))
+        .route("/admin/aml-runs", get(aml::list_runs))
+        .route("/admin/aml-runs", post(aml::create_run))
+        .route("/admin/aml-cases", get(aml::list_cases))
+        .route("/admin/aml-cases/{id}", get(aml::get_case))
+        .route("/admin/aml-cases/{id}/status", put(aml::update_case_status))
+        .route("/admin/approval-requests", get(approvals::list_requests))
+        .route("/admin/approval-requests/{id}", get(approvals::get_request))
+        .route("/admin/approval-requests/{id}/comments", post(approvals::comment_request))
+        .route("/admin/approval-requests/{id}/approve", post(approvals::approve_request))
+        .route("/admin/approval-requests/{id}/reject", post(approvals::reject_request))
+        .route("/admin/accounts/{id}/adjustments", post(approvals::create_adjustment))
+        .route("/admin/approval-thresholds", get(approvals::list_thresholds))
+        .route("/admin/approval-thresholds", put(approvals::set_threshold))
+        .route("/admin/approval-thresholds/{currency}", delete(approvals::delete_threshold))
+
+        .layer(middleware::from_fn_with_state(pool.clone(), auth_middleware));
+
+    // Probes are added after the rate limiter so orchestrators are never throttled
+    let probe_routes = Router::new()
+        .route("/healthz", get(health::healthz))
+        .route("/readyz", get(health::readyz));
 
     Router::new()
         .merge(public_routes)
         .merge(protected_routes)
         .layer(middleware::from_fn_with_state((), rate_limit_middleware))
+        .merge(probe_routes)
+        .layer(middleware::from_fn(metrics_middleware))
+        .with_state(pool)
+}
+
+// Served on the internal metrics listener only, so scrapes need no token and
+// the metrics aren't exposed on the public port.
+pub fn create_metrics_router(pool: Pool) -> Router {
+    Router::new()
+        .route("/metrics", get(metrics::prometheus_metrics))
         .with_state(pool)
-}
\ No newline at end of file
+}
@@ -0,0 +1,86 @@
+use chrono::{DateTime, Duration, Utc};
+use rust_decimal::Decimal;
+use serde_json::Value;
+use crate::base::{
+    error::AppError,
+    models::aml::{AmlDetection, AmlScenario, AmlScenarioCode, AmlScenarioParams},
+    validation::parse_params as parse,
+};
+
+// Reads and validates `params` for the scenario `code`. Errors are reported
+// under `params`, e.g. `params.min_count`.
+pub fn parse_params(code: AmlScenarioCode, params: &Value) -> Result<AmlScenarioParams, AppError> {
+    Ok(match code {
+        AmlScenarioCode::Structuring => AmlScenarioParams::Structuring(parse(params)?),
+        AmlScenarioCode::RapidMovement => AmlScenarioParams::RapidMovement(parse(params)?),
+        AmlScenarioCode::DormantReactivation => AmlScenarioParams::DormantReactivation(parse(params)?),
+    })
+}
+
+// The enabled scenarios with their params. Params are validated when they
+// are set, so a scenario whose stored params don't parse is skipped with a
+// warning.
+pub fn active_scenarios(scenarios: Vec<AmlScenario>) -> Vec<AmlScenarioParams> {
+    scenarios
+        .into_iter()
+        .filter(|scenario| scenario.enabled)
+        .filter_map(|scenario| match parse_params(scenario.code, &scenario.params) {
+            Ok(params) => Some(params),
+            Err(_) => {
+                tracing::warn!(scenario = %scenario.code, "Skipping AML scenario with invalid params");
+                None
+            }
+        })
+        .collect()
+}
+
+// Start of the rolling window the scenario scans, ending at `now`.
+pub fn window_start(params: &AmlScenarioParams, now: DateTime<Utc>) -> DateTime<Utc> {
+    let hours = match params {
+        AmlScenarioParams::Structuring(params) => params.window_hours,
+        AmlScenarioParams::RapidMovement(params) => params.window_hours,
+        AmlScenarioParams::DormantReactivation(params) => params.window_hours,
+    };
+    now - Duration::hours(hours)
+}
+
+// Deposits of at least this much count towards structuring.
+pub fn structuring_floor(threshold: Decimal, margin_percent: Decimal) -> Decimal {
+    (threshold * (Decimal::ONE_HUNDRED - margin_percent) / Decimal::ONE_HUNDRED).round_dp(2)
+}
+
+// What an investigator reads first about the case.
+pub fn reason(detection: &AmlDetection, params: &AmlScenarioParams) -> String {
+    let currency = &detection.currency;
+    let detail = |key: &str| match detection.details.get(key) {
+        Some(Value::String(value)) => value.clone(),
+        Some(value) => value.to_string(),
+        None => String::new(),
+    };
+
+    match params {
+        AmlScenarioParams::Structuring(params) => format!(
+            "{} deposits totalling {} {} between {} and {} within {} hours",
+            detection.transaction_ids.len(),
+            detection.total_amount,
+            currency,
+            structuring_floor(params.threshold, params.margin_percent),
+            params.threshold,
+            params.window_hours
+        ),
+        AmlScenarioParams::RapidMovement(params) => format!(
+            "{} {} of {} {} deposited was withdrawn again within {} hours",
+            detail("withdrawn"),
+            currency,
+            detail("deposited"),
+            currency,
+            params.window_hours
+        ),
+        AmlScenarioParams::DormantReactivation(_) => format!(
+            "{} {} moved after {} days without activity",
+            detection.total_amount,
+            currency,
+            detail("inactive_days")
+        ),
+    }
+}
@@ -0,0 +1,116 @@
+use sha2::{Digest, Sha256};
+use crate::base::models::audit::AuditChainLink;
+
+// `prev_hash` of the first entry.
+pub const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+// SHA-256 over the entry's fields in a fixed order, each written as
+// `<byte length>:<value>` or `~` for NULL. Must match `audit_log_hash` in the
+// audit chain migration, which computes the stored hashes.
+pub fn entry_hash(link: &AuditChainLink) -> String {
+    let fields = [
+        Some(link.seq.to_string()),
+        Some(link.prev_hash.clone()),
+        Some(link.id.to_string()),
+        Some(link.created_at.format("%Y-%m-%dT%H:%M:%S%.6fZ").to_string()),
+        link.actor_id.map(|id| id.to_string()),
+        Some(link.action.clone()),
+        Some(link.target_type.clone()),
+        link.target_id.map(|id| id.to_string()),
+        link.before.clone(),
+        link.after.clone(),
+        Some(link.details.clone()),
+        link.request_id.clone(),
+        link.ip_address.clone(),
+    ];
+
+    let mut hasher = Sha256::new();
+    for field in &fields {
+        match field {
+            Some(value) => hasher.update(format!("{}:{}", value.len(), value)),
+            None => hasher.update("~"),
+        }
+    }
+    format!("{:x}", hasher.finalize())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+    use uuid::Uuid;
+
+    fn uuid(value: &str) -> Uuid {
+        Uuid::parse_str(value).unwrap()
+    }
+
+    // The expected hashes were computed by `audit_log_hash` in Postgres from
+    // the same values. JSONB fields are given as Postgres renders them.
+    #[test]
+    fn matches_sql_hash_with_null_fields_and_multibyte_text() {
+        let link = AuditChainLink {
+            seq: 42,
+            prev_hash: "ab".repeat(32),
+            id: uuid("6f1c2a0e-5b7d-4c3e-9a1f-2d8e4b6c0a17"),
+            created_at: Utc.with_ymd_and_hms(2024, 3, 10, 8, 15, 30).unwrap() + chrono::Duration::microseconds(123_456),
+            actor_id: None,
+            action: "user.updated".into(),
+            target_type: "user".into(),
+            target_id: Some(uuid("c3b1e9d4-7a26-4f58-8e0b-91d2f4a6b3c5")),
+            before: None,
+            after: Some(r#"{"name": "José Müller"}"#.into()),
+            details: r#"{"note": "Zoë moved to 東京"}"#.into(),
+            request_id: None,
+            ip_address: Some("203.0.113.7".into()),
+            hash: String::new(),
+        };
+
+        assert_eq!(entry_hash(&link), "0043f0457560d6d99a384120e8fcb8642811e69230f3cb900c47b27f5ac4276c");
+    }
+
+    #[test]
+    fn matches_sql_hash_of_first_entry() {
+        let link = AuditChainLink {
+            seq: 1,
+            prev_hash: GENESIS_HASH.into(),
+            id: uuid("0a4f6e2d-1c3b-4d5e-8f70-9a1b2c3d4e5f"),
+            created_at: Utc.with_ymd_and_hms(2025, 11, 2, 23, 59, 59).unwrap() + chrono::Duration::microseconds(1),
+            actor_id: Some(uuid("b2c4d6e8-0a1c-4e3f-9b5d-7f9e1a3c5b7d")),
+            action: "account.withdrawal".into(),
+            target_type: "account".into(),
+            target_id: Some(uuid("e1d2c3b4-a596-4788-9a0b-1c2d3e4f5a6b")),
+            before: Some(r#"{"balance": "1000.00"}"#.into()),
+            after: Some(r#"{"balance": "747.50"}"#.into()),
+            details: r#"{"fee": "2.50", "amount": "250.00"}"#.into(),
+            request_id: Some("req-7f3a".into()),
+            ip_address: Some("2001:db8::1".into()),
+            hash: String::new(),
+        };
+
+        assert_eq!(entry_hash(&link), "3269f75da179210f91fb0ed26d8925ecede190813fcdbdcbcc05c649abc11774");
+    }
+
+    #[test]
+    fn null_differs_from_empty_text() {
+        let mut link = AuditChainLink {
+            seq: 1,
+            prev_hash: GENESIS_HASH.into(),
+            id: uuid("0a4f6e2d-1c3b-4d5e-8f70-9a1b2c3d4e5f"),
+            created_at: Utc.with_ymd_and_hms(2025, 1, 1, 0, 0, 0).unwrap(),
+            actor_id: None,
+            action: "user.erased".into(),
+            target_type: "user".into(),
+            target_id: None,
+            before: None,
+            after: None,
+            details: "{}".into(),
+            request_id: None,
+            ip_address: None,
+            hash: String::new(),
+        };
+        let with_null = entry_hash(&link);
+        link.request_id = Some(String::new());
+
+        assert_ne!(entry_hash(&link), with_null);
+    }
+}
@@ -1,16 +1,32 @@
 use deadpool_postgres::{Config, ManagerConfig, PoolConfig, RecyclingMethod, Runtime};
 use std::env;
+use std::time::Duration;
+use crate::base::{
+    constants::{DEFAULT_METRICS_ADDR, DEFAULT_SHUTDOWN_TIMEOUT_SECS},
+    telemetry::LogFormat,
+};
 
 pub struct AppConfig {
     pub database_url: String,
+    pub shutdown_timeout: Duration,
+    pub log_format: LogFormat,
+    pub metrics_addr: String,
 }
 
 impl AppConfig {
     pub fn from_env() -> Result<Self, env::VarError> {        
         dotenv::dotenv().ok();
 
+        let shutdown_timeout = env::var("SHUTDOWN_TIMEOUT_SECS")
+            .ok()
+            .and_then(|secs| secs.parse::<u64>().ok())
+            .unwrap_or(DEFAULT_SHUTDOWN_TIMEOUT_SECS);
+
         Ok(Self {
             database_url: env::var("DATABASE_URL")?,
+            shutdown_timeout: Duration::from_secs(shutdown_timeout),
+            log_format: LogFormat::parse(&env::var("LOG_FORMAT").unwrap_or_default()),
+            metrics_addr: env::var("METRICS_ADDR").unwrap_or_else(|_| DEFAULT_METRICS_ADDR.to_string()),
         })
     }
 
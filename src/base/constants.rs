@@ -1 +1,129 @@
+use rust_decimal::Decimal;
+use std::time::Duration;
 
+// Startup retries while waiting for the database to accept connections.
+pub const DB_CONNECT_ATTEMPTS: u32 = 10;
+pub const DB_CONNECT_RETRY_DELAY: Duration = Duration::from_secs(3);
+
+// Connections opened eagerly at startup.
+pub const DB_WARM_UP_CONNECTIONS: usize = 5;
+
+pub const DEFAULT_SHUTDOWN_TIMEOUT_SECS: u64 = 30;
+
+// Metrics are served on their own listener, reachable only from the host by
+// default
+pub const DEFAULT_METRICS_ADDR: &str = "127.0.0.1:9090";
+
+// Amounts are stored as DECIMAL(12, 2), so at most 9,999,999,999.99
+pub const MAX_AMOUNT_SCALE: u32 = 2;
+pub const MAX_AMOUNT: Decimal = Decimal::from_parts(3_567_587_327, 232, 0, false, 2);
+
+// Accounts opened without a currency are in rupees
+pub const DEFAULT_CURRENCY: &str = "INR";
+
+pub const DEFAULT_PAGE_SIZE: i64 = 10;
+pub const MAX_PAGE_SIZE: i64 = 100;
+pub const MAX_DESCRIPTION_LENGTH: u64 = 500;
+pub const MAX_SEARCH_LENGTH: u64 = 200;
+pub const MAX_TAGS: usize = 20;
+pub const MAX_TAG_LENGTH: usize = 50;
+
+// Transactions fetched per query while streaming a statement.
+pub const STATEMENT_BATCH_SIZE: i64 = 500;
+
+// A bank may book an entry up to this many days after the transaction was created.
+pub const RECONCILIATION_DATE_TOLERANCE_DAYS: u64 = 3;
+// Candidates fetched when a statement line has no usable reference.
+pub const RECONCILIATION_MAX_CANDIDATES: i64 = 10;
+pub const MAX_STATEMENT_FILE_SIZE: usize = 10 * 1024 * 1024;
+
+// How often the snapshot job looks for finished days without a balance snapshot.
+pub const BALANCE_SNAPSHOT_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+// How often the export job looks for queued personal data exports.
+pub const DATA_EXPORT_POLL_INTERVAL: Duration = Duration::from_secs(10);
+// A RUNNING export not finished within this time is picked up again.
+pub const DATA_EXPORT_STALE_AFTER: Duration = Duration::from_secs(30 * 60);
+// How long an export can be downloaded once it is ready.
+pub const DATA_EXPORT_TTL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+pub const MAX_LISTED_EXPORTS: i64 = 20;
+
+// Audit log entries read per query while verifying the hash chain.
+pub const AUDIT_VERIFY_BATCH_SIZE: i64 = 1000;
+
+pub const MAX_WEBHOOK_URL_LENGTH: u64 = 2048;
+// How often the dispatcher looks for due webhook deliveries.
+pub const WEBHOOK_POLL_INTERVAL: Duration = Duration::from_secs(5);
+pub const WEBHOOK_BATCH_SIZE: i64 = 50;
+pub const WEBHOOK_REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+// A claimed delivery whose worker died is attempted again after this long.
+pub const WEBHOOK_CLAIM_LEASE: Duration = Duration::from_secs(60);
+// Retries wait 30s, 1m, 2m, ... up to an hour; the delivery is dead after the last attempt.
+pub const WEBHOOK_RETRY_BASE_DELAY: Duration = Duration::from_secs(30);
+pub const WEBHOOK_RETRY_MAX_DELAY: Duration = Duration::from_secs(60 * 60);
+pub const WEBHOOK_MAX_ATTEMPTS: i32 = 10;
+
+// Channel the `account_events` trigger notifies on.
+pub const ACCOUNT_EVENTS_CHANNEL: &str = "account_events";
+// Events buffered per replica for slow subscribers; one that falls further
+// behind catches up from the database.
+pub const ACCOUNT_EVENT_BUFFER: usize = 1024;
+// Events read per query when a subscriber resumes or catches up.
+pub const ACCOUNT_EVENT_BATCH_SIZE: i64 = 500;
+// Events older than this are pruned and can no longer be resumed from.
+pub const ACCOUNT_EVENT_RETENTION: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+pub const ACCOUNT_EVENT_PRUNE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+// Wait before the listener connects again after losing its connection.
+pub const ACCOUNT_EVENT_RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+pub const MAX_CRON_LENGTH: u64 = 200;
+pub const MAX_RRULE_LENGTH: u64 = 500;
+// How often the scheduler looks for due scheduled payments.
+pub const SCHEDULED_PAYMENT_POLL_INTERVAL: Duration = Duration::from_secs(15);
+pub const SCHEDULED_PAYMENT_BATCH_SIZE: i64 = 50;
+
+pub const MAX_FEE_SCHEDULE_NAME_LENGTH: u64 = 100;
+pub const MAX_FEE_TIERS: usize = 10;
+// Fee percentages are stored with four decimal places, e.g. 0.0125%.
+pub const MAX_FEE_PERCENTAGE_SCALE: u32 = 4;
+// Tag on the transactions that charge and collect a fee.
+pub const FEE_TRANSACTION_TAG: &str = "fee";
+
+// Debits scoring at least this much are held for manual review, and at least
+// the block score are refused. Rule scores are set by admins.
+pub const RISK_REVIEW_SCORE: i32 = 50;
+pub const RISK_BLOCK_SCORE: i32 = 100;
+pub const MAX_RISK_RULE_SCORE: i32 = 100;
+pub const MAX_REVIEW_NOTE_LENGTH: u64 = 500;
+
+// Name similarity, in percent, from which a sanctions match is queued for
+// review and from which it is treated as a hit.
+pub const SCREENING_REVIEW_SCORE: i32 = 85;
+pub const SCREENING_BLOCK_SCORE: i32 = 97;
+// Matches kept per screening result, best first.
+pub const MAX_SCREENING_MATCHES: usize = 10;
+pub const MAX_COUNTERPARTY_NAME_LENGTH: u64 = 255;
+// Watchlist entries inserted per statement when a list is reloaded.
+pub const WATCHLIST_INSERT_BATCH_SIZE: usize = 1000;
+
+// KYC document uploads are described, not stored; these bound the metadata.
+pub const MAX_KYC_DOCUMENT_SIZE: i64 = 20 * 1024 * 1024;
+pub const MAX_KYC_DOCUMENTS: i64 = 20;
+// Characters of a document number that are kept.
+pub const KYC_DOCUMENT_NUMBER_KEPT: usize = 4;
+
+// How often the AML monitoring job scans transactions. A scheduled run is
+// skipped when another replica ran it less than half an interval ago.
+pub const AML_MONITORING_INTERVAL: Duration = Duration::from_secs(60 * 60);
+// Arbitrary key so that only one monitoring run scans at a time.
+pub const AML_MONITORING_LOCK_KEY: i64 = 7_340_023;
+
+// Requests for approval not decided within this time expire without being
+// carried out.
+pub const APPROVAL_REQUEST_TTL: Duration = Duration::from_secs(48 * 60 * 60);
+// How often the expiry job looks for requests past their expiry.
+pub const APPROVAL_EXPIRY_POLL_INTERVAL: Duration = Duration::from_secs(60);
+pub const APPROVAL_EXPIRY_BATCH_SIZE: i64 = 50;
+pub const MAX_APPROVAL_COMMENT_LENGTH: u64 = 1000;
+// Tag on the transactions booked by an approved balance adjustment.
+pub const ADJUSTMENT_TRANSACTION_TAG: &str = "adjustment";
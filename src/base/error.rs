@@ -1,56 +1,264 @@
 use axum::{
-    http::StatusCode,
+    http::{header, StatusCode},
     response::{IntoResponse, Response},
-    Json,
 };
+use serde::Serialize;
 use serde_json::json;
 use thiserror::Error;
+use tokio_postgres::error::SqlState;
+use crate::base::telemetry;
+
+const PROBLEM_CONTENT_TYPE: &str = "application/problem+json";
 
 #[derive(Error, Debug)]
 pub enum AppError {
     #[error("Database error: {0}")]
     Database(String),
+    #[error("Internal error: {0}")]
+    Internal(String),
+    #[error("Service unavailable: {0}")]
+    Unavailable(String),
     #[error("Authentication error: {0}")]
     Auth(String),
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
     #[error("Validation error: {0}")]
     Validation(String),
+    #[error("Validation failed on {} field(s)", .0.len())]
+    InvalidFields(Vec<FieldError>),
     #[error("Not found: {0}")]
     NotFound(String),
+    #[error("Email already registered")]
+    EmailTaken,
+    #[error("Conflict: {0}")]
+    Conflict(String),
+    #[error("Referenced resource does not exist: {0}")]
+    InvalidReference(String),
+    #[error("Insufficient funds")]
+    InsufficientFunds,
+    #[error("Account is frozen")]
+    AccountFrozen,
+    #[error("Account is closed")]
+    AccountClosed,
+    #[error("Limit exceeded: {0}")]
+    LimitExceeded(String),
+    #[error("KYC tier limit reached: {0}")]
+    KycTierLimit(String),
+    #[error("Transaction declined by risk checks")]
+    TransactionDeclined,
+    #[error("Registration declined by sanctions screening")]
+    RegistrationDeclined,
+    #[error("User is restricted pending compliance review")]
+    UserRestricted,
+    #[error("Rate limit exceeded")]
+    RateLimited,
 }
 
-impl IntoResponse for AppError {
-    fn into_response(self) -> Response {
-        let (status, error_code, message) = match self {
-            AppError::Database(ref msg) => (
-                StatusCode::INTERNAL_SERVER_ERROR, 
-                "INTERNAL_SERVER_ERROR", 
-                msg.clone()
-            ),
-            AppError::Auth(ref msg) => (
-                StatusCode::UNAUTHORIZED, 
-                "AUTH_FAILED", 
-                msg.clone()
-            ),
-            AppError::Validation(ref msg) => (
-                StatusCode::BAD_REQUEST, 
-                "INVALID_INPUT", 
-                msg.clone()
-            ),
-            AppError::NotFound(ref msg) => (
-                StatusCode::NOT_FOUND, 
-                "NOT_FOUND", 
-                msg.clone()
-            ),
+// A single rule violation, reported under `errors` in the problem body.
+#[derive(Debug, Clone, Serialize)]
+pub struct FieldError {
+    pub field: String,
+    pub code: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: impl Into<String>, code: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            field: field.into(),
+            code: code.into(),
+            message: message.into(),
+        }
+    }
+}
+
+impl AppError {
+    pub fn invalid_field(field: &str, code: &str, message: &str) -> Self {
+        AppError::InvalidFields(vec![FieldError::new(field, code, message)])
+    }
+
+    pub fn status(&self) -> StatusCode {
+        match self {
+            AppError::Database(_) | AppError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            AppError::Unavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+            AppError::Auth(_) => StatusCode::UNAUTHORIZED,
+            AppError::Forbidden(_) | AppError::UserRestricted => StatusCode::FORBIDDEN,
+            AppError::Validation(_) | AppError::InvalidFields(_) => StatusCode::BAD_REQUEST,
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::EmailTaken | AppError::Conflict(_) => StatusCode::CONFLICT,
+            AppError::InvalidReference(_)
+            | AppError::InsufficientFunds
+            | AppError::AccountFrozen
+            | AppError::AccountClosed
+            | AppError::LimitExceeded(_)
+            | AppError::KycTierLimit(_)
+            | AppError::TransactionDeclined
+            | AppError::RegistrationDeclined => StatusCode::UNPROCESSABLE_ENTITY,
+            AppError::RateLimited => StatusCode::TOO_MANY_REQUESTS,
+        }
+    }
+
+    // Stable machine-readable code; clients should branch on this, not on `detail`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::Database(_) | AppError::Internal(_) => "INTERNAL_SERVER_ERROR",
+            AppError::Unavailable(_) => "SERVICE_UNAVAILABLE",
+            AppError::Auth(_) => "AUTH_FAILED",
+            AppError::Forbidden(_) => "FORBIDDEN",
+            AppError::Validation(_) => "INVALID_INPUT",
+            AppError::InvalidFields(_) => "VALIDATION_FAILED",
+            AppError::NotFound(_) => "NOT_FOUND",
+            AppError::EmailTaken => "EMAIL_TAKEN",
+            AppError::Conflict(_) => "CONFLICT",
+            AppError::InvalidReference(_) => "INVALID_REFERENCE",
+            AppError::InsufficientFunds => "INSUFFICIENT_FUNDS",
+            AppError::AccountFrozen => "ACCOUNT_FROZEN",
+            AppError::AccountClosed => "ACCOUNT_CLOSED",
+            AppError::LimitExceeded(_) => "LIMIT_EXCEEDED",
+            AppError::KycTierLimit(_) => "KYC_TIER_LIMIT",
+            AppError::TransactionDeclined => "TRANSACTION_DECLINED",
+            AppError::RegistrationDeclined => "REGISTRATION_DECLINED",
+            AppError::UserRestricted => "USER_RESTRICTED",
+            AppError::RateLimited => "RATE_LIMITED",
+        }
+    }
+
+    fn title(&self) -> &'static str {
+        match self {
+            AppError::Database(_) | AppError::Internal(_) => "Internal server error",
+            AppError::Unavailable(_) => "Service unavailable",
+            AppError::Auth(_) => "Authentication failed",
+            AppError::Forbidden(_) => "Forbidden",
+            AppError::Validation(_) => "Invalid input",
+            AppError::InvalidFields(_) => "Validation failed",
+            AppError::NotFound(_) => "Resource not found",
+            AppError::EmailTaken => "Email already registered",
+            AppError::Conflict(_) => "Conflict",
+            AppError::InvalidReference(_) => "Invalid reference",
+            AppError::InsufficientFunds => "Insufficient funds",
+            AppError::AccountFrozen => "Account frozen",
+            AppError::AccountClosed => "Account closed",
+            AppError::LimitExceeded(_) => "Limit exceeded",
+            AppError::KycTierLimit(_) => "KYC tier limit reached",
+            AppError::TransactionDeclined => "Transaction declined",
+            AppError::RegistrationDeclined => "Registration declined",
+            AppError::UserRestricted => "User restricted",
+            AppError::RateLimited => "Too many requests",
+        }
+    }
+
+    // Human-readable explanation safe to show to clients. Internal failures are
+    // replaced with a generic message; the real cause only goes to the logs.
+    pub fn detail(&self) -> String {
+        match self {
+            AppError::Database(_) | AppError::Internal(_) => "An unexpected error occurred".into(),
+            AppError::Unavailable(_) => "The service is temporarily unavailable, please retry".into(),
+            AppError::Auth(msg)
+            | AppError::Forbidden(msg)
+            | AppError::Validation(msg)
+            | AppError::NotFound(msg)
+            | AppError::Conflict(msg)
+            | AppError::InvalidReference(msg)
+            | AppError::LimitExceeded(msg)
+            | AppError::KycTierLimit(msg) => msg.clone(),
+            AppError::InvalidFields(_) => "One or more fields are invalid".into(),
+            AppError::EmailTaken => "An account with this email already exists".into(),
+            AppError::InsufficientFunds => "The account balance is too low for this operation".into(),
+            AppError::AccountFrozen => "The account is frozen and can't send funds".into(),
+            AppError::AccountClosed => "The account is closed".into(),
+            // Which checks failed is not revealed to the client
+            AppError::TransactionDeclined => "The transaction was declined".into(),
+            // Screening hits are not revealed either
+            AppError::RegistrationDeclined => "The registration was declined".into(),
+            AppError::UserRestricted => "Account activity is restricted, please contact support".into(),
+            AppError::RateLimited => "Rate limit exceeded, please slow down".into(),
+        }
+    }
+}
+
+impl From<tokio_postgres::Error> for AppError {
+    fn from(error: tokio_postgres::Error) -> Self {
+        let Some(db_error) = error.as_db_error() else {
+            return AppError::Database(error.to_string());
         };
 
-        let body = Json(json!({
-            "error": {
-                "code": error_code,
-                "message": message
+        let constraint = db_error.constraint().unwrap_or_default();
+        let code = db_error.code();
+
+        if *code == SqlState::UNIQUE_VIOLATION {
+            if constraint == "users_email_key" {
+                return AppError::EmailTaken;
+            }
+            return AppError::Conflict("Resource already exists".into());
+        }
+
+        if *code == SqlState::FOREIGN_KEY_VIOLATION {
+            // Postgres reports deletes of still-referenced rows with this prefix
+            if db_error.message().starts_with("update or delete") {
+                return AppError::Conflict("Resource is still referenced by other records".into());
             }
-        }));
+            return AppError::InvalidReference(constraint.to_string());
+        }
+
+        if *code == SqlState::CHECK_VIOLATION || *code == SqlState::NOT_NULL_VIOLATION {
+            return AppError::Validation("Request violates a data constraint".into());
+        }
+
+        if *code == SqlState::NUMERIC_VALUE_OUT_OF_RANGE || *code == SqlState::STRING_DATA_RIGHT_TRUNCATION {
+            return AppError::Validation("Value is out of range".into());
+        }
 
-        (status, body).into_response()
+        AppError::Database(format!(
+            "{} ({}): {}",
+            db_error.message(),
+            code.code(),
+            db_error.detail().unwrap_or_default()
+        ))
+    }
+}
+
+impl From<deadpool_postgres::PoolError> for AppError {
+    fn from(error: deadpool_postgres::PoolError) -> Self {
+        match error {
+            deadpool_postgres::PoolError::Backend(e) => e.into(),
+            e => AppError::Unavailable(e.to_string()),
+        }
+    }
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        let status = self.status();
+
+        if status.is_server_error() {
+            tracing::error!(code = self.code(), "{}", self);
+        } else {
+            tracing::debug!(code = self.code(), "{}", self);
+        }
+
+        let mut body = json!({
+            "type": format!("/errors/{}", self.code().to_lowercase().replace('_', "-")),
+            "title": self.title(),
+            "status": status.as_u16(),
+            "detail": self.detail(),
+            "code": self.code(),
+        });
+
+        if let Some(context) = telemetry::current_request_context() {
+            body["instance"] = json!(context.path);
+            body["request_id"] = json!(context.request_id);
+        }
+
+        if let AppError::InvalidFields(errors) = &self {
+            body["errors"] = json!(errors);
+        }
+
+        (
+            status,
+            [(header::CONTENT_TYPE, PROBLEM_CONTENT_TYPE)],
+            body.to_string(),
+        )
+            .into_response()
     }
 }
 
@@ -58,4 +266,4 @@ impl From<AppError> for Response {
     fn from(error: AppError) -> Self {
         error.into_response()
     }
-} 
\ No newline at end of file
+}
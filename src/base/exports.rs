@@ -0,0 +1,228 @@
+use std::io::{Cursor, Write};
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use serde_json::json;
+use sha2::Sha256;
+use uuid::Uuid;
+use zip::{write::SimpleFileOptions, CompressionMethod, ZipWriter};
+use crate::base::{
+    models::{
+        accounts::Account, consents::Consent, kyc::KycDocument, transactions::Transaction,
+        users::{LoginEvent, User},
+    },
+    statements::csv_field,
+};
+
+// Everything held about a user, as written to their export archive.
+pub struct ExportData {
+    pub user: User,
+    pub accounts: Vec<Account>,
+    pub transactions: Vec<Transaction>,
+    pub login_events: Vec<LoginEvent>,
+    pub consents: Vec<Consent>,
+    pub kyc_documents: Vec<KycDocument>,
+}
+
+// ZIP archive with every dataset as `<name>.json` and `<name>.csv`, plus a
+// `manifest.json` describing the export.
+pub fn build_archive(export_id: Uuid, data: &ExportData, generated_at: DateTime<Utc>) -> Result<Vec<u8>, String> {
+    let mut archive = Archive::new();
+
+    let profile = std::slice::from_ref(&data.user);
+    archive.dataset(
+        "profile",
+        profile,
+        &["id", "name", "email", "role", "kyc_tier", "kyc_status", "created_at", "updated_at"],
+        |user| {
+            vec![
+                user.id.to_string(),
+                csv_field(&user.name),
+                csv_field(&user.email),
+                user.role.to_string(),
+                user.kyc_tier.to_string(),
+                user.kyc_status.to_string(),
+                user.created_at.to_rfc3339(),
+                user.updated_at.to_rfc3339(),
+            ]
+        },
+    )?;
+
+    archive.dataset(
+        "accounts",
+        &data.accounts,
+        &["id", "currency", "balance", "status", "created_at", "updated_at", "closed_at"],
+        |account| {
+            vec![
+                account.id.to_string(),
+                account.currency.clone(),
+                account.balance.to_string(),
+                account.status.to_string(),
+                account.created_at.to_rfc3339(),
+                account.updated_at.to_rfc3339(),
+                account.closed_at.map(|ts| ts.to_rfc3339()).unwrap_or_default(),
+            ]
+        },
+    )?;
+
+    archive.dataset(
+        "transactions",
+        &data.transactions,
+        &["id", "account_id", "type", "status", "amount", "description", "tags", "metadata", "created_at", "updated_at"],
+        |transaction| {
+            vec![
+                transaction.id.to_string(),
+                transaction.account_id.to_string(),
+                transaction.transaction_type.to_string(),
+                transaction.status.to_string(),
+                transaction.amount.to_string(),
+                csv_field(transaction.description.as_deref().unwrap_or_default()),
+                csv_field(&transaction.tags.join(";")),
+                csv_field(&transaction.metadata.to_string()),
+                transaction.created_at.to_rfc3339(),
+                transaction.updated_at.to_rfc3339(),
+            ]
+        },
+    )?;
+
+    archive.dataset(
+        "login_history",
+        &data.login_events,
+        &["created_at", "succeeded", "ip_address", "user_agent"],
+        |event| {
+            vec![
+                event.created_at.to_rfc3339(),
+                event.succeeded.to_string(),
+                event.ip_address.clone().unwrap_or_default(),
+                csv_field(event.user_agent.as_deref().unwrap_or_default()),
+            ]
+        },
+    )?;
+
+    archive.dataset(
+        "consents",
+        &data.consents,
+        &["created_at", "purpose", "granted", "ip_address"],
+        |consent| {
+            vec![
+                consent.created_at.to_rfc3339(),
+                consent.purpose.to_string(),
+                consent.granted.to_string(),
+                consent.ip_address.clone().unwrap_or_default(),
+            ]
+        },
+    )?;
+
+    archive.dataset(
+        "kyc_documents",
+        &data.kyc_documents,
+        &[
+            "id", "document_type", "issuing_country", "document_number_last4", "expires_on", "file_name",
+            "content_type", "size_bytes", "sha256", "status", "created_at",
+        ],
+        |document| {
+            vec![
+                document.id.to_string(),
+                document.document_type.to_string(),
+                document.issuing_country.clone(),
+                document.document_number_last4.clone().unwrap_or_default(),
+                document.expires_on.map(|date| date.to_string()).unwrap_or_default(),
+                csv_field(&document.file_name),
+                csv_field(&document.content_type),
+                document.size_bytes.to_string(),
+                document.sha256.clone(),
+                document.status.to_string(),
+                document.created_at.to_rfc3339(),
+            ]
+        },
+    )?;
+
+    let manifest = json!({
+        "export_id": export_id,
+        "user_id": data.user.id,
+        "generated_at": generated_at.to_rfc3339(),
+        "files": archive.files,
+    });
+    archive.file("manifest.json", &serde_json::to_vec_pretty(&manifest).map_err(|e| e.to_string())?)?;
+
+    archive.finish()
+}
+
+struct Archive {
+    writer: ZipWriter<Cursor<Vec<u8>>>,
+    files: Vec<String>,
+}
+
+impl Archive {
+    fn new() -> Self {
+        Self {
+            writer: ZipWriter::new(Cursor::new(Vec::new())),
+            files: Vec::new(),
+        }
+    }
+
+    fn dataset<T: Serialize>(
+        &mut self,
+        name: &str,
+        records: &[T],
+        columns: &[&str],
+        row: impl Fn(&T) -> Vec<String>,
+    ) -> Result<(), String> {
+        let json = serde_json::to_vec_pretty(records).map_err(|e| e.to_string())?;
+        self.file(&format!("{}.json", name), &json)?;
+
+        // RFC 4180 CSV, like the statement export
+        let mut csv = format!("{}\r\n", columns.join(","));
+        for record in records {
+            csv.push_str(&row(record).join(","));
+            csv.push_str("\r\n");
+        }
+        self.file(&format!("{}.csv", name), csv.as_bytes())
+    }
+
+    fn file(&mut self, name: &str, content: &[u8]) -> Result<(), String> {
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+        self.writer.start_file(name, options).map_err(|e| e.to_string())?;
+        self.writer.write_all(content).map_err(|e| e.to_string())?;
+        self.files.push(name.to_string());
+        Ok(())
+    }
+
+    fn finish(self) -> Result<Vec<u8>, String> {
+        Ok(self.writer.finish().map_err(|e| e.to_string())?.into_inner())
+    }
+}
+
+// Download links are signed rather than stored, so a link can be handed out
+// on every read of the export and stops working at `expires` without any
+// bookkeeping. The export id and expiry are covered by the signature.
+pub fn download_url(export_id: Uuid, expires_at: DateTime<Utc>, key: &[u8]) -> String {
+    let expires = expires_at.timestamp();
+    format!(
+        "/exports/{}/download?expires={}&signature={}",
+        export_id,
+        expires,
+        download_signature(export_id, expires, key)
+    )
+}
+
+pub fn verify_download_signature(export_id: Uuid, expires: i64, signature: &str, key: &[u8]) -> bool {
+    let expected = download_signature(export_id, expires, key);
+    // Compared in constant time so the signature can't be guessed byte by byte
+    expected.len() == signature.len()
+        && expected
+            .bytes()
+            .zip(signature.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+fn download_signature(export_id: Uuid, expires: i64, key: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any length");
+    mac.update(format!("data-export:{}:{}", export_id, expires).as_bytes());
+    mac.finalize()
+        .into_bytes()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
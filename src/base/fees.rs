@@ -0,0 +1,200 @@
+use rust_decimal::{Decimal, RoundingStrategy};
+use crate::base::{
+    constants::MAX_AMOUNT_SCALE,
+    models::fees::{FeeBreakdown, FeeCap, FeeSchedule, FeeTransactionType},
+};
+
+// Works out the fee `schedule` charges for `amount`: the flat amount plus the
+// percentage of the amount (from the matching tier when the schedule is
+// tiered), rounded half away from zero to the cent and then held between
+// `min_fee` and `max_fee`.
+pub fn calculate(
+    schedule: Option<&FeeSchedule>,
+    transaction_type: FeeTransactionType,
+    currency: &str,
+    amount: Decimal,
+) -> FeeBreakdown {
+    let Some(schedule) = schedule else {
+        return FeeBreakdown {
+            transaction_type,
+            currency: currency.to_string(),
+            amount: money(amount),
+            fee_schedule_id: None,
+            tier: None,
+            flat_fee: money(Decimal::ZERO),
+            percentage: Decimal::ZERO,
+            percentage_fee: money(Decimal::ZERO),
+            cap_applied: None,
+            fee: money(Decimal::ZERO),
+            net_amount: money(amount),
+            revenue_account_id: None,
+        };
+    };
+
+    let tier = schedule
+        .tiers
+        .iter()
+        .find(|tier| tier.up_to.is_none_or(|up_to| amount <= up_to));
+    let (flat_fee, percentage) = match tier {
+        Some(tier) => (tier.flat_amount, tier.percentage),
+        None => (schedule.flat_amount, schedule.percentage),
+    };
+
+    let percentage_fee = money(amount * percentage / Decimal::ONE_HUNDRED);
+    let mut fee = flat_fee + percentage_fee;
+    let mut cap_applied = None;
+
+    if let Some(min_fee) = schedule.min_fee
+        && fee < min_fee
+    {
+        fee = min_fee;
+        cap_applied = Some(FeeCap::Minimum);
+    }
+    if let Some(max_fee) = schedule.max_fee
+        && fee > max_fee
+    {
+        fee = max_fee;
+        cap_applied = Some(FeeCap::Maximum);
+    }
+
+    let net_amount = match transaction_type {
+        FeeTransactionType::Deposit => amount - fee,
+        FeeTransactionType::Withdrawal | FeeTransactionType::Transfer => amount + fee,
+    };
+
+    FeeBreakdown {
+        transaction_type,
+        currency: currency.to_string(),
+        amount: money(amount),
+        fee_schedule_id: Some(schedule.id),
+        tier: tier.cloned(),
+        flat_fee: money(flat_fee),
+        percentage,
+        percentage_fee,
+        cap_applied,
+        fee: money(fee),
+        net_amount: money(net_amount),
+        revenue_account_id: Some(schedule.revenue_account_id),
+    }
+}
+
+// Rounded to the cent and always shown with two decimal places.
+fn money(value: Decimal) -> Decimal {
+    let mut value = value.round_dp_with_strategy(MAX_AMOUNT_SCALE, RoundingStrategy::MidpointAwayFromZero);
+    value.rescale(MAX_AMOUNT_SCALE);
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use uuid::Uuid;
+    use crate::base::models::fees::FeeTier;
+
+    fn dec(value: &str) -> Decimal {
+        value.parse().unwrap()
+    }
+
+    fn schedule(flat_amount: &str, percentage: &str) -> FeeSchedule {
+        FeeSchedule {
+            id: Uuid::nil(),
+            name: "Test".into(),
+            transaction_type: FeeTransactionType::Withdrawal,
+            currency: "INR".into(),
+            flat_amount: dec(flat_amount),
+            percentage: dec(percentage),
+            tiers: Vec::new(),
+            min_fee: None,
+            max_fee: None,
+            revenue_account_id: Uuid::nil(),
+            active: true,
+            created_by: None,
+            created_at: Utc::now(),
+            deactivated_at: None,
+        }
+    }
+
+    fn tier(up_to: Option<&str>, flat_amount: &str, percentage: &str) -> FeeTier {
+        FeeTier { up_to: up_to.map(dec), flat_amount: dec(flat_amount), percentage: dec(percentage) }
+    }
+
+    fn fee(schedule: &FeeSchedule, amount: &str) -> FeeBreakdown {
+        calculate(Some(schedule), FeeTransactionType::Withdrawal, "INR", dec(amount))
+    }
+
+    #[test]
+    fn no_schedule_charges_nothing() {
+        let breakdown = calculate(None, FeeTransactionType::Withdrawal, "INR", dec("12.5"));
+
+        assert_eq!(breakdown.fee.to_string(), "0.00");
+        assert_eq!(breakdown.net_amount.to_string(), "12.50");
+        assert_eq!(breakdown.fee_schedule_id, None);
+    }
+
+    #[test]
+    fn adds_flat_and_percentage_fee() {
+        let schedule = schedule("1.00", "2.5");
+
+        let withdrawal = fee(&schedule, "100");
+        assert_eq!(withdrawal.percentage_fee, dec("2.50"));
+        assert_eq!(withdrawal.fee, dec("3.50"));
+        assert_eq!(withdrawal.net_amount, dec("103.50"));
+
+        let deposit = calculate(Some(&schedule), FeeTransactionType::Deposit, "INR", dec("100"));
+        assert_eq!(deposit.net_amount, dec("96.50"));
+    }
+
+    #[test]
+    fn picks_first_tier_covering_the_amount() {
+        let mut schedule = schedule("0", "0");
+        schedule.tiers = vec![
+            tier(Some("1000"), "0", "1"),
+            tier(Some("10000"), "5", "0.5"),
+            tier(None, "10", "0.25"),
+        ];
+
+        assert_eq!(fee(&schedule, "1000").fee, dec("10.00"));
+        assert_eq!(fee(&schedule, "1000.01").fee, dec("10.00"));
+        assert_eq!(fee(&schedule, "1000.01").tier.unwrap().up_to, Some(dec("10000")));
+        assert_eq!(fee(&schedule, "20000").fee, dec("60.00"));
+    }
+
+    #[test]
+    fn falls_back_to_schedule_rates_above_the_last_tier() {
+        let mut schedule = schedule("2", "0");
+        schedule.tiers = vec![tier(Some("500"), "0", "1")];
+
+        let breakdown = fee(&schedule, "501");
+        assert!(breakdown.tier.is_none());
+        assert_eq!(breakdown.fee, dec("2.00"));
+    }
+
+    #[test]
+    fn holds_fee_between_min_and_max() {
+        let mut schedule = schedule("0", "1");
+        schedule.min_fee = Some(dec("0.50"));
+        schedule.max_fee = Some(dec("25.00"));
+
+        let low = fee(&schedule, "10");
+        assert_eq!(low.fee, dec("0.50"));
+        assert!(matches!(low.cap_applied, Some(FeeCap::Minimum)));
+
+        let high = fee(&schedule, "5000");
+        assert_eq!(high.fee, dec("25.00"));
+        assert_eq!(high.net_amount, dec("5025.00"));
+        assert!(matches!(high.cap_applied, Some(FeeCap::Maximum)));
+
+        let between = fee(&schedule, "100");
+        assert_eq!(between.fee, dec("1.00"));
+        assert!(between.cap_applied.is_none());
+    }
+
+    #[test]
+    fn rounds_percentage_fee_half_away_from_zero() {
+        assert_eq!(fee(&schedule("0", "0.5"), "1").fee.to_string(), "0.01");
+        assert_eq!(fee(&schedule("0", "1.5"), "10.10").fee.to_string(), "0.15");
+        assert_eq!(fee(&schedule("0", "0.4"), "1").fee.to_string(), "0.00");
+        assert_eq!(fee(&schedule("0.1", "0"), "3").fee.to_string(), "0.10");
+    }
+}
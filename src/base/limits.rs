@@ -0,0 +1,126 @@
+use chrono::{DateTime, Datelike, Duration, NaiveDate, TimeZone, Utc};
+use rust_decimal::Decimal;
+use crate::base::models::limits::{DebitUsage, LimitHeadroom, LimitUsage, Limits};
+
+// Daily and monthly totals reset at midnight UTC; the hourly count is over the
+// last 60 minutes.
+#[derive(Debug, Clone, Copy)]
+pub struct DebitWindows {
+    pub day_start: DateTime<Utc>,
+    pub month_start: DateTime<Utc>,
+    pub hour_start: DateTime<Utc>,
+    pub day_end: DateTime<Utc>,
+    pub month_end: DateTime<Utc>,
+}
+
+impl DebitWindows {
+    pub fn at(now: DateTime<Utc>) -> Self {
+        let today = now.date_naive();
+        let month = NaiveDate::from_ymd_opt(today.year(), today.month(), 1).unwrap();
+        let next_month = month.checked_add_months(chrono::Months::new(1)).unwrap();
+
+        DebitWindows {
+            day_start: midnight(today),
+            month_start: midnight(month),
+            hour_start: now - Duration::hours(1),
+            day_end: midnight(today.succ_opt().unwrap()),
+            month_end: midnight(next_month),
+        }
+    }
+}
+
+fn midnight(date: NaiveDate) -> DateTime<Utc> {
+    Utc.from_utc_datetime(&date.and_hms_opt(0, 0, 0).unwrap())
+}
+
+// A limit a debit would go over; `limit` names it for metrics.
+#[derive(Debug)]
+pub struct LimitBreach {
+    pub limit: &'static str,
+    pub message: String,
+}
+
+// Checks a debit of `amount` against `limits`, given what was already debited.
+// `subject` says whose limits they are, e.g. "the account".
+pub fn check(
+    limits: &Limits,
+    usage: &DebitUsage,
+    amount: Decimal,
+    currency: &str,
+    subject: &str,
+) -> Result<(), LimitBreach> {
+    if let Some(max) = limits.max_single_withdrawal
+        && amount > max
+    {
+        return Err(LimitBreach {
+            limit: "max_single_withdrawal",
+            message: format!("Withdrawals from {} are limited to {} {} each", subject, max, currency),
+        });
+    }
+    if let Some(daily) = limits.daily_debit_limit
+        && usage.day_total + amount > daily
+    {
+        return Err(LimitBreach {
+            limit: "daily_debit_limit",
+            message: format!(
+                "Daily debit limit of {} {} for {} exceeded; {} {} remaining today",
+                daily,
+                currency,
+                subject,
+                (daily - usage.day_total).max(Decimal::ZERO),
+                currency
+            ),
+        });
+    }
+    if let Some(monthly) = limits.monthly_debit_limit
+        && usage.month_total + amount > monthly
+    {
+        return Err(LimitBreach {
+            limit: "monthly_debit_limit",
+            message: format!(
+                "Monthly debit limit of {} {} for {} exceeded; {} {} remaining this month",
+                monthly,
+                currency,
+                subject,
+                (monthly - usage.month_total).max(Decimal::ZERO),
+                currency
+            ),
+        });
+    }
+    if let Some(per_hour) = limits.max_debits_per_hour
+        && usage.hour_count >= i64::from(per_hour)
+    {
+        return Err(LimitBreach {
+            limit: "max_debits_per_hour",
+            message: format!("At most {} debits per hour are allowed for {}", per_hour, subject),
+        });
+    }
+    Ok(())
+}
+
+pub fn headroom(limits: &Limits, usage: &DebitUsage, windows: &DebitWindows) -> LimitHeadroom {
+    let remaining = |limit: Option<Decimal>, used: Decimal| limit.map(|limit| (limit - used).max(Decimal::ZERO));
+    let per_hour = limits.max_debits_per_hour.map(i64::from);
+
+    LimitHeadroom {
+        max_single_withdrawal: limits.max_single_withdrawal,
+        daily_debits: LimitUsage {
+            limit: limits.daily_debit_limit,
+            used: usage.day_total,
+            remaining: remaining(limits.daily_debit_limit, usage.day_total),
+            resets_at: Some(windows.day_end),
+        },
+        monthly_debits: LimitUsage {
+            limit: limits.monthly_debit_limit,
+            used: usage.month_total,
+            remaining: remaining(limits.monthly_debit_limit, usage.month_total),
+            resets_at: Some(windows.month_end),
+        },
+        hourly_debits: LimitUsage {
+            limit: per_hour,
+            used: usage.hour_count,
+            remaining: per_hour.map(|limit| (limit - usage.hour_count).max(0)),
+            resets_at: usage.hour_resets_at,
+        },
+    }
+}
@@ -0,0 +1,187 @@
+use metrics::{counter, gauge, histogram};
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
+use rust_decimal::{prelude::ToPrimitive, Decimal};
+use std::time::Duration;
+
+pub const HTTP_REQUESTS_TOTAL: &str = "http_requests_total";
+pub const HTTP_REQUEST_DURATION_SECONDS: &str = "http_request_duration_seconds";
+pub const DB_POOL_SIZE: &str = "db_pool_connections";
+pub const DB_POOL_AVAILABLE: &str = "db_pool_connections_available";
+pub const DB_POOL_WAITING: &str = "db_pool_waiting_requests";
+pub const DB_POOL_MAX_SIZE: &str = "db_pool_max_connections";
+pub const RATE_LIMIT_REJECTIONS_TOTAL: &str = "rate_limit_rejections_total";
+pub const DEPOSITS_TOTAL: &str = "deposits_total";
+pub const DEPOSIT_VOLUME_TOTAL: &str = "deposit_volume_minor_units_total";
+pub const WITHDRAWALS_TOTAL: &str = "withdrawals_total";
+pub const WITHDRAWAL_VOLUME_TOTAL: &str = "withdrawal_volume_minor_units_total";
+pub const WITHDRAWALS_INSUFFICIENT_BALANCE_TOTAL: &str = "withdrawals_insufficient_balance_total";
+pub const RECONCILIATION_LINES_TOTAL: &str = "reconciliation_lines_total";
+pub const BALANCE_SNAPSHOTS_TOTAL: &str = "balance_snapshots_total";
+pub const DATA_EXPORTS_TOTAL: &str = "data_exports_total";
+pub const WEBHOOK_DELIVERY_ATTEMPTS_TOTAL: &str = "webhook_delivery_attempts_total";
+pub const ACCOUNT_EVENT_STREAMS: &str = "account_event_streams";
+pub const SCHEDULED_PAYMENT_RUNS_TOTAL: &str = "scheduled_payment_runs_total";
+pub const FEES_CHARGED_TOTAL: &str = "fees_charged_total";
+pub const FEE_VOLUME_TOTAL: &str = "fee_volume_minor_units_total";
+pub const LIMIT_REJECTIONS_TOTAL: &str = "transaction_limit_rejections_total";
+pub const RISK_DECISIONS_TOTAL: &str = "risk_decisions_total";
+pub const RISK_REVIEWS_TOTAL: &str = "risk_reviews_total";
+pub const SCREENINGS_TOTAL: &str = "sanctions_screenings_total";
+pub const SCREENING_REVIEWS_TOTAL: &str = "sanctions_screening_reviews_total";
+pub const KYC_APPLICATIONS_TOTAL: &str = "kyc_applications_total";
+pub const KYC_CAP_REJECTIONS_TOTAL: &str = "kyc_tier_cap_rejections_total";
+pub const AML_CASES_OPENED_TOTAL: &str = "aml_cases_opened_total";
+pub const AML_CASE_STATUS_CHANGES_TOTAL: &str = "aml_case_status_changes_total";
+pub const APPROVAL_REQUESTS_TOTAL: &str = "approval_requests_total";
+
+const HTTP_LATENCY_BUCKETS: &[f64] = &[
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+// Installs the global Prometheus recorder. The returned handle renders the
+// scrape output for `GET /metrics`.
+pub fn install() -> PrometheusHandle {
+    PrometheusBuilder::new()
+        .set_buckets_for_metric(
+            Matcher::Full(HTTP_REQUEST_DURATION_SECONDS.to_string()),
+            HTTP_LATENCY_BUCKETS,
+        )
+        .expect("Invalid histogram buckets")
+        .install_recorder()
+        .expect("Failed to install metrics recorder")
+}
+
+pub fn record_http_request(method: &str, route: &str, status: u16, elapsed: Duration) {
+    let labels = [
+        ("method", method.to_string()),
+        ("route", route.to_string()),
+        ("status", status.to_string()),
+    ];
+
+    counter!(HTTP_REQUESTS_TOTAL, &labels).increment(1);
+    histogram!(HTTP_REQUEST_DURATION_SECONDS, &labels).record(elapsed.as_secs_f64());
+}
+
+pub fn record_pool_status(status: deadpool_postgres::Status) {
+    gauge!(DB_POOL_MAX_SIZE).set(status.max_size as f64);
+    gauge!(DB_POOL_SIZE).set(status.size as f64);
+    gauge!(DB_POOL_AVAILABLE).set(status.available as f64);
+    gauge!(DB_POOL_WAITING).set(status.waiting as f64);
+}
+
+pub fn record_rate_limit_rejection() {
+    counter!(RATE_LIMIT_REJECTIONS_TOTAL).increment(1);
+}
+
+pub fn record_deposit(currency: &str, amount: Decimal) {
+    counter!(DEPOSITS_TOTAL, "currency" => currency.to_string()).increment(1);
+    counter!(DEPOSIT_VOLUME_TOTAL, "currency" => currency.to_string()).increment(volume(amount));
+}
+
+pub fn record_withdrawal(currency: &str, amount: Decimal) {
+    counter!(WITHDRAWALS_TOTAL, "currency" => currency.to_string()).increment(1);
+    counter!(WITHDRAWAL_VOLUME_TOTAL, "currency" => currency.to_string()).increment(volume(amount));
+}
+
+pub fn record_insufficient_balance(currency: &str) {
+    counter!(WITHDRAWALS_INSUFFICIENT_BALANCE_TOTAL, "currency" => currency.to_string()).increment(1);
+}
+
+pub fn record_reconciliation_line(status: &str) {
+    counter!(RECONCILIATION_LINES_TOTAL, "status" => status.to_string()).increment(1);
+}
+
+pub fn record_balance_snapshots(count: u64) {
+    counter!(BALANCE_SNAPSHOTS_TOTAL).increment(count);
+}
+
+pub fn record_data_export(status: &str) {
+    counter!(DATA_EXPORTS_TOTAL, "status" => status.to_string()).increment(1);
+}
+
+// `outcome` is `delivered`, `retry` or `dead`.
+pub fn record_webhook_attempt(outcome: &str) {
+    counter!(WEBHOOK_DELIVERY_ATTEMPTS_TOTAL, "outcome" => outcome.to_string()).increment(1);
+}
+
+// Open SSE and WebSocket streams of account events on this replica.
+pub fn record_account_event_stream_opened() {
+    gauge!(ACCOUNT_EVENT_STREAMS).increment(1.0);
+}
+
+pub fn record_account_event_stream_closed() {
+    gauge!(ACCOUNT_EVENT_STREAMS).decrement(1.0);
+}
+
+// `status` is `SUCCEEDED`, `FAILED` or `IN_REVIEW`.
+pub fn record_scheduled_payment_run(status: &str) {
+    counter!(SCHEDULED_PAYMENT_RUNS_TOTAL, "status" => status.to_string()).increment(1);
+}
+
+// `transaction_type` is `DEPOSIT`, `WITHDRAWAL` or `TRANSFER`.
+pub fn record_fee(currency: &str, transaction_type: &str, amount: Decimal) {
+    let labels = [("currency", currency.to_string()), ("transaction_type", transaction_type.to_string())];
+
+    counter!(FEES_CHARGED_TOTAL, &labels).increment(1);
+    counter!(FEE_VOLUME_TOTAL, &labels).increment(volume(amount));
+}
+
+// `limit` is the limit that refused the debit, e.g. `daily_debit_limit`.
+pub fn record_limit_rejection(limit: &str) {
+    counter!(LIMIT_REJECTIONS_TOTAL, "limit" => limit.to_string()).increment(1);
+}
+
+// `action` is `ALLOW`, `REVIEW` or `BLOCK`.
+pub fn record_risk_decision(action: &str) {
+    counter!(RISK_DECISIONS_TOTAL, "action" => action.to_string()).increment(1);
+}
+
+// `status` is `APPROVED` or `REJECTED`.
+pub fn record_risk_review(status: &str) {
+    counter!(RISK_REVIEWS_TOTAL, "status" => status.to_string()).increment(1);
+}
+
+// `trigger` is `SIGNUP`, `NAME_CHANGE`, `RESCREEN` or `WITHDRAWAL`; `decision`
+// is `CLEAR`, `REVIEW` or `BLOCK`.
+pub fn record_screening(trigger: &str, decision: &str) {
+    let labels = [("trigger", trigger.to_string()), ("decision", decision.to_string())];
+    counter!(SCREENINGS_TOTAL, &labels).increment(1);
+}
+
+// `status` is `CLEARED` or `CONFIRMED`.
+pub fn record_screening_review(status: &str) {
+    counter!(SCREENING_REVIEWS_TOTAL, "status" => status.to_string()).increment(1);
+}
+
+// `status` is `PENDING` when submitted, then `APPROVED` or `REJECTED`.
+pub fn record_kyc_application(tier: &str, status: &str) {
+    let labels = [("tier", tier.to_string()), ("status", status.to_string())];
+    counter!(KYC_APPLICATIONS_TOTAL, &labels).increment(1);
+}
+
+// `cap` is the cap that refused the operation, e.g. `max_balance`.
+pub fn record_kyc_cap_rejection(tier: &str, cap: &str) {
+    let labels = [("tier", tier.to_string()), ("cap", cap.to_string())];
+    counter!(KYC_CAP_REJECTIONS_TOTAL, &labels).increment(1);
+}
+
+// `scenario` is e.g. `STRUCTURING`.
+pub fn record_aml_case_opened(scenario: &str) {
+    counter!(AML_CASES_OPENED_TOTAL, "scenario" => scenario.to_string()).increment(1);
+}
+
+// `status` is `INVESTIGATING`, `DISMISSED` or `REPORTED`.
+pub fn record_aml_case_status(status: &str) {
+    counter!(AML_CASE_STATUS_CHANGES_TOTAL, "status" => status.to_string()).increment(1);
+}
+
+// `status` is `PENDING` when submitted, then the status it was decided with.
+pub fn record_approval_request(operation: &str, status: &str) {
+    let labels = [("operation", operation.to_string()), ("status", status.to_string())];
+    counter!(APPROVAL_REQUESTS_TOTAL, &labels).increment(1);
+}
+
+// Volumes are exported in minor units (paise, cents) so they fit an integer counter.
+fn volume(amount: Decimal) -> u64 {
+    (amount * Decimal::from(100)).trunc().to_u64().unwrap_or(0)
+}
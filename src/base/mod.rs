@@ -3,3 +3,18 @@ pub mod error;
 pub mod utils;
 pub mod constants;
 pub mod models;
+pub mod metrics;
+pub mod telemetry;
+pub mod validation;
+pub mod pagination;
+pub mod statements;
+pub mod reconciliation;
+pub mod exports;
+pub mod audit;
+pub mod webhooks;
+pub mod schedules;
+pub mod fees;
+pub mod limits;
+pub mod risk;
+pub mod screening;
+pub mod aml;
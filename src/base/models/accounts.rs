@@ -2,8 +2,49 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use tokio_postgres::Row;
 use std::convert::TryFrom;
+use std::fmt;
 use uuid::Uuid;
 use rust_decimal::Decimal;
+use validator::Validate;
+use serde_json::Value;
+use validator::ValidationError;
+use crate::base::{
+    constants::{MAX_COUNTERPARTY_NAME_LENGTH, MAX_DESCRIPTION_LENGTH, MAX_PAGE_SIZE},
+    error::AppError,
+    pagination::{sort_field_in, Cursor, CursorValue, Keyset, SortField, SortOrder},
+    validation::{currency_code, metadata_object, non_negative_amount, not_blank, positive_amount, tag_list},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum AccountStatus {
+    #[default]
+    Active,
+    // Can receive funds but not send them
+    Frozen,
+    // Read-only, kept for its history
+    Closed,
+}
+
+impl AccountStatus {
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "FROZEN" => AccountStatus::Frozen,
+            "CLOSED" => AccountStatus::Closed,
+            _ => AccountStatus::Active,
+        }
+    }
+}
+
+impl fmt::Display for AccountStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AccountStatus::Active => write!(f, "ACTIVE"),
+            AccountStatus::Frozen => write!(f, "FROZEN"),
+            AccountStatus::Closed => write!(f, "CLOSED"),
+        }
+    }
+}
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Account {
@@ -11,10 +52,31 @@ pub struct Account {
     pub user_id: Uuid,
     pub balance: Decimal,
     pub currency: String,
+    pub status: AccountStatus,
+    pub closed_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
+impl Account {
+    // Deposits are accepted unless the account is closed.
+    pub fn ensure_can_credit(&self) -> Result<(), AppError> {
+        match self.status {
+            AccountStatus::Closed => Err(AppError::AccountClosed),
+            _ => Ok(()),
+        }
+    }
+
+    // Withdrawals and payouts need an active account.
+    pub fn ensure_can_debit(&self) -> Result<(), AppError> {
+        match self.status {
+            AccountStatus::Active => Ok(()),
+            AccountStatus::Frozen => Err(AppError::AccountFrozen),
+            AccountStatus::Closed => Err(AppError::AccountClosed),
+        }
+    }
+}
+
 impl TryFrom<Row> for Account {
     type Error = tokio_postgres::Error;
 
@@ -24,39 +86,107 @@ impl TryFrom<Row> for Account {
             user_id: row.get("user_id"),
             balance: row.get::<_, Decimal>("balance"),
             currency: row.get("currency"),
+            status: AccountStatus::parse(row.get("status")),
+            closed_at: row.get("closed_at"),
             created_at: row.get("created_at"),
             updated_at: row.get("updated_at"),
         })
     }
 }
 
-#[derive(Debug, Deserialize)]
+impl Keyset for Account {
+    fn cursor(&self, sort: SortField) -> Cursor {
+        let value = match sort {
+            SortField::Balance => CursorValue::Decimal(self.balance),
+            _ => CursorValue::Timestamp(self.created_at),
+        };
+        Cursor { value, id: self.id }
+    }
+}
+
+#[derive(Debug, Deserialize, Validate)]
 pub struct CreateAccountRequest {
+    // Always replaced with the authenticated user
+    #[serde(default)]
     pub user_id: Uuid,
+    #[validate(custom(function = "currency_code"))]
     pub currency: Option<String>,
+    #[validate(custom(function = "non_negative_amount"))]
     pub initial_balance: Option<Decimal>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Validate)]
 pub struct UpdateAccountRequest {
+    #[validate(custom(function = "currency_code"))]
     pub currency: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Validate)]
 pub struct DepositRequest {
+    #[validate(custom(function = "positive_amount"))]
     pub amount: Decimal,
+    #[validate(length(max = MAX_DESCRIPTION_LENGTH, code = "MAX_LENGTH", message = "Description must be at most 500 characters"))]
     pub description: Option<String>,
+    #[validate(custom(function = "metadata_object"))]
+    pub metadata: Option<Value>,
+    #[validate(custom(function = "tag_list"))]
+    pub tags: Option<Vec<String>>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Validate)]
 pub struct WithdrawalRequest {
+    #[validate(custom(function = "positive_amount"))]
     pub amount: Decimal,
+    #[validate(length(max = MAX_DESCRIPTION_LENGTH, code = "MAX_LENGTH", message = "Description must be at most 500 characters"))]
     pub description: Option<String>,
+    #[validate(custom(function = "metadata_object"))]
+    pub metadata: Option<Value>,
+    #[validate(custom(function = "tag_list"))]
+    pub tags: Option<Vec<String>>,
+    // Who the money is paid out to; screened against the watchlists
+    #[validate(
+        custom(function = "not_blank"),
+        length(max = MAX_COUNTERPARTY_NAME_LENGTH, code = "MAX_LENGTH", message = "Counterparty name must be at most 255 characters")
+    )]
+    pub counterparty_name: Option<String>,
+}
+
+impl WithdrawalRequest {
+    // The metadata stored on the transaction, with the counterparty's name
+    // under `counterparty_name`.
+    pub fn metadata_with_counterparty(&self) -> Option<Value> {
+        let Some(name) = &self.counterparty_name else {
+            return self.metadata.clone();
+        };
+
+        let mut metadata = self.metadata.clone().unwrap_or_else(|| Value::Object(Default::default()));
+        if let Some(object) = metadata.as_object_mut() {
+            object.insert("counterparty_name".into(), Value::String(name.clone()));
+        }
+        Some(metadata)
+    }
+}
+
+#[derive(Debug, Default, Deserialize, Validate)]
+pub struct CloseAccountRequest {
+    // Receives the remaining balance; required unless the balance is zero
+    pub payout_account_id: Option<Uuid>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Validate)]
 pub struct AccountPaginationParams {
-    pub page: Option<i64>,
-    pub per_page: Option<i64>,
+    #[serde(alias = "per_page")]
+    #[validate(range(min = 1, max = MAX_PAGE_SIZE, code = "RANGE", message = "limit must be between 1 and 100"))]
+    pub limit: Option<i64>,
+    pub cursor: Option<String>,
+    #[validate(custom(function = "account_sort_field"))]
+    pub sort: Option<SortField>,
+    pub order: Option<SortOrder>,
+    #[serde(default)]
+    pub include_total: bool,
     pub user_id: Option<Uuid>,
+}
+
+fn account_sort_field(sort: &SortField) -> Result<(), ValidationError> {
+    sort_field_in(sort, &[SortField::CreatedAt, SortField::Balance])
 }
\ No newline at end of file
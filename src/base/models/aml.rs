@@ -0,0 +1,331 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio_postgres::Row;
+use std::convert::TryFrom;
+use std::fmt;
+use uuid::Uuid;
+use validator::Validate;
+use crate::base::{
+    constants::{MAX_PAGE_SIZE, MAX_REVIEW_NOTE_LENGTH},
+    models::transactions::Transaction,
+    pagination::{Cursor, CursorValue, Keyset, SortField, SortOrder},
+    validation::{fee_percentage, positive_amount},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum AmlScenarioCode {
+    // Several deposits just below the reporting threshold
+    Structuring,
+    // Money deposited into an account and withdrawn again shortly after
+    RapidMovement,
+    // A large transaction on an account that had no activity for a long time
+    DormantReactivation,
+}
+
+impl AmlScenarioCode {
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "STRUCTURING" => AmlScenarioCode::Structuring,
+            "RAPID_MOVEMENT" => AmlScenarioCode::RapidMovement,
+            _ => AmlScenarioCode::DormantReactivation,
+        }
+    }
+}
+
+impl fmt::Display for AmlScenarioCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AmlScenarioCode::Structuring => write!(f, "STRUCTURING"),
+            AmlScenarioCode::RapidMovement => write!(f, "RAPID_MOVEMENT"),
+            AmlScenarioCode::DormantReactivation => write!(f, "DORMANT_REACTIVATION"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AmlScenario {
+    pub code: AmlScenarioCode,
+    pub enabled: bool,
+    pub params: Value,
+    pub updated_by: Option<Uuid>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl TryFrom<Row> for AmlScenario {
+    type Error = tokio_postgres::Error;
+
+    fn try_from(row: Row) -> Result<Self, Self::Error> {
+        Ok(AmlScenario {
+            code: AmlScenarioCode::parse(row.get("code")),
+            enabled: row.get("enabled"),
+            params: row.get("params"),
+            updated_by: row.get("updated_by"),
+            updated_at: row.get("updated_at"),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+#[serde(deny_unknown_fields)]
+pub struct StructuringParams {
+    #[validate(custom(function = "positive_amount"))]
+    pub threshold: Decimal,
+    // Deposits within this percentage below the threshold count
+    #[validate(custom(function = "fee_percentage"))]
+    pub margin_percent: Decimal,
+    #[validate(range(min = 2, max = 1000, code = "RANGE", message = "min_count must be between 2 and 1000"))]
+    pub min_count: i64,
+    #[validate(range(min = 1, max = 720, code = "RANGE", message = "window_hours must be between 1 and 720"))]
+    pub window_hours: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+#[serde(deny_unknown_fields)]
+pub struct RapidMovementParams {
+    #[validate(range(min = 1, max = 720, code = "RANGE", message = "window_hours must be between 1 and 720"))]
+    pub window_hours: i64,
+    // Deposits in the window needed before the scenario applies
+    #[validate(custom(function = "positive_amount"))]
+    pub min_amount: Decimal,
+    // Share of the deposits that has to be withdrawn again
+    #[validate(custom(function = "fee_percentage"))]
+    pub min_out_percent: Decimal,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+#[serde(deny_unknown_fields)]
+pub struct DormantReactivationParams {
+    #[validate(range(min = 1, max = 3650, code = "RANGE", message = "dormant_days must be between 1 and 3650"))]
+    pub dormant_days: i32,
+    #[validate(custom(function = "positive_amount"))]
+    pub min_amount: Decimal,
+    #[validate(range(min = 1, max = 720, code = "RANGE", message = "window_hours must be between 1 and 720"))]
+    pub window_hours: i64,
+}
+
+// The parsed `params` of a scenario.
+#[derive(Debug, Clone)]
+pub enum AmlScenarioParams {
+    Structuring(StructuringParams),
+    RapidMovement(RapidMovementParams),
+    DormantReactivation(DormantReactivationParams),
+}
+
+// Fields left out are kept as they are; `params` replaces all of the scenario's params.
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateAmlScenarioRequest {
+    pub enabled: Option<bool>,
+    pub params: Option<Value>,
+}
+
+// Suspicious activity a scenario found in the scanned window. `account_id`
+// is not set for structuring, which looks at all of the user's accounts in
+// the currency.
+#[derive(Debug, Clone)]
+pub struct AmlDetection {
+    pub scenario: AmlScenarioCode,
+    pub user_id: Uuid,
+    pub account_id: Option<Uuid>,
+    pub currency: String,
+    pub total_amount: Decimal,
+    pub transaction_ids: Vec<Uuid>,
+    pub details: Value,
+}
+
+impl AmlDetection {
+    pub fn from_row(scenario: AmlScenarioCode, row: Row) -> Self {
+        AmlDetection {
+            scenario,
+            user_id: row.get("user_id"),
+            account_id: row.get("account_id"),
+            currency: row.get("currency"),
+            total_amount: row.get("total_amount"),
+            transaction_ids: row.get("transaction_ids"),
+            details: row.get("details"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AmlRun {
+    pub id: Uuid,
+    // The admin who started the run; not set for scheduled runs
+    pub triggered_by: Option<Uuid>,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    pub detections: i32,
+    pub cases_opened: i32,
+    pub cases_updated: i32,
+}
+
+impl TryFrom<Row> for AmlRun {
+    type Error = tokio_postgres::Error;
+
+    fn try_from(row: Row) -> Result<Self, Self::Error> {
+        Ok(AmlRun {
+            id: row.get("id"),
+            triggered_by: row.get("triggered_by"),
+            started_at: row.get("started_at"),
+            finished_at: row.get("finished_at"),
+            detections: row.get("detections"),
+            cases_opened: row.get("cases_opened"),
+            cases_updated: row.get("cases_updated"),
+        })
+    }
+}
+
+impl Keyset for AmlRun {
+    fn cursor(&self, _sort: SortField) -> Cursor {
+        Cursor {
+            value: CursorValue::Timestamp(self.started_at),
+            id: self.id,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct AmlRunPaginationParams {
+    #[serde(alias = "per_page")]
+    #[validate(range(min = 1, max = MAX_PAGE_SIZE, code = "RANGE", message = "limit must be between 1 and 100"))]
+    pub limit: Option<i64>,
+    pub cursor: Option<String>,
+    pub order: Option<SortOrder>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum AmlCaseStatus {
+    Open,
+    Investigating,
+    // Looked into and found not to be suspicious
+    Dismissed,
+    // Reported to the financial intelligence unit
+    Reported,
+}
+
+impl AmlCaseStatus {
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "INVESTIGATING" => AmlCaseStatus::Investigating,
+            "DISMISSED" => AmlCaseStatus::Dismissed,
+            "REPORTED" => AmlCaseStatus::Reported,
+            _ => AmlCaseStatus::Open,
+        }
+    }
+
+    pub fn is_closed(self) -> bool {
+        matches!(self, AmlCaseStatus::Dismissed | AmlCaseStatus::Reported)
+    }
+
+    // Cases move forward only: an open case can be taken up or closed, one
+    // under investigation can be closed, and closed cases stay closed.
+    pub fn can_become(self, next: AmlCaseStatus) -> bool {
+        match self {
+            AmlCaseStatus::Open => next != AmlCaseStatus::Open,
+            AmlCaseStatus::Investigating => next.is_closed(),
+            AmlCaseStatus::Dismissed | AmlCaseStatus::Reported => false,
+        }
+    }
+}
+
+impl fmt::Display for AmlCaseStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AmlCaseStatus::Open => write!(f, "OPEN"),
+            AmlCaseStatus::Investigating => write!(f, "INVESTIGATING"),
+            AmlCaseStatus::Dismissed => write!(f, "DISMISSED"),
+            AmlCaseStatus::Reported => write!(f, "REPORTED"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AmlCase {
+    pub id: Uuid,
+    pub scenario: AmlScenarioCode,
+    pub status: AmlCaseStatus,
+    pub user_id: Uuid,
+    pub account_id: Option<Uuid>,
+    pub currency: String,
+    pub total_amount: Decimal,
+    // The transactions the activity was found in, oldest first
+    pub transaction_ids: Vec<Uuid>,
+    pub reason: String,
+    pub details: Value,
+    // The run that opened the case or last added to it
+    pub run_id: Option<Uuid>,
+    pub assigned_to: Option<Uuid>,
+    pub resolved_by: Option<Uuid>,
+    pub resolved_at: Option<DateTime<Utc>>,
+    pub resolution_note: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl TryFrom<Row> for AmlCase {
+    type Error = tokio_postgres::Error;
+
+    fn try_from(row: Row) -> Result<Self, Self::Error> {
+        Ok(AmlCase {
+            id: row.get("id"),
+            scenario: AmlScenarioCode::parse(row.get("scenario")),
+            status: AmlCaseStatus::parse(row.get("status")),
+            user_id: row.get("user_id"),
+            account_id: row.get("account_id"),
+            currency: row.get("currency"),
+            total_amount: row.get("total_amount"),
+            transaction_ids: row.get("transaction_ids"),
+            reason: row.get("reason"),
+            details: row.get("details"),
+            run_id: row.get("run_id"),
+            assigned_to: row.get("assigned_to"),
+            resolved_by: row.get("resolved_by"),
+            resolved_at: row.get("resolved_at"),
+            resolution_note: row.get("resolution_note"),
+            created_at: row.get("created_at"),
+            updated_at: row.get("updated_at"),
+        })
+    }
+}
+
+impl Keyset for AmlCase {
+    fn cursor(&self, _sort: SortField) -> Cursor {
+        Cursor {
+            value: CursorValue::Timestamp(self.created_at),
+            id: self.id,
+        }
+    }
+}
+
+// A case with the transactions it was found in.
+#[derive(Debug, Serialize)]
+pub struct AmlCaseDetail {
+    #[serde(flatten)]
+    pub case: AmlCase,
+    pub transactions: Vec<Transaction>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct AmlCasePaginationParams {
+    #[serde(alias = "per_page")]
+    #[validate(range(min = 1, max = MAX_PAGE_SIZE, code = "RANGE", message = "limit must be between 1 and 100"))]
+    pub limit: Option<i64>,
+    pub cursor: Option<String>,
+    pub order: Option<SortOrder>,
+    pub status: Option<AmlCaseStatus>,
+    pub scenario: Option<AmlScenarioCode>,
+    pub user_id: Option<Uuid>,
+    pub account_id: Option<Uuid>,
+    #[serde(default)]
+    pub include_total: bool,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateAmlCaseStatusRequest {
+    pub status: AmlCaseStatus,
+    #[validate(length(max = MAX_REVIEW_NOTE_LENGTH, code = "MAX_LENGTH", message = "Note must be at most 500 characters"))]
+    pub note: Option<String>,
+}
@@ -0,0 +1,247 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio_postgres::Row;
+use std::convert::TryFrom;
+use std::fmt;
+use uuid::Uuid;
+use validator::Validate;
+use crate::base::{
+    constants::{MAX_APPROVAL_COMMENT_LENGTH, MAX_DESCRIPTION_LENGTH, MAX_PAGE_SIZE},
+    pagination::{Cursor, CursorValue, Keyset, SortField, SortOrder},
+    validation::{currency_code, not_blank, positive_amount},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ApprovalOperation {
+    // A withdrawal above the threshold of its currency
+    Withdrawal,
+    // An admin crediting or debiting an account
+    BalanceAdjustment,
+    // The holder closing their account
+    AccountClosure,
+}
+
+impl ApprovalOperation {
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "WITHDRAWAL" => ApprovalOperation::Withdrawal,
+            "BALANCE_ADJUSTMENT" => ApprovalOperation::BalanceAdjustment,
+            _ => ApprovalOperation::AccountClosure,
+        }
+    }
+}
+
+impl fmt::Display for ApprovalOperation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApprovalOperation::Withdrawal => write!(f, "WITHDRAWAL"),
+            ApprovalOperation::BalanceAdjustment => write!(f, "BALANCE_ADJUSTMENT"),
+            ApprovalOperation::AccountClosure => write!(f, "ACCOUNT_CLOSURE"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum ApprovalStatus {
+    Pending,
+    Approved,
+    Rejected,
+    // Withdrawn by the user who asked for it
+    Cancelled,
+    // Not decided in time
+    Expired,
+}
+
+impl ApprovalStatus {
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "APPROVED" => ApprovalStatus::Approved,
+            "REJECTED" => ApprovalStatus::Rejected,
+            "CANCELLED" => ApprovalStatus::Cancelled,
+            "EXPIRED" => ApprovalStatus::Expired,
+            _ => ApprovalStatus::Pending,
+        }
+    }
+}
+
+impl fmt::Display for ApprovalStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApprovalStatus::Pending => write!(f, "PENDING"),
+            ApprovalStatus::Approved => write!(f, "APPROVED"),
+            ApprovalStatus::Rejected => write!(f, "REJECTED"),
+            ApprovalStatus::Cancelled => write!(f, "CANCELLED"),
+            ApprovalStatus::Expired => write!(f, "EXPIRED"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ApprovalRequest {
+    pub id: Uuid,
+    pub operation: ApprovalOperation,
+    pub status: ApprovalStatus,
+    pub account_id: Uuid,
+    pub requested_by: Option<Uuid>,
+    pub amount: Option<Decimal>,
+    // The transaction an approved withdrawal or adjustment booked
+    pub transaction_id: Option<Uuid>,
+    pub details: Value,
+    pub decided_by: Option<Uuid>,
+    pub decided_at: Option<DateTime<Utc>>,
+    pub decision_note: Option<String>,
+    pub expires_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl TryFrom<Row> for ApprovalRequest {
+    type Error = tokio_postgres::Error;
+
+    fn try_from(row: Row) -> Result<Self, Self::Error> {
+        Ok(ApprovalRequest {
+            id: row.get("id"),
+            operation: ApprovalOperation::parse(row.get("operation")),
+            status: ApprovalStatus::parse(row.get("status")),
+            account_id: row.get("account_id"),
+            requested_by: row.get("requested_by"),
+            amount: row.get("amount"),
+            transaction_id: row.get("transaction_id"),
+            details: row.get("details"),
+            decided_by: row.get("decided_by"),
+            decided_at: row.get("decided_at"),
+            decision_note: row.get("decision_note"),
+            expires_at: row.get("expires_at"),
+            created_at: row.get("created_at"),
+            updated_at: row.get("updated_at"),
+        })
+    }
+}
+
+impl Keyset for ApprovalRequest {
+    fn cursor(&self, _sort: SortField) -> Cursor {
+        Cursor {
+            value: CursorValue::Timestamp(self.created_at),
+            id: self.id,
+        }
+    }
+}
+
+// What is stored when an operation is submitted for approval.
+#[derive(Debug, Clone)]
+pub struct NewApprovalRequest {
+    pub operation: ApprovalOperation,
+    pub account_id: Uuid,
+    pub requested_by: Uuid,
+    pub amount: Option<Decimal>,
+    pub details: Value,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ApprovalComment {
+    pub id: Uuid,
+    pub request_id: Uuid,
+    pub author_id: Option<Uuid>,
+    pub body: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl TryFrom<Row> for ApprovalComment {
+    type Error = tokio_postgres::Error;
+
+    fn try_from(row: Row) -> Result<Self, Self::Error> {
+        Ok(ApprovalComment {
+            id: row.get("id"),
+            request_id: row.get("request_id"),
+            author_id: row.get("author_id"),
+            body: row.get("body"),
+            created_at: row.get("created_at"),
+        })
+    }
+}
+
+// A request with its comments, oldest first.
+#[derive(Debug, Serialize)]
+pub struct ApprovalRequestDetail {
+    #[serde(flatten)]
+    pub request: ApprovalRequest,
+    pub comments: Vec<ApprovalComment>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct ApprovalRequestPaginationParams {
+    #[serde(alias = "per_page")]
+    #[validate(range(min = 1, max = MAX_PAGE_SIZE, code = "RANGE", message = "limit must be between 1 and 100"))]
+    pub limit: Option<i64>,
+    pub cursor: Option<String>,
+    pub order: Option<SortOrder>,
+    pub status: Option<ApprovalStatus>,
+    pub operation: Option<ApprovalOperation>,
+    pub account_id: Option<Uuid>,
+    pub requested_by: Option<Uuid>,
+    #[serde(default)]
+    pub include_total: bool,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateApprovalCommentRequest {
+    #[validate(
+        length(min = 1, max = MAX_APPROVAL_COMMENT_LENGTH, code = "LENGTH", message = "body must be between 1 and 1000 characters"),
+        custom(function = "not_blank")
+    )]
+    pub body: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum AdjustmentDirection {
+    Credit,
+    Debit,
+}
+
+// An admin correction of an account's balance, booked once approved.
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct BalanceAdjustmentRequest {
+    pub direction: AdjustmentDirection,
+    #[validate(custom(function = "positive_amount"))]
+    pub amount: Decimal,
+    #[validate(
+        length(min = 1, max = MAX_DESCRIPTION_LENGTH, code = "LENGTH", message = "reason must be between 1 and 500 characters"),
+        custom(function = "not_blank")
+    )]
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ApprovalThreshold {
+    pub currency: String,
+    pub withdrawal_threshold: Decimal,
+    pub updated_by: Option<Uuid>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl TryFrom<Row> for ApprovalThreshold {
+    type Error = tokio_postgres::Error;
+
+    fn try_from(row: Row) -> Result<Self, Self::Error> {
+        Ok(ApprovalThreshold {
+            currency: row.get("currency"),
+            withdrawal_threshold: row.get("withdrawal_threshold"),
+            updated_by: row.get("updated_by"),
+            updated_at: row.get("updated_at"),
+        })
+    }
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct SetApprovalThresholdRequest {
+    #[validate(custom(function = "currency_code"))]
+    pub currency: String,
+    // Withdrawals above this amount need approval
+    #[validate(custom(function = "positive_amount"))]
+    pub withdrawal_threshold: Decimal,
+}
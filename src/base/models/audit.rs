@@ -0,0 +1,210 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio_postgres::Row;
+use std::convert::TryFrom;
+use uuid::Uuid;
+use validator::Validate;
+use crate::base::{
+    constants::{MAX_PAGE_SIZE, MAX_SEARCH_LENGTH},
+    pagination::{Cursor, CursorValue, Keyset, SortField, SortOrder},
+};
+
+// Something an actor did to a record, written to `audit_log`. `details` and
+// the before/after values must not contain PII, since audit entries outlive
+// erasure and can't be changed.
+#[derive(Debug)]
+pub struct NewAuditEntry {
+    pub actor_id: Option<Uuid>,
+    pub action: &'static str,
+    pub target_type: &'static str,
+    pub target_id: Option<Uuid>,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+    pub details: Value,
+}
+
+impl NewAuditEntry {
+    pub fn new(actor_id: Option<Uuid>, action: &'static str, target_type: &'static str, target_id: Option<Uuid>) -> Self {
+        Self {
+            actor_id,
+            action,
+            target_type,
+            target_id,
+            before: None,
+            after: None,
+            details: json!({}),
+        }
+    }
+
+    pub fn before(mut self, value: &impl Serialize) -> Self {
+        self.before = serde_json::to_value(value).ok();
+        self
+    }
+
+    pub fn after(mut self, value: &impl Serialize) -> Self {
+        self.after = serde_json::to_value(value).ok();
+        self
+    }
+
+    pub fn details(mut self, details: Value) -> Self {
+        self.details = details;
+        self
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct AuditEntry {
+    pub id: Uuid,
+    pub seq: i64,
+    pub actor_id: Option<Uuid>,
+    pub action: String,
+    pub target_type: String,
+    pub target_id: Option<Uuid>,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+    pub details: Value,
+    pub request_id: Option<String>,
+    pub ip_address: Option<String>,
+    pub prev_hash: String,
+    pub hash: String,
+    pub created_at: DateTime<Utc>,
+}
+
+impl TryFrom<Row> for AuditEntry {
+    type Error = tokio_postgres::Error;
+
+    fn try_from(row: Row) -> Result<Self, Self::Error> {
+        Ok(AuditEntry {
+            id: row.get("id"),
+            seq: row.get("seq"),
+            actor_id: row.get("actor_id"),
+            action: row.get("action"),
+            target_type: row.get("target_type"),
+            target_id: row.get("target_id"),
+            before: row.get("before"),
+            after: row.get("after"),
+            details: row.get("details"),
+            request_id: row.get("request_id"),
+            ip_address: row.get("ip_address"),
+            prev_hash: row.get("prev_hash"),
+            hash: row.get("hash"),
+            created_at: row.get("created_at"),
+        })
+    }
+}
+
+impl Keyset for AuditEntry {
+    fn cursor(&self, _sort: SortField) -> Cursor {
+        Cursor {
+            value: CursorValue::Timestamp(self.created_at),
+            id: self.id,
+        }
+    }
+}
+
+// An entry as it is hashed: JSON values in the text form Postgres stores them
+// in, so the hash doesn't depend on how a client re-serializes them.
+#[derive(Debug)]
+pub struct AuditChainLink {
+    pub seq: i64,
+    pub prev_hash: String,
+    pub id: Uuid,
+    pub created_at: DateTime<Utc>,
+    pub actor_id: Option<Uuid>,
+    pub action: String,
+    pub target_type: String,
+    pub target_id: Option<Uuid>,
+    pub before: Option<String>,
+    pub after: Option<String>,
+    pub details: String,
+    pub request_id: Option<String>,
+    pub ip_address: Option<String>,
+    pub hash: String,
+}
+
+impl TryFrom<Row> for AuditChainLink {
+    type Error = tokio_postgres::Error;
+
+    fn try_from(row: Row) -> Result<Self, Self::Error> {
+        Ok(AuditChainLink {
+            seq: row.get("seq"),
+            prev_hash: row.get("prev_hash"),
+            id: row.get("id"),
+            created_at: row.get("created_at"),
+            actor_id: row.get("actor_id"),
+            action: row.get("action"),
+            target_type: row.get("target_type"),
+            target_id: row.get("target_id"),
+            before: row.get("before"),
+            after: row.get("after"),
+            details: row.get("details"),
+            request_id: row.get("request_id"),
+            ip_address: row.get("ip_address"),
+            hash: row.get("hash"),
+        })
+    }
+}
+
+// Outcome of `verify-audit-log`. `head_seq` and `head_hash` identify the
+// newest entry; recording them elsewhere also makes truncation detectable.
+#[derive(Debug, Serialize)]
+pub struct ChainReport {
+    pub entries: i64,
+    pub head_seq: Option<i64>,
+    pub head_hash: Option<String>,
+    pub first_broken_seq: Option<i64>,
+    pub problem: Option<String>,
+}
+
+impl ChainReport {
+    pub fn is_intact(&self) -> bool {
+        self.problem.is_none()
+    }
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct AuditLogParams {
+    #[serde(alias = "per_page")]
+    #[validate(range(min = 1, max = MAX_PAGE_SIZE, code = "RANGE", message = "limit must be between 1 and 100"))]
+    pub limit: Option<i64>,
+    pub cursor: Option<String>,
+    pub order: Option<SortOrder>,
+    pub actor_id: Option<Uuid>,
+    #[validate(length(min = 1, max = MAX_SEARCH_LENGTH, code = "LENGTH", message = "action must be between 1 and 200 characters"))]
+    pub action: Option<String>,
+    #[validate(length(min = 1, max = MAX_SEARCH_LENGTH, code = "LENGTH", message = "target_type must be between 1 and 200 characters"))]
+    pub target_type: Option<String>,
+    pub target_id: Option<Uuid>,
+    pub request_id: Option<String>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub include_total: bool,
+}
+
+// Filters of an audit log query.
+#[derive(Debug, Default)]
+pub struct AuditLogFilter {
+    pub actor_id: Option<Uuid>,
+    pub action: Option<String>,
+    pub target_type: Option<String>,
+    pub target_id: Option<Uuid>,
+    pub request_id: Option<String>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+}
+
+impl From<&AuditLogParams> for AuditLogFilter {
+    fn from(params: &AuditLogParams) -> Self {
+        Self {
+            actor_id: params.actor_id,
+            action: params.action.clone(),
+            target_type: params.target_type.clone(),
+            target_id: params.target_id,
+            request_id: params.request_id.clone(),
+            from: params.from,
+            to: params.to,
+        }
+    }
+}
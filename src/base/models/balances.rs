@@ -0,0 +1,47 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use tokio_postgres::Row;
+use std::convert::TryFrom;
+use uuid::Uuid;
+use validator::{Validate, ValidationError};
+
+// Balance of an account at a point in time: the latest end-of-day snapshot
+// taken before `at` plus the transactions since, failed ones excepted.
+#[derive(Debug, Clone, Serialize)]
+pub struct AccountBalance {
+    pub account_id: Uuid,
+    pub currency: String,
+    pub at: DateTime<Utc>,
+    pub balance: Decimal,
+    // Day of the snapshot the balance was built on, if any
+    pub snapshot_date: Option<NaiveDate>,
+}
+
+impl TryFrom<Row> for AccountBalance {
+    type Error = tokio_postgres::Error;
+
+    fn try_from(row: Row) -> Result<Self, Self::Error> {
+        Ok(AccountBalance {
+            account_id: row.get("account_id"),
+            currency: row.get("currency"),
+            at: row.get("at"),
+            balance: row.get("balance"),
+            snapshot_date: row.get("snapshot_date"),
+        })
+    }
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct BalanceParams {
+    // Defaults to the time of the request
+    #[validate(custom(function = "not_in_future"))]
+    pub at: Option<DateTime<Utc>>,
+}
+
+fn not_in_future(at: &DateTime<Utc>) -> Result<(), ValidationError> {
+    if *at > Utc::now() {
+        return Err(ValidationError::new("RANGE").with_message("at can't be in the future".into()));
+    }
+    Ok(())
+}
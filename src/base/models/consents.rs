@@ -0,0 +1,69 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio_postgres::Row;
+use std::convert::TryFrom;
+use std::fmt;
+use uuid::Uuid;
+use validator::Validate;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ConsentPurpose {
+    Marketing,
+    Analytics,
+    ThirdPartySharing,
+}
+
+impl ConsentPurpose {
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "ANALYTICS" => ConsentPurpose::Analytics,
+            "THIRD_PARTY_SHARING" => ConsentPurpose::ThirdPartySharing,
+            _ => ConsentPurpose::Marketing,
+        }
+    }
+}
+
+impl fmt::Display for ConsentPurpose {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConsentPurpose::Marketing => write!(f, "MARKETING"),
+            ConsentPurpose::Analytics => write!(f, "ANALYTICS"),
+            ConsentPurpose::ThirdPartySharing => write!(f, "THIRD_PARTY_SHARING"),
+        }
+    }
+}
+
+// One consent decision. Decisions are never updated; the latest one per
+// purpose is in effect.
+#[derive(Debug, Serialize)]
+pub struct Consent {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub purpose: ConsentPurpose,
+    pub granted: bool,
+    pub ip_address: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl TryFrom<Row> for Consent {
+    type Error = tokio_postgres::Error;
+
+    fn try_from(row: Row) -> Result<Self, Self::Error> {
+        let purpose: String = row.get("purpose");
+        Ok(Consent {
+            id: row.get("id"),
+            user_id: row.get("user_id"),
+            purpose: ConsentPurpose::parse(&purpose),
+            granted: row.get("granted"),
+            ip_address: row.get("ip_address"),
+            created_at: row.get("created_at"),
+        })
+    }
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct RecordConsentRequest {
+    pub purpose: ConsentPurpose,
+    pub granted: bool,
+}
@@ -0,0 +1,41 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio_postgres::Row;
+use std::convert::TryFrom;
+use uuid::Uuid;
+use validator::Validate;
+
+// A change to an account as pushed to its event stream. `data` has the same
+// shape as the webhook payload of the event type.
+#[derive(Debug, Clone, Serialize)]
+pub struct AccountEvent {
+    pub id: i64,
+    pub account_id: Uuid,
+    #[serde(rename = "type")]
+    pub event_type: String,
+    pub data: Value,
+    pub created_at: DateTime<Utc>,
+}
+
+impl TryFrom<Row> for AccountEvent {
+    type Error = tokio_postgres::Error;
+
+    fn try_from(row: Row) -> Result<Self, Self::Error> {
+        Ok(AccountEvent {
+            id: row.get("id"),
+            account_id: row.get("account_id"),
+            event_type: row.get("event_type"),
+            data: row.get("payload"),
+            created_at: row.get("created_at"),
+        })
+    }
+}
+
+// Where a WebSocket client resumes; SSE clients send the `Last-Event-ID`
+// header instead, which takes precedence.
+#[derive(Debug, Deserialize, Validate)]
+pub struct AccountEventParams {
+    #[validate(range(min = 0))]
+    pub last_event_id: Option<i64>,
+}
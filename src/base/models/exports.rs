@@ -0,0 +1,86 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio_postgres::Row;
+use std::convert::TryFrom;
+use std::fmt;
+use uuid::Uuid;
+use validator::Validate;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum ExportStatus {
+    Pending,
+    // Claimed by a worker
+    Running,
+    // The archive can be downloaded until the export expires
+    Ready,
+    Failed,
+    // The archive has been deleted
+    Expired,
+}
+
+impl ExportStatus {
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "RUNNING" => ExportStatus::Running,
+            "READY" => ExportStatus::Ready,
+            "FAILED" => ExportStatus::Failed,
+            "EXPIRED" => ExportStatus::Expired,
+            _ => ExportStatus::Pending,
+        }
+    }
+}
+
+impl fmt::Display for ExportStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExportStatus::Pending => write!(f, "PENDING"),
+            ExportStatus::Running => write!(f, "RUNNING"),
+            ExportStatus::Ready => write!(f, "READY"),
+            ExportStatus::Failed => write!(f, "FAILED"),
+            ExportStatus::Expired => write!(f, "EXPIRED"),
+        }
+    }
+}
+
+// A personal data export. The archive itself is only read by the download
+// endpoint; `download_url` is signed on every read while the export is READY.
+#[derive(Debug, Serialize)]
+pub struct DataExport {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub status: ExportStatus,
+    pub size_bytes: Option<i64>,
+    pub error: Option<String>,
+    pub download_url: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl TryFrom<Row> for DataExport {
+    type Error = tokio_postgres::Error;
+
+    fn try_from(row: Row) -> Result<Self, Self::Error> {
+        let status: String = row.get("status");
+        Ok(DataExport {
+            id: row.get("id"),
+            user_id: row.get("user_id"),
+            status: ExportStatus::parse(&status),
+            size_bytes: row.get("size_bytes"),
+            error: row.get("error"),
+            download_url: None,
+            created_at: row.get("created_at"),
+            completed_at: row.get("completed_at"),
+            expires_at: row.get("expires_at"),
+        })
+    }
+}
+
+// Query string of a signed download link.
+#[derive(Debug, Deserialize, Validate)]
+pub struct ExportDownloadParams {
+    pub expires: i64,
+    #[validate(length(equal = 64, code = "LENGTH", message = "signature must be 64 hex characters"))]
+    pub signature: String,
+}
@@ -0,0 +1,225 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use tokio_postgres::Row;
+use std::convert::TryFrom;
+use std::fmt;
+use uuid::Uuid;
+use validator::{Validate, ValidationError};
+use crate::base::{
+    constants::{MAX_FEE_SCHEDULE_NAME_LENGTH, MAX_FEE_TIERS, MAX_PAGE_SIZE},
+    models::accounts::Account,
+    pagination::{Cursor, CursorValue, Keyset, SortField, SortOrder},
+    validation::{currency_code, fee_percentage, field_error, non_negative_amount, not_blank, positive_amount},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum FeeTransactionType {
+    Deposit,
+    Withdrawal,
+    // Movements between two accounts, such as scheduled payments
+    Transfer,
+}
+
+impl FeeTransactionType {
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "DEPOSIT" => FeeTransactionType::Deposit,
+            "TRANSFER" => FeeTransactionType::Transfer,
+            _ => FeeTransactionType::Withdrawal,
+        }
+    }
+}
+
+impl fmt::Display for FeeTransactionType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FeeTransactionType::Deposit => write!(f, "DEPOSIT"),
+            FeeTransactionType::Withdrawal => write!(f, "WITHDRAWAL"),
+            FeeTransactionType::Transfer => write!(f, "TRANSFER"),
+        }
+    }
+}
+
+// Rates for amounts up to and including `up_to`; the last tier has no bound.
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct FeeTier {
+    #[validate(custom(function = "positive_amount"))]
+    pub up_to: Option<Decimal>,
+    #[serde(default)]
+    #[validate(custom(function = "non_negative_amount"))]
+    pub flat_amount: Decimal,
+    #[serde(default)]
+    #[validate(custom(function = "fee_percentage"))]
+    pub percentage: Decimal,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct FeeSchedule {
+    pub id: Uuid,
+    pub name: String,
+    pub transaction_type: FeeTransactionType,
+    pub currency: String,
+    pub flat_amount: Decimal,
+    pub percentage: Decimal,
+    pub tiers: Vec<FeeTier>,
+    pub min_fee: Option<Decimal>,
+    pub max_fee: Option<Decimal>,
+    pub revenue_account_id: Uuid,
+    pub active: bool,
+    pub created_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub deactivated_at: Option<DateTime<Utc>>,
+}
+
+impl TryFrom<Row> for FeeSchedule {
+    type Error = tokio_postgres::Error;
+
+    fn try_from(row: Row) -> Result<Self, Self::Error> {
+        // Tiers are validated before they are stored
+        let tiers = serde_json::from_value(row.get("tiers")).unwrap_or_default();
+        Ok(FeeSchedule {
+            id: row.get("id"),
+            name: row.get("name"),
+            transaction_type: FeeTransactionType::parse(row.get("transaction_type")),
+            currency: row.get("currency"),
+            flat_amount: row.get("flat_amount"),
+            percentage: row.get("percentage"),
+            tiers,
+            min_fee: row.get("min_fee"),
+            max_fee: row.get("max_fee"),
+            revenue_account_id: row.get("revenue_account_id"),
+            active: row.get("active"),
+            created_by: row.get("created_by"),
+            created_at: row.get("created_at"),
+            deactivated_at: row.get("deactivated_at"),
+        })
+    }
+}
+
+impl Keyset for FeeSchedule {
+    fn cursor(&self, _sort: SortField) -> Cursor {
+        Cursor {
+            value: CursorValue::Timestamp(self.created_at),
+            id: self.id,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Validate)]
+#[validate(schema(function = "fee_schedule_rules"))]
+pub struct CreateFeeScheduleRequest {
+    #[validate(
+        length(min = 1, max = MAX_FEE_SCHEDULE_NAME_LENGTH, code = "LENGTH", message = "name must be between 1 and 100 characters"),
+        custom(function = "not_blank")
+    )]
+    pub name: String,
+    pub transaction_type: FeeTransactionType,
+    #[validate(custom(function = "currency_code"))]
+    pub currency: String,
+    #[serde(default)]
+    #[validate(custom(function = "non_negative_amount"))]
+    pub flat_amount: Decimal,
+    #[serde(default)]
+    #[validate(custom(function = "fee_percentage"))]
+    pub percentage: Decimal,
+    // When given, the tier rates replace `flat_amount` and `percentage`
+    #[serde(default)]
+    #[validate(nested)]
+    pub tiers: Vec<FeeTier>,
+    #[validate(custom(function = "non_negative_amount"))]
+    pub min_fee: Option<Decimal>,
+    #[validate(custom(function = "non_negative_amount"))]
+    pub max_fee: Option<Decimal>,
+    pub revenue_account_id: Uuid,
+}
+
+fn fee_schedule_rules(request: &CreateFeeScheduleRequest) -> Result<(), ValidationError> {
+    if request.tiers.len() > MAX_FEE_TIERS {
+        return Err(field_error("tiers", "MAX_ITEMS", "At most 10 tiers are allowed"));
+    }
+    if let Some((last, bounded)) = request.tiers.split_last() {
+        if last.up_to.is_some() {
+            return Err(field_error("tiers", "TIERS", "The last tier must not have an up_to bound"));
+        }
+        let bounds: Option<Vec<Decimal>> = bounded.iter().map(|tier| tier.up_to).collect();
+        let Some(bounds) = bounds else {
+            return Err(field_error("tiers", "TIERS", "Only the last tier may omit up_to"));
+        };
+        if bounds.windows(2).any(|pair| pair[0] >= pair[1]) {
+            return Err(field_error("tiers", "TIERS", "Tier up_to bounds must be increasing"));
+        }
+    }
+    if let (Some(min_fee), Some(max_fee)) = (request.min_fee, request.max_fee)
+        && min_fee > max_fee
+    {
+        return Err(field_error("max_fee", "RANGE", "max_fee must not be less than min_fee"));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct FeeSchedulePaginationParams {
+    #[serde(alias = "per_page")]
+    #[validate(range(min = 1, max = MAX_PAGE_SIZE, code = "RANGE", message = "limit must be between 1 and 100"))]
+    pub limit: Option<i64>,
+    pub cursor: Option<String>,
+    pub order: Option<SortOrder>,
+    #[validate(custom(function = "currency_code"))]
+    pub currency: Option<String>,
+    pub transaction_type: Option<FeeTransactionType>,
+    pub active: Option<bool>,
+    #[serde(default)]
+    pub include_total: bool,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct FeePreviewRequest {
+    pub transaction_type: FeeTransactionType,
+    #[validate(custom(function = "currency_code"))]
+    pub currency: String,
+    #[validate(custom(function = "positive_amount"))]
+    pub amount: Decimal,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum FeeCap {
+    Minimum,
+    Maximum,
+}
+
+// How the fee for one amount was worked out. Without an active schedule the
+// fee is zero and `fee_schedule_id` is null.
+#[derive(Debug, Clone, Serialize)]
+pub struct FeeBreakdown {
+    pub transaction_type: FeeTransactionType,
+    pub currency: String,
+    pub amount: Decimal,
+    pub fee_schedule_id: Option<Uuid>,
+    // The tier the amount fell in, for tiered schedules
+    pub tier: Option<FeeTier>,
+    pub flat_fee: Decimal,
+    pub percentage: Decimal,
+    pub percentage_fee: Decimal,
+    // Set when `min_fee` or `max_fee` changed the fee
+    pub cap_applied: Option<FeeCap>,
+    pub fee: Decimal,
+    // What reaches the account for deposits, what leaves it for withdrawals
+    // and transfers
+    pub net_amount: Decimal,
+    #[serde(skip)]
+    pub revenue_account_id: Option<Uuid>,
+}
+
+// Deposits and withdrawals return the account together with the fee charged.
+#[derive(Debug, Serialize)]
+pub struct AccountWithFee {
+    #[serde(flatten)]
+    pub account: Account,
+    pub fee: FeeBreakdown,
+    // Set when the withdrawal is held until an approval request is decided
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub approval_request_id: Option<Uuid>,
+}
@@ -0,0 +1,389 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use tokio_postgres::Row;
+use std::convert::TryFrom;
+use std::fmt;
+use uuid::Uuid;
+use validator::Validate;
+use crate::base::{
+    constants::{MAX_KYC_DOCUMENT_SIZE, MAX_PAGE_SIZE},
+    pagination::{Cursor, CursorValue, Keyset, SortField, SortOrder},
+    validation::{country_code, currency_code, non_negative_amount, not_blank, positive_amount, sha256_hex},
+};
+
+// Tiers are ordered: each one allows at least what the one below does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum KycTier {
+    #[default]
+    Unverified,
+    // Identity document checked
+    Basic,
+    // Identity and address checked
+    Full,
+}
+
+impl KycTier {
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "BASIC" => KycTier::Basic,
+            "FULL" => KycTier::Full,
+            _ => KycTier::Unverified,
+        }
+    }
+
+    // Document types an application for the tier must include.
+    pub fn required_documents(&self) -> &'static [&'static [KycDocumentType]] {
+        const IDENTITY: &[KycDocumentType] =
+            &[KycDocumentType::Passport, KycDocumentType::NationalId, KycDocumentType::DrivingLicense];
+        match self {
+            KycTier::Unverified => &[],
+            KycTier::Basic => &[IDENTITY],
+            KycTier::Full => &[IDENTITY, &[KycDocumentType::ProofOfAddress]],
+        }
+    }
+}
+
+impl fmt::Display for KycTier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KycTier::Unverified => write!(f, "UNVERIFIED"),
+            KycTier::Basic => write!(f, "BASIC"),
+            KycTier::Full => write!(f, "FULL"),
+        }
+    }
+}
+
+// Where the user's latest application stands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum KycStatus {
+    #[default]
+    NotStarted,
+    Pending,
+    Verified,
+    Rejected,
+}
+
+impl KycStatus {
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "PENDING" => KycStatus::Pending,
+            "VERIFIED" => KycStatus::Verified,
+            "REJECTED" => KycStatus::Rejected,
+            _ => KycStatus::NotStarted,
+        }
+    }
+}
+
+impl fmt::Display for KycStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KycStatus::NotStarted => write!(f, "NOT_STARTED"),
+            KycStatus::Pending => write!(f, "PENDING"),
+            KycStatus::Verified => write!(f, "VERIFIED"),
+            KycStatus::Rejected => write!(f, "REJECTED"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum KycDocumentType {
+    Passport,
+    NationalId,
+    DrivingLicense,
+    ProofOfAddress,
+    Selfie,
+}
+
+impl KycDocumentType {
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "PASSPORT" => KycDocumentType::Passport,
+            "NATIONAL_ID" => KycDocumentType::NationalId,
+            "DRIVING_LICENSE" => KycDocumentType::DrivingLicense,
+            "PROOF_OF_ADDRESS" => KycDocumentType::ProofOfAddress,
+            _ => KycDocumentType::Selfie,
+        }
+    }
+}
+
+impl fmt::Display for KycDocumentType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KycDocumentType::Passport => write!(f, "PASSPORT"),
+            KycDocumentType::NationalId => write!(f, "NATIONAL_ID"),
+            KycDocumentType::DrivingLicense => write!(f, "DRIVING_LICENSE"),
+            KycDocumentType::ProofOfAddress => write!(f, "PROOF_OF_ADDRESS"),
+            KycDocumentType::Selfie => write!(f, "SELFIE"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum KycDocumentStatus {
+    Pending,
+    Verified,
+    Rejected,
+}
+
+impl KycDocumentStatus {
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "VERIFIED" => KycDocumentStatus::Verified,
+            "REJECTED" => KycDocumentStatus::Rejected,
+            _ => KycDocumentStatus::Pending,
+        }
+    }
+}
+
+impl fmt::Display for KycDocumentStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KycDocumentStatus::Pending => write!(f, "PENDING"),
+            KycDocumentStatus::Verified => write!(f, "VERIFIED"),
+            KycDocumentStatus::Rejected => write!(f, "REJECTED"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum KycApplicationStatus {
+    Pending,
+    Approved,
+    Rejected,
+}
+
+impl KycApplicationStatus {
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "APPROVED" => KycApplicationStatus::Approved,
+            "REJECTED" => KycApplicationStatus::Rejected,
+            _ => KycApplicationStatus::Pending,
+        }
+    }
+}
+
+impl fmt::Display for KycApplicationStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KycApplicationStatus::Pending => write!(f, "PENDING"),
+            KycApplicationStatus::Approved => write!(f, "APPROVED"),
+            KycApplicationStatus::Rejected => write!(f, "REJECTED"),
+        }
+    }
+}
+
+// Describes a document uploaded to the document store; only its metadata is
+// sent here.
+#[derive(Debug, Deserialize, Validate)]
+pub struct SubmitKycDocumentRequest {
+    pub document_type: KycDocumentType,
+    #[validate(custom(function = "country_code"))]
+    pub issuing_country: String,
+    // Only the last characters are kept
+    #[validate(
+        custom(function = "not_blank"),
+        length(max = 50, code = "MAX_LENGTH", message = "Document number must be at most 50 characters")
+    )]
+    pub document_number: Option<String>,
+    pub expires_on: Option<NaiveDate>,
+    #[validate(
+        custom(function = "not_blank"),
+        length(max = 255, code = "MAX_LENGTH", message = "File name must be at most 255 characters")
+    )]
+    pub file_name: String,
+    #[validate(
+        custom(function = "not_blank"),
+        length(max = 100, code = "MAX_LENGTH", message = "Content type must be at most 100 characters")
+    )]
+    pub content_type: String,
+    #[validate(range(min = 1, max = MAX_KYC_DOCUMENT_SIZE, code = "RANGE", message = "size_bytes must be between 1 and 20 MiB"))]
+    pub size_bytes: i64,
+    #[validate(custom(function = "sha256_hex"))]
+    pub sha256: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct KycDocument {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub document_type: KycDocumentType,
+    pub issuing_country: String,
+    pub document_number_last4: Option<String>,
+    pub expires_on: Option<NaiveDate>,
+    pub file_name: String,
+    pub content_type: String,
+    pub size_bytes: i64,
+    pub sha256: String,
+    pub status: KycDocumentStatus,
+    pub created_at: DateTime<Utc>,
+}
+
+impl TryFrom<Row> for KycDocument {
+    type Error = tokio_postgres::Error;
+
+    fn try_from(row: Row) -> Result<Self, Self::Error> {
+        Ok(KycDocument {
+            id: row.get("id"),
+            user_id: row.get("user_id"),
+            document_type: KycDocumentType::parse(row.get("document_type")),
+            issuing_country: row.get("issuing_country"),
+            document_number_last4: row.get("document_number_last4"),
+            expires_on: row.get("expires_on"),
+            file_name: row.get("file_name"),
+            content_type: row.get("content_type"),
+            size_bytes: row.get("size_bytes"),
+            sha256: row.get("sha256"),
+            status: KycDocumentStatus::parse(row.get("status")),
+            created_at: row.get("created_at"),
+        })
+    }
+}
+
+impl KycDocument {
+    // Rejected and expired documents can't back an application.
+    pub fn is_usable(&self, today: NaiveDate) -> bool {
+        self.status != KycDocumentStatus::Rejected && self.expires_on.is_none_or(|expires_on| expires_on > today)
+    }
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateKycApplicationRequest {
+    pub tier: KycTier,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct KycApplication {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub requested_tier: KycTier,
+    pub document_ids: Vec<Uuid>,
+    pub status: KycApplicationStatus,
+    pub reviewed_by: Option<Uuid>,
+    pub reviewed_at: Option<DateTime<Utc>>,
+    pub review_note: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl TryFrom<Row> for KycApplication {
+    type Error = tokio_postgres::Error;
+
+    fn try_from(row: Row) -> Result<Self, Self::Error> {
+        Ok(KycApplication {
+            id: row.get("id"),
+            user_id: row.get("user_id"),
+            requested_tier: KycTier::parse(row.get("requested_tier")),
+            document_ids: row.get("document_ids"),
+            status: KycApplicationStatus::parse(row.get("status")),
+            reviewed_by: row.get("reviewed_by"),
+            reviewed_at: row.get("reviewed_at"),
+            review_note: row.get("review_note"),
+            created_at: row.get("created_at"),
+        })
+    }
+}
+
+impl Keyset for KycApplication {
+    fn cursor(&self, _sort: SortField) -> Cursor {
+        Cursor {
+            value: CursorValue::Timestamp(self.created_at),
+            id: self.id,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct KycApplicationPaginationParams {
+    #[serde(alias = "per_page")]
+    #[validate(range(min = 1, max = MAX_PAGE_SIZE, code = "RANGE", message = "limit must be between 1 and 100"))]
+    pub limit: Option<i64>,
+    pub cursor: Option<String>,
+    pub order: Option<SortOrder>,
+    pub status: Option<KycApplicationStatus>,
+    pub requested_tier: Option<KycTier>,
+    pub user_id: Option<Uuid>,
+    #[serde(default)]
+    pub include_total: bool,
+}
+
+// The caps themselves; `None` means no cap.
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct Caps {
+    pub max_accounts: Option<i32>,
+    pub max_balance: Option<Decimal>,
+    pub max_single_transaction: Option<Decimal>,
+    pub max_monthly_volume: Option<Decimal>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct KycTierCaps {
+    pub tier: KycTier,
+    pub currency: String,
+    #[serde(flatten)]
+    pub caps: Caps,
+    pub updated_by: Option<Uuid>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl TryFrom<Row> for KycTierCaps {
+    type Error = tokio_postgres::Error;
+
+    fn try_from(row: Row) -> Result<Self, Self::Error> {
+        Ok(KycTierCaps {
+            tier: KycTier::parse(row.get("tier")),
+            currency: row.get("currency"),
+            caps: Caps {
+                max_accounts: row.get("max_accounts"),
+                max_balance: row.get("max_balance"),
+                max_single_transaction: row.get("max_single_transaction"),
+                max_monthly_volume: row.get("max_monthly_volume"),
+            },
+            updated_by: row.get("updated_by"),
+            updated_at: row.get("updated_at"),
+        })
+    }
+}
+
+// Replaces every cap of the tier in the currency; caps left out are removed.
+#[derive(Debug, Deserialize, Validate)]
+pub struct SetKycTierCapsRequest {
+    pub tier: KycTier,
+    #[validate(custom(function = "currency_code"))]
+    pub currency: String,
+    #[validate(range(min = 0, code = "RANGE", message = "max_accounts must not be negative"))]
+    pub max_accounts: Option<i32>,
+    #[validate(custom(function = "non_negative_amount"))]
+    pub max_balance: Option<Decimal>,
+    #[validate(custom(function = "positive_amount"))]
+    pub max_single_transaction: Option<Decimal>,
+    #[validate(custom(function = "non_negative_amount"))]
+    pub max_monthly_volume: Option<Decimal>,
+}
+
+impl SetKycTierCapsRequest {
+    pub fn caps(&self) -> Caps {
+        Caps {
+            max_accounts: self.max_accounts,
+            max_balance: self.max_balance,
+            max_single_transaction: self.max_single_transaction,
+            max_monthly_volume: self.max_monthly_volume,
+        }
+    }
+}
+
+// A user's verification: tier, status, documents on file, applications and
+// the caps of their tier.
+#[derive(Debug, Serialize)]
+pub struct KycProfile {
+    pub user_id: Uuid,
+    pub tier: KycTier,
+    pub status: KycStatus,
+    pub documents: Vec<KycDocument>,
+    pub applications: Vec<KycApplication>,
+    pub caps: Vec<KycTierCaps>,
+}
@@ -0,0 +1,241 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use tokio_postgres::Row;
+use std::convert::TryFrom;
+use std::fmt;
+use uuid::Uuid;
+use validator::{Validate, ValidationError};
+use crate::base::{
+    constants::MAX_PAGE_SIZE,
+    pagination::{Cursor, CursorValue, Keyset, SortField, SortOrder},
+    validation::{currency_code, field_error, non_negative_amount, positive_amount},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum LimitScope {
+    // Fills in the limits an account has no value of its own for
+    Default,
+    // Totals over all of a user's accounts in one currency
+    User,
+    Account,
+}
+
+impl LimitScope {
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "DEFAULT" => LimitScope::Default,
+            "USER" => LimitScope::User,
+            _ => LimitScope::Account,
+        }
+    }
+}
+
+impl fmt::Display for LimitScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LimitScope::Default => write!(f, "DEFAULT"),
+            LimitScope::User => write!(f, "USER"),
+            LimitScope::Account => write!(f, "ACCOUNT"),
+        }
+    }
+}
+
+// The limits themselves; `None` means unlimited.
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct Limits {
+    pub max_single_withdrawal: Option<Decimal>,
+    pub daily_debit_limit: Option<Decimal>,
+    pub monthly_debit_limit: Option<Decimal>,
+    pub max_debits_per_hour: Option<i32>,
+}
+
+impl Limits {
+    pub fn is_unlimited(&self) -> bool {
+        self.max_single_withdrawal.is_none()
+            && self.daily_debit_limit.is_none()
+            && self.monthly_debit_limit.is_none()
+            && self.max_debits_per_hour.is_none()
+    }
+
+    // Each limit of `self`, or of `fallback` where `self` has none.
+    pub fn or(self, fallback: Limits) -> Limits {
+        Limits {
+            max_single_withdrawal: self.max_single_withdrawal.or(fallback.max_single_withdrawal),
+            daily_debit_limit: self.daily_debit_limit.or(fallback.daily_debit_limit),
+            monthly_debit_limit: self.monthly_debit_limit.or(fallback.monthly_debit_limit),
+            max_debits_per_hour: self.max_debits_per_hour.or(fallback.max_debits_per_hour),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct TransactionLimit {
+    pub id: Uuid,
+    pub scope: LimitScope,
+    pub user_id: Option<Uuid>,
+    pub account_id: Option<Uuid>,
+    pub currency: String,
+    #[serde(flatten)]
+    pub limits: Limits,
+    pub updated_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl TryFrom<Row> for TransactionLimit {
+    type Error = tokio_postgres::Error;
+
+    fn try_from(row: Row) -> Result<Self, Self::Error> {
+        Ok(TransactionLimit {
+            id: row.get("id"),
+            scope: LimitScope::parse(row.get("scope")),
+            user_id: row.get("user_id"),
+            account_id: row.get("account_id"),
+            currency: row.get("currency"),
+            limits: Limits {
+                max_single_withdrawal: row.get("max_single_withdrawal"),
+                daily_debit_limit: row.get("daily_debit_limit"),
+                monthly_debit_limit: row.get("monthly_debit_limit"),
+                max_debits_per_hour: row.get("max_debits_per_hour"),
+            },
+            updated_by: row.get("updated_by"),
+            created_at: row.get("created_at"),
+            updated_at: row.get("updated_at"),
+        })
+    }
+}
+
+impl Keyset for TransactionLimit {
+    fn cursor(&self, _sort: SortField) -> Cursor {
+        Cursor {
+            value: CursorValue::Timestamp(self.created_at),
+            id: self.id,
+        }
+    }
+}
+
+// Replaces every limit of the target; limits left out are removed.
+#[derive(Debug, Deserialize, Validate)]
+#[validate(schema(function = "limit_target"))]
+pub struct SetTransactionLimitRequest {
+    pub scope: LimitScope,
+    pub user_id: Option<Uuid>,
+    pub account_id: Option<Uuid>,
+    // Required for DEFAULT and USER; an ACCOUNT limit uses the account's currency
+    #[validate(custom(function = "currency_code"))]
+    pub currency: Option<String>,
+    #[validate(custom(function = "positive_amount"))]
+    pub max_single_withdrawal: Option<Decimal>,
+    #[validate(custom(function = "non_negative_amount"))]
+    pub daily_debit_limit: Option<Decimal>,
+    #[validate(custom(function = "non_negative_amount"))]
+    pub monthly_debit_limit: Option<Decimal>,
+    #[validate(range(min = 0, code = "RANGE", message = "max_debits_per_hour must not be negative"))]
+    pub max_debits_per_hour: Option<i32>,
+}
+
+impl SetTransactionLimitRequest {
+    pub fn limits(&self) -> Limits {
+        Limits {
+            max_single_withdrawal: self.max_single_withdrawal,
+            daily_debit_limit: self.daily_debit_limit,
+            monthly_debit_limit: self.monthly_debit_limit,
+            max_debits_per_hour: self.max_debits_per_hour,
+        }
+    }
+}
+
+fn limit_target(request: &SetTransactionLimitRequest) -> Result<(), ValidationError> {
+    match request.scope {
+        LimitScope::Default | LimitScope::User if request.currency.is_none() => {
+            return Err(field_error("currency", "REQUIRED", "currency is required for DEFAULT and USER limits"));
+        }
+        LimitScope::User if request.user_id.is_none() => {
+            return Err(field_error("user_id", "REQUIRED", "user_id is required for USER limits"));
+        }
+        LimitScope::Account if request.account_id.is_none() => {
+            return Err(field_error("account_id", "REQUIRED", "account_id is required for ACCOUNT limits"));
+        }
+        _ => {}
+    }
+    if request.user_id.is_some() && request.scope != LimitScope::User {
+        return Err(field_error("user_id", "NOT_ALLOWED", "user_id is only allowed for USER limits"));
+    }
+    if request.account_id.is_some() && request.scope != LimitScope::Account {
+        return Err(field_error("account_id", "NOT_ALLOWED", "account_id is only allowed for ACCOUNT limits"));
+    }
+    if let (Some(daily), Some(monthly)) = (request.daily_debit_limit, request.monthly_debit_limit)
+        && daily > monthly
+    {
+        return Err(field_error("daily_debit_limit", "RANGE", "daily_debit_limit must not exceed monthly_debit_limit"));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct LimitPaginationParams {
+    #[serde(alias = "per_page")]
+    #[validate(range(min = 1, max = MAX_PAGE_SIZE, code = "RANGE", message = "limit must be between 1 and 100"))]
+    pub limit: Option<i64>,
+    pub cursor: Option<String>,
+    pub order: Option<SortOrder>,
+    pub scope: Option<LimitScope>,
+    pub user_id: Option<Uuid>,
+    pub account_id: Option<Uuid>,
+    #[validate(custom(function = "currency_code"))]
+    pub currency: Option<String>,
+    #[serde(default)]
+    pub include_total: bool,
+}
+
+// Debits made in the current windows: the UTC day and month, and the last hour.
+#[derive(Debug, Clone, Copy)]
+pub struct DebitUsage {
+    pub day_total: Decimal,
+    pub month_total: Decimal,
+    pub hour_count: i64,
+    // When the oldest debit of the last hour stops counting
+    pub hour_resets_at: Option<DateTime<Utc>>,
+}
+
+impl TryFrom<Row> for DebitUsage {
+    type Error = tokio_postgres::Error;
+
+    fn try_from(row: Row) -> Result<Self, Self::Error> {
+        Ok(DebitUsage {
+            day_total: row.get("day_total"),
+            month_total: row.get("month_total"),
+            hour_count: row.get("hour_count"),
+            hour_resets_at: row.get("hour_resets_at"),
+        })
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct LimitUsage<T> {
+    pub limit: Option<T>,
+    pub used: T,
+    // Null when there is no limit
+    pub remaining: Option<T>,
+    pub resets_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LimitHeadroom {
+    pub max_single_withdrawal: Option<Decimal>,
+    pub daily_debits: LimitUsage<Decimal>,
+    pub monthly_debits: LimitUsage<Decimal>,
+    pub hourly_debits: LimitUsage<i64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AccountLimits {
+    pub account_id: Uuid,
+    pub currency: String,
+    // This account's limits, including defaults for its currency
+    pub account: LimitHeadroom,
+    // Totals over all of the owner's accounts in the currency
+    pub user: LimitHeadroom,
+}
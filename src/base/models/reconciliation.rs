@@ -0,0 +1,237 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use tokio_postgres::Row;
+use std::convert::TryFrom;
+use std::fmt;
+use uuid::Uuid;
+use validator::Validate;
+use crate::base::{
+    constants::MAX_PAGE_SIZE,
+    models::transactions::Transaction,
+    pagination::{Cursor, CursorValue, Keyset, SortField, SortOrder},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StatementFileFormat {
+    Mt940,
+    Camt053,
+}
+
+impl StatementFileFormat {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.to_ascii_uppercase().as_str() {
+            "MT940" => Some(StatementFileFormat::Mt940),
+            "CAMT053" => Some(StatementFileFormat::Camt053),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for StatementFileFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StatementFileFormat::Mt940 => write!(f, "MT940"),
+            StatementFileFormat::Camt053 => write!(f, "CAMT053"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LineStatus {
+    // Settles a transaction
+    #[serde(rename = "MATCHED")]
+    Matched,
+    // No transaction could be identified
+    #[serde(rename = "UNMATCHED")]
+    Unmatched,
+    // A transaction was identified but disagrees with the line
+    #[serde(rename = "MISMATCHED")]
+    Mismatched,
+}
+
+impl fmt::Display for LineStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LineStatus::Matched => write!(f, "MATCHED"),
+            LineStatus::Unmatched => write!(f, "UNMATCHED"),
+            LineStatus::Mismatched => write!(f, "MISMATCHED"),
+        }
+    }
+}
+
+impl LineStatus {
+    fn parse(value: &str) -> Self {
+        match value {
+            "MATCHED" => LineStatus::Matched,
+            "MISMATCHED" => LineStatus::Mismatched,
+            _ => LineStatus::Unmatched,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct StatementImport {
+    pub id: Uuid,
+    pub format: StatementFileFormat,
+    pub filename: Option<String>,
+    pub file_sha256: String,
+    pub statement_reference: Option<String>,
+    pub account_reference: Option<String>,
+    pub line_count: i32,
+    pub matched_count: i32,
+    pub exception_count: i32,
+    pub imported_by: Option<Uuid>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl TryFrom<Row> for StatementImport {
+    type Error = tokio_postgres::Error;
+
+    fn try_from(row: Row) -> Result<Self, Self::Error> {
+        let format: String = row.get("format");
+        Ok(StatementImport {
+            id: row.get("id"),
+            format: StatementFileFormat::parse(&format).unwrap_or(StatementFileFormat::Mt940),
+            filename: row.get("filename"),
+            file_sha256: row.get("file_sha256"),
+            statement_reference: row.get("statement_reference"),
+            account_reference: row.get("account_reference"),
+            line_count: row.get("line_count"),
+            matched_count: row.get("matched_count"),
+            exception_count: row.get("exception_count"),
+            imported_by: row.get("imported_by"),
+            created_at: row.get("created_at"),
+        })
+    }
+}
+
+impl Keyset for StatementImport {
+    fn cursor(&self, _sort: SortField) -> Cursor {
+        Cursor {
+            value: CursorValue::Timestamp(self.created_at),
+            id: self.id,
+        }
+    }
+}
+
+// Metadata of an import, recorded together with its lines.
+#[derive(Debug)]
+pub struct NewStatementImport {
+    pub format: StatementFileFormat,
+    pub filename: Option<String>,
+    pub file_sha256: String,
+    pub statement_reference: Option<String>,
+    pub account_reference: Option<String>,
+    pub imported_by: Option<Uuid>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StatementLine {
+    pub id: Uuid,
+    pub import_id: Uuid,
+    pub line_number: i32,
+    pub booking_date: NaiveDate,
+    pub amount: Decimal,
+    pub currency: Option<String>,
+    pub reference: Option<String>,
+    pub description: Option<String>,
+    pub status: LineStatus,
+    pub transaction_id: Option<Uuid>,
+    pub reason: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl TryFrom<Row> for StatementLine {
+    type Error = tokio_postgres::Error;
+
+    fn try_from(row: Row) -> Result<Self, Self::Error> {
+        let status: String = row.get("status");
+        Ok(StatementLine {
+            id: row.get("id"),
+            import_id: row.get("import_id"),
+            line_number: row.get("line_number"),
+            booking_date: row.get("booking_date"),
+            amount: row.get("amount"),
+            currency: row.get("currency"),
+            reference: row.get("reference"),
+            description: row.get("description"),
+            status: LineStatus::parse(&status),
+            transaction_id: row.get("transaction_id"),
+            reason: row.get("reason"),
+            created_at: row.get("created_at"),
+        })
+    }
+}
+
+// Outcome of matching one parsed entry, ready to be stored.
+#[derive(Debug)]
+pub struct ReconciledLine {
+    pub line_number: i32,
+    pub booking_date: NaiveDate,
+    pub amount: Decimal,
+    pub currency: Option<String>,
+    pub reference: Option<String>,
+    pub description: Option<String>,
+    pub status: LineStatus,
+    pub transaction_id: Option<Uuid>,
+    pub reason: Option<String>,
+}
+
+// A transaction considered for a statement line, with the currency and holder
+// of its account and whether an earlier line already settled it.
+#[derive(Debug)]
+pub struct ReconciliationCandidate {
+    pub transaction: Transaction,
+    pub currency: String,
+    pub user_id: Uuid,
+    pub reconciled: bool,
+}
+
+impl TryFrom<Row> for ReconciliationCandidate {
+    type Error = tokio_postgres::Error;
+
+    fn try_from(row: Row) -> Result<Self, Self::Error> {
+        let currency = row.get("currency");
+        let user_id = row.get("user_id");
+        let reconciled = row.get("reconciled");
+        Ok(ReconciliationCandidate {
+            transaction: row.try_into()?,
+            currency,
+            user_id,
+            reconciled,
+        })
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportReport {
+    pub import: StatementImport,
+    // Lines that did not settle a transaction
+    pub exceptions: Vec<StatementLine>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct ImportParams {
+    // Detected from the content when omitted
+    pub format: Option<StatementFileFormat>,
+    #[validate(length(min = 1, max = 255, code = "LENGTH", message = "filename must be between 1 and 255 characters"))]
+    pub filename: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct ImportPaginationParams {
+    #[serde(alias = "per_page")]
+    #[validate(range(min = 1, max = MAX_PAGE_SIZE, code = "RANGE", message = "limit must be between 1 and 100"))]
+    pub limit: Option<i64>,
+    pub cursor: Option<String>,
+    pub order: Option<SortOrder>,
+    #[serde(default)]
+    pub include_total: bool,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct StatementLineParams {
+    pub status: Option<LineStatus>,
+}
@@ -0,0 +1,306 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio_postgres::Row;
+use std::convert::TryFrom;
+use std::fmt;
+use uuid::Uuid;
+use validator::Validate;
+use crate::base::{
+    constants::{MAX_PAGE_SIZE, MAX_REVIEW_NOTE_LENGTH, MAX_RISK_RULE_SCORE},
+    pagination::{Cursor, CursorValue, Keyset, SortField, SortOrder},
+    validation::positive_amount,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RiskRuleCode {
+    // A large withdrawal from an account opened recently
+    NewAccountLargeWithdrawal,
+    // Several debits from the same account in a short time
+    RapidWithdrawals,
+    // A request from an IP address the user never logged in from
+    NewIp,
+    // An amount far above the user's average debit
+    AmountAboveHistory,
+}
+
+impl RiskRuleCode {
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "NEW_ACCOUNT_LARGE_WITHDRAWAL" => RiskRuleCode::NewAccountLargeWithdrawal,
+            "RAPID_WITHDRAWALS" => RiskRuleCode::RapidWithdrawals,
+            "NEW_IP" => RiskRuleCode::NewIp,
+            _ => RiskRuleCode::AmountAboveHistory,
+        }
+    }
+}
+
+impl fmt::Display for RiskRuleCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RiskRuleCode::NewAccountLargeWithdrawal => write!(f, "NEW_ACCOUNT_LARGE_WITHDRAWAL"),
+            RiskRuleCode::RapidWithdrawals => write!(f, "RAPID_WITHDRAWALS"),
+            RiskRuleCode::NewIp => write!(f, "NEW_IP"),
+            RiskRuleCode::AmountAboveHistory => write!(f, "AMOUNT_ABOVE_HISTORY"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RiskRule {
+    pub code: RiskRuleCode,
+    pub enabled: bool,
+    pub score: i32,
+    pub params: Value,
+    pub updated_by: Option<Uuid>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl TryFrom<Row> for RiskRule {
+    type Error = tokio_postgres::Error;
+
+    fn try_from(row: Row) -> Result<Self, Self::Error> {
+        Ok(RiskRule {
+            code: RiskRuleCode::parse(row.get("code")),
+            enabled: row.get("enabled"),
+            score: row.get("score"),
+            params: row.get("params"),
+            updated_by: row.get("updated_by"),
+            updated_at: row.get("updated_at"),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+#[serde(deny_unknown_fields)]
+pub struct NewAccountLargeWithdrawalParams {
+    #[validate(range(min = 1, max = 3650, code = "RANGE", message = "max_account_age_days must be between 1 and 3650"))]
+    pub max_account_age_days: i64,
+    #[validate(custom(function = "positive_amount"))]
+    pub min_amount: Decimal,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+#[serde(deny_unknown_fields)]
+pub struct RapidWithdrawalsParams {
+    // Debits allowed within the window before the rule matches
+    #[validate(range(min = 1, max = 1000, code = "RANGE", message = "max_count must be between 1 and 1000"))]
+    pub max_count: i64,
+    #[validate(range(min = 1, max = 1440, code = "RANGE", message = "window_minutes must be between 1 and 1440"))]
+    pub window_minutes: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+#[serde(deny_unknown_fields)]
+pub struct NewIpParams {}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+#[serde(deny_unknown_fields)]
+pub struct AmountAboveHistoryParams {
+    #[validate(custom(function = "positive_amount"))]
+    pub multiplier: Decimal,
+    // Debits the user needs in the lookback before the rule applies
+    #[validate(range(min = 1, max = 1000, code = "RANGE", message = "min_history must be between 1 and 1000"))]
+    pub min_history: i64,
+    #[validate(range(min = 1, max = 3650, code = "RANGE", message = "lookback_days must be between 1 and 3650"))]
+    pub lookback_days: i64,
+}
+
+// The parsed `params` of a rule.
+#[derive(Debug, Clone)]
+pub enum RiskRuleParams {
+    NewAccountLargeWithdrawal(NewAccountLargeWithdrawalParams),
+    RapidWithdrawals(RapidWithdrawalsParams),
+    NewIp(NewIpParams),
+    AmountAboveHistory(AmountAboveHistoryParams),
+}
+
+// Fields left out are kept as they are; `params` replaces all of the rule's params.
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateRiskRuleRequest {
+    pub enabled: Option<bool>,
+    #[validate(range(min = 0, max = MAX_RISK_RULE_SCORE, code = "RANGE", message = "score must be between 0 and 100"))]
+    pub score: Option<i32>,
+    pub params: Option<Value>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum RiskAction {
+    Allow,
+    // Held as a PENDING transaction until an admin approves or rejects it
+    Review,
+    Block,
+}
+
+impl RiskAction {
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "REVIEW" => RiskAction::Review,
+            "BLOCK" => RiskAction::Block,
+            _ => RiskAction::Allow,
+        }
+    }
+}
+
+impl fmt::Display for RiskAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RiskAction::Allow => write!(f, "ALLOW"),
+            RiskAction::Review => write!(f, "REVIEW"),
+            RiskAction::Block => write!(f, "BLOCK"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum ReviewStatus {
+    Pending,
+    Approved,
+    Rejected,
+}
+
+impl ReviewStatus {
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "APPROVED" => ReviewStatus::Approved,
+            "REJECTED" => ReviewStatus::Rejected,
+            _ => ReviewStatus::Pending,
+        }
+    }
+}
+
+impl fmt::Display for ReviewStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReviewStatus::Pending => write!(f, "PENDING"),
+            ReviewStatus::Approved => write!(f, "APPROVED"),
+            ReviewStatus::Rejected => write!(f, "REJECTED"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MatchedRule {
+    pub rule: RiskRuleCode,
+    pub score: i32,
+    pub reason: String,
+}
+
+// What the engine made of a debit: the sum of the matched rules' scores and
+// the action it leads to.
+#[derive(Debug, Clone)]
+pub struct RiskDecision {
+    pub score: i32,
+    pub action: RiskAction,
+    pub matched_rules: Vec<MatchedRule>,
+}
+
+// What the rules look at besides the account and the amount.
+#[derive(Debug, Clone, Copy)]
+pub struct RiskFacts {
+    // Debits from the account since the RAPID_WITHDRAWALS window started
+    pub recent_debits: i64,
+    // Whether the user ever logged in from the request's IP address; true
+    // when there is no address, e.g. for scheduled payments
+    pub known_ip: bool,
+    // Whether any successful login of the user is on record; logins before
+    // the history was kept, or with tokens issued before, leave none
+    pub has_logins: bool,
+    // The user's debits in the account's currency over the lookback
+    pub history_count: i64,
+    pub history_average: Option<Decimal>,
+}
+
+impl TryFrom<Row> for RiskFacts {
+    type Error = tokio_postgres::Error;
+
+    fn try_from(row: Row) -> Result<Self, Self::Error> {
+        Ok(RiskFacts {
+            recent_debits: row.get("recent_debits"),
+            known_ip: row.get("known_ip"),
+            has_logins: row.get("has_logins"),
+            history_count: row.get("history_count"),
+            history_average: row.get("history_average"),
+        })
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct RiskAssessment {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub account_id: Uuid,
+    pub transaction_id: Option<Uuid>,
+    pub destination_account_id: Option<Uuid>,
+    pub amount: Decimal,
+    pub ip_address: Option<String>,
+    pub score: i32,
+    pub action: RiskAction,
+    pub matched_rules: Vec<MatchedRule>,
+    pub review_status: Option<ReviewStatus>,
+    pub reviewed_by: Option<Uuid>,
+    pub reviewed_at: Option<DateTime<Utc>>,
+    pub review_note: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl TryFrom<Row> for RiskAssessment {
+    type Error = tokio_postgres::Error;
+
+    fn try_from(row: Row) -> Result<Self, Self::Error> {
+        let matched_rules = serde_json::from_value(row.get("matched_rules")).unwrap_or_default();
+        let review_status: Option<&str> = row.get("review_status");
+
+        Ok(RiskAssessment {
+            id: row.get("id"),
+            user_id: row.get("user_id"),
+            account_id: row.get("account_id"),
+            transaction_id: row.get("transaction_id"),
+            destination_account_id: row.get("destination_account_id"),
+            amount: row.get("amount"),
+            ip_address: row.get("ip_address"),
+            score: row.get("score"),
+            action: RiskAction::parse(row.get("action")),
+            matched_rules,
+            review_status: review_status.map(ReviewStatus::parse),
+            reviewed_by: row.get("reviewed_by"),
+            reviewed_at: row.get("reviewed_at"),
+            review_note: row.get("review_note"),
+            created_at: row.get("created_at"),
+        })
+    }
+}
+
+impl Keyset for RiskAssessment {
+    fn cursor(&self, _sort: SortField) -> Cursor {
+        Cursor {
+            value: CursorValue::Timestamp(self.created_at),
+            id: self.id,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct RiskAssessmentPaginationParams {
+    #[serde(alias = "per_page")]
+    #[validate(range(min = 1, max = MAX_PAGE_SIZE, code = "RANGE", message = "limit must be between 1 and 100"))]
+    pub limit: Option<i64>,
+    pub cursor: Option<String>,
+    pub order: Option<SortOrder>,
+    pub action: Option<RiskAction>,
+    pub review_status: Option<ReviewStatus>,
+    pub user_id: Option<Uuid>,
+    pub account_id: Option<Uuid>,
+    #[serde(default)]
+    pub include_total: bool,
+}
+
+#[derive(Debug, Default, Deserialize, Validate)]
+pub struct ReviewDecisionRequest {
+    #[validate(length(max = MAX_REVIEW_NOTE_LENGTH, code = "MAX_LENGTH", message = "Note must be at most 500 characters"))]
+    pub note: Option<String>,
+}
@@ -0,0 +1,295 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use tokio_postgres::Row;
+use std::convert::TryFrom;
+use std::fmt;
+use uuid::Uuid;
+use validator::{Validate, ValidationError};
+use crate::base::{
+    constants::{MAX_CRON_LENGTH, MAX_DESCRIPTION_LENGTH, MAX_PAGE_SIZE, MAX_RRULE_LENGTH},
+    pagination::{Cursor, CursorValue, Keyset, SortField, SortOrder},
+    schedules::{parse_timezone, Recurrence, SchedulePlan},
+    validation::{field_error, positive_amount},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum ScheduleStatus {
+    Active,
+    Paused,
+    Cancelled,
+    // No runs left: one-off schedules after their run, recurring ones past
+    // their end date, run count or last occurrence
+    Completed,
+}
+
+impl ScheduleStatus {
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "PAUSED" => ScheduleStatus::Paused,
+            "CANCELLED" => ScheduleStatus::Cancelled,
+            "COMPLETED" => ScheduleStatus::Completed,
+            _ => ScheduleStatus::Active,
+        }
+    }
+}
+
+impl fmt::Display for ScheduleStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScheduleStatus::Active => write!(f, "ACTIVE"),
+            ScheduleStatus::Paused => write!(f, "PAUSED"),
+            ScheduleStatus::Cancelled => write!(f, "CANCELLED"),
+            ScheduleStatus::Completed => write!(f, "COMPLETED"),
+        }
+    }
+}
+
+// A standing order moving `amount` between two accounts of the same user.
+#[derive(Debug, Serialize)]
+pub struct ScheduledPayment {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub source_account_id: Uuid,
+    pub destination_account_id: Uuid,
+    pub amount: Decimal,
+    pub description: Option<String>,
+    pub cron: Option<String>,
+    pub rrule: Option<String>,
+    pub timezone: String,
+    pub start_at: DateTime<Utc>,
+    pub end_at: Option<DateTime<Utc>>,
+    pub max_runs: Option<i32>,
+    pub status: ScheduleStatus,
+    pub next_run_at: Option<DateTime<Utc>>,
+    pub runs_count: i32,
+    pub last_run_at: Option<DateTime<Utc>>,
+    // Set when a database error kept a due run from happening; cleared by the
+    // next run
+    pub last_error_code: Option<String>,
+    pub last_error_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl ScheduledPayment {
+    // Rules are validated when the schedule is created, so they still parse.
+    pub fn plan(&self) -> SchedulePlan {
+        SchedulePlan {
+            recurrence: Recurrence::parse(self.cron.as_deref(), self.rrule.as_deref()).unwrap_or(Recurrence::Once),
+            timezone: parse_timezone(&self.timezone).unwrap_or(chrono_tz::UTC),
+            start_at: self.start_at,
+            end_at: self.end_at,
+            max_runs: self.max_runs,
+        }
+    }
+}
+
+impl TryFrom<Row> for ScheduledPayment {
+    type Error = tokio_postgres::Error;
+
+    fn try_from(row: Row) -> Result<Self, Self::Error> {
+        Ok(ScheduledPayment {
+            id: row.get("id"),
+            user_id: row.get("user_id"),
+            source_account_id: row.get("source_account_id"),
+            destination_account_id: row.get("destination_account_id"),
+            amount: row.get("amount"),
+            description: row.get("description"),
+            cron: row.get("cron"),
+            rrule: row.get("rrule"),
+            timezone: row.get("timezone"),
+            start_at: row.get("start_at"),
+            end_at: row.get("end_at"),
+            max_runs: row.get("max_runs"),
+            status: ScheduleStatus::parse(row.get("status")),
+            next_run_at: row.get("next_run_at"),
+            runs_count: row.get("runs_count"),
+            last_run_at: row.get("last_run_at"),
+            last_error_code: row.get("last_error_code"),
+            last_error_at: row.get("last_error_at"),
+            created_at: row.get("created_at"),
+            updated_at: row.get("updated_at"),
+        })
+    }
+}
+
+impl Keyset for ScheduledPayment {
+    fn cursor(&self, _sort: SortField) -> Cursor {
+        Cursor {
+            value: CursorValue::Timestamp(self.created_at),
+            id: self.id,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Validate)]
+#[validate(schema(function = "schedule_rules"))]
+pub struct CreateScheduledPaymentRequest {
+    pub source_account_id: Uuid,
+    pub destination_account_id: Uuid,
+    #[validate(custom(function = "positive_amount"))]
+    pub amount: Decimal,
+    #[validate(length(max = MAX_DESCRIPTION_LENGTH, code = "MAX_LENGTH", message = "Description must be at most 500 characters"))]
+    pub description: Option<String>,
+    // Without a rule the schedule runs once, at `start_at`
+    #[validate(length(max = MAX_CRON_LENGTH, code = "MAX_LENGTH", message = "cron must be at most 200 characters"))]
+    pub cron: Option<String>,
+    #[validate(length(max = MAX_RRULE_LENGTH, code = "MAX_LENGTH", message = "rrule must be at most 500 characters"))]
+    pub rrule: Option<String>,
+    // IANA name; defaults to UTC
+    pub timezone: Option<String>,
+    // Defaults to the time of the request
+    pub start_at: Option<DateTime<Utc>>,
+    pub end_at: Option<DateTime<Utc>>,
+    #[validate(range(min = 1, code = "RANGE", message = "max_runs must be at least 1"))]
+    pub max_runs: Option<i32>,
+}
+
+impl CreateScheduledPaymentRequest {
+    pub fn timezone(&self) -> &str {
+        self.timezone.as_deref().unwrap_or("UTC")
+    }
+
+    // `None` when the rules don't validate.
+    pub fn plan(&self, now: DateTime<Utc>) -> Option<SchedulePlan> {
+        Some(SchedulePlan {
+            recurrence: Recurrence::parse(self.cron.as_deref(), self.rrule.as_deref()).ok()?,
+            timezone: parse_timezone(self.timezone())?,
+            start_at: self.start_at.unwrap_or(now),
+            end_at: self.end_at,
+            max_runs: self.max_runs,
+        })
+    }
+}
+
+fn schedule_rules(request: &CreateScheduledPaymentRequest) -> Result<(), ValidationError> {
+    if request.source_account_id == request.destination_account_id {
+        return Err(field_error(
+            "destination_account_id",
+            "SAME_ACCOUNT",
+            "destination_account_id must be a different account",
+        ));
+    }
+    if let Err(message) = Recurrence::parse(request.cron.as_deref(), request.rrule.as_deref()) {
+        let field = if request.cron.is_some() && request.rrule.is_none() { "cron" } else { "rrule" };
+        return Err(field_error(field, "RULE", message));
+    }
+    if parse_timezone(request.timezone()).is_none() {
+        return Err(field_error("timezone", "TIMEZONE", "timezone must be an IANA time zone name"));
+    }
+    if let (Some(start_at), Some(end_at)) = (request.start_at, request.end_at)
+        && end_at <= start_at
+    {
+        return Err(field_error("end_at", "RANGE", "end_at must be after start_at"));
+    }
+
+    let now = Utc::now();
+    if request.plan(now).and_then(|plan| plan.next_run(now, 0)).is_none() {
+        return Err(field_error("end_at", "NO_RUNS", "The schedule has no runs left"));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct ScheduledPaymentPaginationParams {
+    #[serde(alias = "per_page")]
+    #[validate(range(min = 1, max = MAX_PAGE_SIZE, code = "RANGE", message = "limit must be between 1 and 100"))]
+    pub limit: Option<i64>,
+    pub cursor: Option<String>,
+    pub order: Option<SortOrder>,
+    pub status: Option<ScheduleStatus>,
+    #[serde(default)]
+    pub include_total: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RunStatus {
+    Succeeded,
+    Failed,
+    // The transfer is held for risk review; the deposit is booked on approval
+    InReview,
+}
+
+impl RunStatus {
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "SUCCEEDED" => RunStatus::Succeeded,
+            "IN_REVIEW" => RunStatus::InReview,
+            _ => RunStatus::Failed,
+        }
+    }
+}
+
+impl fmt::Display for RunStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RunStatus::Succeeded => write!(f, "SUCCEEDED"),
+            RunStatus::Failed => write!(f, "FAILED"),
+            RunStatus::InReview => write!(f, "IN_REVIEW"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum RunOutcome {
+    Succeeded { withdrawal_id: Uuid, deposit_id: Uuid },
+    // `code` is the error code of the refusal, e.g. `INSUFFICIENT_FUNDS`
+    Failed { code: &'static str, reason: String },
+    InReview { withdrawal_id: Uuid },
+}
+
+// One executed occurrence: the transfer it booked, or why it was refused.
+#[derive(Debug, Serialize)]
+pub struct ScheduledPaymentRun {
+    pub id: Uuid,
+    pub schedule_id: Uuid,
+    pub scheduled_for: DateTime<Utc>,
+    pub status: RunStatus,
+    pub withdrawal_transaction_id: Option<Uuid>,
+    pub deposit_transaction_id: Option<Uuid>,
+    pub failure_code: Option<String>,
+    pub failure_reason: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl TryFrom<Row> for ScheduledPaymentRun {
+    type Error = tokio_postgres::Error;
+
+    fn try_from(row: Row) -> Result<Self, Self::Error> {
+        Ok(ScheduledPaymentRun {
+            id: row.get("id"),
+            schedule_id: row.get("schedule_id"),
+            scheduled_for: row.get("scheduled_for"),
+            status: RunStatus::parse(row.get("status")),
+            withdrawal_transaction_id: row.get("withdrawal_transaction_id"),
+            deposit_transaction_id: row.get("deposit_transaction_id"),
+            failure_code: row.get("failure_code"),
+            failure_reason: row.get("failure_reason"),
+            created_at: row.get("created_at"),
+        })
+    }
+}
+
+impl Keyset for ScheduledPaymentRun {
+    fn cursor(&self, _sort: SortField) -> Cursor {
+        Cursor {
+            value: CursorValue::Timestamp(self.created_at),
+            id: self.id,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct RunPaginationParams {
+    #[serde(alias = "per_page")]
+    #[validate(range(min = 1, max = MAX_PAGE_SIZE, code = "RANGE", message = "limit must be between 1 and 100"))]
+    pub limit: Option<i64>,
+    pub cursor: Option<String>,
+    pub order: Option<SortOrder>,
+    pub status: Option<RunStatus>,
+    #[serde(default)]
+    pub include_total: bool,
+}
@@ -0,0 +1,370 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio_postgres::Row;
+use std::convert::TryFrom;
+use std::fmt;
+use uuid::Uuid;
+use validator::Validate;
+use crate::base::{
+    constants::MAX_PAGE_SIZE,
+    pagination::{Cursor, CursorValue, Keyset, SortField, SortOrder},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum WatchlistSource {
+    // OFAC Specially Designated Nationals list, `sdn.csv` and `alt.csv`
+    OfacSdn,
+    // UN Security Council consolidated list, XML
+    Un,
+}
+
+impl WatchlistSource {
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "UN" => WatchlistSource::Un,
+            _ => WatchlistSource::OfacSdn,
+        }
+    }
+}
+
+impl fmt::Display for WatchlistSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WatchlistSource::OfacSdn => write!(f, "OFAC_SDN"),
+            WatchlistSource::Un => write!(f, "UN"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum WatchlistEntryType {
+    Individual,
+    Entity,
+    Vessel,
+    Aircraft,
+}
+
+impl WatchlistEntryType {
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "INDIVIDUAL" => WatchlistEntryType::Individual,
+            "VESSEL" => WatchlistEntryType::Vessel,
+            "AIRCRAFT" => WatchlistEntryType::Aircraft,
+            _ => WatchlistEntryType::Entity,
+        }
+    }
+}
+
+impl fmt::Display for WatchlistEntryType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WatchlistEntryType::Individual => write!(f, "INDIVIDUAL"),
+            WatchlistEntryType::Entity => write!(f, "ENTITY"),
+            WatchlistEntryType::Vessel => write!(f, "VESSEL"),
+            WatchlistEntryType::Aircraft => write!(f, "AIRCRAFT"),
+        }
+    }
+}
+
+// An entry as read from a list file, before it is stored.
+#[derive(Debug, Clone, Serialize)]
+pub struct NewWatchlistEntry {
+    pub external_id: String,
+    pub entry_type: WatchlistEntryType,
+    pub name: String,
+    pub aliases: Vec<String>,
+    pub programs: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WatchlistEntry {
+    pub id: Uuid,
+    pub source: WatchlistSource,
+    pub external_id: String,
+    pub entry_type: WatchlistEntryType,
+    pub name: String,
+    pub aliases: Vec<String>,
+    pub programs: Vec<String>,
+    pub loaded_at: DateTime<Utc>,
+}
+
+impl TryFrom<Row> for WatchlistEntry {
+    type Error = tokio_postgres::Error;
+
+    fn try_from(row: Row) -> Result<Self, Self::Error> {
+        Ok(WatchlistEntry {
+            id: row.get("id"),
+            source: WatchlistSource::parse(row.get("source")),
+            external_id: row.get("external_id"),
+            entry_type: WatchlistEntryType::parse(row.get("entry_type")),
+            name: row.get("name"),
+            aliases: row.get("aliases"),
+            programs: row.get("programs"),
+            loaded_at: row.get("loaded_at"),
+        })
+    }
+}
+
+// What is loaded of one list.
+#[derive(Debug, Clone, Serialize)]
+pub struct WatchlistSummary {
+    pub source: WatchlistSource,
+    pub entries: i64,
+    pub loaded_at: Option<DateTime<Utc>>,
+}
+
+impl TryFrom<Row> for WatchlistSummary {
+    type Error = tokio_postgres::Error;
+
+    fn try_from(row: Row) -> Result<Self, Self::Error> {
+        Ok(WatchlistSummary {
+            source: WatchlistSource::parse(row.get("source")),
+            entries: row.get("entries"),
+            loaded_at: row.get("loaded_at"),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum ScreeningSubject {
+    // A user's own name; screened against individuals
+    User,
+    // The payee named on a withdrawal; screened against individuals and entities
+    Counterparty,
+}
+
+impl ScreeningSubject {
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "COUNTERPARTY" => ScreeningSubject::Counterparty,
+            _ => ScreeningSubject::User,
+        }
+    }
+
+    pub fn screens(&self, entry_type: WatchlistEntryType) -> bool {
+        match self {
+            ScreeningSubject::User => entry_type == WatchlistEntryType::Individual,
+            ScreeningSubject::Counterparty => {
+                matches!(entry_type, WatchlistEntryType::Individual | WatchlistEntryType::Entity)
+            }
+        }
+    }
+}
+
+impl fmt::Display for ScreeningSubject {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScreeningSubject::User => write!(f, "USER"),
+            ScreeningSubject::Counterparty => write!(f, "COUNTERPARTY"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ScreeningTrigger {
+    Signup,
+    NameChange,
+    Rescreen,
+    Withdrawal,
+}
+
+impl ScreeningTrigger {
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "NAME_CHANGE" => ScreeningTrigger::NameChange,
+            "RESCREEN" => ScreeningTrigger::Rescreen,
+            "WITHDRAWAL" => ScreeningTrigger::Withdrawal,
+            _ => ScreeningTrigger::Signup,
+        }
+    }
+
+    // Possible matches always wait for a review. Hits refuse signups and
+    // withdrawals outright, but an existing user stays restricted until an
+    // admin has looked at the hit.
+    pub fn needs_review(&self, decision: ScreeningDecision) -> bool {
+        match decision {
+            ScreeningDecision::Clear => false,
+            ScreeningDecision::Review => true,
+            ScreeningDecision::Block => matches!(self, ScreeningTrigger::NameChange | ScreeningTrigger::Rescreen),
+        }
+    }
+}
+
+impl fmt::Display for ScreeningTrigger {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScreeningTrigger::Signup => write!(f, "SIGNUP"),
+            ScreeningTrigger::NameChange => write!(f, "NAME_CHANGE"),
+            ScreeningTrigger::Rescreen => write!(f, "RESCREEN"),
+            ScreeningTrigger::Withdrawal => write!(f, "WITHDRAWAL"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum ScreeningDecision {
+    Clear,
+    // A possible match, queued for review
+    Review,
+    // A near-exact match: signups and withdrawals are refused, existing users
+    // are restricted until reviewed
+    Block,
+}
+
+impl ScreeningDecision {
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "REVIEW" => ScreeningDecision::Review,
+            "BLOCK" => ScreeningDecision::Block,
+            _ => ScreeningDecision::Clear,
+        }
+    }
+}
+
+impl fmt::Display for ScreeningDecision {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScreeningDecision::Clear => write!(f, "CLEAR"),
+            ScreeningDecision::Review => write!(f, "REVIEW"),
+            ScreeningDecision::Block => write!(f, "BLOCK"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum ScreeningReviewStatus {
+    Pending,
+    // A false positive; the matched entries are not raised for the user again
+    Cleared,
+    Confirmed,
+}
+
+impl ScreeningReviewStatus {
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "CLEARED" => ScreeningReviewStatus::Cleared,
+            "CONFIRMED" => ScreeningReviewStatus::Confirmed,
+            _ => ScreeningReviewStatus::Pending,
+        }
+    }
+}
+
+impl fmt::Display for ScreeningReviewStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScreeningReviewStatus::Pending => write!(f, "PENDING"),
+            ScreeningReviewStatus::Cleared => write!(f, "CLEARED"),
+            ScreeningReviewStatus::Confirmed => write!(f, "CONFIRMED"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScreeningMatch {
+    pub source: WatchlistSource,
+    pub external_id: String,
+    pub entry_type: WatchlistEntryType,
+    pub listed_name: String,
+    // The listed name or alias that scored best
+    pub matched_name: String,
+    // Similarity in percent
+    pub score: i32,
+    pub programs: Vec<String>,
+}
+
+// What the matcher made of a name: the best score and the matches scoring at
+// least the review threshold.
+#[derive(Debug, Clone)]
+pub struct ScreeningOutcome {
+    pub score: i32,
+    pub decision: ScreeningDecision,
+    pub matches: Vec<ScreeningMatch>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ScreeningResult {
+    pub id: Uuid,
+    pub subject_type: ScreeningSubject,
+    pub trigger: ScreeningTrigger,
+    pub user_id: Option<Uuid>,
+    pub transaction_id: Option<Uuid>,
+    pub subject_name: String,
+    pub score: i32,
+    pub decision: ScreeningDecision,
+    pub matches: Vec<ScreeningMatch>,
+    pub review_status: Option<ScreeningReviewStatus>,
+    pub reviewed_by: Option<Uuid>,
+    pub reviewed_at: Option<DateTime<Utc>>,
+    pub review_note: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl TryFrom<Row> for ScreeningResult {
+    type Error = tokio_postgres::Error;
+
+    fn try_from(row: Row) -> Result<Self, Self::Error> {
+        let matches = serde_json::from_value(row.get("matches")).unwrap_or_default();
+        let review_status: Option<&str> = row.get("review_status");
+
+        Ok(ScreeningResult {
+            id: row.get("id"),
+            subject_type: ScreeningSubject::parse(row.get("subject_type")),
+            trigger: ScreeningTrigger::parse(row.get("trigger")),
+            user_id: row.get("user_id"),
+            transaction_id: row.get("transaction_id"),
+            subject_name: row.get("subject_name"),
+            score: row.get("score"),
+            decision: ScreeningDecision::parse(row.get("decision")),
+            matches,
+            review_status: review_status.map(ScreeningReviewStatus::parse),
+            reviewed_by: row.get("reviewed_by"),
+            reviewed_at: row.get("reviewed_at"),
+            review_note: row.get("review_note"),
+            created_at: row.get("created_at"),
+        })
+    }
+}
+
+impl Keyset for ScreeningResult {
+    fn cursor(&self, _sort: SortField) -> Cursor {
+        Cursor {
+            value: CursorValue::Timestamp(self.created_at),
+            id: self.id,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct ScreeningResultPaginationParams {
+    #[serde(alias = "per_page")]
+    #[validate(range(min = 1, max = MAX_PAGE_SIZE, code = "RANGE", message = "limit must be between 1 and 100"))]
+    pub limit: Option<i64>,
+    pub cursor: Option<String>,
+    pub order: Option<SortOrder>,
+    pub subject_type: Option<ScreeningSubject>,
+    pub decision: Option<ScreeningDecision>,
+    pub review_status: Option<ScreeningReviewStatus>,
+    pub user_id: Option<Uuid>,
+    #[serde(default)]
+    pub include_total: bool,
+}
+
+// Outcome of screening every user against the lists.
+#[derive(Debug, Default, Serialize)]
+pub struct RescreenReport {
+    pub screened: usize,
+    pub review: usize,
+    pub block: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WatchlistReloadReport {
+    pub lists: Vec<WatchlistSummary>,
+    pub rescreen: RescreenReport,
+}
@@ -0,0 +1,54 @@
+use chrono::{DateTime, Months, NaiveDate, Utc};
+use serde::Deserialize;
+use validator::{Validate, ValidationError};
+use crate::base::{statements::StatementFormat, validation::field_error};
+
+#[derive(Debug, Deserialize, Validate)]
+#[validate(schema(function = "statement_period"))]
+pub struct StatementParams {
+    // Defaults to the account's creation time
+    pub from: Option<DateTime<Utc>>,
+    // Defaults to the time of the request
+    pub to: Option<DateTime<Utc>>,
+    // Calendar month as `YYYY-MM`, in place of `from` and `to`
+    #[validate(custom(function = "statement_month"))]
+    pub month: Option<String>,
+    #[serde(default)]
+    pub format: StatementFormat,
+}
+
+impl StatementParams {
+    // Requested bounds of the period; `None` falls back to the defaults.
+    pub fn period(&self) -> (Option<DateTime<Utc>>, Option<DateTime<Utc>>) {
+        match self.month.as_deref().and_then(month_start) {
+            Some(start) => (Some(start), start.checked_add_months(Months::new(1))),
+            None => (self.from, self.to),
+        }
+    }
+}
+
+fn month_start(month: &str) -> Option<DateTime<Utc>> {
+    NaiveDate::parse_from_str(&format!("{}-01", month), "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .map(|start| start.and_utc())
+}
+
+fn statement_month(month: &str) -> Result<(), ValidationError> {
+    if month.len() == 7 && month_start(month).is_some() {
+        return Ok(());
+    }
+    Err(ValidationError::new("FORMAT").with_message("month must be formatted as YYYY-MM".into()))
+}
+
+fn statement_period(params: &StatementParams) -> Result<(), ValidationError> {
+    if params.month.is_some() && (params.from.is_some() || params.to.is_some()) {
+        return Err(field_error("month", "EXCLUSIVE", "month can't be combined with from or to"));
+    }
+    if let (Some(from), Some(to)) = (params.from, params.to)
+        && from >= to
+    {
+        return Err(field_error("to", "RANGE", "to must be after from"));
+    }
+    Ok(())
+}
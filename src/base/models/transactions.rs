@@ -2,8 +2,17 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use tokio_postgres::Row;
 use std::convert::TryFrom;
+use std::fmt;
 use uuid::Uuid;
 use rust_decimal::Decimal;
+use validator::Validate;
+use validator::ValidationError;
+use serde_json::{Map, Value};
+use crate::base::{
+    constants::{MAX_DESCRIPTION_LENGTH, MAX_PAGE_SIZE, MAX_SEARCH_LENGTH},
+    pagination::{sort_field_in, Cursor, CursorValue, Keyset, SortField, SortOrder},
+    validation::{currency_code, field_error, metadata_object, non_negative_amount, positive_amount, tag_list},
+};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub enum TransactionType {
@@ -13,16 +22,16 @@ pub enum TransactionType {
     Deposit,
 }
 
-impl ToString for TransactionType {
-    fn to_string(&self) -> String {
+impl fmt::Display for TransactionType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            TransactionType::Withdrawal => "WITHDRAWAL".to_string(),
-            TransactionType::Deposit => "DEPOSIT".to_string(),
+            TransactionType::Withdrawal => write!(f, "WITHDRAWAL"),
+            TransactionType::Deposit => write!(f, "DEPOSIT"),
         }
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
 pub enum TransactionStatus {
     #[serde(rename = "PENDING")]
     Pending,
@@ -32,12 +41,12 @@ pub enum TransactionStatus {
     Failed,
 }
 
-impl ToString for TransactionStatus {
-    fn to_string(&self) -> String {
+impl fmt::Display for TransactionStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            TransactionStatus::Pending => "PENDING".to_string(),
-            TransactionStatus::Completed => "COMPLETED".to_string(),
-            TransactionStatus::Failed => "FAILED".to_string(),
+            TransactionStatus::Pending => write!(f, "PENDING"),
+            TransactionStatus::Completed => write!(f, "COMPLETED"),
+            TransactionStatus::Failed => write!(f, "FAILED"),
         }
     }
 }
@@ -50,6 +59,10 @@ pub struct Transaction {
     pub transaction_type: TransactionType,
     pub status: TransactionStatus,
     pub description: Option<String>,
+    pub metadata: Value,
+    pub tags: Vec<String>,
+    // False when recorded with `POST /transactions`, which doesn't move the balance
+    pub booked: bool,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -80,30 +93,166 @@ impl TryFrom<Row> for Transaction {
             transaction_type,
             status,
             description: row.get("description"),
+            metadata: row.get("metadata"),
+            tags: row.get("tags"),
+            booked: row.get("booked"),
             created_at: row.get("created_at"),
             updated_at: row.get("updated_at"),
         })
     }
 }
 
-#[derive(Debug, Deserialize)]
+impl Keyset for Transaction {
+    fn cursor(&self, sort: SortField) -> Cursor {
+        let value = match sort {
+            SortField::Amount => CursorValue::Decimal(self.amount),
+            _ => CursorValue::Timestamp(self.created_at),
+        };
+        Cursor { value, id: self.id }
+    }
+}
+
+#[derive(Debug, Deserialize, Validate)]
 pub struct CreateTransactionRequest {
     pub account_id: Uuid,
+    #[validate(custom(function = "positive_amount"))]
     pub amount: Decimal,
     pub transaction_type: TransactionType,
+    #[validate(length(max = MAX_DESCRIPTION_LENGTH, code = "MAX_LENGTH", message = "Description must be at most 500 characters"))]
     pub description: Option<String>,
+    #[validate(custom(function = "metadata_object"))]
+    pub metadata: Option<Value>,
+    #[validate(custom(function = "tag_list"))]
+    pub tags: Option<Vec<String>>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Validate)]
 pub struct UpdateTransactionStatusRequest {
     pub status: TransactionStatus,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Validate)]
+#[validate(schema(function = "search_ranges"))]
 pub struct TransactionPaginationParams {
-    pub page: Option<i64>,
-    pub per_page: Option<i64>,
+    #[serde(alias = "per_page")]
+    #[validate(range(min = 1, max = MAX_PAGE_SIZE, code = "RANGE", message = "limit must be between 1 and 100"))]
+    pub limit: Option<i64>,
+    pub cursor: Option<String>,
+    #[validate(custom(function = "transaction_sort_field"))]
+    pub sort: Option<SortField>,
+    pub order: Option<SortOrder>,
+    #[serde(default)]
+    pub include_total: bool,
     pub account_id: Option<Uuid>,
     pub transaction_type: Option<TransactionType>,
     pub status: Option<TransactionStatus>,
-} 
\ No newline at end of file
+    // Inclusive lower and exclusive upper bound on `created_at`
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    #[validate(custom(function = "non_negative_amount"))]
+    pub min_amount: Option<Decimal>,
+    #[validate(custom(function = "non_negative_amount"))]
+    pub max_amount: Option<Decimal>,
+    #[validate(custom(function = "currency_code"))]
+    pub currency: Option<String>,
+    // Full-text search over the description, in web search syntax
+    #[validate(length(min = 1, max = MAX_SEARCH_LENGTH, code = "LENGTH", message = "q must be between 1 and 200 characters"))]
+    pub q: Option<String>,
+    // Comma-separated; transactions must carry all of them
+    #[validate(custom(function = "tag_filter"))]
+    pub tags: Option<String>,
+    // Comma-separated `key:value` pairs matched against metadata; values are JSON or plain text
+    #[validate(custom(function = "metadata_filter"))]
+    pub metadata: Option<String>,
+}
+
+// Conditions shared by the transaction listing and its total count.
+#[derive(Debug, Clone, Default)]
+pub struct TransactionFilter {
+    pub user_id: Uuid,
+    pub account_id: Option<Uuid>,
+    pub transaction_type: Option<TransactionType>,
+    pub status: Option<TransactionStatus>,
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    pub min_amount: Option<Decimal>,
+    pub max_amount: Option<Decimal>,
+    pub currency: Option<String>,
+    pub text: Option<String>,
+    pub tags: Vec<String>,
+    pub metadata: Map<String, Value>,
+}
+
+impl TransactionFilter {
+    pub fn new(user_id: Uuid, params: &TransactionPaginationParams) -> Self {
+        Self {
+            user_id,
+            account_id: params.account_id,
+            transaction_type: params.transaction_type.clone(),
+            status: params.status.clone(),
+            from: params.from,
+            to: params.to,
+            min_amount: params.min_amount,
+            max_amount: params.max_amount,
+            currency: params.currency.clone(),
+            text: params.q.clone(),
+            tags: params.tags.as_deref().map(split_list).unwrap_or_default(),
+            metadata: params
+                .metadata
+                .as_deref()
+                .map(|pairs| {
+                    split_list(pairs)
+                        .into_iter()
+                        .filter_map(|pair| {
+                            let (key, value) = pair.split_once(':')?;
+                            Some((key.trim().to_string(), metadata_value(value.trim())))
+                        })
+                        .collect()
+                })
+                .unwrap_or_default(),
+        }
+    }
+}
+
+// Values are read as JSON, so `42`, `true` and `"42"` match a number, a
+// boolean and a string; anything that isn't JSON is taken as a string.
+fn metadata_value(value: &str) -> Value {
+    serde_json::from_str(value).unwrap_or_else(|_| Value::String(value.to_string()))
+}
+
+fn split_list(value: &str) -> Vec<String> {
+    value
+        .split(',')
+        .map(|item| item.trim().to_string())
+        .filter(|item| !item.is_empty())
+        .collect()
+}
+
+fn search_ranges(params: &TransactionPaginationParams) -> Result<(), ValidationError> {
+    if let (Some(from), Some(to)) = (params.from, params.to)
+        && from >= to
+    {
+        return Err(field_error("to", "RANGE", "to must be after from"));
+    }
+    if let (Some(min), Some(max)) = (params.min_amount, params.max_amount)
+        && min > max
+    {
+        return Err(field_error("max_amount", "RANGE", "max_amount must not be less than min_amount"));
+    }
+    Ok(())
+}
+
+fn tag_filter(tags: &str) -> Result<(), ValidationError> {
+    tag_list(&split_list(tags))
+}
+
+fn metadata_filter(pairs: &str) -> Result<(), ValidationError> {
+    if split_list(pairs).iter().all(|pair| pair.split_once(':').is_some_and(|(key, _)| !key.trim().is_empty())) {
+        return Ok(());
+    }
+    Err(ValidationError::new("FORMAT").with_message("metadata must be comma-separated key:value pairs".into()))
+}
+
+fn transaction_sort_field(sort: &SortField) -> Result<(), ValidationError> {
+    sort_field_in(sort, &[SortField::CreatedAt, SortField::Amount])
+}
@@ -1,15 +1,53 @@
 use std::convert::TryFrom;
+use std::fmt;
 use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
 use tokio_postgres::Row;
 use chrono::{DateTime, Utc};
 use uuid::Uuid;
-use crate::base::utils::ts_rfc3339;
+use validator::Validate;
+use crate::base::{
+    constants::MAX_PAGE_SIZE,
+    models::kyc::{KycStatus, KycTier},
+    pagination::{Cursor, CursorValue, Keyset, SortField, SortOrder},
+    utils::ts_rfc3339,
+    validation::not_blank,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum Role {
+    #[default]
+    User,
+    Admin,
+}
+
+impl Role {
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "ADMIN" => Role::Admin,
+            _ => Role::User,
+        }
+    }
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Role::User => write!(f, "USER"),
+            Role::Admin => write!(f, "ADMIN"),
+        }
+    }
+}
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct User {
     pub id: Uuid,
     pub name: String,
     pub email: String,
+    pub role: Role,
+    pub kyc_tier: KycTier,
+    pub kyc_status: KycStatus,
     #[serde(with = "ts_rfc3339")]
     pub created_at: DateTime<Utc>,
     #[serde(with = "ts_rfc3339")]
@@ -26,6 +64,9 @@ impl TryFrom<Row> for User {
             id: row.get("id"),
             name: row.get("name"),
             email: row.get("email"),
+            role: Role::parse(row.get("role")),
+            kyc_tier: KycTier::parse(row.get("kyc_tier")),
+            kyc_status: KycStatus::parse(row.get("kyc_status")),
             password: row.try_get("password").unwrap_or("".to_string()),
             created_at: row.get("created_at"),
             updated_at: row.get("updated_at"),
@@ -33,23 +74,76 @@ impl TryFrom<Row> for User {
     }
 }
 
-#[derive(Debug, Deserialize)]
+impl User {
+    // What audit entries record about a user. Audit entries can't be erased,
+    // so the name and email are left out.
+    pub fn audit_snapshot(&self) -> Value {
+        json!({
+            "id": self.id,
+            "role": self.role,
+            "kyc_tier": self.kyc_tier,
+            "kyc_status": self.kyc_status,
+            "created_at": self.created_at.to_rfc3339(),
+            "updated_at": self.updated_at.to_rfc3339(),
+        })
+    }
+}
+
+impl Keyset for User {
+    fn cursor(&self, _sort: SortField) -> Cursor {
+        Cursor {
+            value: CursorValue::Timestamp(self.created_at),
+            id: self.id,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Validate)]
 pub struct CreateUserRequest {
+    #[validate(
+        custom(function = "not_blank"),
+        length(max = 255, code = "MAX_LENGTH", message = "Name must be at most 255 characters")
+    )]
     pub name: String,
+    #[validate(email(code = "EMAIL", message = "Email must be a valid email address"))]
     pub email: String,
+    #[validate(length(min = 8, max = 128, code = "LENGTH", message = "Password must be between 8 and 128 characters"))]
     pub password: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Validate)]
 pub struct UpdateUserRequest {
+    #[validate(
+        custom(function = "not_blank"),
+        length(max = 255, code = "MAX_LENGTH", message = "Name must be at most 255 characters")
+    )]
     pub name: Option<String>,
+    #[validate(email(code = "EMAIL", message = "Email must be a valid email address"))]
     pub email: Option<String>,
+    #[validate(length(min = 8, max = 128, code = "LENGTH", message = "Password must be between 8 and 128 characters"))]
     pub password: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+impl UpdateUserRequest {
+    // Names of the fields the request changes, for the audit log.
+    pub fn changed_fields(&self) -> Vec<&'static str> {
+        [
+            ("name", self.name.is_some()),
+            ("email", self.email.is_some()),
+            ("password", self.password.is_some()),
+        ]
+        .into_iter()
+        .filter(|(_, changed)| *changed)
+        .map(|(field, _)| field)
+        .collect()
+    }
+}
+
+#[derive(Debug, Deserialize, Validate)]
 pub struct LoginRequest {
+    #[validate(email(code = "EMAIL", message = "Email must be a valid email address"))]
     pub email: String,
+    #[validate(length(min = 1, code = "REQUIRED", message = "Password is required"))]
     pub password: String,
 }
 
@@ -59,8 +153,39 @@ pub struct LoginResponse {
     pub user: User,
 }
 
-#[derive(Debug, Deserialize)]
+// Users are always listed by creation time
+#[derive(Debug, Deserialize, Validate)]
 pub struct PaginationParams {
-    pub page: Option<i64>,
-    pub per_page: Option<i64>,
-}
\ No newline at end of file
+    #[serde(alias = "per_page")]
+    #[validate(range(min = 1, max = MAX_PAGE_SIZE, code = "RANGE", message = "limit must be between 1 and 100"))]
+    pub limit: Option<i64>,
+    pub cursor: Option<String>,
+    pub order: Option<SortOrder>,
+    #[serde(default)]
+    pub include_total: bool,
+}
+// A login attempt against an existing user.
+#[derive(Debug, Serialize)]
+pub struct LoginEvent {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub succeeded: bool,
+    pub ip_address: Option<String>,
+    pub user_agent: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl TryFrom<Row> for LoginEvent {
+    type Error = tokio_postgres::Error;
+
+    fn try_from(row: Row) -> Result<Self, Self::Error> {
+        Ok(LoginEvent {
+            id: row.get("id"),
+            user_id: row.get("user_id"),
+            succeeded: row.get("succeeded"),
+            ip_address: row.get("ip_address"),
+            user_agent: row.get("user_agent"),
+            created_at: row.get("created_at"),
+        })
+    }
+}
@@ -0,0 +1,246 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio_postgres::Row;
+use std::convert::TryFrom;
+use std::fmt;
+use uuid::Uuid;
+use validator::Validate;
+use crate::base::{
+    constants::{MAX_DESCRIPTION_LENGTH, MAX_PAGE_SIZE, MAX_WEBHOOK_URL_LENGTH},
+    pagination::{Cursor, CursorValue, Keyset, SortField, SortOrder},
+    validation::webhook_url,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum WebhookEventType {
+    #[serde(rename = "transaction.created")]
+    TransactionCreated,
+    #[serde(rename = "transaction.status_changed")]
+    TransactionStatusChanged,
+    #[serde(rename = "account.balance_changed")]
+    AccountBalanceChanged,
+}
+
+impl WebhookEventType {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "transaction.created" => Some(WebhookEventType::TransactionCreated),
+            "transaction.status_changed" => Some(WebhookEventType::TransactionStatusChanged),
+            "account.balance_changed" => Some(WebhookEventType::AccountBalanceChanged),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for WebhookEventType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WebhookEventType::TransactionCreated => write!(f, "transaction.created"),
+            WebhookEventType::TransactionStatusChanged => write!(f, "transaction.status_changed"),
+            WebhookEventType::AccountBalanceChanged => write!(f, "account.balance_changed"),
+        }
+    }
+}
+
+// Event types as stored, without duplicates.
+pub fn event_type_names(event_types: &[WebhookEventType]) -> Vec<String> {
+    let mut event_types = event_types.to_vec();
+    event_types.sort();
+    event_types.dedup();
+    event_types.iter().map(|event_type| event_type.to_string()).collect()
+}
+
+// A registered endpoint. The signing secret is left out; it is only returned
+// once, by `CreatedWebhookEndpoint`.
+#[derive(Debug, Serialize)]
+pub struct WebhookEndpoint {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub url: String,
+    pub event_types: Vec<WebhookEventType>,
+    pub description: Option<String>,
+    pub active: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl TryFrom<Row> for WebhookEndpoint {
+    type Error = tokio_postgres::Error;
+
+    fn try_from(row: Row) -> Result<Self, Self::Error> {
+        let event_types: Vec<String> = row.get("event_types");
+        Ok(WebhookEndpoint {
+            id: row.get("id"),
+            user_id: row.get("user_id"),
+            url: row.get("url"),
+            event_types: event_types.iter().filter_map(|value| WebhookEventType::parse(value)).collect(),
+            description: row.get("description"),
+            active: row.get("active"),
+            created_at: row.get("created_at"),
+            updated_at: row.get("updated_at"),
+        })
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct CreatedWebhookEndpoint {
+    #[serde(flatten)]
+    pub endpoint: WebhookEndpoint,
+    pub secret: String,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateWebhookEndpointRequest {
+    #[validate(
+        length(max = MAX_WEBHOOK_URL_LENGTH, code = "MAX_LENGTH", message = "url must be at most 2048 characters"),
+        custom(function = "webhook_url")
+    )]
+    pub url: String,
+    #[validate(length(min = 1, code = "REQUIRED", message = "At least one event type is required"))]
+    pub event_types: Vec<WebhookEventType>,
+    #[validate(length(max = MAX_DESCRIPTION_LENGTH, code = "MAX_LENGTH", message = "Description must be at most 500 characters"))]
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpdateWebhookEndpointRequest {
+    #[validate(
+        length(max = MAX_WEBHOOK_URL_LENGTH, code = "MAX_LENGTH", message = "url must be at most 2048 characters"),
+        custom(function = "webhook_url")
+    )]
+    pub url: Option<String>,
+    #[validate(length(min = 1, code = "REQUIRED", message = "At least one event type is required"))]
+    pub event_types: Option<Vec<WebhookEventType>>,
+    #[validate(length(max = MAX_DESCRIPTION_LENGTH, code = "MAX_LENGTH", message = "Description must be at most 500 characters"))]
+    pub description: Option<String>,
+    pub active: Option<bool>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum DeliveryStatus {
+    // Waiting for its first attempt or a retry
+    Pending,
+    Delivered,
+    // Gave up after the maximum number of attempts
+    Dead,
+}
+
+impl DeliveryStatus {
+    pub fn parse(value: &str) -> Self {
+        match value {
+            "DELIVERED" => DeliveryStatus::Delivered,
+            "DEAD" => DeliveryStatus::Dead,
+            _ => DeliveryStatus::Pending,
+        }
+    }
+}
+
+impl fmt::Display for DeliveryStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DeliveryStatus::Pending => write!(f, "PENDING"),
+            DeliveryStatus::Delivered => write!(f, "DELIVERED"),
+            DeliveryStatus::Dead => write!(f, "DEAD"),
+        }
+    }
+}
+
+// One event sent to one endpoint, with the outcome of its latest attempt.
+#[derive(Debug, Serialize)]
+pub struct WebhookDelivery {
+    pub id: Uuid,
+    pub endpoint_id: Uuid,
+    pub event_id: Uuid,
+    pub event_type: String,
+    pub payload: Value,
+    pub status: DeliveryStatus,
+    pub attempts: i32,
+    pub next_attempt_at: Option<DateTime<Utc>>,
+    pub last_attempt_at: Option<DateTime<Utc>>,
+    pub response_status: Option<i32>,
+    pub last_error: Option<String>,
+    pub delivered_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl TryFrom<Row> for WebhookDelivery {
+    type Error = tokio_postgres::Error;
+
+    fn try_from(row: Row) -> Result<Self, Self::Error> {
+        let status = DeliveryStatus::parse(row.get("status"));
+        Ok(WebhookDelivery {
+            id: row.get("id"),
+            endpoint_id: row.get("endpoint_id"),
+            event_id: row.get("event_id"),
+            event_type: row.get("event_type"),
+            payload: row.get("payload"),
+            status,
+            // Only meaningful while the delivery is still being attempted
+            next_attempt_at: (status == DeliveryStatus::Pending).then(|| row.get("next_attempt_at")),
+            attempts: row.get("attempts"),
+            last_attempt_at: row.get("last_attempt_at"),
+            response_status: row.get("response_status"),
+            last_error: row.get("last_error"),
+            delivered_at: row.get("delivered_at"),
+            created_at: row.get("created_at"),
+        })
+    }
+}
+
+impl Keyset for WebhookDelivery {
+    fn cursor(&self, _sort: SortField) -> Cursor {
+        Cursor {
+            value: CursorValue::Timestamp(self.created_at),
+            id: self.id,
+        }
+    }
+}
+
+// A delivery claimed by the dispatcher, with what it needs to send it.
+#[derive(Debug)]
+pub struct ClaimedDelivery {
+    pub id: Uuid,
+    pub attempts: i32,
+    pub event_id: Uuid,
+    pub event_type: String,
+    pub payload: Value,
+    pub event_created_at: DateTime<Utc>,
+    pub url: String,
+    pub secret: String,
+}
+
+impl TryFrom<Row> for ClaimedDelivery {
+    type Error = tokio_postgres::Error;
+
+    fn try_from(row: Row) -> Result<Self, Self::Error> {
+        Ok(ClaimedDelivery {
+            id: row.get("id"),
+            attempts: row.get("attempts"),
+            event_id: row.get("event_id"),
+            event_type: row.get("event_type"),
+            payload: row.get("payload"),
+            event_created_at: row.get("event_created_at"),
+            url: row.get("url"),
+            secret: row.get("secret"),
+        })
+    }
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct DeliveryPaginationParams {
+    #[serde(alias = "per_page")]
+    #[validate(range(min = 1, max = MAX_PAGE_SIZE, code = "RANGE", message = "limit must be between 1 and 100"))]
+    pub limit: Option<i64>,
+    pub cursor: Option<String>,
+    pub order: Option<SortOrder>,
+    pub status: Option<DeliveryStatus>,
+    #[serde(default)]
+    pub include_total: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ReplayReport {
+    pub replayed: u64,
+}
@@ -0,0 +1,204 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+use uuid::Uuid;
+use crate::base::{constants::DEFAULT_PAGE_SIZE, error::AppError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortField {
+    #[default]
+    CreatedAt,
+    Amount,
+    Balance,
+}
+
+impl SortField {
+    fn as_str(&self) -> &'static str {
+        match self {
+            SortField::CreatedAt => "created_at",
+            SortField::Amount => "amount",
+            SortField::Balance => "balance",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SortOrder {
+    Asc,
+    #[default]
+    Desc,
+}
+
+impl SortOrder {
+    pub fn sql(&self) -> &'static str {
+        match self {
+            SortOrder::Asc => "ASC",
+            SortOrder::Desc => "DESC",
+        }
+    }
+
+    // Row comparison operator that selects rows after the cursor
+    pub fn comparator(&self) -> &'static str {
+        match self {
+            SortOrder::Asc => ">",
+            SortOrder::Desc => "<",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum CursorValue {
+    Timestamp(DateTime<Utc>),
+    Decimal(Decimal),
+}
+
+impl CursorValue {
+    fn encode(&self) -> String {
+        match self {
+            CursorValue::Timestamp(ts) => ts.to_rfc3339(),
+            CursorValue::Decimal(value) => value.to_string(),
+        }
+    }
+}
+
+// Position after the last row of a page: the sort key of that row plus its id
+// as a tie-breaker, so rows with equal sort keys are neither skipped nor repeated.
+#[derive(Debug, Clone)]
+pub struct Cursor {
+    pub value: CursorValue,
+    pub id: Uuid,
+}
+
+#[derive(Serialize, Deserialize)]
+struct EncodedCursor {
+    sort: SortField,
+    order: SortOrder,
+    value: String,
+    id: Uuid,
+}
+
+impl Cursor {
+    pub fn encode(&self, sort: SortField, order: SortOrder) -> String {
+        let encoded = EncodedCursor {
+            sort,
+            order,
+            value: self.value.encode(),
+            id: self.id,
+        };
+        URL_SAFE_NO_PAD.encode(serde_json::to_vec(&encoded).unwrap_or_default())
+    }
+
+    // Cursors are opaque to clients and only valid for the sort they were issued for.
+    pub fn decode(token: &str, sort: SortField, order: SortOrder) -> Result<Self, AppError> {
+        let invalid = || AppError::invalid_field("cursor", "INVALID_CURSOR", "Cursor is malformed or expired");
+
+        let bytes = URL_SAFE_NO_PAD.decode(token).map_err(|_| invalid())?;
+        let encoded: EncodedCursor = serde_json::from_slice(&bytes).map_err(|_| invalid())?;
+
+        if encoded.sort != sort || encoded.order != order {
+            return Err(AppError::invalid_field(
+                "cursor",
+                "CURSOR_MISMATCH",
+                "Cursor was issued for a different sort or order",
+            ));
+        }
+
+        let value = match sort {
+            SortField::CreatedAt => DateTime::parse_from_rfc3339(&encoded.value)
+                .map(|ts| CursorValue::Timestamp(ts.with_timezone(&Utc)))
+                .map_err(|_| invalid())?,
+            SortField::Amount | SortField::Balance => Decimal::from_str(&encoded.value)
+                .map(CursorValue::Decimal)
+                .map_err(|_| invalid())?,
+        };
+
+        Ok(Cursor { value, id: encoded.id })
+    }
+}
+
+// Implemented by models that can be listed with keyset pagination.
+pub trait Keyset {
+    fn cursor(&self, sort: SortField) -> Cursor;
+}
+
+// Validated paging request handed to the DAL.
+#[derive(Debug, Clone)]
+pub struct PageRequest {
+    pub limit: i64,
+    pub cursor: Option<Cursor>,
+    pub sort: SortField,
+    pub order: SortOrder,
+}
+
+impl PageRequest {
+    pub fn new(
+        limit: Option<i64>,
+        cursor: Option<&str>,
+        sort: Option<SortField>,
+        order: Option<SortOrder>,
+    ) -> Result<Self, AppError> {
+        let sort = sort.unwrap_or_default();
+        let order = order.unwrap_or_default();
+        let cursor = cursor
+            .filter(|token| !token.is_empty())
+            .map(|token| Cursor::decode(token, sort, order))
+            .transpose()?;
+
+        Ok(Self {
+            limit: limit.unwrap_or(DEFAULT_PAGE_SIZE),
+            cursor,
+            sort,
+            order,
+        })
+    }
+
+    // One extra row is fetched to know whether another page exists
+    pub fn fetch_limit(&self) -> i64 {
+        self.limit + 1
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Page<T> {
+    pub data: Vec<T>,
+    pub next_cursor: Option<String>,
+    pub has_more: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub total_count: Option<i64>,
+}
+
+impl<T: Keyset> Page<T> {
+    pub fn new(mut rows: Vec<T>, request: &PageRequest, total_count: Option<i64>) -> Self {
+        let has_more = rows.len() as i64 > request.limit;
+        rows.truncate(request.limit as usize);
+
+        let next_cursor = if has_more {
+            rows.last()
+                .map(|row| row.cursor(request.sort).encode(request.sort, request.order))
+        } else {
+            None
+        };
+
+        Self {
+            data: rows,
+            next_cursor,
+            has_more,
+            total_count,
+        }
+    }
+}
+
+// Rejects sort fields a resource doesn't support, from `#[validate(custom)]`.
+pub fn sort_field_in(sort: &SortField, allowed: &[SortField]) -> Result<(), validator::ValidationError> {
+    if allowed.contains(sort) {
+        return Ok(());
+    }
+
+    let names: Vec<&str> = allowed.iter().map(SortField::as_str).collect();
+    Err(validator::ValidationError::new("SORT_FIELD")
+        .with_message(format!("sort must be one of: {}", names.join(", ")).into()))
+}
@@ -0,0 +1,277 @@
+use chrono::NaiveDate;
+use quick_xml::{events::Event, Reader};
+use rust_decimal::Decimal;
+use std::str::FromStr;
+use super::{ParsedEntry, ParsedStatement};
+
+// Reference elements under `Ntry` and `NtryDtls/TxDtls/Refs`.
+const REFERENCE_ELEMENTS: &[&str] = &["NtryRef", "AcctSvcrRef", "EndToEndId", "InstrId", "TxId", "MsgId", "PmtInfId"];
+
+#[derive(Default)]
+struct EntryBuilder {
+    amount: Option<Decimal>,
+    currency: Option<String>,
+    credit: Option<bool>,
+    booking_date: Option<NaiveDate>,
+    value_date: Option<NaiveDate>,
+    status: Option<String>,
+    references: Vec<String>,
+    remittance: Vec<String>,
+    additional_info: Option<String>,
+}
+
+// ISO 20022 camt.053 bank-to-customer statement, any version: elements are
+// matched by local name so the namespace doesn't matter. Only the first `Stmt`
+// provides the statement and account references.
+pub fn parse(content: &str) -> Result<ParsedStatement, String> {
+    let mut reader = Reader::from_str(content);
+    reader.config_mut().trim_text(true);
+
+    let mut statement = ParsedStatement::default();
+    let mut path: Vec<String> = Vec::new();
+    let mut entry: Option<EntryBuilder> = None;
+    let mut entry_count = 0;
+
+    loop {
+        let event = reader
+            .read_event()
+            .map_err(|e| format!("invalid XML at byte {}: {}", reader.buffer_position(), e))?;
+
+        match event {
+            Event::Start(element) => {
+                let name = String::from_utf8_lossy(element.local_name().as_ref()).into_owned();
+
+                if name == "Ntry" {
+                    entry = Some(EntryBuilder::default());
+                    entry_count += 1;
+                } else if name == "Amt"
+                    && path.last().is_some_and(|parent| parent == "Ntry")
+                    && let Some(entry) = entry.as_mut()
+                {
+                    entry.currency = element
+                        .try_get_attribute("Ccy")
+                        .ok()
+                        .flatten()
+                        .and_then(|attribute| attribute.unescape_value().ok())
+                        .map(|value| value.into_owned());
+                }
+
+                path.push(name);
+            }
+            Event::End(_) => {
+                if path.pop().as_deref() == Some("Ntry")
+                    && let Some(builder) = entry.take()
+                {
+                    let parsed = finish_entry(builder, entry_count, statement.currency.clone())
+                        .map_err(|e| format!("entry {}: {}", entry_count, e))?;
+                    statement.entries.push(parsed);
+                }
+            }
+            Event::Text(text) => {
+                let value = text
+                    .unescape()
+                    .map_err(|e| format!("invalid text at byte {}: {}", reader.buffer_position(), e))?
+                    .trim()
+                    .to_string();
+                if value.is_empty() {
+                    continue;
+                }
+
+                match entry.as_mut() {
+                    Some(entry) => entry_text(entry, &path, value)?,
+                    None => statement_text(&mut statement, &path, value),
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    Ok(statement)
+}
+
+fn ends_with(path: &[String], suffix: &[&str]) -> bool {
+    path.len() >= suffix.len()
+        && path[path.len() - suffix.len()..]
+            .iter()
+            .zip(suffix)
+            .all(|(element, expected)| element == expected)
+}
+
+fn statement_text(statement: &mut ParsedStatement, path: &[String], value: String) {
+    if ends_with(path, &["Stmt", "Id"]) {
+        statement.statement_reference.get_or_insert(value);
+    } else if ends_with(path, &["Stmt", "Acct", "Id", "IBAN"]) || ends_with(path, &["Stmt", "Acct", "Id", "Othr", "Id"]) {
+        statement.account_reference.get_or_insert(value);
+    } else if ends_with(path, &["Stmt", "Acct", "Ccy"]) {
+        statement.currency.get_or_insert(value);
+    }
+}
+
+fn entry_text(entry: &mut EntryBuilder, path: &[String], value: String) -> Result<(), String> {
+    if ends_with(path, &["Ntry", "Amt"]) {
+        entry.amount = Some(Decimal::from_str(&value).map_err(|_| format!("invalid amount `{}`", value))?);
+    } else if ends_with(path, &["Ntry", "CdtDbtInd"]) {
+        entry.credit = match value.as_str() {
+            "CRDT" => Some(true),
+            "DBIT" => Some(false),
+            _ => return Err(format!("invalid CdtDbtInd `{}`", value)),
+        };
+    } else if ends_with(path, &["Ntry", "Sts"]) || ends_with(path, &["Ntry", "Sts", "Cd"]) {
+        entry.status = Some(value);
+    } else if ends_with(path, &["Ntry", "BookgDt", "Dt"]) || ends_with(path, &["Ntry", "BookgDt", "DtTm"]) {
+        entry.booking_date = Some(date(&value)?);
+    } else if ends_with(path, &["Ntry", "ValDt", "Dt"]) || ends_with(path, &["Ntry", "ValDt", "DtTm"]) {
+        entry.value_date = Some(date(&value)?);
+    } else if ends_with(path, &["RmtInf", "Ustrd"]) {
+        entry.remittance.push(value);
+    } else if ends_with(path, &["Ntry", "AddtlNtryInf"]) {
+        entry.additional_info = Some(value);
+    } else if let Some(element) = path.last()
+        && REFERENCE_ELEMENTS.contains(&element.as_str())
+        && (path.len() >= 2 && path[path.len() - 2] == "Ntry" || ends_with(&path[..path.len() - 1], &["Refs"]))
+        && !value.eq_ignore_ascii_case("NOTPROVIDED")
+        && !entry.references.contains(&value)
+    {
+        entry.references.push(value);
+    }
+    Ok(())
+}
+
+fn finish_entry(entry: EntryBuilder, position: usize, currency: Option<String>) -> Result<ParsedEntry, String> {
+    let amount = entry.amount.ok_or("missing Amt")?.round_dp(2);
+    let credit = entry.credit.ok_or("missing CdtDbtInd")?;
+    let booking_date = entry
+        .booking_date
+        .or(entry.value_date)
+        .ok_or("missing BookgDt")?;
+
+    let description = if entry.remittance.is_empty() {
+        entry.additional_info
+    } else {
+        Some(entry.remittance.join(" "))
+    };
+
+    Ok(ParsedEntry {
+        line_number: position as i32,
+        booking_date,
+        amount: if credit { amount } else { -amount },
+        currency: entry.currency.or(currency),
+        references: entry.references,
+        description,
+        booked: entry.status.as_deref().is_none_or(|status| status == "BOOK"),
+    })
+}
+
+// `Dt` is a date, `DtTm` a date-time; both start with YYYY-MM-DD.
+fn date(value: &str) -> Result<NaiveDate, String> {
+    value
+        .get(..10)
+        .and_then(|day| NaiveDate::parse_from_str(day, "%Y-%m-%d").ok())
+        .ok_or_else(|| format!("invalid date `{}`", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dec(value: &str) -> Decimal {
+        value.parse().unwrap()
+    }
+
+    fn document(entries: &str) -> String {
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<Document xmlns="urn:iso:std:iso:20022:tech:xsd:camt.053.001.08">
+ <BkToCstmrStmt>
+  <GrpHdr><MsgId>MSG1</MsgId></GrpHdr>
+  <Stmt>
+   <Id>CAMT-STMT-7</Id>
+   <Acct><Id><IBAN>DE89370400440532013000</IBAN></Id><Ccy>INR</Ccy></Acct>
+   {}
+  </Stmt>
+ </BkToCstmrStmt>
+</Document>"#,
+            entries
+        )
+    }
+
+    #[test]
+    fn reads_statement_fields() {
+        let statement = parse(&document(
+            "<Ntry><Amt>1.00</Amt><CdtDbtInd>CRDT</CdtDbtInd><BookgDt><Dt>2026-10-19</Dt></BookgDt></Ntry>",
+        ))
+        .unwrap();
+
+        assert_eq!(statement.statement_reference.as_deref(), Some("CAMT-STMT-7"));
+        assert_eq!(statement.account_reference.as_deref(), Some("DE89370400440532013000"));
+        assert_eq!(statement.currency.as_deref(), Some("INR"));
+        assert_eq!(statement.entries.len(), 1);
+        assert_eq!(statement.entries[0].currency.as_deref(), Some("INR"));
+    }
+
+    #[test]
+    fn reads_entries() {
+        let statement = parse(&document(
+            r#"<Ntry>
+                <NtryRef>NREF-1</NtryRef>
+                <Amt Ccy="EUR">250.50</Amt><CdtDbtInd>CRDT</CdtDbtInd><Sts><Cd>BOOK</Cd></Sts>
+                <BookgDt><DtTm>2026-10-19T10:00:00</DtTm></BookgDt>
+                <NtryDtls><TxDtls>
+                 <Refs><EndToEndId>9ccc2c79-3edd-4326-85fd-1a31458c51a7</EndToEndId><TxId>NOTPROVIDED</TxId><InstrId>NREF-1</InstrId></Refs>
+                 <RmtInf><Ustrd>Deposit &amp; settlement</Ustrd><Ustrd>part 2</Ustrd></RmtInf>
+                </TxDtls></NtryDtls>
+               </Ntry>
+               <Ntry>
+                <Amt Ccy="INR">5.00</Amt><CdtDbtInd>DBIT</CdtDbtInd><Sts>PDNG</Sts>
+                <ValDt><Dt>2026-10-20</Dt></ValDt>
+                <AddtlNtryInf>Card fee</AddtlNtryInf>
+               </Ntry>"#,
+        ))
+        .unwrap();
+
+        let first = &statement.entries[0];
+        assert_eq!(first.line_number, 1);
+        assert_eq!(first.amount, dec("250.50"));
+        assert_eq!(first.currency.as_deref(), Some("EUR"));
+        assert_eq!(first.booking_date, NaiveDate::from_ymd_opt(2026, 10, 19).unwrap());
+        assert_eq!(first.references, vec!["NREF-1", "9ccc2c79-3edd-4326-85fd-1a31458c51a7"]);
+        assert_eq!(first.description.as_deref(), Some("Deposit & settlement part 2"));
+        assert!(first.booked);
+
+        let second = &statement.entries[1];
+        assert_eq!(second.line_number, 2);
+        assert_eq!(second.amount, dec("-5.00"));
+        assert_eq!(second.booking_date, NaiveDate::from_ymd_opt(2026, 10, 20).unwrap());
+        assert_eq!(second.description.as_deref(), Some("Card fee"));
+        assert!(!second.booked);
+    }
+
+    #[test]
+    fn matches_elements_whatever_the_namespace() {
+        let content = r#"<c:Document xmlns:c="urn:iso:std:iso:20022:tech:xsd:camt.053.001.02"><c:BkToCstmrStmt><c:Stmt>
+            <c:Id>S-2</c:Id>
+            <c:Acct><c:Id><c:Othr><c:Id>12345678</c:Id></c:Othr></c:Id></c:Acct>
+            <c:Ntry><c:Amt Ccy="USD">10</c:Amt><c:CdtDbtInd>DBIT</c:CdtDbtInd><c:BookgDt><c:Dt>2026-01-05</c:Dt></c:BookgDt></c:Ntry>
+            </c:Stmt></c:BkToCstmrStmt></c:Document>"#;
+
+        let statement = parse(content).unwrap();
+
+        assert_eq!(statement.statement_reference.as_deref(), Some("S-2"));
+        assert_eq!(statement.account_reference.as_deref(), Some("12345678"));
+        assert_eq!(statement.entries[0].amount, dec("-10"));
+        assert_eq!(statement.entries[0].currency.as_deref(), Some("USD"));
+    }
+
+    #[test]
+    fn reports_incomplete_entries() {
+        let missing_mark = parse(&document("<Ntry><Amt>1.00</Amt><BookgDt><Dt>2026-10-19</Dt></BookgDt></Ntry>"));
+        assert_eq!(missing_mark.unwrap_err(), "entry 1: missing CdtDbtInd");
+
+        let bad_mark = parse(&document("<Ntry><Amt>1.00</Amt><CdtDbtInd>X</CdtDbtInd></Ntry>"));
+        assert_eq!(bad_mark.unwrap_err(), "invalid CdtDbtInd `X`");
+
+        let missing_date = parse(&document("<Ntry><Amt>1.00</Amt><CdtDbtInd>CRDT</CdtDbtInd></Ntry>"));
+        assert_eq!(missing_date.unwrap_err(), "entry 1: missing BookgDt");
+    }
+}
@@ -0,0 +1,73 @@
+mod camt053;
+mod mt940;
+
+use chrono::NaiveDate;
+use regex::Regex;
+use rust_decimal::Decimal;
+use std::sync::LazyLock;
+use uuid::Uuid;
+use crate::base::models::reconciliation::StatementFileFormat;
+
+// Bank statement as read from a settlement file, before it is matched.
+#[derive(Debug, Default)]
+pub struct ParsedStatement {
+    pub statement_reference: Option<String>,
+    pub account_reference: Option<String>,
+    pub currency: Option<String>,
+    pub entries: Vec<ParsedEntry>,
+}
+
+#[derive(Debug)]
+pub struct ParsedEntry {
+    // Line of the `:61:` field in MT940, position of the `Ntry` in camt.053
+    pub line_number: i32,
+    pub booking_date: NaiveDate,
+    // Credits positive, debits negative
+    pub amount: Decimal,
+    pub currency: Option<String>,
+    pub references: Vec<String>,
+    pub description: Option<String>,
+    // Pending entries are reported but never settle a transaction
+    pub booked: bool,
+}
+
+impl ParsedEntry {
+    // Our statements and payouts carry the transaction id, with or without
+    // hyphens, in one of the reference fields or in the narrative.
+    pub fn transaction_reference(&self) -> Option<Uuid> {
+        self.references
+            .iter()
+            .map(String::as_str)
+            .chain(self.description.as_deref())
+            .find_map(|text| {
+                UUID_PATTERN
+                    .find(text)
+                    .and_then(|found| Uuid::parse_str(found.as_str()).ok())
+            })
+    }
+}
+
+static UUID_PATTERN: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)\b[0-9a-f]{8}-?[0-9a-f]{4}-?[0-9a-f]{4}-?[0-9a-f]{4}-?[0-9a-f]{12}\b").unwrap()
+});
+
+// camt.053 is XML; anything else is treated as MT940.
+pub fn detect_format(content: &str) -> StatementFileFormat {
+    if content.trim_start_matches('\u{feff}').trim_start().starts_with('<') {
+        StatementFileFormat::Camt053
+    } else {
+        StatementFileFormat::Mt940
+    }
+}
+
+pub fn parse(format: StatementFileFormat, content: &str) -> Result<ParsedStatement, String> {
+    let statement = match format {
+        StatementFileFormat::Mt940 => mt940::parse(content)?,
+        StatementFileFormat::Camt053 => camt053::parse(content)?,
+    };
+
+    if statement.entries.is_empty() {
+        return Err("File contains no statement entries".into());
+    }
+    Ok(statement)
+}
@@ -0,0 +1,261 @@
+use chrono::{Months, NaiveDate};
+use regex::Regex;
+use rust_decimal::Decimal;
+use std::{str::FromStr, sync::LazyLock};
+use super::{ParsedEntry, ParsedStatement};
+
+// `:61:` statement line: value date, optional entry date, debit/credit mark,
+// optional funds code, amount, transaction type, customer and bank reference.
+static STATEMENT_LINE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^(?P<value>\d{6})(?P<entry>\d{4})?(?P<mark>R?[CD])(?P<funds>[A-Z])?(?P<amount>\d{1,15},\d{0,2})(?P<kind>[NFS][A-Z0-9]{3})(?P<customer>[^/]*?)(?://(?P<bank>.*))?$").unwrap()
+});
+
+static FIELD: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^:(?P<tag>\d{2}[A-Z]?):(?P<value>.*)$").unwrap());
+
+struct Field {
+    tag: String,
+    line: usize,
+    value: String,
+}
+
+// SWIFT MT940 customer statement. Block headers (`{1:...}`) and the `-}`
+// trailer are ignored, so both raw messages and bare field lists are accepted.
+pub fn parse(content: &str) -> Result<ParsedStatement, String> {
+    let mut statement = ParsedStatement::default();
+
+    for field in fields(content) {
+        match field.tag.as_str() {
+            "20" => {
+                statement.statement_reference.get_or_insert(field.value.trim().to_string());
+            }
+            "25" => {
+                statement.account_reference.get_or_insert(field.value.trim().to_string());
+            }
+            "60F" | "60M" => {
+                // Opening balance: D/C mark, date, then the currency
+                let currency = field.value.get(7..10).map(str::to_string);
+                if statement.currency.is_none() {
+                    statement.currency = currency;
+                }
+            }
+            "61" => {
+                let entry = statement_line(&field, statement.currency.clone())
+                    .map_err(|e| format!("line {}: {}", field.line, e))?;
+                statement.entries.push(entry);
+            }
+            "86" => {
+                // Narrative belongs to the statement line right before it
+                if let Some(entry) = statement.entries.last_mut()
+                    && entry.description.is_none()
+                {
+                    let narrative: Vec<&str> = field.value.lines().map(str::trim).filter(|line| !line.is_empty()).collect();
+                    entry.description = Some(narrative.join(" "));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(statement)
+}
+
+fn fields(content: &str) -> Vec<Field> {
+    let mut fields: Vec<Field> = Vec::new();
+
+    for (index, raw) in content.lines().enumerate() {
+        let line = raw.trim_end_matches('\r');
+        if line.starts_with('{') || line == "-}" || line == "-" {
+            continue;
+        }
+
+        if let Some(captures) = FIELD.captures(line) {
+            fields.push(Field {
+                tag: captures["tag"].to_string(),
+                line: index + 1,
+                value: captures["value"].to_string(),
+            });
+        } else if let Some(field) = fields.last_mut() {
+            field.value.push('\n');
+            field.value.push_str(line);
+        }
+    }
+
+    fields
+}
+
+fn statement_line(field: &Field, currency: Option<String>) -> Result<ParsedEntry, String> {
+    let mut lines = field.value.lines();
+    let first = lines.next().unwrap_or_default().trim();
+    let supplementary = lines.next().map(|line| line.trim().to_string());
+
+    let captures = STATEMENT_LINE
+        .captures(first)
+        .ok_or_else(|| format!("malformed :61: statement line `{}`", first))?;
+
+    let value_date = date(&captures["value"])?;
+    let booking_date = match captures.name("entry") {
+        Some(entry) => entry_date(value_date, entry.as_str())?,
+        None => value_date,
+    };
+
+    let amount = Decimal::from_str(&format!("{}0", captures["amount"].replace(',', ".")))
+        .map_err(|_| format!("invalid amount `{}`", &captures["amount"]))?
+        .round_dp(2);
+    // A reversal of a credit is a debit and vice versa
+    let amount = match &captures["mark"] {
+        "C" | "RD" => amount,
+        _ => -amount,
+    };
+
+    let mut references = Vec::new();
+    for name in ["customer", "bank"] {
+        if let Some(reference) = captures.name(name).map(|m| m.as_str().trim())
+            && !reference.is_empty()
+            && reference != "NONREF"
+        {
+            references.push(reference.to_string());
+        }
+    }
+    references.extend(supplementary.filter(|details| !details.is_empty()));
+
+    Ok(ParsedEntry {
+        line_number: field.line as i32,
+        booking_date,
+        amount,
+        currency,
+        references,
+        description: None,
+        booked: true,
+    })
+}
+
+fn date(yymmdd: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(&format!("20{}", yymmdd), "%Y%m%d").map_err(|_| format!("invalid date `{}`", yymmdd))
+}
+
+// The entry date has no year; around New Year it can fall into the year
+// after the value date or the one before it.
+fn entry_date(value_date: NaiveDate, mmdd: &str) -> Result<NaiveDate, String> {
+    let year = value_date.format("%Y").to_string();
+    let same_year = NaiveDate::parse_from_str(&format!("{}{}", year, mmdd), "%Y%m%d")
+        .map_err(|_| format!("invalid entry date `{}`", mmdd))?;
+
+    let days = (same_year - value_date).num_days();
+    let shifted = if days < -180 {
+        same_year.checked_add_months(Months::new(12))
+    } else if days > 180 {
+        same_year.checked_sub_months(Months::new(12))
+    } else {
+        Some(same_year)
+    };
+    shifted.ok_or_else(|| format!("invalid entry date `{}`", mmdd))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dec(value: &str) -> Decimal {
+        value.parse().unwrap()
+    }
+
+    fn day(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn entry(line: &str) -> ParsedEntry {
+        let statement = parse(&format!(":60F:C261019INR0,00\n:61:{}\n", line)).unwrap();
+        statement.entries.into_iter().next().unwrap()
+    }
+
+    #[test]
+    fn reads_statement_fields_and_skips_block_headers() {
+        let content = "{1:F01BANKDEFFAXXX0000000000}{2:I940BANKDEFFXXXXN}{4:\r\n\
+                       :20:STMT-1\r\n\
+                       :25:DE89370400440532013000\r\n\
+                       :28C:00001/001\r\n\
+                       :60F:C261019INR0,00\r\n\
+                       :61:261019C100,00NTRFNONREF\r\n\
+                       :61:261019D40,00NTRFNONREF\r\n\
+                       :62F:C261019INR60,00\r\n\
+                       -}";
+
+        let statement = parse(content).unwrap();
+
+        assert_eq!(statement.statement_reference.as_deref(), Some("STMT-1"));
+        assert_eq!(statement.account_reference.as_deref(), Some("DE89370400440532013000"));
+        assert_eq!(statement.currency.as_deref(), Some("INR"));
+        assert_eq!(statement.entries.len(), 2);
+        assert_eq!(statement.entries[0].line_number, 6);
+        assert_eq!(statement.entries[0].currency.as_deref(), Some("INR"));
+        assert!(statement.entries[0].booked);
+    }
+
+    #[test]
+    fn signs_amounts_by_mark_and_reversal() {
+        assert_eq!(entry("261019C100,00NTRFNONREF").amount, dec("100.00"));
+        assert_eq!(entry("261019D100,00NTRFNONREF").amount, dec("-100.00"));
+        assert_eq!(entry("261019RC100,00NTRFNONREF").amount, dec("-100.00"));
+        assert_eq!(entry("261019RD100,00NTRFNONREF").amount, dec("100.00"));
+    }
+
+    #[test]
+    fn skips_the_funds_code() {
+        assert_eq!(entry("261019CR100,00NTRFNONREF").amount, dec("100.00"));
+        assert_eq!(entry("261019RDR100,00NTRFNONREF").amount, dec("100.00"));
+    }
+
+    #[test]
+    fn reads_amounts_without_decimals() {
+        assert_eq!(entry("261019C1000,NTRFNONREF").amount, dec("1000.00"));
+        assert_eq!(entry("261019C12,3NTRFNONREF").amount, dec("12.30"));
+    }
+
+    #[test]
+    fn keeps_references_except_nonref() {
+        assert!(entry("261019C100,00NTRFNONREF").references.is_empty());
+        assert_eq!(entry("261019C100,00NTRFNONREF//BANK1").references, vec!["BANK1"]);
+        assert_eq!(entry("261019C100,00NMSCCUST-7//BANK1").references, vec!["CUST-7", "BANK1"]);
+    }
+
+    #[test]
+    fn adds_supplementary_details_from_the_continuation_line() {
+        let content = ":61:261019C100,00NTRFNONREF//BANK1\n\
+                       c1d3812b-3807-43af-80ce-d5da928a501a\n\
+                       :86:Payout for\n 9ccc2c79 settlement\n";
+
+        let statement = parse(content).unwrap();
+        let entry = &statement.entries[0];
+
+        assert_eq!(entry.references, vec!["BANK1", "c1d3812b-3807-43af-80ce-d5da928a501a"]);
+        assert_eq!(entry.description.as_deref(), Some("Payout for 9ccc2c79 settlement"));
+        assert_eq!(
+            entry.transaction_reference(),
+            Some(uuid::Uuid::parse_str("c1d3812b-3807-43af-80ce-d5da928a501a").unwrap())
+        );
+    }
+
+    #[test]
+    fn books_on_the_entry_date_in_the_same_year() {
+        assert_eq!(entry("2610191020C100,00NTRFNONREF").booking_date, day(2026, 10, 20));
+        assert_eq!(entry("261019C100,00NTRFNONREF").booking_date, day(2026, 10, 19));
+    }
+
+    #[test]
+    fn wraps_the_entry_date_into_the_next_year() {
+        assert_eq!(entry("2612310102C100,00NTRFNONREF").booking_date, day(2027, 1, 2));
+    }
+
+    #[test]
+    fn wraps_the_entry_date_into_the_previous_year() {
+        assert_eq!(entry("2401021231C100,00NTRFNONREF").booking_date, day(2023, 12, 31));
+    }
+
+    #[test]
+    fn reports_malformed_lines_with_their_line_number() {
+        let error = parse(":20:STMT-1\n:61:261019X100,00NTRFNONREF\n").unwrap_err();
+
+        assert!(error.starts_with("line 2: malformed :61: statement line"), "{}", error);
+        assert_eq!(parse(":61:261399C100,00NTRFNONREF").unwrap_err(), "line 1: invalid date `261399`");
+    }
+}
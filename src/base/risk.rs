@@ -0,0 +1,298 @@
+use chrono::{DateTime, Duration, Utc};
+use rust_decimal::Decimal;
+use serde_json::Value;
+use crate::base::{
+    constants::{RISK_BLOCK_SCORE, RISK_REVIEW_SCORE},
+    error::AppError,
+    models::{
+        accounts::Account,
+        risk::{MatchedRule, RiskAction, RiskDecision, RiskFacts, RiskRule, RiskRuleCode, RiskRuleParams},
+    },
+    validation::parse_params as parse,
+};
+
+// Reads and validates `params` for the rule `code`. Errors are reported
+// under `params`, e.g. `params.max_count`.
+pub fn parse_params(code: RiskRuleCode, params: &Value) -> Result<RiskRuleParams, AppError> {
+    Ok(match code {
+        RiskRuleCode::NewAccountLargeWithdrawal => RiskRuleParams::NewAccountLargeWithdrawal(parse(params)?),
+        RiskRuleCode::RapidWithdrawals => RiskRuleParams::RapidWithdrawals(parse(params)?),
+        RiskRuleCode::NewIp => RiskRuleParams::NewIp(parse(params)?),
+        RiskRuleCode::AmountAboveHistory => RiskRuleParams::AmountAboveHistory(parse(params)?),
+    })
+}
+
+// The enabled rules with their params. Params are validated when they are
+// set, so a rule whose stored params don't parse is skipped with a warning.
+pub fn active_rules(rules: Vec<RiskRule>) -> Vec<(RiskRule, RiskRuleParams)> {
+    rules
+        .into_iter()
+        .filter(|rule| rule.enabled)
+        .filter_map(|rule| match parse_params(rule.code, &rule.params) {
+            Ok(params) => Some((rule, params)),
+            Err(_) => {
+                tracing::warn!(rule = %rule.code, "Skipping risk rule with invalid params");
+                None
+            }
+        })
+        .collect()
+}
+
+// Start of the RAPID_WITHDRAWALS window and of the AMOUNT_ABOVE_HISTORY
+// lookback; `now` when the rule is not active, so nothing is counted.
+pub fn fact_windows(rules: &[(RiskRule, RiskRuleParams)], now: DateTime<Utc>) -> (DateTime<Utc>, DateTime<Utc>) {
+    let mut recent_since = now;
+    let mut history_since = now;
+    for (_, params) in rules {
+        match params {
+            RiskRuleParams::RapidWithdrawals(params) => recent_since = now - Duration::minutes(params.window_minutes),
+            RiskRuleParams::AmountAboveHistory(params) => history_since = now - Duration::days(params.lookback_days),
+            _ => {}
+        }
+    }
+    (recent_since, history_since)
+}
+
+// Scores a debit of `amount` from `account`: each matching rule adds its score,
+// and the total decides the action.
+pub fn evaluate(
+    rules: &[(RiskRule, RiskRuleParams)],
+    account: &Account,
+    amount: Decimal,
+    facts: &RiskFacts,
+    now: DateTime<Utc>,
+) -> RiskDecision {
+    let matched_rules: Vec<MatchedRule> = rules
+        .iter()
+        .filter_map(|(rule, params)| {
+            reason(params, account, amount, facts, now).map(|reason| MatchedRule {
+                rule: rule.code,
+                score: rule.score,
+                reason,
+            })
+        })
+        .collect();
+
+    let score = matched_rules.iter().map(|matched| matched.score).sum();
+
+    RiskDecision { score, action: action(score), matched_rules }
+}
+
+// The action for a total score.
+fn action(score: i32) -> RiskAction {
+    if score >= RISK_BLOCK_SCORE {
+        RiskAction::Block
+    } else if score >= RISK_REVIEW_SCORE {
+        RiskAction::Review
+    } else {
+        RiskAction::Allow
+    }
+}
+
+// Why the rule matches, or `None` when it doesn't.
+fn reason(
+    params: &RiskRuleParams,
+    account: &Account,
+    amount: Decimal,
+    facts: &RiskFacts,
+    now: DateTime<Utc>,
+) -> Option<String> {
+    match params {
+        RiskRuleParams::NewAccountLargeWithdrawal(params) => {
+            let new_account = now - account.created_at < Duration::days(params.max_account_age_days);
+            (new_account && amount >= params.min_amount).then(|| {
+                format!(
+                    "Debit of {} {} from an account opened less than {} days ago",
+                    amount, account.currency, params.max_account_age_days
+                )
+            })
+        }
+        RiskRuleParams::RapidWithdrawals(params) => (facts.recent_debits >= params.max_count).then(|| {
+            format!(
+                "{} debits from the account in the last {} minutes",
+                facts.recent_debits + 1,
+                params.window_minutes
+            )
+        }),
+        // Without a login on record every address would be new
+        RiskRuleParams::NewIp(_) => (facts.has_logins && !facts.known_ip)
+            .then(|| "Request from an IP address the user never logged in from".to_string()),
+        RiskRuleParams::AmountAboveHistory(params) => {
+            let average = facts.history_average.filter(|_| facts.history_count >= params.min_history)?;
+            (amount > average * params.multiplier).then(|| {
+                format!(
+                    "Debit of {} {} is over {} times the user's average debit of {} {}",
+                    amount,
+                    account.currency,
+                    params.multiplier,
+                    average.round_dp(2),
+                    account.currency
+                )
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use uuid::Uuid;
+    use crate::base::models::accounts::AccountStatus;
+
+    fn dec(value: &str) -> Decimal {
+        value.parse().unwrap()
+    }
+
+    fn now() -> DateTime<Utc> {
+        "2026-10-19T12:00:00Z".parse().unwrap()
+    }
+
+    fn account(age_days: i64) -> Account {
+        Account {
+            id: Uuid::nil(),
+            user_id: Uuid::nil(),
+            balance: dec("100000.00"),
+            currency: "INR".into(),
+            status: AccountStatus::Active,
+            closed_at: None,
+            created_at: now() - Duration::days(age_days),
+            updated_at: now(),
+        }
+    }
+
+    // Nothing about the debit stands out.
+    fn facts() -> RiskFacts {
+        RiskFacts { recent_debits: 0, known_ip: true, has_logins: true, history_count: 0, history_average: None }
+    }
+
+    fn rule(code: RiskRuleCode, score: i32, params: Value) -> RiskRule {
+        RiskRule { code, enabled: true, score, params, updated_by: None, updated_at: now() }
+    }
+
+    // The rules as seeded by the migration.
+    fn seeded() -> Vec<(RiskRule, RiskRuleParams)> {
+        active_rules(vec![
+            rule(
+                RiskRuleCode::NewAccountLargeWithdrawal,
+                60,
+                json!({ "max_account_age_days": 7, "min_amount": "5000.00" }),
+            ),
+            rule(RiskRuleCode::RapidWithdrawals, 40, json!({ "max_count": 3, "window_minutes": 10 })),
+            rule(RiskRuleCode::NewIp, 30, json!({})),
+            rule(
+                RiskRuleCode::AmountAboveHistory,
+                50,
+                json!({ "multiplier": "5", "min_history": 3, "lookback_days": 90 }),
+            ),
+        ])
+    }
+
+    fn matched(account: &Account, amount: &str, facts: &RiskFacts) -> Vec<RiskRuleCode> {
+        evaluate(&seeded(), account, dec(amount), facts, now())
+            .matched_rules
+            .into_iter()
+            .map(|matched| matched.rule)
+            .collect()
+    }
+
+    #[test]
+    fn decides_by_the_score_thresholds() {
+        assert_eq!(action(0), RiskAction::Allow);
+        assert_eq!(action(RISK_REVIEW_SCORE - 1), RiskAction::Allow);
+        assert_eq!(action(RISK_REVIEW_SCORE), RiskAction::Review);
+        assert_eq!(action(RISK_BLOCK_SCORE - 1), RiskAction::Review);
+        assert_eq!(action(RISK_BLOCK_SCORE), RiskAction::Block);
+    }
+
+    #[test]
+    fn allows_an_ordinary_debit() {
+        let decision = evaluate(&seeded(), &account(365), dec("100.00"), &facts(), now());
+
+        assert_eq!(decision.score, 0);
+        assert_eq!(decision.action, RiskAction::Allow);
+        assert!(decision.matched_rules.is_empty());
+    }
+
+    #[test]
+    fn new_account_large_withdrawal() {
+        assert_eq!(matched(&account(6), "5000.00", &facts()), vec![RiskRuleCode::NewAccountLargeWithdrawal]);
+        assert!(matched(&account(6), "4999.99", &facts()).is_empty());
+        assert!(matched(&account(7), "5000.00", &facts()).is_empty());
+    }
+
+    #[test]
+    fn rapid_withdrawals() {
+        let busy = RiskFacts { recent_debits: 3, ..facts() };
+        let decision = evaluate(&seeded(), &account(365), dec("100.00"), &busy, now());
+
+        assert_eq!(decision.matched_rules[0].rule, RiskRuleCode::RapidWithdrawals);
+        assert_eq!(decision.matched_rules[0].reason, "4 debits from the account in the last 10 minutes");
+        assert!(matched(&account(365), "100.00", &RiskFacts { recent_debits: 2, ..facts() }).is_empty());
+    }
+
+    #[test]
+    fn new_ip() {
+        let unknown = RiskFacts { known_ip: false, ..facts() };
+        assert_eq!(matched(&account(365), "100.00", &unknown), vec![RiskRuleCode::NewIp]);
+    }
+
+    #[test]
+    fn new_ip_is_skipped_without_login_history() {
+        let no_logins = RiskFacts { known_ip: false, has_logins: false, ..facts() };
+        assert!(matched(&account(365), "100.00", &no_logins).is_empty());
+    }
+
+    #[test]
+    fn amount_above_history() {
+        let history = RiskFacts { history_count: 3, history_average: Some(dec("100.00")), ..facts() };
+        assert_eq!(matched(&account(365), "500.01", &history), vec![RiskRuleCode::AmountAboveHistory]);
+        assert!(matched(&account(365), "500.00", &history).is_empty());
+
+        let short_history = RiskFacts { history_count: 2, ..history };
+        assert!(matched(&account(365), "5000.00", &short_history).is_empty());
+    }
+
+    #[test]
+    fn sums_the_scores_of_matched_rules() {
+        // NEW_IP 30 + RAPID_WITHDRAWALS 40
+        let review = RiskFacts { known_ip: false, recent_debits: 3, ..facts() };
+        let decision = evaluate(&seeded(), &account(365), dec("100.00"), &review, now());
+        assert_eq!(decision.score, 70);
+        assert_eq!(decision.action, RiskAction::Review);
+
+        // NEW_ACCOUNT_LARGE_WITHDRAWAL 60 + RAPID_WITHDRAWALS 40
+        let block = RiskFacts { recent_debits: 3, ..facts() };
+        let decision = evaluate(&seeded(), &account(1), dec("5000.00"), &block, now());
+        assert_eq!(decision.score, 100);
+        assert_eq!(decision.action, RiskAction::Block);
+    }
+
+    #[test]
+    fn reviews_at_exactly_the_review_score() {
+        // NEW_IP 30 + a RAPID_WITHDRAWALS scored 20
+        let mut rules = seeded();
+        rules[1].0.score = 20;
+        let facts = RiskFacts { known_ip: false, recent_debits: 3, ..facts() };
+
+        let decision = evaluate(&rules, &account(365), dec("100.00"), &facts, now());
+
+        assert_eq!(decision.score, RISK_REVIEW_SCORE);
+        assert_eq!(decision.action, RiskAction::Review);
+    }
+
+    #[test]
+    fn skips_disabled_rules_and_invalid_params() {
+        let mut disabled = rule(RiskRuleCode::NewIp, 30, json!({}));
+        disabled.enabled = false;
+        let invalid = rule(RiskRuleCode::RapidWithdrawals, 40, json!({ "max_count": 0 }));
+
+        assert!(active_rules(vec![disabled, invalid]).is_empty());
+    }
+
+    #[test]
+    fn windows_follow_the_active_rules() {
+        assert_eq!(fact_windows(&seeded(), now()), (now() - Duration::minutes(10), now() - Duration::days(90)));
+        assert_eq!(fact_windows(&[], now()), (now(), now()));
+    }
+}
@@ -0,0 +1,197 @@
+use chrono::{DateTime, TimeDelta, Utc};
+use chrono_tz::Tz;
+use croner::{parser::{CronParser, Seconds, Year}, Cron};
+use rrule::{RRule, RRuleSet, Unvalidated};
+
+// When a schedule recurs. Rules are evaluated in the schedule's time zone, so
+// "09:00 on the 1st" stays at 09:00 local time across DST changes.
+#[derive(Debug, Clone)]
+pub enum Recurrence {
+    // Runs once, at the start time
+    Once,
+    // Standard 5-field cron expression: minute, hour, day of month, month, day of week
+    Cron(Cron),
+    // RFC 5545 recurrence rule, e.g. `FREQ=MONTHLY;BYMONTHDAY=1;COUNT=12`. Its
+    // DTSTART is the schedule's start time.
+    Rule(RRule<Unvalidated>),
+}
+
+impl Recurrence {
+    // `None` when neither rule is set; errors name what is wrong with the rule.
+    pub fn parse(cron: Option<&str>, rrule: Option<&str>) -> Result<Self, &'static str> {
+        match (cron, rrule) {
+            (Some(_), Some(_)) => Err("cron and rrule can't be combined"),
+            (Some(expression), None) => CronParser::builder()
+                .seconds(Seconds::Disallowed)
+                .year(Year::Disallowed)
+                .build()
+                .parse(expression)
+                .map(Recurrence::Cron)
+                .map_err(|_| "cron must be a 5-field cron expression"),
+            (None, Some(rule)) => {
+                let rule = rule.trim();
+                let rule = rule.strip_prefix("RRULE:").unwrap_or(rule);
+                rule.parse::<RRule<Unvalidated>>()
+                    .map(Recurrence::Rule)
+                    .map_err(|_| "rrule must be an RFC 5545 recurrence rule")
+            }
+            (None, None) => Ok(Recurrence::Once),
+        }
+    }
+
+    // The first occurrence at or after `start_at`, or with `after`, the first
+    // one strictly after it. `None` once the rule has no occurrences left.
+    pub fn next_occurrence(
+        &self,
+        start_at: DateTime<Utc>,
+        timezone: Tz,
+        after: Option<DateTime<Utc>>,
+    ) -> Option<DateTime<Utc>> {
+        let after = after.filter(|after| *after >= start_at);
+
+        match self {
+            Recurrence::Once => after.is_none().then_some(start_at),
+            Recurrence::Cron(cron) => {
+                let from = after.unwrap_or(start_at).with_timezone(&timezone);
+                cron.find_next_occurrence(&from, after.is_none())
+                    .ok()
+                    .map(|next| next.with_timezone(&Utc))
+            }
+            Recurrence::Rule(rule) => {
+                let rrule_timezone = rrule::Tz::Tz(timezone);
+                let set: RRuleSet = rule.clone().build(start_at.with_timezone(&rrule_timezone)).ok()?;
+                // `after` includes its bound; occurrences are whole seconds
+                let set = match after {
+                    Some(after) => set.after((after + TimeDelta::seconds(1)).with_timezone(&rrule_timezone)),
+                    None => set,
+                };
+                set.all(1).dates.first().map(|next| next.with_timezone(&Utc))
+            }
+        }
+    }
+}
+
+pub fn parse_timezone(name: &str) -> Option<Tz> {
+    name.parse::<Tz>().ok()
+}
+
+// Everything that decides when a schedule runs.
+#[derive(Debug, Clone)]
+pub struct SchedulePlan {
+    pub recurrence: Recurrence,
+    pub timezone: Tz,
+    pub start_at: DateTime<Utc>,
+    pub end_at: Option<DateTime<Utc>>,
+    pub max_runs: Option<i32>,
+}
+
+impl SchedulePlan {
+    // When the schedule runs next, given that it has run `runs_count` times
+    // and nothing before `from` is due any more. Occurrences missed while the
+    // server was down or the schedule was paused are skipped, except that a
+    // one-off schedule that never ran runs as soon as possible. The start time
+    // itself is only due while the schedule hasn't run yet.
+    pub fn next_run(&self, from: DateTime<Utc>, runs_count: i32) -> Option<DateTime<Utc>> {
+        if self.max_runs.is_some_and(|max_runs| runs_count >= max_runs) {
+            return None;
+        }
+
+        let after = (from > self.start_at || runs_count > 0).then_some(from);
+        let next = match self.recurrence {
+            Recurrence::Once => (runs_count == 0).then(|| self.start_at.max(from)),
+            _ => self.recurrence.next_occurrence(self.start_at, self.timezone, after),
+        };
+
+        next.filter(|next| self.end_at.is_none_or(|end_at| *next <= end_at))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn utc(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(year, month, day, hour, minute, 0).unwrap()
+    }
+
+    fn plan(cron: Option<&str>, rrule: Option<&str>, timezone: &str, start_at: DateTime<Utc>) -> SchedulePlan {
+        SchedulePlan {
+            recurrence: Recurrence::parse(cron, rrule).unwrap(),
+            timezone: parse_timezone(timezone).unwrap(),
+            start_at,
+            end_at: None,
+            max_runs: None,
+        }
+    }
+
+    #[test]
+    fn cron_keeps_local_time_across_dst() {
+        // 09:00 in Berlin is 08:00 UTC in winter and 07:00 UTC in summer
+        let plan = plan(Some("0 9 * * *"), None, "Europe/Berlin", utc(2025, 3, 29, 8, 0));
+
+        let first = plan.next_run(plan.start_at, 0).unwrap();
+        assert_eq!(first, utc(2025, 3, 29, 8, 0));
+        assert_eq!(plan.next_run(first, 1), Some(utc(2025, 3, 30, 7, 0)));
+        assert_eq!(plan.next_run(utc(2025, 10, 25, 7, 0), 2), Some(utc(2025, 10, 26, 8, 0)));
+    }
+
+    #[test]
+    fn rrule_keeps_local_time_across_dst() {
+        let plan = plan(None, Some("FREQ=DAILY"), "America/New_York", utc(2025, 3, 8, 14, 0));
+
+        assert_eq!(plan.next_run(plan.start_at, 0), Some(utc(2025, 3, 8, 14, 0)));
+        assert_eq!(plan.next_run(utc(2025, 3, 8, 14, 0), 1), Some(utc(2025, 3, 9, 13, 0)));
+    }
+
+    #[test]
+    fn rrule_count_ends_the_schedule() {
+        let plan = plan(None, Some("RRULE:FREQ=MONTHLY;BYMONTHDAY=1;COUNT=2"), "UTC", utc(2025, 1, 1, 9, 0));
+
+        assert_eq!(plan.next_run(plan.start_at, 0), Some(utc(2025, 1, 1, 9, 0)));
+        assert_eq!(plan.next_run(utc(2025, 1, 1, 9, 0), 1), Some(utc(2025, 2, 1, 9, 0)));
+        assert_eq!(plan.next_run(utc(2025, 2, 1, 9, 0), 2), None);
+    }
+
+    #[test]
+    fn max_runs_ends_the_schedule() {
+        let mut plan = plan(Some("0 9 * * *"), None, "UTC", utc(2025, 1, 1, 9, 0));
+        plan.max_runs = Some(3);
+
+        assert_eq!(plan.next_run(utc(2025, 1, 2, 9, 0), 2), Some(utc(2025, 1, 3, 9, 0)));
+        assert_eq!(plan.next_run(utc(2025, 1, 3, 9, 0), 3), None);
+    }
+
+    #[test]
+    fn end_at_is_the_last_possible_run() {
+        let mut plan = plan(Some("0 9 * * *"), None, "UTC", utc(2025, 1, 1, 9, 0));
+        plan.end_at = Some(utc(2025, 1, 3, 9, 0));
+
+        assert_eq!(plan.next_run(utc(2025, 1, 2, 9, 0), 2), Some(utc(2025, 1, 3, 9, 0)));
+        assert_eq!(plan.next_run(utc(2025, 1, 3, 9, 0), 3), None);
+    }
+
+    #[test]
+    fn run_at_the_start_time_moves_on() {
+        let plan = plan(Some("0 9 * * *"), None, "UTC", utc(2025, 1, 1, 9, 0));
+
+        assert_eq!(plan.next_run(utc(2025, 1, 1, 9, 0), 0), Some(utc(2025, 1, 1, 9, 0)));
+        assert_eq!(plan.next_run(utc(2025, 1, 1, 9, 0), 1), Some(utc(2025, 1, 2, 9, 0)));
+    }
+
+    #[test]
+    fn missed_occurrences_are_skipped() {
+        let plan = plan(Some("0 9 * * *"), None, "UTC", utc(2025, 1, 1, 9, 0));
+
+        assert_eq!(plan.next_run(utc(2025, 1, 10, 12, 0), 1), Some(utc(2025, 1, 11, 9, 0)));
+    }
+
+    #[test]
+    fn one_off_runs_once_even_when_late() {
+        let plan = plan(None, None, "UTC", utc(2025, 1, 1, 9, 0));
+
+        assert_eq!(plan.next_run(utc(2024, 12, 31, 0, 0), 0), Some(utc(2025, 1, 1, 9, 0)));
+        assert_eq!(plan.next_run(utc(2025, 1, 5, 0, 0), 0), Some(utc(2025, 1, 5, 0, 0)));
+        assert_eq!(plan.next_run(utc(2025, 1, 5, 0, 0), 1), None);
+    }
+}
@@ -0,0 +1,387 @@
+mod ofac;
+mod un;
+
+use std::collections::{HashMap, HashSet};
+use unicode_normalization::{char::is_combining_mark, UnicodeNormalization};
+use crate::base::{
+    constants::{MAX_SCREENING_MATCHES, SCREENING_BLOCK_SCORE, SCREENING_REVIEW_SCORE},
+    models::screening::{
+        NewWatchlistEntry, ScreeningDecision, ScreeningMatch, ScreeningOutcome, ScreeningSubject, WatchlistEntry,
+        WatchlistSource,
+    },
+};
+
+// Titles and legal forms that say nothing about who is named.
+const IGNORED_TOKENS: &[&str] = &[
+    "mr", "mrs", "ms", "dr", "the", "of", "and", "co", "corp", "company", "inc", "llc", "ltd", "limited", "plc",
+    "sa", "gmbh",
+];
+
+pub enum WatchlistFile {
+    OfacSdn(Vec<NewWatchlistEntry>),
+    // (ent_num, alias) pairs from `alt.csv`
+    OfacAliases(Vec<(String, String)>),
+    Un(Vec<NewWatchlistEntry>),
+}
+
+// The UN list is XML; the OFAC files are told apart by their number of columns.
+pub fn parse_file(content: &str) -> Result<WatchlistFile, String> {
+    let content = content.trim_start_matches('\u{feff}');
+    if content.trim_start().starts_with('<') {
+        return Ok(WatchlistFile::Un(un::parse(content)?));
+    }
+
+    match ofac::columns(content) {
+        ofac::SDN_COLUMNS => Ok(WatchlistFile::OfacSdn(ofac::parse_sdn(content)?)),
+        ofac::ALT_COLUMNS => Ok(WatchlistFile::OfacAliases(ofac::parse_alt(content)?)),
+        columns => Err(format!("not a UN list, OFAC sdn.csv or alt.csv ({} columns)", columns)),
+    }
+}
+
+// One list per source, with the OFAC aliases added to their entries. A list
+// without entries is refused so a truncated file can't empty it.
+pub fn combine(files: Vec<WatchlistFile>) -> Result<Vec<(WatchlistSource, Vec<NewWatchlistEntry>)>, String> {
+    let mut lists: Vec<(WatchlistSource, Vec<NewWatchlistEntry>)> = Vec::new();
+    let mut aliases: Vec<(String, String)> = Vec::new();
+
+    for file in files {
+        let (source, entries) = match file {
+            WatchlistFile::OfacSdn(entries) => (WatchlistSource::OfacSdn, entries),
+            WatchlistFile::Un(entries) => (WatchlistSource::Un, entries),
+            WatchlistFile::OfacAliases(pairs) => {
+                aliases.extend(pairs);
+                continue;
+            }
+        };
+        if lists.iter().any(|(listed, _)| *listed == source) {
+            return Err(format!("{} list given more than once", source));
+        }
+        if entries.is_empty() {
+            return Err(format!("{} list has no entries", source));
+        }
+        lists.push((source, entries));
+    }
+
+    if !aliases.is_empty() {
+        let Some((_, entries)) = lists.iter_mut().find(|(source, _)| *source == WatchlistSource::OfacSdn) else {
+            return Err("alt.csv needs the sdn.csv it belongs to".into());
+        };
+        let positions: HashMap<String, usize> = entries
+            .iter()
+            .enumerate()
+            .map(|(position, entry)| (entry.external_id.clone(), position))
+            .collect();
+        for (external_id, alias) in aliases {
+            if let Some(position) = positions.get(&external_id) {
+                entries[*position].aliases.push(alias);
+            }
+        }
+    }
+
+    Ok(lists)
+}
+
+// A name reduced for matching: ASCII-folded lowercase words without titles
+// and legal forms, plus the words sorted and run together, so word order and
+// spacing (`Bin Laden`, `BINLADEN`) don't matter.
+struct NameKey {
+    tokens: Vec<String>,
+    joined: String,
+}
+
+impl NameKey {
+    fn new(name: &str) -> Self {
+        let folded: String = name
+            .nfkd()
+            .filter(|c| !is_combining_mark(*c))
+            .flat_map(char::to_lowercase)
+            .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+            .collect();
+
+        let tokens: Vec<String> = folded
+            .split_whitespace()
+            .filter(|token| !IGNORED_TOKENS.contains(token))
+            .map(str::to_string)
+            .collect();
+        let mut sorted = tokens.clone();
+        sorted.sort();
+
+        NameKey { tokens, joined: sorted.concat() }
+    }
+
+    // Jaro-Winkler similarity of the best-matching words, averaged both ways
+    // so extra words on either side lower the score, or of the joined names
+    // when that is higher.
+    fn similarity(&self, other: &NameKey) -> f64 {
+        if self.tokens.is_empty() || other.tokens.is_empty() {
+            return 0.0;
+        }
+
+        let words = (coverage(&self.tokens, &other.tokens) + coverage(&other.tokens, &self.tokens)) / 2.0;
+        words.max(strsim::jaro_winkler(&self.joined, &other.joined))
+    }
+}
+
+// How well each of `tokens` is found in `other`, on average.
+fn coverage(tokens: &[String], other: &[String]) -> f64 {
+    let total: f64 = tokens
+        .iter()
+        .map(|token| {
+            other
+                .iter()
+                .map(|candidate| strsim::jaro_winkler(token, candidate))
+                .fold(0.0, f64::max)
+        })
+        .sum();
+    total / tokens.len() as f64
+}
+
+struct IndexedName {
+    entry: usize,
+    name: String,
+    key: NameKey,
+}
+
+// The loaded lists, with every name and alias prepared for matching.
+pub struct Watchlist {
+    entries: Vec<WatchlistEntry>,
+    names: Vec<IndexedName>,
+}
+
+impl Watchlist {
+    pub fn new(entries: Vec<WatchlistEntry>) -> Self {
+        let names = entries
+            .iter()
+            .enumerate()
+            .flat_map(|(position, entry)| {
+                std::iter::once(&entry.name)
+                    .chain(&entry.aliases)
+                    .map(move |name| IndexedName { entry: position, name: name.clone(), key: NameKey::new(name) })
+            })
+            .collect();
+
+        Watchlist { entries, names }
+    }
+
+    // Scores `name` against the entries relevant to the subject, except those
+    // in `skip` (source, external id), which were already reviewed for it.
+    pub fn screen(
+        &self,
+        name: &str,
+        subject: ScreeningSubject,
+        skip: &HashSet<(WatchlistSource, String)>,
+    ) -> ScreeningOutcome {
+        let key = NameKey::new(name);
+        let mut best: HashMap<usize, (i32, &str)> = HashMap::new();
+
+        for indexed in &self.names {
+            let entry = &self.entries[indexed.entry];
+            if !subject.screens(entry.entry_type) || skip.contains(&(entry.source, entry.external_id.clone())) {
+                continue;
+            }
+
+            let score = (key.similarity(&indexed.key) * 100.0).round() as i32;
+            if score < SCREENING_REVIEW_SCORE {
+                continue;
+            }
+            let current = best.entry(indexed.entry).or_insert((score, &indexed.name));
+            if score > current.0 {
+                *current = (score, &indexed.name);
+            }
+        }
+
+        let mut matches: Vec<ScreeningMatch> = best
+            .into_iter()
+            .map(|(position, (score, matched_name))| {
+                let entry = &self.entries[position];
+                ScreeningMatch {
+                    source: entry.source,
+                    external_id: entry.external_id.clone(),
+                    entry_type: entry.entry_type,
+                    listed_name: entry.name.clone(),
+                    matched_name: matched_name.to_string(),
+                    score,
+                    programs: entry.programs.clone(),
+                }
+            })
+            .collect();
+        matches.sort_by(|a, b| b.score.cmp(&a.score).then_with(|| a.external_id.cmp(&b.external_id)));
+        matches.truncate(MAX_SCREENING_MATCHES);
+
+        let score = matches.first().map(|found| found.score).unwrap_or_default();
+
+        ScreeningOutcome { score, decision: decision(score), matches }
+    }
+}
+
+// The decision for the best match's score.
+fn decision(score: i32) -> ScreeningDecision {
+    if score >= SCREENING_BLOCK_SCORE {
+        ScreeningDecision::Block
+    } else if score >= SCREENING_REVIEW_SCORE {
+        ScreeningDecision::Review
+    } else {
+        ScreeningDecision::Clear
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use uuid::Uuid;
+    use crate::base::models::screening::WatchlistEntryType;
+
+    fn listed(
+        source: WatchlistSource,
+        external_id: &str,
+        entry_type: WatchlistEntryType,
+        name: &str,
+        aliases: &[&str],
+    ) -> WatchlistEntry {
+        WatchlistEntry {
+            id: Uuid::new_v4(),
+            source,
+            external_id: external_id.into(),
+            entry_type,
+            name: name.into(),
+            aliases: aliases.iter().map(|alias| alias.to_string()).collect(),
+            programs: vec!["SDGT".into()],
+            loaded_at: Utc::now(),
+        }
+    }
+
+    fn watchlist() -> Watchlist {
+        Watchlist::new(vec![
+            listed(WatchlistSource::OfacSdn, "7000", WatchlistEntryType::Individual, "IVANOV, Petr", &[]),
+            listed(WatchlistSource::OfacSdn, "2674", WatchlistEntryType::Entity, "ABU SAYYAF GROUP", &["ABBAS, Abu"]),
+            listed(WatchlistSource::Un, "QDi.999", WatchlistEntryType::Individual, "José María Quintero", &["Pepe Quintero"]),
+            listed(WatchlistSource::OfacSdn, "9001", WatchlistEntryType::Vessel, "SEA STAR", &[]),
+        ])
+    }
+
+    fn screen(name: &str, subject: ScreeningSubject) -> ScreeningOutcome {
+        watchlist().screen(name, subject, &HashSet::new())
+    }
+
+    #[test]
+    fn normalises_case_accents_punctuation_and_titles() {
+        let key = NameKey::new("Mr. JOSÉ-María  Quintero Ltd");
+
+        assert_eq!(key.tokens, vec!["jose", "maria", "quintero"]);
+        assert_eq!(key.joined, "josemariaquintero");
+    }
+
+    #[test]
+    fn ignores_word_order_and_spacing() {
+        assert_eq!(NameKey::new("Petr Ivanov").similarity(&NameKey::new("IVANOV, Petr")), 1.0);
+        assert_eq!(NameKey::new("BINLADEN").similarity(&NameKey::new("Bin Laden")), 1.0);
+    }
+
+    #[test]
+    fn names_made_only_of_ignored_words_match_nothing() {
+        assert_eq!(NameKey::new("The Company Ltd").similarity(&NameKey::new("Company")), 0.0);
+        assert_eq!(screen("Mr", ScreeningSubject::Counterparty).decision, ScreeningDecision::Clear);
+    }
+
+    #[test]
+    fn decides_by_the_score_thresholds() {
+        assert_eq!(decision(0), ScreeningDecision::Clear);
+        assert_eq!(decision(SCREENING_REVIEW_SCORE - 1), ScreeningDecision::Clear);
+        assert_eq!(decision(SCREENING_REVIEW_SCORE), ScreeningDecision::Review);
+        assert_eq!(decision(SCREENING_BLOCK_SCORE - 1), ScreeningDecision::Review);
+        assert_eq!(decision(SCREENING_BLOCK_SCORE), ScreeningDecision::Block);
+        assert_eq!(decision(100), ScreeningDecision::Block);
+    }
+
+    #[test]
+    fn blocks_a_listed_name() {
+        let outcome = screen("Petr Ivanov", ScreeningSubject::User);
+
+        assert_eq!(outcome.score, 100);
+        assert_eq!(outcome.decision, ScreeningDecision::Block);
+        assert_eq!(outcome.matches[0].external_id, "7000");
+        assert_eq!(outcome.matches[0].listed_name, "IVANOV, Petr");
+    }
+
+    #[test]
+    fn holds_a_close_name_for_review() {
+        let outcome = screen("Petr Ivanov Smirnov", ScreeningSubject::User);
+
+        assert!(
+            (SCREENING_REVIEW_SCORE..SCREENING_BLOCK_SCORE).contains(&outcome.score),
+            "score {}",
+            outcome.score
+        );
+        assert_eq!(outcome.decision, ScreeningDecision::Review);
+    }
+
+    #[test]
+    fn clears_an_unrelated_name() {
+        let outcome = screen("Anita Sharma", ScreeningSubject::Counterparty);
+
+        assert_eq!(outcome.score, 0);
+        assert_eq!(outcome.decision, ScreeningDecision::Clear);
+        assert!(outcome.matches.is_empty());
+    }
+
+    #[test]
+    fn matches_aliases_and_reports_the_alias() {
+        let outcome = screen("Pepe Quintero", ScreeningSubject::Counterparty);
+
+        assert_eq!(outcome.decision, ScreeningDecision::Block);
+        assert_eq!(outcome.matches[0].external_id, "QDi.999");
+        assert_eq!(outcome.matches[0].listed_name, "José María Quintero");
+        assert_eq!(outcome.matches[0].matched_name, "Pepe Quintero");
+    }
+
+    #[test]
+    fn screens_users_against_individuals_only() {
+        assert_eq!(screen("Abu Sayyaf Group", ScreeningSubject::User).decision, ScreeningDecision::Clear);
+        assert_eq!(screen("Abu Sayyaf Group", ScreeningSubject::Counterparty).decision, ScreeningDecision::Block);
+        assert_eq!(screen("Sea Star", ScreeningSubject::Counterparty).decision, ScreeningDecision::Clear);
+    }
+
+    #[test]
+    fn leaves_out_reviewed_entries() {
+        let skip = HashSet::from([(WatchlistSource::OfacSdn, "7000".to_string())]);
+
+        let outcome = watchlist().screen("Petr Ivanov", ScreeningSubject::User, &skip);
+
+        assert_eq!(outcome.decision, ScreeningDecision::Clear);
+    }
+
+    #[test]
+    fn detects_the_file_format() {
+        assert!(matches!(parse_file("\u{feff}<CONSOLIDATED_LIST/>"), Ok(WatchlistFile::Un(_))));
+        assert!(matches!(
+            parse_file("36,\"AEROCARIBBEAN AIRLINES\",-0- ,\"CUBA\",-0- ,-0- ,-0- ,-0- ,-0- ,-0- ,-0- ,-0- "),
+            Ok(WatchlistFile::OfacSdn(_))
+        ));
+        assert!(matches!(parse_file("36,1002,\"aka\",\"AERO-CARIBBEAN\",-0- "), Ok(WatchlistFile::OfacAliases(_))));
+        assert!(parse_file("a,b,c").is_err());
+    }
+
+    #[test]
+    fn combines_ofac_aliases_with_their_entries() {
+        let sdn = parse_file("36,\"AEROCARIBBEAN AIRLINES\",-0- ,\"CUBA\",-0- ,-0- ,-0- ,-0- ,-0- ,-0- ,-0- ,-0- ").unwrap();
+        let alt = parse_file("36,1002,\"aka\",\"AERO-CARIBBEAN\",-0- \n99,1003,\"aka\",\"UNKNOWN\",-0- ").unwrap();
+
+        let lists = combine(vec![alt, sdn]).unwrap();
+
+        assert_eq!(lists.len(), 1);
+        assert_eq!(lists[0].0, WatchlistSource::OfacSdn);
+        assert_eq!(lists[0].1[0].aliases, vec!["AERO-CARIBBEAN"]);
+    }
+
+    #[test]
+    fn refuses_incomplete_or_repeated_lists() {
+        let sdn = || parse_file("36,\"AEROCARIBBEAN AIRLINES\",-0- ,\"CUBA\",-0- ,-0- ,-0- ,-0- ,-0- ,-0- ,-0- ,-0- ").unwrap();
+        let alt = || parse_file("36,1002,\"aka\",\"AERO-CARIBBEAN\",-0- ").unwrap();
+        let un = || parse_file("<CONSOLIDATED_LIST><INDIVIDUALS/></CONSOLIDATED_LIST>").unwrap();
+
+        assert_eq!(combine(vec![alt()]).unwrap_err(), "alt.csv needs the sdn.csv it belongs to");
+        assert!(combine(vec![un()]).unwrap_err().ends_with("list has no entries"));
+        assert!(combine(vec![sdn(), sdn()]).unwrap_err().ends_with("list given more than once"));
+    }
+}
@@ -0,0 +1,151 @@
+use csv::ReaderBuilder;
+use crate::base::models::screening::{NewWatchlistEntry, WatchlistEntryType};
+
+// OFAC marks empty fields with `-0-`.
+const EMPTY_FIELD: &str = "-0-";
+
+// `sdn.csv` has 12 columns and no header: ent_num, SDN_Name, SDN_Type,
+// Program, Title, Call_Sign, Vess_type, Tonnage, GRT, Vess_flag, Vess_owner,
+// Remarks. `alt.csv` lists the aliases: ent_num, alt_num, alt_type, alt_name,
+// alt_remarks.
+pub const SDN_COLUMNS: usize = 12;
+pub const ALT_COLUMNS: usize = 5;
+
+// Number of fields of the first record, to tell the two files apart.
+pub fn columns(content: &str) -> usize {
+    reader(content)
+        .records()
+        .next()
+        .and_then(Result::ok)
+        .map(|record| record.len())
+        .unwrap_or_default()
+}
+
+pub fn parse_sdn(content: &str) -> Result<Vec<NewWatchlistEntry>, String> {
+    let mut entries = Vec::new();
+
+    for (index, record) in reader(content).records().enumerate() {
+        let record = record.map_err(|e| format!("line {}: {}", index + 1, e))?;
+        // The file ends with an EOF control character on its own line
+        if record.len() < 4 {
+            continue;
+        }
+
+        let (Some(external_id), Some(name)) = (field(&record, 0), field(&record, 1)) else {
+            return Err(format!("line {}: missing ent_num or SDN_Name", index + 1));
+        };
+        let entry_type = match field(&record, 2).map(str::to_lowercase).as_deref() {
+            Some("individual") => WatchlistEntryType::Individual,
+            Some("vessel") => WatchlistEntryType::Vessel,
+            Some("aircraft") => WatchlistEntryType::Aircraft,
+            _ => WatchlistEntryType::Entity,
+        };
+        // e.g. `SDGT] [IRGC`
+        let programs = field(&record, 3)
+            .map(|programs| {
+                programs
+                    .split("] [")
+                    .map(|program| program.trim_matches(|c| c == '[' || c == ']').trim().to_string())
+                    .filter(|program| !program.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        entries.push(NewWatchlistEntry {
+            external_id: external_id.to_string(),
+            entry_type,
+            name: name.to_string(),
+            aliases: Vec::new(),
+            programs,
+        });
+    }
+
+    Ok(entries)
+}
+
+// (ent_num, alias) pairs.
+pub fn parse_alt(content: &str) -> Result<Vec<(String, String)>, String> {
+    let mut aliases = Vec::new();
+
+    for (index, record) in reader(content).records().enumerate() {
+        let record = record.map_err(|e| format!("line {}: {}", index + 1, e))?;
+        if record.len() < 4 {
+            continue;
+        }
+
+        if let (Some(external_id), Some(alias)) = (field(&record, 0), field(&record, 3)) {
+            aliases.push((external_id.to_string(), alias.to_string()));
+        }
+    }
+
+    Ok(aliases)
+}
+
+fn reader(content: &str) -> csv::Reader<&[u8]> {
+    ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(content.as_bytes())
+}
+
+fn field(record: &csv::StringRecord, index: usize) -> Option<&str> {
+    record
+        .get(index)
+        .map(str::trim)
+        .filter(|value| !value.is_empty() && *value != EMPTY_FIELD)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SDN: &str = "36,\"AEROCARIBBEAN AIRLINES\",-0- ,\"CUBA\",-0- ,-0- ,-0- ,-0- ,-0- ,-0- ,-0- ,-0- \r\n\
+                       7000,\"IVANOV, Petr\",\"individual\",\"SDGT] [IRGC\",-0- ,-0- ,-0- ,-0- ,-0- ,-0- ,-0- ,\"DOB 1970.\"\r\n\
+                       7001,\"SEA STAR\",\"vessel\",-0- ,-0- ,-0- ,\"Cargo\",-0- ,-0- ,-0- ,-0- ,-0- \r\n\
+                       \u{1a}\r\n";
+
+    const ALT: &str = "36,1002,\"aka\",\"AERO-CARIBBEAN\",-0- \r\n\
+                       7000,1003,\"fka\",\"IVANOFF, Pyotr\",\"Russian spelling\"\r\n\
+                       7001,1004,\"aka\",-0- ,-0- \r\n";
+
+    #[test]
+    fn tells_the_files_apart_by_their_columns() {
+        assert_eq!(columns(SDN), SDN_COLUMNS);
+        assert_eq!(columns(ALT), ALT_COLUMNS);
+        assert_eq!(columns(""), 0);
+    }
+
+    #[test]
+    fn parses_sdn_entries() {
+        let entries = parse_sdn(SDN).unwrap();
+
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].external_id, "36");
+        assert_eq!(entries[0].name, "AEROCARIBBEAN AIRLINES");
+        assert_eq!(entries[0].entry_type, WatchlistEntryType::Entity);
+        assert_eq!(entries[0].programs, vec!["CUBA"]);
+        assert_eq!(entries[1].name, "IVANOV, Petr");
+        assert_eq!(entries[1].entry_type, WatchlistEntryType::Individual);
+        assert_eq!(entries[1].programs, vec!["SDGT", "IRGC"]);
+        assert_eq!(entries[2].entry_type, WatchlistEntryType::Vessel);
+        assert!(entries[2].programs.is_empty());
+        assert!(entries.iter().all(|entry| entry.aliases.is_empty()));
+    }
+
+    #[test]
+    fn refuses_sdn_entries_without_a_name() {
+        let error = parse_sdn("36,-0- ,-0- ,\"CUBA\",-0- ,-0- ,-0- ,-0- ,-0- ,-0- ,-0- ,-0- ").unwrap_err();
+
+        assert_eq!(error, "line 1: missing ent_num or SDN_Name");
+    }
+
+    #[test]
+    fn parses_aliases_and_skips_empty_ones() {
+        let aliases = parse_alt(ALT).unwrap();
+
+        assert_eq!(
+            aliases,
+            vec![("36".to_string(), "AERO-CARIBBEAN".to_string()), ("7000".to_string(), "IVANOFF, Pyotr".to_string())]
+        );
+    }
+}
@@ -0,0 +1,181 @@
+use quick_xml::{events::Event, Reader};
+use crate::base::models::screening::{NewWatchlistEntry, WatchlistEntryType};
+
+// Parts of an individual's name, in order.
+const NAME_ELEMENTS: &[&str] = &["FIRST_NAME", "SECOND_NAME", "THIRD_NAME", "FOURTH_NAME"];
+
+#[derive(Default)]
+struct EntryBuilder {
+    data_id: Option<String>,
+    reference_number: Option<String>,
+    name_parts: Vec<(usize, String)>,
+    aliases: Vec<String>,
+    programs: Vec<String>,
+}
+
+// UN Security Council consolidated list: `INDIVIDUAL` and `ENTITY` elements
+// with the name in `FIRST_NAME`..`FOURTH_NAME` and aliases in
+// `INDIVIDUAL_ALIAS`/`ENTITY_ALIAS`. Entries are identified by their
+// reference number, e.g. `QDi.001`.
+pub fn parse(content: &str) -> Result<Vec<NewWatchlistEntry>, String> {
+    let mut reader = Reader::from_str(content);
+    reader.config_mut().trim_text(true);
+
+    let mut entries = Vec::new();
+    let mut path: Vec<String> = Vec::new();
+    let mut entry: Option<(WatchlistEntryType, EntryBuilder)> = None;
+
+    loop {
+        let event = reader
+            .read_event()
+            .map_err(|e| format!("invalid XML at byte {}: {}", reader.buffer_position(), e))?;
+
+        match event {
+            Event::Start(element) => {
+                let name = String::from_utf8_lossy(element.local_name().as_ref()).into_owned();
+                match name.as_str() {
+                    "INDIVIDUAL" => entry = Some((WatchlistEntryType::Individual, EntryBuilder::default())),
+                    "ENTITY" => entry = Some((WatchlistEntryType::Entity, EntryBuilder::default())),
+                    _ => {}
+                }
+                path.push(name);
+            }
+            Event::End(_) => {
+                let closed = path.pop();
+                if matches!(closed.as_deref(), Some("INDIVIDUAL" | "ENTITY"))
+                    && let Some((entry_type, builder)) = entry.take()
+                {
+                    let position = entries.len() + 1;
+                    entries.push(finish_entry(entry_type, builder).map_err(|e| format!("entry {}: {}", position, e))?);
+                }
+            }
+            Event::Text(text) => {
+                let value = text
+                    .unescape()
+                    .map_err(|e| format!("invalid text at byte {}: {}", reader.buffer_position(), e))?
+                    .trim()
+                    .to_string();
+                if value.is_empty() {
+                    continue;
+                }
+
+                if let Some((_, builder)) = entry.as_mut() {
+                    entry_text(builder, &path, value);
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    Ok(entries)
+}
+
+fn entry_text(entry: &mut EntryBuilder, path: &[String], value: String) {
+    let Some(element) = path.last().map(String::as_str) else {
+        return;
+    };
+    let parent = path.len().checked_sub(2).map(|index| path[index].as_str());
+
+    match element {
+        "DATAID" => entry.data_id = Some(value),
+        "REFERENCE_NUMBER" => entry.reference_number = Some(value),
+        "UN_LIST_TYPE" => entry.programs.push(value),
+        "ALIAS_NAME" if matches!(parent, Some("INDIVIDUAL_ALIAS" | "ENTITY_ALIAS")) => entry.aliases.push(value),
+        _ => {
+            if let Some(position) = NAME_ELEMENTS.iter().position(|name| *name == element) {
+                entry.name_parts.push((position, value));
+            }
+        }
+    }
+}
+
+fn finish_entry(entry_type: WatchlistEntryType, mut entry: EntryBuilder) -> Result<NewWatchlistEntry, String> {
+    let external_id = entry
+        .reference_number
+        .or(entry.data_id)
+        .ok_or("missing REFERENCE_NUMBER and DATAID")?;
+
+    entry.name_parts.sort_by_key(|(position, _)| *position);
+    let name = entry
+        .name_parts
+        .into_iter()
+        .map(|(_, part)| part)
+        .collect::<Vec<_>>()
+        .join(" ");
+    if name.is_empty() {
+        return Err(format!("{} has no name", external_id));
+    }
+
+    Ok(NewWatchlistEntry {
+        external_id,
+        entry_type,
+        name,
+        aliases: entry.aliases,
+        programs: entry.programs,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIST: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<CONSOLIDATED_LIST dateGenerated="2026-10-01T00:00:00">
+ <INDIVIDUALS>
+  <INDIVIDUAL>
+   <DATAID>6908555</DATAID>
+   <REFERENCE_NUMBER>QDi.999</REFERENCE_NUMBER>
+   <UN_LIST_TYPE>Al-Qaida</UN_LIST_TYPE>
+   <SECOND_NAME>María</SECOND_NAME>
+   <FIRST_NAME>José</FIRST_NAME>
+   <THIRD_NAME>Quintero</THIRD_NAME>
+   <INDIVIDUAL_ALIAS><QUALITY>Good</QUALITY><ALIAS_NAME>Pepe Quintero</ALIAS_NAME></INDIVIDUAL_ALIAS>
+   <INDIVIDUAL_ALIAS><QUALITY>Low</QUALITY><ALIAS_NAME>El Profe</ALIAS_NAME></INDIVIDUAL_ALIAS>
+   <INDIVIDUAL_ADDRESS><CITY>Caracas</CITY></INDIVIDUAL_ADDRESS>
+  </INDIVIDUAL>
+ </INDIVIDUALS>
+ <ENTITIES>
+  <ENTITY>
+   <DATAID>110426</DATAID>
+   <FIRST_NAME>Global Relief Foundation</FIRST_NAME>
+   <UN_LIST_TYPE>Al-Qaida</UN_LIST_TYPE>
+   <ENTITY_ALIAS><ALIAS_NAME>GRF</ALIAS_NAME></ENTITY_ALIAS>
+  </ENTITY>
+ </ENTITIES>
+</CONSOLIDATED_LIST>"#;
+
+    #[test]
+    fn parses_individuals_and_entities() {
+        let entries = parse(LIST).unwrap();
+
+        assert_eq!(entries.len(), 2);
+
+        let individual = &entries[0];
+        assert_eq!(individual.external_id, "QDi.999");
+        assert_eq!(individual.entry_type, WatchlistEntryType::Individual);
+        assert_eq!(individual.name, "José María Quintero");
+        assert_eq!(individual.aliases, vec!["Pepe Quintero", "El Profe"]);
+        assert_eq!(individual.programs, vec!["Al-Qaida"]);
+
+        let entity = &entries[1];
+        assert_eq!(entity.external_id, "110426");
+        assert_eq!(entity.entry_type, WatchlistEntryType::Entity);
+        assert_eq!(entity.name, "Global Relief Foundation");
+        assert_eq!(entity.aliases, vec!["GRF"]);
+    }
+
+    #[test]
+    fn refuses_entries_without_an_id_or_name() {
+        let without_id = parse("<CONSOLIDATED_LIST><INDIVIDUAL><FIRST_NAME>A</FIRST_NAME></INDIVIDUAL></CONSOLIDATED_LIST>");
+        assert_eq!(without_id.unwrap_err(), "entry 1: missing REFERENCE_NUMBER and DATAID");
+
+        let without_name = parse("<CONSOLIDATED_LIST><ENTITY><DATAID>1</DATAID></ENTITY></CONSOLIDATED_LIST>");
+        assert_eq!(without_name.unwrap_err(), "entry 1: 1 has no name");
+    }
+
+    #[test]
+    fn reports_invalid_xml() {
+        assert!(parse("<CONSOLIDATED_LIST><INDIVIDUAL></ENTITY>").unwrap_err().starts_with("invalid XML"));
+    }
+}
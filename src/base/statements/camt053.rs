@@ -0,0 +1,99 @@
+use chrono::{DateTime, SecondsFormat, Utc};
+use rust_decimal::Decimal;
+use super::{truncate, xml_escape, Statement, StatementEntry, StatementRenderer};
+use crate::base::models::transactions::TransactionStatus;
+
+// ISO 20022 bank-to-customer statement, camt.053.001.02. Per-entry running
+// balances have no dedicated element and go into `AddtlNtryInf`.
+pub struct Camt053Renderer;
+
+impl StatementRenderer for Camt053Renderer {
+    fn header(&mut self, statement: &Statement) -> String {
+        format!(
+            concat!(
+                "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+                "<Document xmlns=\"urn:iso:std:iso:20022:tech:xsd:camt.053.001.02\">\n",
+                "<BkToCstmrStmt>\n",
+                "<GrpHdr><MsgId>{id}</MsgId><CreDtTm>{generated}</CreDtTm></GrpHdr>\n",
+                "<Stmt>\n",
+                "<Id>{id}</Id><CreDtTm>{generated}</CreDtTm>",
+                "<FrToDt><FrDtTm>{from}</FrDtTm><ToDtTm>{to}</ToDtTm></FrToDt>\n",
+                "<Acct><Id><Othr><Id>{account}</Id></Othr></Id><Ccy>{currency}</Ccy></Acct>\n",
+                "{opening}",
+                "{closing}",
+            ),
+            id = statement.id.simple(),
+            generated = iso_datetime(statement.generated_at),
+            from = iso_datetime(statement.from),
+            to = iso_datetime(statement.to),
+            account = statement.account_id.simple(),
+            currency = xml_escape(&statement.currency),
+            opening = balance("OPBD", statement.opening_balance, &statement.currency, statement.from),
+            closing = balance("CLBD", statement.closing_balance, &statement.currency, statement.to),
+        )
+    }
+
+    fn entry(&mut self, statement: &Statement, entry: &StatementEntry) -> String {
+        let transaction = entry.transaction;
+        let status = match transaction.status {
+            TransactionStatus::Completed => "BOOK",
+            _ => "PDNG",
+        };
+        let reference = transaction.id.simple();
+        let booked_at = iso_datetime(transaction.created_at);
+        let description = transaction.description.as_deref().unwrap_or_default();
+        let remittance = if description.is_empty() {
+            String::new()
+        } else {
+            format!("<RmtInf><Ustrd>{}</Ustrd></RmtInf>", xml_escape(truncate(description, 140)))
+        };
+
+        format!(
+            concat!(
+                "<Ntry><NtryRef>{reference}</NtryRef>",
+                "<Amt Ccy=\"{currency}\">{amount}</Amt><CdtDbtInd>{indicator}</CdtDbtInd><Sts>{status}</Sts>",
+                "<BookgDt><DtTm>{booked_at}</DtTm></BookgDt><ValDt><DtTm>{booked_at}</DtTm></ValDt>",
+                "<AcctSvcrRef>{reference}</AcctSvcrRef>",
+                "<BkTxCd><Prtry><Cd>{transaction_type}</Cd></Prtry></BkTxCd>",
+                "<NtryDtls><TxDtls><Refs><AcctSvcrRef>{reference}</AcctSvcrRef></Refs>{remittance}</TxDtls></NtryDtls>",
+                "<AddtlNtryInf>Running balance {running_balance}</AddtlNtryInf></Ntry>\n",
+            ),
+            reference = reference,
+            currency = xml_escape(&statement.currency),
+            amount = amount(entry.amount.abs()),
+            indicator = indicator(entry.amount),
+            status = status,
+            booked_at = booked_at,
+            transaction_type = transaction.transaction_type,
+            remittance = remittance,
+            running_balance = amount(entry.running_balance),
+        )
+    }
+
+    fn footer(&mut self, _statement: &Statement) -> String {
+        "</Stmt>\n</BkToCstmrStmt>\n</Document>\n".to_string()
+    }
+}
+
+fn balance(code: &str, value: Decimal, currency: &str, at: DateTime<Utc>) -> String {
+    format!(
+        "<Bal><Tp><CdOrPrtry><Cd>{}</Cd></CdOrPrtry></Tp><Amt Ccy=\"{}\">{}</Amt><CdtDbtInd>{}</CdtDbtInd><Dt><DtTm>{}</DtTm></Dt></Bal>\n",
+        code,
+        xml_escape(currency),
+        amount(value.abs()),
+        indicator(value),
+        iso_datetime(at),
+    )
+}
+
+fn indicator(value: Decimal) -> &'static str {
+    if value.is_sign_negative() && !value.is_zero() { "DBIT" } else { "CRDT" }
+}
+
+fn iso_datetime(ts: DateTime<Utc>) -> String {
+    ts.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+fn amount(value: Decimal) -> String {
+    format!("{:.2}", value)
+}
@@ -0,0 +1,56 @@
+use super::{Statement, StatementEntry, StatementRenderer};
+
+const HEADER: &str = "date,transaction_id,type,status,description,amount,running_balance\r\n";
+
+// RFC 4180 CSV. The opening and closing balances are rows of their own so the
+// file stays importable into spreadsheets as a single table.
+pub struct CsvRenderer;
+
+impl StatementRenderer for CsvRenderer {
+    fn header(&mut self, statement: &Statement) -> String {
+        format!(
+            "{}{},,OPENING_BALANCE,,,,{}\r\n",
+            HEADER,
+            statement.from.to_rfc3339(),
+            statement.opening_balance
+        )
+    }
+
+    fn entry(&mut self, _statement: &Statement, entry: &StatementEntry) -> String {
+        let transaction = entry.transaction;
+        format!(
+            "{},{},{},{},{},{},{}\r\n",
+            transaction.created_at.to_rfc3339(),
+            transaction.id,
+            transaction.transaction_type,
+            transaction.status,
+            field(transaction.description.as_deref().unwrap_or_default()),
+            entry.amount,
+            entry.running_balance
+        )
+    }
+
+    fn footer(&mut self, statement: &Statement) -> String {
+        format!(
+            "{},,CLOSING_BALANCE,,,,{}\r\n",
+            statement.to.to_rfc3339(),
+            statement.closing_balance
+        )
+    }
+}
+
+// Quotes free text and neutralises leading characters that spreadsheets would
+// evaluate as a formula.
+pub fn field(value: &str) -> String {
+    let value = if value.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+        format!("'{}", value)
+    } else {
+        value.to_string()
+    };
+
+    if value.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value
+    }
+}
@@ -0,0 +1,43 @@
+use serde_json::json;
+use super::{Statement, StatementEntry, StatementRenderer};
+
+// One JSON document; entries are written as elements of `transactions`.
+#[derive(Default)]
+pub struct JsonRenderer {
+    entries: usize,
+}
+
+impl StatementRenderer for JsonRenderer {
+    fn header(&mut self, statement: &Statement) -> String {
+        let header = json!({
+            "statement_id": statement.id,
+            "account_id": statement.account_id,
+            "currency": statement.currency,
+            "from": statement.from,
+            "to": statement.to,
+            "generated_at": statement.generated_at,
+            "opening_balance": statement.opening_balance,
+        })
+        .to_string();
+
+        // Reopen the object to append the entries and closing balance
+        format!("{},\"transactions\":[", header.trim_end_matches('}'))
+    }
+
+    fn entry(&mut self, _statement: &Statement, entry: &StatementEntry) -> String {
+        let mut value = json!(entry.transaction);
+        value["signed_amount"] = json!(entry.amount);
+        value["running_balance"] = json!(entry.running_balance);
+
+        let separator = if self.entries == 0 { "" } else { "," };
+        self.entries += 1;
+        format!("{}{}", separator, value)
+    }
+
+    fn footer(&mut self, statement: &Statement) -> String {
+        format!(
+            "],\"closing_balance\":{}}}",
+            json!(statement.closing_balance)
+        )
+    }
+}
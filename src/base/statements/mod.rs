@@ -0,0 +1,167 @@
+mod camt053;
+mod csv;
+mod json;
+mod ofx;
+mod pdf;
+
+pub use csv::field as csv_field;
+
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use uuid::Uuid;
+use crate::base::models::{
+    accounts::Account,
+    users::User,
+    transactions::{Transaction, TransactionType},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StatementFormat {
+    #[default]
+    Json,
+    Csv,
+    Ofx,
+    Camt053,
+    Pdf,
+}
+
+impl StatementFormat {
+    pub fn renderer(&self) -> Box<dyn StatementRenderer> {
+        match self {
+            StatementFormat::Json => Box::new(json::JsonRenderer::default()),
+            StatementFormat::Csv => Box::new(csv::CsvRenderer),
+            StatementFormat::Ofx => Box::new(ofx::OfxRenderer),
+            StatementFormat::Camt053 => Box::new(camt053::Camt053Renderer),
+            StatementFormat::Pdf => Box::new(pdf::PdfRenderer::default()),
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            StatementFormat::Json => "application/json",
+            StatementFormat::Csv => "text/csv; charset=utf-8",
+            StatementFormat::Ofx => "application/x-ofx",
+            StatementFormat::Camt053 => "application/xml",
+            StatementFormat::Pdf => "application/pdf",
+        }
+    }
+
+    fn extension(&self) -> &'static str {
+        match self {
+            StatementFormat::Json => "json",
+            StatementFormat::Csv => "csv",
+            StatementFormat::Ofx => "ofx",
+            StatementFormat::Camt053 => "xml",
+            StatementFormat::Pdf => "pdf",
+        }
+    }
+}
+
+// Everything known about a statement before its entries are streamed.
+#[derive(Debug, Clone)]
+pub struct Statement {
+    pub id: Uuid,
+    pub account_id: Uuid,
+    pub holder_name: String,
+    pub holder_email: String,
+    pub currency: String,
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub opening_balance: Decimal,
+    pub closing_balance: Decimal,
+    pub generated_at: DateTime<Utc>,
+}
+
+impl Statement {
+    pub fn new(
+        account: &Account,
+        holder: &User,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        opening: Decimal,
+        closing: Decimal,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            account_id: account.id,
+            holder_name: holder.name.clone(),
+            holder_email: holder.email.clone(),
+            currency: account.currency.clone(),
+            from,
+            to,
+            opening_balance: opening,
+            closing_balance: closing,
+            generated_at: Utc::now(),
+        }
+    }
+
+    pub fn filename(&self, format: StatementFormat) -> String {
+        format!(
+            "statement-{}-{}-{}.{}",
+            self.account_id,
+            self.from.format("%Y%m%d"),
+            self.to.format("%Y%m%d"),
+            format.extension()
+        )
+    }
+}
+
+// A transaction as it appears on a statement: signed from the account's point
+// of view, with the balance after it was applied.
+pub struct StatementEntry<'a> {
+    pub transaction: &'a Transaction,
+    pub amount: Decimal,
+    pub running_balance: Decimal,
+}
+
+impl<'a> StatementEntry<'a> {
+    pub fn new(transaction: &'a Transaction, previous_balance: Decimal) -> Self {
+        let amount = signed_amount(transaction);
+        Self {
+            transaction,
+            amount,
+            running_balance: previous_balance + amount,
+        }
+    }
+}
+
+pub fn signed_amount(transaction: &Transaction) -> Decimal {
+    match transaction.transaction_type {
+        TransactionType::Deposit => transaction.amount,
+        TransactionType::Withdrawal => -transaction.amount,
+    }
+}
+
+// Renders a statement in pieces so it can be streamed: the header once, each
+// entry in booking order, then the footer. Formats that can't be written
+// incrementally may return everything from `footer`.
+pub trait StatementRenderer: Send {
+    fn header(&mut self, statement: &Statement) -> String;
+    fn entry(&mut self, statement: &Statement, entry: &StatementEntry) -> String;
+    fn footer(&mut self, statement: &Statement) -> String;
+}
+
+pub(crate) fn xml_escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// Truncates on a character boundary for formats with field length limits.
+pub(crate) fn truncate(value: &str, max_chars: usize) -> &str {
+    match value.char_indices().nth(max_chars) {
+        Some((index, _)) => &value[..index],
+        None => value,
+    }
+}
@@ -0,0 +1,80 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use super::{truncate, xml_escape, Statement, StatementEntry, StatementRenderer};
+use crate::base::models::transactions::TransactionType;
+
+// OFX 2.2 bank statement. OFX has no per-transaction balance, so the running
+// balance is only available in the other formats; the opening balance is
+// reported in BALLIST and the closing balance as the ledger balance.
+pub struct OfxRenderer;
+
+impl StatementRenderer for OfxRenderer {
+    fn header(&mut self, statement: &Statement) -> String {
+        format!(
+            concat!(
+                "<?xml version=\"1.0\" encoding=\"UTF-8\" standalone=\"no\"?>\n",
+                "<?OFX OFXHEADER=\"200\" VERSION=\"220\" SECURITY=\"NONE\" OLDFILEUID=\"NONE\" NEWFILEUID=\"NONE\"?>\n",
+                "<OFX>\n",
+                "<SIGNONMSGSRSV1><SONRS>",
+                "<STATUS><CODE>0</CODE><SEVERITY>INFO</SEVERITY></STATUS>",
+                "<DTSERVER>{generated}</DTSERVER><LANGUAGE>ENG</LANGUAGE>",
+                "</SONRS></SIGNONMSGSRSV1>\n",
+                "<BANKMSGSRSV1><STMTTRNRS>",
+                "<TRNUID>{id}</TRNUID>",
+                "<STATUS><CODE>0</CODE><SEVERITY>INFO</SEVERITY></STATUS>\n",
+                "<STMTRS><CURDEF>{currency}</CURDEF>",
+                "<BANKACCTFROM><BANKID>0</BANKID><ACCTID>{account}</ACCTID><ACCTTYPE>CHECKING</ACCTTYPE></BANKACCTFROM>\n",
+                "<BANKTRANLIST><DTSTART>{from}</DTSTART><DTEND>{to}</DTEND>\n",
+            ),
+            generated = ofx_datetime(statement.generated_at),
+            id = statement.id.simple(),
+            currency = xml_escape(&statement.currency),
+            account = statement.account_id.simple(),
+            from = ofx_datetime(statement.from),
+            to = ofx_datetime(statement.to),
+        )
+    }
+
+    fn entry(&mut self, _statement: &Statement, entry: &StatementEntry) -> String {
+        let transaction = entry.transaction;
+        let transaction_type = match transaction.transaction_type {
+            TransactionType::Deposit => "CREDIT",
+            TransactionType::Withdrawal => "DEBIT",
+        };
+        let memo = transaction.description.as_deref().unwrap_or_default();
+
+        format!(
+            "<STMTTRN><TRNTYPE>{}</TRNTYPE><DTPOSTED>{}</DTPOSTED><TRNAMT>{}</TRNAMT><FITID>{}</FITID><MEMO>{}</MEMO></STMTTRN>\n",
+            transaction_type,
+            ofx_datetime(transaction.created_at),
+            entry.amount,
+            transaction.id.simple(),
+            xml_escape(truncate(memo, 255)),
+        )
+    }
+
+    fn footer(&mut self, statement: &Statement) -> String {
+        format!(
+            concat!(
+                "</BANKTRANLIST>\n",
+                "<LEDGERBAL><BALAMT>{closing}</BALAMT><DTASOF>{to}</DTASOF></LEDGERBAL>\n",
+                "<BALLIST><BAL><NAME>Opening balance</NAME><DESC>Balance at start of period</DESC>",
+                "<BALTYPE>DOLLAR</BALTYPE><VALUE>{opening}</VALUE><DTASOF>{from}</DTASOF></BAL></BALLIST>\n",
+                "</STMTRS></STMTTRNRS></BANKMSGSRSV1>\n",
+                "</OFX>\n",
+            ),
+            closing = amount(statement.closing_balance),
+            opening = amount(statement.opening_balance),
+            from = ofx_datetime(statement.from),
+            to = ofx_datetime(statement.to),
+        )
+    }
+}
+
+fn ofx_datetime(ts: DateTime<Utc>) -> String {
+    format!("{}[0:UTC]", ts.format("%Y%m%d%H%M%S%.3f"))
+}
+
+fn amount(value: Decimal) -> String {
+    format!("{:.2}", value)
+}
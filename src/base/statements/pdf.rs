@@ -0,0 +1,294 @@
+use std::collections::BTreeMap;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use super::{Statement, StatementEntry, StatementRenderer};
+
+// A4 in points
+const PAGE_WIDTH: f32 = 595.0;
+const PAGE_HEIGHT: f32 = 842.0;
+const MARGIN: f32 = 50.0;
+const ROW_HEIGHT: f32 = 14.0;
+const FONT_SIZE: f32 = 9.0;
+
+// Left edge of each table column; amounts are right-aligned to the next edge.
+const COL_DATE: f32 = MARGIN;
+const COL_DESCRIPTION: f32 = 120.0;
+const COL_TYPE: f32 = 300.0;
+const COL_STATUS: f32 = 375.0;
+const COL_AMOUNT_RIGHT: f32 = 475.0;
+const COL_BALANCE_RIGHT: f32 = PAGE_WIDTH - MARGIN;
+
+#[derive(Default)]
+struct Total {
+    count: u64,
+    amount: Decimal,
+}
+
+// Customer-facing statement as a PDF 1.4 document using the standard Helvetica
+// fonts, which every reader provides, so nothing needs to be embedded. A PDF
+// ends with a cross-reference table of byte offsets, so the document is built
+// up page by page and written out as a whole by `footer`.
+#[derive(Default)]
+pub struct PdfRenderer {
+    pages: Vec<String>,
+    content: String,
+    y: f32,
+    totals: BTreeMap<String, Total>,
+}
+
+impl StatementRenderer for PdfRenderer {
+    fn header(&mut self, statement: &Statement) -> String {
+        self.y = PAGE_HEIGHT - MARGIN;
+        self.text(MARGIN, self.y, 18.0, true, "Account Statement");
+        self.y -= 30.0;
+
+        let details = [
+            ("Account holder", statement.holder_name.clone()),
+            ("Email", statement.holder_email.clone()),
+            ("Account", statement.account_id.to_string()),
+            ("Currency", statement.currency.clone()),
+            ("Period", format!("{} to {}", datetime(statement.from), datetime(statement.to))),
+            ("Generated", datetime(statement.generated_at)),
+        ];
+        for (label, value) in details {
+            self.text(MARGIN, self.y, 10.0, true, label);
+            self.text(MARGIN + 90.0, self.y, 10.0, false, &value);
+            self.y -= ROW_HEIGHT;
+        }
+
+        self.y -= 10.0;
+        self.text(MARGIN, self.y, 10.0, true, "Opening balance");
+        self.text_right(COL_BALANCE_RIGHT, self.y, 10.0, &amount(statement.opening_balance));
+        self.y -= 24.0;
+        self.table_header();
+
+        String::new()
+    }
+
+    fn entry(&mut self, _statement: &Statement, entry: &StatementEntry) -> String {
+        if self.y < MARGIN + ROW_HEIGHT {
+            self.new_page();
+            self.table_header();
+        }
+
+        let transaction = entry.transaction;
+        let transaction_type = transaction.transaction_type.to_string();
+        let description = transaction.description.as_deref().unwrap_or_default();
+
+        self.text(COL_DATE, self.y, FONT_SIZE, false, &transaction.created_at.format("%Y-%m-%d %H:%M").to_string());
+        self.text(COL_DESCRIPTION, self.y, FONT_SIZE, false, &fit(description, COL_TYPE - COL_DESCRIPTION - 8.0));
+        self.text(COL_TYPE, self.y, FONT_SIZE, false, &transaction_type);
+        self.text(COL_STATUS, self.y, FONT_SIZE, false, &transaction.status.to_string());
+        self.text_right(COL_AMOUNT_RIGHT, self.y, FONT_SIZE, &amount(entry.amount));
+        self.text_right(COL_BALANCE_RIGHT, self.y, FONT_SIZE, &amount(entry.running_balance));
+        self.y -= ROW_HEIGHT;
+
+        let total = self.totals.entry(transaction_type).or_default();
+        total.count += 1;
+        total.amount += transaction.amount;
+
+        String::new()
+    }
+
+    fn footer(&mut self, statement: &Statement) -> String {
+        let summary_rows = self.totals.len() + 2;
+        if self.y < MARGIN + ROW_HEIGHT * (summary_rows as f32 + 2.0) {
+            self.new_page();
+        }
+
+        self.line(MARGIN, self.y + ROW_HEIGHT - 4.0, COL_BALANCE_RIGHT);
+        self.y -= 10.0;
+        self.text(MARGIN, self.y, 11.0, true, "Summary");
+        self.y -= ROW_HEIGHT + 4.0;
+
+        let totals = std::mem::take(&mut self.totals);
+        for (transaction_type, total) in &totals {
+            let label = format!("Total {} ({})", transaction_type.to_lowercase(), total.count);
+            self.text(MARGIN, self.y, 10.0, false, &label);
+            self.text_right(COL_BALANCE_RIGHT, self.y, 10.0, &amount(total.amount));
+            self.y -= ROW_HEIGHT;
+        }
+        if totals.is_empty() {
+            self.text(MARGIN, self.y, 10.0, false, "No transactions in this period");
+            self.y -= ROW_HEIGHT;
+        }
+
+        self.text(MARGIN, self.y, 10.0, true, "Closing balance");
+        self.text_right(COL_BALANCE_RIGHT, self.y, 10.0, &amount(statement.closing_balance));
+
+        self.pages.push(std::mem::take(&mut self.content));
+        self.document(statement)
+    }
+}
+
+impl PdfRenderer {
+    fn new_page(&mut self) {
+        self.pages.push(std::mem::take(&mut self.content));
+        self.y = PAGE_HEIGHT - MARGIN;
+    }
+
+    fn table_header(&mut self) {
+        self.content.push_str(&format!(
+            "0.92 g {} {} {} {} re f 0 g\n",
+            MARGIN - 4.0,
+            self.y - 4.0,
+            COL_BALANCE_RIGHT - MARGIN + 8.0,
+            ROW_HEIGHT
+        ));
+        self.text(COL_DATE, self.y, FONT_SIZE, true, "Date");
+        self.text(COL_DESCRIPTION, self.y, FONT_SIZE, true, "Description");
+        self.text(COL_TYPE, self.y, FONT_SIZE, true, "Type");
+        self.text(COL_STATUS, self.y, FONT_SIZE, true, "Status");
+        self.text_right(COL_AMOUNT_RIGHT, self.y, FONT_SIZE, "Amount");
+        self.text_right(COL_BALANCE_RIGHT, self.y, FONT_SIZE, "Balance");
+        self.y -= ROW_HEIGHT + 2.0;
+    }
+
+    fn text(&mut self, x: f32, y: f32, size: f32, bold: bool, value: &str) {
+        let font = if bold { "F2" } else { "F1" };
+        self.content.push_str(&format!(
+            "BT /{} {} Tf {:.2} {:.2} Td ({}) Tj ET\n",
+            font,
+            size,
+            x,
+            y,
+            escape(value)
+        ));
+    }
+
+    fn text_right(&mut self, right: f32, y: f32, size: f32, value: &str) {
+        let x = right - text_width(value, size);
+        self.text(x, y, size, false, value);
+    }
+
+    fn line(&mut self, x1: f32, y: f32, x2: f32) {
+        self.content.push_str(&format!("0.5 w {:.2} {:.2} m {:.2} {:.2} l S\n", x1, y, x2, y));
+    }
+
+    // Objects 1-4 are the catalog, page tree and the two fonts; each page then
+    // takes two objects, the page and its content stream, followed by the info
+    // dictionary.
+    fn document(&self, statement: &Statement) -> String {
+        let page_count = self.pages.len();
+        let mut objects: Vec<String> = Vec::with_capacity(5 + page_count * 2);
+
+        let kids: Vec<String> = (0..page_count).map(|i| format!("{} 0 R", 5 + i * 2)).collect();
+        objects.push("<< /Type /Catalog /Pages 2 0 R >>".to_string());
+        objects.push(format!("<< /Type /Pages /Kids [{}] /Count {} >>", kids.join(" "), page_count));
+        objects.push(font("Helvetica"));
+        objects.push(font("Helvetica-Bold"));
+
+        for (index, content) in self.pages.iter().enumerate() {
+            let page_number = format!("Page {} of {}", index + 1, page_count);
+            let footer = format!(
+                "BT /F1 8 Tf {:.2} {:.2} Td ({}) Tj ET\n",
+                COL_BALANCE_RIGHT - text_width(&page_number, 8.0),
+                MARGIN / 2.0,
+                page_number
+            );
+            let stream = format!("{}{}", content, footer);
+
+            objects.push(format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Resources << /Font << /F1 3 0 R /F2 4 0 R >> >> /Contents {} 0 R >>",
+                PAGE_WIDTH,
+                PAGE_HEIGHT,
+                6 + index * 2
+            ));
+            objects.push(format!("<< /Length {} >>\nstream\n{}endstream", stream.len(), stream));
+        }
+
+        objects.push(format!(
+            "<< /Title (Statement {}) /Producer (payments-api) /CreationDate (D:{}Z) >>",
+            statement.account_id,
+            statement.generated_at.format("%Y%m%d%H%M%S")
+        ));
+
+        // Every byte is ASCII, so string lengths are byte offsets
+        let mut pdf = String::from("%PDF-1.4\n");
+        let mut offsets = Vec::with_capacity(objects.len());
+        for (index, object) in objects.iter().enumerate() {
+            offsets.push(pdf.len());
+            pdf.push_str(&format!("{} 0 obj\n{}\nendobj\n", index + 1, object));
+        }
+
+        let xref = pdf.len();
+        pdf.push_str(&format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1));
+        for offset in offsets {
+            pdf.push_str(&format!("{:010} 00000 n \n", offset));
+        }
+        pdf.push_str(&format!(
+            "trailer\n<< /Size {} /Root 1 0 R /Info {} 0 R >>\nstartxref\n{}\n%%EOF\n",
+            objects.len() + 1,
+            objects.len(),
+            xref
+        ));
+        pdf
+    }
+}
+
+fn font(name: &str) -> String {
+    format!("<< /Type /Font /Subtype /Type1 /BaseFont /{} /Encoding /WinAnsiEncoding >>", name)
+}
+
+// Literal string for a content stream. Latin-1 characters are written as octal
+// escapes (WinAnsi agrees with Latin-1 there); anything else becomes `?`.
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' | '(' | ')' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            ' '..='~' => escaped.push(c),
+            '\u{a0}'..='\u{ff}' => escaped.push_str(&format!("\\{:03o}", c as u32)),
+            _ => escaped.push('?'),
+        }
+    }
+    escaped
+}
+
+// Helvetica advance widths for printable ASCII, in 1/1000 em.
+const HELVETICA_WIDTHS: [u16; 95] = [
+    278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278,
+    556, 556, 556, 556, 556, 556, 556, 556, 556, 556, 278, 278, 584, 584, 584, 556,
+    1015, 667, 667, 722, 722, 667, 611, 778, 722, 278, 500, 667, 556, 833, 722, 778,
+    667, 778, 722, 667, 611, 722, 667, 944, 667, 667, 611, 278, 278, 278, 469, 556,
+    333, 556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500, 222, 833, 556, 556,
+    556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, 334, 260, 334, 584,
+];
+
+fn text_width(value: &str, size: f32) -> f32 {
+    let units: u32 = value
+        .chars()
+        .map(|c| match c {
+            ' '..='~' => HELVETICA_WIDTHS[c as usize - 32] as u32,
+            _ => 556,
+        })
+        .sum();
+    units as f32 * size / 1000.0
+}
+
+// Shortens text with an ellipsis so it fits in `width` points.
+fn fit(value: &str, width: f32) -> String {
+    if text_width(value, FONT_SIZE) <= width {
+        return value.to_string();
+    }
+
+    let mut fitted = String::new();
+    for c in value.chars() {
+        if text_width(&format!("{}{}...", fitted, c), FONT_SIZE) > width {
+            break;
+        }
+        fitted.push(c);
+    }
+    format!("{}...", fitted.trim_end())
+}
+
+fn amount(value: Decimal) -> String {
+    format!("{:.2}", value)
+}
+
+fn datetime(ts: DateTime<Utc>) -> String {
+    ts.format("%Y-%m-%d %H:%M UTC").to_string()
+}
@@ -0,0 +1,203 @@
+use regex::Regex;
+use std::{
+    future::Future,
+    io::{self, Write},
+    sync::LazyLock,
+};
+use tracing_subscriber::{fmt::MakeWriter, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LogFormat {
+    Text,
+    Json,
+}
+
+impl LogFormat {
+    pub fn parse(value: &str) -> Self {
+        match value.to_ascii_lowercase().as_str() {
+            "json" => LogFormat::Json,
+            _ => LogFormat::Text,
+        }
+    }
+}
+
+pub fn init_tracing(format: LogFormat) {
+    let filter = EnvFilter::new(std::env::var("RUST_LOG").unwrap_or_else(|_| "info".into()));
+    let registry = tracing_subscriber::registry().with(filter);
+
+    match format {
+        LogFormat::Json => registry
+            .with(
+                tracing_subscriber::fmt::layer()
+                    .json()
+                    .with_current_span(true)
+                    .with_span_list(true)
+                    .with_writer(RedactingMakeWriter),
+            )
+            .init(),
+        // ANSI colors are disabled so escape codes can't split a field from its value
+        LogFormat::Text => registry
+            .with(
+                tracing_subscriber::fmt::layer()
+                    .with_ansi(false)
+                    .with_writer(RedactingMakeWriter),
+            )
+            .init(),
+    }
+}
+
+// Identifiers of the request currently being served, available to any code
+// running inside the request task (error rendering, audit records).
+#[derive(Debug, Clone)]
+pub struct RequestContext {
+    pub request_id: String,
+    pub path: String,
+    pub ip_address: Option<String>,
+}
+
+tokio::task_local! {
+    static REQUEST_CONTEXT: RequestContext;
+}
+
+pub async fn with_request_context<F: Future>(context: RequestContext, f: F) -> F::Output {
+    REQUEST_CONTEXT.scope(context, f).await
+}
+
+pub fn current_request_context() -> Option<RequestContext> {
+    REQUEST_CONTEXT.try_with(|context| context.clone()).ok()
+}
+
+// W3C Trace Context `traceparent` header: `00-<trace-id>-<parent-id>-<flags>`.
+#[derive(Debug, Clone)]
+pub struct TraceParent {
+    pub trace_id: String,
+    pub span_id: String,
+    pub parent_span_id: Option<String>,
+    pub flags: String,
+}
+
+impl TraceParent {
+    // Continues the caller's trace when the header is valid, otherwise starts a new one.
+    pub fn from_header(header: Option<&str>) -> Self {
+        match header.and_then(parse_traceparent) {
+            Some((trace_id, parent_span_id, flags)) => Self {
+                trace_id,
+                span_id: new_span_id(),
+                parent_span_id: Some(parent_span_id),
+                flags,
+            },
+            None => Self {
+                trace_id: Uuid::new_v4().simple().to_string(),
+                span_id: new_span_id(),
+                parent_span_id: None,
+                flags: "01".to_string(),
+            },
+        }
+    }
+
+    pub fn header_value(&self) -> String {
+        format!("00-{}-{}-{}", self.trace_id, self.span_id, self.flags)
+    }
+}
+
+fn parse_traceparent(header: &str) -> Option<(String, String, String)> {
+    let parts: Vec<&str> = header.trim().split('-').collect();
+    let [version, trace_id, parent_id, flags] = parts.as_slice() else {
+        return None;
+    };
+
+    let is_hex = |s: &str, len: usize| {
+        s.len() == len && s.chars().all(|c| c.is_ascii_hexdigit() && !c.is_ascii_uppercase())
+    };
+    let is_zero = |s: &str| s.chars().all(|c| c == '0');
+
+    if !is_hex(version, 2) || *version == "ff" || !is_hex(flags, 2) {
+        return None;
+    }
+    if !is_hex(trace_id, 32) || is_zero(trace_id) || !is_hex(parent_id, 16) || is_zero(parent_id) {
+        return None;
+    }
+
+    Some((trace_id.to_string(), parent_id.to_string(), flags.to_string()))
+}
+
+fn new_span_id() -> String {
+    Uuid::new_v4().simple().to_string()[..16].to_string()
+}
+
+// Secrets and PII are masked in every formatted log line before it is written,
+// so a stray field or error message can't leak them.
+static REDACTIONS: LazyLock<Vec<(Regex, &'static str)>> = LazyLock::new(|| {
+    vec![
+        (
+            Regex::new(r#"(?i)("?\b(?:password|password_hash|passwd|secret|token|authorization|jwt|api_key)\b"?\s*[:=]\s*)("(?:[^"\\]|\\.)*"|[^\s,}]+)"#).unwrap(),
+            r#"$1"[REDACTED]""#,
+        ),
+        (
+            Regex::new(r"(?i)\bbearer\s+[A-Za-z0-9\-_.~+/]+=*").unwrap(),
+            "Bearer [REDACTED]",
+        ),
+        (
+            Regex::new(r"\beyJ[A-Za-z0-9_-]+\.[A-Za-z0-9_-]+\.[A-Za-z0-9_-]+").unwrap(),
+            "[REDACTED_JWT]",
+        ),
+        (
+            Regex::new(r"\$argon2[a-z]*\$[^\s,}]+").unwrap(),
+            "[REDACTED_HASH]",
+        ),
+        (
+            Regex::new(r"[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}").unwrap(),
+            "[REDACTED_EMAIL]",
+        ),
+    ]
+});
+
+pub fn redact(line: &str) -> String {
+    REDACTIONS
+        .iter()
+        .fold(line.to_string(), |acc, (pattern, replacement)| {
+            pattern.replace_all(&acc, *replacement).into_owned()
+        })
+}
+
+pub struct RedactingMakeWriter;
+
+impl<'a> MakeWriter<'a> for RedactingMakeWriter {
+    type Writer = RedactingWriter;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        RedactingWriter { buffer: Vec::new() }
+    }
+}
+
+// Buffers one formatted event and writes the redacted result to stdout on drop.
+pub struct RedactingWriter {
+    buffer: Vec<u8>,
+}
+
+impl Write for RedactingWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+
+        let line = redact(&String::from_utf8_lossy(&self.buffer));
+        self.buffer.clear();
+
+        let mut stdout = io::stdout().lock();
+        stdout.write_all(line.as_bytes())?;
+        stdout.flush()
+    }
+}
+
+impl Drop for RedactingWriter {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
@@ -13,8 +13,6 @@ pub fn hash_password(password: &str) -> Result<String, argon2::password_hash::Er
 }
 
 pub fn validate_password(password: &str, password_hash: &str) -> Result<(), argon2::password_hash::Error> {
-    println!("password: {}", password);
-    println!("password_hash: {}", password_hash);
     let argon2 = Argon2::default();
     let hash = PasswordHash::new(password_hash)?;
     argon2.verify_password(password.as_bytes(), &hash)?;
@@ -0,0 +1,170 @@
+use rust_decimal::Decimal;
+use serde::de::DeserializeOwned;
+use std::borrow::Cow;
+use validator::{Validate, ValidateUrl, ValidationError, ValidationErrors, ValidationErrorsKind};
+use serde_json::Value;
+use crate::base::{
+    constants::{MAX_AMOUNT, MAX_AMOUNT_SCALE, MAX_FEE_PERCENTAGE_SCALE, MAX_TAGS, MAX_TAG_LENGTH},
+    error::{AppError, FieldError},
+};
+
+// Custom rules referenced from `#[validate(custom(function = ...))]` attributes.
+
+pub fn positive_amount(amount: &Decimal) -> Result<(), ValidationError> {
+    if *amount <= Decimal::ZERO {
+        return Err(rule_error("POSITIVE", "Amount must be greater than zero"));
+    }
+    amount_within_limits(amount)
+}
+
+pub fn non_negative_amount(amount: &Decimal) -> Result<(), ValidationError> {
+    if *amount < Decimal::ZERO {
+        return Err(rule_error("NON_NEGATIVE", "Amount must not be negative"));
+    }
+    amount_within_limits(amount)
+}
+
+fn amount_within_limits(amount: &Decimal) -> Result<(), ValidationError> {
+    if amount.normalize().scale() > MAX_AMOUNT_SCALE {
+        return Err(rule_error("PRECISION", "Amount must have at most 2 decimal places"));
+    }
+    if *amount > MAX_AMOUNT {
+        return Err(rule_error("MAX", "Amount exceeds the maximum allowed value"));
+    }
+    Ok(())
+}
+
+pub fn fee_percentage(percentage: &Decimal) -> Result<(), ValidationError> {
+    if *percentage < Decimal::ZERO || *percentage > Decimal::ONE_HUNDRED {
+        return Err(rule_error("RANGE", "percentage must be between 0 and 100"));
+    }
+    if percentage.normalize().scale() > MAX_FEE_PERCENTAGE_SCALE {
+        return Err(rule_error("PRECISION", "percentage must have at most 4 decimal places"));
+    }
+    Ok(())
+}
+
+pub fn currency_code(currency: &str) -> Result<(), ValidationError> {
+    if currency.len() != 3 || !currency.chars().all(|c| c.is_ascii_uppercase()) {
+        return Err(rule_error("CURRENCY", "Currency must be a 3-letter ISO 4217 code"));
+    }
+    Ok(())
+}
+
+pub fn country_code(country: &str) -> Result<(), ValidationError> {
+    if country.len() != 2 || !country.chars().all(|c| c.is_ascii_uppercase()) {
+        return Err(rule_error("COUNTRY", "Country must be a 2-letter ISO 3166 code"));
+    }
+    Ok(())
+}
+
+pub fn sha256_hex(checksum: &str) -> Result<(), ValidationError> {
+    if checksum.len() != 64 || !checksum.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(rule_error("SHA256", "sha256 must be 64 hexadecimal characters"));
+    }
+    Ok(())
+}
+
+pub fn not_blank(value: &str) -> Result<(), ValidationError> {
+    if value.trim().is_empty() {
+        return Err(rule_error("NOT_BLANK", "Value must not be blank"));
+    }
+    Ok(())
+}
+
+pub fn metadata_object(metadata: &Value) -> Result<(), ValidationError> {
+    if !metadata.is_object() {
+        return Err(rule_error("OBJECT", "Metadata must be a JSON object"));
+    }
+    Ok(())
+}
+
+pub fn tag_list(tags: &[String]) -> Result<(), ValidationError> {
+    if tags.len() > MAX_TAGS {
+        return Err(rule_error("MAX_ITEMS", "At most 20 tags are allowed"));
+    }
+    if tags.iter().any(|tag| tag.trim().is_empty() || tag.len() > MAX_TAG_LENGTH) {
+        return Err(rule_error("TAG", "Tags must be between 1 and 50 characters"));
+    }
+    Ok(())
+}
+
+pub fn webhook_url(url: &str) -> Result<(), ValidationError> {
+    if !url.starts_with("https://") || !url.validate_url() {
+        return Err(rule_error("URL", "url must be an absolute https URL"));
+    }
+    Ok(())
+}
+
+fn rule_error(code: &'static str, message: &'static str) -> ValidationError {
+    ValidationError::new(code).with_message(Cow::Borrowed(message))
+}
+
+// Struct-level rules are reported by `validator` under `__all__`; this records
+// the field they concern so it can be reported instead.
+pub fn field_error(field: &'static str, code: &'static str, message: &'static str) -> ValidationError {
+    let mut error = rule_error(code, message);
+    error.add_param(Cow::Borrowed("field"), &field);
+    error
+}
+
+// Reads and validates the JSON `params` of a configurable rule. Errors are
+// reported under `params`, e.g. `params.max_count`.
+pub fn parse_params<T: DeserializeOwned + Validate>(params: &Value) -> Result<T, AppError> {
+    let parsed: T = serde_json::from_value(params.clone())
+        .map_err(|e| AppError::invalid_field("params", "INVALID_PARAMS", &e.to_string()))?;
+
+    parsed.validate().map_err(|errors| {
+        let mut field_errors = to_field_errors(&errors);
+        for error in &mut field_errors {
+            error.field = format!("params.{}", error.field);
+        }
+        AppError::InvalidFields(field_errors)
+    })?;
+
+    Ok(parsed)
+}
+
+// Flattens `validator` output into the `errors` list of the problem body,
+// using dotted paths for nested fields.
+pub fn to_field_errors(errors: &ValidationErrors) -> Vec<FieldError> {
+    let mut field_errors = Vec::new();
+    collect(errors, "", &mut field_errors);
+    field_errors.sort_by(|a, b| a.field.cmp(&b.field));
+    field_errors
+}
+
+fn collect(errors: &ValidationErrors, prefix: &str, out: &mut Vec<FieldError>) {
+    for (field, kind) in errors.errors() {
+        let path = if prefix.is_empty() {
+            field.to_string()
+        } else {
+            format!("{}.{}", prefix, field)
+        };
+
+        match kind {
+            ValidationErrorsKind::Field(errors) => {
+                out.extend(errors.iter().map(|error| {
+                    let message = error
+                        .message
+                        .as_ref()
+                        .map(|m| m.to_string())
+                        .unwrap_or_else(|| format!("{} is invalid", path));
+                    let field = error
+                        .params
+                        .get("field")
+                        .and_then(|field| field.as_str())
+                        .map(str::to_string)
+                        .unwrap_or_else(|| path.clone());
+                    FieldError::new(field, error.code.to_uppercase(), message)
+                }));
+            }
+            ValidationErrorsKind::Struct(nested) => collect(nested, &path, out),
+            ValidationErrorsKind::List(items) => {
+                for (index, nested) in items {
+                    collect(nested, &format!("{}[{}]", path, index), out);
+                }
+            }
+        }
+    }
+}
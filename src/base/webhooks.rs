@@ -0,0 +1,170 @@
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use hmac::{Hmac, Mac};
+use serde_json::json;
+use sha2::Sha256;
+use std::{
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    time::Duration,
+};
+use crate::base::{
+    constants::{WEBHOOK_RETRY_BASE_DELAY, WEBHOOK_RETRY_MAX_DELAY},
+    models::webhooks::ClaimedDelivery,
+};
+
+pub const ID_HEADER: &str = "webhook-id";
+pub const TIMESTAMP_HEADER: &str = "webhook-timestamp";
+pub const SIGNATURE_HEADER: &str = "webhook-signature";
+
+pub fn generate_secret() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    format!("whsec_{}", hex(&bytes))
+}
+
+// The request body of a delivery. The event id doubles as an idempotency key,
+// since a delivery can arrive more than once.
+pub fn delivery_body(delivery: &ClaimedDelivery) -> Vec<u8> {
+    json!({
+        "id": delivery.event_id,
+        "type": delivery.event_type,
+        "created_at": delivery.event_created_at,
+        "data": delivery.payload,
+    })
+    .to_string()
+    .into_bytes()
+}
+
+// `v1=` followed by the hex HMAC-SHA256 of `<timestamp>.<body>` under the
+// endpoint secret. Receivers recompute it and reject stale timestamps, so a
+// captured request can't be replayed later.
+pub fn signature(secret: &str, timestamp: i64, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(format!("{}.", timestamp).as_bytes());
+    mac.update(body);
+    format!("v1={}", hex(&mac.finalize().into_bytes()))
+}
+
+// Exponential backoff after the given number of failed attempts.
+pub fn retry_delay(attempts: i32) -> Duration {
+    let exponent = attempts.saturating_sub(1).clamp(0, 16) as u32;
+    WEBHOOK_RETRY_BASE_DELAY
+        .saturating_mul(2u32.pow(exponent))
+        .min(WEBHOOK_RETRY_MAX_DELAY)
+}
+
+// Deliveries only go to public addresses, so an endpoint can't be pointed at
+// the internal network. IPv6 addresses that embed an IPv4 address (mapped,
+// IPv4-compatible, NAT64 and 6to4) are checked as that IPv4 address, since
+// they can be routed to it.
+pub fn is_public_address(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_ipv4(ip),
+        IpAddr::V6(ip) => match embedded_ipv4(ip) {
+            Some(ip) => is_public_ipv4(ip),
+            None => !(ip.is_loopback() || ip.is_unspecified() || ip.is_unique_local() || ip.is_unicast_link_local()),
+        },
+    }
+}
+
+fn is_public_ipv4(ip: Ipv4Addr) -> bool {
+    let [a, b, c, _] = ip.octets();
+    let this_network = a == 0;
+    let shared = a == 100 && (b & 0xc0) == 64;
+    let protocol_assignments = a == 192 && b == 0 && c == 0;
+    let benchmarking = a == 198 && (b & 0xfe) == 18;
+
+    !(ip.is_loopback()
+        || ip.is_private()
+        || ip.is_link_local()
+        || ip.is_broadcast()
+        || this_network
+        || shared
+        || protocol_assignments
+        || benchmarking)
+}
+
+fn embedded_ipv4(ip: Ipv6Addr) -> Option<Ipv4Addr> {
+    let [s0, s1, s2, s3, s4, s5, s6, s7] = ip.segments();
+    let ipv4 = |high: u16, low: u16| Some(Ipv4Addr::from((u32::from(high) << 16) | u32::from(low)));
+    match [s0, s1, s2, s3, s4, s5] {
+        // ::ffff:a.b.c.d and ::a.b.c.d; :: and ::1 land in 0.0.0.0/8
+        [0, 0, 0, 0, 0, 0xffff | 0] => ipv4(s6, s7),
+        // NAT64, 64:ff9b::/96
+        [0x64, 0xff9b, 0, 0, 0, 0] => ipv4(s6, s7),
+        // 6to4, 2002::/16
+        [0x2002, ..] => ipv4(s1, s2),
+        _ => None,
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn public(ip: &str) -> bool {
+        is_public_address(ip.parse().unwrap())
+    }
+
+    #[test]
+    fn public_ipv4_addresses_are_allowed() {
+        assert!(public("93.184.216.34"));
+        assert!(public("8.8.8.8"));
+        assert!(public("100.128.0.1"));
+        assert!(public("198.20.0.1"));
+    }
+
+    #[test]
+    fn internal_ipv4_ranges_are_refused() {
+        for ip in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "255.255.255.255",
+            "0.0.0.0",
+            "0.1.2.3",
+            "100.64.0.1",
+            "100.127.255.254",
+            "192.0.0.8",
+            "198.18.0.1",
+            "198.19.255.254",
+        ] {
+            assert!(!public(ip), "{} should be refused", ip);
+        }
+    }
+
+    #[test]
+    fn public_ipv6_addresses_are_allowed() {
+        assert!(public("2606:4700::1111"));
+        assert!(public("64:ff9b::808:808"));
+        assert!(public("2002:808:808::1"));
+        assert!(public("::ffff:8.8.8.8"));
+    }
+
+    #[test]
+    fn internal_ipv6_ranges_are_refused() {
+        for ip in ["::1", "::", "fd00::1", "fe80::1"] {
+            assert!(!public(ip), "{} should be refused", ip);
+        }
+    }
+
+    #[test]
+    fn embedded_ipv4_addresses_are_checked_as_ipv4() {
+        for ip in [
+            "::ffff:127.0.0.1",
+            "::127.0.0.1",
+            "::10.0.0.1",
+            "64:ff9b::7f00:1",
+            "64:ff9b::a9fe:a9fe",
+            "2002:7f00:1::",
+            "2002:c0a8:101::1",
+        ] {
+            assert!(!public(ip), "{} should be refused", ip);
+        }
+    }
+}
@@ -0,0 +1,161 @@
+use deadpool_postgres::Pool;
+use crate::{
+    base::{
+        models::{audit::NewAuditEntry, reconciliation::StatementFileFormat, screening::WatchlistReloadReport, users::Role},
+        screening,
+    },
+    db::dal::{audit as audit_queries, users as user_queries},
+    services::{
+        aml as aml_service, audit as audit_service, reconciliation as reconciliation_service,
+        screening::{self as screening_service, WatchlistCache},
+    },
+};
+
+pub const USAGE: &str = "Usage:
+  dodo-assignment-rust                                   Start the API server
+  dodo-assignment-rust grant-admin <email>               Give a user the ADMIN role
+  dodo-assignment-rust import-statement <file> [mt940|camt053]
+                                                         Import and reconcile a bank statement
+  dodo-assignment-rust verify-audit-log                  Check the audit log hash chain
+  dodo-assignment-rust reload-watchlists <file>...       Load OFAC sdn.csv/alt.csv or UN XML sanctions lists
+                                                         and rescreen all users
+  dodo-assignment-rust rescreen-users                    Screen all users against the loaded lists
+  dodo-assignment-rust run-aml-monitoring                Scan transactions for the AML scenarios now";
+
+// Maintenance commands that run against the database instead of starting the server.
+pub enum Command {
+    Serve,
+    GrantAdmin { email: String },
+    ImportStatement { path: String, format: Option<StatementFileFormat> },
+    VerifyAuditLog,
+    ReloadWatchlists { paths: Vec<String> },
+    RescreenUsers,
+    RunAmlMonitoring,
+}
+
+impl Command {
+    pub fn parse(args: &[String]) -> Result<Self, String> {
+        match args {
+            [] => Ok(Command::Serve),
+            [command] if command == "verify-audit-log" => Ok(Command::VerifyAuditLog),
+            [command] if command == "rescreen-users" => Ok(Command::RescreenUsers),
+            [command] if command == "run-aml-monitoring" => Ok(Command::RunAmlMonitoring),
+            [command, paths @ ..] if command == "reload-watchlists" && !paths.is_empty() => {
+                Ok(Command::ReloadWatchlists { paths: paths.to_vec() })
+            }
+            [command, email] if command == "grant-admin" => Ok(Command::GrantAdmin { email: email.clone() }),
+            [command, path] if command == "import-statement" => Ok(Command::ImportStatement {
+                path: path.clone(),
+                format: None,
+            }),
+            [command, path, format] if command == "import-statement" => Ok(Command::ImportStatement {
+                path: path.clone(),
+                format: Some(
+                    StatementFileFormat::parse(format).ok_or_else(|| format!("Unknown statement format `{}`", format))?,
+                ),
+            }),
+            _ => Err(USAGE.to_string()),
+        }
+    }
+}
+
+pub async fn run(command: Command, pool: &Pool) -> Result<(), String> {
+    let mut client = pool.get().await.map_err(|e| e.to_string())?;
+
+    match command {
+        Command::Serve => Ok(()),
+        Command::GrantAdmin { email } => {
+            let db_transaction = client.transaction().await.map_err(|e| e.to_string())?;
+
+            let existing = user_queries::get_user_by_email(&db_transaction, &email)
+                .await
+                .map_err(|e| e.to_string())?
+                .ok_or_else(|| format!("No user with email {}", email))?;
+            let user = user_queries::set_user_role(&db_transaction, &email, Role::Admin)
+                .await
+                .map_err(|e| e.to_string())?
+                .ok_or_else(|| format!("No user with email {}", email))?;
+
+            audit_queries::create_entry(
+                &db_transaction,
+                &NewAuditEntry::new(None, "user.role_changed", "user", Some(user.id))
+                    .before(&existing.audit_snapshot())
+                    .after(&user.audit_snapshot()),
+            )
+            .await
+            .map_err(|e| e.to_string())?;
+
+            db_transaction.commit().await.map_err(|e| e.to_string())?;
+            println!("{} ({}) is now {}", user.email, user.id, user.role);
+            Ok(())
+        }
+        Command::ImportStatement { path, format } => {
+            let content = std::fs::read_to_string(&path).map_err(|e| format!("Can't read {}: {}", path, e))?;
+            let filename = std::path::Path::new(&path)
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned());
+
+            let report = reconciliation_service::import_statement(&mut client, &content, format, filename, None)
+                .await
+                .map_err(|e| match e {
+                    crate::base::error::AppError::InvalidFields(errors) => errors
+                        .iter()
+                        .map(|error| error.message.clone())
+                        .collect::<Vec<_>>()
+                        .join("; "),
+                    e => e.to_string(),
+                })?;
+
+            println!("{}", serde_json::to_string_pretty(&report).map_err(|e| e.to_string())?);
+            Ok(())
+        }
+        Command::VerifyAuditLog => {
+            let report = audit_service::verify_chain(&client).await.map_err(|e| e.to_string())?;
+            println!("{}", serde_json::to_string_pretty(&report).map_err(|e| e.to_string())?);
+
+            if report.is_intact() {
+                Ok(())
+            } else {
+                Err(format!(
+                    "Audit log chain is broken at entry {}: {}",
+                    report.first_broken_seq.unwrap_or_default(),
+                    report.problem.unwrap_or_default()
+                ))
+            }
+        }
+        Command::ReloadWatchlists { paths } => {
+            let mut files = Vec::new();
+            for path in &paths {
+                let content = std::fs::read_to_string(path).map_err(|e| format!("Can't read {}: {}", path, e))?;
+                files.push(screening::parse_file(&content).map_err(|e| format!("{}: {}", path, e))?);
+            }
+            let lists = screening::combine(files)?;
+
+            let watchlists = WatchlistCache::default();
+            let lists = screening_service::reload_watchlists(&mut client, &watchlists, lists)
+                .await
+                .map_err(|e| e.to_string())?;
+            let rescreen =
+                screening_service::rescreen_users(&client, &watchlists).await.map_err(|e| e.to_string())?;
+
+            let report = WatchlistReloadReport { lists, rescreen };
+            println!("{}", serde_json::to_string_pretty(&report).map_err(|e| e.to_string())?);
+            Ok(())
+        }
+        Command::RescreenUsers => {
+            let report = screening_service::rescreen_users(&client, &WatchlistCache::default())
+                .await
+                .map_err(|e| e.to_string())?;
+            println!("{}", serde_json::to_string_pretty(&report).map_err(|e| e.to_string())?);
+            Ok(())
+        }
+        Command::RunAmlMonitoring => {
+            let run = aml_service::run(&mut client, None)
+                .await
+                .map_err(|e| e.to_string())?
+                .ok_or_else(|| "An AML monitoring run is already in progress".to_string())?;
+            println!("{}", serde_json::to_string_pretty(&run).map_err(|e| e.to_string())?);
+            Ok(())
+        }
+    }
+}
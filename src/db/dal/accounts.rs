@@ -1,17 +1,24 @@
-use crate::base::models::accounts::{Account, CreateAccountRequest, UpdateAccountRequest};
-use deadpool_postgres::Client;
+use crate::base::{
+    constants::DEFAULT_CURRENCY,
+    models::accounts::{Account, AccountStatus, CreateAccountRequest, UpdateAccountRequest},
+    pagination::{PageRequest, SortField},
+};
+use crate::db::query::QueryBuilder;
+use deadpool_postgres::{Client, GenericClient};
 use tokio_postgres::Error;
+use tracing::instrument;
 use uuid::Uuid;
 use rust_decimal::Decimal;
 
-pub async fn create_account(client: &Client, account: &CreateAccountRequest) -> Result<Account, Error> {
-    let currency: String = account.currency.clone().unwrap_or("INR".to_string());
+#[instrument(name = "dal.accounts.create_account", skip_all)]
+pub async fn create_account(client: &impl GenericClient, account: &CreateAccountRequest) -> Result<Account, Error> {
+    let currency: String = account.currency.clone().unwrap_or(DEFAULT_CURRENCY.to_string());
     let initial_balance: Decimal = account.initial_balance.unwrap_or(Decimal::from(0));
 
     let statement = client
         .prepare(
             "INSERT INTO accounts (user_id, balance, currency) VALUES ($1, $2, $3)
-             RETURNING id, user_id, balance, currency, created_at, updated_at",
+             RETURNING id, user_id, balance, currency, status, closed_at, created_at, updated_at",
         )
         .await?;
 
@@ -21,10 +28,11 @@ pub async fn create_account(client: &Client, account: &CreateAccountRequest) ->
         .try_into()
 }
 
-pub async fn get_account_by_id(client: &Client, id: Uuid) -> Result<Option<Account>, Error> {
+#[instrument(name = "dal.accounts.get_account_by_id", skip_all, fields(id = %id))]
+pub async fn get_account_by_id(client: &impl GenericClient, id: Uuid) -> Result<Option<Account>, Error> {
     let statement = client
         .prepare(
-            "SELECT id, user_id, balance, currency, created_at, updated_at
+            "SELECT id, user_id, balance, currency, status, closed_at, created_at, updated_at
              FROM accounts WHERE id = $1",
         )
         .await?;
@@ -35,28 +43,40 @@ pub async fn get_account_by_id(client: &Client, id: Uuid) -> Result<Option<Accou
         .map(|row| row.try_into().unwrap()))
 }
 
+#[instrument(name = "dal.accounts.get_accounts_by_user_id", skip_all, fields(user_id = %user_id))]
 pub async fn get_accounts_by_user_id(
     client: &Client,
     user_id: Uuid,
-    offset: i64,
-    limit: i64,
+    page: &PageRequest,
 ) -> Result<Vec<Account>, Error> {
-    let statement = client
-        .prepare(
-            "SELECT id, user_id, balance, currency, created_at, updated_at
-             FROM accounts
-             WHERE user_id = $1
-             ORDER BY created_at DESC
-             LIMIT $2 OFFSET $3",
-        )
-        .await?;
+    let mut query = QueryBuilder::new(
+        "SELECT id, user_id, balance, currency, status, closed_at, created_at, updated_at
+         FROM accounts",
+    );
+    query.push_bind(" WHERE user_id = {}", user_id);
 
-    let rows = client.query(&statement, &[&user_id, &limit, &offset]).await?;
+    let sort_column = match page.sort {
+        SortField::Balance => "balance",
+        _ => "created_at",
+    };
+    query.paginate(page, sort_column, "id");
+
+    let statement = client.prepare(query.sql()).await?;
+    let rows = client.query(&statement, &query.params()).await?;
     Ok(rows.into_iter().map(|row| row.try_into().unwrap()).collect())
 }
 
+#[instrument(name = "dal.accounts.count_accounts_by_user_id", skip_all, fields(user_id = %user_id))]
+pub async fn count_accounts_by_user_id(client: &Client, user_id: Uuid) -> Result<i64, Error> {
+    let statement = client
+        .prepare("SELECT COUNT(*) FROM accounts WHERE user_id = $1")
+        .await?;
+    Ok(client.query_one(&statement, &[&user_id]).await?.get(0))
+}
+
+#[instrument(name = "dal.accounts.update_account", skip_all, fields(id = %id))]
 pub async fn update_account(
-    client: &Client,
+    client: &impl GenericClient,
     id: Uuid,
     account: &UpdateAccountRequest,
 ) -> Result<Option<Account>, Error> {
@@ -66,7 +86,7 @@ pub async fn update_account(
              SET currency = COALESCE($1, currency),
              updated_at = NOW()
              WHERE id = $2
-             RETURNING id, user_id, balance, currency, created_at, updated_at",
+             RETURNING id, user_id, balance, currency, status, closed_at, created_at, updated_at",
         )
         .await?;
 
@@ -79,16 +99,118 @@ pub async fn update_account(
         .map(|row| row.try_into().unwrap()))
 }
 
-pub async fn delete_account(client: &Client, id: Uuid) -> Result<bool, Error> {
+// Locks the account row until the surrounding transaction ends.
+#[instrument(name = "dal.accounts.get_account_for_update", skip_all, fields(id = %id))]
+pub async fn get_account_for_update(client: &impl GenericClient, id: Uuid) -> Result<Option<Account>, Error> {
+    let statement = client
+        .prepare(
+            "SELECT id, user_id, balance, currency, status, closed_at, created_at, updated_at
+             FROM accounts WHERE id = $1
+             FOR UPDATE",
+        )
+        .await?;
+
+    Ok(client
+        .query_opt(&statement, &[&id])
+        .await?
+        .map(|row| row.try_into().unwrap()))
+}
+
+#[instrument(name = "dal.accounts.set_account_status", skip_all, fields(id = %id))]
+pub async fn set_account_status(
+    client: &impl GenericClient,
+    id: Uuid,
+    status: AccountStatus,
+) -> Result<Option<Account>, Error> {
+    let statement = client
+        .prepare(
+            "UPDATE accounts
+             SET status = $1::VARCHAR,
+             closed_at = CASE WHEN $1::VARCHAR = 'CLOSED' THEN NOW() END,
+             updated_at = NOW()
+             WHERE id = $2
+             RETURNING id, user_id, balance, currency, status, closed_at, created_at, updated_at",
+        )
+        .await?;
+
+    Ok(client
+        .query_opt(&statement, &[&status.to_string(), &id])
+        .await?
+        .map(|row| row.try_into().unwrap()))
+}
+
+// Every account of a user, oldest first, including closed ones.
+#[instrument(name = "dal.accounts.get_all_accounts_by_user_id", skip_all, fields(user_id = %user_id))]
+pub async fn get_all_accounts_by_user_id(client: &impl GenericClient, user_id: Uuid) -> Result<Vec<Account>, Error> {
+    let statement = client
+        .prepare(
+            "SELECT id, user_id, balance, currency, status, closed_at, created_at, updated_at
+             FROM accounts WHERE user_id = $1
+             ORDER BY created_at, id",
+        )
+        .await?;
+
+    let rows = client.query(&statement, &[&user_id]).await?;
+    Ok(rows.into_iter().map(|row| row.try_into().unwrap()).collect())
+}
+
+// Locks every account of a user until the surrounding transaction ends.
+#[instrument(name = "dal.accounts.lock_accounts_by_user_id", skip_all, fields(user_id = %user_id))]
+pub async fn lock_accounts_by_user_id(client: &impl GenericClient, user_id: Uuid) -> Result<Vec<Account>, Error> {
     let statement = client
-        .prepare("DELETE FROM accounts WHERE id = $1")
+        .prepare(
+            "SELECT id, user_id, balance, currency, status, closed_at, created_at, updated_at
+             FROM accounts WHERE user_id = $1
+             ORDER BY created_at, id
+             FOR UPDATE",
+        )
         .await?;
 
-    Ok(client.execute(&statement, &[&id]).await? > 0)
+    let rows = client.query(&statement, &[&user_id]).await?;
+    Ok(rows.into_iter().map(|row| row.try_into().unwrap()).collect())
 }
 
+#[instrument(name = "dal.accounts.close_accounts_by_user_id", skip_all, fields(user_id = %user_id))]
+pub async fn close_accounts_by_user_id(client: &impl GenericClient, user_id: Uuid) -> Result<u64, Error> {
+    let statement = client
+        .prepare(
+            "UPDATE accounts
+             SET status = 'CLOSED',
+             closed_at = NOW(),
+             updated_at = NOW()
+             WHERE user_id = $1 AND status <> 'CLOSED'",
+        )
+        .await?;
+
+    client.execute(&statement, &[&user_id]).await
+}
+
+// Fees only ever accompany a transaction the account was allowed to make, so
+// unlike other debits they can also be taken from a frozen account. No row is
+// returned when the balance doesn't cover the fee.
+#[instrument(name = "dal.accounts.deduct_fee", skip_all, fields(id = %id))]
+pub async fn deduct_fee(client: &impl GenericClient, id: Uuid, amount: Decimal) -> Result<Option<Account>, Error> {
+    let statement = client
+        .prepare(
+            "UPDATE accounts
+             SET balance = balance - $1,
+             updated_at = NOW()
+             WHERE id = $2 AND balance >= $1 AND status <> 'CLOSED'
+             RETURNING id, user_id, balance, currency, status, closed_at, created_at, updated_at",
+        )
+        .await?;
+
+    Ok(client
+        .query_opt(&statement, &[&amount, &id])
+        .await?
+        .map(|row| row.try_into().unwrap()))
+}
+
+// Credits fail on closed accounts and debits on anything but active ones, in
+// which case no row is returned.
+#[instrument(name = "dal.accounts.update_account_balance", skip_all, fields(id = %id))]
 pub async fn update_account_balance(
-    client: &Client,
+    client: &impl GenericClient,
     id: Uuid,
     amount: Decimal,
     is_credit: bool,
@@ -99,8 +221,8 @@ pub async fn update_account_balance(
                 "UPDATE accounts
                  SET balance = balance + $1,
                  updated_at = NOW()
-                 WHERE id = $2
-                 RETURNING id, user_id, balance, currency, created_at, updated_at",
+                 WHERE id = $2 AND status <> 'CLOSED'
+                 RETURNING id, user_id, balance, currency, status, closed_at, created_at, updated_at",
             )
             .await?
     } else {
@@ -109,8 +231,8 @@ pub async fn update_account_balance(
                 "UPDATE accounts
                  SET balance = balance - $1,
                  updated_at = NOW()
-                 WHERE id = $2 AND balance >= $1
-                 RETURNING id, user_id, balance, currency, created_at, updated_at",
+                 WHERE id = $2 AND balance >= $1 AND status = 'ACTIVE'
+                 RETURNING id, user_id, balance, currency, status, closed_at, created_at, updated_at",
             )
             .await?
     };
@@ -0,0 +1,502 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde_json::Value;
+use crate::base::{
+    constants::{AML_MONITORING_LOCK_KEY, FEE_TRANSACTION_TAG},
+    models::{
+        aml::{
+            AmlCase, AmlCasePaginationParams, AmlCaseStatus, AmlDetection, AmlRun, AmlScenario, AmlScenarioCode,
+        },
+        transactions::Transaction,
+    },
+    pagination::PageRequest,
+};
+use crate::db::query::QueryBuilder;
+use deadpool_postgres::{Client, GenericClient};
+use tokio_postgres::Error;
+use tracing::instrument;
+use uuid::Uuid;
+
+const SCENARIO_COLUMNS: &str = "code, enabled, params, updated_by, updated_at";
+
+const RUN_COLUMNS: &str = "id, triggered_by, started_at, finished_at, detections, cases_opened, cases_updated";
+
+const CASE_COLUMNS: &str =
+    "id, scenario, status, user_id, account_id, currency, total_amount, transaction_ids, reason, details, run_id,
+     assigned_to, resolved_by, resolved_at, resolution_note, created_at, updated_at";
+
+#[instrument(name = "dal.aml.list_scenarios", skip_all)]
+pub async fn list_scenarios(client: &impl GenericClient) -> Result<Vec<AmlScenario>, Error> {
+    let statement = client
+        .prepare(&format!("SELECT {} FROM aml_scenarios ORDER BY code", SCENARIO_COLUMNS))
+        .await?;
+
+    let rows = client.query(&statement, &[]).await?;
+    Ok(rows.into_iter().map(|row| row.try_into().unwrap()).collect())
+}
+
+#[instrument(name = "dal.aml.get_scenario_for_update", skip_all, fields(code = %code))]
+pub async fn get_scenario_for_update(
+    client: &impl GenericClient,
+    code: AmlScenarioCode,
+) -> Result<Option<AmlScenario>, Error> {
+    let statement = client
+        .prepare(&format!("SELECT {} FROM aml_scenarios WHERE code = $1 FOR UPDATE", SCENARIO_COLUMNS))
+        .await?;
+
+    Ok(client
+        .query_opt(&statement, &[&code.to_string()])
+        .await?
+        .map(|row| row.try_into().unwrap()))
+}
+
+#[instrument(name = "dal.aml.update_scenario", skip_all, fields(code = %code))]
+pub async fn update_scenario(
+    client: &impl GenericClient,
+    code: AmlScenarioCode,
+    enabled: bool,
+    params: &Value,
+    updated_by: Uuid,
+) -> Result<AmlScenario, Error> {
+    let statement = client
+        .prepare(&format!(
+            "UPDATE aml_scenarios
+             SET enabled = $2, params = $3, updated_by = $4, updated_at = NOW()
+             WHERE code = $1
+             RETURNING {}",
+            SCENARIO_COLUMNS
+        ))
+        .await?;
+
+    let row = client
+        .query_one(&statement, &[&code.to_string(), &enabled, params, &updated_by])
+        .await?;
+    Ok(row.try_into().unwrap())
+}
+
+// Keeps monitoring runs from overlapping until the surrounding transaction
+// ends. Returns false when another run holds the lock.
+#[instrument(name = "dal.aml.try_lock_run", skip_all)]
+pub async fn try_lock_run(client: &impl GenericClient) -> Result<bool, Error> {
+    let statement = client.prepare("SELECT pg_try_advisory_xact_lock($1)").await?;
+
+    Ok(client.query_one(&statement, &[&AML_MONITORING_LOCK_KEY]).await?.get(0))
+}
+
+#[instrument(name = "dal.aml.last_run_started_at", skip_all)]
+pub async fn last_run_started_at(client: &impl GenericClient) -> Result<Option<DateTime<Utc>>, Error> {
+    let statement = client.prepare("SELECT MAX(started_at) FROM aml_runs").await?;
+
+    Ok(client.query_one(&statement, &[]).await?.get(0))
+}
+
+// Deposits of at least `floor` and below `threshold` since `since`, per user
+// and currency, when there are at least `min_count` of them. Failed
+// transactions and fees don't count.
+#[instrument(name = "dal.aml.detect_structuring", skip_all)]
+pub async fn detect_structuring(
+    client: &impl GenericClient,
+    since: DateTime<Utc>,
+    floor: Decimal,
+    threshold: Decimal,
+    min_count: i64,
+) -> Result<Vec<AmlDetection>, Error> {
+    let statement = client
+        .prepare(
+            "SELECT a.user_id, NULL::UUID AS account_id, a.currency,
+                    SUM(t.amount) AS total_amount,
+                    ARRAY_AGG(t.id ORDER BY t.created_at, t.id) AS transaction_ids,
+                    jsonb_build_object('deposits', COUNT(*), 'accounts', COUNT(DISTINCT t.account_id)) AS details
+             FROM transactions t
+             JOIN accounts a ON a.id = t.account_id
+             WHERE t.type = 'DEPOSIT' AND t.status <> 'FAILED' AND NOT ($5 = ANY(t.tags))
+               AND t.created_at >= $1 AND t.amount >= $2 AND t.amount < $3
+             GROUP BY a.user_id, a.currency
+             HAVING COUNT(*) >= $4",
+        )
+        .await?;
+
+    let rows = client
+        .query(&statement, &[&since, &floor, &threshold, &min_count, &FEE_TRANSACTION_TAG])
+        .await?;
+    Ok(rows
+        .into_iter()
+        .map(|row| AmlDetection::from_row(AmlScenarioCode::Structuring, row))
+        .collect())
+}
+
+// Accounts that took in at least `min_amount` since `since` and paid out at
+// least `min_out_percent` of it again. Only transactions from the first
+// deposit in the window on count. Failed transactions and fees don't count.
+#[instrument(name = "dal.aml.detect_rapid_movement", skip_all)]
+pub async fn detect_rapid_movement(
+    client: &impl GenericClient,
+    since: DateTime<Utc>,
+    min_amount: Decimal,
+    min_out_percent: Decimal,
+) -> Result<Vec<AmlDetection>, Error> {
+    let statement = client
+        .prepare(
+            "WITH scanned AS (
+                 SELECT t.id, t.account_id, t.amount, t.type, t.created_at, a.user_id, a.currency
+                 FROM transactions t
+                 JOIN accounts a ON a.id = t.account_id
+                 WHERE t.status <> 'FAILED' AND NOT ($4 = ANY(t.tags)) AND t.created_at >= $1
+             ),
+             movement AS (
+                 SELECT s.user_id, s.account_id, s.currency,
+                        COALESCE(SUM(s.amount) FILTER (WHERE s.type = 'DEPOSIT'), 0) AS deposited,
+                        COALESCE(SUM(s.amount) FILTER (WHERE s.type = 'WITHDRAWAL'), 0) AS withdrawn,
+                        SUM(s.amount) AS total_amount,
+                        ARRAY_AGG(s.id ORDER BY s.created_at, s.id) AS transaction_ids
+                 FROM scanned s
+                 JOIN (
+                     SELECT account_id, MIN(created_at) AS first_deposit_at
+                     FROM scanned WHERE type = 'DEPOSIT'
+                     GROUP BY account_id
+                 ) first_in ON first_in.account_id = s.account_id AND s.created_at >= first_in.first_deposit_at
+                 GROUP BY s.user_id, s.account_id, s.currency
+             )
+             SELECT user_id, account_id, currency, total_amount, transaction_ids,
+                    jsonb_build_object('deposited', deposited::TEXT, 'withdrawn', withdrawn::TEXT) AS details
+             FROM movement
+             WHERE deposited >= $2 AND withdrawn >= deposited * $3 / 100",
+        )
+        .await?;
+
+    let rows = client
+        .query(&statement, &[&since, &min_amount, &min_out_percent, &FEE_TRANSACTION_TAG])
+        .await?;
+    Ok(rows
+        .into_iter()
+        .map(|row| AmlDetection::from_row(AmlScenarioCode::RapidMovement, row))
+        .collect())
+}
+
+// Transactions of at least `min_amount` since `since` on accounts that had
+// no other activity for `dormant_days` before them, or were opened that long
+// before them. Failed transactions and fees are not activity.
+#[instrument(name = "dal.aml.detect_dormant_reactivation", skip_all)]
+pub async fn detect_dormant_reactivation(
+    client: &impl GenericClient,
+    since: DateTime<Utc>,
+    min_amount: Decimal,
+    dormant_days: i32,
+) -> Result<Vec<AmlDetection>, Error> {
+    let statement = client
+        .prepare(
+            "SELECT a.user_id, a.id AS account_id, a.currency, t.amount AS total_amount,
+                    ARRAY[t.id] AS transaction_ids,
+                    jsonb_build_object(
+                        'last_activity_at', previous.last_activity_at,
+                        'inactive_days', EXTRACT(DAY FROM t.created_at - previous.last_activity_at)::INT
+                    ) AS details
+             FROM transactions t
+             JOIN accounts a ON a.id = t.account_id
+             CROSS JOIN LATERAL (
+                 SELECT COALESCE(MAX(p.created_at), a.created_at) AS last_activity_at
+                 FROM transactions p
+                 WHERE p.account_id = t.account_id AND p.status <> 'FAILED' AND NOT ($4 = ANY(p.tags))
+                   AND p.created_at < t.created_at
+             ) previous
+             WHERE t.status <> 'FAILED' AND NOT ($4 = ANY(t.tags)) AND t.created_at >= $1 AND t.amount >= $2
+               AND previous.last_activity_at <= t.created_at - make_interval(days => $3)
+             ORDER BY t.created_at, t.id",
+        )
+        .await?;
+
+    let rows = client
+        .query(&statement, &[&since, &min_amount, &dormant_days, &FEE_TRANSACTION_TAG])
+        .await?;
+    Ok(rows
+        .into_iter()
+        .map(|row| AmlDetection::from_row(AmlScenarioCode::DormantReactivation, row))
+        .collect())
+}
+
+#[instrument(name = "dal.aml.create_run", skip_all)]
+pub async fn create_run(
+    client: &impl GenericClient,
+    triggered_by: Option<Uuid>,
+    started_at: DateTime<Utc>,
+    detections: i32,
+) -> Result<AmlRun, Error> {
+    let statement = client
+        .prepare(&format!(
+            "INSERT INTO aml_runs (triggered_by, started_at, detections, cases_opened, cases_updated)
+             VALUES ($1, $2, $3, 0, 0)
+             RETURNING {}",
+            RUN_COLUMNS
+        ))
+        .await?;
+
+    let row = client.query_one(&statement, &[&triggered_by, &started_at, &detections]).await?;
+    Ok(row.try_into().unwrap())
+}
+
+#[instrument(name = "dal.aml.finish_run", skip_all, fields(id = %id))]
+pub async fn finish_run(
+    client: &impl GenericClient,
+    id: Uuid,
+    cases_opened: i32,
+    cases_updated: i32,
+) -> Result<AmlRun, Error> {
+    let statement = client
+        .prepare(&format!(
+            "UPDATE aml_runs SET cases_opened = $2, cases_updated = $3, finished_at = clock_timestamp()
+             WHERE id = $1
+             RETURNING {}",
+            RUN_COLUMNS
+        ))
+        .await?;
+
+    let row = client.query_one(&statement, &[&id, &cases_opened, &cases_updated]).await?;
+    Ok(row.try_into().unwrap())
+}
+
+#[instrument(name = "dal.aml.list_runs", skip_all)]
+pub async fn list_runs(client: &Client, page: &PageRequest) -> Result<Vec<AmlRun>, Error> {
+    let mut query = QueryBuilder::new(&format!("SELECT {} FROM aml_runs WHERE TRUE", RUN_COLUMNS));
+    query.paginate(page, "started_at", "id");
+
+    let statement = client.prepare(query.sql()).await?;
+    let rows = client.query(&statement, &query.params()).await?;
+    Ok(rows.into_iter().map(|row| row.try_into().unwrap()).collect())
+}
+
+// The OPEN or INVESTIGATING case of the scenario on the same subject, which
+// new evidence is added to.
+#[instrument(name = "dal.aml.get_open_case_for_update", skip_all, fields(user_id = %detection.user_id))]
+pub async fn get_open_case_for_update(
+    client: &impl GenericClient,
+    detection: &AmlDetection,
+) -> Result<Option<AmlCase>, Error> {
+    let statement = client
+        .prepare(&format!(
+            "SELECT {} FROM aml_cases
+             WHERE scenario = $1 AND user_id = $2 AND account_id IS NOT DISTINCT FROM $3 AND currency = $4
+               AND status IN ('OPEN', 'INVESTIGATING')
+             ORDER BY created_at DESC
+             LIMIT 1
+             FOR UPDATE",
+            CASE_COLUMNS
+        ))
+        .await?;
+
+    Ok(client
+        .query_opt(
+            &statement,
+            &[
+                &detection.scenario.to_string(),
+                &detection.user_id,
+                &detection.account_id,
+                &detection.currency,
+            ],
+        )
+        .await?
+        .map(|row| row.try_into().unwrap()))
+}
+
+// Whether a closed case of the scenario already holds all of the detection's
+// transactions, so it isn't opened again.
+#[instrument(name = "dal.aml.is_already_resolved", skip_all, fields(user_id = %detection.user_id))]
+pub async fn is_already_resolved(client: &impl GenericClient, detection: &AmlDetection) -> Result<bool, Error> {
+    let statement = client
+        .prepare(
+            "SELECT EXISTS (
+                 SELECT 1 FROM aml_cases
+                 WHERE scenario = $1 AND user_id = $2 AND status IN ('DISMISSED', 'REPORTED')
+                   AND transaction_ids @> $3
+             )",
+        )
+        .await?;
+
+    Ok(client
+        .query_one(
+            &statement,
+            &[&detection.scenario.to_string(), &detection.user_id, &detection.transaction_ids],
+        )
+        .await?
+        .get(0))
+}
+
+#[instrument(name = "dal.aml.create_case", skip_all, fields(user_id = %detection.user_id))]
+pub async fn create_case(
+    client: &impl GenericClient,
+    detection: &AmlDetection,
+    reason: &str,
+    run_id: Uuid,
+) -> Result<AmlCase, Error> {
+    let statement = client
+        .prepare(&format!(
+            "INSERT INTO aml_cases
+                 (scenario, user_id, account_id, currency, total_amount, transaction_ids, reason, details, run_id)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+             RETURNING {}",
+            CASE_COLUMNS
+        ))
+        .await?;
+
+    let row = client
+        .query_one(
+            &statement,
+            &[
+                &detection.scenario.to_string(),
+                &detection.user_id,
+                &detection.account_id,
+                &detection.currency,
+                &detection.total_amount,
+                &detection.transaction_ids,
+                &reason,
+                &detection.details,
+                &run_id,
+            ],
+        )
+        .await?;
+
+    Ok(row.try_into().unwrap())
+}
+
+// Adds the detection's transactions to the case and recomputes its total
+// from all of them.
+#[instrument(name = "dal.aml.add_evidence", skip_all, fields(id = %id))]
+pub async fn add_evidence(
+    client: &impl GenericClient,
+    id: Uuid,
+    detection: &AmlDetection,
+    reason: &str,
+    run_id: Uuid,
+) -> Result<AmlCase, Error> {
+    let statement = client
+        .prepare(&format!(
+            "UPDATE aml_cases
+             SET transaction_ids = (
+                     SELECT ARRAY_AGG(t.id ORDER BY t.created_at, t.id) FROM transactions t
+                     WHERE t.id = ANY(aml_cases.transaction_ids || $2::UUID[])
+                 ),
+                 total_amount = (
+                     SELECT SUM(t.amount) FROM transactions t
+                     WHERE t.id = ANY(aml_cases.transaction_ids || $2::UUID[])
+                 ),
+                 reason = $3, details = $4, run_id = $5, updated_at = NOW()
+             WHERE id = $1
+             RETURNING {}",
+            CASE_COLUMNS
+        ))
+        .await?;
+
+    let row = client
+        .query_one(&statement, &[&id, &detection.transaction_ids, &reason, &detection.details, &run_id])
+        .await?;
+    Ok(row.try_into().unwrap())
+}
+
+#[instrument(name = "dal.aml.get_case_by_id", skip_all, fields(id = %id))]
+pub async fn get_case_by_id(client: &impl GenericClient, id: Uuid) -> Result<Option<AmlCase>, Error> {
+    let statement = client
+        .prepare(&format!("SELECT {} FROM aml_cases WHERE id = $1", CASE_COLUMNS))
+        .await?;
+
+    Ok(client
+        .query_opt(&statement, &[&id])
+        .await?
+        .map(|row| row.try_into().unwrap()))
+}
+
+#[instrument(name = "dal.aml.get_case_for_update", skip_all, fields(id = %id))]
+pub async fn get_case_for_update(client: &impl GenericClient, id: Uuid) -> Result<Option<AmlCase>, Error> {
+    let statement = client
+        .prepare(&format!("SELECT {} FROM aml_cases WHERE id = $1 FOR UPDATE", CASE_COLUMNS))
+        .await?;
+
+    Ok(client
+        .query_opt(&statement, &[&id])
+        .await?
+        .map(|row| row.try_into().unwrap()))
+}
+
+// The case's transactions, oldest first.
+#[instrument(name = "dal.aml.list_case_transactions", skip_all, fields(id = %case.id))]
+pub async fn list_case_transactions(client: &impl GenericClient, case: &AmlCase) -> Result<Vec<Transaction>, Error> {
+    let statement = client
+        .prepare(
+            "SELECT id, account_id, amount, type, status, description, metadata, tags, booked, created_at, updated_at
+             FROM transactions
+             WHERE id = ANY($1)
+             ORDER BY created_at, id",
+        )
+        .await?;
+
+    let rows = client.query(&statement, &[&case.transaction_ids]).await?;
+    Ok(rows.into_iter().map(|row| row.try_into().unwrap()).collect())
+}
+
+#[instrument(name = "dal.aml.list_cases", skip_all)]
+pub async fn list_cases(
+    client: &Client,
+    params: &AmlCasePaginationParams,
+    page: &PageRequest,
+) -> Result<Vec<AmlCase>, Error> {
+    let mut query = QueryBuilder::new(&format!("SELECT {} FROM aml_cases WHERE TRUE", CASE_COLUMNS));
+    push_case_filters(&mut query, params);
+    query.paginate(page, "created_at", "id");
+
+    let statement = client.prepare(query.sql()).await?;
+    let rows = client.query(&statement, &query.params()).await?;
+    Ok(rows.into_iter().map(|row| row.try_into().unwrap()).collect())
+}
+
+#[instrument(name = "dal.aml.count_cases", skip_all)]
+pub async fn count_cases(client: &Client, params: &AmlCasePaginationParams) -> Result<i64, Error> {
+    let mut query = QueryBuilder::new("SELECT COUNT(*) FROM aml_cases WHERE TRUE");
+    push_case_filters(&mut query, params);
+
+    let statement = client.prepare(query.sql()).await?;
+    Ok(client.query_one(&statement, &query.params()).await?.get(0))
+}
+
+fn push_case_filters(query: &mut QueryBuilder, params: &AmlCasePaginationParams) {
+    if let Some(status) = params.status {
+        query.push_bind(" AND status = {}", status.to_string());
+    }
+    if let Some(scenario) = params.scenario {
+        query.push_bind(" AND scenario = {}", scenario.to_string());
+    }
+    if let Some(user_id) = params.user_id {
+        query.push_bind(" AND user_id = {}", user_id);
+    }
+    if let Some(account_id) = params.account_id {
+        query.push_bind(" AND account_id = {}", account_id);
+    }
+}
+
+// Taking a case up assigns it to `actor_id`; closing it records who resolved
+// it and why.
+#[instrument(name = "dal.aml.set_case_status", skip_all, fields(id = %id))]
+pub async fn set_case_status(
+    client: &impl GenericClient,
+    id: Uuid,
+    status: AmlCaseStatus,
+    actor_id: Uuid,
+    note: Option<&str>,
+) -> Result<AmlCase, Error> {
+    let statement = client
+        .prepare(&format!(
+            "UPDATE aml_cases
+             SET status = $2,
+                 assigned_to = CASE WHEN $2::VARCHAR = 'INVESTIGATING' THEN $3 ELSE assigned_to END,
+                 resolved_by = CASE WHEN $2::VARCHAR IN ('DISMISSED', 'REPORTED') THEN $3 END,
+                 resolved_at = CASE WHEN $2::VARCHAR IN ('DISMISSED', 'REPORTED') THEN NOW() END,
+                 resolution_note = CASE WHEN $2::VARCHAR IN ('DISMISSED', 'REPORTED') THEN $4 END,
+                 updated_at = NOW()
+             WHERE id = $1
+             RETURNING {}",
+            CASE_COLUMNS
+        ))
+        .await?;
+
+    let row = client
+        .query_one(&statement, &[&id, &status.to_string(), &actor_id, &note])
+        .await?;
+    Ok(row.try_into().unwrap())
+}
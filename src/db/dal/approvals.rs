@@ -0,0 +1,283 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use crate::base::{
+    models::approvals::{
+        ApprovalComment, ApprovalOperation, ApprovalRequest, ApprovalRequestPaginationParams, ApprovalStatus,
+        ApprovalThreshold, NewApprovalRequest,
+    },
+    pagination::PageRequest,
+};
+use crate::db::query::QueryBuilder;
+use deadpool_postgres::{Client, GenericClient};
+use tokio_postgres::Error;
+use tracing::instrument;
+use uuid::Uuid;
+
+const REQUEST_COLUMNS: &str =
+    "id, operation, status, account_id, requested_by, amount, transaction_id, details, decided_by, decided_at,
+     decision_note, expires_at, created_at, updated_at";
+
+const COMMENT_COLUMNS: &str = "id, request_id, author_id, body, created_at";
+
+const THRESHOLD_COLUMNS: &str = "currency, withdrawal_threshold, updated_by, updated_at";
+
+#[instrument(name = "dal.approvals.create_request", skip_all, fields(account_id = %request.account_id))]
+pub async fn create_request(
+    client: &impl GenericClient,
+    request: &NewApprovalRequest,
+    expires_at: DateTime<Utc>,
+) -> Result<ApprovalRequest, Error> {
+    let statement = client
+        .prepare(&format!(
+            "INSERT INTO approval_requests
+                 (operation, account_id, requested_by, amount, details, expires_at)
+             VALUES ($1, $2, $3, $4, $5, $6)
+             RETURNING {}",
+            REQUEST_COLUMNS
+        ))
+        .await?;
+
+    let row = client
+        .query_one(
+            &statement,
+            &[
+                &request.operation.to_string(),
+                &request.account_id,
+                &request.requested_by,
+                &request.amount,
+                &request.details,
+                &expires_at,
+            ],
+        )
+        .await?;
+
+    Ok(row.try_into().unwrap())
+}
+
+#[instrument(name = "dal.approvals.get_request_by_id", skip_all, fields(id = %id))]
+pub async fn get_request_by_id(client: &impl GenericClient, id: Uuid) -> Result<Option<ApprovalRequest>, Error> {
+    let statement = client
+        .prepare(&format!("SELECT {} FROM approval_requests WHERE id = $1", REQUEST_COLUMNS))
+        .await?;
+
+    Ok(client
+        .query_opt(&statement, &[&id])
+        .await?
+        .map(|row| row.try_into().unwrap()))
+}
+
+#[instrument(name = "dal.approvals.get_request_for_update", skip_all, fields(id = %id))]
+pub async fn get_request_for_update(client: &impl GenericClient, id: Uuid) -> Result<Option<ApprovalRequest>, Error> {
+    let statement = client
+        .prepare(&format!("SELECT {} FROM approval_requests WHERE id = $1 FOR UPDATE", REQUEST_COLUMNS))
+        .await?;
+
+    Ok(client
+        .query_opt(&statement, &[&id])
+        .await?
+        .map(|row| row.try_into().unwrap()))
+}
+
+#[instrument(name = "dal.approvals.list_requests", skip_all)]
+pub async fn list_requests(
+    client: &Client,
+    params: &ApprovalRequestPaginationParams,
+    page: &PageRequest,
+) -> Result<Vec<ApprovalRequest>, Error> {
+    let mut query = QueryBuilder::new(&format!("SELECT {} FROM approval_requests WHERE TRUE", REQUEST_COLUMNS));
+    push_request_filters(&mut query, params);
+    query.paginate(page, "created_at", "id");
+
+    let statement = client.prepare(query.sql()).await?;
+    let rows = client.query(&statement, &query.params()).await?;
+    Ok(rows.into_iter().map(|row| row.try_into().unwrap()).collect())
+}
+
+#[instrument(name = "dal.approvals.count_requests", skip_all)]
+pub async fn count_requests(client: &Client, params: &ApprovalRequestPaginationParams) -> Result<i64, Error> {
+    let mut query = QueryBuilder::new("SELECT COUNT(*) FROM approval_requests WHERE TRUE");
+    push_request_filters(&mut query, params);
+
+    let statement = client.prepare(query.sql()).await?;
+    Ok(client.query_one(&statement, &query.params()).await?.get(0))
+}
+
+fn push_request_filters(query: &mut QueryBuilder, params: &ApprovalRequestPaginationParams) {
+    if let Some(status) = params.status {
+        query.push_bind(" AND status = {}", status.to_string());
+    }
+    if let Some(operation) = params.operation {
+        query.push_bind(" AND operation = {}", operation.to_string());
+    }
+    if let Some(account_id) = params.account_id {
+        query.push_bind(" AND account_id = {}", account_id);
+    }
+    if let Some(requested_by) = params.requested_by {
+        query.push_bind(" AND requested_by = {}", requested_by);
+    }
+}
+
+// Decides a PENDING request. `transaction_id` is set when approving booked a
+// transaction; `decided_by` is not set for requests that expired.
+#[instrument(name = "dal.approvals.resolve_request", skip_all, fields(id = %id))]
+pub async fn resolve_request(
+    client: &impl GenericClient,
+    id: Uuid,
+    status: ApprovalStatus,
+    decided_by: Option<Uuid>,
+    note: Option<&str>,
+    transaction_id: Option<Uuid>,
+) -> Result<Option<ApprovalRequest>, Error> {
+    let statement = client
+        .prepare(&format!(
+            "UPDATE approval_requests
+             SET status = $2, decided_by = $3, decided_at = NOW(), decision_note = $4,
+                 transaction_id = COALESCE($5, transaction_id), updated_at = NOW()
+             WHERE id = $1 AND status = 'PENDING'
+             RETURNING {}",
+            REQUEST_COLUMNS
+        ))
+        .await?;
+
+    Ok(client
+        .query_opt(&statement, &[&id, &status.to_string(), &decided_by, &note, &transaction_id])
+        .await?
+        .map(|row| row.try_into().unwrap()))
+}
+
+// Locks a batch of PENDING requests past their expiry. Rows locked by
+// another replica are skipped.
+#[instrument(name = "dal.approvals.claim_expired", skip_all)]
+pub async fn claim_expired(
+    client: &impl GenericClient,
+    now: DateTime<Utc>,
+    limit: i64,
+) -> Result<Vec<ApprovalRequest>, Error> {
+    let statement = client
+        .prepare(&format!(
+            "SELECT {} FROM approval_requests
+             WHERE status = 'PENDING' AND expires_at <= $1
+             ORDER BY expires_at
+             LIMIT $2
+             FOR UPDATE SKIP LOCKED",
+            REQUEST_COLUMNS
+        ))
+        .await?;
+
+    let rows = client.query(&statement, &[&now, &limit]).await?;
+    Ok(rows.into_iter().map(|row| row.try_into().unwrap()).collect())
+}
+
+// Whether the operation already waits for approval on the account.
+#[instrument(name = "dal.approvals.has_pending", skip_all, fields(account_id = %account_id))]
+pub async fn has_pending(
+    client: &impl GenericClient,
+    account_id: Uuid,
+    operation: ApprovalOperation,
+) -> Result<bool, Error> {
+    let statement = client
+        .prepare(
+            "SELECT EXISTS (
+                 SELECT 1 FROM approval_requests WHERE account_id = $1 AND operation = $2 AND status = 'PENDING'
+             )",
+        )
+        .await?;
+
+    Ok(client
+        .query_one(&statement, &[&account_id, &operation.to_string()])
+        .await?
+        .get(0))
+}
+
+#[instrument(name = "dal.approvals.create_comment", skip_all, fields(request_id = %request_id))]
+pub async fn create_comment(
+    client: &impl GenericClient,
+    request_id: Uuid,
+    author_id: Uuid,
+    body: &str,
+) -> Result<ApprovalComment, Error> {
+    let statement = client
+        .prepare(&format!(
+            "INSERT INTO approval_comments (request_id, author_id, body) VALUES ($1, $2, $3) RETURNING {}",
+            COMMENT_COLUMNS
+        ))
+        .await?;
+
+    let row = client.query_one(&statement, &[&request_id, &author_id, &body]).await?;
+    Ok(row.try_into().unwrap())
+}
+
+#[instrument(name = "dal.approvals.list_comments", skip_all, fields(request_id = %request_id))]
+pub async fn list_comments(client: &impl GenericClient, request_id: Uuid) -> Result<Vec<ApprovalComment>, Error> {
+    let statement = client
+        .prepare(&format!(
+            "SELECT {} FROM approval_comments WHERE request_id = $1 ORDER BY created_at, id",
+            COMMENT_COLUMNS
+        ))
+        .await?;
+
+    let rows = client.query(&statement, &[&request_id]).await?;
+    Ok(rows.into_iter().map(|row| row.try_into().unwrap()).collect())
+}
+
+#[instrument(name = "dal.approvals.list_thresholds", skip_all)]
+pub async fn list_thresholds(client: &impl GenericClient) -> Result<Vec<ApprovalThreshold>, Error> {
+    let statement = client
+        .prepare(&format!("SELECT {} FROM approval_thresholds ORDER BY currency", THRESHOLD_COLUMNS))
+        .await?;
+
+    let rows = client.query(&statement, &[]).await?;
+    Ok(rows.into_iter().map(|row| row.try_into().unwrap()).collect())
+}
+
+#[instrument(name = "dal.approvals.get_threshold", skip_all, fields(currency = %currency))]
+pub async fn get_threshold(client: &impl GenericClient, currency: &str) -> Result<Option<ApprovalThreshold>, Error> {
+    let statement = client
+        .prepare(&format!("SELECT {} FROM approval_thresholds WHERE currency = $1", THRESHOLD_COLUMNS))
+        .await?;
+
+    Ok(client
+        .query_opt(&statement, &[&currency])
+        .await?
+        .map(|row| row.try_into().unwrap()))
+}
+
+#[instrument(name = "dal.approvals.set_threshold", skip_all, fields(currency = %currency))]
+pub async fn set_threshold(
+    client: &impl GenericClient,
+    currency: &str,
+    withdrawal_threshold: Decimal,
+    updated_by: Uuid,
+) -> Result<ApprovalThreshold, Error> {
+    let statement = client
+        .prepare(&format!(
+            "INSERT INTO approval_thresholds (currency, withdrawal_threshold, updated_by)
+             VALUES ($1, $2, $3)
+             ON CONFLICT (currency) DO UPDATE
+             SET withdrawal_threshold = EXCLUDED.withdrawal_threshold, updated_by = EXCLUDED.updated_by,
+                 updated_at = NOW()
+             RETURNING {}",
+            THRESHOLD_COLUMNS
+        ))
+        .await?;
+
+    let row = client
+        .query_one(&statement, &[&currency, &withdrawal_threshold, &updated_by])
+        .await?;
+    Ok(row.try_into().unwrap())
+}
+
+#[instrument(name = "dal.approvals.delete_threshold", skip_all, fields(currency = %currency))]
+pub async fn delete_threshold(client: &impl GenericClient, currency: &str) -> Result<Option<ApprovalThreshold>, Error> {
+    let statement = client
+        .prepare(&format!(
+            "DELETE FROM approval_thresholds WHERE currency = $1 RETURNING {}",
+            THRESHOLD_COLUMNS
+        ))
+        .await?;
+
+    Ok(client
+        .query_opt(&statement, &[&currency])
+        .await?
+        .map(|row| row.try_into().unwrap()))
+}
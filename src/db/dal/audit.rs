@@ -0,0 +1,121 @@
+use crate::base::{
+    models::audit::{AuditChainLink, AuditEntry, AuditLogFilter, NewAuditEntry},
+    pagination::PageRequest,
+    telemetry,
+};
+use crate::db::query::QueryBuilder;
+use deadpool_postgres::GenericClient;
+use tokio_postgres::Error;
+use tracing::instrument;
+
+const ENTRY_COLUMNS: &str =
+    "id, seq, actor_id, action, target_type, target_id, before, after, details,
+     request_id, ip_address, prev_hash, hash, created_at";
+
+// The request id and client address are taken from the request being served,
+// if any. `seq`, `prev_hash` and `hash` are filled in by the insert trigger.
+#[instrument(name = "dal.audit.create_entry", skip_all, fields(action = entry.action))]
+pub async fn create_entry(client: &impl GenericClient, entry: &NewAuditEntry) -> Result<(), Error> {
+    let context = telemetry::current_request_context();
+    let request_id = context.as_ref().map(|context| context.request_id.clone());
+    let ip_address = context.and_then(|context| context.ip_address);
+
+    let statement = client
+        .prepare(
+            "INSERT INTO audit_log
+                 (actor_id, action, target_type, target_id, before, after, details, request_id, ip_address)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)",
+        )
+        .await?;
+
+    client
+        .execute(
+            &statement,
+            &[
+                &entry.actor_id,
+                &entry.action,
+                &entry.target_type,
+                &entry.target_id,
+                &entry.before,
+                &entry.after,
+                &entry.details,
+                &request_id,
+                &ip_address,
+            ],
+        )
+        .await?;
+    Ok(())
+}
+
+#[instrument(name = "dal.audit.list_entries", skip_all)]
+pub async fn list_entries(
+    client: &impl GenericClient,
+    filter: &AuditLogFilter,
+    page: &PageRequest,
+) -> Result<Vec<AuditEntry>, Error> {
+    let mut query = QueryBuilder::new(&format!("SELECT {} FROM audit_log WHERE TRUE", ENTRY_COLUMNS));
+    push_filters(&mut query, filter);
+    query.paginate(page, "created_at", "id");
+
+    let statement = client.prepare(query.sql()).await?;
+    let rows = client.query(&statement, &query.params()).await?;
+    Ok(rows.into_iter().map(|row| row.try_into().unwrap()).collect())
+}
+
+#[instrument(name = "dal.audit.count_entries", skip_all)]
+pub async fn count_entries(client: &impl GenericClient, filter: &AuditLogFilter) -> Result<i64, Error> {
+    let mut query = QueryBuilder::new("SELECT COUNT(*) FROM audit_log WHERE TRUE");
+    push_filters(&mut query, filter);
+
+    let statement = client.prepare(query.sql()).await?;
+    Ok(client.query_one(&statement, &query.params()).await?.get(0))
+}
+
+// Entries after `after_seq` in chain order, with JSON values as stored text.
+#[instrument(name = "dal.audit.list_chain", skip_all)]
+pub async fn list_chain(client: &impl GenericClient, after_seq: i64, limit: i64) -> Result<Vec<AuditChainLink>, Error> {
+    let statement = client
+        .prepare(
+            "SELECT seq, prev_hash, id, created_at, actor_id, action, target_type, target_id,
+                    before::TEXT AS before, after::TEXT AS after, details::TEXT AS details,
+                    request_id, ip_address, hash
+             FROM audit_log
+             WHERE seq > $1
+             ORDER BY seq
+             LIMIT $2",
+        )
+        .await?;
+
+    let rows = client.query(&statement, &[&after_seq, &limit]).await?;
+    Ok(rows.into_iter().map(|row| row.try_into().unwrap()).collect())
+}
+
+fn push_filters(query: &mut QueryBuilder, filter: &AuditLogFilter) {
+    if let Some(actor_id) = filter.actor_id {
+        query.push_bind(" AND actor_id = {}", actor_id);
+    }
+
+    if let Some(action) = &filter.action {
+        query.push_bind(" AND action = {}", action.clone());
+    }
+
+    if let Some(target_type) = &filter.target_type {
+        query.push_bind(" AND target_type = {}", target_type.clone());
+    }
+
+    if let Some(target_id) = filter.target_id {
+        query.push_bind(" AND target_id = {}", target_id);
+    }
+
+    if let Some(request_id) = &filter.request_id {
+        query.push_bind(" AND request_id = {}", request_id.clone());
+    }
+
+    if let Some(from) = filter.from {
+        query.push_bind(" AND created_at >= {}", from);
+    }
+
+    if let Some(to) = filter.to {
+        query.push_bind(" AND created_at < {}", to);
+    }
+}
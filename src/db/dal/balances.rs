@@ -0,0 +1,102 @@
+use crate::base::models::balances::AccountBalance;
+use chrono::{DateTime, NaiveDate, Utc};
+use deadpool_postgres::{Client, GenericClient};
+use tokio_postgres::Error;
+use tracing::instrument;
+use uuid::Uuid;
+
+// Balance of every account in `accounts a` at the instant bound to `at`: the
+// latest snapshot taken by then plus the transactions created since, up to but
+// excluding `at`. Deposits and withdrawals move the balance when they are
+// made, so only failed transactions and those recorded without moving it
+// (`booked` unset) are left out. Before the first snapshot
+// the starting point is the opening balance, which is the current balance
+// minus every transaction that moved it.
+fn balance_query(at: &str) -> String {
+    format!(
+        "SELECT a.id AS account_id, a.currency, {at}::TIMESTAMPTZ AS at, s.snapshot_date,
+             CASE WHEN {at} < a.created_at THEN 0.00
+             ELSE COALESCE(
+                      s.balance,
+                      a.balance - (SELECT COALESCE(SUM(CASE WHEN t.type = 'DEPOSIT' THEN t.amount ELSE -t.amount END), 0)
+                                   FROM transactions t
+                                   WHERE t.account_id = a.id AND t.booked AND t.status <> 'FAILED'))
+                  + (SELECT COALESCE(SUM(CASE WHEN t.type = 'DEPOSIT' THEN t.amount ELSE -t.amount END), 0)
+                     FROM transactions t
+                     WHERE t.account_id = a.id
+                       AND t.booked
+                       AND t.status <> 'FAILED'
+                       AND t.created_at >= COALESCE(s.as_of, '-infinity')
+                       AND t.created_at < {at})
+             END AS balance
+         FROM accounts a
+         LEFT JOIN LATERAL (
+             SELECT snapshot_date, as_of, balance
+             FROM balance_snapshots
+             WHERE account_id = a.id AND as_of <= {at}
+             ORDER BY as_of DESC
+             LIMIT 1
+         ) s ON TRUE",
+        at = at
+    )
+}
+
+#[instrument(name = "dal.balances.get_balance_at", skip_all, fields(id = %id))]
+pub async fn get_balance_at(client: &Client, id: Uuid, at: DateTime<Utc>) -> Result<Option<AccountBalance>, Error> {
+    let statement = client
+        .prepare(&format!("{} WHERE a.id = $1", balance_query("$2")))
+        .await?;
+
+    Ok(client
+        .query_opt(&statement, &[&id, &at])
+        .await?
+        .map(|row| row.try_into().unwrap()))
+}
+
+// Earliest UTC day some account has no snapshot for, if there are accounts.
+#[instrument(name = "dal.balances.first_missing_snapshot_date", skip_all)]
+pub async fn first_missing_snapshot_date(client: &Client) -> Result<Option<NaiveDate>, Error> {
+    let statement = client
+        .prepare(
+            "SELECT MIN(COALESCE(s.last_date + 1, (a.created_at AT TIME ZONE 'UTC')::DATE))
+             FROM accounts a
+             LEFT JOIN (
+                 SELECT account_id, MAX(snapshot_date) AS last_date
+                 FROM balance_snapshots
+                 GROUP BY account_id
+             ) s ON s.account_id = a.id",
+        )
+        .await?;
+
+    Ok(client.query_one(&statement, &[]).await?.get(0))
+}
+
+// Snapshots the balance at the end of `day` for every account that existed
+// then and has none for that day yet. Days must be taken in order, as each
+// snapshot builds on the previous one.
+#[instrument(name = "dal.balances.create_snapshots", skip_all, fields(day = %day))]
+pub async fn create_snapshots(client: &Client, day: NaiveDate, as_of: DateTime<Utc>) -> Result<u64, Error> {
+    let statement = client
+        .prepare(&format!(
+            "INSERT INTO balance_snapshots (account_id, snapshot_date, as_of, balance)
+             SELECT b.account_id, $2, b.at, b.balance
+             FROM ({} WHERE a.created_at < $1
+                     AND NOT EXISTS (SELECT 1 FROM balance_snapshots e WHERE e.account_id = a.id AND e.snapshot_date = $2)) b
+             ON CONFLICT (account_id, snapshot_date) DO NOTHING",
+            balance_query("$1")
+        ))
+        .await?;
+
+    client.execute(&statement, &[&as_of, &day]).await
+}
+
+// Drops the snapshots made stale by a transaction created at `since` failing
+// or being revived; the snapshot job takes them again.
+#[instrument(name = "dal.balances.invalidate_snapshots", skip_all, fields(account_id = %account_id))]
+pub async fn invalidate_snapshots(client: &impl GenericClient, account_id: Uuid, since: DateTime<Utc>) -> Result<u64, Error> {
+    let statement = client
+        .prepare("DELETE FROM balance_snapshots WHERE account_id = $1 AND as_of > $2")
+        .await?;
+
+    client.execute(&statement, &[&account_id, &since]).await
+}
@@ -0,0 +1,70 @@
+use crate::base::models::consents::{Consent, RecordConsentRequest};
+use deadpool_postgres::{Client, GenericClient};
+use tokio_postgres::Error;
+use tracing::instrument;
+use uuid::Uuid;
+
+const CONSENT_COLUMNS: &str = "id, user_id, purpose, granted, ip_address, created_at";
+
+#[instrument(name = "dal.consents.create_consent", skip_all, fields(user_id = %user_id))]
+pub async fn create_consent(
+    client: &impl GenericClient,
+    user_id: Uuid,
+    consent: &RecordConsentRequest,
+    ip_address: Option<&str>,
+) -> Result<Consent, Error> {
+    let statement = client
+        .prepare(&format!(
+            "INSERT INTO consents (user_id, purpose, granted, ip_address)
+             VALUES ($1, $2, $3, $4)
+             RETURNING {}",
+            CONSENT_COLUMNS
+        ))
+        .await?;
+
+    client
+        .query_one(&statement, &[&user_id, &consent.purpose.to_string(), &consent.granted, &ip_address])
+        .await?
+        .try_into()
+}
+
+// The decision in effect for every purpose the user has decided on.
+#[instrument(name = "dal.consents.current_consents", skip_all, fields(user_id = %user_id))]
+pub async fn current_consents(client: &Client, user_id: Uuid) -> Result<Vec<Consent>, Error> {
+    let statement = client
+        .prepare(&format!(
+            "SELECT DISTINCT ON (purpose) {}
+             FROM consents
+             WHERE user_id = $1
+             ORDER BY purpose, created_at DESC, id",
+            CONSENT_COLUMNS
+        ))
+        .await?;
+
+    let rows = client.query(&statement, &[&user_id]).await?;
+    Ok(rows.into_iter().map(|row| row.try_into().unwrap()).collect())
+}
+
+// Every decision the user has made, oldest first.
+#[instrument(name = "dal.consents.list_consents", skip_all, fields(user_id = %user_id))]
+pub async fn list_consents(client: &impl GenericClient, user_id: Uuid) -> Result<Vec<Consent>, Error> {
+    let statement = client
+        .prepare(&format!(
+            "SELECT {} FROM consents WHERE user_id = $1 ORDER BY created_at, id",
+            CONSENT_COLUMNS
+        ))
+        .await?;
+
+    let rows = client.query(&statement, &[&user_id]).await?;
+    Ok(rows.into_iter().map(|row| row.try_into().unwrap()).collect())
+}
+
+// Consent decisions are kept as evidence after erasure, without the address
+// they were made from.
+#[instrument(name = "dal.consents.clear_ip_addresses", skip_all, fields(user_id = %user_id))]
+pub async fn clear_ip_addresses(client: &impl GenericClient, user_id: Uuid) -> Result<u64, Error> {
+    let statement = client
+        .prepare("UPDATE consents SET ip_address = NULL WHERE user_id = $1")
+        .await?;
+    client.execute(&statement, &[&user_id]).await
+}
@@ -0,0 +1,101 @@
+use crate::base::models::events::AccountEvent;
+use chrono::{DateTime, Utc};
+use deadpool_postgres::{Client, GenericClient};
+use serde_json::Value;
+use tokio_postgres::Error;
+use tracing::instrument;
+use uuid::Uuid;
+
+const EVENT_COLUMNS: &str = "id, account_id, event_type, payload, created_at";
+
+#[instrument(name = "dal.events.create_event", skip_all, fields(account_id = %account_id, event_type = event_type))]
+pub async fn create_event(
+    client: &impl GenericClient,
+    account_id: Uuid,
+    event_type: &str,
+    payload: &Value,
+) -> Result<AccountEvent, Error> {
+    let statement = client
+        .prepare(&format!(
+            "INSERT INTO account_events (account_id, event_type, payload)
+             VALUES ($1, $2, $3)
+             RETURNING {}",
+            EVENT_COLUMNS
+        ))
+        .await?;
+
+    let row = client.query_one(&statement, &[&account_id, &event_type, &payload]).await?;
+
+    Ok(row.try_into().unwrap())
+}
+
+#[instrument(name = "dal.events.get_event_by_id", skip_all, fields(event_id = id))]
+pub async fn get_event_by_id(client: &Client, id: i64) -> Result<Option<AccountEvent>, Error> {
+    let statement = client
+        .prepare(&format!("SELECT {} FROM account_events WHERE id = $1", EVENT_COLUMNS))
+        .await?;
+
+    let row = client.query_opt(&statement, &[&id]).await?;
+
+    Ok(row.map(|row| row.try_into().unwrap()))
+}
+
+// Events of every account after `after_id`, oldest first. Used to catch up on
+// notifications missed while the listener was disconnected.
+#[instrument(name = "dal.events.list_events_after", skip_all, fields(after_id = after_id))]
+pub async fn list_events_after(client: &Client, after_id: i64, limit: i64) -> Result<Vec<AccountEvent>, Error> {
+    let statement = client
+        .prepare(&format!(
+            "SELECT {} FROM account_events WHERE id > $1 ORDER BY id LIMIT $2",
+            EVENT_COLUMNS
+        ))
+        .await?;
+
+    let rows = client.query(&statement, &[&after_id, &limit]).await?;
+
+    Ok(rows.into_iter().map(|row| row.try_into().unwrap()).collect())
+}
+
+#[instrument(name = "dal.events.list_account_events_after", skip_all, fields(account_id = %account_id, after_id = after_id))]
+pub async fn list_account_events_after(
+    client: &Client,
+    account_id: Uuid,
+    after_id: i64,
+    limit: i64,
+) -> Result<Vec<AccountEvent>, Error> {
+    let statement = client
+        .prepare(&format!(
+            "SELECT {} FROM account_events WHERE account_id = $1 AND id > $2 ORDER BY id LIMIT $3",
+            EVENT_COLUMNS
+        ))
+        .await?;
+
+    let rows = client.query(&statement, &[&account_id, &after_id, &limit]).await?;
+
+    Ok(rows.into_iter().map(|row| row.try_into().unwrap()).collect())
+}
+
+// Id of the newest event, or 0 when there is none. With `account_id`, only
+// events of that account are considered.
+#[instrument(name = "dal.events.latest_event_id", skip_all)]
+pub async fn latest_event_id(client: &Client, account_id: Option<Uuid>) -> Result<i64, Error> {
+    let statement = client
+        .prepare(
+            "SELECT COALESCE(MAX(id), 0) FROM account_events
+             WHERE $1::UUID IS NULL OR account_id = $1::UUID",
+        )
+        .await?;
+
+    let row = client.query_one(&statement, &[&account_id]).await?;
+
+    Ok(row.get(0))
+}
+
+#[instrument(name = "dal.events.delete_events_before", skip_all)]
+pub async fn delete_events_before(client: &Client, before: DateTime<Utc>) -> Result<u64, Error> {
+    let statement = client
+        .prepare("DELETE FROM account_events WHERE created_at < $1")
+        .await?;
+
+    client.execute(&statement, &[&before]).await
+}
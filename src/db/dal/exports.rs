@@ -0,0 +1,167 @@
+use crate::base::models::exports::DataExport;
+use chrono::{DateTime, Utc};
+use deadpool_postgres::{Client, GenericClient};
+use tokio_postgres::Error;
+use tracing::instrument;
+use uuid::Uuid;
+
+const EXPORT_COLUMNS: &str =
+    "id, user_id, status, size_bytes, error, created_at, completed_at, expires_at";
+
+#[instrument(name = "dal.exports.create_export", skip_all, fields(user_id = %user_id))]
+pub async fn create_export(client: &impl GenericClient, user_id: Uuid) -> Result<DataExport, Error> {
+    let statement = client
+        .prepare(&format!(
+            "INSERT INTO data_exports (user_id) VALUES ($1) RETURNING {}",
+            EXPORT_COLUMNS
+        ))
+        .await?;
+
+    client.query_one(&statement, &[&user_id]).await?.try_into()
+}
+
+// The export of the user that is queued or being built, if any.
+#[instrument(name = "dal.exports.get_active_export", skip_all, fields(user_id = %user_id))]
+pub async fn get_active_export(client: &Client, user_id: Uuid) -> Result<Option<DataExport>, Error> {
+    let statement = client
+        .prepare(&format!(
+            "SELECT {} FROM data_exports
+             WHERE user_id = $1 AND status IN ('PENDING', 'RUNNING')",
+            EXPORT_COLUMNS
+        ))
+        .await?;
+
+    Ok(client
+        .query_opt(&statement, &[&user_id])
+        .await?
+        .map(|row| row.try_into().unwrap()))
+}
+
+#[instrument(name = "dal.exports.get_export_by_id", skip_all, fields(id = %id))]
+pub async fn get_export_by_id(client: &Client, id: Uuid) -> Result<Option<DataExport>, Error> {
+    let statement = client
+        .prepare(&format!("SELECT {} FROM data_exports WHERE id = $1", EXPORT_COLUMNS))
+        .await?;
+
+    Ok(client
+        .query_opt(&statement, &[&id])
+        .await?
+        .map(|row| row.try_into().unwrap()))
+}
+
+#[instrument(name = "dal.exports.list_exports_by_user_id", skip_all, fields(user_id = %user_id))]
+pub async fn list_exports_by_user_id(client: &Client, user_id: Uuid, limit: i64) -> Result<Vec<DataExport>, Error> {
+    let statement = client
+        .prepare(&format!(
+            "SELECT {} FROM data_exports
+             WHERE user_id = $1
+             ORDER BY created_at DESC, id
+             LIMIT $2",
+            EXPORT_COLUMNS
+        ))
+        .await?;
+
+    let rows = client.query(&statement, &[&user_id, &limit]).await?;
+    Ok(rows.into_iter().map(|row| row.try_into().unwrap()).collect())
+}
+
+// Claims the oldest queued export. An export left RUNNING for longer than
+// `stale_after` belongs to a worker that died and is claimed again.
+#[instrument(name = "dal.exports.claim_next_export", skip_all)]
+pub async fn claim_next_export(client: &Client, stale_after: DateTime<Utc>) -> Result<Option<DataExport>, Error> {
+    let statement = client
+        .prepare(&format!(
+            "UPDATE data_exports
+             SET status = 'RUNNING', started_at = NOW()
+             WHERE id = (
+                 SELECT id FROM data_exports
+                 WHERE status = 'PENDING' OR (status = 'RUNNING' AND started_at < $1)
+                 ORDER BY created_at
+                 LIMIT 1
+                 FOR UPDATE SKIP LOCKED
+             )
+             RETURNING {}",
+            EXPORT_COLUMNS
+        ))
+        .await?;
+
+    Ok(client
+        .query_opt(&statement, &[&stale_after])
+        .await?
+        .map(|row| row.try_into().unwrap()))
+}
+
+#[instrument(name = "dal.exports.complete_export", skip_all, fields(id = %id))]
+pub async fn complete_export(
+    client: &Client,
+    id: Uuid,
+    archive: &[u8],
+    expires_at: DateTime<Utc>,
+) -> Result<Option<DataExport>, Error> {
+    let statement = client
+        .prepare(&format!(
+            "UPDATE data_exports
+             SET status = 'READY',
+                 archive = $1,
+                 size_bytes = $2,
+                 completed_at = NOW(),
+                 expires_at = $3
+             WHERE id = $4 AND status = 'RUNNING'
+             RETURNING {}",
+            EXPORT_COLUMNS
+        ))
+        .await?;
+
+    Ok(client
+        .query_opt(&statement, &[&archive, &(archive.len() as i64), &expires_at, &id])
+        .await?
+        .map(|row| row.try_into().unwrap()))
+}
+
+#[instrument(name = "dal.exports.fail_export", skip_all, fields(id = %id))]
+pub async fn fail_export(client: &Client, id: Uuid, error: &str) -> Result<(), Error> {
+    let statement = client
+        .prepare(
+            "UPDATE data_exports
+             SET status = 'FAILED', error = $1, completed_at = NOW()
+             WHERE id = $2 AND status = 'RUNNING'",
+        )
+        .await?;
+
+    client.execute(&statement, &[&error, &id]).await?;
+    Ok(())
+}
+
+// The archive of a READY export that hasn't expired yet.
+#[instrument(name = "dal.exports.get_archive", skip_all, fields(id = %id))]
+pub async fn get_archive(client: &Client, id: Uuid) -> Result<Option<Vec<u8>>, Error> {
+    let statement = client
+        .prepare(
+            "SELECT archive FROM data_exports
+             WHERE id = $1 AND status = 'READY' AND expires_at > NOW()",
+        )
+        .await?;
+
+    Ok(client.query_opt(&statement, &[&id]).await?.map(|row| row.get("archive")))
+}
+
+// Deletes the archives of exports whose download link has expired.
+#[instrument(name = "dal.exports.expire_exports", skip_all)]
+pub async fn expire_exports(client: &Client) -> Result<u64, Error> {
+    let statement = client
+        .prepare(
+            "UPDATE data_exports
+             SET status = 'EXPIRED', archive = NULL
+             WHERE status = 'READY' AND expires_at <= NOW()",
+        )
+        .await?;
+    client.execute(&statement, &[]).await
+}
+
+#[instrument(name = "dal.exports.delete_exports_by_user_id", skip_all, fields(user_id = %user_id))]
+pub async fn delete_exports_by_user_id(client: &impl GenericClient, user_id: Uuid) -> Result<u64, Error> {
+    let statement = client
+        .prepare("DELETE FROM data_exports WHERE user_id = $1")
+        .await?;
+    client.execute(&statement, &[&user_id]).await
+}
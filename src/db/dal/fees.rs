@@ -0,0 +1,162 @@
+use crate::base::{
+    models::fees::{CreateFeeScheduleRequest, FeeSchedule, FeeSchedulePaginationParams, FeeTransactionType},
+    pagination::PageRequest,
+};
+use crate::db::query::QueryBuilder;
+use deadpool_postgres::{Client, GenericClient};
+use tokio_postgres::Error;
+use tracing::instrument;
+use uuid::Uuid;
+
+const SCHEDULE_COLUMNS: &str =
+    "id, name, transaction_type, currency, flat_amount, percentage, tiers, min_fee, max_fee, revenue_account_id,
+     active, created_by, created_at, deactivated_at";
+
+#[instrument(name = "dal.fees.create_schedule", skip_all)]
+pub async fn create_schedule(
+    client: &impl GenericClient,
+    created_by: Uuid,
+    schedule: &CreateFeeScheduleRequest,
+) -> Result<FeeSchedule, Error> {
+    let statement = client
+        .prepare(&format!(
+            "INSERT INTO fee_schedules
+                 (name, transaction_type, currency, flat_amount, percentage, tiers, min_fee, max_fee,
+                  revenue_account_id, created_by)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+             RETURNING {}",
+            SCHEDULE_COLUMNS
+        ))
+        .await?;
+
+    let tiers = serde_json::to_value(&schedule.tiers).unwrap();
+    let row = client
+        .query_one(
+            &statement,
+            &[
+                &schedule.name,
+                &schedule.transaction_type.to_string(),
+                &schedule.currency,
+                &schedule.flat_amount,
+                &schedule.percentage,
+                &tiers,
+                &schedule.min_fee,
+                &schedule.max_fee,
+                &schedule.revenue_account_id,
+                &created_by,
+            ],
+        )
+        .await?;
+
+    Ok(row.try_into().unwrap())
+}
+
+#[instrument(name = "dal.fees.get_schedule_by_id", skip_all, fields(id = %id))]
+pub async fn get_schedule_by_id(client: &Client, id: Uuid) -> Result<Option<FeeSchedule>, Error> {
+    let statement = client
+        .prepare(&format!("SELECT {} FROM fee_schedules WHERE id = $1", SCHEDULE_COLUMNS))
+        .await?;
+
+    Ok(client
+        .query_opt(&statement, &[&id])
+        .await?
+        .map(|row| row.try_into().unwrap()))
+}
+
+#[instrument(name = "dal.fees.get_active_schedule", skip_all, fields(currency = %currency, transaction_type = %transaction_type))]
+pub async fn get_active_schedule(
+    client: &impl GenericClient,
+    currency: &str,
+    transaction_type: FeeTransactionType,
+) -> Result<Option<FeeSchedule>, Error> {
+    let statement = client
+        .prepare(&format!(
+            "SELECT {} FROM fee_schedules WHERE currency = $1 AND transaction_type = $2 AND active",
+            SCHEDULE_COLUMNS
+        ))
+        .await?;
+
+    Ok(client
+        .query_opt(&statement, &[&currency, &transaction_type.to_string()])
+        .await?
+        .map(|row| row.try_into().unwrap()))
+}
+
+#[instrument(name = "dal.fees.list_schedules", skip_all)]
+pub async fn list_schedules(
+    client: &Client,
+    params: &FeeSchedulePaginationParams,
+    page: &PageRequest,
+) -> Result<Vec<FeeSchedule>, Error> {
+    let mut query = QueryBuilder::new(&format!("SELECT {} FROM fee_schedules WHERE TRUE", SCHEDULE_COLUMNS));
+    push_schedule_filters(&mut query, params);
+    query.paginate(page, "created_at", "id");
+
+    let statement = client.prepare(query.sql()).await?;
+    let rows = client.query(&statement, &query.params()).await?;
+    Ok(rows.into_iter().map(|row| row.try_into().unwrap()).collect())
+}
+
+#[instrument(name = "dal.fees.count_schedules", skip_all)]
+pub async fn count_schedules(client: &Client, params: &FeeSchedulePaginationParams) -> Result<i64, Error> {
+    let mut query = QueryBuilder::new("SELECT COUNT(*) FROM fee_schedules WHERE TRUE");
+    push_schedule_filters(&mut query, params);
+
+    let statement = client.prepare(query.sql()).await?;
+    Ok(client.query_one(&statement, &query.params()).await?.get(0))
+}
+
+fn push_schedule_filters(query: &mut QueryBuilder, params: &FeeSchedulePaginationParams) {
+    if let Some(currency) = &params.currency {
+        query.push_bind(" AND currency = {}", currency.clone());
+    }
+    if let Some(transaction_type) = params.transaction_type {
+        query.push_bind(" AND transaction_type = {}", transaction_type.to_string());
+    }
+    if let Some(active) = params.active {
+        query.push_bind(" AND active = {}", active);
+    }
+}
+
+// Deactivates the schedule with `id`, if it is still active.
+#[instrument(name = "dal.fees.deactivate_schedule", skip_all, fields(id = %id))]
+pub async fn deactivate_schedule(client: &impl GenericClient, id: Uuid) -> Result<Option<FeeSchedule>, Error> {
+    let statement = client
+        .prepare(&format!(
+            "UPDATE fee_schedules
+             SET active = FALSE, deactivated_at = NOW()
+             WHERE id = $1 AND active
+             RETURNING {}",
+            SCHEDULE_COLUMNS
+        ))
+        .await?;
+
+    Ok(client
+        .query_opt(&statement, &[&id])
+        .await?
+        .map(|row| row.try_into().unwrap()))
+}
+
+// Deactivates the schedule in force for `currency` and `transaction_type`, so
+// a new one can take its place.
+#[instrument(name = "dal.fees.deactivate_active_schedule", skip_all, fields(currency = %currency, transaction_type = %transaction_type))]
+pub async fn deactivate_active_schedule(
+    client: &impl GenericClient,
+    currency: &str,
+    transaction_type: FeeTransactionType,
+) -> Result<Option<FeeSchedule>, Error> {
+    let statement = client
+        .prepare(&format!(
+            "UPDATE fee_schedules
+             SET active = FALSE, deactivated_at = NOW()
+             WHERE currency = $1 AND transaction_type = $2 AND active
+             RETURNING {}",
+            SCHEDULE_COLUMNS
+        ))
+        .await?;
+
+    Ok(client
+        .query_opt(&statement, &[&currency, &transaction_type.to_string()])
+        .await?
+        .map(|row| row.try_into().unwrap()))
+}
@@ -0,0 +1,400 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use crate::base::{
+    constants::FEE_TRANSACTION_TAG,
+    models::kyc::{
+        Caps, KycApplication, KycApplicationPaginationParams, KycApplicationStatus, KycDocument, KycDocumentStatus,
+        KycTier, KycTierCaps, SubmitKycDocumentRequest,
+    },
+    pagination::PageRequest,
+};
+use crate::db::query::QueryBuilder;
+use deadpool_postgres::{Client, GenericClient};
+use tokio_postgres::Error;
+use tracing::instrument;
+use uuid::Uuid;
+
+const DOCUMENT_COLUMNS: &str =
+    "id, user_id, document_type, issuing_country, document_number_last4, expires_on, file_name, content_type,
+     size_bytes, sha256, status, created_at";
+
+const APPLICATION_COLUMNS: &str =
+    "id, user_id, requested_tier, document_ids, status, reviewed_by, reviewed_at, review_note, created_at";
+
+const CAPS_COLUMNS: &str =
+    "tier, currency, max_accounts, max_balance, max_single_transaction, max_monthly_volume, updated_by, updated_at";
+
+#[instrument(name = "dal.kyc.create_document", skip_all, fields(user_id = %user_id))]
+pub async fn create_document(
+    client: &impl GenericClient,
+    user_id: Uuid,
+    document: &SubmitKycDocumentRequest,
+    document_number_last4: Option<&str>,
+) -> Result<KycDocument, Error> {
+    let statement = client
+        .prepare(&format!(
+            "INSERT INTO kyc_documents
+                 (user_id, document_type, issuing_country, document_number_last4, expires_on, file_name, content_type,
+                  size_bytes, sha256)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+             RETURNING {}",
+            DOCUMENT_COLUMNS
+        ))
+        .await?;
+
+    let row = client
+        .query_one(
+            &statement,
+            &[
+                &user_id,
+                &document.document_type.to_string(),
+                &document.issuing_country,
+                &document_number_last4,
+                &document.expires_on,
+                &document.file_name,
+                &document.content_type,
+                &document.size_bytes,
+                &document.sha256.to_lowercase(),
+            ],
+        )
+        .await?;
+
+    Ok(row.try_into().unwrap())
+}
+
+#[instrument(name = "dal.kyc.list_documents", skip_all, fields(user_id = %user_id))]
+pub async fn list_documents(client: &impl GenericClient, user_id: Uuid) -> Result<Vec<KycDocument>, Error> {
+    let statement = client
+        .prepare(&format!(
+            "SELECT {} FROM kyc_documents WHERE user_id = $1 ORDER BY created_at DESC, id DESC",
+            DOCUMENT_COLUMNS
+        ))
+        .await?;
+
+    let rows = client.query(&statement, &[&user_id]).await?;
+    Ok(rows.into_iter().map(|row| row.try_into().unwrap()).collect())
+}
+
+#[instrument(name = "dal.kyc.count_documents", skip_all, fields(user_id = %user_id))]
+pub async fn count_documents(client: &impl GenericClient, user_id: Uuid) -> Result<i64, Error> {
+    let statement = client
+        .prepare("SELECT COUNT(*) FROM kyc_documents WHERE user_id = $1")
+        .await?;
+
+    Ok(client.query_one(&statement, &[&user_id]).await?.get(0))
+}
+
+// Sets the status of the documents an application was made with.
+#[instrument(name = "dal.kyc.set_documents_status", skip_all, fields(status = %status))]
+pub async fn set_documents_status(
+    client: &impl GenericClient,
+    ids: &[Uuid],
+    status: KycDocumentStatus,
+) -> Result<u64, Error> {
+    let statement = client
+        .prepare("UPDATE kyc_documents SET status = $2 WHERE id = ANY($1)")
+        .await?;
+
+    client.execute(&statement, &[&ids, &status.to_string()]).await
+}
+
+// Deletes the user's documents and applications, e.g. when the user is erased.
+#[instrument(name = "dal.kyc.delete_by_user_id", skip_all, fields(user_id = %user_id))]
+pub async fn delete_by_user_id(client: &impl GenericClient, user_id: Uuid) -> Result<u64, Error> {
+    let applications = client
+        .execute("DELETE FROM kyc_applications WHERE user_id = $1", &[&user_id])
+        .await?;
+    let documents = client
+        .execute("DELETE FROM kyc_documents WHERE user_id = $1", &[&user_id])
+        .await?;
+
+    Ok(applications + documents)
+}
+
+#[instrument(name = "dal.kyc.create_application", skip_all, fields(user_id = %user_id, requested_tier = %requested_tier))]
+pub async fn create_application(
+    client: &impl GenericClient,
+    user_id: Uuid,
+    requested_tier: KycTier,
+    document_ids: &[Uuid],
+) -> Result<KycApplication, Error> {
+    let statement = client
+        .prepare(&format!(
+            "INSERT INTO kyc_applications (user_id, requested_tier, document_ids)
+             VALUES ($1, $2, $3)
+             RETURNING {}",
+            APPLICATION_COLUMNS
+        ))
+        .await?;
+
+    let row = client
+        .query_one(&statement, &[&user_id, &requested_tier.to_string(), &document_ids])
+        .await?;
+
+    Ok(row.try_into().unwrap())
+}
+
+#[instrument(name = "dal.kyc.get_application_by_id", skip_all, fields(id = %id))]
+pub async fn get_application_by_id(client: &impl GenericClient, id: Uuid) -> Result<Option<KycApplication>, Error> {
+    let statement = client
+        .prepare(&format!("SELECT {} FROM kyc_applications WHERE id = $1", APPLICATION_COLUMNS))
+        .await?;
+
+    Ok(client
+        .query_opt(&statement, &[&id])
+        .await?
+        .map(|row| row.try_into().unwrap()))
+}
+
+#[instrument(name = "dal.kyc.get_application_for_update", skip_all, fields(id = %id))]
+pub async fn get_application_for_update(
+    client: &impl GenericClient,
+    id: Uuid,
+) -> Result<Option<KycApplication>, Error> {
+    let statement = client
+        .prepare(&format!("SELECT {} FROM kyc_applications WHERE id = $1 FOR UPDATE", APPLICATION_COLUMNS))
+        .await?;
+
+    Ok(client
+        .query_opt(&statement, &[&id])
+        .await?
+        .map(|row| row.try_into().unwrap()))
+}
+
+#[instrument(name = "dal.kyc.list_applications_by_user", skip_all, fields(user_id = %user_id))]
+pub async fn list_applications_by_user(
+    client: &impl GenericClient,
+    user_id: Uuid,
+) -> Result<Vec<KycApplication>, Error> {
+    let statement = client
+        .prepare(&format!(
+            "SELECT {} FROM kyc_applications WHERE user_id = $1 ORDER BY created_at DESC, id DESC",
+            APPLICATION_COLUMNS
+        ))
+        .await?;
+
+    let rows = client.query(&statement, &[&user_id]).await?;
+    Ok(rows.into_iter().map(|row| row.try_into().unwrap()).collect())
+}
+
+#[instrument(name = "dal.kyc.list_applications", skip_all)]
+pub async fn list_applications(
+    client: &Client,
+    params: &KycApplicationPaginationParams,
+    page: &PageRequest,
+) -> Result<Vec<KycApplication>, Error> {
+    let mut query = QueryBuilder::new(&format!("SELECT {} FROM kyc_applications WHERE TRUE", APPLICATION_COLUMNS));
+    push_application_filters(&mut query, params);
+    query.paginate(page, "created_at", "id");
+
+    let statement = client.prepare(query.sql()).await?;
+    let rows = client.query(&statement, &query.params()).await?;
+    Ok(rows.into_iter().map(|row| row.try_into().unwrap()).collect())
+}
+
+#[instrument(name = "dal.kyc.count_applications", skip_all)]
+pub async fn count_applications(client: &Client, params: &KycApplicationPaginationParams) -> Result<i64, Error> {
+    let mut query = QueryBuilder::new("SELECT COUNT(*) FROM kyc_applications WHERE TRUE");
+    push_application_filters(&mut query, params);
+
+    let statement = client.prepare(query.sql()).await?;
+    Ok(client.query_one(&statement, &query.params()).await?.get(0))
+}
+
+fn push_application_filters(query: &mut QueryBuilder, params: &KycApplicationPaginationParams) {
+    if let Some(status) = params.status {
+        query.push_bind(" AND status = {}", status.to_string());
+    }
+    if let Some(requested_tier) = params.requested_tier {
+        query.push_bind(" AND requested_tier = {}", requested_tier.to_string());
+    }
+    if let Some(user_id) = params.user_id {
+        query.push_bind(" AND user_id = {}", user_id);
+    }
+}
+
+#[instrument(name = "dal.kyc.resolve_application", skip_all, fields(id = %id, status = %status))]
+pub async fn resolve_application(
+    client: &impl GenericClient,
+    id: Uuid,
+    status: KycApplicationStatus,
+    reviewed_by: Uuid,
+    note: Option<&str>,
+) -> Result<Option<KycApplication>, Error> {
+    let statement = client
+        .prepare(&format!(
+            "UPDATE kyc_applications
+             SET status = $2, reviewed_by = $3, reviewed_at = NOW(), review_note = $4
+             WHERE id = $1 AND status = 'PENDING'
+             RETURNING {}",
+            APPLICATION_COLUMNS
+        ))
+        .await?;
+
+    Ok(client
+        .query_opt(&statement, &[&id, &status.to_string(), &reviewed_by, &note])
+        .await?
+        .map(|row| row.try_into().unwrap()))
+}
+
+#[instrument(name = "dal.kyc.list_caps", skip_all)]
+pub async fn list_caps(client: &impl GenericClient, tier: Option<KycTier>) -> Result<Vec<KycTierCaps>, Error> {
+    let statement = client
+        .prepare(&format!(
+            "SELECT {} FROM kyc_tier_caps
+             WHERE $1::TEXT IS NULL OR tier = $1
+             ORDER BY CASE tier WHEN 'UNVERIFIED' THEN 0 WHEN 'BASIC' THEN 1 ELSE 2 END, currency",
+            CAPS_COLUMNS
+        ))
+        .await?;
+
+    let rows = client.query(&statement, &[&tier.map(|tier| tier.to_string())]).await?;
+    Ok(rows.into_iter().map(|row| row.try_into().unwrap()).collect())
+}
+
+#[instrument(name = "dal.kyc.get_caps", skip_all, fields(tier = %tier))]
+pub async fn get_caps(
+    client: &impl GenericClient,
+    tier: KycTier,
+    currency: &str,
+) -> Result<Option<KycTierCaps>, Error> {
+    let statement = client
+        .prepare(&format!("SELECT {} FROM kyc_tier_caps WHERE tier = $1 AND currency = $2", CAPS_COLUMNS))
+        .await?;
+
+    Ok(client
+        .query_opt(&statement, &[&tier.to_string(), &currency])
+        .await?
+        .map(|row| row.try_into().unwrap()))
+}
+
+// The caps of the tier the user is verified to, in `currency`.
+#[instrument(name = "dal.kyc.get_caps_for_user", skip_all, fields(user_id = %user_id))]
+pub async fn get_caps_for_user(
+    client: &impl GenericClient,
+    user_id: Uuid,
+    currency: &str,
+) -> Result<Option<KycTierCaps>, Error> {
+    let statement = client
+        .prepare(
+            "SELECT c.tier, c.currency, c.max_accounts, c.max_balance, c.max_single_transaction,
+                    c.max_monthly_volume, c.updated_by, c.updated_at
+             FROM kyc_tier_caps c
+             JOIN users u ON u.kyc_tier = c.tier
+             WHERE u.id = $1 AND c.currency = $2",
+        )
+        .await?;
+
+    Ok(client
+        .query_opt(&statement, &[&user_id, &currency])
+        .await?
+        .map(|row| row.try_into().unwrap()))
+}
+
+// Creates the caps of the tier in the currency or replaces the ones it has.
+#[instrument(name = "dal.kyc.set_caps", skip_all, fields(tier = %tier))]
+pub async fn set_caps(
+    client: &impl GenericClient,
+    tier: KycTier,
+    currency: &str,
+    caps: &Caps,
+    updated_by: Uuid,
+) -> Result<KycTierCaps, Error> {
+    let statement = client
+        .prepare(&format!(
+            "INSERT INTO kyc_tier_caps
+                 (tier, currency, max_accounts, max_balance, max_single_transaction, max_monthly_volume, updated_by)
+             VALUES ($1, $2, $3, $4, $5, $6, $7)
+             ON CONFLICT (tier, currency) DO UPDATE
+             SET max_accounts = EXCLUDED.max_accounts,
+                 max_balance = EXCLUDED.max_balance,
+                 max_single_transaction = EXCLUDED.max_single_transaction,
+                 max_monthly_volume = EXCLUDED.max_monthly_volume,
+                 updated_by = EXCLUDED.updated_by,
+                 updated_at = NOW()
+             RETURNING {}",
+            CAPS_COLUMNS
+        ))
+        .await?;
+
+    let row = client
+        .query_one(
+            &statement,
+            &[
+                &tier.to_string(),
+                &currency,
+                &caps.max_accounts,
+                &caps.max_balance,
+                &caps.max_single_transaction,
+                &caps.max_monthly_volume,
+                &updated_by,
+            ],
+        )
+        .await?;
+
+    Ok(row.try_into().unwrap())
+}
+
+#[instrument(name = "dal.kyc.delete_caps", skip_all, fields(tier = %tier))]
+pub async fn delete_caps(
+    client: &impl GenericClient,
+    tier: KycTier,
+    currency: &str,
+) -> Result<Option<KycTierCaps>, Error> {
+    let statement = client
+        .prepare(&format!(
+            "DELETE FROM kyc_tier_caps WHERE tier = $1 AND currency = $2 RETURNING {}",
+            CAPS_COLUMNS
+        ))
+        .await?;
+
+    Ok(client
+        .query_opt(&statement, &[&tier.to_string(), &currency])
+        .await?
+        .map(|row| row.try_into().unwrap()))
+}
+
+// The user's accounts in `currency` that aren't closed.
+#[instrument(name = "dal.kyc.count_open_accounts", skip_all, fields(user_id = %user_id))]
+pub async fn count_open_accounts(client: &impl GenericClient, user_id: Uuid, currency: &str) -> Result<i64, Error> {
+    let statement = client
+        .prepare("SELECT COUNT(*) FROM accounts WHERE user_id = $1 AND currency = $2 AND status <> 'CLOSED'")
+        .await?;
+
+    Ok(client.query_one(&statement, &[&user_id, &currency]).await?.get(0))
+}
+
+// What the user holds over all of their accounts in `currency`.
+#[instrument(name = "dal.kyc.total_balance", skip_all, fields(user_id = %user_id))]
+pub async fn total_balance(client: &impl GenericClient, user_id: Uuid, currency: &str) -> Result<Decimal, Error> {
+    let statement = client
+        .prepare("SELECT COALESCE(SUM(balance), 0) FROM accounts WHERE user_id = $1 AND currency = $2")
+        .await?;
+
+    Ok(client.query_one(&statement, &[&user_id, &currency]).await?.get(0))
+}
+
+// Deposits and withdrawals over all of the user's accounts in `currency`
+// since `since`. Failed transactions and fees don't count.
+#[instrument(name = "dal.kyc.volume_since", skip_all, fields(user_id = %user_id))]
+pub async fn volume_since(
+    client: &impl GenericClient,
+    user_id: Uuid,
+    currency: &str,
+    since: DateTime<Utc>,
+) -> Result<Decimal, Error> {
+    let statement = client
+        .prepare(
+            "SELECT COALESCE(SUM(t.amount), 0)
+             FROM transactions t
+             JOIN accounts a ON a.id = t.account_id
+             WHERE a.user_id = $1 AND a.currency = $2 AND t.created_at >= $3
+               AND t.status <> 'FAILED' AND NOT ($4 = ANY(t.tags))",
+        )
+        .await?;
+
+    Ok(client
+        .query_one(&statement, &[&user_id, &currency, &since, &FEE_TRANSACTION_TAG])
+        .await?
+        .get(0))
+}
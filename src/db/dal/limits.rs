@@ -0,0 +1,263 @@
+use crate::base::{
+    constants::FEE_TRANSACTION_TAG,
+    limits::DebitWindows,
+    models::limits::{DebitUsage, LimitPaginationParams, LimitScope, Limits, TransactionLimit},
+    pagination::PageRequest,
+};
+use crate::db::query::QueryBuilder;
+use deadpool_postgres::{Client, GenericClient};
+use tokio_postgres::Error;
+use tracing::instrument;
+use uuid::Uuid;
+
+const LIMIT_COLUMNS: &str =
+    "id, scope, user_id, account_id, currency, max_single_withdrawal, daily_debit_limit, monthly_debit_limit,
+     max_debits_per_hour, updated_by, created_at, updated_at";
+
+// Withdrawals that weren't rejected count against the limits, except fees,
+// which belong to the transaction they were charged for.
+const DEBIT_USAGE: &str =
+    "SELECT COALESCE(SUM(t.amount) FILTER (WHERE t.created_at >= $2::TIMESTAMPTZ), 0) AS day_total,
+            COALESCE(SUM(t.amount) FILTER (WHERE t.created_at >= $3::TIMESTAMPTZ), 0) AS month_total,
+            COUNT(*) FILTER (WHERE t.created_at > $4::TIMESTAMPTZ) AS hour_count,
+            MIN(t.created_at) FILTER (WHERE t.created_at > $4::TIMESTAMPTZ) + INTERVAL '1 hour' AS hour_resets_at
+     FROM transactions t
+     JOIN accounts a ON a.id = t.account_id
+     WHERE t.type = 'WITHDRAWAL' AND t.status <> 'FAILED' AND NOT ($5 = ANY(t.tags))
+       AND t.created_at >= LEAST($3::TIMESTAMPTZ, $4::TIMESTAMPTZ)";
+
+// Creates the limits of the target or replaces the ones it has.
+#[instrument(name = "dal.limits.set_limit", skip_all, fields(scope = %scope))]
+pub async fn set_limit(
+    client: &impl GenericClient,
+    scope: LimitScope,
+    user_id: Option<Uuid>,
+    account_id: Option<Uuid>,
+    currency: &str,
+    limits: &Limits,
+    updated_by: Uuid,
+) -> Result<TransactionLimit, Error> {
+    let conflict_target = match scope {
+        LimitScope::Default => "(currency) WHERE scope = 'DEFAULT'",
+        LimitScope::User => "(user_id, currency) WHERE scope = 'USER'",
+        LimitScope::Account => "(account_id) WHERE scope = 'ACCOUNT'",
+    };
+    let statement = client
+        .prepare(&format!(
+            "INSERT INTO transaction_limits
+                 (scope, user_id, account_id, currency, max_single_withdrawal, daily_debit_limit, monthly_debit_limit,
+                  max_debits_per_hour, updated_by)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+             ON CONFLICT {} DO UPDATE
+             SET max_single_withdrawal = EXCLUDED.max_single_withdrawal,
+                 daily_debit_limit = EXCLUDED.daily_debit_limit,
+                 monthly_debit_limit = EXCLUDED.monthly_debit_limit,
+                 max_debits_per_hour = EXCLUDED.max_debits_per_hour,
+                 updated_by = EXCLUDED.updated_by,
+                 updated_at = NOW()
+             RETURNING {}",
+            conflict_target, LIMIT_COLUMNS
+        ))
+        .await?;
+
+    let row = client
+        .query_one(
+            &statement,
+            &[
+                &scope.to_string(),
+                &user_id,
+                &account_id,
+                &currency,
+                &limits.max_single_withdrawal,
+                &limits.daily_debit_limit,
+                &limits.monthly_debit_limit,
+                &limits.max_debits_per_hour,
+                &updated_by,
+            ],
+        )
+        .await?;
+
+    Ok(row.try_into().unwrap())
+}
+
+#[instrument(name = "dal.limits.get_limit_by_id", skip_all, fields(id = %id))]
+pub async fn get_limit_by_id(client: &impl GenericClient, id: Uuid) -> Result<Option<TransactionLimit>, Error> {
+    let statement = client
+        .prepare(&format!("SELECT {} FROM transaction_limits WHERE id = $1", LIMIT_COLUMNS))
+        .await?;
+
+    Ok(client
+        .query_opt(&statement, &[&id])
+        .await?
+        .map(|row| row.try_into().unwrap()))
+}
+
+// The row `set_limit` would replace, if there is one.
+#[instrument(name = "dal.limits.find_limit", skip_all, fields(scope = %scope))]
+pub async fn find_limit(
+    client: &impl GenericClient,
+    scope: LimitScope,
+    user_id: Option<Uuid>,
+    account_id: Option<Uuid>,
+    currency: &str,
+) -> Result<Option<TransactionLimit>, Error> {
+    let mut query = QueryBuilder::new(&format!("SELECT {} FROM transaction_limits WHERE TRUE", LIMIT_COLUMNS));
+    query.push_bind(" AND scope = {}", scope.to_string());
+    match scope {
+        LimitScope::Default => query.push_bind(" AND currency = {}", currency.to_string()),
+        LimitScope::User => query
+            .push_bind(" AND user_id = {}", user_id)
+            .push_bind(" AND currency = {}", currency.to_string()),
+        LimitScope::Account => query.push_bind(" AND account_id = {}", account_id),
+    };
+
+    let statement = client.prepare(query.sql()).await?;
+    Ok(client
+        .query_opt(&statement, &query.params())
+        .await?
+        .map(|row| row.try_into().unwrap()))
+}
+
+// The account's own limits, falling back to the defaults for its currency,
+// and the limits of its owner in that currency.
+#[instrument(name = "dal.limits.get_limits_for_account", skip_all, fields(account_id = %account_id))]
+pub async fn get_limits_for_account(
+    client: &impl GenericClient,
+    account_id: Uuid,
+    user_id: Uuid,
+    currency: &str,
+) -> Result<(Limits, Limits), Error> {
+    let statement = client
+        .prepare(&format!(
+            "SELECT {} FROM transaction_limits
+             WHERE (scope = 'ACCOUNT' AND account_id = $1)
+                OR (scope = 'USER' AND user_id = $2 AND currency = $3)
+                OR (scope = 'DEFAULT' AND currency = $3)",
+            LIMIT_COLUMNS
+        ))
+        .await?;
+
+    let rows = client.query(&statement, &[&account_id, &user_id, &currency]).await?;
+    let (mut own, mut defaults, mut user) = (Limits::default(), Limits::default(), Limits::default());
+    for row in rows {
+        let limit: TransactionLimit = row.try_into().unwrap();
+        match limit.scope {
+            LimitScope::Account => own = limit.limits,
+            LimitScope::Default => defaults = limit.limits,
+            LimitScope::User => user = limit.limits,
+        }
+    }
+
+    Ok((own.or(defaults), user))
+}
+
+#[instrument(name = "dal.limits.list_limits", skip_all)]
+pub async fn list_limits(
+    client: &Client,
+    params: &LimitPaginationParams,
+    page: &PageRequest,
+) -> Result<Vec<TransactionLimit>, Error> {
+    let mut query = QueryBuilder::new(&format!("SELECT {} FROM transaction_limits WHERE TRUE", LIMIT_COLUMNS));
+    push_limit_filters(&mut query, params);
+    query.paginate(page, "created_at", "id");
+
+    let statement = client.prepare(query.sql()).await?;
+    let rows = client.query(&statement, &query.params()).await?;
+    Ok(rows.into_iter().map(|row| row.try_into().unwrap()).collect())
+}
+
+#[instrument(name = "dal.limits.count_limits", skip_all)]
+pub async fn count_limits(client: &Client, params: &LimitPaginationParams) -> Result<i64, Error> {
+    let mut query = QueryBuilder::new("SELECT COUNT(*) FROM transaction_limits WHERE TRUE");
+    push_limit_filters(&mut query, params);
+
+    let statement = client.prepare(query.sql()).await?;
+    Ok(client.query_one(&statement, &query.params()).await?.get(0))
+}
+
+fn push_limit_filters(query: &mut QueryBuilder, params: &LimitPaginationParams) {
+    if let Some(scope) = params.scope {
+        query.push_bind(" AND scope = {}", scope.to_string());
+    }
+    if let Some(user_id) = params.user_id {
+        query.push_bind(" AND user_id = {}", user_id);
+    }
+    if let Some(account_id) = params.account_id {
+        query.push_bind(" AND account_id = {}", account_id);
+    }
+    if let Some(currency) = &params.currency {
+        query.push_bind(" AND currency = {}", currency.clone());
+    }
+}
+
+#[instrument(name = "dal.limits.delete_limit", skip_all, fields(id = %id))]
+pub async fn delete_limit(client: &impl GenericClient, id: Uuid) -> Result<Option<TransactionLimit>, Error> {
+    let statement = client
+        .prepare(&format!("DELETE FROM transaction_limits WHERE id = $1 RETURNING {}", LIMIT_COLUMNS))
+        .await?;
+
+    Ok(client
+        .query_opt(&statement, &[&id])
+        .await?
+        .map(|row| row.try_into().unwrap()))
+}
+
+#[instrument(name = "dal.limits.account_debit_usage", skip_all, fields(account_id = %account_id))]
+pub async fn account_debit_usage(
+    client: &impl GenericClient,
+    account_id: Uuid,
+    windows: &DebitWindows,
+) -> Result<DebitUsage, Error> {
+    let statement = client
+        .prepare(&format!("{} AND t.account_id = $1", DEBIT_USAGE))
+        .await?;
+
+    client
+        .query_one(
+            &statement,
+            &[&account_id, &windows.day_start, &windows.month_start, &windows.hour_start, &FEE_TRANSACTION_TAG],
+        )
+        .await?
+        .try_into()
+}
+
+// Debits over all of the user's accounts in `currency`.
+#[instrument(name = "dal.limits.user_debit_usage", skip_all, fields(user_id = %user_id))]
+pub async fn user_debit_usage(
+    client: &impl GenericClient,
+    user_id: Uuid,
+    currency: &str,
+    windows: &DebitWindows,
+) -> Result<DebitUsage, Error> {
+    let statement = client
+        .prepare(&format!("{} AND a.user_id = $1 AND a.currency = $6", DEBIT_USAGE))
+        .await?;
+
+    client
+        .query_one(
+            &statement,
+            &[
+                &user_id,
+                &windows.day_start,
+                &windows.month_start,
+                &windows.hour_start,
+                &FEE_TRANSACTION_TAG,
+                &currency,
+            ],
+        )
+        .await?
+        .try_into()
+}
+
+// Serializes debits across the user's accounts until the surrounding
+// transaction ends, so their totals can't be exceeded by concurrent debits
+// from different accounts.
+#[instrument(name = "dal.limits.lock_user_debits", skip_all, fields(user_id = %user_id))]
+pub async fn lock_user_debits(client: &impl GenericClient, user_id: Uuid) -> Result<(), Error> {
+    let statement = client
+        .prepare("SELECT pg_advisory_xact_lock(hashtextextended('user_debits:' || $1::UUID::TEXT, 0))")
+        .await?;
+
+    client.execute(&statement, &[&user_id]).await?;
+    Ok(())
+}
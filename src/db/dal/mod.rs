@@ -1,3 +1,18 @@
 pub mod users;
 pub mod accounts;
 pub mod transactions;
+pub mod reconciliation;
+pub mod balances;
+pub mod audit;
+pub mod consents;
+pub mod exports;
+pub mod webhooks;
+pub mod events;
+pub mod scheduled_payments;
+pub mod fees;
+pub mod limits;
+pub mod risk;
+pub mod screening;
+pub mod kyc;
+pub mod aml;
+pub mod approvals;
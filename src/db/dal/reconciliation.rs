@@ -0,0 +1,228 @@
+use crate::base::{
+    models::{
+        reconciliation::{LineStatus, NewStatementImport, ReconciledLine, ReconciliationCandidate, StatementImport, StatementLine},
+        transactions::TransactionType,
+    },
+    pagination::PageRequest,
+};
+use crate::db::query::QueryBuilder;
+use chrono::NaiveDate;
+use deadpool_postgres::GenericClient;
+use rust_decimal::Decimal;
+use tokio_postgres::Error;
+use tracing::instrument;
+use uuid::Uuid;
+
+const CANDIDATE_COLUMNS: &str =
+    "t.id, t.account_id, t.amount, t.type, t.status, t.description, t.metadata, t.tags, t.booked, t.created_at, t.updated_at,
+     a.currency, a.user_id,
+     EXISTS (SELECT 1 FROM statement_lines l WHERE l.transaction_id = t.id AND l.status = 'MATCHED') AS reconciled";
+
+const IMPORT_COLUMNS: &str =
+    "id, format, filename, file_sha256, statement_reference, account_reference,
+     line_count, matched_count, exception_count, imported_by, created_at";
+
+const LINE_COLUMNS: &str =
+    "id, import_id, line_number, booking_date, amount, currency, reference, description,
+     status, transaction_id, reason, created_at";
+
+// Locks the transaction so concurrent imports can't settle it twice.
+#[instrument(name = "dal.reconciliation.get_candidate_by_id", skip_all, fields(id = %id))]
+pub async fn get_candidate_by_id(client: &impl GenericClient, id: Uuid) -> Result<Option<ReconciliationCandidate>, Error> {
+    let statement = client
+        .prepare(&format!(
+            "SELECT {} FROM transactions t
+             JOIN accounts a ON t.account_id = a.id
+             WHERE t.id = $1
+             FOR UPDATE OF t",
+            CANDIDATE_COLUMNS
+        ))
+        .await?;
+
+    Ok(client
+        .query_opt(&statement, &[&id])
+        .await?
+        .map(|row| row.try_into().unwrap()))
+}
+
+// Unsettled, non-failed transactions with the given amount and direction that
+// were created between `from` and `to` (inclusive, UTC dates).
+#[instrument(name = "dal.reconciliation.find_candidates", skip_all)]
+pub async fn find_candidates(
+    client: &impl GenericClient,
+    amount: Decimal,
+    transaction_type: &TransactionType,
+    from: NaiveDate,
+    to: NaiveDate,
+    limit: i64,
+) -> Result<Vec<ReconciliationCandidate>, Error> {
+    let statement = client
+        .prepare(&format!(
+            "SELECT {} FROM transactions t
+             JOIN accounts a ON t.account_id = a.id
+             WHERE t.amount = $1
+               AND t.type = $2
+               AND t.status <> 'FAILED'
+               AND (t.created_at AT TIME ZONE 'UTC')::date BETWEEN $3 AND $4
+               AND NOT EXISTS (SELECT 1 FROM statement_lines l WHERE l.transaction_id = t.id AND l.status = 'MATCHED')
+             ORDER BY t.created_at, t.id
+             LIMIT $5
+             FOR UPDATE OF t",
+            CANDIDATE_COLUMNS
+        ))
+        .await?;
+
+    let rows = client
+        .query(&statement, &[&amount, &transaction_type.to_string(), &from, &to, &limit])
+        .await?;
+    Ok(rows.into_iter().map(|row| row.try_into().unwrap()).collect())
+}
+
+#[instrument(name = "dal.reconciliation.get_import_by_sha256", skip_all)]
+pub async fn get_import_by_sha256(client: &impl GenericClient, sha256: &str) -> Result<Option<StatementImport>, Error> {
+    let statement = client
+        .prepare(&format!("SELECT {} FROM statement_imports WHERE file_sha256 = $1", IMPORT_COLUMNS))
+        .await?;
+
+    Ok(client
+        .query_opt(&statement, &[&sha256])
+        .await?
+        .map(|row| row.try_into().unwrap()))
+}
+
+#[instrument(name = "dal.reconciliation.create_import", skip_all)]
+pub async fn create_import(client: &impl GenericClient, import: &NewStatementImport) -> Result<StatementImport, Error> {
+    let statement = client
+        .prepare(&format!(
+            "INSERT INTO statement_imports
+                 (format, filename, file_sha256, statement_reference, account_reference, imported_by,
+                  line_count, matched_count, exception_count)
+             VALUES ($1, $2, $3, $4, $5, $6, 0, 0, 0)
+             RETURNING {}",
+            IMPORT_COLUMNS
+        ))
+        .await?;
+
+    client
+        .query_one(
+            &statement,
+            &[
+                &import.format.to_string(),
+                &import.filename,
+                &import.file_sha256,
+                &import.statement_reference,
+                &import.account_reference,
+                &import.imported_by,
+            ],
+        )
+        .await?
+        .try_into()
+}
+
+#[instrument(name = "dal.reconciliation.finish_import", skip_all, fields(id = %id))]
+pub async fn finish_import(
+    client: &impl GenericClient,
+    id: Uuid,
+    line_count: i32,
+    matched_count: i32,
+) -> Result<StatementImport, Error> {
+    let statement = client
+        .prepare(&format!(
+            "UPDATE statement_imports
+             SET line_count = $1::INT,
+                 matched_count = $2::INT,
+                 exception_count = $1::INT - $2::INT
+             WHERE id = $3
+             RETURNING {}",
+            IMPORT_COLUMNS
+        ))
+        .await?;
+
+    client
+        .query_one(&statement, &[&line_count, &matched_count, &id])
+        .await?
+        .try_into()
+}
+
+#[instrument(name = "dal.reconciliation.create_line", skip_all, fields(import_id = %import_id))]
+pub async fn create_line(client: &impl GenericClient, import_id: Uuid, line: &ReconciledLine) -> Result<StatementLine, Error> {
+    let statement = client
+        .prepare(&format!(
+            "INSERT INTO statement_lines
+                 (import_id, line_number, booking_date, amount, currency, reference, description, status, transaction_id, reason)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+             RETURNING {}",
+            LINE_COLUMNS
+        ))
+        .await?;
+
+    client
+        .query_one(
+            &statement,
+            &[
+                &import_id,
+                &line.line_number,
+                &line.booking_date,
+                &line.amount,
+                &line.currency,
+                &line.reference,
+                &line.description,
+                &line.status.to_string(),
+                &line.transaction_id,
+                &line.reason,
+            ],
+        )
+        .await?
+        .try_into()
+}
+
+#[instrument(name = "dal.reconciliation.get_import_by_id", skip_all, fields(id = %id))]
+pub async fn get_import_by_id(client: &impl GenericClient, id: Uuid) -> Result<Option<StatementImport>, Error> {
+    let statement = client
+        .prepare(&format!("SELECT {} FROM statement_imports WHERE id = $1", IMPORT_COLUMNS))
+        .await?;
+
+    Ok(client
+        .query_opt(&statement, &[&id])
+        .await?
+        .map(|row| row.try_into().unwrap()))
+}
+
+#[instrument(name = "dal.reconciliation.list_imports", skip_all)]
+pub async fn list_imports(client: &impl GenericClient, page: &PageRequest) -> Result<Vec<StatementImport>, Error> {
+    let mut query = QueryBuilder::new(&format!("SELECT {} FROM statement_imports WHERE TRUE", IMPORT_COLUMNS));
+    query.paginate(page, "created_at", "id");
+
+    let statement = client.prepare(query.sql()).await?;
+    let rows = client.query(&statement, &query.params()).await?;
+    Ok(rows.into_iter().map(|row| row.try_into().unwrap()).collect())
+}
+
+#[instrument(name = "dal.reconciliation.count_imports", skip_all)]
+pub async fn count_imports(client: &impl GenericClient) -> Result<i64, Error> {
+    let statement = client.prepare("SELECT COUNT(*) FROM statement_imports").await?;
+    Ok(client.query_one(&statement, &[]).await?.get(0))
+}
+
+// Lines of an import in file order. `exceptions_only` leaves out matched lines.
+#[instrument(name = "dal.reconciliation.list_lines", skip_all, fields(import_id = %import_id))]
+pub async fn list_lines(
+    client: &impl GenericClient,
+    import_id: Uuid,
+    status: Option<LineStatus>,
+    exceptions_only: bool,
+) -> Result<Vec<StatementLine>, Error> {
+    let mut query = QueryBuilder::new(&format!("SELECT {} FROM statement_lines", LINE_COLUMNS));
+    query.push_bind(" WHERE import_id = {}", import_id);
+
+    if let Some(status) = status {
+        query.push_bind(" AND status = {}", status.to_string());
+    }
+    if exceptions_only {
+        query.push_bind(" AND status <> {}", LineStatus::Matched.to_string());
+    }
+
+    let statement = client.prepare(&format!("{} ORDER BY line_number, id", query.sql())).await?;
+    let rows = client.query(&statement, &query.params()).await?;
+    Ok(rows.into_iter().map(|row| row.try_into().unwrap()).collect())
+}
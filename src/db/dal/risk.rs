@@ -0,0 +1,281 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde_json::Value;
+use crate::base::{
+    constants::FEE_TRANSACTION_TAG,
+    models::{
+        accounts::Account,
+        risk::{
+            ReviewStatus, RiskAction, RiskAssessment, RiskAssessmentPaginationParams, RiskDecision, RiskFacts,
+            RiskRule, RiskRuleCode,
+        },
+    },
+    pagination::PageRequest,
+};
+use crate::db::query::QueryBuilder;
+use deadpool_postgres::{Client, GenericClient};
+use tokio_postgres::Error;
+use tracing::instrument;
+use uuid::Uuid;
+
+const RULE_COLUMNS: &str = "code, enabled, score, params, updated_by, updated_at";
+
+const ASSESSMENT_COLUMNS: &str =
+    "id, user_id, account_id, transaction_id, destination_account_id, amount, ip_address, score, action,
+     matched_rules, review_status, reviewed_by, reviewed_at, review_note, created_at";
+
+#[instrument(name = "dal.risk.list_rules", skip_all)]
+pub async fn list_rules(client: &impl GenericClient) -> Result<Vec<RiskRule>, Error> {
+    let statement = client
+        .prepare(&format!("SELECT {} FROM risk_rules ORDER BY code", RULE_COLUMNS))
+        .await?;
+
+    let rows = client.query(&statement, &[]).await?;
+    Ok(rows.into_iter().map(|row| row.try_into().unwrap()).collect())
+}
+
+#[instrument(name = "dal.risk.get_rule_for_update", skip_all, fields(code = %code))]
+pub async fn get_rule_for_update(client: &impl GenericClient, code: RiskRuleCode) -> Result<Option<RiskRule>, Error> {
+    let statement = client
+        .prepare(&format!("SELECT {} FROM risk_rules WHERE code = $1 FOR UPDATE", RULE_COLUMNS))
+        .await?;
+
+    Ok(client
+        .query_opt(&statement, &[&code.to_string()])
+        .await?
+        .map(|row| row.try_into().unwrap()))
+}
+
+#[instrument(name = "dal.risk.update_rule", skip_all, fields(code = %code))]
+pub async fn update_rule(
+    client: &impl GenericClient,
+    code: RiskRuleCode,
+    enabled: bool,
+    score: i32,
+    params: &Value,
+    updated_by: Uuid,
+) -> Result<RiskRule, Error> {
+    let statement = client
+        .prepare(&format!(
+            "UPDATE risk_rules
+             SET enabled = $2, score = $3, params = $4, updated_by = $5, updated_at = NOW()
+             WHERE code = $1
+             RETURNING {}",
+            RULE_COLUMNS
+        ))
+        .await?;
+
+    let row = client
+        .query_one(&statement, &[&code.to_string(), &enabled, &score, params, &updated_by])
+        .await?;
+    Ok(row.try_into().unwrap())
+}
+
+// Debits that weren't rejected count, except fees. `ip_address` is NULL for
+// debits not made over HTTP, which then count as coming from a known address.
+#[instrument(name = "dal.risk.risk_facts", skip_all, fields(account_id = %account.id))]
+pub async fn risk_facts(
+    client: &impl GenericClient,
+    account: &Account,
+    ip_address: Option<&str>,
+    recent_since: DateTime<Utc>,
+    history_since: DateTime<Utc>,
+) -> Result<RiskFacts, Error> {
+    let statement = client
+        .prepare(
+            "SELECT
+                 (SELECT COUNT(*) FROM transactions t
+                  WHERE t.account_id = $1 AND t.type = 'WITHDRAWAL' AND t.status <> 'FAILED'
+                    AND NOT ($6 = ANY(t.tags)) AND t.created_at > $4) AS recent_debits,
+                 ($5::TEXT IS NULL OR EXISTS (
+                     SELECT 1 FROM login_events l WHERE l.user_id = $2 AND l.succeeded AND l.ip_address = $5
+                 )) AS known_ip,
+                 EXISTS (SELECT 1 FROM login_events l WHERE l.user_id = $2 AND l.succeeded) AS has_logins,
+                 history.history_count,
+                 history.history_average
+             FROM (
+                 SELECT COUNT(*) AS history_count, AVG(t.amount) AS history_average
+                 FROM transactions t
+                 JOIN accounts a ON a.id = t.account_id
+                 WHERE a.user_id = $2 AND a.currency = $3 AND t.type = 'WITHDRAWAL' AND t.status <> 'FAILED'
+                   AND NOT ($6 = ANY(t.tags)) AND t.created_at > $7
+             ) history",
+        )
+        .await?;
+
+    client
+        .query_one(
+            &statement,
+            &[
+                &account.id,
+                &account.user_id,
+                &account.currency,
+                &recent_since,
+                &ip_address,
+                &FEE_TRANSACTION_TAG,
+                &history_since,
+            ],
+        )
+        .await?
+        .try_into()
+}
+
+// REVIEW assessments start out PENDING review.
+#[instrument(name = "dal.risk.create_assessment", skip_all, fields(account_id = %account.id))]
+pub async fn create_assessment(
+    client: &impl GenericClient,
+    account: &Account,
+    transaction_id: Option<Uuid>,
+    destination_account_id: Option<Uuid>,
+    amount: Decimal,
+    ip_address: Option<&str>,
+    decision: &RiskDecision,
+) -> Result<RiskAssessment, Error> {
+    let statement = client
+        .prepare(&format!(
+            "INSERT INTO risk_assessments
+                 (user_id, account_id, transaction_id, destination_account_id, amount, ip_address, score, action,
+                  matched_rules, review_status)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+             RETURNING {}",
+            ASSESSMENT_COLUMNS
+        ))
+        .await?;
+
+    let matched_rules = serde_json::to_value(&decision.matched_rules).unwrap();
+    let review_status = (decision.action == RiskAction::Review).then(|| ReviewStatus::Pending.to_string());
+
+    let row = client
+        .query_one(
+            &statement,
+            &[
+                &account.user_id,
+                &account.id,
+                &transaction_id,
+                &destination_account_id,
+                &amount,
+                &ip_address,
+                &decision.score,
+                &decision.action.to_string(),
+                &matched_rules,
+                &review_status,
+            ],
+        )
+        .await?;
+
+    Ok(row.try_into().unwrap())
+}
+
+#[instrument(name = "dal.risk.get_assessment_by_id", skip_all, fields(id = %id))]
+pub async fn get_assessment_by_id(client: &impl GenericClient, id: Uuid) -> Result<Option<RiskAssessment>, Error> {
+    let statement = client
+        .prepare(&format!("SELECT {} FROM risk_assessments WHERE id = $1", ASSESSMENT_COLUMNS))
+        .await?;
+
+    Ok(client
+        .query_opt(&statement, &[&id])
+        .await?
+        .map(|row| row.try_into().unwrap()))
+}
+
+#[instrument(name = "dal.risk.get_assessment_for_update", skip_all, fields(id = %id))]
+pub async fn get_assessment_for_update(client: &impl GenericClient, id: Uuid) -> Result<Option<RiskAssessment>, Error> {
+    let statement = client
+        .prepare(&format!("SELECT {} FROM risk_assessments WHERE id = $1 FOR UPDATE", ASSESSMENT_COLUMNS))
+        .await?;
+
+    Ok(client
+        .query_opt(&statement, &[&id])
+        .await?
+        .map(|row| row.try_into().unwrap()))
+}
+
+#[instrument(name = "dal.risk.list_assessments", skip_all)]
+pub async fn list_assessments(
+    client: &Client,
+    params: &RiskAssessmentPaginationParams,
+    page: &PageRequest,
+) -> Result<Vec<RiskAssessment>, Error> {
+    let mut query = QueryBuilder::new(&format!("SELECT {} FROM risk_assessments WHERE TRUE", ASSESSMENT_COLUMNS));
+    push_assessment_filters(&mut query, params);
+    query.paginate(page, "created_at", "id");
+
+    let statement = client.prepare(query.sql()).await?;
+    let rows = client.query(&statement, &query.params()).await?;
+    Ok(rows.into_iter().map(|row| row.try_into().unwrap()).collect())
+}
+
+#[instrument(name = "dal.risk.count_assessments", skip_all)]
+pub async fn count_assessments(client: &Client, params: &RiskAssessmentPaginationParams) -> Result<i64, Error> {
+    let mut query = QueryBuilder::new("SELECT COUNT(*) FROM risk_assessments WHERE TRUE");
+    push_assessment_filters(&mut query, params);
+
+    let statement = client.prepare(query.sql()).await?;
+    Ok(client.query_one(&statement, &query.params()).await?.get(0))
+}
+
+fn push_assessment_filters(query: &mut QueryBuilder, params: &RiskAssessmentPaginationParams) {
+    if let Some(action) = params.action {
+        query.push_bind(" AND action = {}", action.to_string());
+    }
+    if let Some(review_status) = params.review_status {
+        query.push_bind(" AND review_status = {}", review_status.to_string());
+    }
+    if let Some(user_id) = params.user_id {
+        query.push_bind(" AND user_id = {}", user_id);
+    }
+    if let Some(account_id) = params.account_id {
+        query.push_bind(" AND account_id = {}", account_id);
+    }
+}
+
+// Whether the transaction is held for review.
+#[instrument(name = "dal.risk.is_under_review", skip_all, fields(transaction_id = %transaction_id))]
+pub async fn is_under_review(client: &impl GenericClient, transaction_id: Uuid) -> Result<bool, Error> {
+    let statement = client
+        .prepare(
+            "SELECT EXISTS (
+                 SELECT 1 FROM risk_assessments WHERE transaction_id = $1 AND review_status = 'PENDING'
+             )",
+        )
+        .await?;
+
+    Ok(client.query_one(&statement, &[&transaction_id]).await?.get(0))
+}
+
+// Reviews still pending for debits from or transfers to the account.
+#[instrument(name = "dal.risk.count_pending_reviews", skip_all, fields(account_id = %account_id))]
+pub async fn count_pending_reviews(client: &impl GenericClient, account_id: Uuid) -> Result<i64, Error> {
+    let statement = client
+        .prepare(
+            "SELECT COUNT(*) FROM risk_assessments
+             WHERE review_status = 'PENDING' AND (account_id = $1 OR destination_account_id = $1)",
+        )
+        .await?;
+
+    Ok(client.query_one(&statement, &[&account_id]).await?.get(0))
+}
+
+#[instrument(name = "dal.risk.resolve_review", skip_all, fields(id = %id))]
+pub async fn resolve_review(
+    client: &impl GenericClient,
+    id: Uuid,
+    status: ReviewStatus,
+    reviewed_by: Uuid,
+    note: Option<&str>,
+) -> Result<Option<RiskAssessment>, Error> {
+    let statement = client
+        .prepare(&format!(
+            "UPDATE risk_assessments
+             SET review_status = $2, reviewed_by = $3, reviewed_at = NOW(), review_note = $4
+             WHERE id = $1 AND review_status = 'PENDING'
+             RETURNING {}",
+            ASSESSMENT_COLUMNS
+        ))
+        .await?;
+
+    Ok(client
+        .query_opt(&statement, &[&id, &status.to_string(), &reviewed_by, &note])
+        .await?
+        .map(|row| row.try_into().unwrap()))
+}
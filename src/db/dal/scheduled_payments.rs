@@ -0,0 +1,355 @@
+use crate::base::{
+    models::scheduled_payments::{
+        CreateScheduledPaymentRequest, RunOutcome, RunStatus, ScheduleStatus, ScheduledPayment, ScheduledPaymentRun,
+    },
+    pagination::PageRequest,
+};
+use crate::db::query::QueryBuilder;
+use chrono::{DateTime, Utc};
+use deadpool_postgres::{Client, GenericClient};
+use tokio_postgres::Error;
+use tracing::instrument;
+use uuid::Uuid;
+
+const SCHEDULE_COLUMNS: &str =
+    "id, user_id, source_account_id, destination_account_id, amount, description, cron, rrule, timezone,
+     start_at, end_at, max_runs, status, next_run_at, runs_count, last_run_at, last_error_code, last_error_at,
+     created_at, updated_at";
+
+const RUN_COLUMNS: &str =
+    "id, schedule_id, scheduled_for, status, withdrawal_transaction_id, deposit_transaction_id, failure_code,
+     failure_reason, created_at";
+
+#[instrument(name = "dal.scheduled_payments.create_schedule", skip_all, fields(user_id = %user_id))]
+pub async fn create_schedule(
+    client: &impl GenericClient,
+    user_id: Uuid,
+    schedule: &CreateScheduledPaymentRequest,
+    start_at: DateTime<Utc>,
+    next_run_at: DateTime<Utc>,
+) -> Result<ScheduledPayment, Error> {
+    let statement = client
+        .prepare(&format!(
+            "INSERT INTO scheduled_payments
+                 (user_id, source_account_id, destination_account_id, amount, description, cron, rrule, timezone,
+                  start_at, end_at, max_runs, next_run_at)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)
+             RETURNING {}",
+            SCHEDULE_COLUMNS
+        ))
+        .await?;
+
+    let row = client
+        .query_one(
+            &statement,
+            &[
+                &user_id,
+                &schedule.source_account_id,
+                &schedule.destination_account_id,
+                &schedule.amount,
+                &schedule.description,
+                &schedule.cron,
+                &schedule.rrule,
+                &schedule.timezone(),
+                &start_at,
+                &schedule.end_at,
+                &schedule.max_runs,
+                &next_run_at,
+            ],
+        )
+        .await?;
+
+    Ok(row.try_into().unwrap())
+}
+
+#[instrument(name = "dal.scheduled_payments.get_schedule_by_id", skip_all, fields(id = %id))]
+pub async fn get_schedule_by_id(client: &Client, id: Uuid) -> Result<Option<ScheduledPayment>, Error> {
+    let statement = client
+        .prepare(&format!("SELECT {} FROM scheduled_payments WHERE id = $1", SCHEDULE_COLUMNS))
+        .await?;
+
+    Ok(client
+        .query_opt(&statement, &[&id])
+        .await?
+        .map(|row| row.try_into().unwrap()))
+}
+
+#[instrument(name = "dal.scheduled_payments.get_schedule_for_update", skip_all, fields(id = %id))]
+pub async fn get_schedule_for_update(client: &impl GenericClient, id: Uuid) -> Result<Option<ScheduledPayment>, Error> {
+    let statement = client
+        .prepare(&format!(
+            "SELECT {} FROM scheduled_payments WHERE id = $1 FOR UPDATE",
+            SCHEDULE_COLUMNS
+        ))
+        .await?;
+
+    Ok(client
+        .query_opt(&statement, &[&id])
+        .await?
+        .map(|row| row.try_into().unwrap()))
+}
+
+#[instrument(name = "dal.scheduled_payments.list_schedules", skip_all, fields(user_id = %user_id))]
+pub async fn list_schedules(
+    client: &Client,
+    user_id: Uuid,
+    status: Option<ScheduleStatus>,
+    page: &PageRequest,
+) -> Result<Vec<ScheduledPayment>, Error> {
+    let mut query = QueryBuilder::new(&format!("SELECT {} FROM scheduled_payments WHERE TRUE", SCHEDULE_COLUMNS));
+    push_schedule_filters(&mut query, user_id, status);
+    query.paginate(page, "created_at", "id");
+
+    let statement = client.prepare(query.sql()).await?;
+    let rows = client.query(&statement, &query.params()).await?;
+    Ok(rows.into_iter().map(|row| row.try_into().unwrap()).collect())
+}
+
+#[instrument(name = "dal.scheduled_payments.count_schedules", skip_all, fields(user_id = %user_id))]
+pub async fn count_schedules(client: &Client, user_id: Uuid, status: Option<ScheduleStatus>) -> Result<i64, Error> {
+    let mut query = QueryBuilder::new("SELECT COUNT(*) FROM scheduled_payments WHERE TRUE");
+    push_schedule_filters(&mut query, user_id, status);
+
+    let statement = client.prepare(query.sql()).await?;
+    Ok(client.query_one(&statement, &query.params()).await?.get(0))
+}
+
+fn push_schedule_filters(query: &mut QueryBuilder, user_id: Uuid, status: Option<ScheduleStatus>) {
+    query.push_bind(" AND user_id = {}", user_id);
+
+    if let Some(status) = status {
+        query.push_bind(" AND status = {}", status.to_string());
+    }
+}
+
+// `next_run_at` is cleared for every status but ACTIVE.
+#[instrument(name = "dal.scheduled_payments.set_schedule_status", skip_all, fields(id = %id, status = %status))]
+pub async fn set_schedule_status(
+    client: &impl GenericClient,
+    id: Uuid,
+    status: ScheduleStatus,
+    next_run_at: Option<DateTime<Utc>>,
+) -> Result<Option<ScheduledPayment>, Error> {
+    let statement = client
+        .prepare(&format!(
+            "UPDATE scheduled_payments
+             SET status = $2, next_run_at = $3, updated_at = NOW()
+             WHERE id = $1
+             RETURNING {}",
+            SCHEDULE_COLUMNS
+        ))
+        .await?;
+
+    Ok(client
+        .query_opt(&statement, &[&id, &status.to_string(), &next_run_at])
+        .await?
+        .map(|row| row.try_into().unwrap()))
+}
+
+// Active schedules due at `now`, most overdue first.
+#[instrument(name = "dal.scheduled_payments.list_due_schedule_ids", skip_all)]
+pub async fn list_due_schedule_ids(client: &Client, now: DateTime<Utc>, limit: i64) -> Result<Vec<Uuid>, Error> {
+    let statement = client
+        .prepare(
+            "SELECT id FROM scheduled_payments
+             WHERE status = 'ACTIVE' AND next_run_at <= $1
+             ORDER BY next_run_at
+             LIMIT $2",
+        )
+        .await?;
+
+    let rows = client.query(&statement, &[&now, &limit]).await?;
+    Ok(rows.into_iter().map(|row| row.get(0)).collect())
+}
+
+// Locks the schedule if it is still due. Returns `None` when another
+// scheduler holds it or has already run it.
+#[instrument(name = "dal.scheduled_payments.claim_due_schedule", skip_all, fields(id = %id))]
+pub async fn claim_due_schedule(
+    client: &impl GenericClient,
+    id: Uuid,
+    now: DateTime<Utc>,
+) -> Result<Option<ScheduledPayment>, Error> {
+    let statement = client
+        .prepare(&format!(
+            "SELECT {} FROM scheduled_payments
+             WHERE id = $1 AND status = 'ACTIVE' AND next_run_at <= $2
+             FOR UPDATE SKIP LOCKED",
+            SCHEDULE_COLUMNS
+        ))
+        .await?;
+
+    Ok(client
+        .query_opt(&statement, &[&id, &now])
+        .await?
+        .map(|row| row.try_into().unwrap()))
+}
+
+// Counts a run and moves the schedule on to `next_run_at`, or completes it
+// when there is none.
+#[instrument(name = "dal.scheduled_payments.advance_schedule", skip_all, fields(id = %id))]
+pub async fn advance_schedule(
+    client: &impl GenericClient,
+    id: Uuid,
+    ran_at: DateTime<Utc>,
+    next_run_at: Option<DateTime<Utc>>,
+) -> Result<ScheduledPayment, Error> {
+    let statement = client
+        .prepare(&format!(
+            "UPDATE scheduled_payments
+             SET runs_count = runs_count + 1,
+                 last_run_at = $2,
+                 next_run_at = $3,
+                 status = CASE WHEN $3::TIMESTAMPTZ IS NULL THEN 'COMPLETED' ELSE status END,
+                 last_error_code = NULL,
+                 last_error_at = NULL,
+                 updated_at = NOW()
+             WHERE id = $1
+             RETURNING {}",
+            SCHEDULE_COLUMNS
+        ))
+        .await?;
+
+    let row = client.query_one(&statement, &[&id, &ran_at, &next_run_at]).await?;
+    Ok(row.try_into().unwrap())
+}
+
+// Notes an error that kept the schedule from running. It stays due.
+#[instrument(name = "dal.scheduled_payments.record_schedule_error", skip_all, fields(id = %id))]
+pub async fn record_schedule_error(
+    client: &impl GenericClient,
+    id: Uuid,
+    code: &str,
+    failed_at: DateTime<Utc>,
+) -> Result<u64, Error> {
+    let statement = client
+        .prepare(
+            "UPDATE scheduled_payments
+             SET last_error_code = $2, last_error_at = $3, updated_at = NOW()
+             WHERE id = $1",
+        )
+        .await?;
+
+    client.execute(&statement, &[&id, &code, &failed_at]).await
+}
+
+// Used when the owner is erased.
+#[instrument(name = "dal.scheduled_payments.cancel_schedules_by_user_id", skip_all, fields(user_id = %user_id))]
+pub async fn cancel_schedules_by_user_id(client: &impl GenericClient, user_id: Uuid) -> Result<u64, Error> {
+    let statement = client
+        .prepare(
+            "UPDATE scheduled_payments
+             SET status = 'CANCELLED', next_run_at = NULL, updated_at = NOW()
+             WHERE user_id = $1 AND status IN ('ACTIVE', 'PAUSED')",
+        )
+        .await?;
+
+    client.execute(&statement, &[&user_id]).await
+}
+
+#[instrument(name = "dal.scheduled_payments.create_run", skip_all, fields(schedule_id = %schedule_id))]
+pub async fn create_run(
+    client: &impl GenericClient,
+    schedule_id: Uuid,
+    scheduled_for: DateTime<Utc>,
+    outcome: &RunOutcome,
+) -> Result<ScheduledPaymentRun, Error> {
+    let statement = client
+        .prepare(&format!(
+            "INSERT INTO scheduled_payment_runs
+                 (schedule_id, scheduled_for, status, withdrawal_transaction_id, deposit_transaction_id,
+                  failure_code, failure_reason)
+             VALUES ($1, $2, $3, $4, $5, $6, $7)
+             RETURNING {}",
+            RUN_COLUMNS
+        ))
+        .await?;
+
+    let (status, withdrawal_id, deposit_id, failure_code, failure_reason) = match outcome {
+        RunOutcome::Succeeded { withdrawal_id, deposit_id } => {
+            (RunStatus::Succeeded, Some(*withdrawal_id), Some(*deposit_id), None, None)
+        }
+        RunOutcome::Failed { code, reason } => (RunStatus::Failed, None, None, Some(*code), Some(reason.as_str())),
+        RunOutcome::InReview { withdrawal_id } => (RunStatus::InReview, Some(*withdrawal_id), None, None, None),
+    };
+
+    let row = client
+        .query_one(
+            &statement,
+            &[
+                &schedule_id,
+                &scheduled_for,
+                &status.to_string(),
+                &withdrawal_id,
+                &deposit_id,
+                &failure_code,
+                &failure_reason,
+            ],
+        )
+        .await?;
+
+    Ok(row.try_into().unwrap())
+}
+
+// Records how the review of a held transfer ended: SUCCEEDED with the deposit
+// booked on approval, or FAILED when it was rejected.
+#[instrument(name = "dal.scheduled_payments.settle_run", skip_all, fields(withdrawal_id = %withdrawal_id))]
+pub async fn settle_run(
+    client: &impl GenericClient,
+    withdrawal_id: Uuid,
+    outcome: &RunOutcome,
+) -> Result<Option<ScheduledPaymentRun>, Error> {
+    let statement = client
+        .prepare(&format!(
+            "UPDATE scheduled_payment_runs
+             SET status = $2, deposit_transaction_id = $3, failure_code = $4, failure_reason = $5
+             WHERE withdrawal_transaction_id = $1 AND status = 'IN_REVIEW'
+             RETURNING {}",
+            RUN_COLUMNS
+        ))
+        .await?;
+
+    let (status, deposit_id, failure_code, failure_reason) = match outcome {
+        RunOutcome::Succeeded { deposit_id, .. } => (RunStatus::Succeeded, Some(*deposit_id), None, None),
+        RunOutcome::Failed { code, reason } => (RunStatus::Failed, None, Some(*code), Some(reason.as_str())),
+        RunOutcome::InReview { .. } => (RunStatus::InReview, None, None, None),
+    };
+
+    Ok(client
+        .query_opt(&statement, &[&withdrawal_id, &status.to_string(), &deposit_id, &failure_code, &failure_reason])
+        .await?
+        .map(|row| row.try_into().unwrap()))
+}
+
+#[instrument(name = "dal.scheduled_payments.list_runs", skip_all, fields(schedule_id = %schedule_id))]
+pub async fn list_runs(
+    client: &Client,
+    schedule_id: Uuid,
+    status: Option<RunStatus>,
+    page: &PageRequest,
+) -> Result<Vec<ScheduledPaymentRun>, Error> {
+    let mut query = QueryBuilder::new(&format!("SELECT {} FROM scheduled_payment_runs WHERE TRUE", RUN_COLUMNS));
+    push_run_filters(&mut query, schedule_id, status);
+    query.paginate(page, "created_at", "id");
+
+    let statement = client.prepare(query.sql()).await?;
+    let rows = client.query(&statement, &query.params()).await?;
+    Ok(rows.into_iter().map(|row| row.try_into().unwrap()).collect())
+}
+
+#[instrument(name = "dal.scheduled_payments.count_runs", skip_all, fields(schedule_id = %schedule_id))]
+pub async fn count_runs(client: &Client, schedule_id: Uuid, status: Option<RunStatus>) -> Result<i64, Error> {
+    let mut query = QueryBuilder::new("SELECT COUNT(*) FROM scheduled_payment_runs WHERE TRUE");
+    push_run_filters(&mut query, schedule_id, status);
+
+    let statement = client.prepare(query.sql()).await?;
+    Ok(client.query_one(&statement, &query.params()).await?.get(0))
+}
+
+fn push_run_filters(query: &mut QueryBuilder, schedule_id: Uuid, status: Option<RunStatus>) {
+    query.push_bind(" AND schedule_id = {}", schedule_id);
+
+    if let Some(status) = status {
+        query.push_bind(" AND status = {}", status.to_string());
+    }
+}
@@ -0,0 +1,290 @@
+use std::collections::{HashMap, HashSet};
+use crate::base::{
+    constants::WATCHLIST_INSERT_BATCH_SIZE,
+    models::screening::{
+        NewWatchlistEntry, ScreeningOutcome, ScreeningResult, ScreeningResultPaginationParams, ScreeningReviewStatus,
+        ScreeningSubject, ScreeningTrigger, WatchlistEntry, WatchlistSource, WatchlistSummary,
+    },
+    pagination::PageRequest,
+};
+use crate::db::query::QueryBuilder;
+use deadpool_postgres::{Client, GenericClient};
+use tokio_postgres::Error;
+use tracing::instrument;
+use uuid::Uuid;
+
+const ENTRY_COLUMNS: &str = "id, source, external_id, entry_type, name, aliases, programs, loaded_at";
+
+const RESULT_COLUMNS: &str =
+    "id, subject_type, trigger, user_id, transaction_id, subject_name, score, decision, matches, review_status,
+     reviewed_by, reviewed_at, review_note, created_at";
+
+#[instrument(name = "dal.screening.list_watchlist_entries", skip_all)]
+pub async fn list_watchlist_entries(client: &impl GenericClient) -> Result<Vec<WatchlistEntry>, Error> {
+    let statement = client
+        .prepare(&format!("SELECT {} FROM watchlist_entries", ENTRY_COLUMNS))
+        .await?;
+
+    let rows = client.query(&statement, &[]).await?;
+    Ok(rows.into_iter().map(|row| row.try_into().unwrap()).collect())
+}
+
+#[instrument(name = "dal.screening.watchlist_version", skip_all)]
+pub async fn watchlist_version(client: &impl GenericClient) -> Result<i64, Error> {
+    let statement = client.prepare("SELECT version FROM watchlist_version").await?;
+    Ok(client.query_one(&statement, &[]).await?.get(0))
+}
+
+#[instrument(name = "dal.screening.bump_watchlist_version", skip_all)]
+pub async fn bump_watchlist_version(client: &impl GenericClient) -> Result<i64, Error> {
+    let statement = client
+        .prepare("UPDATE watchlist_version SET version = version + 1 RETURNING version")
+        .await?;
+    Ok(client.query_one(&statement, &[]).await?.get(0))
+}
+
+#[instrument(name = "dal.screening.watchlist_summary", skip_all)]
+pub async fn watchlist_summary(client: &impl GenericClient) -> Result<Vec<WatchlistSummary>, Error> {
+    let statement = client
+        .prepare(
+            "SELECT source, COUNT(*) AS entries, MAX(loaded_at) AS loaded_at
+             FROM watchlist_entries
+             GROUP BY source
+             ORDER BY source",
+        )
+        .await?;
+
+    let rows = client.query(&statement, &[]).await?;
+    Ok(rows.into_iter().map(|row| row.try_into().unwrap()).collect())
+}
+
+// Replaces every entry of the source. Entries are sent in batches as JSON
+// arrays rather than one statement per entry.
+#[instrument(name = "dal.screening.replace_watchlist", skip_all, fields(source = %source, entries = entries.len()))]
+pub async fn replace_watchlist(
+    client: &impl GenericClient,
+    source: WatchlistSource,
+    entries: &[NewWatchlistEntry],
+) -> Result<u64, Error> {
+    client
+        .execute("DELETE FROM watchlist_entries WHERE source = $1", &[&source.to_string()])
+        .await?;
+
+    let statement = client
+        .prepare(
+            "INSERT INTO watchlist_entries (source, external_id, entry_type, name, aliases, programs)
+             SELECT $1, e.external_id, e.entry_type, e.name, e.aliases, e.programs
+             FROM jsonb_to_recordset($2) AS e(
+                 external_id TEXT, entry_type TEXT, name TEXT, aliases TEXT[], programs TEXT[]
+             )
+             ON CONFLICT (source, external_id) DO NOTHING",
+        )
+        .await?;
+
+    let mut inserted = 0;
+    for batch in entries.chunks(WATCHLIST_INSERT_BATCH_SIZE) {
+        let batch = serde_json::to_value(batch).unwrap();
+        inserted += client.execute(&statement, &[&source.to_string(), &batch]).await?;
+    }
+    Ok(inserted)
+}
+
+// Results that leave someone waiting on a review start out PENDING.
+#[instrument(name = "dal.screening.create_result", skip_all, fields(subject_type = %subject_type, trigger = %trigger))]
+pub async fn create_result(
+    client: &impl GenericClient,
+    subject_type: ScreeningSubject,
+    trigger: ScreeningTrigger,
+    user_id: Option<Uuid>,
+    transaction_id: Option<Uuid>,
+    subject_name: &str,
+    outcome: &ScreeningOutcome,
+) -> Result<ScreeningResult, Error> {
+    let statement = client
+        .prepare(&format!(
+            "INSERT INTO screening_results
+                 (subject_type, trigger, user_id, transaction_id, subject_name, score, decision, matches, review_status)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
+             RETURNING {}",
+            RESULT_COLUMNS
+        ))
+        .await?;
+
+    let matches = serde_json::to_value(&outcome.matches).unwrap();
+    let review_status = trigger.needs_review(outcome.decision).then(|| ScreeningReviewStatus::Pending.to_string());
+
+    let row = client
+        .query_one(
+            &statement,
+            &[
+                &subject_type.to_string(),
+                &trigger.to_string(),
+                &user_id,
+                &transaction_id,
+                &subject_name,
+                &outcome.score,
+                &outcome.decision.to_string(),
+                &matches,
+                &review_status,
+            ],
+        )
+        .await?;
+
+    Ok(row.try_into().unwrap())
+}
+
+#[instrument(name = "dal.screening.get_result_by_id", skip_all, fields(id = %id))]
+pub async fn get_result_by_id(client: &impl GenericClient, id: Uuid) -> Result<Option<ScreeningResult>, Error> {
+    let statement = client
+        .prepare(&format!("SELECT {} FROM screening_results WHERE id = $1", RESULT_COLUMNS))
+        .await?;
+
+    Ok(client
+        .query_opt(&statement, &[&id])
+        .await?
+        .map(|row| row.try_into().unwrap()))
+}
+
+#[instrument(name = "dal.screening.get_result_for_update", skip_all, fields(id = %id))]
+pub async fn get_result_for_update(client: &impl GenericClient, id: Uuid) -> Result<Option<ScreeningResult>, Error> {
+    let statement = client
+        .prepare(&format!("SELECT {} FROM screening_results WHERE id = $1 FOR UPDATE", RESULT_COLUMNS))
+        .await?;
+
+    Ok(client
+        .query_opt(&statement, &[&id])
+        .await?
+        .map(|row| row.try_into().unwrap()))
+}
+
+#[instrument(name = "dal.screening.list_results", skip_all)]
+pub async fn list_results(
+    client: &Client,
+    params: &ScreeningResultPaginationParams,
+    page: &PageRequest,
+) -> Result<Vec<ScreeningResult>, Error> {
+    let mut query = QueryBuilder::new(&format!("SELECT {} FROM screening_results WHERE TRUE", RESULT_COLUMNS));
+    push_result_filters(&mut query, params);
+    query.paginate(page, "created_at", "id");
+
+    let statement = client.prepare(query.sql()).await?;
+    let rows = client.query(&statement, &query.params()).await?;
+    Ok(rows.into_iter().map(|row| row.try_into().unwrap()).collect())
+}
+
+#[instrument(name = "dal.screening.count_results", skip_all)]
+pub async fn count_results(client: &Client, params: &ScreeningResultPaginationParams) -> Result<i64, Error> {
+    let mut query = QueryBuilder::new("SELECT COUNT(*) FROM screening_results WHERE TRUE");
+    push_result_filters(&mut query, params);
+
+    let statement = client.prepare(query.sql()).await?;
+    Ok(client.query_one(&statement, &query.params()).await?.get(0))
+}
+
+fn push_result_filters(query: &mut QueryBuilder, params: &ScreeningResultPaginationParams) {
+    if let Some(subject_type) = params.subject_type {
+        query.push_bind(" AND subject_type = {}", subject_type.to_string());
+    }
+    if let Some(decision) = params.decision {
+        query.push_bind(" AND decision = {}", decision.to_string());
+    }
+    if let Some(review_status) = params.review_status {
+        query.push_bind(" AND review_status = {}", review_status.to_string());
+    }
+    if let Some(user_id) = params.user_id {
+        query.push_bind(" AND user_id = {}", user_id);
+    }
+}
+
+#[instrument(name = "dal.screening.resolve_review", skip_all, fields(id = %id))]
+pub async fn resolve_review(
+    client: &impl GenericClient,
+    id: Uuid,
+    status: ScreeningReviewStatus,
+    reviewed_by: Uuid,
+    note: Option<&str>,
+) -> Result<Option<ScreeningResult>, Error> {
+    let statement = client
+        .prepare(&format!(
+            "UPDATE screening_results
+             SET review_status = $2, reviewed_by = $3, reviewed_at = NOW(), review_note = $4
+             WHERE id = $1 AND review_status = 'PENDING'
+             RETURNING {}",
+            RESULT_COLUMNS
+        ))
+        .await?;
+
+    Ok(client
+        .query_opt(&statement, &[&id, &status.to_string(), &reviewed_by, &note])
+        .await?
+        .map(|row| row.try_into().unwrap()))
+}
+
+// Whether a review of the user's own name is pending or confirmed a match.
+#[instrument(name = "dal.screening.is_user_restricted", skip_all, fields(user_id = %user_id))]
+pub async fn is_user_restricted(client: &impl GenericClient, user_id: Uuid) -> Result<bool, Error> {
+    let statement = client
+        .prepare(
+            "SELECT EXISTS (
+                 SELECT 1 FROM screening_results
+                 WHERE user_id = $1 AND subject_type = 'USER' AND review_status IN ('PENDING', 'CONFIRMED')
+             )",
+        )
+        .await?;
+
+    Ok(client.query_one(&statement, &[&user_id]).await?.get(0))
+}
+
+// Entries matched by reviews of users' own names, pending or decided, per
+// user; `user_id` limits it to one user.
+#[instrument(name = "dal.screening.reviewed_entries", skip_all)]
+pub async fn reviewed_entries(
+    client: &impl GenericClient,
+    user_id: Option<Uuid>,
+) -> Result<HashMap<Uuid, HashSet<(WatchlistSource, String)>>, Error> {
+    let statement = client
+        .prepare(
+            "SELECT DISTINCT r.user_id, m->>'source' AS source, m->>'external_id' AS external_id
+             FROM screening_results r, jsonb_array_elements(r.matches) m
+             WHERE r.subject_type = 'USER' AND r.review_status IS NOT NULL AND r.user_id IS NOT NULL
+               AND ($1::UUID IS NULL OR r.user_id = $1)",
+        )
+        .await?;
+
+    let mut reviewed: HashMap<Uuid, HashSet<(WatchlistSource, String)>> = HashMap::new();
+    for row in client.query(&statement, &[&user_id]).await? {
+        reviewed
+            .entry(row.get("user_id"))
+            .or_default()
+            .insert((WatchlistSource::parse(row.get("source")), row.get("external_id")));
+    }
+    Ok(reviewed)
+}
+
+// Whether the transaction is held for a review of its payee.
+#[instrument(name = "dal.screening.is_under_review", skip_all, fields(transaction_id = %transaction_id))]
+pub async fn is_under_review(client: &impl GenericClient, transaction_id: Uuid) -> Result<bool, Error> {
+    let statement = client
+        .prepare(
+            "SELECT EXISTS (
+                 SELECT 1 FROM screening_results WHERE transaction_id = $1 AND review_status = 'PENDING'
+             )",
+        )
+        .await?;
+
+    Ok(client.query_one(&statement, &[&transaction_id]).await?.get(0))
+}
+
+// Withdrawals from the account held for a review of their payee.
+#[instrument(name = "dal.screening.count_pending_reviews", skip_all, fields(account_id = %account_id))]
+pub async fn count_pending_reviews(client: &impl GenericClient, account_id: Uuid) -> Result<i64, Error> {
+    let statement = client
+        .prepare(
+            "SELECT COUNT(*) FROM screening_results r
+             JOIN transactions t ON t.id = r.transaction_id
+             WHERE r.review_status = 'PENDING' AND t.account_id = $1",
+        )
+        .await?;
+
+    Ok(client.query_one(&statement, &[&account_id]).await?.get(0))
+}
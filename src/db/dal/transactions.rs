@@ -1,31 +1,58 @@
-use crate::base::models::transactions::{Transaction, CreateTransactionRequest, TransactionStatus, TransactionType, UpdateTransactionStatusRequest};
-use deadpool_postgres::Client;
+use crate::base::{
+    models::transactions::{Transaction, CreateTransactionRequest, TransactionFilter, TransactionStatus, UpdateTransactionStatusRequest},
+    pagination::{PageRequest, SortField},
+};
+use crate::db::query::QueryBuilder;
+use deadpool_postgres::{Client, GenericClient};
 use tokio_postgres::Error;
+use tracing::instrument;
 use uuid::Uuid;
 
-pub async fn create_transaction(client: &Client, transaction: &CreateTransactionRequest) -> Result<Transaction, Error> {
+#[instrument(name = "dal.transactions.create_transaction", skip_all)]
+pub async fn create_transaction(client: &impl GenericClient, transaction: &CreateTransactionRequest) -> Result<Transaction, Error> {
+    insert_transaction(client, transaction, true).await
+}
+
+// Records a transaction that doesn't move the balance, as `POST /transactions`
+// does; historical balances and statements leave it out.
+#[instrument(name = "dal.transactions.create_unbooked_transaction", skip_all)]
+pub async fn create_unbooked_transaction(
+    client: &impl GenericClient,
+    transaction: &CreateTransactionRequest,
+) -> Result<Transaction, Error> {
+    insert_transaction(client, transaction, false).await
+}
+
+async fn insert_transaction(
+    client: &impl GenericClient,
+    transaction: &CreateTransactionRequest,
+    booked: bool,
+) -> Result<Transaction, Error> {
     let description = transaction.description.clone().unwrap_or_default();
     let transaction_type = transaction.transaction_type.to_string();
     let status = TransactionStatus::Pending.to_string();
+    let metadata = transaction.metadata.clone().unwrap_or_else(|| serde_json::json!({}));
+    let tags = transaction.tags.clone().unwrap_or_default();
 
     let statement = client
         .prepare(
-            "INSERT INTO transactions (account_id, amount, type, status, description) 
-             VALUES ($1, $2, $3, $4, $5) 
-             RETURNING id, account_id, amount, type, status, description, created_at, updated_at",
+            "INSERT INTO transactions (account_id, amount, type, status, description, metadata, tags, booked) 
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8) 
+             RETURNING id, account_id, amount, type, status, description, metadata, tags, booked, created_at, updated_at",
         )
         .await?;
 
     client
-        .query_one(&statement, &[&transaction.account_id, &transaction.amount, &transaction_type, &status, &description])
+        .query_one(&statement, &[&transaction.account_id, &transaction.amount, &transaction_type, &status, &description, &metadata, &tags, &booked])
         .await?
         .try_into()
 }
 
-pub async fn get_transaction_by_id(client: &Client, id: Uuid) -> Result<Option<Transaction>, Error> {
+#[instrument(name = "dal.transactions.get_transaction_by_id", skip_all, fields(id = %id))]
+pub async fn get_transaction_by_id(client: &impl GenericClient, id: Uuid) -> Result<Option<Transaction>, Error> {
     let statement = client
         .prepare(
-            "SELECT id, account_id, amount, type, status, description, created_at, updated_at 
+            "SELECT id, account_id, amount, type, status, description, metadata, tags, booked, created_at, updated_at 
              FROM transactions WHERE id = $1",
         )
         .await?;
@@ -36,8 +63,26 @@ pub async fn get_transaction_by_id(client: &Client, id: Uuid) -> Result<Option<T
         .map(|row| row.try_into().unwrap()))
 }
 
+// Locks the transaction row until the surrounding transaction ends.
+#[instrument(name = "dal.transactions.get_transaction_for_update", skip_all, fields(id = %id))]
+pub async fn get_transaction_for_update(client: &impl GenericClient, id: Uuid) -> Result<Option<Transaction>, Error> {
+    let statement = client
+        .prepare(
+            "SELECT id, account_id, amount, type, status, description, metadata, tags, booked, created_at, updated_at 
+             FROM transactions WHERE id = $1
+             FOR UPDATE",
+        )
+        .await?;
+
+    Ok(client
+        .query_opt(&statement, &[&id])
+        .await?
+        .map(|row| row.try_into().unwrap()))
+}
+
+#[instrument(name = "dal.transactions.update_transaction_status", skip_all, fields(id = %id))]
 pub async fn update_transaction_status(
-    client: &Client,
+    client: &impl GenericClient,
     id: Uuid,
     status_request: &UpdateTransactionStatusRequest,
 ) -> Result<Option<Transaction>, Error> {
@@ -49,7 +94,7 @@ pub async fn update_transaction_status(
              SET status = $1,
              updated_at = NOW()
              WHERE id = $2
-             RETURNING id, account_id, amount, type, status, description, created_at, updated_at",
+             RETURNING id, account_id, amount, type, status, description, metadata, tags, booked, created_at, updated_at",
         )
         .await?;
 
@@ -62,54 +107,107 @@ pub async fn update_transaction_status(
         .map(|row| row.try_into().unwrap()))
 }
 
+// Every transaction on any account of the user, oldest first.
+#[instrument(name = "dal.transactions.list_transactions_by_user_id", skip_all, fields(user_id = %user_id))]
+pub async fn list_transactions_by_user_id(client: &impl GenericClient, user_id: Uuid) -> Result<Vec<Transaction>, Error> {
+    let statement = client
+        .prepare(
+            "SELECT t.id, t.account_id, t.amount, t.type, t.status, t.description, t.metadata, t.tags, t.booked, t.created_at, t.updated_at
+             FROM transactions t
+             JOIN accounts a ON t.account_id = a.id
+             WHERE a.user_id = $1
+             ORDER BY t.created_at, t.id",
+        )
+        .await?;
+
+    let rows = client.query(&statement, &[&user_id]).await?;
+    Ok(rows.into_iter().map(|row| row.try_into().unwrap()).collect())
+}
+
+#[instrument(name = "dal.transactions.list_filtered_transactions", skip_all, fields(user_id = %filter.user_id))]
 pub async fn list_filtered_transactions(
     client: &Client,
-    user_id: Uuid,
-    account_id: Option<Uuid>,
-    transaction_type: Option<&TransactionType>,
-    status: Option<&TransactionStatus>,
-    offset: i64,
-    limit: i64,
+    filter: &TransactionFilter,
+    page: &PageRequest,
 ) -> Result<Vec<Transaction>, Error> {
-    let mut query = String::from(
-        "SELECT t.id, t.account_id, t.amount, t.type, t.status, t.description, t.created_at, t.updated_at 
+    let mut query = QueryBuilder::new(
+        "SELECT t.id, t.account_id, t.amount, t.type, t.status, t.description, t.metadata, t.tags, t.booked, t.created_at, t.updated_at 
          FROM transactions t
-         JOIN accounts a ON t.account_id = a.id
-         WHERE a.user_id = $1"
+         JOIN accounts a ON t.account_id = a.id"
+    );
+    push_filters(&mut query, filter);
+
+    let sort_column = match page.sort {
+        SortField::Amount => "t.amount",
+        _ => "t.created_at",
+    };
+    query.paginate(page, sort_column, "t.id");
+
+    let statement = client.prepare(query.sql()).await?;
+    let rows = client.query(&statement, &query.params()).await?;
+
+    Ok(rows.into_iter().map(|row| row.try_into().unwrap()).collect())
+}
+
+#[instrument(name = "dal.transactions.count_filtered_transactions", skip_all, fields(user_id = %filter.user_id))]
+pub async fn count_filtered_transactions(client: &Client, filter: &TransactionFilter) -> Result<i64, Error> {
+    let mut query = QueryBuilder::new(
+        "SELECT COUNT(*) FROM transactions t
+         JOIN accounts a ON t.account_id = a.id"
     );
+    push_filters(&mut query, filter);
 
-    let mut params: Vec<&(dyn tokio_postgres::types::ToSql + Sync)> = vec![&user_id];
+    let statement = client.prepare(query.sql()).await?;
+    Ok(client.query_one(&statement, &query.params()).await?.get(0))
+}
 
-    let mut type_str_owned = None;
-    let mut status_str_owned = None;
+fn push_filters(query: &mut QueryBuilder, filter: &TransactionFilter) {
+    query.push_bind(" WHERE a.user_id = {}", filter.user_id);
 
-    if let Some(acc_id) = &account_id {
-        query.push_str(&format!(" AND t.account_id = ${}", params.len() + 1));
-        params.push(acc_id);
+    if let Some(account_id) = filter.account_id {
+        query.push_bind(" AND t.account_id = {}", account_id);
     }
 
-    if let Some(t_type) = transaction_type {
-        type_str_owned = Some(t_type.to_string());
-        query.push_str(&format!(" AND t.type = ${}", params.len() + 1));
-        params.push(type_str_owned.as_ref().unwrap());
+    if let Some(transaction_type) = &filter.transaction_type {
+        query.push_bind(" AND t.type = {}", transaction_type.to_string());
     }
 
-    if let Some(t_status) = status {
-        status_str_owned = Some(t_status.to_string());
-        query.push_str(&format!(" AND t.status = ${}", params.len() + 1));
-        params.push(status_str_owned.as_ref().unwrap());
+    if let Some(status) = &filter.status {
+        query.push_bind(" AND t.status = {}", status.to_string());
     }
 
-    query.push_str(&format!(
-        " ORDER BY t.created_at DESC LIMIT ${} OFFSET ${}",
-        params.len() + 1,
-        params.len() + 2
-    ));
-    params.push(&limit);
-    params.push(&offset);
+    if let Some(from) = filter.from {
+        query.push_bind(" AND t.created_at >= {}", from);
+    }
 
-    let statement = client.prepare(&query).await?;
-    let rows = client.query(&statement, &params[..]).await?;
+    if let Some(to) = filter.to {
+        query.push_bind(" AND t.created_at < {}", to);
+    }
 
-    Ok(rows.into_iter().map(|row| row.try_into().unwrap()).collect())
+    if let Some(min_amount) = filter.min_amount {
+        query.push_bind(" AND t.amount >= {}", min_amount);
+    }
+
+    if let Some(max_amount) = filter.max_amount {
+        query.push_bind(" AND t.amount <= {}", max_amount);
+    }
+
+    if let Some(currency) = &filter.currency {
+        query.push_bind(" AND a.currency = {}", currency.clone());
+    }
+
+    // Matches the generated `description_tsv` column so the GIN index is used
+    if let Some(text) = &filter.text {
+        query.push_bind(" AND t.description_tsv @@ websearch_to_tsquery('simple', {})", text.clone());
+    }
+
+    if !filter.tags.is_empty() {
+        query.push_bind(" AND t.tags @> {}", filter.tags.clone());
+    }
+
+    // Containment keeps the GIN index on `metadata` usable
+    for (key, value) in &filter.metadata {
+        let key = query.bind(key.clone());
+        query.push_bind(&format!(" AND t.metadata @> jsonb_build_object({}::text, {{}}::jsonb)", key), value.clone());
+    }
 }
@@ -1,14 +1,23 @@
-use crate::base::models::users::{CreateUserRequest, UpdateUserRequest, User};
-use deadpool_postgres::Client;
+use crate::base::{
+    models::{
+        kyc::{KycStatus, KycTier},
+        users::{CreateUserRequest, LoginEvent, Role, UpdateUserRequest, User},
+    },
+    pagination::PageRequest,
+};
+use crate::db::query::QueryBuilder;
+use deadpool_postgres::{Client, GenericClient};
 use tokio_postgres::Error;
+use tracing::instrument;
 use uuid::Uuid;
 
-pub async fn create_user(client: &Client, user: &CreateUserRequest) -> Result<User, Error> {
+#[instrument(name = "dal.users.create_user", skip_all)]
+pub async fn create_user(client: &impl GenericClient, user: &CreateUserRequest) -> Result<User, Error> {
     let statement = client
         .prepare(
             "INSERT INTO users (name, email, password) 
              VALUES ($1, $2, $3) 
-             RETURNING id, name, email, created_at, updated_at",
+             RETURNING id, name, email, role, kyc_tier, kyc_status, created_at, updated_at",
         )
         .await?;
 
@@ -19,11 +28,12 @@ pub async fn create_user(client: &Client, user: &CreateUserRequest) -> Result<Us
         .unwrap())
 }
 
-pub async fn get_user_by_id(client: &Client, id: Uuid) -> Result<Option<User>, Error> {
+#[instrument(name = "dal.users.get_user_by_id", skip_all, fields(id = %id))]
+pub async fn get_user_by_id(client: &impl GenericClient, id: Uuid) -> Result<Option<User>, Error> {
     let statement = client
         .prepare(
-            "SELECT id, name, email, password, created_at, updated_at
-             FROM users WHERE id = $1",
+            "SELECT id, name, email, role, kyc_tier, kyc_status, password, created_at, updated_at
+             FROM users WHERE id = $1 AND deleted_at IS NULL",
         )
         .await?;
 
@@ -33,11 +43,22 @@ pub async fn get_user_by_id(client: &Client, id: Uuid) -> Result<Option<User>, E
         .map(|row| row.try_into().unwrap()))
 }
 
-pub async fn get_user_by_email(client: &Client, email: &str) -> Result<Option<User>, Error> {
+// Whether the user exists and wasn't erased. Checked on every authenticated
+// request, so tokens issued before an erasure stop working.
+#[instrument(name = "dal.users.is_active_user", skip_all, fields(id = %id))]
+pub async fn is_active_user(client: &impl GenericClient, id: Uuid) -> Result<bool, Error> {
+    let statement = client
+        .prepare("SELECT EXISTS (SELECT 1 FROM users WHERE id = $1 AND deleted_at IS NULL)")
+        .await?;
+    Ok(client.query_one(&statement, &[&id]).await?.get(0))
+}
+
+#[instrument(name = "dal.users.get_user_by_email", skip_all)]
+pub async fn get_user_by_email(client: &impl GenericClient, email: &str) -> Result<Option<User>, Error> {
     let statement = client
         .prepare(
-            "SELECT id, name, email, password, created_at, updated_at
-             FROM users WHERE email = $1",
+            "SELECT id, name, email, role, kyc_tier, kyc_status, password, created_at, updated_at
+             FROM users WHERE email = $1 AND deleted_at IS NULL",
         )
         .await?;
 
@@ -47,8 +68,26 @@ pub async fn get_user_by_email(client: &Client, email: &str) -> Result<Option<Us
         .map(|row| row.try_into().unwrap()))
 }
 
+// Locks the user until the surrounding transaction ends.
+#[instrument(name = "dal.users.get_user_for_update", skip_all, fields(id = %id))]
+pub async fn get_user_for_update(client: &impl GenericClient, id: Uuid) -> Result<Option<User>, Error> {
+    let statement = client
+        .prepare(
+            "SELECT id, name, email, role, kyc_tier, kyc_status, created_at, updated_at
+             FROM users WHERE id = $1 AND deleted_at IS NULL
+             FOR UPDATE",
+        )
+        .await?;
+
+    Ok(client
+        .query_opt(&statement, &[&id])
+        .await?
+        .map(|row| row.try_into().unwrap()))
+}
+
+#[instrument(name = "dal.users.update_user", skip_all, fields(id = %id))]
 pub async fn update_user(
-    client: &Client,
+    client: &impl GenericClient,
     id: Uuid,
     user: &UpdateUserRequest,
 ) -> Result<Option<User>, Error> {
@@ -59,8 +98,8 @@ pub async fn update_user(
                  email = COALESCE($2, email), 
                  password = COALESCE($3, password),
                  updated_at = NOW()
-             WHERE id = $4
-             RETURNING id, name, email, created_at, updated_at",
+             WHERE id = $4 AND deleted_at IS NULL
+             RETURNING id, name, email, role, kyc_tier, kyc_status, created_at, updated_at",
         )
         .await?;
 
@@ -73,28 +112,143 @@ pub async fn update_user(
         .map(|row| row.try_into().unwrap()))
 }
 
-pub async fn delete_user(client: &Client, id: Uuid) -> Result<bool, Error> {
+// Replaces the user's PII with placeholders and marks the row deleted. The
+// email placeholder is unique per user and can't receive mail.
+#[instrument(name = "dal.users.erase_user", skip_all, fields(id = %id))]
+pub async fn erase_user(client: &impl GenericClient, id: Uuid) -> Result<Option<User>, Error> {
+    let statement = client
+        .prepare(
+            "UPDATE users
+             SET name = 'Deleted user',
+                 email = 'deleted-' || id || '@erased.invalid',
+                 password = '',
+                 role = 'USER',
+                 deleted_at = NOW(),
+                 updated_at = NOW()
+             WHERE id = $1 AND deleted_at IS NULL
+             RETURNING id, name, email, role, kyc_tier, kyc_status, created_at, updated_at",
+        )
+        .await?;
+
+    Ok(client
+        .query_opt(&statement, &[&id])
+        .await?
+        .map(|row| row.try_into().unwrap()))
+}
+
+#[instrument(name = "dal.users.list_users", skip_all)]
+pub async fn list_users(client: &Client, page: &PageRequest) -> Result<Vec<User>, Error> {
+    let mut query = QueryBuilder::new(
+        "SELECT id, name, email, role, kyc_tier, kyc_status, created_at, updated_at
+         FROM users
+         WHERE deleted_at IS NULL",
+    );
+    query.paginate(page, "created_at", "id");
+
+    let statement = client.prepare(query.sql()).await?;
+    let rows = client.query(&statement, &query.params()).await?;
+    Ok(rows.into_iter().map(|row| row.try_into().unwrap()).collect())
+}
+
+#[instrument(name = "dal.users.count_users", skip_all)]
+pub async fn count_users(client: &Client) -> Result<i64, Error> {
+    let statement = client.prepare("SELECT COUNT(*) FROM users WHERE deleted_at IS NULL").await?;
+    Ok(client.query_one(&statement, &[]).await?.get(0))
+}
+
+// Ids and names of every user that isn't erased, for screening them all.
+#[instrument(name = "dal.users.list_user_names", skip_all)]
+pub async fn list_user_names(client: &impl GenericClient) -> Result<Vec<(Uuid, String)>, Error> {
     let statement = client
-        .prepare("DELETE FROM users WHERE id = $1")
+        .prepare("SELECT id, name FROM users WHERE deleted_at IS NULL ORDER BY created_at, id")
         .await?;
 
-    Ok(client.execute(&statement, &[&id]).await? > 0)
+    let rows = client.query(&statement, &[]).await?;
+    Ok(rows.into_iter().map(|row| (row.get("id"), row.get("name"))).collect())
 }
 
-pub async fn list_users(
+#[instrument(name = "dal.users.set_user_role", skip_all)]
+pub async fn set_user_role(client: &impl GenericClient, email: &str, role: Role) -> Result<Option<User>, Error> {
+    let statement = client
+        .prepare(
+            "UPDATE users
+             SET role = $1,
+                 updated_at = NOW()
+             WHERE email = $2 AND deleted_at IS NULL
+             RETURNING id, name, email, role, kyc_tier, kyc_status, created_at, updated_at",
+        )
+        .await?;
+
+    Ok(client
+        .query_opt(&statement, &[&role.to_string(), &email])
+        .await?
+        .map(|row| row.try_into().unwrap()))
+}
+
+#[instrument(name = "dal.users.set_kyc", skip_all, fields(id = %id))]
+pub async fn set_kyc(
+    client: &impl GenericClient,
+    id: Uuid,
+    tier: KycTier,
+    status: KycStatus,
+) -> Result<Option<User>, Error> {
+    let statement = client
+        .prepare(
+            "UPDATE users
+             SET kyc_tier = $2,
+                 kyc_status = $3,
+                 updated_at = NOW()
+             WHERE id = $1 AND deleted_at IS NULL
+             RETURNING id, name, email, role, kyc_tier, kyc_status, created_at, updated_at",
+        )
+        .await?;
+
+    Ok(client
+        .query_opt(&statement, &[&id, &tier.to_string(), &status.to_string()])
+        .await?
+        .map(|row| row.try_into().unwrap()))
+}
+
+#[instrument(name = "dal.users.record_login_event", skip_all, fields(user_id = %user_id))]
+pub async fn record_login_event(
     client: &Client,
-    offset: i64,
-    limit: i64,
-) -> Result<Vec<User>, Error> {
+    user_id: Uuid,
+    succeeded: bool,
+    ip_address: Option<&str>,
+    user_agent: Option<&str>,
+) -> Result<(), Error> {
     let statement = client
         .prepare(
-            "SELECT id, name, email, created_at, updated_at
-             FROM users 
-             ORDER BY created_at DESC 
-             LIMIT $1 OFFSET $2",
+            "INSERT INTO login_events (user_id, succeeded, ip_address, user_agent)
+             VALUES ($1, $2, $3, $4)",
         )
         .await?;
 
-    let rows = client.query(&statement, &[&limit, &offset]).await?;
+    client
+        .execute(&statement, &[&user_id, &succeeded, &ip_address, &user_agent])
+        .await?;
+    Ok(())
+}
+
+#[instrument(name = "dal.users.list_login_events", skip_all, fields(user_id = %user_id))]
+pub async fn list_login_events(client: &impl GenericClient, user_id: Uuid) -> Result<Vec<LoginEvent>, Error> {
+    let statement = client
+        .prepare(
+            "SELECT id, user_id, succeeded, ip_address, user_agent, created_at
+             FROM login_events
+             WHERE user_id = $1
+             ORDER BY created_at, id",
+        )
+        .await?;
+
+    let rows = client.query(&statement, &[&user_id]).await?;
     Ok(rows.into_iter().map(|row| row.try_into().unwrap()).collect())
-} 
\ No newline at end of file
+}
+
+#[instrument(name = "dal.users.delete_login_events", skip_all, fields(user_id = %user_id))]
+pub async fn delete_login_events(client: &impl GenericClient, user_id: Uuid) -> Result<u64, Error> {
+    let statement = client
+        .prepare("DELETE FROM login_events WHERE user_id = $1")
+        .await?;
+    client.execute(&statement, &[&user_id]).await
+}
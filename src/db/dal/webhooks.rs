@@ -0,0 +1,328 @@
+use crate::base::{
+    models::webhooks::{
+        ClaimedDelivery, CreateWebhookEndpointRequest, DeliveryStatus, UpdateWebhookEndpointRequest, WebhookDelivery,
+        WebhookEndpoint,
+    },
+    pagination::PageRequest,
+};
+use crate::db::query::QueryBuilder;
+use chrono::{DateTime, Utc};
+use deadpool_postgres::{Client, GenericClient};
+use serde_json::Value;
+use tokio_postgres::Error;
+use tracing::instrument;
+use uuid::Uuid;
+
+const ENDPOINT_COLUMNS: &str = "id, user_id, url, event_types, description, active, created_at, updated_at";
+
+const DELIVERY_COLUMNS: &str =
+    "d.id, d.endpoint_id, d.event_id, e.event_type, e.payload, d.status, d.attempts, d.next_attempt_at,
+     d.last_attempt_at, d.response_status, d.last_error, d.delivered_at, d.created_at";
+
+#[instrument(name = "dal.webhooks.create_endpoint", skip_all, fields(user_id = %user_id))]
+pub async fn create_endpoint(
+    client: &impl GenericClient,
+    user_id: Uuid,
+    endpoint: &CreateWebhookEndpointRequest,
+    event_types: &[String],
+    secret: &str,
+) -> Result<WebhookEndpoint, Error> {
+    let statement = client
+        .prepare(&format!(
+            "INSERT INTO webhook_endpoints (user_id, url, event_types, description, secret)
+             VALUES ($1, $2, $3, $4, $5)
+             RETURNING {}",
+            ENDPOINT_COLUMNS
+        ))
+        .await?;
+
+    client
+        .query_one(&statement, &[&user_id, &endpoint.url, &event_types, &endpoint.description, &secret])
+        .await?
+        .try_into()
+}
+
+#[instrument(name = "dal.webhooks.get_endpoint_by_id", skip_all, fields(id = %id))]
+pub async fn get_endpoint_by_id(client: &Client, id: Uuid) -> Result<Option<WebhookEndpoint>, Error> {
+    let statement = client
+        .prepare(&format!("SELECT {} FROM webhook_endpoints WHERE id = $1", ENDPOINT_COLUMNS))
+        .await?;
+
+    Ok(client
+        .query_opt(&statement, &[&id])
+        .await?
+        .map(|row| row.try_into().unwrap()))
+}
+
+// Locks the endpoint row until the surrounding transaction ends.
+#[instrument(name = "dal.webhooks.get_endpoint_for_update", skip_all, fields(id = %id))]
+pub async fn get_endpoint_for_update(client: &impl GenericClient, id: Uuid) -> Result<Option<WebhookEndpoint>, Error> {
+    let statement = client
+        .prepare(&format!(
+            "SELECT {} FROM webhook_endpoints WHERE id = $1 FOR UPDATE",
+            ENDPOINT_COLUMNS
+        ))
+        .await?;
+
+    Ok(client
+        .query_opt(&statement, &[&id])
+        .await?
+        .map(|row| row.try_into().unwrap()))
+}
+
+#[instrument(name = "dal.webhooks.list_endpoints_by_user_id", skip_all, fields(user_id = %user_id))]
+pub async fn list_endpoints_by_user_id(client: &Client, user_id: Uuid) -> Result<Vec<WebhookEndpoint>, Error> {
+    let statement = client
+        .prepare(&format!(
+            "SELECT {} FROM webhook_endpoints WHERE user_id = $1 ORDER BY created_at, id",
+            ENDPOINT_COLUMNS
+        ))
+        .await?;
+
+    let rows = client.query(&statement, &[&user_id]).await?;
+    Ok(rows.into_iter().map(|row| row.try_into().unwrap()).collect())
+}
+
+#[instrument(name = "dal.webhooks.update_endpoint", skip_all, fields(id = %id))]
+pub async fn update_endpoint(
+    client: &impl GenericClient,
+    id: Uuid,
+    endpoint: &UpdateWebhookEndpointRequest,
+    event_types: Option<&[String]>,
+) -> Result<Option<WebhookEndpoint>, Error> {
+    let statement = client
+        .prepare(&format!(
+            "UPDATE webhook_endpoints
+             SET url = COALESCE($1, url),
+                 event_types = COALESCE($2, event_types),
+                 description = COALESCE($3, description),
+                 active = COALESCE($4, active),
+                 updated_at = NOW()
+             WHERE id = $5
+             RETURNING {}",
+            ENDPOINT_COLUMNS
+        ))
+        .await?;
+
+    Ok(client
+        .query_opt(
+            &statement,
+            &[&endpoint.url, &event_types, &endpoint.description, &endpoint.active, &id],
+        )
+        .await?
+        .map(|row| row.try_into().unwrap()))
+}
+
+// Deliveries of the endpoint are deleted with it.
+#[instrument(name = "dal.webhooks.delete_endpoint", skip_all, fields(id = %id))]
+pub async fn delete_endpoint(client: &impl GenericClient, id: Uuid) -> Result<u64, Error> {
+    let statement = client
+        .prepare("DELETE FROM webhook_endpoints WHERE id = $1")
+        .await?;
+    client.execute(&statement, &[&id]).await
+}
+
+// Writes an event to the outbox with a delivery for every active endpoint of
+// the user subscribed to it. Nothing is written when there is none. Returns the
+// number of deliveries queued.
+#[instrument(name = "dal.webhooks.create_event", skip_all, fields(user_id = %user_id, event_type = event_type))]
+pub async fn create_event(
+    client: &impl GenericClient,
+    user_id: Uuid,
+    event_type: &str,
+    payload: &Value,
+) -> Result<u64, Error> {
+    let statement = client
+        .prepare(
+            "WITH endpoints AS (
+                 SELECT id FROM webhook_endpoints
+                 WHERE user_id = $1 AND active AND $2::TEXT = ANY(event_types)
+             ), event AS (
+                 INSERT INTO webhook_events (user_id, event_type, payload)
+                 SELECT $1, $2::TEXT, $3 WHERE EXISTS (SELECT 1 FROM endpoints)
+                 RETURNING id
+             )
+             INSERT INTO webhook_deliveries (event_id, endpoint_id)
+             SELECT event.id, endpoints.id FROM event, endpoints",
+        )
+        .await?;
+
+    client.execute(&statement, &[&user_id, &event_type, &payload]).await
+}
+
+// Claims up to `limit` due deliveries of active endpoints by moving their next
+// attempt to `lease_until`, so a worker that dies mid-attempt only delays them.
+#[instrument(name = "dal.webhooks.claim_due_deliveries", skip_all)]
+pub async fn claim_due_deliveries(
+    client: &Client,
+    lease_until: DateTime<Utc>,
+    limit: i64,
+) -> Result<Vec<ClaimedDelivery>, Error> {
+    let statement = client
+        .prepare(
+            "WITH claimed AS (
+                 UPDATE webhook_deliveries
+                 SET attempts = attempts + 1,
+                     last_attempt_at = NOW(),
+                     next_attempt_at = $1
+                 WHERE id IN (
+                     SELECT d.id FROM webhook_deliveries d
+                     JOIN webhook_endpoints w ON w.id = d.endpoint_id
+                     WHERE d.status = 'PENDING' AND d.next_attempt_at <= NOW() AND w.active
+                     ORDER BY d.next_attempt_at
+                     LIMIT $2
+                     FOR UPDATE OF d SKIP LOCKED
+                 )
+                 RETURNING id, event_id, endpoint_id, attempts
+             )
+             SELECT claimed.id, claimed.attempts, e.id AS event_id, e.event_type, e.payload,
+                    e.created_at AS event_created_at, w.url, w.secret
+             FROM claimed
+             JOIN webhook_events e ON e.id = claimed.event_id
+             JOIN webhook_endpoints w ON w.id = claimed.endpoint_id",
+        )
+        .await?;
+
+    let rows = client.query(&statement, &[&lease_until, &limit]).await?;
+    Ok(rows.into_iter().map(|row| row.try_into().unwrap()).collect())
+}
+
+#[instrument(name = "dal.webhooks.mark_delivered", skip_all, fields(id = %id))]
+pub async fn mark_delivered(client: &Client, id: Uuid, response_status: i32) -> Result<(), Error> {
+    let statement = client
+        .prepare(
+            "UPDATE webhook_deliveries
+             SET status = 'DELIVERED', response_status = $1, last_error = NULL, delivered_at = NOW()
+             WHERE id = $2",
+        )
+        .await?;
+
+    client.execute(&statement, &[&response_status, &id]).await?;
+    Ok(())
+}
+
+// Records a failed attempt. Without `retry_at` the delivery is dead.
+#[instrument(name = "dal.webhooks.record_failure", skip_all, fields(id = %id))]
+pub async fn record_failure(
+    client: &Client,
+    id: Uuid,
+    response_status: Option<i32>,
+    error: &str,
+    retry_at: Option<DateTime<Utc>>,
+) -> Result<(), Error> {
+    let statement = client
+        .prepare(
+            "UPDATE webhook_deliveries
+             SET status = CASE WHEN $4::TIMESTAMPTZ IS NULL THEN 'DEAD' ELSE 'PENDING' END,
+                 response_status = $1,
+                 last_error = $2,
+                 next_attempt_at = COALESCE($4::TIMESTAMPTZ, next_attempt_at)
+             WHERE id = $3",
+        )
+        .await?;
+
+    client.execute(&statement, &[&response_status, &error, &id, &retry_at]).await?;
+    Ok(())
+}
+
+#[instrument(name = "dal.webhooks.list_deliveries", skip_all, fields(endpoint_id = %endpoint_id))]
+pub async fn list_deliveries(
+    client: &Client,
+    endpoint_id: Uuid,
+    status: Option<DeliveryStatus>,
+    page: &PageRequest,
+) -> Result<Vec<WebhookDelivery>, Error> {
+    let mut query = QueryBuilder::new(&format!(
+        "SELECT {} FROM webhook_deliveries d
+         JOIN webhook_events e ON e.id = d.event_id
+         WHERE TRUE",
+        DELIVERY_COLUMNS
+    ));
+    push_delivery_filters(&mut query, endpoint_id, status);
+    query.paginate(page, "d.created_at", "d.id");
+
+    let statement = client.prepare(query.sql()).await?;
+    let rows = client.query(&statement, &query.params()).await?;
+    Ok(rows.into_iter().map(|row| row.try_into().unwrap()).collect())
+}
+
+#[instrument(name = "dal.webhooks.count_deliveries", skip_all, fields(endpoint_id = %endpoint_id))]
+pub async fn count_deliveries(client: &Client, endpoint_id: Uuid, status: Option<DeliveryStatus>) -> Result<i64, Error> {
+    let mut query = QueryBuilder::new("SELECT COUNT(*) FROM webhook_deliveries d WHERE TRUE");
+    push_delivery_filters(&mut query, endpoint_id, status);
+
+    let statement = client.prepare(query.sql()).await?;
+    Ok(client.query_one(&statement, &query.params()).await?.get(0))
+}
+
+#[instrument(name = "dal.webhooks.get_delivery", skip_all, fields(id = %id))]
+pub async fn get_delivery(client: &impl GenericClient, endpoint_id: Uuid, id: Uuid) -> Result<Option<WebhookDelivery>, Error> {
+    let statement = client
+        .prepare(&format!(
+            "SELECT {} FROM webhook_deliveries d
+             JOIN webhook_events e ON e.id = d.event_id
+             WHERE d.id = $1 AND d.endpoint_id = $2",
+            DELIVERY_COLUMNS
+        ))
+        .await?;
+
+    Ok(client
+        .query_opt(&statement, &[&id, &endpoint_id])
+        .await?
+        .map(|row| row.try_into().unwrap()))
+}
+
+// Queues a finished delivery again with a fresh set of attempts.
+#[instrument(name = "dal.webhooks.replay_delivery", skip_all, fields(id = %id))]
+pub async fn replay_delivery(client: &impl GenericClient, endpoint_id: Uuid, id: Uuid) -> Result<Option<WebhookDelivery>, Error> {
+    let statement = client
+        .prepare(&format!(
+            "WITH d AS (
+                 UPDATE webhook_deliveries
+                 SET status = 'PENDING', attempts = 0, next_attempt_at = NOW(), delivered_at = NULL
+                 WHERE id = $1 AND endpoint_id = $2 AND status <> 'PENDING'
+                 RETURNING *
+             )
+             SELECT {} FROM d JOIN webhook_events e ON e.id = d.event_id",
+            DELIVERY_COLUMNS
+        ))
+        .await?;
+
+    Ok(client
+        .query_opt(&statement, &[&id, &endpoint_id])
+        .await?
+        .map(|row| row.try_into().unwrap()))
+}
+
+#[instrument(name = "dal.webhooks.replay_dead_deliveries", skip_all, fields(endpoint_id = %endpoint_id))]
+pub async fn replay_dead_deliveries(client: &impl GenericClient, endpoint_id: Uuid) -> Result<u64, Error> {
+    let statement = client
+        .prepare(
+            "UPDATE webhook_deliveries
+             SET status = 'PENDING', attempts = 0, next_attempt_at = NOW()
+             WHERE endpoint_id = $1 AND status = 'DEAD'",
+        )
+        .await?;
+    client.execute(&statement, &[&endpoint_id]).await
+}
+
+// Endpoints, events and their deliveries of the user.
+#[instrument(name = "dal.webhooks.delete_webhooks_by_user_id", skip_all, fields(user_id = %user_id))]
+pub async fn delete_webhooks_by_user_id(client: &impl GenericClient, user_id: Uuid) -> Result<u64, Error> {
+    let endpoints = client
+        .prepare("DELETE FROM webhook_endpoints WHERE user_id = $1")
+        .await?;
+    let events = client
+        .prepare("DELETE FROM webhook_events WHERE user_id = $1")
+        .await?;
+
+    Ok(client.execute(&endpoints, &[&user_id]).await? + client.execute(&events, &[&user_id]).await?)
+}
+
+fn push_delivery_filters(query: &mut QueryBuilder, endpoint_id: Uuid, status: Option<DeliveryStatus>) {
+    query.push_bind(" AND d.endpoint_id = {}", endpoint_id);
+
+    if let Some(status) = status {
+        query.push_bind(" AND d.status = {}", status.to_string());
+    }
+}
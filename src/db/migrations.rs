@@ -0,0 +1,82 @@
+use deadpool_postgres::Client;
+use tokio_postgres::Error;
+
+// Ordered list of schema migrations. `ddl.sql` is the baseline schema; every
+// later change gets its own numbered file under `migrations/`.
+const MIGRATIONS: &[(i32, &str, &str)] = &[
+    (1, "initial_schema", include_str!("ddl.sql")),
+    (2, "transaction_search", include_str!("migrations/0002_transaction_search.sql")),
+    (3, "user_roles", include_str!("migrations/0003_user_roles.sql")),
+    (4, "reconciliation", include_str!("migrations/0004_reconciliation.sql")),
+    (5, "balance_snapshots", include_str!("migrations/0005_balance_snapshots.sql")),
+    (6, "account_status", include_str!("migrations/0006_account_status.sql")),
+    (7, "user_erasure", include_str!("migrations/0007_user_erasure.sql")),
+    (8, "data_exports", include_str!("migrations/0008_data_exports.sql")),
+    (9, "audit_chain", include_str!("migrations/0009_audit_chain.sql")),
+    (10, "webhooks", include_str!("migrations/0010_webhooks.sql")),
+    (11, "account_events", include_str!("migrations/0011_account_events.sql")),
+    (12, "scheduled_payments", include_str!("migrations/0012_scheduled_payments.sql")),
+    (13, "fee_schedules", include_str!("migrations/0013_fee_schedules.sql")),
+    (14, "transaction_limits", include_str!("migrations/0014_transaction_limits.sql")),
+    (15, "risk_engine", include_str!("migrations/0015_risk_engine.sql")),
+    (16, "sanctions_screening", include_str!("migrations/0016_sanctions_screening.sql")),
+    (17, "kyc", include_str!("migrations/0017_kyc.sql")),
+    (18, "aml_monitoring", include_str!("migrations/0018_aml_monitoring.sql")),
+    (19, "approvals", include_str!("migrations/0019_approvals.sql")),
+    (20, "watchlist_version", include_str!("migrations/0020_watchlist_version.sql")),
+    (21, "transaction_booked", include_str!("migrations/0021_transaction_booked.sql")),
+    (22, "schedule_last_error", include_str!("migrations/0022_schedule_last_error.sql")),
+];
+
+// Arbitrary key so that only one API replica applies migrations at a time.
+const MIGRATION_LOCK_KEY: i64 = 7_340_021;
+
+pub fn latest_version() -> i32 {
+    MIGRATIONS.last().map(|(version, _, _)| *version).unwrap_or(0)
+}
+
+pub async fn run(client: &mut Client) -> Result<(), Error> {
+    client
+        .batch_execute(
+            "CREATE TABLE IF NOT EXISTS schema_migrations (
+                version INTEGER PRIMARY KEY,
+                name VARCHAR(255) NOT NULL,
+                applied_at TIMESTAMPTZ DEFAULT NOW() NOT NULL
+            )",
+        )
+        .await?;
+
+    client.execute("SELECT pg_advisory_lock($1)", &[&MIGRATION_LOCK_KEY]).await?;
+    let result = apply_pending(client).await;
+    client.execute("SELECT pg_advisory_unlock($1)", &[&MIGRATION_LOCK_KEY]).await?;
+
+    result
+}
+
+async fn apply_pending(client: &mut Client) -> Result<(), Error> {
+    let current = current_version(client).await?;
+
+    for (version, name, sql) in MIGRATIONS.iter().filter(|(version, _, _)| *version > current) {
+        let transaction = client.transaction().await?;
+        transaction.batch_execute(sql).await?;
+        transaction
+            .execute(
+                "INSERT INTO schema_migrations (version, name) VALUES ($1, $2)",
+                &[version, name],
+            )
+            .await?;
+        transaction.commit().await?;
+
+        tracing::info!("Applied migration {} ({})", version, name);
+    }
+
+    Ok(())
+}
+
+pub async fn current_version(client: &Client) -> Result<i32, Error> {
+    let row = client
+        .query_one("SELECT COALESCE(MAX(version), 0) AS version FROM schema_migrations", &[])
+        .await?;
+
+    Ok(row.get("version"))
+}
@@ -0,0 +1,36 @@
+CREATE TABLE IF NOT EXISTS statement_imports (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    format VARCHAR(10) NOT NULL CHECK (format IN ('MT940', 'CAMT053')),
+    filename TEXT,
+    -- The same file can only be imported once
+    file_sha256 CHAR(64) NOT NULL UNIQUE,
+    statement_reference TEXT,
+    account_reference TEXT,
+    line_count INTEGER NOT NULL,
+    matched_count INTEGER NOT NULL,
+    exception_count INTEGER NOT NULL,
+    imported_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ DEFAULT NOW() NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS statement_lines (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    import_id UUID NOT NULL REFERENCES statement_imports(id) ON DELETE CASCADE,
+    line_number INTEGER NOT NULL,
+    booking_date DATE NOT NULL,
+    -- Signed from the account's point of view: credits positive, debits negative
+    amount DECIMAL(12, 2) NOT NULL,
+    currency VARCHAR(3),
+    reference TEXT,
+    description TEXT,
+    status VARCHAR(10) NOT NULL CHECK (status IN ('MATCHED', 'UNMATCHED', 'MISMATCHED')),
+    transaction_id UUID REFERENCES transactions(id) ON DELETE SET NULL,
+    reason TEXT,
+    created_at TIMESTAMPTZ DEFAULT NOW() NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_statement_lines_import_id ON statement_lines (import_id, line_number);
+-- A transaction is settled by at most one statement line
+CREATE UNIQUE INDEX IF NOT EXISTS idx_statement_lines_matched_transaction
+    ON statement_lines (transaction_id) WHERE status = 'MATCHED';
+CREATE INDEX IF NOT EXISTS idx_statement_imports_created_at ON statement_imports (created_at, id);
@@ -0,0 +1,14 @@
+-- End-of-day balance of every account, taken once the UTC day is over.
+-- `as_of` is the first instant after that day.
+CREATE TABLE IF NOT EXISTS balance_snapshots (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    account_id UUID NOT NULL,
+    snapshot_date DATE NOT NULL,
+    as_of TIMESTAMPTZ NOT NULL,
+    balance DECIMAL(12, 2) NOT NULL,
+    created_at TIMESTAMPTZ DEFAULT NOW() NOT NULL,
+    FOREIGN KEY (account_id) REFERENCES accounts(id) ON DELETE CASCADE,
+    UNIQUE (account_id, snapshot_date)
+);
+
+CREATE INDEX IF NOT EXISTS idx_balance_snapshots_account_as_of ON balance_snapshots(account_id, as_of DESC);
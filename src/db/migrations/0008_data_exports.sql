@@ -0,0 +1,45 @@
+-- Every login attempt against an existing user, kept as their login history.
+CREATE TABLE IF NOT EXISTS login_events (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    succeeded BOOLEAN NOT NULL,
+    ip_address VARCHAR(45),
+    user_agent TEXT,
+    created_at TIMESTAMPTZ DEFAULT NOW() NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_login_events_user_created_at ON login_events(user_id, created_at);
+
+-- Consent decisions are appended, never updated, so the history of every
+-- purpose is preserved; the latest row per purpose is the current state.
+CREATE TABLE IF NOT EXISTS consents (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    purpose VARCHAR(30) NOT NULL CHECK (purpose IN ('MARKETING', 'ANALYTICS', 'THIRD_PARTY_SHARING')),
+    granted BOOLEAN NOT NULL,
+    ip_address VARCHAR(45),
+    created_at TIMESTAMPTZ DEFAULT NOW() NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_consents_user_purpose ON consents(user_id, purpose, created_at DESC);
+
+-- Personal data exports, built by a background job. The archive is dropped
+-- once `expires_at` has passed.
+CREATE TABLE IF NOT EXISTS data_exports (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    status VARCHAR(10) NOT NULL DEFAULT 'PENDING'
+        CHECK (status IN ('PENDING', 'RUNNING', 'READY', 'FAILED', 'EXPIRED')),
+    archive BYTEA,
+    size_bytes BIGINT,
+    error TEXT,
+    started_at TIMESTAMPTZ,
+    completed_at TIMESTAMPTZ,
+    expires_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ DEFAULT NOW() NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_data_exports_user_created_at ON data_exports(user_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_data_exports_queue ON data_exports(created_at) WHERE status IN ('PENDING', 'RUNNING');
+-- At most one export per user is queued or being built at a time
+CREATE UNIQUE INDEX IF NOT EXISTS idx_data_exports_active_user ON data_exports(user_id) WHERE status IN ('PENDING', 'RUNNING');
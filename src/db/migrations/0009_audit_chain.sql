@@ -0,0 +1,98 @@
+-- Makes `audit_log` append-only and hash-chained: every entry stores the hash
+-- of the entry before it, so editing, deleting or reordering history breaks
+-- the chain. `seq`, `prev_hash` and `hash` are set by the insert trigger under
+-- a lock, whoever writes the entry. The verifier (`verify-audit-log`)
+-- recomputes the hashes independently; `audit_log_hash` and
+-- `base::audit::entry_hash` must agree.
+ALTER TABLE audit_log
+    ADD COLUMN IF NOT EXISTS seq BIGINT,
+    ADD COLUMN IF NOT EXISTS before JSONB,
+    ADD COLUMN IF NOT EXISTS after JSONB,
+    ADD COLUMN IF NOT EXISTS request_id VARCHAR(128),
+    ADD COLUMN IF NOT EXISTS ip_address VARCHAR(45),
+    ADD COLUMN IF NOT EXISTS prev_hash CHAR(64),
+    ADD COLUMN IF NOT EXISTS hash CHAR(64);
+
+-- Length-prefixed so no two different entries produce the same input.
+CREATE OR REPLACE FUNCTION audit_log_field(value TEXT) RETURNS TEXT AS $$
+    SELECT CASE WHEN value IS NULL THEN '~' ELSE octet_length(value) || ':' || value END
+$$ LANGUAGE SQL IMMUTABLE;
+
+CREATE OR REPLACE FUNCTION audit_log_hash(entry audit_log) RETURNS CHAR(64) AS $$
+    SELECT encode(sha256(convert_to(
+        audit_log_field(entry.seq::TEXT)
+        || audit_log_field(entry.prev_hash::TEXT)
+        || audit_log_field(entry.id::TEXT)
+        || audit_log_field(to_char(entry.created_at AT TIME ZONE 'UTC', 'YYYY-MM-DD"T"HH24:MI:SS.US"Z"'))
+        || audit_log_field(entry.actor_id::TEXT)
+        || audit_log_field(entry.action::TEXT)
+        || audit_log_field(entry.target_type::TEXT)
+        || audit_log_field(entry.target_id::TEXT)
+        || audit_log_field(entry.before::TEXT)
+        || audit_log_field(entry.after::TEXT)
+        || audit_log_field(entry.details::TEXT)
+        || audit_log_field(entry.request_id::TEXT)
+        || audit_log_field(entry.ip_address::TEXT),
+        'UTF8'
+    )), 'hex')
+$$ LANGUAGE SQL STABLE;
+
+-- Chains the entries written before the log was hash-chained, oldest first.
+DO $$
+DECLARE
+    entry audit_log;
+    previous_hash CHAR(64) := repeat('0', 64);
+    next_seq BIGINT := 0;
+BEGIN
+    FOR entry IN SELECT * FROM audit_log WHERE seq IS NULL ORDER BY created_at, id LOOP
+        next_seq := next_seq + 1;
+        entry.seq := next_seq;
+        entry.prev_hash := previous_hash;
+        entry.hash := audit_log_hash(entry);
+        UPDATE audit_log SET seq = entry.seq, prev_hash = entry.prev_hash, hash = entry.hash WHERE id = entry.id;
+        previous_hash := entry.hash;
+    END LOOP;
+END $$;
+
+ALTER TABLE audit_log
+    ALTER COLUMN seq SET NOT NULL,
+    ALTER COLUMN prev_hash SET NOT NULL,
+    ALTER COLUMN hash SET NOT NULL;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_audit_log_seq ON audit_log(seq);
+CREATE INDEX IF NOT EXISTS idx_audit_log_created_at ON audit_log(created_at, id);
+CREATE INDEX IF NOT EXISTS idx_audit_log_actor ON audit_log(actor_id, created_at);
+
+-- The advisory lock is held until the writing transaction ends, so the next
+-- writer always sees the entry it chains onto.
+CREATE OR REPLACE FUNCTION audit_log_chain() RETURNS TRIGGER AS $$
+DECLARE
+    head audit_log;
+BEGIN
+    PERFORM pg_advisory_xact_lock(7340022);
+    SELECT * INTO head FROM audit_log ORDER BY seq DESC LIMIT 1;
+
+    NEW.seq := COALESCE(head.seq, 0) + 1;
+    NEW.prev_hash := COALESCE(head.hash, repeat('0', 64));
+    NEW.hash := audit_log_hash(NEW);
+    RETURN NEW;
+END
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION audit_log_append_only() RETURNS TRIGGER AS $$
+BEGIN
+    RAISE EXCEPTION 'audit_log is append-only';
+END
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS audit_log_chain ON audit_log;
+CREATE TRIGGER audit_log_chain BEFORE INSERT ON audit_log
+    FOR EACH ROW EXECUTE FUNCTION audit_log_chain();
+
+DROP TRIGGER IF EXISTS audit_log_append_only ON audit_log;
+CREATE TRIGGER audit_log_append_only BEFORE UPDATE OR DELETE ON audit_log
+    FOR EACH ROW EXECUTE FUNCTION audit_log_append_only();
+
+DROP TRIGGER IF EXISTS audit_log_no_truncate ON audit_log;
+CREATE TRIGGER audit_log_no_truncate BEFORE TRUNCATE ON audit_log
+    FOR EACH STATEMENT EXECUTE FUNCTION audit_log_append_only();
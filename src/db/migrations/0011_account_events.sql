@@ -0,0 +1,27 @@
+-- Changes to an account, streamed live to its owner. An event is written in
+-- the same database transaction as the change, and the trigger notifies every
+-- API replica when that transaction commits. The ids order the events of an
+-- account and are what clients resume from.
+CREATE TABLE IF NOT EXISTS account_events (
+    id BIGSERIAL PRIMARY KEY,
+    account_id UUID NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+    event_type VARCHAR(50) NOT NULL,
+    payload JSONB NOT NULL,
+    created_at TIMESTAMPTZ DEFAULT NOW() NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_account_events_account_id ON account_events(account_id, id);
+CREATE INDEX IF NOT EXISTS idx_account_events_created_at ON account_events(created_at);
+
+-- The payload is only the event id; listeners read the event itself, since a
+-- notification is limited to 8000 bytes.
+CREATE OR REPLACE FUNCTION account_events_notify() RETURNS TRIGGER AS $$
+BEGIN
+    PERFORM pg_notify('account_events', NEW.id::TEXT);
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS account_events_notify ON account_events;
+CREATE TRIGGER account_events_notify AFTER INSERT ON account_events
+    FOR EACH ROW EXECUTE FUNCTION account_events_notify();
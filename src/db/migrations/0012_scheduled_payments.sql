@@ -0,0 +1,43 @@
+-- Standing orders: transfers between two accounts of the same user, run once
+-- at `start_at` or recurring by a cron expression or RRULE. `next_run_at` is
+-- when the scheduler runs it next; NULL once it is no longer active.
+CREATE TABLE IF NOT EXISTS scheduled_payments (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    source_account_id UUID NOT NULL REFERENCES accounts(id),
+    destination_account_id UUID NOT NULL REFERENCES accounts(id),
+    amount DECIMAL(15,2) NOT NULL CHECK (amount > 0),
+    description VARCHAR(500),
+    cron VARCHAR(200),
+    rrule VARCHAR(500),
+    timezone VARCHAR(64) NOT NULL DEFAULT 'UTC',
+    start_at TIMESTAMPTZ NOT NULL,
+    end_at TIMESTAMPTZ,
+    max_runs INT CHECK (max_runs > 0),
+    status VARCHAR(10) NOT NULL DEFAULT 'ACTIVE'
+        CHECK (status IN ('ACTIVE', 'PAUSED', 'CANCELLED', 'COMPLETED')),
+    next_run_at TIMESTAMPTZ,
+    runs_count INT NOT NULL DEFAULT 0,
+    last_run_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ DEFAULT NOW() NOT NULL,
+    updated_at TIMESTAMPTZ DEFAULT NOW() NOT NULL,
+    CHECK (cron IS NULL OR rrule IS NULL)
+);
+
+CREATE INDEX IF NOT EXISTS idx_scheduled_payments_user_id ON scheduled_payments(user_id, created_at);
+CREATE INDEX IF NOT EXISTS idx_scheduled_payments_due ON scheduled_payments(next_run_at) WHERE status = 'ACTIVE';
+
+-- One row per occurrence that was run. The unique key guarantees that an
+-- occurrence is executed at most once, however many schedulers are running.
+CREATE TABLE IF NOT EXISTS scheduled_payment_runs (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    schedule_id UUID NOT NULL REFERENCES scheduled_payments(id) ON DELETE CASCADE,
+    scheduled_for TIMESTAMPTZ NOT NULL,
+    status VARCHAR(10) NOT NULL CHECK (status IN ('SUCCEEDED', 'FAILED')),
+    withdrawal_transaction_id UUID REFERENCES transactions(id),
+    deposit_transaction_id UUID REFERENCES transactions(id),
+    failure_code VARCHAR(50),
+    failure_reason TEXT,
+    created_at TIMESTAMPTZ DEFAULT NOW() NOT NULL,
+    UNIQUE (schedule_id, scheduled_for)
+);
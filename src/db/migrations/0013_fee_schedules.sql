@@ -0,0 +1,31 @@
+-- Fees charged per currency and transaction type, credited to a revenue
+-- account. A schedule charges `flat_amount` plus `percentage` of the amount, or
+-- the rates of the first tier the amount falls in, capped by `min_fee` and
+-- `max_fee`. Schedules are never edited: a new one replaces the active one, so
+-- the terms a fee was charged under stay on record.
+CREATE TABLE IF NOT EXISTS fee_schedules (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    name VARCHAR(100) NOT NULL,
+    transaction_type VARCHAR(10) NOT NULL CHECK (transaction_type IN ('DEPOSIT', 'WITHDRAWAL', 'TRANSFER')),
+    currency VARCHAR(3) NOT NULL,
+    flat_amount DECIMAL(15,2) NOT NULL DEFAULT 0 CHECK (flat_amount >= 0),
+    percentage DECIMAL(7,4) NOT NULL DEFAULT 0 CHECK (percentage >= 0 AND percentage <= 100),
+    tiers JSONB NOT NULL DEFAULT '[]',
+    min_fee DECIMAL(15,2) CHECK (min_fee >= 0),
+    max_fee DECIMAL(15,2) CHECK (max_fee >= 0),
+    revenue_account_id UUID NOT NULL REFERENCES accounts(id),
+    active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ DEFAULT NOW() NOT NULL,
+    deactivated_at TIMESTAMPTZ,
+    CHECK (min_fee IS NULL OR max_fee IS NULL OR min_fee <= max_fee)
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_fee_schedules_active
+    ON fee_schedules(currency, transaction_type) WHERE active;
+CREATE INDEX IF NOT EXISTS idx_fee_schedules_created_at ON fee_schedules(created_at);
+
+-- Fee transactions point at the transaction they were charged for
+CREATE INDEX IF NOT EXISTS idx_transactions_fee_for
+    ON transactions((metadata->>'fee_for_transaction_id'))
+    WHERE metadata ? 'fee_for_transaction_id';
@@ -0,0 +1,34 @@
+-- Limits on money leaving accounts. An ACCOUNT row limits that account, a
+-- USER row the total over the user's accounts in `currency`, and the DEFAULT
+-- row for a currency fills in whatever an account's own row leaves unset.
+-- NULL means no limit.
+CREATE TABLE IF NOT EXISTS transaction_limits (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    scope VARCHAR(10) NOT NULL CHECK (scope IN ('DEFAULT', 'USER', 'ACCOUNT')),
+    user_id UUID REFERENCES users(id) ON DELETE CASCADE,
+    account_id UUID REFERENCES accounts(id) ON DELETE CASCADE,
+    currency VARCHAR(3) NOT NULL,
+    max_single_withdrawal DECIMAL(15,2) CHECK (max_single_withdrawal > 0),
+    daily_debit_limit DECIMAL(15,2) CHECK (daily_debit_limit >= 0),
+    monthly_debit_limit DECIMAL(15,2) CHECK (monthly_debit_limit >= 0),
+    max_debits_per_hour INT CHECK (max_debits_per_hour >= 0),
+    updated_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    created_at TIMESTAMPTZ DEFAULT NOW() NOT NULL,
+    updated_at TIMESTAMPTZ DEFAULT NOW() NOT NULL,
+    CHECK (
+        (scope = 'DEFAULT' AND user_id IS NULL AND account_id IS NULL)
+        OR (scope = 'USER' AND user_id IS NOT NULL AND account_id IS NULL)
+        OR (scope = 'ACCOUNT' AND account_id IS NOT NULL AND user_id IS NULL)
+    )
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_transaction_limits_default
+    ON transaction_limits(currency) WHERE scope = 'DEFAULT';
+CREATE UNIQUE INDEX IF NOT EXISTS idx_transaction_limits_user
+    ON transaction_limits(user_id, currency) WHERE scope = 'USER';
+CREATE UNIQUE INDEX IF NOT EXISTS idx_transaction_limits_account
+    ON transaction_limits(account_id) WHERE scope = 'ACCOUNT';
+
+-- Debit totals are summed per account over the current day and month
+CREATE INDEX IF NOT EXISTS idx_transactions_debits
+    ON transactions(account_id, created_at) WHERE type = 'WITHDRAWAL' AND status <> 'FAILED';
@@ -0,0 +1,49 @@
+-- Rules the risk engine scores debits with. Every enabled rule that matches
+-- adds its `score`; `params` holds the rule's thresholds.
+CREATE TABLE IF NOT EXISTS risk_rules (
+    code VARCHAR(50) PRIMARY KEY,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    score INT NOT NULL CHECK (score BETWEEN 0 AND 100),
+    params JSONB NOT NULL DEFAULT '{}',
+    updated_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    updated_at TIMESTAMPTZ DEFAULT NOW() NOT NULL
+);
+
+INSERT INTO risk_rules (code, score, params) VALUES
+    ('NEW_ACCOUNT_LARGE_WITHDRAWAL', 60, '{"max_account_age_days": 7, "min_amount": "5000.00"}'),
+    ('RAPID_WITHDRAWALS', 40, '{"max_count": 3, "window_minutes": 10}'),
+    ('NEW_IP', 30, '{}'),
+    ('AMOUNT_ABOVE_HISTORY', 50, '{"multiplier": "5", "min_history": 3, "lookback_days": 90}')
+ON CONFLICT (code) DO NOTHING;
+
+-- One row per debit at least one rule matched. REVIEW rows hold a PENDING
+-- transaction until an admin approves or rejects it; BLOCK rows have no
+-- transaction. `destination_account_id` is set for transfers, whose deposit
+-- is only booked once the review is approved.
+CREATE TABLE IF NOT EXISTS risk_assessments (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    account_id UUID NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+    transaction_id UUID REFERENCES transactions(id) ON DELETE CASCADE,
+    destination_account_id UUID REFERENCES accounts(id) ON DELETE CASCADE,
+    amount DECIMAL(15,2) NOT NULL,
+    ip_address VARCHAR(45),
+    score INT NOT NULL,
+    action VARCHAR(10) NOT NULL CHECK (action IN ('ALLOW', 'REVIEW', 'BLOCK')),
+    matched_rules JSONB NOT NULL DEFAULT '[]',
+    review_status VARCHAR(10) CHECK (review_status IN ('PENDING', 'APPROVED', 'REJECTED')),
+    reviewed_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    reviewed_at TIMESTAMPTZ,
+    review_note VARCHAR(500),
+    created_at TIMESTAMPTZ DEFAULT NOW() NOT NULL,
+    CHECK ((action = 'REVIEW') = (review_status IS NOT NULL))
+);
+
+CREATE INDEX IF NOT EXISTS idx_risk_assessments_review ON risk_assessments(review_status, created_at)
+    WHERE action = 'REVIEW';
+CREATE INDEX IF NOT EXISTS idx_risk_assessments_transaction_id ON risk_assessments(transaction_id);
+
+-- A scheduled payment whose transfer is held for review
+ALTER TABLE scheduled_payment_runs DROP CONSTRAINT IF EXISTS scheduled_payment_runs_status_check;
+ALTER TABLE scheduled_payment_runs ADD CONSTRAINT scheduled_payment_runs_status_check
+    CHECK (status IN ('SUCCEEDED', 'FAILED', 'IN_REVIEW'));
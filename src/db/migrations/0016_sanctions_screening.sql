@@ -0,0 +1,42 @@
+-- Sanctions lists as last loaded from the OFAC SDN and UN consolidated files.
+-- A reload replaces every entry of its source.
+CREATE TABLE IF NOT EXISTS watchlist_entries (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    source VARCHAR(20) NOT NULL CHECK (source IN ('OFAC_SDN', 'UN')),
+    -- OFAC `ent_num` or UN reference number
+    external_id VARCHAR(50) NOT NULL,
+    entry_type VARCHAR(20) NOT NULL CHECK (entry_type IN ('INDIVIDUAL', 'ENTITY', 'VESSEL', 'AIRCRAFT')),
+    name TEXT NOT NULL,
+    aliases TEXT[] NOT NULL DEFAULT '{}',
+    programs TEXT[] NOT NULL DEFAULT '{}',
+    loaded_at TIMESTAMPTZ DEFAULT NOW() NOT NULL,
+    UNIQUE (source, external_id)
+);
+
+-- One row per name screened. `user_id` is the screened user for USER
+-- subjects, NULL when the signup was refused, and the paying user for
+-- COUNTERPARTY subjects, whose held withdrawal is `transaction_id`.
+-- A PENDING or CONFIRMED review of a USER subject restricts the user.
+CREATE TABLE IF NOT EXISTS screening_results (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    subject_type VARCHAR(20) NOT NULL CHECK (subject_type IN ('USER', 'COUNTERPARTY')),
+    trigger VARCHAR(20) NOT NULL CHECK (trigger IN ('SIGNUP', 'NAME_CHANGE', 'RESCREEN', 'WITHDRAWAL')),
+    user_id UUID REFERENCES users(id) ON DELETE CASCADE,
+    transaction_id UUID REFERENCES transactions(id) ON DELETE CASCADE,
+    subject_name TEXT NOT NULL,
+    score INT NOT NULL CHECK (score BETWEEN 0 AND 100),
+    decision VARCHAR(10) NOT NULL CHECK (decision IN ('CLEAR', 'REVIEW', 'BLOCK')),
+    matches JSONB NOT NULL DEFAULT '[]',
+    review_status VARCHAR(10) CHECK (review_status IN ('PENDING', 'CLEARED', 'CONFIRMED')),
+    reviewed_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    reviewed_at TIMESTAMPTZ,
+    review_note TEXT,
+    created_at TIMESTAMPTZ DEFAULT NOW() NOT NULL,
+    CHECK (decision <> 'CLEAR' OR review_status IS NULL)
+);
+
+CREATE INDEX IF NOT EXISTS idx_screening_results_review
+    ON screening_results (review_status, created_at DESC) WHERE review_status IS NOT NULL;
+CREATE INDEX IF NOT EXISTS idx_screening_results_user_id ON screening_results (user_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_screening_results_transaction_id
+    ON screening_results (transaction_id) WHERE transaction_id IS NOT NULL;
@@ -0,0 +1,65 @@
+-- Identity verification. `kyc_tier` is the tier the user was verified to and
+-- `kyc_status` where their latest application stands.
+ALTER TABLE users ADD COLUMN IF NOT EXISTS kyc_tier VARCHAR(12) NOT NULL DEFAULT 'UNVERIFIED'
+    CHECK (kyc_tier IN ('UNVERIFIED', 'BASIC', 'FULL'));
+ALTER TABLE users ADD COLUMN IF NOT EXISTS kyc_status VARCHAR(12) NOT NULL DEFAULT 'NOT_STARTED'
+    CHECK (kyc_status IN ('NOT_STARTED', 'PENDING', 'VERIFIED', 'REJECTED'));
+
+-- Metadata of identity documents; the files themselves are kept elsewhere and
+-- identified by their checksum. Only the last characters of the document
+-- number are stored.
+CREATE TABLE IF NOT EXISTS kyc_documents (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    document_type VARCHAR(20) NOT NULL
+        CHECK (document_type IN ('PASSPORT', 'NATIONAL_ID', 'DRIVING_LICENSE', 'PROOF_OF_ADDRESS', 'SELFIE')),
+    issuing_country VARCHAR(2) NOT NULL,
+    document_number_last4 VARCHAR(4),
+    expires_on DATE,
+    file_name VARCHAR(255) NOT NULL,
+    content_type VARCHAR(100) NOT NULL,
+    size_bytes BIGINT NOT NULL CHECK (size_bytes > 0),
+    sha256 VARCHAR(64) NOT NULL,
+    status VARCHAR(10) NOT NULL DEFAULT 'PENDING' CHECK (status IN ('PENDING', 'VERIFIED', 'REJECTED')),
+    created_at TIMESTAMPTZ DEFAULT NOW() NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_kyc_documents_user_id ON kyc_documents(user_id, created_at);
+
+-- A request to be verified to `requested_tier` with the documents on file at
+-- the time. A user has at most one application waiting for review.
+CREATE TABLE IF NOT EXISTS kyc_applications (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    requested_tier VARCHAR(12) NOT NULL CHECK (requested_tier IN ('BASIC', 'FULL')),
+    document_ids UUID[] NOT NULL,
+    status VARCHAR(10) NOT NULL DEFAULT 'PENDING' CHECK (status IN ('PENDING', 'APPROVED', 'REJECTED')),
+    reviewed_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    reviewed_at TIMESTAMPTZ,
+    review_note VARCHAR(500),
+    created_at TIMESTAMPTZ DEFAULT NOW() NOT NULL
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_kyc_applications_pending ON kyc_applications(user_id) WHERE status = 'PENDING';
+CREATE INDEX IF NOT EXISTS idx_kyc_applications_status ON kyc_applications(status, created_at);
+
+-- What users of a tier may do in a currency: open accounts, hold a total
+-- balance, move in a single transaction and move in and out per calendar
+-- month. NULL means no cap; a currency without a row isn't capped for the tier.
+CREATE TABLE IF NOT EXISTS kyc_tier_caps (
+    tier VARCHAR(12) NOT NULL CHECK (tier IN ('UNVERIFIED', 'BASIC', 'FULL')),
+    currency VARCHAR(3) NOT NULL,
+    max_accounts INT CHECK (max_accounts >= 0),
+    max_balance DECIMAL(15,2) CHECK (max_balance >= 0),
+    max_single_transaction DECIMAL(15,2) CHECK (max_single_transaction > 0),
+    max_monthly_volume DECIMAL(15,2) CHECK (max_monthly_volume >= 0),
+    updated_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    updated_at TIMESTAMPTZ DEFAULT NOW() NOT NULL,
+    PRIMARY KEY (tier, currency)
+);
+
+INSERT INTO kyc_tier_caps (tier, currency, max_accounts, max_balance, max_single_transaction, max_monthly_volume) VALUES
+    ('UNVERIFIED', 'INR', 1, 10000.00, 5000.00, 20000.00),
+    ('BASIC', 'INR', 3, 200000.00, 50000.00, 500000.00)
+ON CONFLICT (tier, currency) DO NOTHING;
+
@@ -0,0 +1,57 @@
+-- Scenarios the AML monitoring job scans booked transactions for. `params`
+-- holds the scenario's thresholds and window.
+CREATE TABLE IF NOT EXISTS aml_scenarios (
+    code VARCHAR(50) PRIMARY KEY,
+    enabled BOOLEAN NOT NULL DEFAULT TRUE,
+    params JSONB NOT NULL DEFAULT '{}',
+    updated_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    updated_at TIMESTAMPTZ DEFAULT NOW() NOT NULL
+);
+
+INSERT INTO aml_scenarios (code, params) VALUES
+    ('STRUCTURING', '{"threshold": "50000.00", "margin_percent": "10", "min_count": 3, "window_hours": 72}'),
+    ('RAPID_MOVEMENT', '{"window_hours": 24, "min_amount": "10000.00", "min_out_percent": "80"}'),
+    ('DORMANT_REACTIVATION', '{"dormant_days": 180, "min_amount": "10000.00", "window_hours": 24}')
+ON CONFLICT (code) DO NOTHING;
+
+-- One row per finished monitoring run. `triggered_by` is the admin who
+-- started it, NULL for scheduled runs.
+CREATE TABLE IF NOT EXISTS aml_runs (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    triggered_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    started_at TIMESTAMPTZ NOT NULL,
+    finished_at TIMESTAMPTZ DEFAULT clock_timestamp() NOT NULL,
+    detections INT NOT NULL,
+    cases_opened INT NOT NULL,
+    cases_updated INT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_aml_runs_started_at ON aml_runs(started_at);
+
+-- Suspicious activity found by a scenario, with the transactions it was
+-- found in. Structuring is detected across a user's accounts in a currency,
+-- so `account_id` is NULL for it. A later detection on the same subject adds
+-- its transactions to the case while it is still open.
+CREATE TABLE IF NOT EXISTS aml_cases (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    scenario VARCHAR(50) NOT NULL,
+    status VARCHAR(15) NOT NULL DEFAULT 'OPEN'
+        CHECK (status IN ('OPEN', 'INVESTIGATING', 'DISMISSED', 'REPORTED')),
+    user_id UUID NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+    account_id UUID REFERENCES accounts(id) ON DELETE CASCADE,
+    currency VARCHAR(3) NOT NULL,
+    total_amount DECIMAL(15,2) NOT NULL,
+    transaction_ids UUID[] NOT NULL,
+    reason TEXT NOT NULL,
+    details JSONB NOT NULL DEFAULT '{}',
+    run_id UUID REFERENCES aml_runs(id) ON DELETE SET NULL,
+    assigned_to UUID REFERENCES users(id) ON DELETE SET NULL,
+    resolved_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    resolved_at TIMESTAMPTZ,
+    resolution_note VARCHAR(500),
+    created_at TIMESTAMPTZ DEFAULT NOW() NOT NULL,
+    updated_at TIMESTAMPTZ DEFAULT NOW() NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_aml_cases_status ON aml_cases(status, created_at);
+CREATE INDEX IF NOT EXISTS idx_aml_cases_subject ON aml_cases(scenario, user_id, currency);
@@ -0,0 +1,51 @@
+-- Operations that need a second person: the user who asks for one can't
+-- approve it. Operations are only carried out once approved; `transaction_id`
+-- is then the withdrawal or adjustment booked. `details` holds what the
+-- operation needs and what the approver should see.
+CREATE TABLE IF NOT EXISTS approval_requests (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    operation VARCHAR(20) NOT NULL CHECK (operation IN ('WITHDRAWAL', 'BALANCE_ADJUSTMENT', 'ACCOUNT_CLOSURE')),
+    status VARCHAR(10) NOT NULL DEFAULT 'PENDING'
+        CHECK (status IN ('PENDING', 'APPROVED', 'REJECTED', 'CANCELLED', 'EXPIRED')),
+    account_id UUID NOT NULL REFERENCES accounts(id) ON DELETE CASCADE,
+    requested_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    amount DECIMAL(12,2),
+    transaction_id UUID REFERENCES transactions(id) ON DELETE SET NULL,
+    details JSONB NOT NULL DEFAULT '{}',
+    decided_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    decided_at TIMESTAMPTZ,
+    decision_note VARCHAR(500),
+    expires_at TIMESTAMPTZ NOT NULL,
+    created_at TIMESTAMPTZ DEFAULT NOW() NOT NULL,
+    updated_at TIMESTAMPTZ DEFAULT NOW() NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_approval_requests_status ON approval_requests(status, created_at);
+CREATE INDEX IF NOT EXISTS idx_approval_requests_requested_by ON approval_requests(requested_by, created_at);
+CREATE INDEX IF NOT EXISTS idx_approval_requests_transaction_id ON approval_requests(transaction_id);
+CREATE INDEX IF NOT EXISTS idx_approval_requests_expires_at ON approval_requests(expires_at) WHERE status = 'PENDING';
+-- An account has at most one closure waiting for approval
+CREATE UNIQUE INDEX IF NOT EXISTS idx_approval_requests_pending_closure ON approval_requests(account_id)
+    WHERE status = 'PENDING' AND operation = 'ACCOUNT_CLOSURE';
+
+CREATE TABLE IF NOT EXISTS approval_comments (
+    id UUID PRIMARY KEY DEFAULT uuid_generate_v4(),
+    request_id UUID NOT NULL REFERENCES approval_requests(id) ON DELETE CASCADE,
+    author_id UUID REFERENCES users(id) ON DELETE SET NULL,
+    body VARCHAR(1000) NOT NULL,
+    created_at TIMESTAMPTZ DEFAULT NOW() NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_approval_comments_request_id ON approval_comments(request_id, created_at);
+
+-- Withdrawals above the threshold of their currency need approval; a
+-- currency without a threshold doesn't.
+CREATE TABLE IF NOT EXISTS approval_thresholds (
+    currency VARCHAR(3) PRIMARY KEY,
+    withdrawal_threshold DECIMAL(12,2) NOT NULL CHECK (withdrawal_threshold > 0),
+    updated_by UUID REFERENCES users(id) ON DELETE SET NULL,
+    updated_at TIMESTAMPTZ DEFAULT NOW() NOT NULL
+);
+
+INSERT INTO approval_thresholds (currency, withdrawal_threshold) VALUES ('INR', 100000.00)
+ON CONFLICT (currency) DO NOTHING;
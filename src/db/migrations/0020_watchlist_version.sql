@@ -0,0 +1,8 @@
+-- Bumped by every watchlist reload. API replicas keep the lists in memory
+-- and rebuild them when the version they were built at is no longer current.
+CREATE TABLE IF NOT EXISTS watchlist_version (
+    singleton BOOLEAN PRIMARY KEY DEFAULT TRUE CHECK (singleton),
+    version BIGINT NOT NULL DEFAULT 0
+);
+
+INSERT INTO watchlist_version DEFAULT VALUES ON CONFLICT DO NOTHING;
@@ -0,0 +1,17 @@
+-- Transactions recorded with POST /transactions never move the balance, unlike
+-- deposits, withdrawals, fees and transfers, so historical balances and
+-- statements leave them out. Rows recorded that way before this column existed
+-- are found by their audit entries.
+ALTER TABLE transactions ADD COLUMN IF NOT EXISTS booked BOOLEAN NOT NULL DEFAULT TRUE;
+
+UPDATE transactions t
+SET booked = FALSE
+FROM audit_log l
+WHERE l.action = 'transaction.created' AND l.target_type = 'transaction' AND l.target_id = t.id;
+
+-- Snapshots taken since then counted those rows; the snapshot job takes them again.
+DELETE FROM balance_snapshots s
+WHERE EXISTS (
+    SELECT 1 FROM transactions t
+    WHERE t.account_id = s.account_id AND NOT t.booked AND t.created_at < s.as_of
+);
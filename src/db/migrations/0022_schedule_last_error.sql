@@ -0,0 +1,4 @@
+-- The error that last kept a due schedule from running. The schedule stays
+-- due and is tried again; its next run clears these.
+ALTER TABLE scheduled_payments ADD COLUMN IF NOT EXISTS last_error_code VARCHAR(50);
+ALTER TABLE scheduled_payments ADD COLUMN IF NOT EXISTS last_error_at TIMESTAMPTZ;
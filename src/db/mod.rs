@@ -1 +1,4 @@
 pub mod dal;
+pub mod migrations;
+pub mod pool;
+pub mod query;
@@ -0,0 +1,35 @@
+use deadpool_postgres::{Pool, PoolError};
+use crate::base::constants::{DB_CONNECT_ATTEMPTS, DB_CONNECT_RETRY_DELAY};
+
+// Opens `connections` connections up front so that the first requests don't
+// pay for connection setup. Retries while the database is still starting.
+pub async fn warm_up(pool: &Pool, connections: usize) -> Result<(), PoolError> {
+    let mut attempt = 1;
+    loop {
+        match open_connections(pool, connections).await {
+            Ok(()) => return Ok(()),
+            Err(e) if attempt < DB_CONNECT_ATTEMPTS => {
+                tracing::warn!(
+                    "Database not reachable (attempt {}/{}): {}",
+                    attempt,
+                    DB_CONNECT_ATTEMPTS,
+                    e
+                );
+                attempt += 1;
+                tokio::time::sleep(DB_CONNECT_RETRY_DELAY).await;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+async fn open_connections(pool: &Pool, connections: usize) -> Result<(), PoolError> {
+    let mut clients = Vec::with_capacity(connections);
+    for _ in 0..connections {
+        let client = pool.get().await?;
+        client.simple_query("SELECT 1").await?;
+        clients.push(client);
+    }
+
+    Ok(())
+}
@@ -0,0 +1,72 @@
+use tokio_postgres::types::ToSql;
+use crate::base::pagination::{CursorValue, PageRequest};
+
+// Builds SQL with a variable number of positional parameters, keeping the
+// parameter values owned so filters can be assembled in helper functions.
+pub struct QueryBuilder {
+    sql: String,
+    params: Vec<Box<dyn ToSql + Sync + Send>>,
+}
+
+impl QueryBuilder {
+    pub fn new(sql: &str) -> Self {
+        Self {
+            sql: sql.to_string(),
+            params: Vec::new(),
+        }
+    }
+
+    // Registers a parameter and returns its placeholder (`$n`).
+    pub fn bind<T: ToSql + Sync + Send + 'static>(&mut self, value: T) -> String {
+        self.params.push(Box::new(value));
+        format!("${}", self.params.len())
+    }
+
+    // Appends `sql` with its `{}` replaced by the placeholder for `value`.
+    pub fn push_bind<T: ToSql + Sync + Send + 'static>(&mut self, sql: &str, value: T) -> &mut Self {
+        let placeholder = self.bind(value);
+        self.sql.push_str(&sql.replacen("{}", &placeholder, 1));
+        self
+    }
+
+    pub fn sql(&self) -> &str {
+        &self.sql
+    }
+
+    pub fn params(&self) -> Vec<&(dyn ToSql + Sync)> {
+        self.params
+            .iter()
+            .map(|param| param.as_ref() as &(dyn ToSql + Sync))
+            .collect()
+    }
+
+    // Appends the keyset condition, ordering and limit for `page`. The query must
+    // already have a WHERE clause. `sort_column` and `id_column` are trusted SQL.
+    pub fn paginate(&mut self, page: &PageRequest, sort_column: &str, id_column: &str) -> &mut Self {
+        if let Some(cursor) = &page.cursor {
+            let value = match cursor.value {
+                CursorValue::Timestamp(ts) => self.bind(ts),
+                CursorValue::Decimal(value) => self.bind(value),
+            };
+            let id = self.bind(cursor.id);
+            self.sql.push_str(&format!(
+                " AND ({}, {}) {} ({}, {})",
+                sort_column,
+                id_column,
+                page.order.comparator(),
+                value,
+                id
+            ));
+        }
+
+        let limit = self.bind(page.fetch_limit());
+        self.sql.push_str(&format!(
+            " ORDER BY {sort} {dir}, {id} {dir} LIMIT {limit}",
+            sort = sort_column,
+            id = id_column,
+            dir = page.order.sql(),
+            limit = limit
+        ));
+        self
+    }
+}
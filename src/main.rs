@@ -1,40 +1,139 @@
 mod base;
 mod db;
 mod api;
+mod workers;
+mod services;
+mod cli;
 use std::time::Duration;
 use std::net::SocketAddr;
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 use tower_http::trace::TraceLayer;
-use axum::Extension;
-use api::middleware::rate_limit::RateLimiter;
+use tokio_util::sync::CancellationToken;
+use axum::{Extension, middleware};
+use api::middleware::{rate_limit::RateLimiter, request_id::request_id_middleware};
+use base::constants::DB_WARM_UP_CONNECTIONS;
+use services::{events::AccountEventHub, screening::WatchlistCache};
+use workers::{account_events, aml_monitoring, approval_expiry, balance_snapshots, data_exports, scheduled_payments, webhooks, Workers};
 
 #[tokio::main]
 async fn main() {
     let config = base::config::AppConfig::from_env()
         .expect("Failed to load configuration");
 
-    tracing_subscriber::registry()
-        .with(tracing_subscriber::EnvFilter::new(
-            std::env::var("RUST_LOG").unwrap_or_else(|_| "info".into()),
-        ))
-        .with(tracing_subscriber::fmt::layer())
-        .init();
+    base::telemetry::init_tracing(config.log_format);
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let command = match cli::Command::parse(&args) {
+        Ok(command) => command,
+        Err(usage) => {
+            eprintln!("{}", usage);
+            std::process::exit(2);
+        }
+    };
+
+    let metrics_handle = base::metrics::install();
 
     let pool = config.pg_pool();
 
+    db::pool::warm_up(&pool, DB_WARM_UP_CONNECTIONS)
+        .await
+        .expect("Failed to connect to database");
+
+    let mut client = pool.get().await.expect("Failed to get database connection");
+    db::migrations::run(&mut client)
+        .await
+        .expect("Failed to apply migrations");
+    drop(client);
+
+    if !matches!(command, cli::Command::Serve) {
+        if let Err(e) = cli::run(command, &pool).await {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    let shutdown = CancellationToken::new();
+    let workers = Workers::new(shutdown.clone());
+    workers.spawn(|token| balance_snapshots::run(pool.clone(), token));
+    workers.spawn(|token| data_exports::run(pool.clone(), token));
+    workers.spawn(|token| webhooks::run(pool.clone(), token));
+    workers.spawn(|token| scheduled_payments::run(pool.clone(), token));
+    workers.spawn(|token| aml_monitoring::run(pool.clone(), token));
+    workers.spawn(|token| approval_expiry::run(pool.clone(), token));
+
+    let event_hub = AccountEventHub::new(shutdown.clone());
+    workers.spawn(|token| account_events::run(config.database_url.clone(), pool.clone(), event_hub.clone(), token));
+
     // 100 requests per minute per IP
     let rate_limiter = RateLimiter::new(100, Duration::from_secs(60));
 
+    let metrics_app = api::routes::create_metrics_router(pool.clone()).layer(Extension(metrics_handle));
+
     let app = api::routes::create_router(pool)
         .layer(Extension(rate_limiter))
-        .layer(TraceLayer::new_for_http());
+        .layer(Extension(event_hub))
+        .layer(Extension(WatchlistCache::default()))
+        .layer(TraceLayer::new_for_http())
+        .layer(middleware::from_fn(request_id_middleware));
 
     let listener = tokio::net::TcpListener::bind("0.0.0.0:3000")
         .await
         .unwrap();
     tracing::info!("Listening on {}", listener.local_addr().unwrap());
 
-    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+    let metrics_listener = tokio::net::TcpListener::bind(&config.metrics_addr)
         .await
-        .unwrap();
+        .expect("Failed to bind metrics listener");
+    tracing::info!("Serving metrics on {}", metrics_listener.local_addr().unwrap());
+
+    let server = tokio::spawn(
+        axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+            .with_graceful_shutdown(shutdown.clone().cancelled_owned())
+            .into_future(),
+    );
+    let metrics_server = tokio::spawn(
+        axum::serve(metrics_listener, metrics_app)
+            .with_graceful_shutdown(shutdown.clone().cancelled_owned())
+            .into_future(),
+    );
+
+    shutdown_signal().await;
+    tracing::info!("Shutdown signal received, draining in-flight requests");
+    shutdown.cancel();
+
+    // In-flight requests and background workers share one deadline
+    let drained = tokio::time::timeout(config.shutdown_timeout, async {
+        let _ = server.await;
+        let _ = metrics_server.await;
+        workers.shutdown().await;
+    })
+    .await;
+
+    match drained {
+        Ok(()) => tracing::info!("Shutdown complete"),
+        Err(_) => tracing::warn!(
+            "Shutdown timed out after {:?}, exiting with work still in flight",
+            config.shutdown_timeout
+        ),
+    }
+}
+
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("Failed to install Ctrl+C handler");
+    };
+
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("Failed to install SIGTERM handler")
+            .recv()
+            .await;
+    };
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
 }
@@ -0,0 +1,258 @@
+use deadpool_postgres::GenericClient;
+use rust_decimal::Decimal;
+use serde_json::json;
+use uuid::Uuid;
+use crate::{
+    base::{
+        error::AppError,
+        models::{
+            accounts::{Account, AccountStatus},
+            audit::NewAuditEntry,
+            transactions::{
+                CreateTransactionRequest, Transaction, TransactionStatus, TransactionType, UpdateTransactionStatusRequest,
+            },
+        },
+    },
+    db::dal::{
+        accounts as account_queries, audit as audit_queries, balances as balance_queries, risk as risk_queries,
+        screening as screening_queries, transactions as transaction_queries,
+    },
+    services::{events as event_service, screening as screening_service},
+};
+
+// Closes an account, first moving any remaining balance to `payout_account_id`,
+// which must be another account of the same holder in the same currency. The
+// account row is kept so its history stays readable. Closures are carried out
+// when their approval request is approved, inside the caller's transaction.
+pub async fn close_account(
+    db_transaction: &impl GenericClient,
+    actor_id: Uuid,
+    id: Uuid,
+    payout_account_id: Option<Uuid>,
+) -> Result<Account, AppError> {
+    let account = account_queries::get_account_for_update(db_transaction, id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Account not found".into()))?;
+    account.ensure_can_debit()?;
+    screening_service::ensure_not_restricted(db_transaction, account.user_id).await?;
+
+    // Held funds are out of the balance, and a rejection has to return them
+    if risk_queries::count_pending_reviews(db_transaction, id).await? > 0 {
+        return Err(AppError::Conflict("Account has transactions held for risk review".into()));
+    }
+    if screening_queries::count_pending_reviews(db_transaction, id).await? > 0 {
+        return Err(AppError::Conflict("Account has withdrawals held for screening review".into()));
+    }
+
+    if account.balance > Decimal::ZERO {
+        let payout_account_id = payout_account_id.ok_or_else(|| {
+            AppError::invalid_field(
+                "payout_account_id",
+                "REQUIRED",
+                "payout_account_id is required while the account holds funds",
+            )
+        })?;
+        pay_out(db_transaction, &account, payout_account_id).await?;
+    }
+
+    let closed = account_queries::set_account_status(db_transaction, id, AccountStatus::Closed)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Account not found".into()))?;
+
+    audit_queries::create_entry(
+        db_transaction,
+        &NewAuditEntry::new(Some(actor_id), "account.closed", "account", Some(id))
+            .before(&account)
+            .after(&closed)
+            .details(json!({ "payout_account_id": payout_account_id })),
+    )
+    .await?;
+
+    tracing::info!(account_id = %id, payout_account_id = ?payout_account_id, "Account closed");
+
+    Ok(closed)
+}
+
+// Moves the whole balance as a completed withdrawal and deposit pair.
+async fn pay_out(client: &impl GenericClient, account: &Account, payout_account_id: Uuid) -> Result<(), AppError> {
+    if payout_account_id == account.id {
+        return Err(AppError::invalid_field(
+            "payout_account_id",
+            "SAME_ACCOUNT",
+            "payout_account_id must be a different account",
+        ));
+    }
+
+    let target = account_queries::get_account_for_update(client, payout_account_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Payout account not found".into()))?;
+
+    if target.user_id != account.user_id {
+        return Err(AppError::Forbidden("Not allowed to pay out to this account".into()));
+    }
+    if target.currency != account.currency {
+        return Err(AppError::invalid_field(
+            "payout_account_id",
+            "CURRENCY_MISMATCH",
+            "payout account must have the same currency",
+        ));
+    }
+    target.ensure_can_credit()?;
+
+    transfer(
+        client,
+        account,
+        &target,
+        account.balance,
+        format!("Payout to account {} on closing", target.id),
+        format!("Payout from closed account {}", account.id),
+    )
+    .await?;
+
+    Ok(())
+}
+
+// Moves `amount` from `source` to `target` as a completed withdrawal and
+// deposit pair, returning both transactions. Both accounts must be locked by
+// the caller and already checked for status and currency.
+pub async fn transfer(
+    client: &impl GenericClient,
+    source: &Account,
+    target: &Account,
+    amount: Decimal,
+    withdrawal_description: String,
+    deposit_description: String,
+) -> Result<(Transaction, Transaction), AppError> {
+    let withdrawal = leg(source, amount, TransactionType::Withdrawal, withdrawal_description);
+    let (withdrawal, _) = book(client, source, withdrawal).await?;
+    let deposit = leg(target, amount, TransactionType::Deposit, deposit_description);
+    let (deposit, _) = book(client, target, deposit).await?;
+
+    Ok((withdrawal, deposit))
+}
+
+fn leg(account: &Account, amount: Decimal, transaction_type: TransactionType, description: String) -> CreateTransactionRequest {
+    CreateTransactionRequest {
+        account_id: account.id,
+        amount,
+        transaction_type,
+        description: Some(description),
+        metadata: None,
+        tags: None,
+    }
+}
+
+// Books `request` on `account` as a completed transaction and returns it with
+// the updated account. The account must be locked by the caller.
+pub async fn book(
+    client: &impl GenericClient,
+    account: &Account,
+    request: CreateTransactionRequest,
+) -> Result<(Transaction, Account), AppError> {
+    let is_credit = matches!(request.transaction_type, TransactionType::Deposit);
+    let completed = UpdateTransactionStatusRequest { status: TransactionStatus::Completed };
+
+    let transaction = transaction_queries::create_transaction(client, &request).await?;
+    let transaction = transaction_queries::update_transaction_status(client, transaction.id, &completed)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Transaction not found".into()))?;
+    let updated = account_queries::update_account_balance(client, account.id, request.amount, is_credit)
+        .await?
+        .ok_or(if is_credit { AppError::AccountClosed } else { AppError::InsufficientFunds })?;
+
+    event_service::transaction_created(client, account.user_id, &transaction).await?;
+    event_service::balance_changed(client, account, &updated, transaction.id).await?;
+
+    Ok((transaction, updated))
+}
+
+// Books `request` on `account` as a PENDING debit, taking the funds from the
+// balance while the transaction waits, e.g. for a risk review. The account
+// must be locked by the caller.
+pub async fn hold(
+    client: &impl GenericClient,
+    account: &Account,
+    request: CreateTransactionRequest,
+) -> Result<(Transaction, Account), AppError> {
+    let transaction = transaction_queries::create_transaction(client, &request).await?;
+    let updated = account_queries::update_account_balance(client, account.id, request.amount, false)
+        .await?
+        .ok_or(AppError::InsufficientFunds)?;
+
+    event_service::transaction_created(client, account.user_id, &transaction).await?;
+    event_service::balance_changed(client, account, &updated, transaction.id).await?;
+
+    Ok((transaction, updated))
+}
+
+// Completes a debit booked by `hold`. The account must be locked by the caller.
+pub async fn complete_hold(
+    client: &impl GenericClient,
+    account: &Account,
+    transaction_id: Uuid,
+) -> Result<Transaction, AppError> {
+    let transaction = transaction_queries::get_transaction_for_update(client, transaction_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Transaction not found".into()))?;
+    let completed = UpdateTransactionStatusRequest { status: TransactionStatus::Completed };
+    let updated = transaction_queries::update_transaction_status(client, transaction_id, &completed)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Transaction not found".into()))?;
+
+    event_service::transaction_status_changed(client, account.user_id, &transaction, &updated).await?;
+
+    Ok(updated)
+}
+
+// Moves the balance for a booked transaction an admin moved into FAILED
+// (`failed`) or out of it: failing a withdrawal returns its amount and failing
+// a deposit takes it back, while reviving one books it again. Returns the
+// updated account. The account must be locked by the caller.
+pub async fn rebook(
+    client: &impl GenericClient,
+    account: &Account,
+    transaction: &Transaction,
+    failed: bool,
+) -> Result<Account, AppError> {
+    let is_credit = matches!(transaction.transaction_type, TransactionType::Deposit) != failed;
+    if is_credit {
+        account.ensure_can_credit()?;
+    } else {
+        account.ensure_can_debit()?;
+    }
+
+    let updated = account_queries::update_account_balance(client, account.id, transaction.amount, is_credit)
+        .await?
+        .ok_or(if is_credit { AppError::AccountClosed } else { AppError::InsufficientFunds })?;
+
+    event_service::balance_changed(client, account, &updated, transaction.id).await?;
+
+    Ok(updated)
+}
+
+// Fails a debit booked by `hold` and returns its amount to the account. The
+// account must be locked by the caller.
+pub async fn return_hold(
+    client: &impl GenericClient,
+    account: &Account,
+    transaction_id: Uuid,
+) -> Result<Transaction, AppError> {
+    let transaction = transaction_queries::get_transaction_for_update(client, transaction_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Transaction not found".into()))?;
+    let failed = UpdateTransactionStatusRequest { status: TransactionStatus::Failed };
+    let updated = transaction_queries::update_transaction_status(client, transaction_id, &failed)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Transaction not found".into()))?;
+    // Snapshots taken while the debit was held counted it
+    balance_queries::invalidate_snapshots(client, account.id, transaction.created_at).await?;
+
+    let refunded = account_queries::update_account_balance(client, account.id, transaction.amount, true)
+        .await?
+        .ok_or(AppError::AccountClosed)?;
+
+    event_service::transaction_status_changed(client, account.user_id, &transaction, &updated).await?;
+    event_service::balance_changed(client, account, &refunded, transaction_id).await?;
+
+    Ok(updated)
+}
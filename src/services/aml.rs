@@ -0,0 +1,148 @@
+use chrono::{DateTime, Utc};
+use deadpool_postgres::{Client, GenericClient};
+use serde_json::json;
+use uuid::Uuid;
+use crate::{
+    base::{
+        aml,
+        error::AppError,
+        metrics,
+        models::{
+            aml::{AmlCase, AmlCaseStatus, AmlDetection, AmlRun, AmlScenarioParams},
+            audit::NewAuditEntry,
+        },
+    },
+    db::dal::{aml as aml_queries, audit as audit_queries},
+};
+
+// Scans the transactions in each enabled scenario's window and files what it
+// finds as cases. A detection on a subject with an open case adds its
+// transactions to that case; one whose transactions were all in a case that
+// was already closed is not opened again. Returns None when another run is
+// in progress. `triggered_by` is the admin who started the run.
+pub async fn run(client: &mut Client, triggered_by: Option<Uuid>) -> Result<Option<AmlRun>, AppError> {
+    let started_at = Utc::now();
+    let db_transaction = client.transaction().await?;
+
+    if !aml_queries::try_lock_run(&db_transaction).await? {
+        return Ok(None);
+    }
+
+    let mut detections = Vec::new();
+    for params in aml::active_scenarios(aml_queries::list_scenarios(&db_transaction).await?) {
+        for detection in detect(&db_transaction, &params, started_at).await? {
+            detections.push((detection, params.clone()));
+        }
+    }
+
+    let run = aml_queries::create_run(&db_transaction, triggered_by, started_at, detections.len() as i32).await?;
+    let (mut cases_opened, mut cases_updated) = (0, 0);
+
+    for (detection, params) in &detections {
+        let reason = aml::reason(detection, params);
+
+        if let Some(case) = aml_queries::get_open_case_for_update(&db_transaction, detection).await? {
+            if detection.transaction_ids.iter().all(|id| case.transaction_ids.contains(id)) {
+                continue;
+            }
+            let updated = aml_queries::add_evidence(&db_transaction, case.id, detection, &reason, run.id).await?;
+            audit_queries::create_entry(
+                &db_transaction,
+                &NewAuditEntry::new(triggered_by, "aml_case.evidence_added", "aml_case", Some(case.id))
+                    .before(&case)
+                    .after(&updated)
+                    .details(json!({ "run_id": run.id })),
+            )
+            .await?;
+            cases_updated += 1;
+            continue;
+        }
+
+        if aml_queries::is_already_resolved(&db_transaction, detection).await? {
+            continue;
+        }
+
+        let case = aml_queries::create_case(&db_transaction, detection, &reason, run.id).await?;
+        audit_queries::create_entry(
+            &db_transaction,
+            &NewAuditEntry::new(triggered_by, "aml_case.opened", "aml_case", Some(case.id))
+                .after(&case)
+                .details(json!({ "run_id": run.id, "scenario": case.scenario })),
+        )
+        .await?;
+        metrics::record_aml_case_opened(&case.scenario.to_string());
+        cases_opened += 1;
+    }
+
+    let run = aml_queries::finish_run(&db_transaction, run.id, cases_opened, cases_updated).await?;
+
+    db_transaction.commit().await?;
+
+    tracing::info!(
+        run_id = %run.id,
+        detections = run.detections,
+        cases_opened,
+        cases_updated,
+        "AML monitoring run finished"
+    );
+
+    Ok(Some(run))
+}
+
+async fn detect(
+    client: &impl GenericClient,
+    params: &AmlScenarioParams,
+    now: DateTime<Utc>,
+) -> Result<Vec<AmlDetection>, AppError> {
+    let since = aml::window_start(params, now);
+
+    Ok(match params {
+        AmlScenarioParams::Structuring(params) => {
+            let floor = aml::structuring_floor(params.threshold, params.margin_percent);
+            aml_queries::detect_structuring(client, since, floor, params.threshold, params.min_count).await?
+        }
+        AmlScenarioParams::RapidMovement(params) => {
+            aml_queries::detect_rapid_movement(client, since, params.min_amount, params.min_out_percent).await?
+        }
+        AmlScenarioParams::DormantReactivation(params) => {
+            aml_queries::detect_dormant_reactivation(client, since, params.min_amount, params.dormant_days).await?
+        }
+    })
+}
+
+// Moves a case along: taking it up assigns it to the actor, and dismissing or
+// reporting it closes it with the note.
+pub async fn set_case_status(
+    client: &mut Client,
+    actor_id: Uuid,
+    id: Uuid,
+    status: AmlCaseStatus,
+    note: Option<&str>,
+) -> Result<AmlCase, AppError> {
+    let db_transaction = client.transaction().await?;
+
+    let case = aml_queries::get_case_for_update(&db_transaction, id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("AML case not found".into()))?;
+    if !case.status.can_become(status) {
+        return Err(AppError::Conflict(format!("AML case can't move from {} to {}", case.status, status)));
+    }
+
+    let updated = aml_queries::set_case_status(&db_transaction, id, status, actor_id, note).await?;
+
+    audit_queries::create_entry(
+        &db_transaction,
+        &NewAuditEntry::new(Some(actor_id), "aml_case.status_changed", "aml_case", Some(id))
+            .before(&case)
+            .after(&updated)
+            .details(json!({ "from": case.status, "to": status })),
+    )
+    .await?;
+
+    db_transaction.commit().await?;
+
+    metrics::record_aml_case_status(&status.to_string());
+    tracing::info!(case_id = %id, from = %case.status, to = %status, "AML case status changed");
+
+    Ok(updated)
+}
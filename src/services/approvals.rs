@@ -0,0 +1,613 @@
+use chrono::Utc;
+use deadpool_postgres::{Client, GenericClient};
+use rust_decimal::Decimal;
+use serde_json::json;
+use uuid::Uuid;
+use crate::{
+    base::{
+        constants::{ADJUSTMENT_TRANSACTION_TAG, APPROVAL_EXPIRY_BATCH_SIZE, APPROVAL_REQUEST_TTL},
+        error::AppError,
+        metrics,
+        models::{
+            accounts::Account,
+            approvals::{
+                AdjustmentDirection, ApprovalComment, ApprovalOperation, ApprovalRequest, ApprovalStatus,
+                BalanceAdjustmentRequest, NewApprovalRequest,
+            },
+            audit::NewAuditEntry,
+            fees::{FeeBreakdown, FeeTransactionType},
+            risk::RiskAction,
+            screening::{ScreeningDecision, ScreeningOutcome, ScreeningSubject, ScreeningTrigger},
+            transactions::{CreateTransactionRequest, TransactionType},
+        },
+    },
+    db::dal::{
+        accounts as account_queries, approvals as approval_queries, audit as audit_queries, risk as risk_queries,
+        screening as screening_queries,
+    },
+    services::{
+        accounts as account_service, fees as fee_service, kyc as kyc_service, limits as limit_service,
+        risk as risk_service, screening::{self as screening_service, WatchlistCache},
+    },
+};
+
+// Stores `request` as PENDING until it is decided or expires. The caller owns
+// the database transaction and records the metric once it commits.
+pub async fn submit(client: &impl GenericClient, request: &NewApprovalRequest) -> Result<ApprovalRequest, AppError> {
+    let created = approval_queries::create_request(client, request, Utc::now() + APPROVAL_REQUEST_TTL).await?;
+
+    audit_queries::create_entry(
+        client,
+        &NewAuditEntry::new(Some(request.requested_by), "approval_request.created", "approval_request", Some(created.id))
+            .after(&created)
+            .details(json!({ "operation": created.operation, "account_id": created.account_id })),
+    )
+    .await?;
+
+    tracing::info!(
+        approval_request_id = %created.id,
+        operation = %created.operation,
+        account_id = %created.account_id,
+        "Approval requested"
+    );
+
+    Ok(created)
+}
+
+// Asks for the holder's account to be closed. The checks `close_account` makes
+// are made now too, so a request that could never be carried out is refused
+// up front; they are made again on approval.
+pub async fn request_closure(
+    client: &mut Client,
+    actor_id: Uuid,
+    account_id: Uuid,
+    payout_account_id: Option<Uuid>,
+) -> Result<ApprovalRequest, AppError> {
+    let db_transaction = client.transaction().await?;
+
+    let account = account_queries::get_account_for_update(&db_transaction, account_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Account not found".into()))?;
+    if account.user_id != actor_id {
+        return Err(AppError::Forbidden("Not allowed to close this account".into()));
+    }
+    account.ensure_can_debit()?;
+    screening_service::ensure_not_restricted(&db_transaction, account.user_id).await?;
+
+    if approval_queries::has_pending(&db_transaction, account_id, ApprovalOperation::AccountClosure).await? {
+        return Err(AppError::Conflict("Account closure is already waiting for approval".into()));
+    }
+    if risk_queries::count_pending_reviews(&db_transaction, account_id).await? > 0 {
+        return Err(AppError::Conflict("Account has transactions held for risk review".into()));
+    }
+    if screening_queries::count_pending_reviews(&db_transaction, account_id).await? > 0 {
+        return Err(AppError::Conflict("Account has withdrawals held for screening review".into()));
+    }
+    if account.balance > Decimal::ZERO && payout_account_id.is_none() {
+        return Err(AppError::invalid_field(
+            "payout_account_id",
+            "REQUIRED",
+            "payout_account_id is required while the account holds funds",
+        ));
+    }
+
+    let request = NewApprovalRequest {
+        operation: ApprovalOperation::AccountClosure,
+        account_id,
+        requested_by: actor_id,
+        amount: None,
+        details: json!({ "payout_account_id": payout_account_id }),
+    };
+    let created = submit(&db_transaction, &request).await?;
+
+    db_transaction.commit().await?;
+
+    metrics::record_approval_request(&created.operation.to_string(), &created.status.to_string());
+
+    Ok(created)
+}
+
+// Asks for an admin correction of an account's balance.
+pub async fn request_adjustment(
+    client: &mut Client,
+    actor_id: Uuid,
+    account_id: Uuid,
+    adjustment: &BalanceAdjustmentRequest,
+) -> Result<ApprovalRequest, AppError> {
+    let db_transaction = client.transaction().await?;
+
+    let account = account_queries::get_account_for_update(&db_transaction, account_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Account not found".into()))?;
+    account.ensure_can_credit()?;
+
+    let request = NewApprovalRequest {
+        operation: ApprovalOperation::BalanceAdjustment,
+        account_id,
+        requested_by: actor_id,
+        amount: Some(adjustment.amount),
+        details: json!({ "direction": adjustment.direction, "reason": adjustment.reason }),
+    };
+    let created = submit(&db_transaction, &request).await?;
+
+    db_transaction.commit().await?;
+
+    metrics::record_approval_request(&created.operation.to_string(), &created.status.to_string());
+
+    Ok(created)
+}
+
+// Carries out the operation and marks the request APPROVED. It has to be
+// decided by someone other than the user who made it or owns the account. A
+// withdrawal is booked and its fee charged at the rates in force now, which is
+// returned; no fee is returned when its payee is held for a screening review.
+pub async fn approve(
+    client: &mut Client,
+    watchlists: &WatchlistCache,
+    actor_id: Uuid,
+    id: Uuid,
+    note: Option<&str>,
+) -> Result<(ApprovalRequest, Option<FeeBreakdown>), AppError> {
+    // The payee is screened before the account is locked, since matching
+    // takes a while; the outcome is acted on in `withdraw`.
+    let payee = approval_queries::get_request_by_id(&*client, id)
+        .await?
+        .filter(|request| request.operation == ApprovalOperation::Withdrawal)
+        .and_then(|request| request.details["metadata"]["counterparty_name"].as_str().map(str::to_owned));
+    let screening = match &payee {
+        Some(name) => Some(
+            screening_service::screen_name(&*client, watchlists, name, ScreeningSubject::Counterparty, None).await?,
+        ),
+        None => None,
+    };
+
+    let db_transaction = client.transaction().await?;
+
+    let request = pending_request(&db_transaction, id).await?;
+    let account = account_queries::get_account_for_update(&db_transaction, request.account_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Account not found".into()))?;
+    ensure_checker(&request, account.user_id, actor_id)?;
+    if request.expires_at <= Utc::now() {
+        return Err(AppError::Conflict("Approval request has expired".into()));
+    }
+
+    let mut fee = None;
+    let mut booked_id = None;
+    match request.operation {
+        ApprovalOperation::Withdrawal => {
+            let screened = payee.as_deref().zip(screening.as_ref());
+            match withdraw(&db_transaction, &account, &request, screened).await? {
+                Withdrawal::Booked { transaction_id, fee: breakdown } => {
+                    booked_id = Some(transaction_id);
+                    fee = Some(*breakdown);
+                }
+                Withdrawal::Held { transaction_id } => booked_id = Some(transaction_id),
+                // The decline is kept on record and the request stays pending
+                Withdrawal::Declined => {
+                    db_transaction.commit().await?;
+                    return Err(AppError::TransactionDeclined);
+                }
+            }
+        }
+        ApprovalOperation::BalanceAdjustment => {
+            booked_id = Some(adjust(&db_transaction, &account, &request).await?);
+        }
+        ApprovalOperation::AccountClosure => {
+            let payout_account_id = request.details["payout_account_id"]
+                .as_str()
+                .and_then(|value| Uuid::parse_str(value).ok());
+            account_service::close_account(&db_transaction, actor_id, account.id, payout_account_id).await?;
+        }
+    }
+
+    let approved =
+        approval_queries::resolve_request(&db_transaction, id, ApprovalStatus::Approved, Some(actor_id), note, booked_id)
+            .await?
+            .ok_or_else(|| AppError::Conflict("Approval request is not pending".into()))?;
+
+    audit_queries::create_entry(
+        &db_transaction,
+        &NewAuditEntry::new(Some(actor_id), "approval_request.approved", "approval_request", Some(id))
+            .before(&request)
+            .after(&approved)
+            .details(json!({
+                "operation": approved.operation,
+                "transaction_id": approved.transaction_id,
+                "fee": fee.as_ref().map(|fee| fee.fee),
+            })),
+    )
+    .await?;
+
+    db_transaction.commit().await?;
+
+    metrics::record_approval_request(&approved.operation.to_string(), &approved.status.to_string());
+    tracing::info!(approval_request_id = %id, operation = %approved.operation, "Approval request approved");
+
+    Ok((approved, fee))
+}
+
+// What became of an approved withdrawal.
+enum Withdrawal {
+    // Completed with its fee charged
+    Booked { transaction_id: Uuid, fee: Box<FeeBreakdown> },
+    // Booked PENDING while its payee waits for a screening review
+    Held { transaction_id: Uuid },
+    // Refused by the risk or screening checks; nothing was booked
+    Declined,
+}
+
+// Books an approved withdrawal. The funds weren't held while the request
+// waited, so the checks a direct withdrawal goes through are made again,
+// `screening` being the payee's name and outcome. A failed check leaves the
+// request pending. The approval stands in for a risk review, so only a risk
+// block declines it; a payee needing review holds it for screening as a
+// direct withdrawal would be.
+async fn withdraw(
+    client: &impl GenericClient,
+    account: &Account,
+    request: &ApprovalRequest,
+    screening: Option<(&str, &ScreeningOutcome)>,
+) -> Result<Withdrawal, AppError> {
+    let transaction_request: CreateTransactionRequest = serde_json::from_value(json!({
+        "account_id": account.id,
+        "amount": request.amount,
+        "transaction_type": TransactionType::Withdrawal,
+        "description": request.details["description"],
+        "metadata": request.details["metadata"],
+        "tags": request.details["tags"],
+    }))
+    .map_err(|e| AppError::Internal(format!("Invalid withdrawal: {}", e)))?;
+    let amount = transaction_request.amount;
+
+    account.ensure_can_debit()?;
+    screening_service::ensure_not_restricted(client, account.user_id).await?;
+    limit_service::check_debit(client, account, amount, Utc::now()).await?;
+    kyc_service::check_transaction(client, account, amount, false, Utc::now()).await?;
+
+    let fee = fee_service::quote(client, FeeTransactionType::Withdrawal, &account.currency, amount).await?;
+    if account.balance < fee.net_amount {
+        metrics::record_insufficient_balance(&account.currency);
+        return Err(AppError::InsufficientFunds);
+    }
+
+    if let Some((name, outcome)) = screening {
+        match outcome.decision {
+            ScreeningDecision::Block => {
+                screening_service::record(
+                    client,
+                    ScreeningSubject::Counterparty,
+                    ScreeningTrigger::Withdrawal,
+                    Some(account.user_id),
+                    None,
+                    name,
+                    outcome,
+                )
+                .await?;
+                return Ok(Withdrawal::Declined);
+            }
+            ScreeningDecision::Review => {
+                let (transaction, updated) = account_service::hold(client, account, transaction_request).await?;
+                let result = screening_service::record(
+                    client,
+                    ScreeningSubject::Counterparty,
+                    ScreeningTrigger::Withdrawal,
+                    Some(account.user_id),
+                    Some(transaction.id),
+                    name,
+                    outcome,
+                )
+                .await?;
+                audit_queries::create_entry(
+                    client,
+                    &NewAuditEntry::new(request.requested_by, "account.withdrawal", "account", Some(account.id))
+                        .before(account)
+                        .after(&updated)
+                        .details(json!({
+                            "transaction_id": transaction.id,
+                            "amount": amount,
+                            "fee": fee.fee,
+                            "approval_request_id": request.id,
+                            "screening_result_id": result.id,
+                            "held_for_review": true,
+                        })),
+                )
+                .await?;
+                return Ok(Withdrawal::Held { transaction_id: transaction.id });
+            }
+            ScreeningDecision::Clear => {}
+        }
+    }
+
+    let decision = risk_service::assess(client, account, amount, None, Utc::now()).await?;
+    if decision.action == RiskAction::Block {
+        risk_service::record(client, account, None, None, amount, None, &decision).await?;
+        return Ok(Withdrawal::Declined);
+    }
+
+    let (transaction, updated) = account_service::book(client, account, transaction_request).await?;
+    let charged = fee_service::charge(client, &updated, &fee, transaction.id).await?.unwrap_or(updated);
+
+    let assessment = match decision.action {
+        RiskAction::Allow => {
+            risk_service::record(client, account, Some(transaction.id), None, amount, None, &decision).await?
+        }
+        _ => None,
+    };
+    let screening_result = match screening {
+        Some((name, outcome)) => Some(
+            screening_service::record(
+                client,
+                ScreeningSubject::Counterparty,
+                ScreeningTrigger::Withdrawal,
+                Some(account.user_id),
+                Some(transaction.id),
+                name,
+                outcome,
+            )
+            .await?,
+        ),
+        None => None,
+    };
+
+    audit_queries::create_entry(
+        client,
+        &NewAuditEntry::new(request.requested_by, "account.withdrawal", "account", Some(account.id))
+            .before(account)
+            .after(&charged)
+            .details(json!({
+                "transaction_id": transaction.id,
+                "amount": amount,
+                "fee": fee.fee,
+                "approval_request_id": request.id,
+                "risk_score": decision.score,
+                "matched_rules": decision.matched_rules,
+                "risk_assessment_id": assessment.as_ref().map(|assessment| assessment.id),
+                "screening_result_id": screening_result.as_ref().map(|result| result.id),
+                "held_for_review": false,
+            })),
+    )
+    .await?;
+
+    Ok(Withdrawal::Booked { transaction_id: transaction.id, fee: Box::new(fee) })
+}
+
+// Books an approved adjustment as a completed deposit or withdrawal and
+// returns the transaction's id. A debit the balance can't cover fails and the
+// request stays pending.
+async fn adjust(client: &impl GenericClient, account: &Account, request: &ApprovalRequest) -> Result<Uuid, AppError> {
+    let adjustment: BalanceAdjustmentRequest = serde_json::from_value(json!({
+        "direction": request.details["direction"],
+        "amount": request.amount,
+        "reason": request.details["reason"],
+    }))
+    .map_err(|e| AppError::Internal(format!("Invalid balance adjustment: {}", e)))?;
+
+    let transaction_type = match adjustment.direction {
+        AdjustmentDirection::Credit => {
+            account.ensure_can_credit()?;
+            TransactionType::Deposit
+        }
+        AdjustmentDirection::Debit => {
+            account.ensure_can_debit()?;
+            TransactionType::Withdrawal
+        }
+    };
+
+    let transaction_request = CreateTransactionRequest {
+        account_id: account.id,
+        amount: adjustment.amount,
+        transaction_type,
+        description: Some(adjustment.reason.clone()),
+        metadata: Some(json!({ "approval_request_id": request.id })),
+        tags: Some(vec![ADJUSTMENT_TRANSACTION_TAG.to_string()]),
+    };
+    let (transaction, updated) = account_service::book(client, account, transaction_request).await?;
+
+    audit_queries::create_entry(
+        client,
+        &NewAuditEntry::new(request.requested_by, "account.adjusted", "account", Some(account.id))
+            .before(account)
+            .after(&updated)
+            .details(json!({
+                "approval_request_id": request.id,
+                "transaction_id": transaction.id,
+                "direction": adjustment.direction,
+                "amount": adjustment.amount,
+                "reason": adjustment.reason,
+            })),
+    )
+    .await?;
+
+    Ok(transaction.id)
+}
+
+// Declines the request.
+pub async fn reject(
+    client: &mut Client,
+    actor_id: Uuid,
+    id: Uuid,
+    note: Option<&str>,
+) -> Result<ApprovalRequest, AppError> {
+    let db_transaction = client.transaction().await?;
+
+    let request = pending_request(&db_transaction, id).await?;
+    let account = account_queries::get_account_by_id(&db_transaction, request.account_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Account not found".into()))?;
+    ensure_checker(&request, account.user_id, actor_id)?;
+
+    let rejected = release(&db_transaction, &request, ApprovalStatus::Rejected, Some(actor_id), note).await?;
+
+    db_transaction.commit().await?;
+
+    metrics::record_approval_request(&rejected.operation.to_string(), &rejected.status.to_string());
+    tracing::info!(approval_request_id = %id, operation = %rejected.operation, "Approval request rejected");
+
+    Ok(rejected)
+}
+
+// Withdraws a request its maker no longer wants.
+pub async fn cancel(client: &mut Client, actor_id: Uuid, id: Uuid) -> Result<ApprovalRequest, AppError> {
+    let db_transaction = client.transaction().await?;
+
+    let request = pending_request(&db_transaction, id).await?;
+    if request.requested_by != Some(actor_id) {
+        return Err(AppError::Forbidden("Not allowed to cancel this approval request".into()));
+    }
+
+    let cancelled = release(&db_transaction, &request, ApprovalStatus::Cancelled, Some(actor_id), None).await?;
+
+    db_transaction.commit().await?;
+
+    metrics::record_approval_request(&cancelled.operation.to_string(), &cancelled.status.to_string());
+    tracing::info!(approval_request_id = %id, operation = %cancelled.operation, "Approval request cancelled");
+
+    Ok(cancelled)
+}
+
+// Expires a batch of requests that were not decided in time and returns how
+// many there were.
+pub async fn expire_due(client: &mut Client) -> Result<usize, AppError> {
+    let db_transaction = client.transaction().await?;
+
+    let requests = approval_queries::claim_expired(&db_transaction, Utc::now(), APPROVAL_EXPIRY_BATCH_SIZE).await?;
+    let mut expired = Vec::with_capacity(requests.len());
+    for request in &requests {
+        expired.push(release(&db_transaction, request, ApprovalStatus::Expired, None, None).await?);
+    }
+
+    db_transaction.commit().await?;
+
+    for request in &expired {
+        metrics::record_approval_request(&request.operation.to_string(), &request.status.to_string());
+        tracing::info!(approval_request_id = %request.id, operation = %request.operation, "Approval request expired");
+    }
+
+    Ok(expired.len())
+}
+
+// Closes a request without carrying it out. Nothing was booked for it, so
+// there is nothing to undo. `status` is REJECTED, CANCELLED or EXPIRED.
+async fn release(
+    client: &impl GenericClient,
+    request: &ApprovalRequest,
+    status: ApprovalStatus,
+    actor_id: Option<Uuid>,
+    note: Option<&str>,
+) -> Result<ApprovalRequest, AppError> {
+    let released = approval_queries::resolve_request(client, request.id, status, actor_id, note, None)
+        .await?
+        .ok_or_else(|| AppError::Conflict("Approval request is not pending".into()))?;
+
+    let action = match status {
+        ApprovalStatus::Rejected => "approval_request.rejected",
+        ApprovalStatus::Cancelled => "approval_request.cancelled",
+        _ => "approval_request.expired",
+    };
+    audit_queries::create_entry(
+        client,
+        &NewAuditEntry::new(actor_id, action, "approval_request", Some(request.id))
+            .before(request)
+            .after(&released)
+            .details(json!({ "operation": released.operation })),
+    )
+    .await?;
+
+    Ok(released)
+}
+
+// Adds a note to the request's discussion; decided requests can still be
+// commented on.
+pub async fn comment(
+    client: &mut Client,
+    actor_id: Uuid,
+    request: &ApprovalRequest,
+    body: &str,
+) -> Result<ApprovalComment, AppError> {
+    let db_transaction = client.transaction().await?;
+
+    let comment = approval_queries::create_comment(&db_transaction, request.id, actor_id, body).await?;
+
+    audit_queries::create_entry(
+        &db_transaction,
+        &NewAuditEntry::new(Some(actor_id), "approval_request.commented", "approval_request", Some(request.id))
+            .after(&comment),
+    )
+    .await?;
+
+    db_transaction.commit().await?;
+
+    Ok(comment)
+}
+
+async fn pending_request(client: &impl GenericClient, id: Uuid) -> Result<ApprovalRequest, AppError> {
+    let request = approval_queries::get_request_for_update(client, id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Approval request not found".into()))?;
+
+    if request.status != ApprovalStatus::Pending {
+        return Err(AppError::Conflict("Approval request is not pending".into()));
+    }
+    Ok(request)
+}
+
+// Maker-checker: neither the user who made a request nor the account's owner
+// can decide it, so an admin can't approve a change to their own account that
+// another admin filed.
+fn ensure_checker(request: &ApprovalRequest, owner_id: Uuid, actor_id: Uuid) -> Result<(), AppError> {
+    if request.requested_by == Some(actor_id) {
+        return Err(AppError::Forbidden("An approval request can't be decided by the user who made it".into()));
+    }
+    if owner_id == actor_id {
+        return Err(AppError::Forbidden("An approval request can't be decided by the account's owner".into()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(requested_by: Uuid) -> ApprovalRequest {
+        ApprovalRequest {
+            id: Uuid::new_v4(),
+            operation: ApprovalOperation::BalanceAdjustment,
+            status: ApprovalStatus::Pending,
+            account_id: Uuid::new_v4(),
+            requested_by: Some(requested_by),
+            amount: Some(Decimal::ONE_HUNDRED),
+            transaction_id: None,
+            details: json!({ "direction": "CREDIT", "reason": "Correction" }),
+            decided_by: None,
+            decided_at: None,
+            decision_note: None,
+            expires_at: Utc::now() + APPROVAL_REQUEST_TTL,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn checker_must_not_be_the_maker() {
+        let maker = Uuid::new_v4();
+
+        let result = ensure_checker(&request(maker), Uuid::new_v4(), maker);
+
+        assert!(matches!(result, Err(AppError::Forbidden(_))));
+    }
+
+    #[test]
+    fn checker_must_not_own_the_account() {
+        let owner = Uuid::new_v4();
+
+        let result = ensure_checker(&request(Uuid::new_v4()), owner, owner);
+
+        assert!(matches!(result, Err(AppError::Forbidden(_))));
+    }
+
+    #[test]
+    fn another_user_can_decide() {
+        assert!(ensure_checker(&request(Uuid::new_v4()), Uuid::new_v4(), Uuid::new_v4()).is_ok());
+    }
+}
@@ -0,0 +1,58 @@
+use deadpool_postgres::Client;
+use crate::{
+    base::{
+        audit::{entry_hash, GENESIS_HASH},
+        constants::AUDIT_VERIFY_BATCH_SIZE,
+        error::AppError,
+        models::audit::ChainReport,
+    },
+    db::dal::audit as audit_queries,
+};
+
+// Walks the audit log in chain order and checks that sequence numbers have no
+// gaps, that every entry links to the hash of the one before it and that its
+// own hash matches its content. Stops at the first broken entry.
+pub async fn verify_chain(client: &Client) -> Result<ChainReport, AppError> {
+    let mut report = ChainReport {
+        entries: 0,
+        head_seq: None,
+        head_hash: None,
+        first_broken_seq: None,
+        problem: None,
+    };
+    let mut previous_hash = GENESIS_HASH.to_string();
+    let mut previous_seq = 0;
+
+    loop {
+        let links = audit_queries::list_chain(client, previous_seq, AUDIT_VERIFY_BATCH_SIZE).await?;
+        if links.is_empty() {
+            break;
+        }
+
+        for link in &links {
+            let problem = if link.seq != previous_seq + 1 {
+                Some(format!("expected entry {}, found {}", previous_seq + 1, link.seq))
+            } else if link.prev_hash != previous_hash {
+                Some("prev_hash doesn't match the hash of the previous entry".to_string())
+            } else if entry_hash(link) != link.hash {
+                Some("hash doesn't match the entry's content".to_string())
+            } else {
+                None
+            };
+
+            if problem.is_some() {
+                report.first_broken_seq = Some(link.seq);
+                report.problem = problem;
+                return Ok(report);
+            }
+
+            report.entries += 1;
+            report.head_seq = Some(link.seq);
+            report.head_hash = Some(link.hash.clone());
+            previous_seq = link.seq;
+            previous_hash = link.hash.clone();
+        }
+    }
+
+    Ok(report)
+}
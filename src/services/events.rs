@@ -0,0 +1,234 @@
+use deadpool_postgres::{GenericClient, Pool};
+use futures_util::{stream, Stream};
+use serde_json::{json, Value};
+use std::{collections::VecDeque, sync::Arc};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+use crate::{
+    base::{
+        constants::{ACCOUNT_EVENT_BATCH_SIZE, ACCOUNT_EVENT_BUFFER},
+        error::AppError,
+        metrics,
+        models::{accounts::Account, events::AccountEvent, transactions::Transaction, webhooks::WebhookEventType},
+    },
+    db::dal::events as event_queries,
+    services::webhooks as webhook_service,
+};
+
+// Every change is published twice through the caller's database transaction:
+// to the webhook outbox of the owner and to the event stream of the account.
+
+pub async fn transaction_created(
+    client: &impl GenericClient,
+    user_id: Uuid,
+    transaction: &Transaction,
+) -> Result<(), AppError> {
+    publish(
+        client,
+        user_id,
+        transaction.account_id,
+        WebhookEventType::TransactionCreated,
+        json!({ "transaction": transaction }),
+    )
+    .await
+}
+
+pub async fn transaction_status_changed(
+    client: &impl GenericClient,
+    user_id: Uuid,
+    before: &Transaction,
+    after: &Transaction,
+) -> Result<(), AppError> {
+    publish(
+        client,
+        user_id,
+        after.account_id,
+        WebhookEventType::TransactionStatusChanged,
+        json!({ "transaction": after, "previous_status": before.status }),
+    )
+    .await
+}
+
+pub async fn balance_changed(
+    client: &impl GenericClient,
+    before: &Account,
+    after: &Account,
+    transaction_id: Uuid,
+) -> Result<(), AppError> {
+    publish(
+        client,
+        after.user_id,
+        after.id,
+        WebhookEventType::AccountBalanceChanged,
+        json!({
+            "account": after,
+            "previous_balance": before.balance,
+            "transaction_id": transaction_id,
+        }),
+    )
+    .await
+}
+
+async fn publish(
+    client: &impl GenericClient,
+    user_id: Uuid,
+    account_id: Uuid,
+    event_type: WebhookEventType,
+    data: Value,
+) -> Result<(), AppError> {
+    event_queries::create_event(client, account_id, &event_type.to_string(), &data).await?;
+    webhook_service::publish(client, user_id, event_type, data).await
+}
+
+// Fans committed account events out to the streams open on this replica. The
+// listener worker feeds it from Postgres notifications, so events written by
+// any replica reach every subscriber.
+#[derive(Clone)]
+pub struct AccountEventHub {
+    sender: broadcast::Sender<Arc<AccountEvent>>,
+    shutdown: CancellationToken,
+}
+
+impl AccountEventHub {
+    // Open streams end once `shutdown` is cancelled, so they don't hold up a
+    // graceful shutdown.
+    pub fn new(shutdown: CancellationToken) -> Self {
+        let (sender, _) = broadcast::channel(ACCOUNT_EVENT_BUFFER);
+        Self { sender, shutdown }
+    }
+
+    pub fn broadcast(&self, event: AccountEvent) {
+        // Nobody listening is not an error
+        let _ = self.sender.send(Arc::new(event));
+    }
+
+    // Events of the account from now on, preceded by those after
+    // `last_event_id` when resuming.
+    pub async fn subscribe(
+        &self,
+        pool: &Pool,
+        account_id: Uuid,
+        last_event_id: Option<i64>,
+    ) -> Result<AccountEventFeed, AppError> {
+        // Subscribe before reading the resume point, so that nothing committed
+        // in between is lost
+        let receiver = self.sender.subscribe();
+
+        let cursor = match last_event_id {
+            Some(id) => id,
+            None => {
+                let client = pool.get().await?;
+                event_queries::latest_event_id(&client, Some(account_id)).await?
+            }
+        };
+
+        metrics::record_account_event_stream_opened();
+
+        Ok(AccountEventFeed {
+            pool: pool.clone(),
+            account_id,
+            receiver,
+            shutdown: self.shutdown.clone(),
+            cursor,
+            skip_up_to: 0,
+            catching_up: last_event_id.is_some(),
+            pending: VecDeque::new(),
+            finished: false,
+        })
+    }
+}
+
+// One subscriber's view of an account's events, in id order. Replays stored
+// events while catching up, then follows the hub; a subscriber that falls too
+// far behind the hub catches up from the database again.
+pub struct AccountEventFeed {
+    pool: Pool,
+    account_id: Uuid,
+    receiver: broadcast::Receiver<Arc<AccountEvent>>,
+    shutdown: CancellationToken,
+    // Id of the newest event handed out
+    cursor: i64,
+    // Live events up to here were already replayed from the database
+    skip_up_to: i64,
+    catching_up: bool,
+    pending: VecDeque<AccountEvent>,
+    finished: bool,
+}
+
+impl AccountEventFeed {
+    // `None` once the server shuts down or after an error.
+    pub async fn next(&mut self) -> Option<Result<AccountEvent, AppError>> {
+        if self.finished || self.shutdown.is_cancelled() {
+            return None;
+        }
+
+        match self.next_event().await {
+            Ok(Some(event)) => {
+                self.cursor = self.cursor.max(event.id);
+                Some(Ok(event))
+            }
+            Ok(None) => {
+                self.finished = true;
+                None
+            }
+            Err(e) => {
+                self.finished = true;
+                Some(Err(e))
+            }
+        }
+    }
+
+    pub fn into_stream(self) -> impl Stream<Item = Result<AccountEvent, AppError>> {
+        stream::unfold(self, |mut feed| async move { feed.next().await.map(|item| (item, feed)) })
+    }
+
+    async fn next_event(&mut self) -> Result<Option<AccountEvent>, AppError> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Ok(Some(event));
+            }
+
+            if self.catching_up {
+                let client = self.pool.get().await?;
+                let events = event_queries::list_account_events_after(
+                    &client,
+                    self.account_id,
+                    self.cursor,
+                    ACCOUNT_EVENT_BATCH_SIZE,
+                )
+                .await?;
+
+                if (events.len() as i64) < ACCOUNT_EVENT_BATCH_SIZE {
+                    self.catching_up = false;
+                }
+                if let Some(last) = events.last() {
+                    self.skip_up_to = self.skip_up_to.max(last.id);
+                }
+                self.pending.extend(events);
+                continue;
+            }
+
+            tokio::select! {
+                _ = self.shutdown.cancelled() => return Ok(None),
+                received = self.receiver.recv() => match received {
+                    Ok(event) if event.account_id == self.account_id && event.id > self.skip_up_to => {
+                        return Ok(Some(AccountEvent::clone(&event)));
+                    }
+                    Ok(_) => {}
+                    Err(RecvError::Lagged(missed)) => {
+                        tracing::warn!(account_id = %self.account_id, missed, "Account event subscriber fell behind");
+                        self.catching_up = true;
+                    }
+                    Err(RecvError::Closed) => return Ok(None),
+                },
+            }
+        }
+    }
+}
+
+impl Drop for AccountEventFeed {
+    fn drop(&mut self) {
+        metrics::record_account_event_stream_closed();
+    }
+}
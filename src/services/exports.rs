@@ -0,0 +1,42 @@
+use chrono::Utc;
+use deadpool_postgres::Client;
+use tokio_postgres::IsolationLevel;
+use crate::{
+    base::{
+        error::AppError,
+        exports::{self, ExportData},
+        models::exports::DataExport,
+    },
+    db::dal::{
+        accounts as account_queries, consents as consent_queries, kyc as kyc_queries, transactions as transaction_queries,
+        users as user_queries,
+    },
+};
+
+// Builds the archive of an export. All datasets are read from one snapshot so
+// they agree with each other, e.g. no transaction refers to a missing account.
+pub async fn build_archive(client: &mut Client, export: &DataExport) -> Result<Vec<u8>, AppError> {
+    let snapshot = client
+        .build_transaction()
+        .isolation_level(IsolationLevel::RepeatableRead)
+        .read_only(true)
+        .start()
+        .await?;
+
+    let user = user_queries::get_user_by_id(&snapshot, export.user_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("User not found".into()))?;
+
+    let data = ExportData {
+        accounts: account_queries::get_all_accounts_by_user_id(&snapshot, user.id).await?,
+        transactions: transaction_queries::list_transactions_by_user_id(&snapshot, user.id).await?,
+        login_events: user_queries::list_login_events(&snapshot, user.id).await?,
+        consents: consent_queries::list_consents(&snapshot, user.id).await?,
+        kyc_documents: kyc_queries::list_documents(&snapshot, user.id).await?,
+        user,
+    };
+
+    snapshot.commit().await?;
+
+    exports::build_archive(export.id, &data, Utc::now()).map_err(AppError::Internal)
+}
@@ -0,0 +1,94 @@
+use deadpool_postgres::GenericClient;
+use rust_decimal::Decimal;
+use serde_json::json;
+use uuid::Uuid;
+use crate::{
+    base::{
+        constants::FEE_TRANSACTION_TAG,
+        error::AppError,
+        fees,
+        models::{
+            accounts::Account,
+            fees::{FeeBreakdown, FeeTransactionType},
+            transactions::{CreateTransactionRequest, TransactionStatus, TransactionType, UpdateTransactionStatusRequest},
+        },
+    },
+    db::dal::{accounts as account_queries, fees as fee_queries, transactions as transaction_queries},
+    services::{accounts as account_service, events as event_service},
+};
+
+// The fee the active schedule for `currency` and `transaction_type` charges
+// for `amount`; zero when there is none.
+pub async fn quote(
+    client: &impl GenericClient,
+    transaction_type: FeeTransactionType,
+    currency: &str,
+    amount: Decimal,
+) -> Result<FeeBreakdown, AppError> {
+    let schedule = fee_queries::get_active_schedule(client, currency, transaction_type).await?;
+    Ok(fees::calculate(schedule.as_ref(), transaction_type, currency, amount))
+}
+
+// Takes `fee` from `account` and credits it to the schedule's revenue account,
+// as two completed transactions linked to `transaction_id` through their
+// metadata. `account` must be locked by the caller and reflect the transaction
+// the fee is for. Returns the account after the fee, or `None` when there was
+// nothing to charge.
+pub async fn charge(
+    client: &impl GenericClient,
+    account: &Account,
+    fee: &FeeBreakdown,
+    transaction_id: Uuid,
+) -> Result<Option<Account>, AppError> {
+    let Some(revenue_account_id) = fee.revenue_account_id.filter(|_| fee.fee > Decimal::ZERO) else {
+        return Ok(None);
+    };
+    let metadata = json!({ "fee_for_transaction_id": transaction_id, "fee_schedule_id": fee.fee_schedule_id });
+
+    let request = CreateTransactionRequest {
+        account_id: account.id,
+        amount: fee.fee,
+        transaction_type: TransactionType::Withdrawal,
+        description: Some(format!("Fee for transaction {}", transaction_id)),
+        metadata: Some(metadata.clone()),
+        tags: Some(vec![FEE_TRANSACTION_TAG.to_string()]),
+    };
+    let completed = UpdateTransactionStatusRequest { status: TransactionStatus::Completed };
+
+    let debit = transaction_queries::create_transaction(client, &request).await?;
+    let debit = transaction_queries::update_transaction_status(client, debit.id, &completed)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Transaction not found".into()))?;
+    let charged = account_queries::deduct_fee(client, account.id, fee.fee)
+        .await?
+        .ok_or(AppError::InsufficientFunds)?;
+
+    event_service::transaction_created(client, account.user_id, &debit).await?;
+    event_service::balance_changed(client, account, &charged, debit.id).await?;
+
+    // A revenue account that can no longer take fees is a configuration
+    // problem, not something the customer can fix
+    let revenue_account = account_queries::get_account_for_update(client, revenue_account_id)
+        .await?
+        .filter(|revenue_account| revenue_account.ensure_can_credit().is_ok())
+        .ok_or_else(|| {
+            tracing::error!(%revenue_account_id, fee_schedule_id = ?fee.fee_schedule_id, "Fee revenue account is unavailable");
+            AppError::Unavailable("Fee revenue account is unavailable".into())
+        })?;
+
+    let credit = CreateTransactionRequest {
+        account_id: revenue_account_id,
+        amount: fee.fee,
+        transaction_type: TransactionType::Deposit,
+        description: Some(format!("Fee revenue from transaction {}", transaction_id)),
+        metadata: Some(metadata),
+        tags: Some(vec![FEE_TRANSACTION_TAG.to_string()]),
+    };
+    let (_, revenue_account_after) = account_service::book(client, &revenue_account, credit).await?;
+
+    // The revenue account may be the charged account itself
+    if revenue_account_id == account.id {
+        return Ok(Some(revenue_account_after));
+    }
+    Ok(Some(charged))
+}
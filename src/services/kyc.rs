@@ -0,0 +1,340 @@
+use chrono::{DateTime, Utc};
+use deadpool_postgres::{Client, GenericClient};
+use rust_decimal::Decimal;
+use serde_json::json;
+use uuid::Uuid;
+use crate::{
+    base::{
+        constants::{KYC_DOCUMENT_NUMBER_KEPT, MAX_KYC_DOCUMENTS},
+        error::AppError,
+        limits::DebitWindows,
+        metrics,
+        models::{
+            accounts::Account,
+            audit::NewAuditEntry,
+            kyc::{
+                KycApplication, KycApplicationStatus, KycDocument, KycDocumentStatus, KycProfile, KycStatus, KycTier,
+                KycTierCaps, SubmitKycDocumentRequest,
+            },
+        },
+    },
+    db::dal::{audit as audit_queries, kyc as kyc_queries, limits as limit_queries, users as user_queries},
+};
+
+pub async fn profile(client: &impl GenericClient, user_id: Uuid) -> Result<KycProfile, AppError> {
+    let user = user_queries::get_user_by_id(client, user_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("User not found".into()))?;
+
+    Ok(KycProfile {
+        user_id,
+        tier: user.kyc_tier,
+        status: user.kyc_status,
+        documents: kyc_queries::list_documents(client, user_id).await?,
+        applications: kyc_queries::list_applications_by_user(client, user_id).await?,
+        caps: kyc_queries::list_caps(client, Some(user.kyc_tier)).await?,
+    })
+}
+
+// Stores the metadata of an uploaded document. Only the last characters of
+// the document number are kept.
+pub async fn submit_document(
+    client: &mut Client,
+    user_id: Uuid,
+    request: &SubmitKycDocumentRequest,
+) -> Result<KycDocument, AppError> {
+    let db_transaction = client.transaction().await?;
+
+    user_queries::get_user_for_update(&db_transaction, user_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("User not found".into()))?;
+
+    if kyc_queries::count_documents(&db_transaction, user_id).await? >= MAX_KYC_DOCUMENTS {
+        return Err(AppError::Conflict(format!(
+            "At most {} documents can be submitted",
+            MAX_KYC_DOCUMENTS
+        )));
+    }
+
+    let last4 = request.document_number.as_deref().map(|number| {
+        let number: Vec<char> = number.trim().chars().collect();
+        number[number.len().saturating_sub(KYC_DOCUMENT_NUMBER_KEPT)..].iter().collect::<String>()
+    });
+    let document = kyc_queries::create_document(&db_transaction, user_id, request, last4.as_deref()).await?;
+
+    audit_queries::create_entry(
+        &db_transaction,
+        &NewAuditEntry::new(Some(user_id), "kyc_document.submitted", "kyc_document", Some(document.id))
+            .after(&document),
+    )
+    .await?;
+
+    db_transaction.commit().await?;
+
+    tracing::info!(user_id = %user_id, document_id = %document.id, document_type = %document.document_type, "KYC document submitted");
+
+    Ok(document)
+}
+
+// Applies for `tier` with the documents on file that weren't rejected and
+// haven't expired; they must include what the tier requires.
+pub async fn apply(client: &mut Client, user_id: Uuid, tier: KycTier) -> Result<KycApplication, AppError> {
+    let db_transaction = client.transaction().await?;
+
+    let user = user_queries::get_user_for_update(&db_transaction, user_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("User not found".into()))?;
+
+    if tier <= user.kyc_tier {
+        return Err(AppError::invalid_field("tier", "TIER_NOT_HIGHER", "tier must be above the current tier"));
+    }
+    if user.kyc_status == KycStatus::Pending {
+        return Err(AppError::Conflict("An application is already waiting for review".into()));
+    }
+
+    let today = Utc::now().date_naive();
+    let documents: Vec<KycDocument> = kyc_queries::list_documents(&db_transaction, user_id)
+        .await?
+        .into_iter()
+        .filter(|document| document.is_usable(today))
+        .collect();
+    for required in tier.required_documents() {
+        if !documents.iter().any(|document| required.contains(&document.document_type)) {
+            let types: Vec<String> = required.iter().map(|document_type| document_type.to_string()).collect();
+            return Err(AppError::invalid_field(
+                "tier",
+                "DOCUMENTS_MISSING",
+                &format!("a valid document of type {} is required", types.join(" or ")),
+            ));
+        }
+    }
+
+    let document_ids: Vec<Uuid> = documents.iter().map(|document| document.id).collect();
+    let application = kyc_queries::create_application(&db_transaction, user_id, tier, &document_ids).await?;
+    let updated = user_queries::set_kyc(&db_transaction, user_id, user.kyc_tier, KycStatus::Pending)
+        .await?
+        .ok_or_else(|| AppError::NotFound("User not found".into()))?;
+
+    audit_queries::create_entry(
+        &db_transaction,
+        &NewAuditEntry::new(Some(user_id), "kyc_application.submitted", "kyc_application", Some(application.id))
+            .before(&user.audit_snapshot())
+            .after(&updated.audit_snapshot())
+            .details(json!({ "requested_tier": tier, "document_ids": document_ids })),
+    )
+    .await?;
+
+    db_transaction.commit().await?;
+
+    metrics::record_kyc_application(&tier.to_string(), "PENDING");
+    tracing::info!(user_id = %user_id, application_id = %application.id, requested_tier = %tier, "KYC application submitted");
+
+    Ok(application)
+}
+
+// Approves an application: the user is verified to the requested tier and
+// its documents are marked verified.
+pub async fn approve(
+    client: &mut Client,
+    actor_id: Uuid,
+    id: Uuid,
+    note: Option<&str>,
+) -> Result<KycApplication, AppError> {
+    review(client, actor_id, id, note, KycApplicationStatus::Approved).await
+}
+
+// Rejects an application: the user keeps their tier and may apply again.
+pub async fn reject(
+    client: &mut Client,
+    actor_id: Uuid,
+    id: Uuid,
+    note: Option<&str>,
+) -> Result<KycApplication, AppError> {
+    review(client, actor_id, id, note, KycApplicationStatus::Rejected).await
+}
+
+async fn review(
+    client: &mut Client,
+    actor_id: Uuid,
+    id: Uuid,
+    note: Option<&str>,
+    status: KycApplicationStatus,
+) -> Result<KycApplication, AppError> {
+    let db_transaction = client.transaction().await?;
+
+    let application = kyc_queries::get_application_for_update(&db_transaction, id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("KYC application not found".into()))?;
+    if application.status != KycApplicationStatus::Pending {
+        return Err(AppError::Conflict("KYC application is not pending review".into()));
+    }
+    // Approving their own application would let an admin lift their own caps
+    if application.user_id == actor_id {
+        return Err(AppError::Forbidden("A KYC application can't be reviewed by the user who made it".into()));
+    }
+
+    let user = user_queries::get_user_for_update(&db_transaction, application.user_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("User not found".into()))?;
+
+    let (tier, user_status, document_status, action) = match status {
+        KycApplicationStatus::Approved => (
+            application.requested_tier,
+            KycStatus::Verified,
+            KycDocumentStatus::Verified,
+            "kyc_application.approved",
+        ),
+        _ => (user.kyc_tier, KycStatus::Rejected, KycDocumentStatus::Rejected, "kyc_application.rejected"),
+    };
+
+    let reviewed = kyc_queries::resolve_application(&db_transaction, id, status, actor_id, note)
+        .await?
+        .ok_or_else(|| AppError::Conflict("KYC application is not pending review".into()))?;
+    kyc_queries::set_documents_status(&db_transaction, &application.document_ids, document_status).await?;
+    let updated = user_queries::set_kyc(&db_transaction, user.id, tier, user_status)
+        .await?
+        .ok_or_else(|| AppError::NotFound("User not found".into()))?;
+
+    audit_queries::create_entry(
+        &db_transaction,
+        &NewAuditEntry::new(Some(actor_id), action, "kyc_application", Some(id))
+            .before(&application)
+            .after(&reviewed)
+            .details(json!({
+                "user_id": user.id,
+                "tier_before": user.kyc_tier,
+                "tier_after": updated.kyc_tier,
+            })),
+    )
+    .await?;
+
+    db_transaction.commit().await?;
+
+    metrics::record_kyc_application(&application.requested_tier.to_string(), &status.to_string());
+    tracing::info!(application_id = %id, user_id = %user.id, status = %status, tier = %updated.kyc_tier, "KYC application reviewed");
+
+    Ok(reviewed)
+}
+
+// Refuses a new or reopened account in `currency` once the user has as many
+// open accounts there as their tier allows, or when its `balance` would take
+// them over the balance they may hold.
+pub async fn check_account_opening(
+    client: &impl GenericClient,
+    user_id: Uuid,
+    currency: &str,
+    balance: Decimal,
+) -> Result<(), AppError> {
+    let Some(caps) = kyc_queries::get_caps_for_user(client, user_id, currency).await? else {
+        return Ok(());
+    };
+    let max_balance = caps.caps.max_balance.filter(|_| balance > Decimal::ZERO);
+    if caps.caps.max_accounts.is_none() && max_balance.is_none() {
+        return Ok(());
+    }
+
+    // Serializes the user's account openings so the totals can't be raced
+    limit_queries::lock_user_debits(client, user_id).await?;
+
+    if let Some(max) = caps.caps.max_accounts {
+        let open = kyc_queries::count_open_accounts(client, user_id, currency).await?;
+        if open >= i64::from(max) {
+            return Err(refuse(
+                &caps,
+                user_id,
+                "max_accounts",
+                format!("{} tier allows at most {} open {} accounts", caps.tier, max, currency),
+            ));
+        }
+    }
+    if let Some(max) = max_balance {
+        check_balance(client, &caps, user_id, currency, balance, max).await?;
+    }
+    Ok(())
+}
+
+// Refuses a transaction of `amount` on `account` that would go over the caps
+// of its owner's tier: the single transaction cap, the monthly volume over
+// all of their accounts in the currency and, when `credits_balance` is set,
+// the total balance they may hold in it.
+pub async fn check_transaction(
+    client: &impl GenericClient,
+    account: &Account,
+    amount: Decimal,
+    credits_balance: bool,
+    now: DateTime<Utc>,
+) -> Result<(), AppError> {
+    let Some(caps) = kyc_queries::get_caps_for_user(client, account.user_id, &account.currency).await? else {
+        return Ok(());
+    };
+    let currency = &account.currency;
+
+    if let Some(max) = caps.caps.max_single_transaction
+        && amount > max
+    {
+        return Err(refuse(
+            &caps,
+            account.user_id,
+            "max_single_transaction",
+            format!("{} tier allows at most {} {} per transaction", caps.tier, max, currency),
+        ));
+    }
+
+    let max_balance = caps.caps.max_balance.filter(|_| credits_balance);
+    if caps.caps.max_monthly_volume.is_none() && max_balance.is_none() {
+        return Ok(());
+    }
+
+    // Totals over the user's accounts are checked one transaction at a time
+    limit_queries::lock_user_debits(client, account.user_id).await?;
+
+    if let Some(max) = caps.caps.max_monthly_volume {
+        let month_start = DebitWindows::at(now).month_start;
+        let volume = kyc_queries::volume_since(client, account.user_id, currency, month_start).await?;
+        if volume + amount > max {
+            return Err(refuse(
+                &caps,
+                account.user_id,
+                "max_monthly_volume",
+                format!(
+                    "{} tier allows {} {} per month, {} {} remaining",
+                    caps.tier,
+                    max,
+                    currency,
+                    (max - volume).max(Decimal::ZERO),
+                    currency
+                ),
+            ));
+        }
+    }
+    if let Some(max) = max_balance {
+        check_balance(client, &caps, account.user_id, currency, amount, max).await?;
+    }
+    Ok(())
+}
+
+async fn check_balance(
+    client: &impl GenericClient,
+    caps: &KycTierCaps,
+    user_id: Uuid,
+    currency: &str,
+    amount: Decimal,
+    max: Decimal,
+) -> Result<(), AppError> {
+    let balance = kyc_queries::total_balance(client, user_id, currency).await?;
+    if balance + amount > max {
+        return Err(refuse(
+            caps,
+            user_id,
+            "max_balance",
+            format!("{} tier allows holding at most {} {}", caps.tier, max, currency),
+        ));
+    }
+    Ok(())
+}
+
+fn refuse(caps: &KycTierCaps, user_id: Uuid, cap: &'static str, message: String) -> AppError {
+    metrics::record_kyc_cap_rejection(&caps.tier.to_string(), cap);
+    tracing::info!(user_id = %user_id, tier = %caps.tier, cap, "Refused by KYC tier cap");
+    AppError::KycTierLimit(format!("{}, verify to a higher tier to raise it", message))
+}
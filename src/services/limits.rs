@@ -0,0 +1,65 @@
+use chrono::{DateTime, Utc};
+use deadpool_postgres::GenericClient;
+use rust_decimal::Decimal;
+use crate::{
+    base::{
+        error::AppError,
+        limits::{self, DebitWindows},
+        metrics,
+        models::{accounts::Account, limits::AccountLimits},
+    },
+    db::dal::limits as limit_queries,
+};
+
+// Refuses a debit of `amount` from `account` that would go over its limits or
+// those of its owner. The caller must hold the account lock, so debits of the
+// same account are checked one at a time; debits from the owner's other
+// accounts are serialized here when the owner has limits.
+pub async fn check_debit(
+    client: &impl GenericClient,
+    account: &Account,
+    amount: Decimal,
+    now: DateTime<Utc>,
+) -> Result<(), AppError> {
+    let (account_limits, user_limits) =
+        limit_queries::get_limits_for_account(client, account.id, account.user_id, &account.currency).await?;
+    let windows = DebitWindows::at(now);
+
+    let mut result = Ok(());
+    if !account_limits.is_unlimited() {
+        let usage = limit_queries::account_debit_usage(client, account.id, &windows).await?;
+        result = limits::check(&account_limits, &usage, amount, &account.currency, "the account");
+    }
+    if result.is_ok() && !user_limits.is_unlimited() {
+        limit_queries::lock_user_debits(client, account.user_id).await?;
+        let usage = limit_queries::user_debit_usage(client, account.user_id, &account.currency, &windows).await?;
+        result = limits::check(&user_limits, &usage, amount, &account.currency, "the account holder");
+    }
+
+    result.map_err(|breach| {
+        metrics::record_limit_rejection(breach.limit);
+        tracing::info!(account_id = %account.id, limit = breach.limit, "Debit refused by transaction limit");
+        AppError::LimitExceeded(breach.message)
+    })
+}
+
+// What the account and its owner can still debit in the current windows.
+pub async fn headroom(
+    client: &impl GenericClient,
+    account: &Account,
+    now: DateTime<Utc>,
+) -> Result<AccountLimits, AppError> {
+    let (account_limits, user_limits) =
+        limit_queries::get_limits_for_account(client, account.id, account.user_id, &account.currency).await?;
+    let windows = DebitWindows::at(now);
+
+    let account_usage = limit_queries::account_debit_usage(client, account.id, &windows).await?;
+    let user_usage = limit_queries::user_debit_usage(client, account.user_id, &account.currency, &windows).await?;
+
+    Ok(AccountLimits {
+        account_id: account.id,
+        currency: account.currency.clone(),
+        account: limits::headroom(&account_limits, &account_usage, &windows),
+        user: limits::headroom(&user_limits, &user_usage, &windows),
+    })
+}
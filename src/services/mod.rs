@@ -0,0 +1,16 @@
+// Operations shared by the HTTP handlers, background workers and CLI commands.
+pub mod accounts;
+pub mod aml;
+pub mod approvals;
+pub mod audit;
+pub mod events;
+pub mod exports;
+pub mod fees;
+pub mod kyc;
+pub mod limits;
+pub mod reconciliation;
+pub mod risk;
+pub mod scheduled_payments;
+pub mod screening;
+pub mod users;
+pub mod webhooks;
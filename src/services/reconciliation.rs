@@ -0,0 +1,228 @@
+use chrono::Days;
+use deadpool_postgres::{Client, GenericClient};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+use crate::{
+    base::{
+        constants::{RECONCILIATION_DATE_TOLERANCE_DAYS, RECONCILIATION_MAX_CANDIDATES},
+        error::AppError,
+        metrics,
+        models::{
+            audit::NewAuditEntry,
+            reconciliation::{
+                ImportReport, LineStatus, NewStatementImport, ReconciledLine, ReconciliationCandidate,
+                StatementFileFormat,
+            },
+            transactions::{TransactionStatus, TransactionType, UpdateTransactionStatusRequest},
+        },
+        reconciliation::{self as parser, ParsedEntry},
+        statements::signed_amount,
+    },
+    db::dal::{audit as audit_queries, reconciliation as reconciliation_queries, transactions as transaction_queries},
+    services::events as event_service,
+};
+
+// Parses a settlement file, matches every entry against `transactions` and
+// settles matched PENDING transactions. The whole import is one database
+// transaction, so a failure leaves no partial state behind.
+pub async fn import_statement(
+    client: &mut Client,
+    content: &str,
+    format: Option<StatementFileFormat>,
+    filename: Option<String>,
+    imported_by: Option<Uuid>,
+) -> Result<ImportReport, AppError> {
+    let format = format.unwrap_or_else(|| parser::detect_format(content));
+    let statement = parser::parse(format, content)
+        .map_err(|e| AppError::invalid_field("file", "PARSE_ERROR", &e))?;
+
+    let file_sha256 = format!("{:x}", Sha256::digest(content.as_bytes()));
+    if reconciliation_queries::get_import_by_sha256(&*client, &file_sha256).await?.is_some() {
+        return Err(AppError::Conflict("This statement file has already been imported".into()));
+    }
+
+    let db_transaction = client.transaction().await?;
+
+    let import = reconciliation_queries::create_import(
+        &db_transaction,
+        &NewStatementImport {
+            format,
+            filename,
+            file_sha256,
+            statement_reference: statement.statement_reference,
+            account_reference: statement.account_reference,
+            imported_by,
+        },
+    )
+    .await?;
+
+    let mut matched = 0;
+    for entry in &statement.entries {
+        let line = reconcile_entry(&db_transaction, entry).await?;
+        metrics::record_reconciliation_line(&line.status.to_string());
+        if line.status == LineStatus::Matched {
+            matched += 1;
+        }
+        reconciliation_queries::create_line(&db_transaction, import.id, &line).await?;
+    }
+
+    let import = reconciliation_queries::finish_import(
+        &db_transaction,
+        import.id,
+        statement.entries.len() as i32,
+        matched,
+    )
+    .await?;
+    let exceptions = reconciliation_queries::list_lines(&db_transaction, import.id, None, true).await?;
+
+    audit_queries::create_entry(
+        &db_transaction,
+        &NewAuditEntry::new(imported_by, "reconciliation.imported", "statement_import", Some(import.id)).details(json!({
+            "file_sha256": import.file_sha256,
+            "lines": import.line_count,
+            "matched": import.matched_count,
+            "exceptions": import.exception_count,
+        })),
+    )
+    .await?;
+
+    db_transaction.commit().await?;
+
+    tracing::info!(
+        import_id = %import.id,
+        lines = import.line_count,
+        matched = import.matched_count,
+        exceptions = import.exception_count,
+        "Statement imported"
+    );
+
+    Ok(ImportReport { import, exceptions })
+}
+
+// Identifies the transaction for an entry by the reference it carries, or
+// failing that by amount, direction and booking date when exactly one
+// unsettled transaction fits.
+async fn reconcile_entry(client: &impl GenericClient, entry: &ParsedEntry) -> Result<ReconciledLine, AppError> {
+    let reference = entry.transaction_reference();
+    let mut line = ReconciledLine {
+        line_number: entry.line_number,
+        booking_date: entry.booking_date,
+        amount: entry.amount,
+        currency: entry.currency.clone(),
+        reference: reference
+            .map(|id| id.to_string())
+            .or_else(|| entry.references.first().cloned()),
+        description: entry.description.clone(),
+        status: LineStatus::Unmatched,
+        transaction_id: None,
+        reason: None,
+    };
+
+    if !entry.booked {
+        line.reason = Some("Entry is not booked yet".into());
+        return Ok(line);
+    }
+
+    let candidate = match reference {
+        Some(id) => match reconciliation_queries::get_candidate_by_id(client, id).await? {
+            Some(candidate) => candidate,
+            None => {
+                line.reason = Some(format!("Referenced transaction {} does not exist", id));
+                return Ok(line);
+            }
+        },
+        None => {
+            let transaction_type = if entry.amount.is_sign_negative() {
+                TransactionType::Withdrawal
+            } else {
+                TransactionType::Deposit
+            };
+            let earliest = entry
+                .booking_date
+                .checked_sub_days(Days::new(RECONCILIATION_DATE_TOLERANCE_DAYS))
+                .unwrap_or(entry.booking_date);
+
+            let mut candidates = reconciliation_queries::find_candidates(
+                client,
+                entry.amount.abs(),
+                &transaction_type,
+                earliest,
+                entry.booking_date,
+                RECONCILIATION_MAX_CANDIDATES,
+            )
+            .await?;
+
+            match candidates.len() {
+                0 => {
+                    line.reason = Some("No reference and no unsettled transaction with this amount and date".into());
+                    return Ok(line);
+                }
+                1 => candidates.remove(0),
+                count => {
+                    line.reason = Some(format!(
+                        "No reference and {} unsettled transactions with this amount and date",
+                        count
+                    ));
+                    return Ok(line);
+                }
+            }
+        }
+    };
+
+    line.transaction_id = Some(candidate.transaction.id);
+
+    let problems = mismatches(&candidate, entry);
+    if !problems.is_empty() {
+        line.status = LineStatus::Mismatched;
+        line.reason = Some(problems.join("; "));
+        return Ok(line);
+    }
+
+    if matches!(candidate.transaction.status, TransactionStatus::Pending) {
+        let completed = UpdateTransactionStatusRequest { status: TransactionStatus::Completed };
+        let settled = transaction_queries::update_transaction_status(client, candidate.transaction.id, &completed)
+            .await?
+            .ok_or_else(|| AppError::NotFound("Transaction not found".into()))?;
+        event_service::transaction_status_changed(client, candidate.user_id, &candidate.transaction, &settled).await?;
+    }
+    line.status = LineStatus::Matched;
+
+    Ok(line)
+}
+
+fn mismatches(candidate: &ReconciliationCandidate, entry: &ParsedEntry) -> Vec<String> {
+    let transaction = &candidate.transaction;
+    let mut problems = Vec::new();
+
+    if candidate.reconciled {
+        problems.push("Transaction was already settled by another statement line".to_string());
+    }
+    if matches!(transaction.status, TransactionStatus::Failed) {
+        problems.push("Transaction is FAILED".to_string());
+    }
+
+    let expected = signed_amount(transaction);
+    if expected != entry.amount {
+        problems.push(format!("Amount {} differs from transaction amount {}", entry.amount, expected));
+    }
+
+    if let Some(currency) = &entry.currency
+        && !currency.eq_ignore_ascii_case(&candidate.currency)
+    {
+        problems.push(format!("Currency {} differs from account currency {}", currency, candidate.currency));
+    }
+
+    let created = transaction.created_at.date_naive();
+    let latest = created
+        .checked_add_days(Days::new(RECONCILIATION_DATE_TOLERANCE_DAYS))
+        .unwrap_or(created);
+    if entry.booking_date < created || entry.booking_date > latest {
+        problems.push(format!(
+            "Booking date {} is outside {}..={}",
+            entry.booking_date, created, latest
+        ));
+    }
+
+    problems
+}
@@ -0,0 +1,234 @@
+use chrono::{DateTime, Utc};
+use deadpool_postgres::{Client, GenericClient};
+use rust_decimal::Decimal;
+use serde_json::json;
+use uuid::Uuid;
+use crate::{
+    base::{
+        error::AppError,
+        metrics,
+        models::{
+            accounts::Account,
+            audit::NewAuditEntry,
+            fees::{FeeBreakdown, FeeTransactionType},
+            risk::{ReviewStatus, RiskAction, RiskAssessment, RiskDecision},
+            scheduled_payments::RunOutcome,
+            transactions::{CreateTransactionRequest, TransactionType},
+        },
+        risk,
+    },
+    db::dal::{
+        accounts as account_queries, audit as audit_queries, risk as risk_queries, scheduled_payments as schedule_queries,
+    },
+    services::{accounts as account_service, fees as fee_service},
+};
+
+// Scores a debit of `amount` from `account` with the enabled rules.
+// `ip_address` is the address of the request, if the debit comes from one.
+pub async fn assess(
+    client: &impl GenericClient,
+    account: &Account,
+    amount: Decimal,
+    ip_address: Option<&str>,
+    now: DateTime<Utc>,
+) -> Result<RiskDecision, AppError> {
+    let rules = risk::active_rules(risk_queries::list_rules(client).await?);
+    let (recent_since, history_since) = risk::fact_windows(&rules, now);
+    let facts = risk_queries::risk_facts(client, account, ip_address, recent_since, history_since).await?;
+
+    let decision = risk::evaluate(&rules, account, amount, &facts, now);
+    metrics::record_risk_decision(&decision.action.to_string());
+
+    Ok(decision)
+}
+
+// Stores the decision when any rule matched. `transaction_id` is the debit it
+// was made for, held PENDING when the action is REVIEW; there is none for a
+// blocked debit. `destination_account_id` is set for transfers.
+pub async fn record(
+    client: &impl GenericClient,
+    account: &Account,
+    transaction_id: Option<Uuid>,
+    destination_account_id: Option<Uuid>,
+    amount: Decimal,
+    ip_address: Option<&str>,
+    decision: &RiskDecision,
+) -> Result<Option<RiskAssessment>, AppError> {
+    if decision.matched_rules.is_empty() {
+        return Ok(None);
+    }
+
+    let assessment = risk_queries::create_assessment(
+        client,
+        account,
+        transaction_id,
+        destination_account_id,
+        amount,
+        ip_address,
+        decision,
+    )
+    .await?;
+
+    if assessment.action != RiskAction::Allow {
+        tracing::warn!(
+            risk_assessment_id = %assessment.id,
+            account_id = %account.id,
+            score = assessment.score,
+            action = %assessment.action,
+            "Debit flagged by risk checks"
+        );
+    }
+
+    Ok(Some(assessment))
+}
+
+// Releases a held debit: the transaction is completed, a transfer's deposit is
+// booked, and the fee is charged at the rates in force now. Returns the
+// reviewed assessment and the fee. A debit from an account that can no longer
+// be debited stays held until it is rejected.
+pub async fn approve(
+    client: &mut Client,
+    actor_id: Uuid,
+    id: Uuid,
+    note: Option<&str>,
+) -> Result<(RiskAssessment, FeeBreakdown), AppError> {
+    let db_transaction = client.transaction().await?;
+
+    let assessment = pending_review(&db_transaction, id, actor_id).await?;
+    let transaction_id = assessment
+        .transaction_id
+        .ok_or_else(|| AppError::NotFound("Transaction not found".into()))?;
+    let (source, target) =
+        lock_accounts(&db_transaction, assessment.account_id, assessment.destination_account_id).await?;
+    // The account may have been frozen while the debit was held
+    source.ensure_can_debit()?;
+
+    let transaction = account_service::complete_hold(&db_transaction, &source, transaction_id).await?;
+
+    let fee_type = if target.is_some() { FeeTransactionType::Transfer } else { FeeTransactionType::Withdrawal };
+    let fee = fee_service::quote(&db_transaction, fee_type, &source.currency, assessment.amount).await?;
+
+    let mut deposit_id = None;
+    if let Some(target) = &target {
+        target.ensure_can_credit()?;
+        let request = CreateTransactionRequest {
+            account_id: target.id,
+            amount: assessment.amount,
+            transaction_type: TransactionType::Deposit,
+            description: transaction.description.clone(),
+            metadata: None,
+            tags: None,
+        };
+        let (deposit, _) = account_service::book(&db_transaction, target, request).await?;
+        let outcome = RunOutcome::Succeeded { withdrawal_id: transaction_id, deposit_id: deposit.id };
+        schedule_queries::settle_run(&db_transaction, transaction_id, &outcome).await?;
+        deposit_id = Some(deposit.id);
+    }
+
+    fee_service::charge(&db_transaction, &source, &fee, transaction_id).await?;
+
+    let reviewed = risk_queries::resolve_review(&db_transaction, id, ReviewStatus::Approved, actor_id, note)
+        .await?
+        .ok_or_else(|| AppError::Conflict("Risk assessment is not pending review".into()))?;
+
+    audit_queries::create_entry(
+        &db_transaction,
+        &NewAuditEntry::new(Some(actor_id), "risk_assessment.approved", "risk_assessment", Some(id))
+            .before(&assessment)
+            .after(&reviewed)
+            .details(json!({
+                "transaction_id": transaction_id,
+                "deposit_transaction_id": deposit_id,
+                "fee": fee.fee,
+            })),
+    )
+    .await?;
+
+    db_transaction.commit().await?;
+
+    tracing::info!(risk_assessment_id = %id, %transaction_id, "Held debit approved");
+
+    Ok((reviewed, fee))
+}
+
+// Fails a held debit and returns the funds to the account. A held transfer's
+// scheduled payment run is marked as failed.
+pub async fn reject(
+    client: &mut Client,
+    actor_id: Uuid,
+    id: Uuid,
+    note: Option<&str>,
+) -> Result<RiskAssessment, AppError> {
+    let db_transaction = client.transaction().await?;
+
+    let assessment = pending_review(&db_transaction, id, actor_id).await?;
+    let transaction_id = assessment
+        .transaction_id
+        .ok_or_else(|| AppError::NotFound("Transaction not found".into()))?;
+    let source = account_queries::get_account_for_update(&db_transaction, assessment.account_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Account not found".into()))?;
+
+    account_service::return_hold(&db_transaction, &source, transaction_id).await?;
+
+    if assessment.destination_account_id.is_some() {
+        let outcome = RunOutcome::Failed { code: "REVIEW_REJECTED", reason: "Rejected in risk review".into() };
+        schedule_queries::settle_run(&db_transaction, transaction_id, &outcome).await?;
+    }
+
+    let reviewed = risk_queries::resolve_review(&db_transaction, id, ReviewStatus::Rejected, actor_id, note)
+        .await?
+        .ok_or_else(|| AppError::Conflict("Risk assessment is not pending review".into()))?;
+
+    audit_queries::create_entry(
+        &db_transaction,
+        &NewAuditEntry::new(Some(actor_id), "risk_assessment.rejected", "risk_assessment", Some(id))
+            .before(&assessment)
+            .after(&reviewed)
+            .details(json!({ "transaction_id": transaction_id })),
+    )
+    .await?;
+
+    db_transaction.commit().await?;
+
+    tracing::info!(risk_assessment_id = %id, %transaction_id, "Held debit rejected");
+
+    Ok(reviewed)
+}
+
+// An admin's own debits are scored like anyone else's; releasing one of them
+// themselves would skip the second look the score asked for.
+async fn pending_review(client: &impl GenericClient, id: Uuid, actor_id: Uuid) -> Result<RiskAssessment, AppError> {
+    let assessment = risk_queries::get_assessment_for_update(client, id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Risk assessment not found".into()))?;
+
+    if assessment.review_status != Some(ReviewStatus::Pending) {
+        return Err(AppError::Conflict("Risk assessment is not pending review".into()));
+    }
+    if assessment.user_id == actor_id {
+        return Err(AppError::Forbidden("A risk assessment can't be reviewed by the user it concerns".into()));
+    }
+    Ok(assessment)
+}
+
+// Locks both accounts of a transfer in a fixed order, like scheduled payments
+// do, so the two can't deadlock.
+async fn lock_accounts(
+    client: &impl GenericClient,
+    source_id: Uuid,
+    target_id: Option<Uuid>,
+) -> Result<(Account, Option<Account>), AppError> {
+    let not_found = || AppError::NotFound("Account not found".into());
+
+    let Some(target_id) = target_id else {
+        let source = account_queries::get_account_for_update(client, source_id).await?.ok_or_else(not_found)?;
+        return Ok((source, None));
+    };
+
+    let (first_id, second_id) = if source_id < target_id { (source_id, target_id) } else { (target_id, source_id) };
+    let first = account_queries::get_account_for_update(client, first_id).await?.ok_or_else(not_found)?;
+    let second = account_queries::get_account_for_update(client, second_id).await?.ok_or_else(not_found)?;
+
+    Ok(if first_id == source_id { (first, Some(second)) } else { (second, Some(first)) })
+}
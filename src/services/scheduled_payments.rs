@@ -0,0 +1,184 @@
+use chrono::{DateTime, Utc};
+use deadpool_postgres::{Client, GenericClient};
+use rust_decimal::Decimal;
+use serde_json::json;
+use uuid::Uuid;
+use crate::{
+    base::{
+        error::AppError,
+        metrics,
+        models::{
+            audit::NewAuditEntry,
+            fees::{FeeBreakdown, FeeTransactionType},
+            risk::RiskAction,
+            scheduled_payments::{RunOutcome, ScheduledPayment, ScheduledPaymentRun},
+            transactions::{CreateTransactionRequest, Transaction, TransactionType},
+        },
+    },
+    db::dal::{accounts as account_queries, audit as audit_queries, scheduled_payments as schedule_queries},
+    services::{
+        accounts as account_service, fees as fee_service, kyc as kyc_service, limits as limit_service, risk as risk_service,
+        screening as screening_service,
+    },
+};
+
+// Runs the schedule if it is due, in one database transaction: the transfer,
+// the run record and the move to the next occurrence commit together. The
+// schedule row stays locked meanwhile, and an occurrence can only be recorded
+// once, so each one is executed exactly once even with several schedulers.
+// A refused transfer (e.g. insufficient funds) is recorded as a failed run;
+// database errors leave the schedule due so it is tried again.
+// A transfer held by the risk checks is recorded as an IN_REVIEW run, which
+// the review settles later.
+pub async fn run_if_due(
+    client: &mut Client,
+    id: Uuid,
+    now: DateTime<Utc>,
+) -> Result<Option<ScheduledPaymentRun>, AppError> {
+    let mut db_transaction = client.transaction().await?;
+
+    let Some(schedule) = schedule_queries::claim_due_schedule(&db_transaction, id, now).await? else {
+        return Ok(None);
+    };
+    let scheduled_for = schedule.next_run_at.unwrap_or(now);
+
+    let savepoint = db_transaction.transaction().await?;
+    let mut fee = None;
+    let outcome = match execute(&savepoint, &schedule, now).await {
+        Ok(Execution::Transferred(withdrawal, deposit, charged)) => {
+            savepoint.commit().await?;
+            fee = Some(*charged);
+            RunOutcome::Succeeded { withdrawal_id: withdrawal.id, deposit_id: deposit.id }
+        }
+        Ok(Execution::Held(withdrawal)) => {
+            savepoint.commit().await?;
+            RunOutcome::InReview { withdrawal_id: withdrawal.id }
+        }
+        // Only the risk assessment was written
+        Ok(Execution::Declined) => {
+            savepoint.commit().await?;
+            let declined = AppError::TransactionDeclined;
+            RunOutcome::Failed { code: declined.code(), reason: declined.detail() }
+        }
+        Err(e) if e.status().is_server_error() => return Err(e),
+        Err(e) => {
+            savepoint.rollback().await?;
+            RunOutcome::Failed { code: e.code(), reason: e.detail() }
+        }
+    };
+
+    let run = schedule_queries::create_run(&db_transaction, schedule.id, scheduled_for, &outcome).await?;
+    let next_run_at = schedule.plan().next_run(now.max(scheduled_for), schedule.runs_count + 1);
+    let updated = schedule_queries::advance_schedule(&db_transaction, schedule.id, now, next_run_at).await?;
+
+    audit_queries::create_entry(
+        &db_transaction,
+        &NewAuditEntry::new(None, "scheduled_payment.run", "scheduled_payment", Some(schedule.id))
+            .before(&schedule)
+            .after(&updated)
+            .details(json!({ "run": run, "fee": fee.as_ref().map(|fee| fee.fee) })),
+    )
+    .await?;
+
+    db_transaction.commit().await?;
+
+    metrics::record_scheduled_payment_run(&run.status.to_string());
+    if let Some(fee) = fee.filter(|fee| fee.fee > Decimal::ZERO) {
+        metrics::record_fee(&fee.currency, &fee.transaction_type.to_string(), fee.fee);
+    }
+    match &outcome {
+        RunOutcome::Succeeded { .. } => tracing::info!(schedule_id = %schedule.id, %scheduled_for, "Scheduled payment executed"),
+        RunOutcome::Failed { code, .. } => tracing::warn!(schedule_id = %schedule.id, %scheduled_for, code, "Scheduled payment failed"),
+        RunOutcome::InReview { .. } => tracing::warn!(schedule_id = %schedule.id, %scheduled_for, "Scheduled payment held for review"),
+    }
+
+    Ok(Some(run))
+}
+
+enum Execution {
+    Transferred(Transaction, Transaction, Box<FeeBreakdown>),
+    // The withdrawal waits for a risk review; nothing is deposited yet
+    Held(Transaction),
+    Declined,
+}
+
+// Both accounts are checked again at every run, since their owner, status,
+// currency or balance may have changed since the schedule was created. The
+// transfer fee is charged to the source account.
+async fn execute(
+    client: &impl GenericClient,
+    schedule: &ScheduledPayment,
+    now: DateTime<Utc>,
+) -> Result<Execution, AppError> {
+    // Lock in a fixed order so concurrent transfers between the same two
+    // accounts can't deadlock
+    let (first_id, second_id) = if schedule.source_account_id < schedule.destination_account_id {
+        (schedule.source_account_id, schedule.destination_account_id)
+    } else {
+        (schedule.destination_account_id, schedule.source_account_id)
+    };
+    let first = account_queries::get_account_for_update(client, first_id).await?;
+    let second = account_queries::get_account_for_update(client, second_id).await?;
+    let (source, target) = if first_id == schedule.source_account_id { (first, second) } else { (second, first) };
+
+    let source = source.ok_or_else(|| AppError::NotFound("Source account not found".into()))?;
+    let target = target.ok_or_else(|| AppError::NotFound("Destination account not found".into()))?;
+
+    if source.user_id != schedule.user_id || target.user_id != schedule.user_id {
+        return Err(AppError::Forbidden("Not allowed to transfer between these accounts".into()));
+    }
+    if source.currency != target.currency {
+        return Err(AppError::Conflict("Accounts no longer have the same currency".into()));
+    }
+    source.ensure_can_debit()?;
+    target.ensure_can_credit()?;
+    screening_service::ensure_not_restricted(client, schedule.user_id).await?;
+    limit_service::check_debit(client, &source, schedule.amount, now).await?;
+    // The funds stay with the user, so only the transaction caps apply
+    kyc_service::check_transaction(client, &source, schedule.amount, false, now).await?;
+
+    let fee = fee_service::quote(client, FeeTransactionType::Transfer, &source.currency, schedule.amount).await?;
+    if source.balance < fee.net_amount {
+        metrics::record_insufficient_balance(&source.currency);
+        return Err(AppError::InsufficientFunds);
+    }
+
+    let description = schedule
+        .description
+        .clone()
+        .unwrap_or_else(|| format!("Scheduled payment {}", schedule.id));
+
+    let decision = risk_service::assess(client, &source, schedule.amount, None, now).await?;
+    match decision.action {
+        RiskAction::Block => {
+            risk_service::record(client, &source, None, Some(target.id), schedule.amount, None, &decision).await?;
+            return Ok(Execution::Declined);
+        }
+        RiskAction::Review => {
+            let request = CreateTransactionRequest {
+                account_id: source.id,
+                amount: schedule.amount,
+                transaction_type: TransactionType::Withdrawal,
+                description: Some(description),
+                metadata: None,
+                tags: None,
+            };
+            let (withdrawal, _) = account_service::hold(client, &source, request).await?;
+            risk_service::record(client, &source, Some(withdrawal.id), Some(target.id), schedule.amount, None, &decision)
+                .await?;
+            return Ok(Execution::Held(withdrawal));
+        }
+        RiskAction::Allow => {}
+    }
+
+    let (withdrawal, deposit) =
+        account_service::transfer(client, &source, &target, schedule.amount, description.clone(), description).await?;
+    risk_service::record(client, &source, Some(withdrawal.id), Some(target.id), schedule.amount, None, &decision).await?;
+
+    let source = account_queries::get_account_for_update(client, source.id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Source account not found".into()))?;
+    fee_service::charge(client, &source, &fee, withdrawal.id).await?;
+
+    Ok(Execution::Transferred(withdrawal, deposit, Box::new(fee)))
+}
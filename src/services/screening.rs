@@ -0,0 +1,326 @@
+use std::{collections::HashSet, sync::Arc};
+use deadpool_postgres::{Client, GenericClient};
+use serde_json::json;
+use tokio::sync::Mutex;
+use uuid::Uuid;
+use crate::{
+    base::{
+        error::AppError,
+        metrics,
+        models::{
+            accounts::Account,
+            audit::NewAuditEntry,
+            fees::{FeeBreakdown, FeeTransactionType},
+            screening::{
+                NewWatchlistEntry, RescreenReport, ScreeningDecision, ScreeningOutcome, ScreeningResult,
+                ScreeningReviewStatus, ScreeningSubject, ScreeningTrigger, WatchlistSource, WatchlistSummary,
+            },
+            transactions::Transaction,
+        },
+        screening::Watchlist,
+    },
+    db::dal::{
+        accounts as account_queries, audit as audit_queries, screening as screening_queries,
+        transactions as transaction_queries, users as user_queries,
+    },
+    services::{accounts as account_service, fees as fee_service},
+};
+
+// The loaded lists, built once and shared by every request. Each copy is
+// tagged with the watchlist version it was built at; a reload in any process
+// bumps the version, and the next screening here builds the lists again.
+#[derive(Clone, Default)]
+pub struct WatchlistCache {
+    current: Arc<Mutex<Option<BuiltWatchlist>>>,
+}
+
+struct BuiltWatchlist {
+    version: i64,
+    watchlist: Arc<Watchlist>,
+}
+
+impl WatchlistCache {
+    pub async fn get(&self, client: &impl GenericClient) -> Result<Arc<Watchlist>, AppError> {
+        let version = screening_queries::watchlist_version(client).await?;
+
+        let mut current = self.current.lock().await;
+        if let Some(built) = current.as_ref()
+            && built.version == version
+        {
+            return Ok(built.watchlist.clone());
+        }
+
+        let entries = screening_queries::list_watchlist_entries(client).await?;
+        let watchlist = Arc::new(blocking(move || Watchlist::new(entries)).await?);
+        *current = Some(BuiltWatchlist { version, watchlist: watchlist.clone() });
+
+        tracing::info!(version, "Watchlists loaded");
+
+        Ok(watchlist)
+    }
+}
+
+// Screens `name` against the loaded lists. For a user's own name, entries
+// already reviewed for `user_id` are left out so a cleared match doesn't come
+// back on every rescreen.
+pub async fn screen_name(
+    client: &impl GenericClient,
+    watchlists: &WatchlistCache,
+    name: &str,
+    subject: ScreeningSubject,
+    user_id: Option<Uuid>,
+) -> Result<ScreeningOutcome, AppError> {
+    let watchlist = watchlists.get(client).await?;
+
+    let skip = match (subject, user_id) {
+        (ScreeningSubject::User, Some(user_id)) => screening_queries::reviewed_entries(client, Some(user_id))
+            .await?
+            .remove(&user_id)
+            .unwrap_or_default(),
+        _ => HashSet::new(),
+    };
+
+    let name = name.to_owned();
+    blocking(move || watchlist.screen(&name, subject, &skip)).await
+}
+
+// Matching compares the name with every listed name, so it runs off the
+// async workers.
+async fn blocking<T: Send + 'static>(work: impl FnOnce() -> T + Send + 'static) -> Result<T, AppError> {
+    tokio::task::spawn_blocking(work)
+        .await
+        .map_err(|e| AppError::Internal(format!("Screening task failed: {}", e)))
+}
+
+// Stores the outcome. `user_id` is unset for a declined signup;
+// `transaction_id` is the withdrawal a counterparty was screened for, unset
+// when it was declined.
+pub async fn record(
+    client: &impl GenericClient,
+    subject: ScreeningSubject,
+    trigger: ScreeningTrigger,
+    user_id: Option<Uuid>,
+    transaction_id: Option<Uuid>,
+    name: &str,
+    outcome: &ScreeningOutcome,
+) -> Result<ScreeningResult, AppError> {
+    let result =
+        screening_queries::create_result(client, subject, trigger, user_id, transaction_id, name, outcome).await?;
+
+    metrics::record_screening(&trigger.to_string(), &outcome.decision.to_string());
+    if outcome.decision != ScreeningDecision::Clear {
+        tracing::warn!(
+            screening_result_id = %result.id,
+            subject_type = %subject,
+            trigger = %trigger,
+            score = outcome.score,
+            decision = %outcome.decision,
+            "Name matched a watchlist"
+        );
+    }
+
+    Ok(result)
+}
+
+// Users whose own name is waiting on a review or confirmed as a match can't
+// move money.
+pub async fn ensure_not_restricted(client: &impl GenericClient, user_id: Uuid) -> Result<(), AppError> {
+    if screening_queries::is_user_restricted(client, user_id).await? {
+        return Err(AppError::UserRestricted);
+    }
+    Ok(())
+}
+
+// Replaces the given lists in one transaction; lists not given are kept.
+// Bumping the version makes every replica rebuild its copy on its next
+// screening; `watchlists` is rebuilt right away.
+pub async fn reload_watchlists(
+    client: &mut Client,
+    watchlists: &WatchlistCache,
+    lists: Vec<(WatchlistSource, Vec<NewWatchlistEntry>)>,
+) -> Result<Vec<WatchlistSummary>, AppError> {
+    let db_transaction = client.transaction().await?;
+
+    let before = screening_queries::watchlist_summary(&db_transaction).await?;
+    let mut loaded = Vec::new();
+    for (source, entries) in &lists {
+        let inserted = screening_queries::replace_watchlist(&db_transaction, *source, entries).await?;
+        loaded.push(json!({ "source": source, "entries": inserted }));
+    }
+    let after = screening_queries::watchlist_summary(&db_transaction).await?;
+    let version = screening_queries::bump_watchlist_version(&db_transaction).await?;
+
+    audit_queries::create_entry(
+        &db_transaction,
+        &NewAuditEntry::new(None, "watchlist.reloaded", "watchlist", None)
+            .before(&before)
+            .after(&after)
+            .details(json!({ "lists": loaded, "version": version })),
+    )
+    .await?;
+
+    db_transaction.commit().await?;
+
+    tracing::info!(lists = lists.len(), version, "Watchlists reloaded");
+
+    watchlists.get(&*client).await?;
+
+    Ok(after)
+}
+
+// Screens every user's name again, e.g. after the lists were reloaded. Only
+// new matches are stored, each waiting on a review.
+pub async fn rescreen_users(client: &impl GenericClient, watchlists: &WatchlistCache) -> Result<RescreenReport, AppError> {
+    let watchlist = watchlists.get(client).await?;
+    let mut reviewed = screening_queries::reviewed_entries(client, None).await?;
+    let users = user_queries::list_user_names(client).await?;
+    let mut report = RescreenReport::default();
+
+    let screened = blocking(move || {
+        users
+            .into_iter()
+            .map(|(user_id, name)| {
+                let skip = reviewed.remove(&user_id).unwrap_or_default();
+                let outcome = watchlist.screen(&name, ScreeningSubject::User, &skip);
+                (user_id, name, outcome)
+            })
+            .collect::<Vec<_>>()
+    })
+    .await?;
+
+    for (user_id, name, outcome) in screened {
+        report.screened += 1;
+
+        match outcome.decision {
+            ScreeningDecision::Clear => continue,
+            ScreeningDecision::Review => report.review += 1,
+            ScreeningDecision::Block => report.block += 1,
+        }
+        record(client, ScreeningSubject::User, ScreeningTrigger::Rescreen, Some(user_id), None, &name, &outcome).await?;
+    }
+
+    tracing::info!(screened = report.screened, review = report.review, block = report.block, "Users rescreened");
+
+    Ok(report)
+}
+
+// Clears a match as a false positive. A held withdrawal is completed and its
+// fee charged at the rates in force now; for a user, the restriction lifts
+// once no other review of their name is pending or confirmed. Returns the
+// withdrawal and fee when there was one.
+pub async fn clear(
+    client: &mut Client,
+    actor_id: Uuid,
+    id: Uuid,
+    note: Option<&str>,
+) -> Result<(ScreeningResult, Option<(Transaction, FeeBreakdown)>), AppError> {
+    let db_transaction = client.transaction().await?;
+
+    let result = pending_review(&db_transaction, id, actor_id).await?;
+
+    let mut released = None;
+    if let Some(transaction_id) = result.transaction_id {
+        let account = held_account(&db_transaction, transaction_id, actor_id).await?;
+        // The account may have been frozen while the withdrawal was held
+        account.ensure_can_debit()?;
+        let transaction = account_service::complete_hold(&db_transaction, &account, transaction_id).await?;
+        let fee =
+            fee_service::quote(&db_transaction, FeeTransactionType::Withdrawal, &account.currency, transaction.amount)
+                .await?;
+        fee_service::charge(&db_transaction, &account, &fee, transaction_id).await?;
+        released = Some((transaction, fee));
+    }
+
+    let reviewed =
+        screening_queries::resolve_review(&db_transaction, id, ScreeningReviewStatus::Cleared, actor_id, note)
+            .await?
+            .ok_or_else(|| AppError::Conflict("Screening result is not pending review".into()))?;
+
+    audit_queries::create_entry(
+        &db_transaction,
+        &NewAuditEntry::new(Some(actor_id), "screening_result.cleared", "screening_result", Some(id))
+            .before(&result)
+            .after(&reviewed)
+            .details(json!({
+                "transaction_id": result.transaction_id,
+                "fee": released.as_ref().map(|(_, fee)| fee.fee),
+            })),
+    )
+    .await?;
+
+    db_transaction.commit().await?;
+
+    tracing::info!(screening_result_id = %id, "Screening match cleared");
+
+    Ok((reviewed, released))
+}
+
+// Confirms a match. A held withdrawal is failed and the funds returned; a user
+// stays restricted.
+pub async fn confirm(
+    client: &mut Client,
+    actor_id: Uuid,
+    id: Uuid,
+    note: Option<&str>,
+) -> Result<ScreeningResult, AppError> {
+    let db_transaction = client.transaction().await?;
+
+    let result = pending_review(&db_transaction, id, actor_id).await?;
+
+    if let Some(transaction_id) = result.transaction_id {
+        let account = held_account(&db_transaction, transaction_id, actor_id).await?;
+        account_service::return_hold(&db_transaction, &account, transaction_id).await?;
+    }
+
+    let reviewed =
+        screening_queries::resolve_review(&db_transaction, id, ScreeningReviewStatus::Confirmed, actor_id, note)
+            .await?
+            .ok_or_else(|| AppError::Conflict("Screening result is not pending review".into()))?;
+
+    audit_queries::create_entry(
+        &db_transaction,
+        &NewAuditEntry::new(Some(actor_id), "screening_result.confirmed", "screening_result", Some(id))
+            .before(&result)
+            .after(&reviewed)
+            .details(json!({ "transaction_id": result.transaction_id })),
+    )
+    .await?;
+
+    db_transaction.commit().await?;
+
+    tracing::warn!(screening_result_id = %id, subject_type = %result.subject_type, "Screening match confirmed");
+
+    Ok(reviewed)
+}
+
+// A possible sanctions match on an admin, or on a payee they named, is cleared
+// by someone else: nobody vouches for their own name.
+async fn pending_review(client: &impl GenericClient, id: Uuid, actor_id: Uuid) -> Result<ScreeningResult, AppError> {
+    let result = screening_queries::get_result_for_update(client, id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Screening result not found".into()))?;
+
+    if result.review_status != Some(ScreeningReviewStatus::Pending) {
+        return Err(AppError::Conflict("Screening result is not pending review".into()));
+    }
+    if result.user_id == Some(actor_id) {
+        return Err(AppError::Forbidden("A screening result can't be reviewed by the user it concerns".into()));
+    }
+    Ok(result)
+}
+
+// The locked account a held withdrawal was taken from, which mustn't belong
+// to the reviewer.
+async fn held_account(client: &impl GenericClient, transaction_id: Uuid, actor_id: Uuid) -> Result<Account, AppError> {
+    let transaction = transaction_queries::get_transaction_for_update(client, transaction_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Transaction not found".into()))?;
+
+    let account = account_queries::get_account_for_update(client, transaction.account_id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("Account not found".into()))?;
+    if account.user_id == actor_id {
+        return Err(AppError::Forbidden("A screening result can't be reviewed by the user it concerns".into()));
+    }
+    Ok(account)
+}
@@ -0,0 +1,59 @@
+use deadpool_postgres::Client;
+use rust_decimal::Decimal;
+use serde_json::json;
+use uuid::Uuid;
+use crate::{
+    base::{error::AppError, models::audit::NewAuditEntry},
+    db::dal::{
+        accounts as account_queries, audit as audit_queries, consents as consent_queries, exports as export_queries,
+        kyc as kyc_queries, scheduled_payments as schedule_queries, users as user_queries, webhooks as webhook_queries,
+    },
+};
+
+// Erases a user: blocked while any account holds funds, otherwise the
+// remaining accounts are closed and the user's name, email and password hash
+// are replaced. Accounts and transactions are kept for record keeping; login
+// history, data export archives, KYC documents and webhooks are deleted, and scheduled
+// payments are cancelled.
+pub async fn erase_user(client: &mut Client, id: Uuid, actor_id: Uuid) -> Result<(), AppError> {
+    let db_transaction = client.transaction().await?;
+
+    let accounts = account_queries::lock_accounts_by_user_id(&db_transaction, id).await?;
+    let funded: Vec<String> = accounts
+        .iter()
+        .filter(|account| account.balance != Decimal::ZERO)
+        .map(|account| account.id.to_string())
+        .collect();
+    if !funded.is_empty() {
+        return Err(AppError::Conflict(format!(
+            "Accounts still hold funds, withdraw or pay out their balance first: {}",
+            funded.join(", ")
+        )));
+    }
+
+    let closed_accounts = account_queries::close_accounts_by_user_id(&db_transaction, id).await?;
+
+    user_queries::erase_user(&db_transaction, id)
+        .await?
+        .ok_or_else(|| AppError::NotFound("User not found".into()))?;
+
+    user_queries::delete_login_events(&db_transaction, id).await?;
+    export_queries::delete_exports_by_user_id(&db_transaction, id).await?;
+    webhook_queries::delete_webhooks_by_user_id(&db_transaction, id).await?;
+    schedule_queries::cancel_schedules_by_user_id(&db_transaction, id).await?;
+    consent_queries::clear_ip_addresses(&db_transaction, id).await?;
+    kyc_queries::delete_by_user_id(&db_transaction, id).await?;
+
+    audit_queries::create_entry(
+        &db_transaction,
+        &NewAuditEntry::new(Some(actor_id), "user.erased", "user", Some(id))
+            .details(json!({ "closed_accounts": closed_accounts })),
+    )
+    .await?;
+
+    db_transaction.commit().await?;
+
+    tracing::info!(user_id = %id, closed_accounts, "User erased");
+
+    Ok(())
+}
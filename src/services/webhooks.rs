@@ -0,0 +1,22 @@
+use deadpool_postgres::GenericClient;
+use serde_json::Value;
+use uuid::Uuid;
+use crate::{
+    base::{
+        error::AppError,
+        models::webhooks::WebhookEventType,
+    },
+    db::dal::webhooks as webhook_queries,
+};
+
+// Events go to the outbox through the caller's database transaction, so they
+// are delivered exactly when the change they describe is committed.
+pub async fn publish(
+    client: &impl GenericClient,
+    user_id: Uuid,
+    event_type: WebhookEventType,
+    data: Value,
+) -> Result<(), AppError> {
+    webhook_queries::create_event(client, user_id, &event_type.to_string(), &data).await?;
+    Ok(())
+}
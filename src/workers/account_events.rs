@@ -0,0 +1,143 @@
+use chrono::Utc;
+use deadpool_postgres::Pool;
+use futures_util::{stream, StreamExt};
+use tokio::sync::mpsc;
+use tokio_postgres::{AsyncMessage, NoTls, Notification};
+use tokio_util::sync::CancellationToken;
+use crate::{
+    base::{
+        constants::{
+            ACCOUNT_EVENTS_CHANNEL, ACCOUNT_EVENT_BATCH_SIZE, ACCOUNT_EVENT_PRUNE_INTERVAL,
+            ACCOUNT_EVENT_RECONNECT_DELAY, ACCOUNT_EVENT_RETENTION,
+        },
+        error::AppError,
+    },
+    db::dal::events as event_queries,
+    services::events::AccountEventHub,
+};
+
+// Listens for committed account events on a dedicated connection, since a
+// pooled one can't hold a LISTEN, and hands them to the hub. After a lost
+// connection it reconnects and catches up on the events it missed. Also
+// prunes events past their retention.
+pub async fn run(database_url: String, pool: Pool, hub: AccountEventHub, token: CancellationToken) {
+    let mut last_seen = None;
+
+    loop {
+        match listen(&database_url, &pool, &hub, &token, &mut last_seen).await {
+            Ok(()) => break,
+            Err(e) => tracing::error!(error = %e, "Account event listener failed, reconnecting"),
+        }
+
+        tokio::select! {
+            _ = token.cancelled() => break,
+            _ = tokio::time::sleep(ACCOUNT_EVENT_RECONNECT_DELAY) => {}
+        }
+    }
+}
+
+// Returns once `token` is cancelled, or with the error that ended the connection.
+async fn listen(
+    database_url: &str,
+    pool: &Pool,
+    hub: &AccountEventHub,
+    token: &CancellationToken,
+    last_seen: &mut Option<i64>,
+) -> Result<(), AppError> {
+    let (client, mut connection) = tokio_postgres::connect(database_url, NoTls).await?;
+
+    // Notifications arrive through the connection, which must be polled for
+    // any query on the client to make progress
+    let (sender, mut notifications) = mpsc::unbounded_channel::<Notification>();
+    let driver = tokio::spawn(async move {
+        let mut messages = stream::poll_fn(move |cx| connection.poll_message(cx));
+        while let Some(message) = messages.next().await {
+            if let AsyncMessage::Notification(notification) = message?
+                && sender.send(notification).is_err()
+            {
+                break;
+            }
+        }
+        Ok::<(), tokio_postgres::Error>(())
+    });
+
+    client.batch_execute(&format!("LISTEN {}", ACCOUNT_EVENTS_CHANNEL)).await?;
+    tracing::info!("Listening for account events");
+
+    catch_up(pool, hub, last_seen).await?;
+
+    let mut prune = tokio::time::interval(ACCOUNT_EVENT_PRUNE_INTERVAL);
+
+    loop {
+        tokio::select! {
+            _ = token.cancelled() => return Ok(()),
+            _ = prune.tick() => prune_events(pool).await?,
+            notification = notifications.recv() => match notification {
+                Some(notification) => forward(pool, hub, &notification, last_seen).await?,
+                None => {
+                    return match driver.await {
+                        Ok(Err(e)) => Err(e.into()),
+                        _ => Err(AppError::Unavailable("Account event connection closed".into())),
+                    };
+                }
+            },
+        }
+    }
+}
+
+// Broadcasts the events committed since `last_seen`; on the first connection
+// there is nothing to catch up on.
+async fn catch_up(pool: &Pool, hub: &AccountEventHub, last_seen: &mut Option<i64>) -> Result<(), AppError> {
+    let client = pool.get().await?;
+
+    let Some(mut after_id) = *last_seen else {
+        *last_seen = Some(event_queries::latest_event_id(&client, None).await?);
+        return Ok(());
+    };
+
+    loop {
+        let events = event_queries::list_events_after(&client, after_id, ACCOUNT_EVENT_BATCH_SIZE).await?;
+        let done = (events.len() as i64) < ACCOUNT_EVENT_BATCH_SIZE;
+
+        for event in events {
+            after_id = event.id;
+            hub.broadcast(event);
+        }
+        *last_seen = Some(after_id);
+
+        if done {
+            return Ok(());
+        }
+    }
+}
+
+async fn forward(
+    pool: &Pool,
+    hub: &AccountEventHub,
+    notification: &Notification,
+    last_seen: &mut Option<i64>,
+) -> Result<(), AppError> {
+    let Ok(id) = notification.payload().parse::<i64>() else {
+        tracing::warn!(payload = notification.payload(), "Ignoring malformed account event notification");
+        return Ok(());
+    };
+
+    let client = pool.get().await?;
+    // Already pruned if it is missing
+    if let Some(event) = event_queries::get_event_by_id(&client, id).await? {
+        hub.broadcast(event);
+    }
+    *last_seen = Some(last_seen.map_or(id, |last| last.max(id)));
+
+    Ok(())
+}
+
+async fn prune_events(pool: &Pool) -> Result<(), AppError> {
+    let client = pool.get().await?;
+    let pruned = event_queries::delete_events_before(&client, Utc::now() - ACCOUNT_EVENT_RETENTION).await?;
+    if pruned > 0 {
+        tracing::info!(pruned, "Pruned old account events");
+    }
+
+    Ok(())
+}
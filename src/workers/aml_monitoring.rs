@@ -0,0 +1,42 @@
+use chrono::Utc;
+use deadpool_postgres::Pool;
+use tokio_util::sync::CancellationToken;
+use crate::{
+    base::{constants::AML_MONITORING_INTERVAL, error::AppError},
+    db::dal::aml as aml_queries,
+    services::aml as aml_service,
+};
+
+// Runs the AML monitoring scenarios at startup and then periodically. Each
+// replica runs the job; a run is skipped when another replica ran it less
+// than half an interval ago.
+pub async fn run(pool: Pool, token: CancellationToken) {
+    let mut interval = tokio::time::interval(AML_MONITORING_INTERVAL);
+
+    loop {
+        tokio::select! {
+            _ = token.cancelled() => break,
+            _ = interval.tick() => {}
+        }
+
+        if let Err(e) = monitor(&pool).await {
+            tracing::error!(error = %e, "AML monitoring run failed");
+        }
+    }
+}
+
+async fn monitor(pool: &Pool) -> Result<(), AppError> {
+    let mut client = pool.get().await?;
+
+    if let Some(last_run) = aml_queries::last_run_started_at(&client).await?
+        && Utc::now().signed_duration_since(last_run).to_std().unwrap_or_default() < AML_MONITORING_INTERVAL / 2
+    {
+        return Ok(());
+    }
+
+    if aml_service::run(&mut client, None).await?.is_none() {
+        tracing::info!("Skipping AML monitoring, a run is already in progress");
+    }
+
+    Ok(())
+}
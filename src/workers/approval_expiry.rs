@@ -0,0 +1,41 @@
+use deadpool_postgres::Pool;
+use tokio_util::sync::CancellationToken;
+use crate::{
+    base::{
+        constants::{APPROVAL_EXPIRY_BATCH_SIZE, APPROVAL_EXPIRY_POLL_INTERVAL},
+        error::AppError,
+    },
+    services::approvals as approval_service,
+};
+
+// Expires approval requests nobody decided in time. Nothing was booked for
+// them, so there is nothing to undo. Requests are claimed with SKIP LOCKED, so
+// any number of replicas can run this job side by side.
+pub async fn run(pool: Pool, token: CancellationToken) {
+    let mut interval = tokio::time::interval(APPROVAL_EXPIRY_POLL_INTERVAL);
+
+    loop {
+        tokio::select! {
+            _ = token.cancelled() => break,
+            _ = interval.tick() => {}
+        }
+
+        if let Err(e) = expire_requests(&pool, &token).await {
+            tracing::error!(error = %e, "Approval request expiry failed");
+        }
+    }
+}
+
+async fn expire_requests(pool: &Pool, token: &CancellationToken) -> Result<(), AppError> {
+    let mut client = pool.get().await?;
+
+    while !token.is_cancelled() {
+        let expired = approval_service::expire_due(&mut client).await?;
+
+        if (expired as i64) < APPROVAL_EXPIRY_BATCH_SIZE {
+            break;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,52 @@
+use chrono::{DateTime, Days, NaiveDate, Utc};
+use deadpool_postgres::Pool;
+use tokio_util::sync::CancellationToken;
+use crate::{
+    base::{constants::BALANCE_SNAPSHOT_INTERVAL, error::AppError, metrics},
+    db::dal::balances as balance_queries,
+};
+
+// Takes the end-of-day balance snapshots once a UTC day is over. Runs at
+// startup and then periodically, catching up on every day it missed, so
+// downtime over midnight doesn't leave gaps.
+pub async fn run(pool: Pool, token: CancellationToken) {
+    let mut interval = tokio::time::interval(BALANCE_SNAPSHOT_INTERVAL);
+
+    loop {
+        tokio::select! {
+            _ = token.cancelled() => break,
+            _ = interval.tick() => {}
+        }
+
+        if let Err(e) = take_snapshots(&pool, &token).await {
+            tracing::error!(error = %e, "Balance snapshot run failed");
+        }
+    }
+}
+
+async fn take_snapshots(pool: &Pool, token: &CancellationToken) -> Result<(), AppError> {
+    let client = pool.get().await?;
+    let today = Utc::now().date_naive();
+
+    let Some(mut day) = balance_queries::first_missing_snapshot_date(&client).await? else {
+        return Ok(());
+    };
+
+    while day < today && !token.is_cancelled() {
+        let as_of = end_of_day(day);
+        let created = balance_queries::create_snapshots(&client, day, as_of).await?;
+        metrics::record_balance_snapshots(created);
+        tracing::info!(day = %day, snapshots = created, "Balance snapshots taken");
+
+        day = as_of.date_naive();
+    }
+
+    Ok(())
+}
+
+fn end_of_day(day: NaiveDate) -> DateTime<Utc> {
+    day.checked_add_days(Days::new(1))
+        .and_then(|next| next.and_hms_opt(0, 0, 0))
+        .map(|midnight| midnight.and_utc())
+        .unwrap_or(DateTime::<Utc>::MAX_UTC)
+}
@@ -0,0 +1,63 @@
+use chrono::Utc;
+use deadpool_postgres::Pool;
+use tokio_util::sync::CancellationToken;
+use crate::{
+    base::{
+        constants::{DATA_EXPORT_POLL_INTERVAL, DATA_EXPORT_STALE_AFTER, DATA_EXPORT_TTL},
+        error::AppError,
+        metrics,
+        models::exports::ExportStatus,
+    },
+    db::dal::exports as export_queries,
+    services::exports as export_service,
+};
+
+// Builds queued personal data exports one at a time and deletes the archives
+// of expired ones. Exports are claimed with SKIP LOCKED, so any number of
+// replicas can run this job side by side.
+pub async fn run(pool: Pool, token: CancellationToken) {
+    let mut interval = tokio::time::interval(DATA_EXPORT_POLL_INTERVAL);
+
+    loop {
+        tokio::select! {
+            _ = token.cancelled() => break,
+            _ = interval.tick() => {}
+        }
+
+        if let Err(e) = process_exports(&pool, &token).await {
+            tracing::error!(error = %e, "Data export run failed");
+        }
+    }
+}
+
+async fn process_exports(pool: &Pool, token: &CancellationToken) -> Result<(), AppError> {
+    let mut client = pool.get().await?;
+
+    let expired = export_queries::expire_exports(&client).await?;
+    if expired > 0 {
+        tracing::info!(expired, "Expired data exports deleted");
+    }
+
+    while !token.is_cancelled() {
+        let stale_after = Utc::now() - DATA_EXPORT_STALE_AFTER;
+        let Some(export) = export_queries::claim_next_export(&client, stale_after).await? else {
+            break;
+        };
+
+        match export_service::build_archive(&mut client, &export).await {
+            Ok(archive) => {
+                let expires_at = Utc::now() + DATA_EXPORT_TTL;
+                export_queries::complete_export(&client, export.id, &archive, expires_at).await?;
+                metrics::record_data_export(&ExportStatus::Ready.to_string());
+                tracing::info!(export_id = %export.id, size_bytes = archive.len(), "Data export ready");
+            }
+            Err(e) => {
+                export_queries::fail_export(&client, export.id, &e.to_string()).await?;
+                metrics::record_data_export(&ExportStatus::Failed.to_string());
+                tracing::error!(export_id = %export.id, error = %e, "Data export failed");
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,44 @@
+use std::future::Future;
+use tokio_util::{sync::CancellationToken, task::TaskTracker};
+
+pub mod account_events;
+pub mod aml_monitoring;
+pub mod approval_expiry;
+pub mod balance_snapshots;
+pub mod data_exports;
+pub mod scheduled_payments;
+pub mod webhooks;
+
+// Owns every background task spawned by the server so that shutdown can
+// signal them and wait for them to finish.
+#[derive(Clone)]
+pub struct Workers {
+    tracker: TaskTracker,
+    token: CancellationToken,
+}
+
+impl Workers {
+    pub fn new(token: CancellationToken) -> Self {
+        Self {
+            tracker: TaskTracker::new(),
+            token,
+        }
+    }
+
+    // Runs a background task until it returns; the task is handed the
+    // shutdown token and is expected to exit soon after it is cancelled.
+    pub fn spawn<F, Fut>(&self, task: F)
+    where
+        F: FnOnce(CancellationToken) -> Fut,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.tracker.spawn(task(self.token.clone()));
+    }
+
+    // Signals all tracked tasks and waits until they have exited.
+    pub async fn shutdown(&self) {
+        self.token.cancel();
+        self.tracker.close();
+        self.tracker.wait().await;
+    }
+}
@@ -0,0 +1,63 @@
+use chrono::Utc;
+use deadpool_postgres::Pool;
+use tokio_util::sync::CancellationToken;
+use crate::{
+    base::{
+        constants::{SCHEDULED_PAYMENT_BATCH_SIZE, SCHEDULED_PAYMENT_POLL_INTERVAL},
+        error::AppError,
+    },
+    db::dal::scheduled_payments as schedule_queries,
+    services::scheduled_payments as schedule_service,
+};
+
+// Runs due scheduled payments. Each schedule is claimed with SKIP LOCKED, so
+// any number of replicas can run this job side by side.
+pub async fn run(pool: Pool, token: CancellationToken) {
+    let mut interval = tokio::time::interval(SCHEDULED_PAYMENT_POLL_INTERVAL);
+
+    loop {
+        tokio::select! {
+            _ = token.cancelled() => break,
+            _ = interval.tick() => {}
+        }
+
+        if let Err(e) = run_due_schedules(&pool, &token).await {
+            tracing::error!(error = %e, "Scheduled payment run failed");
+        }
+    }
+}
+
+async fn run_due_schedules(pool: &Pool, token: &CancellationToken) -> Result<(), AppError> {
+    let mut client = pool.get().await?;
+
+    while !token.is_cancelled() {
+        let now = Utc::now();
+        let due = schedule_queries::list_due_schedule_ids(&client, now, SCHEDULED_PAYMENT_BATCH_SIZE).await?;
+
+        let mut ran = 0;
+        for id in &due {
+            if token.is_cancelled() {
+                break;
+            }
+            // One schedule's failure mustn't hold up the others; it stays due
+            // and is tried again on a later pass
+            match schedule_service::run_if_due(&mut client, *id, now).await {
+                Ok(Some(_)) => ran += 1,
+                Ok(None) => {}
+                Err(e) => {
+                    tracing::error!(schedule_id = %id, error = %e, "Scheduled payment couldn't run");
+                    if let Err(record_error) = schedule_queries::record_schedule_error(&client, *id, e.code(), now).await {
+                        tracing::error!(schedule_id = %id, error = %record_error, "Failed to record scheduled payment error");
+                    }
+                }
+            }
+        }
+
+        // Done once the rest is held by other replicas or nothing is left
+        if ran == 0 || (due.len() as i64) < SCHEDULED_PAYMENT_BATCH_SIZE {
+            break;
+        }
+    }
+
+    Ok(())
+}
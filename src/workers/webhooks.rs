@@ -0,0 +1,167 @@
+use chrono::Utc;
+use deadpool_postgres::{Client, Pool};
+use futures_util::future::join_all;
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+use std::{
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+};
+use tokio_util::sync::CancellationToken;
+use crate::{
+    base::{
+        constants::{
+            WEBHOOK_BATCH_SIZE, WEBHOOK_CLAIM_LEASE, WEBHOOK_MAX_ATTEMPTS, WEBHOOK_POLL_INTERVAL,
+            WEBHOOK_REQUEST_TIMEOUT,
+        },
+        error::AppError,
+        metrics,
+        models::webhooks::ClaimedDelivery,
+        webhooks::{self, ID_HEADER, SIGNATURE_HEADER, TIMESTAMP_HEADER},
+    },
+    db::dal::webhooks as webhook_queries,
+};
+
+// Sends due webhook deliveries from the outbox. Deliveries are claimed with
+// SKIP LOCKED, so any number of replicas can run this job side by side.
+pub async fn run(pool: Pool, token: CancellationToken) {
+    let http = match reqwest::Client::builder()
+        .timeout(WEBHOOK_REQUEST_TIMEOUT)
+        .redirect(reqwest::redirect::Policy::none())
+        .dns_resolver(Arc::new(PublicResolver))
+        // A proxy would resolve and connect to the endpoint itself, past the
+        // address checks
+        .no_proxy()
+        .build()
+    {
+        Ok(http) => http,
+        Err(e) => {
+            tracing::error!(error = %e, "Webhook HTTP client could not be built");
+            return;
+        }
+    };
+    let mut interval = tokio::time::interval(WEBHOOK_POLL_INTERVAL);
+
+    loop {
+        tokio::select! {
+            _ = token.cancelled() => break,
+            _ = interval.tick() => {}
+        }
+
+        if let Err(e) = dispatch(&pool, &http, &token).await {
+            tracing::error!(error = %e, "Webhook dispatch failed");
+        }
+    }
+}
+
+async fn dispatch(pool: &Pool, http: &reqwest::Client, token: &CancellationToken) -> Result<(), AppError> {
+    let client = pool.get().await?;
+
+    while !token.is_cancelled() {
+        let lease_until = Utc::now() + WEBHOOK_CLAIM_LEASE;
+        let deliveries = webhook_queries::claim_due_deliveries(&client, lease_until, WEBHOOK_BATCH_SIZE).await?;
+        if deliveries.is_empty() {
+            break;
+        }
+
+        for result in join_all(deliveries.iter().map(|delivery| attempt(&client, http, delivery))).await {
+            result?;
+        }
+    }
+
+    Ok(())
+}
+
+async fn attempt(client: &Client, http: &reqwest::Client, delivery: &ClaimedDelivery) -> Result<(), AppError> {
+    let body = webhooks::delivery_body(delivery);
+    let timestamp = Utc::now().timestamp();
+
+    let response = match check_endpoint(&delivery.url) {
+        Ok(()) => http
+            .post(&delivery.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(ID_HEADER, delivery.event_id.to_string())
+            .header(TIMESTAMP_HEADER, timestamp.to_string())
+            .header(SIGNATURE_HEADER, webhooks::signature(&delivery.secret, timestamp, &body))
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| error_chain(&e)),
+        Err(error) => Err(error),
+    };
+
+    let (response_status, error) = match response {
+        Ok(response) if response.status().is_success() => {
+            webhook_queries::mark_delivered(client, delivery.id, response.status().as_u16() as i32).await?;
+            metrics::record_webhook_attempt("delivered");
+            return Ok(());
+        }
+        Ok(response) => (
+            Some(response.status().as_u16() as i32),
+            format!("Endpoint responded with {}", response.status()),
+        ),
+        Err(error) => (None, error),
+    };
+
+    let retry_at = (delivery.attempts < WEBHOOK_MAX_ATTEMPTS)
+        .then(|| Utc::now() + webhooks::retry_delay(delivery.attempts));
+    webhook_queries::record_failure(client, delivery.id, response_status, &error, retry_at).await?;
+
+    match retry_at {
+        Some(retry_at) => {
+            metrics::record_webhook_attempt("retry");
+            tracing::warn!(delivery_id = %delivery.id, attempts = delivery.attempts, %retry_at, error = %error, "Webhook delivery failed");
+        }
+        None => {
+            metrics::record_webhook_attempt("dead");
+            tracing::error!(delivery_id = %delivery.id, attempts = delivery.attempts, error = %error, "Webhook delivery is dead");
+        }
+    }
+
+    Ok(())
+}
+
+// Endpoints registered before https was required are refused here, and so are
+// IP address hosts, which reqwest connects to without asking the resolver.
+fn check_endpoint(url: &str) -> Result<(), String> {
+    let url = reqwest::Url::parse(url).map_err(|e| e.to_string())?;
+    if url.scheme() != "https" {
+        return Err("Endpoint URL must use https".into());
+    }
+    let host = url.host_str().unwrap_or_default();
+    if let Ok(ip) = host.trim_start_matches('[').trim_end_matches(']').parse::<IpAddr>()
+        && !webhooks::is_public_address(ip)
+    {
+        return Err(format!("Endpoint address {} is not public", ip));
+    }
+    Ok(())
+}
+
+// reqwest's own message leaves out the cause, e.g. a refused address.
+fn error_chain(error: &reqwest::Error) -> String {
+    let mut message = error.to_string();
+    let mut source = std::error::Error::source(error);
+    while let Some(cause) = source {
+        message.push_str(": ");
+        message.push_str(&cause.to_string());
+        source = cause.source();
+    }
+    message
+}
+
+// Resolves endpoint hosts when a delivery is sent and refuses the lookup if
+// any address isn't public. The connection uses the addresses checked here, so
+// a DNS record changed after registration can't reach the internal network.
+struct PublicResolver;
+
+impl Resolve for PublicResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let addrs: Vec<SocketAddr> = tokio::net::lookup_host((name.as_str(), 0)).await?.collect();
+            if let Some(addr) = addrs.iter().find(|addr| !webhooks::is_public_address(addr.ip())) {
+                return Err(format!("{} resolves to {}, which is not public", name.as_str(), addr.ip()).into());
+            }
+            let addrs: Addrs = Box::new(addrs.into_iter());
+            Ok(addrs)
+        })
+    }
+}